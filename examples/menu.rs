@@ -0,0 +1,46 @@
+extern crate winit;
+
+use winit::{ModifiersState, VirtualKeyCode};
+use winit::menu::{Accelerator, Menu, MenuId, MenuItem};
+
+const QUIT: MenuId = MenuId(1);
+const HELLO: MenuId = MenuId(2);
+
+fn main() {
+    let mut events_loop = winit::EventsLoop::new();
+
+    // Ctrl on Windows, Cmd on macOS.
+    let modifiers = ModifiersState {
+        ctrl: !cfg!(target_os = "macos"),
+        logo: cfg!(target_os = "macos"),
+        ..Default::default()
+    };
+
+    let menu = Menu::new("")
+        .with_submenu(Menu::new("File")
+            .with_item(MenuItem::new(HELLO, "Say hello"))
+            .with_separator()
+            .with_item(MenuItem::new(QUIT, "Quit")
+                .with_accelerator(Accelerator::new(modifiers, VirtualKeyCode::Q))));
+
+    let _window = winit::WindowBuilder::new()
+        .with_title("A window with a menu")
+        .with_menu(menu)
+        .build(&events_loop)
+        .unwrap();
+
+    events_loop.run_forever(|event, _| {
+        match event {
+            winit::Event::MenuEvent { menu_id: HELLO } => {
+                println!("Hello!");
+                winit::ControlFlow::Continue
+            },
+            winit::Event::MenuEvent { menu_id: QUIT } => winit::ControlFlow::Break,
+            winit::Event::WindowEvent {
+                event: winit::WindowEvent::CloseRequested,
+                ..
+            } => winit::ControlFlow::Break,
+            _ => winit::ControlFlow::Continue,
+        }
+    });
+}
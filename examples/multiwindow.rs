@@ -11,7 +11,9 @@ fn main() {
         windows.insert(window.id(), window);
     }
 
-    events_loop.run_forever(|event| {
+    println!("Press N to open another window.");
+
+    events_loop.run_forever(|event, window_target| {
         match event {
             winit::Event::WindowEvent {
                 event: winit::WindowEvent::CloseRequested,
@@ -25,7 +27,21 @@ fn main() {
                 if windows.is_empty() {
                     return winit::ControlFlow::Break;
                 }
-            }
+            },
+            winit::Event::WindowEvent {
+                event: winit::WindowEvent::KeyboardInput {
+                    input: winit::KeyboardInput {
+                        state: winit::ElementState::Pressed,
+                        virtual_keycode: Some(winit::VirtualKeyCode::N),
+                        ..
+                    },
+                    ..
+                },
+                ..
+            } => {
+                let window = winit::Window::new(window_target).unwrap();
+                windows.insert(window.id(), window);
+            },
             _ => (),
         }
         winit::ControlFlow::Continue
@@ -0,0 +1,32 @@
+extern crate winit;
+
+use winit::surface::Surface;
+
+fn main() {
+    let mut events_loop = winit::EventsLoop::new();
+
+    let window = winit::WindowBuilder::new()
+        .with_title("Surface")
+        .build(&events_loop)
+        .unwrap();
+
+    // This is where an OpenGL context would be created from the native handles.
+    let surface = Surface::new(&window).unwrap();
+    println!("{:?}", surface.native());
+
+    events_loop.run_forever(|event, _| {
+        match event {
+            winit::Event::WindowEvent { event: winit::WindowEvent::Resized(size), .. } => {
+                let size = size.to_physical(window.get_hidpi_factor());
+                println!("resizing surface to {:?}", size);
+                surface.resize(size);
+                winit::ControlFlow::Continue
+            },
+            winit::Event::WindowEvent {
+                event: winit::WindowEvent::CloseRequested,
+                ..
+            } => winit::ControlFlow::Break,
+            _ => winit::ControlFlow::Continue,
+        }
+    });
+}
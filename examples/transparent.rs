@@ -9,7 +9,7 @@ fn main() {
 
     window.set_title("A fantastic window!");
 
-    events_loop.run_forever(|event| {
+    events_loop.run_forever(|event, _| {
         println!("{:?}", event);
 
         match event {
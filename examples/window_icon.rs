@@ -31,12 +31,12 @@ fn main() {
         .build(&events_loop)
         .unwrap();
 
-    events_loop.run_forever(|event| {
+    events_loop.run_forever(|event, _| {
         if let winit::Event::WindowEvent { event, .. } = event {
             use winit::WindowEvent::*;
             match event {
                 CloseRequested => return winit::ControlFlow::Break,
-                DroppedFile(path) => {
+                DroppedFile { path, .. } => {
                     use image::GenericImage;
 
                     let icon_image = image::open(path).expect("Failed to open window icon");
@@ -0,0 +1,44 @@
+//! Hooks for exposing a window's contents to assistive technologies, such as screen readers.
+//!
+//! winit doesn't know anything about the widgets drawn inside a window, so it can't describe them
+//! to assistive technologies by itself. Instead, it lets an `AccessibilityHandler` provided by the
+//! GUI toolkit plug the toolkit's own platform accessibility objects into the window.
+//!
+//! Building an accessibility tree isn't free, so toolkits will usually want to wait until it's
+//! actually needed. The first time an assistive technology queries a window, the window receives
+//! `WindowEvent::AccessibilityActivated`, whether or not a handler has been set yet.
+//!
+//! ## Platform-specific
+//!
+//! - **Windows:** The root object is an `IRawElementProviderSimple` from UI Automation, returned
+//!   in response to `WM_GETOBJECT`.
+//! - **macOS:** The root object is an `NSObject` implementing the `NSAccessibility` protocol. It's
+//!   presented as the only accessibility child of the window's content view, so its
+//!   `accessibilityParent` should be that view.
+//! - **X11 / Wayland:** There's no AT-SPI integration. AT-SPI is a D-Bus service rather than
+//!   something attached to windows, so toolkits need to register with the AT-SPI bus themselves.
+//!   The handler is never called, and `AccessibilityActivated` isn't generated.
+//! - **iOS / Android / Emscripten:** Unsupported. The handler is never called.
+
+use std::os::raw::c_void;
+use std::sync::Arc;
+
+/// Provides the accessibility objects of a window. See the module-level documentation.
+///
+/// The handler is called from the thread the window's messages are handled on, which isn't
+/// necessarily the one running the events loop:
+///
+/// - **Windows:** The background thread the `EventsLoop` creates for its windows. Since the
+///   assistive technology waits for the answer to `WM_GETOBJECT`, the handler shouldn't wait on
+///   the thread running the events loop.
+/// - **macOS:** The main thread.
+pub trait AccessibilityHandler: Send + Sync {
+    /// Returns the platform accessibility object at the root of the window's contents, or null if
+    /// there's nothing to expose yet.
+    ///
+    /// The handler keeps ownership of the object. winit retains or `AddRef`s it for as long as it
+    /// needs it.
+    fn get_root_object(&self) -> *mut c_void;
+}
+
+pub(crate) type AccessibilityAdapter = Arc<AccessibilityHandler>;
@@ -1,9 +1,10 @@
 use std::path::PathBuf;
 
-use {DeviceId, LogicalPosition, LogicalSize, WindowId};
+use {DeviceId, LogicalPosition, LogicalSize, MonitorId, WindowId};
 
 /// Describes a generic event.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Event {
     WindowEvent {
         window_id: WindowId,
@@ -15,21 +16,211 @@ pub enum Event {
     },
     Awakened,
 
+    /// An item of a menu set with `Window::set_menu` was activated.
+    MenuEvent {
+        menu_id: ::menu::MenuId,
+    },
+
+    /// An event from a `TrayIcon`.
+    ///
+    /// Only emitted with the `tray` feature, but always defined so that enabling the feature
+    /// doesn't change the variants `Event` has.
+    TrayEvent {
+        tray_id: ::tray::TrayIconId,
+        event: ::tray::TrayEvent,
+    },
+
+    /// The user hasn't used any input device, anywhere on the system, for as long as the threshold
+    /// set with `EventsLoop::set_idle_threshold`.
+    UserIdle,
+
+    /// The user used an input device again after `UserIdle` was emitted.
+    UserActive,
+
+    /// The connection to the display server was lost. Contains a description of the error.
+    ///
+    /// This is the last event delivered by the `EventsLoop`: `run_forever` returns after it, and
+    /// `poll_events` doesn't deliver anything anymore. Windows can't be used past this point, so
+    /// this is an opportunity to save state and exit.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only emitted on Wayland. Xlib exits the process when the connection to the X server is
+    /// lost, and the other platforms don't have a connection that can be lost.
+    ConnectionLost(String),
+
+    /// A property of the root window changed or was deleted. Contains the name of the property.
+    ///
+    /// Only generated on X11, for the properties given to
+    /// `os::unix::EventsLoopExt::x11_watch_root_property`.
+    X11RootPropertyChanged(String),
+
+    /// The user's session is about to end, because they're logging out or shutting down the
+    /// computer. This is the time to save unsaved work, or to ask the user about it.
+    ///
+    /// To have the system wait, call `EventLoopWindowTarget::block_session_end` while there's
+    /// unsaved work, rather than when this is received.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Generated by `WM_QUERYENDSESSION`.
+    /// - **macOS:** Generated by `NSWorkspaceWillPowerOffNotification`. The reason is always
+    ///   `Unknown`.
+    /// - **X11:** Generated by `WM_SAVE_YOURSELF`, which session managers send through `smproxy`.
+    ///   The reason is always `Unknown`, and it may be generated once per window.
+    /// - Not generated on Wayland, iOS, Android, Emscripten, and Web.
+    SessionEnding { reason: SessionEndReason },
+
+    /// The keyboard layouts the user can switch between, or the one they currently use, changed.
+    /// `EventLoopWindowTarget::keyboard_layouts` returns the new ones.
+    ///
+    /// This is the time to refresh anything that depends on the layout, like the keys shown in
+    /// shortcut hints.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Generated by the XKB `XkbStateNotify` events that change the group, and by
+    ///   `XkbNewKeyboardNotify` and `XkbNamesNotify`, when the layouts differ from the ones
+    ///   returned before.
+    /// - **Windows:** Only generated when the active layout changes, by `WM_INPUTLANGCHANGE`.
+    /// - **macOS:** Generated by the notifications the Text Input Sources API posts when the
+    ///   selected or enabled input sources change, which includes the switches done by
+    ///   `NSTextInputContext`.
+    /// - Not generated on Wayland, iOS, Android, Emscripten, and Web.
+    KeyboardLayoutChanged,
+
+    /// The application became the active one, whose windows receive keyboard input.
+    ///
+    /// Unlike `WindowEvent::Focused`, this isn't sent when focus moves between the application's
+    /// own windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Generated by `WM_ACTIVATEAPP`, for the windows of each `EventsLoop`
+    ///   separately.
+    /// - **macOS:** Generated by `NSApplicationDidBecomeActiveNotification`.
+    /// - **iOS:** Generated by `applicationDidBecomeActive:`, and this event's counterpart by
+    ///   `applicationWillResignActive:`.
+    /// - Not generated on X11, Wayland, Android, Emscripten, and Web.
+    ApplicationActivated,
+
+    /// Another application became the active one. The same conditions that apply to
+    /// `ApplicationActivated` apply to this event.
+    ApplicationDeactivated,
+
     /// The application has been suspended or resumed.
     ///
     /// The parameter is true if app was suspended, and false if it has been resumed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Android:** While the app is suspended, `run_forever` stops polling and sleeps until the
+    ///   next event.
     Suspended(bool),
 }
 
+/// A rectangle of a window's client area, in physical pixels from its top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DamageRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The distances, in logical pixels, from each edge of a window's client area to the part of it
+/// that isn't covered by anything, as returned by `Window::get_safe_area_insets`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Insets {
+    pub top: f64,
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+}
+
+/// The orientation of the user interface, as reported by `WindowEvent::OrientationChanged`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Orientation {
+    /// The device is held upright.
+    Portrait,
+    /// The device is held upside down.
+    PortraitUpsideDown,
+    /// The device is held sideways, with its bottom on the left.
+    LandscapeLeft,
+    /// The device is held sideways, with its bottom on the right.
+    LandscapeRight,
+}
+
+/// Why the session is ending, in `Event::SessionEnding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SessionEndReason {
+    /// The user is logging out.
+    Logout,
+    /// The computer is shutting down or restarting.
+    Shutdown,
+    /// The platform doesn't say.
+    Unknown,
+}
+
 /// Describes an event from a `Window`.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WindowEvent {
     /// The size of the window has changed. Contains the client area's new dimensions.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Generated continuously while the user resizes the window, which waits for
+    ///   each of them to be handled before it goes on, so that the application can render at the
+    ///   new size during the drag.
     Resized(LogicalSize),
 
-    /// The position of the window has changed. Contains the window's new position.
+    /// The position of the window has changed. Contains the window's new position, which is the
+    /// position of the top-left hand corner of its frame, as returned by `Window::get_position`
+    /// and set by `Window::set_outer_position`.
+    ///
+    /// While the user drags the window, this is generated continuously where the platform allows
+    /// it, and at least once the drag ends.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** The position is polled at 60 Hz during drags, since `windowDidMove:` is
+    ///   sometimes only sent once they end.
+    /// - **Wayland:** Never generated, since clients can't know where their windows are.
     Moved(LogicalPosition),
 
+    /// The user started resizing the window, e.g. by dragging one of its edges. The `Resized`
+    /// events that follow belong to the same resize, until `ResizeEnded`, so a renderer can switch
+    /// to a cheaper way of presenting frames in the meantime.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Guessed from the window being resized while a mouse button is held, since X11
+    ///   doesn't tell; the resize ends once all the buttons are released.
+    /// - **Wayland:** Follows the resizing state the compositor gives the window.
+    /// - Never generated on iOS, Android, Emscripten, and Web.
+    ResizeStarted,
+
+    /// The user stopped resizing the window. Always follows a `ResizeStarted`.
+    ResizeEnded,
+
+    /// The user started moving the window, e.g. by dragging its title bar. The `Moved` events that
+    /// follow belong to the same move, until `MoveEnded`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Guessed from the window being moved while a mouse button is held, like
+    ///   `ResizeStarted`.
+    /// - Never generated on Wayland, iOS, Android, Emscripten, and Web.
+    MoveStarted,
+
+    /// The user stopped moving the window. Always follows a `MoveStarted`.
+    MoveEnded,
+
     /// The window has been requested to close.
     CloseRequested,
 
@@ -37,7 +228,15 @@ pub enum WindowEvent {
     Destroyed,
 
     /// A file has been dropped into the window.
-    DroppedFile(PathBuf),
+    ///
+    /// When several files are dropped at once, this event is generated for each of them.
+    DroppedFile {
+        path: PathBuf,
+        /// Where the file was dropped, relative to the top-left corner of the client area.
+        position: LogicalPosition,
+        /// The modifiers that were held when the file was dropped.
+        modifiers: ModifiersState,
+    },
 
     /// A file is being hovered over the window.
     HoveredFile(PathBuf),
@@ -48,11 +247,116 @@ pub enum WindowEvent {
     /// The window received a unicode character.
     ReceivedCharacter(char),
 
+    /// The window became fully hidden from view, or became visible again.
+    ///
+    /// The parameter is true if the window is occluded. Nothing the window draws while occluded
+    /// can be seen, so this is a good time to stop rendering.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** A window is only considered occluded when it's minimized or cloaked (e.g.
+    ///   because it's on another virtual desktop). Being covered by other windows isn't detected.
+    /// - **X11:** Based on `VisibilityNotify`, which isn't generated reliably when a compositor is
+    ///   running. Minimized windows are also considered occluded.
+    /// - **Web:** Based on the page's visibility, so it's generated when the browser's tab is
+    ///   switched or its window is minimized.
+    /// - Not generated on Wayland, iOS, Android, or Emscripten.
+    Occluded(bool),
+
+    /// An assistive technology queried the window for the first time.
+    ///
+    /// This is a good time to build the window's accessibility tree and to provide it with
+    /// `Window::set_accessibility_adapter`. See the `accessibility` module for details.
+    AccessibilityActivated,
+
+    /// The user dragged the window's tab out of its tab group, so it's now a window of its own.
+    ///
+    /// Only generated on macOS, for windows created with
+    /// `os::macos::WindowBuilderExt::with_tabbing_identifier`.
+    TabDetached,
+
+    /// The window entered or left fullscreen.
+    ///
+    /// The parameter is the monitor the window is now fullscreen on, or `None` if it left
+    /// fullscreen. Moving a fullscreen window to another monitor with `Window::set_fullscreen`
+    /// also sends this once, with the new monitor, when the window has reached it.
+    ///
+    /// Monitors can't be serialized, so this is always `None` after deserializing.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** A fullscreen window can't move to another screen, so it visibly leaves
+    ///   fullscreen and enters it again on the new one. Only the final event is sent.
+    /// - Only generated on X11, Windows and macOS.
+    FullscreenChanged(#[cfg_attr(feature = "serde", serde(skip))] Option<MonitorId>),
+
+    /// The window was maximized or restored, by the user, the window manager or
+    /// `Window::set_maximized`.
+    ///
+    /// The parameter is true if the window is now maximized.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Leaving fullscreen restores the window's previous state, which may send
+    ///   this with `true`.
+    /// - **macOS:** Only sent for windows that have a title bar and are resizable, since the
+    ///   others can't be zoomed.
+    /// - **X11:** Based on the `_NET_WM_STATE` property, which the window manager sets.
+    /// - Only generated on X11, Windows and macOS.
+    Maximized(bool),
+
+    /// The window was minimized or restored.
+    ///
+    /// The parameter is true if the window is now minimized.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Based on the `WM_STATE` property, which the window manager sets.
+    /// - Only generated on X11, Windows and macOS.
+    Minimized(bool),
+
+    /// The window is fullscreen, but stopped being shown that way without the application asking,
+    /// because another window was activated on its monitor, or it was minimized on losing focus.
+    ///
+    /// The window is still fullscreen, and covers its monitor again once it's activated. A process
+    /// sharing the monitor with another one, like an overlay, can use this to yield to it instead
+    /// of taking the focus back.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Windows are only minimized on losing focus if
+    ///   `os::windows::WindowExt::set_minimize_on_fullscreen_focus_loss` is enabled.
+    /// - Only generated on Windows.
+    FullscreenLost,
+
+    /// A property of the window changed or was deleted. Contains the name of the property.
+    ///
+    /// Only generated on X11, for the properties given to
+    /// `os::unix::WindowExt::x11_watch_property`.
+    X11PropertyChanged(String),
+
     /// The window gained or lost focus.
     ///
     /// The parameter is true if the window has gained focus, and false if it has lost focus.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Follows the key window, which receives keyboard input. See
+    ///   `MainWindowChanged` for the main window.
     Focused(bool),
 
+    /// The window became or stopped being the main window. The parameter is true if it became
+    /// the main window.
+    ///
+    /// The main window is the one whose document the application's commands, like paste, act on.
+    /// It's usually also the key window, except while a panel, like an inspector or a find panel,
+    /// has keyboard focus. It stays the main window while the application is inactive.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only generated on macOS.
+    MainWindowChanged(bool),
+
     /// An event from the keyboard has been received.
     KeyboardInput { device_id: DeviceId, input: KeyboardInput },
 
@@ -74,7 +378,18 @@ pub enum WindowEvent {
     CursorLeft { device_id: DeviceId },
 
     /// A mouse wheel movement or touchpad scroll occurred.
-    MouseWheel { device_id: DeviceId, delta: MouseScrollDelta, phase: TouchPhase, modifiers: ModifiersState },
+    ///
+    /// `phase` tracks the gesture while the user's fingers are on the touchpad, and is always
+    /// `Moved` for devices that don't report scroll gestures (such as most mouse wheels).
+    /// `momentum_phase` is `Some` for the kinetic scrolling events the system emits after the
+    /// fingers have been lifted; it's only reported on macOS.
+    MouseWheel {
+        device_id: DeviceId,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+        momentum_phase: Option<TouchPhase>,
+        modifiers: ModifiersState,
+    },
 
     /// An mouse button press has been received.
     MouseInput { device_id: DeviceId, state: ElementState, button: MouseButton, modifiers: ModifiersState },
@@ -91,7 +406,16 @@ pub enum WindowEvent {
     AxisMotion { device_id: DeviceId, axis: AxisId, value: f64 },
 
     /// The window needs to be redrawn.
-    Refresh,
+    ///
+    /// `damage` contains the parts of the window that need to be redrawn, when the system tells
+    /// which ones they are. When it's `None`, the whole window needs to be redrawn.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Sent for `WM_PAINT`, with the rectangles of the window's update region.
+    /// - **X11:** Sent once for each series of `Expose` events, with their rectangles.
+    /// - **Wayland / Android:** `damage` is always `None`.
+    Refresh { damage: Option<Vec<DamageRect>> },
 
     /// Touch event has been received
     Touch(Touch),
@@ -106,6 +430,35 @@ pub enum WindowEvent {
     ///
     /// For more information about DPI in general, see the [`dpi`](dpi/index.html) module.
     HiDpiFactorChanged(f64),
+
+    /// The parts of the window covered by a display cutout or by system UI have changed. Contains
+    /// the new value of `Window::get_safe_area_insets`.
+    ///
+    /// See `Window::get_safe_area_insets` for the platforms this is generated on.
+    SafeAreaChanged(Insets),
+
+    /// The user interface has been rotated along with the device. The window is also resized,
+    /// which is reported separately as `Resized`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only generated on iOS, including once when the window is first laid out. The orientations
+    /// the window can take are set with `os::ios::WindowBuilderExt::with_supported_orientations`.
+    OrientationChanged(Orientation),
+
+    /// The on-screen keyboard was shown or hidden.
+    ///
+    /// `position` and `size` describe the area covered by the keyboard, relative to the top-left
+    /// corner of the window's client area. It may extend past the window. Both are zero when the
+    /// keyboard is hidden. Since the keyboard may cover the text being typed, you'll usually want
+    /// to scroll that text out of its way.
+    ///
+    /// See `Window::set_virtual_keyboard_visible` for the platforms this is generated on.
+    VirtualKeyboardVisibilityChanged {
+        visible: bool,
+        position: LogicalPosition,
+        size: LogicalSize,
+    },
 }
 
 /// Represents raw hardware events that are not associated with any particular window.
@@ -117,13 +470,37 @@ pub enum WindowEvent {
 ///
 /// Note that these events are delivered regardless of input focus.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DeviceEvent {
+    /// A mouse, keyboard, or game controller was attached. The devices that are already attached
+    /// when the `EventsLoop` is created aren't reported.
+    ///
+    /// The device id can be looked up in `EventLoopWindowTarget::device_infos` to get the name
+    /// and kind of the device.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Also sent for master devices, which aren't in `device_infos`.
+    /// - **macOS:** Every device has the same `DeviceId`, so these events only tell that the
+    ///   attached devices changed.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Not generated.
     Added,
+    /// A device was detached. The same conditions that apply to `Added` apply to this event.
     Removed,
 
     /// Change in physical position of a pointing device.
     ///
     /// This represents raw, unfiltered physical motion. Not to be confused with `WindowEvent::CursorMoved`.
+    ///
+    /// Positive `x` is to the right and positive `y` is downwards, like window coordinates. Unlike
+    /// the cursor position, the motion isn't clamped to the edges of the screen, and keeps being
+    /// reported while the cursor is grabbed, which makes it suitable for first-person camera
+    /// controls.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** The motion includes the system's pointer acceleration.
+    /// - **Wayland:** Requires support for the `zwp_relative_pointer_manager_v1` protocol.
     MouseMotion {
         /// (x, y) change in position in unspecified units.
         ///
@@ -147,7 +524,8 @@ pub enum DeviceEvent {
 }
 
 /// Describes a keyboard input event.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct KeyboardInput {
     /// Identifies the physical key pressed
     ///
@@ -168,11 +546,82 @@ pub struct KeyboardInput {
     ///
     /// This is tracked internally to avoid tracking errors arising from modifier key state changes when events from
     /// this device are not being delivered to the application, e.g. due to keyboard focus being elsewhere.
-    pub modifiers: ModifiersState
+    pub modifiers: ModifiersState,
+
+    /// Whether the key is part of an IME composition, i.e. the input method is using it to edit the
+    /// text being composed rather than it being regular input.
+    ///
+    /// These events are still delivered so that key state stays consistent, but applications that
+    /// use the keyboard for both text and other controls should usually ignore them for the latter.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only macOS and the web report this for now. It's always `false` on other platforms.
+    pub is_composing: bool,
+
+    /// Whether the event wasn't caused by the key actually being pressed or released, but was sent
+    /// so that applications learn about keys that were pressed while the window didn't have focus.
+    ///
+    /// When a window gains focus, a synthetic press is sent for each key that's already held
+    /// down, and when it loses focus, a synthetic release is sent for each key that's still held
+    /// down. These are only sent as `WindowEvent`s, never as `DeviceEvent`s.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only X11, Windows, and macOS send synthetic events for now. It's always `false` on other
+    /// platforms.
+    pub is_synthetic: bool,
+
+    /// Whether the key press was generated by the key being held down, rather than the key
+    /// actually being pressed. Text editors usually handle these like regular presses, while games
+    /// usually ignore them.
+    ///
+    /// This is always `false` for releases, and for `DeviceEvent`s.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Compositors leave key repeat to clients, so winit sends these itself, at the
+    ///   rate and after the delay given by the compositor.
+    /// - **Android / iOS:** Always `false`.
+    pub is_repeat: bool,
+
+    /// The text the key press produced, if any.
+    ///
+    /// `WindowEvent::ReceivedCharacter` is still sent for each of its characters, right after
+    /// this event, so applications can take the text from either one, but not both. Taking it
+    /// from here tells which key produced it, which `ReceivedCharacter` doesn't.
+    ///
+    /// This is `None` for releases, for `DeviceEvent`s, and for the keys that are used by an
+    /// input method. The text an input method commits isn't produced by a single key, so it's
+    /// only sent as `ReceivedCharacter`s.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** A dead key followed by a key it can't be combined with produces both
+    ///   characters, in the second key's event.
+    /// - **Web:** The text is the `key` of the event when it's a single character.
+    /// - **Android / iOS / Emscripten:** Always `None`.
+    pub text: Option<String>,
+
+    /// Whether the key press is a dead key, or a compose key, which starts a sequence that's
+    /// combined with the keys that follow into a single character. It doesn't produce text by
+    /// itself, so applications like terminals can use this to show that an accent is pending
+    /// until text is received.
+    ///
+    /// This is always `false` for releases, and for `DeviceEvent`s.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The other keys of the sequence are used by the input method, so no
+    ///   `KeyboardInput` is sent for their presses, and the text is only sent as
+    ///   `ReceivedCharacter`s.
+    /// - **Android / iOS / Emscripten:** Always `false`.
+    pub is_dead_key: bool,
 }
 
 /// Describes touch-screen input state.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TouchPhase {
     Started,
     Moved,
@@ -196,12 +645,58 @@ pub enum TouchPhase {
 ///
 /// Touch may be cancelled if for example window lost focus.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Touch {
     pub device_id: DeviceId,
     pub phase: TouchPhase,
     pub location: LogicalPosition,
     /// unique identifier of a finger.
-    pub id: u64
+    pub id: u64,
+    /// How hard the screen is being pressed, if the device can tell.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS:** Only available on devices with 3D Touch, as a `Force::Calibrated`.
+    /// - **Windows:** Requires Windows 8.
+    /// - **X11:** Reported by devices with an `ABS_MT_PRESSURE` axis.
+    /// - **Web:** Depends on the browser, as a `Force::Normalized`.
+    /// - Always `None` on Wayland, Android, and Emscripten.
+    pub force: Option<Force>,
+    /// The size of the area in contact with the screen, if the device can tell.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only available on iOS, Windows and the web. On iOS, the area is assumed to be a circle, so both
+    /// dimensions are the same.
+    pub contact_size: Option<LogicalSize>,
+}
+
+/// Describes the force of a touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Force {
+    /// The force is calibrated so that the same value corresponds to roughly the same amount of
+    /// pressure regardless of the device.
+    Calibrated {
+        /// The force of the touch, where 1.0 represents the force of an average touch.
+        force: f64,
+        /// The maximum force the device can report.
+        max_possible_force: f64,
+    },
+    /// The force relative to the maximum the device can report, between 0.0 and 1.0.
+    ///
+    /// How hard 1.0 actually is depends on the device.
+    Normalized(f64),
+}
+
+impl Force {
+    /// Returns the force relative to the maximum the device can report, between 0.0 and 1.0.
+    pub fn normalized(&self) -> f64 {
+        match *self {
+            Force::Calibrated { force, max_possible_force } => force / max_possible_force,
+            Force::Normalized(force) => force,
+        }
+    }
 }
 
 /// Hardware-dependent keyboard scan code.
@@ -215,6 +710,7 @@ pub type ButtonId = u32;
 
 /// Describes the input state of a key.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ElementState {
     Pressed,
     Released,
@@ -222,6 +718,7 @@ pub enum ElementState {
 
 /// Describes a button of a mouse controller.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MouseButton {
     Left,
     Right,
@@ -231,6 +728,7 @@ pub enum MouseButton {
 
 /// Describes a difference in the mouse scroll wheel state.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MouseScrollDelta {
 	/// Amount in lines or rows to scroll in the horizontal
 	/// and vertical directions.
@@ -250,6 +748,7 @@ pub enum MouseScrollDelta {
 /// Symbolic name for a keyboard key.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 #[repr(u32)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VirtualKeyCode {
     /// The '1' key over the letters.
     Key1,
@@ -440,6 +939,7 @@ pub enum VirtualKeyCode {
 ///
 /// Each field of this struct represents a modifier and is `true` if this modifier is active.
 #[derive(Default, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ModifiersState {
     /// The "shift" key
     pub shift: bool,
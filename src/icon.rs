@@ -1,7 +1,9 @@
-use std::{fmt, mem};
+use std::{cmp, fmt, mem};
 use std::error::Error;
 #[cfg(feature = "icon_loading")]
-use std::io::{BufRead, Seek};
+use std::fs::File;
+#[cfg(feature = "icon_loading")]
+use std::io::{BufRead, Read, Seek};
 #[cfg(feature = "icon_loading")]
 use std::path::Path;
 
@@ -20,7 +22,8 @@ pub(crate) struct Pixel {
 pub(crate) const PIXEL_SIZE: usize = mem::size_of::<Pixel>();
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-/// An error produced when using `Icon::from_rgba` with invalid arguments.
+/// An error produced when using `Icon::from_rgba`, `Icon::from_rgba_set`, or
+/// `IconImage::from_rgba` with invalid arguments.
 pub enum BadIcon {
     /// Produced when the length of the `rgba` argument isn't divisible by 4, thus `rgba` can't be
     /// safely interpreted as 32bpp RGBA pixels.
@@ -35,6 +38,8 @@ pub enum BadIcon {
         width_x_height: usize,
         pixel_count: usize,
     },
+    /// Produced when `Icon::from_rgba_set` is given no images.
+    NoImages,
 }
 
 impl fmt::Display for BadIcon {
@@ -53,6 +58,7 @@ impl fmt::Display for BadIcon {
                 "The specified dimensions ({:?}x{:?}) don't match the number of pixels supplied by the `rgba` argument ({:?}). For those dimensions, the expected pixel count is {:?}.",
                 width, height, pixel_count, width_x_height,
             ),
+            &BadIcon::NoImages => "An icon needs at least one image.".to_owned(),
         };
         write!(formatter, "{}", msg)
     }
@@ -68,20 +74,16 @@ impl Error for BadIcon {
     }
 }
 
+/// One image of an `Icon`, in 32bpp RGBA.
 #[derive(Debug, Clone, PartialEq, Eq)]
-/// An icon used for the window titlebar, taskbar, etc.
-///
-/// Enabling the `icon_loading` feature provides you with several convenience methods for creating
-/// an `Icon` from any format supported by the [image](https://github.com/PistonDevelopers/image)
-/// crate.
-pub struct Icon {
+pub struct IconImage {
     pub(crate) rgba: Vec<u8>,
     pub(crate) width: u32,
     pub(crate) height: u32,
 }
 
-impl Icon {
-    /// Creates an `Icon` from 32bpp RGBA data.
+impl IconImage {
+    /// Creates an `IconImage` from 32bpp RGBA data.
     ///
     /// The length of `rgba` must be divisible by 4, and `width * height` must equal
     /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
@@ -98,50 +100,329 @@ impl Icon {
                 pixel_count,
             })
         } else {
-            Ok(Icon { rgba, width, height })
+            Ok(IconImage { rgba, width, height })
         }
     }
 
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels of the image, row by row from the top.
+    #[inline]
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An icon used for the window titlebar, taskbar, etc.
+///
+/// An icon can have several images of different sizes, in which case each platform picks the one
+/// that suits it best.
+///
+/// Enabling the `icon_loading` feature provides you with several convenience methods for creating
+/// an `Icon` from any format supported by the [image](https://github.com/PistonDevelopers/image)
+/// crate, as well as from .ico and .icns files with all of their images.
+pub struct Icon {
+    // Sorted from smallest to largest, and never empty.
+    pub(crate) images: Vec<IconImage>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedIconImage {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedIcon {
+    images: Vec<IconImage>,
+}
+
+// Deserializing goes through `from_rgba` and `from_rgba_set`, so that the images are checked.
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for IconImage {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let image = SerializedIconImage {
+            rgba: self.rgba.clone(),
+            width: self.width,
+            height: self.height,
+        };
+        ::serde::Serialize::serialize(&image, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for IconImage {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let image: SerializedIconImage = ::serde::Deserialize::deserialize(deserializer)?;
+        IconImage::from_rgba(image.rgba, image.width, image.height)
+            .map_err(<D::Error as ::serde::de::Error>::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Icon {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let icon = SerializedIcon { images: self.images.clone() };
+        ::serde::Serialize::serialize(&icon, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Icon {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let icon: SerializedIcon = ::serde::Deserialize::deserialize(deserializer)?;
+        Icon::from_rgba_set(icon.images)
+            .map_err(<D::Error as ::serde::de::Error>::custom)
+    }
+}
+
+impl Icon {
+    /// Creates an `Icon` from 32bpp RGBA data.
+    ///
+    /// The length of `rgba` must be divisible by 4, and `width * height` must equal
+    /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
+        IconImage::from_rgba(rgba, width, height).map(|image| Icon { images: vec![image] })
+    }
+
+    /// Creates an `Icon` from several images of different sizes.
+    ///
+    /// Returns `BadIcon::NoImages` if `images` is empty. Images with the same dimensions as a
+    /// previous one are ignored.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Every image is given to the window manager, which picks a size.
+    /// - **Windows:** The window and tray icons use the smallest image at least as large as the
+    ///   system's small icon size (usually 16x16), and the taskbar icon the smallest one at least
+    ///   as large as the system's icon size (usually 32x32), or the largest image if none is.
+    /// - **macOS:** The tray icon gets every image, and picks one for the screen's DPI factor.
+    pub fn from_rgba_set(mut images: Vec<IconImage>) -> Result<Self, BadIcon> {
+        if images.is_empty() {
+            return Err(BadIcon::NoImages);
+        }
+        images.sort_by_key(|image| (image.width as u64) * (image.height as u64));
+        let mut unique_images: Vec<IconImage> = Vec::with_capacity(images.len());
+        for image in images {
+            let is_duplicate = unique_images.iter().any(|unique_image| {
+                (unique_image.width, unique_image.height) == (image.width, image.height)
+            });
+            if !is_duplicate {
+                unique_images.push(image);
+            }
+        }
+        Ok(Icon { images: unique_images })
+    }
+
+    /// Returns the images of the icon, from smallest to largest.
+    #[inline]
+    pub fn images(&self) -> &[IconImage] {
+        &self.images
+    }
+
+    // Returns the smallest image that's at least `size` pixels wide or high, or the largest one
+    // if none is.
+    #[allow(dead_code)]
+    pub(crate) fn into_image_for_size(mut self, size: u32) -> IconImage {
+        let index = self.images
+            .iter()
+            .position(|image| cmp::max(image.width, image.height) >= size)
+            .unwrap_or(self.images.len() - 1);
+        self.images.swap_remove(index)
+    }
+
     #[cfg(feature = "icon_loading")]
     /// Loads an `Icon` from the path of an image on the filesystem.
     ///
+    /// Every image of .ico and .icns files is loaded. For .icns files, only PNG-encoded images are
+    /// supported, which is how every size from 16x16 upward is stored by current tools.
+    ///
     /// Requires the `icon_loading` feature.
     pub fn from_path<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
-        image::open(path).map(Into::into)
+        let mut bytes = Vec::new();
+        File::open(path.as_ref())?.read_to_end(&mut bytes)?;
+        match guess_icon_format(&bytes) {
+            Some(format) => load_icon_set(&bytes, format),
+            // Falls back to guessing the format from the extension, like `image::open`.
+            None => image::open(path).map(Into::into),
+        }
     }
 
     #[cfg(feature = "icon_loading")]
     /// Loads an `Icon` from anything implementing `BufRead` and `Seek`.
     ///
+    /// If `format` is `ImageFormat::ICO`, every image of the file is loaded.
+    ///
     /// Requires the `icon_loading` feature.
     pub fn from_reader<R: BufRead + Seek>(
-        reader: R,
+        mut reader: R,
         format: image::ImageFormat,
     ) -> image::ImageResult<Self> {
-        image::load(reader, format).map(Into::into)
+        if format == image::ImageFormat::ICO {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            load_icon_set(&bytes, IconSetFormat::Ico)
+        } else {
+            image::load(reader, format).map(Into::into)
+        }
     }
 
     #[cfg(feature = "icon_loading")]
     /// Loads an `Icon` from the unprocessed bytes of an image file.
     /// Uses heuristics to determine format.
     ///
+    /// Every image of .ico and .icns files is loaded, as with `from_path`.
+    ///
     /// Requires the `icon_loading` feature.
     pub fn from_bytes(bytes: &[u8]) -> image::ImageResult<Self> {
-        image::load_from_memory(bytes).map(Into::into)
+        match guess_icon_format(bytes) {
+            Some(format) => load_icon_set(bytes, format),
+            None => image::load_from_memory(bytes).map(Into::into),
+        }
     }
 
     #[cfg(feature = "icon_loading")]
     /// Loads an `Icon` from the unprocessed bytes of an image.
     ///
+    /// If `format` is `ImageFormat::ICO`, every image of the file is loaded.
+    ///
     /// Requires the `icon_loading` feature.
     pub fn from_bytes_with_format(
         bytes: &[u8],
         format: image::ImageFormat,
     ) -> image::ImageResult<Self> {
-        image::load_from_memory_with_format(bytes, format).map(Into::into)
+        if format == image::ImageFormat::ICO {
+            load_icon_set(bytes, IconSetFormat::Ico)
+        } else {
+            image::load_from_memory_with_format(bytes, format).map(Into::into)
+        }
+    }
+}
+
+#[cfg(feature = "icon_loading")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconSetFormat {
+    Ico,
+    Icns,
+}
+
+#[cfg(feature = "icon_loading")]
+const ICO_MAGIC: &[u8] = &[0, 0, 1, 0];
+#[cfg(feature = "icon_loading")]
+const ICNS_MAGIC: &[u8] = b"icns";
+#[cfg(feature = "icon_loading")]
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[cfg(feature = "icon_loading")]
+fn guess_icon_format(bytes: &[u8]) -> Option<IconSetFormat> {
+    if bytes.starts_with(ICO_MAGIC) {
+        Some(IconSetFormat::Ico)
+    } else if bytes.starts_with(ICNS_MAGIC) {
+        Some(IconSetFormat::Icns)
+    } else {
+        None
     }
 }
 
+#[cfg(feature = "icon_loading")]
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes.get(offset..offset + 2).map(|b| b[0] as u16 | (b[1] as u16) << 8)
+}
+
+#[cfg(feature = "icon_loading")]
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| {
+        b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24
+    })
+}
+
+#[cfg(feature = "icon_loading")]
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| {
+        (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32
+    })
+}
+
+#[cfg(feature = "icon_loading")]
+fn load_icon_set(bytes: &[u8], format: IconSetFormat) -> image::ImageResult<Icon> {
+    let images = match format {
+        IconSetFormat::Ico => load_ico_images(bytes)?,
+        IconSetFormat::Icns => load_icns_images(bytes)?,
+    };
+    Icon::from_rgba_set(images).map_err(|_| {
+        let message = "The icon file doesn't contain any supported image";
+        image::ImageError::FormatError(message.to_owned())
+    })
+}
+
+// The `image` crate only decodes the largest image of an .ico file, so each image is decoded from
+// an .ico file of its own.
+#[cfg(feature = "icon_loading")]
+fn load_ico_images(bytes: &[u8]) -> image::ImageResult<Vec<IconImage>> {
+    const HEADER_SIZE: usize = 6;
+    const ENTRY_SIZE: usize = 16;
+    let truncated = || image::ImageError::FormatError("Truncated .ico file".to_owned());
+
+    let count = read_u16_le(bytes, 4).ok_or_else(truncated)? as usize;
+    let mut images = Vec::with_capacity(count);
+    for index in 0..count {
+        let entry_offset = HEADER_SIZE + index * ENTRY_SIZE;
+        let entry = bytes.get(entry_offset..entry_offset + ENTRY_SIZE).ok_or_else(truncated)?;
+        let size = read_u32_le(entry, 8).ok_or_else(truncated)? as usize;
+        let offset = read_u32_le(entry, 12).ok_or_else(truncated)? as usize;
+        let end = offset.checked_add(size).ok_or_else(truncated)?;
+        let data = bytes.get(offset..end).ok_or_else(truncated)?;
+
+        let mut single = Vec::with_capacity(HEADER_SIZE + ENTRY_SIZE + size);
+        single.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+        single.extend_from_slice(&entry[..12]);
+        single.extend_from_slice(&[(HEADER_SIZE + ENTRY_SIZE) as u8, 0, 0, 0]);
+        single.extend_from_slice(data);
+        let image = image::load_from_memory_with_format(&single, image::ImageFormat::ICO)?;
+        images.push(Icon::from(image).images.remove(0));
+    }
+    Ok(images)
+}
+
+// .icns files are a list of tagged elements. Those holding PNG data are loaded, and the others,
+// which are either JPEG 2000 or the old run-length encoded formats, are skipped.
+#[cfg(feature = "icon_loading")]
+fn load_icns_images(bytes: &[u8]) -> image::ImageResult<Vec<IconImage>> {
+    const HEADER_SIZE: usize = 8;
+    let truncated = || image::ImageError::FormatError("Truncated .icns file".to_owned());
+
+    let file_size = read_u32_be(bytes, 4).ok_or_else(truncated)? as usize;
+    let bytes = bytes.get(..file_size).ok_or_else(truncated)?;
+    let mut images = Vec::new();
+    let mut offset = HEADER_SIZE;
+    while offset < bytes.len() {
+        let element_size = read_u32_be(bytes, offset + 4).ok_or_else(truncated)? as usize;
+        if element_size < HEADER_SIZE {
+            return Err(image::ImageError::FormatError("Invalid .icns element size".to_owned()));
+        }
+        let end = offset.checked_add(element_size).ok_or_else(truncated)?;
+        let data = bytes.get(offset + HEADER_SIZE..end).ok_or_else(truncated)?;
+        if data.starts_with(PNG_MAGIC) {
+            let image = image::load_from_memory_with_format(data, image::ImageFormat::PNG)?;
+            images.push(Icon::from(image).images.remove(0));
+        }
+        offset = end;
+    }
+    Ok(images)
+}
+
 #[cfg(feature = "icon_loading")]
 /// Requires the `icon_loading` feature.
 impl From<image::DynamicImage> for Icon {
@@ -152,7 +433,7 @@ impl From<image::DynamicImage> for Icon {
         for (_, _, pixel) in image.pixels() {
             rgba.extend_from_slice(&pixel.to_rgba().data);
         }
-        Icon { rgba, width, height }
+        Icon { images: vec![IconImage { rgba, width, height }] }
     }
 }
 
@@ -165,6 +446,99 @@ impl From<image::RgbaImage> for Icon {
         for (_, _, pixel) in buf.enumerate_pixels() {
             rgba.extend_from_slice(&pixel.data);
         }
-        Icon { rgba, width, height }
+        Icon { images: vec![IconImage { rgba, width, height }] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32) -> IconImage {
+        IconImage::from_rgba(vec![0; (width * height) as usize * PIXEL_SIZE], width, height)
+            .unwrap()
+    }
+
+    #[test]
+    fn from_rgba_set() {
+        let cases = vec![
+            (vec![], None),
+            (vec![(16, 16)], Some(vec![(16, 16)])),
+            (vec![(32, 32), (16, 16)], Some(vec![(16, 16), (32, 32)])),
+            (vec![(16, 16), (32, 32), (16, 16)], Some(vec![(16, 16), (32, 32)])),
+            (vec![(0, 0), (16, 8)], Some(vec![(0, 0), (16, 8)])),
+        ];
+        for (sizes, expected) in cases {
+            let images = sizes.iter().map(|&(width, height)| image(width, height)).collect();
+            let result = Icon::from_rgba_set(images).map(|icon| {
+                icon.images().iter().map(|image| (image.width(), image.height())).collect()
+            });
+            match expected {
+                Some(expected) => assert_eq!(result, Ok(expected), "{:?}", sizes),
+                None => assert_eq!(result, Err(BadIcon::NoImages), "{:?}", sizes),
+            }
+        }
+    }
+
+    #[cfg(feature = "icon_loading")]
+    fn ico(count: u16, entries: &[(u32, u32)], data_len: usize) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 1, 0, count as u8, (count >> 8) as u8];
+        for &(size, offset) in entries {
+            bytes.extend_from_slice(&[16, 16, 0, 0, 1, 0, 32, 0]);
+            for value in &[size, offset] {
+                bytes.extend((0..4).map(|shift| (value >> (shift * 8)) as u8));
+            }
+        }
+        bytes.extend(vec![0; data_len]);
+        bytes
+    }
+
+    #[cfg(feature = "icon_loading")]
+    #[test]
+    fn load_ico_images_rejects_bad_entries() {
+        let cases = vec![
+            ("no header", vec![0, 0, 1, 0]),
+            ("missing entry", ico(1, &[], 0)),
+            ("truncated entry", ico(1, &[], 0).into_iter().chain(vec![0; 8]).collect()),
+            ("data past the end", ico(1, &[(64, 22)], 32)),
+            ("offset past the end", ico(1, &[(8, 1000)], 32)),
+            ("overflowing size", ico(1, &[(u32::max_value(), 22)], 32)),
+            ("overflowing offset", ico(1, &[(8, u32::max_value())], 32)),
+        ];
+        for (name, bytes) in cases {
+            assert!(load_ico_images(&bytes).is_err(), "{}", name);
+        }
+        assert_eq!(load_ico_images(&ico(0, &[], 0)).unwrap().len(), 0);
+    }
+
+    #[cfg(feature = "icon_loading")]
+    fn icns(file_size: u32, elements: &[(&[u8], u32, usize)]) -> Vec<u8> {
+        let mut bytes = b"icns".to_vec();
+        bytes.extend((0..4).rev().map(|shift| (file_size >> (shift * 8)) as u8));
+        for &(tag, element_size, data_len) in elements {
+            bytes.extend_from_slice(tag);
+            bytes.extend((0..4).rev().map(|shift| (element_size >> (shift * 8)) as u8));
+            bytes.extend(vec![0; data_len]);
+        }
+        bytes
+    }
+
+    #[cfg(feature = "icon_loading")]
+    #[test]
+    fn load_icns_images_rejects_bad_elements() {
+        let cases = vec![
+            ("no header", b"icns".to_vec()),
+            ("file size past the end", icns(64, &[])),
+            ("truncated element header", icns(12, &[(b"ic07", 8, 0)])[..12].to_vec()),
+            ("element size too small", icns(16, &[(b"ic07", 4, 0)])),
+            ("element past the end", icns(24, &[(b"ic07", 32, 8)])),
+            ("overflowing element size", icns(24, &[(b"ic07", u32::max_value(), 8)])),
+        ];
+        for (name, bytes) in cases {
+            assert!(load_icns_images(&bytes).is_err(), "{}", name);
+        }
+        // Elements that aren't PNG-encoded are skipped.
+        let bytes = icns(24, &[(b"it32", 16, 8)]);
+        assert_eq!(load_icns_images(&bytes).unwrap().len(), 0);
     }
 }
@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use Event;
+use platform;
+
+// How often the idle time is checked while the user is idle. While the user is active, we know
+// when they can become idle at the earliest, and don't need to check before then.
+const IDLE_POLL_INTERVAL_MS: u64 = 250;
+
+/// Watches the system's idle time on a background thread, queuing `UserIdle` and `UserActive`
+/// events for the `EventsLoop` and waking it up whenever there's a new one.
+pub(crate) struct IdleMonitor {
+    events: Arc<Mutex<VecDeque<Event>>>,
+    // Dropping the sender stops the thread.
+    stop: mpsc::Sender<()>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl IdleMonitor {
+    pub fn new(
+        query: platform::IdleTimeQuery,
+        proxy: platform::EventsLoopProxy,
+        threshold: Duration,
+    ) -> IdleMonitor {
+        let events = Arc::new(Mutex::new(VecDeque::new()));
+        let (stop_sender, stop_receiver) = mpsc::channel();
+
+        let thread_events = events.clone();
+        let thread = thread::spawn(move || {
+            let mut idle = false;
+            loop {
+                let interval = match query.get_idle_time() {
+                    Some(idle_time) => {
+                        if (idle_time >= threshold) != idle {
+                            idle = !idle;
+                            let event = if idle { Event::UserIdle } else { Event::UserActive };
+                            thread_events.lock().unwrap().push_back(event);
+                            if proxy.wakeup().is_err() {
+                                return;
+                            }
+                        }
+                        if idle {
+                            Duration::from_millis(IDLE_POLL_INTERVAL_MS)
+                        } else {
+                            threshold - idle_time
+                        }
+                    },
+                    None => Duration::from_millis(IDLE_POLL_INTERVAL_MS),
+                };
+                match stop_receiver.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => (),
+                    _ => return,
+                }
+            }
+        });
+
+        IdleMonitor {
+            events,
+            stop: stop_sender,
+            thread,
+        }
+    }
+
+    /// Stops the background thread, and waits for it to exit.
+    pub fn stop(self) {
+        let IdleMonitor { stop, thread, .. } = self;
+        drop(stop);
+        let _ = thread.join();
+    }
+
+    /// Returns the next event to be delivered, if any.
+    pub fn next_event(&self) -> Option<Event> {
+        self.events.lock().unwrap().pop_front()
+    }
+}
@@ -62,7 +62,7 @@
 //! # use winit::EventsLoop;
 //! # let mut events_loop = EventsLoop::new();
 //!
-//! events_loop.run_forever(|event| {
+//! events_loop.run_forever(|event, _| {
 //!     match event {
 //!         Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
 //!             println!("The close button was pressed; stopping");
@@ -75,7 +75,8 @@
 //!
 //! If you use multiple windows, the `WindowEvent` event has a member named `window_id`. You can
 //! compare it with the value returned by the `id()` method of `Window` in order to know which
-//! window has received the event.
+//! window has received the event. Windows can also be created while `run_forever` is running,
+//! using the `EventLoopWindowTarget` it passes to the callback.
 //!
 //! # Drawing on the window
 //!
@@ -92,13 +93,24 @@ extern crate libc;
 extern crate log;
 #[cfg(feature = "icon_loading")]
 extern crate image;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "futures")]
+extern crate futures_core;
+#[cfg(feature = "tracing")]
+#[macro_use(trace_span)]
+extern crate tracing;
 
 #[cfg(target_os = "windows")]
+#[macro_use]
 extern crate winapi;
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 #[macro_use]
 extern crate objc;
 #[cfg(target_os = "macos")]
+extern crate objc_exception;
+#[cfg(target_os = "macos")]
 extern crate cocoa;
 #[cfg(target_os = "macos")]
 extern crate core_foundation;
@@ -112,19 +124,46 @@ extern crate parking_lot;
 extern crate percent_encoding;
 #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
 extern crate smithay_client_toolkit as sctk;
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+extern crate wayland_protocols;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+extern crate wasm_bindgen;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+extern crate web_sys;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub(crate) use dpi::*; // TODO: Actually change the imports throughout the codebase.
 pub use events::*;
 pub use window::{AvailableMonitorsIter, MonitorId};
 pub use icon::*;
+pub use subscription::{DispatchOrder, SubscriptionHandle};
+#[cfg(feature = "futures")]
+pub use stream::EventStream;
 
+pub mod accessibility;
 pub mod dpi;
 mod events;
 mod icon;
+mod idle;
+pub mod menu;
 mod platform;
+mod redraw;
+#[cfg(feature = "futures")]
+mod stream;
+mod subscription;
 mod window;
 
 pub mod os;
+#[cfg(feature = "shell")]
+pub mod shell;
+#[cfg(feature = "surface")]
+pub mod surface;
+#[cfg(feature = "test-util")]
+pub mod test;
+pub mod tray;
 
 /// Represents a window.
 ///
@@ -136,7 +175,7 @@ pub mod os;
 /// let mut events_loop = EventsLoop::new();
 /// let window = Window::new(&events_loop).unwrap();
 ///
-/// events_loop.run_forever(|event| {
+/// events_loop.run_forever(|event, _| {
 ///     match event {
 ///         Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
 ///             ControlFlow::Break
@@ -145,8 +184,15 @@ pub mod os;
 ///     }
 /// });
 /// ```
+///
+/// A `Window` is `Send` and `Sync`, so it can be used from other threads than the one running its
+/// `EventsLoop`, except on the web with the `atomics` target feature, since the browser's objects
+/// it refers to are tied to the thread that created them.
 pub struct Window {
     window: platform::Window,
+    subscribers: subscription::Subscribers,
+    redraws: redraw::RedrawRequester,
+    _live: Arc<()>,
 }
 
 /// Identifier of a window. Unique for each window.
@@ -166,6 +212,32 @@ pub struct WindowId(platform::WindowId);
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId(platform::DeviceId);
 
+// IDs are serialized as opaque integers. They're only meaningful to the process that produced them.
+#[cfg(feature = "serde")]
+macro_rules! serialize_as_raw_id {
+    ($id:path, $platform_id:path) => {
+        impl serde::Serialize for $id {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_u64(self.0.into_raw())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $id {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let raw = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+                Ok($id($platform_id(raw)))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+serialize_as_raw_id!(WindowId, platform::WindowId::from_raw);
+#[cfg(feature = "serde")]
+serialize_as_raw_id!(DeviceId, platform::DeviceId::from_raw);
+#[cfg(feature = "serde")]
+serialize_as_raw_id!(tray::TrayIconId, platform::TrayIconId::from_raw);
+
 /// Provides a way to retrieve events from the system and from the windows that were registered to
 /// the events loop.
 ///
@@ -179,11 +251,79 @@ pub struct DeviceId(platform::DeviceId);
 /// forbiding it), as such it is neither `Send` nor `Sync`. If you need cross-thread access, the
 /// `Window` created from this `EventsLoop` _can_ be sent to an other thread, and the
 /// `EventsLoopProxy` allows you to wakeup an `EventsLoop` from an other thread.
+///
+/// An `EventsLoop` dereferences to an `EventLoopWindowTarget`, so it can be used wherever one is
+/// expected.
 pub struct EventsLoop {
+    window_target: EventLoopWindowTarget,
+    // When the last frame started, if `poll_events_vsync_aligned` had to pace frames by itself.
+    last_frame: Option<Instant>,
+    idle_monitor: Option<idle::IdleMonitor>,
+}
+
+/// The part of an `EventsLoop` that windows are created with.
+///
+/// It's passed to the callback given to `EventsLoop::run_forever`, so that windows can be created
+/// while the `EventsLoop` is running. Outside of that callback, an `EventsLoop` can be used
+/// directly, since it dereferences to its `EventLoopWindowTarget`.
+///
+/// ```no_run
+/// use winit::{ControlFlow, Event, EventsLoop, Window, WindowEvent};
+///
+/// let mut events_loop = EventsLoop::new();
+/// let mut windows = vec![Window::new(&events_loop).unwrap()];
+///
+/// events_loop.run_forever(|event, window_target| {
+///     match event {
+///         Event::WindowEvent { event: WindowEvent::ReceivedCharacter('n'), .. } => {
+///             windows.push(Window::new(window_target).unwrap());
+///         },
+///         Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+///             return ControlFlow::Break;
+///         },
+///         _ => (),
+///     }
+///     ControlFlow::Continue
+/// });
+/// ```
+pub struct EventLoopWindowTarget {
     events_loop: platform::EventsLoop,
+    // Events queued with `test::EventsLoopExt::inject_event`.
+    #[cfg(feature = "test-util")]
+    injected_events: test::EventQueue,
+    // Callbacks registered with `Window::on_event`.
+    subscribers: subscription::Subscribers,
+    // Redraws requested with `Window::request_redraw`.
+    redraws: redraw::RedrawQueue,
+    // Cloned into each window and proxy created with this target, so that `EventsLoop::shutdown`
+    // can tell how many are still alive.
+    live_windows: Arc<()>,
+    live_proxies: Arc<()>,
+    // Set by `EventsLoop::set_forced_hidpi_factor`.
+    forced_hidpi_factor: Option<f64>,
     _marker: ::std::marker::PhantomData<*mut ()> // Not Send nor Sync
 }
 
+/// How the windows of an application are presented by the system.
+///
+/// See `EventsLoop::set_activation_policy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivationPolicy {
+    /// The application appears in the dock or taskbar, and in the application switcher.
+    Regular,
+    /// The application doesn't appear in the dock or taskbar, nor in the application switcher,
+    /// but its windows can still be activated, for example by clicking them.
+    Accessory,
+    /// Like `Accessory`. On macOS, the application also can't create windows or be activated.
+    Prohibited,
+}
+
+impl Default for ActivationPolicy {
+    fn default() -> Self {
+        ActivationPolicy::Regular
+    }
+}
+
 /// Returned by the user callback given to the `EventsLoop::run_forever` method.
 ///
 /// Indicates whether the `run_forever` method should continue or complete.
@@ -202,50 +342,449 @@ impl EventsLoop {
     /// using an environment variable `WINIT_UNIX_BACKEND`. Legal values are `x11` and `wayland`.
     /// If it is not set, winit will try to connect to a wayland connection, and if it fails will
     /// fallback on x11. If this variable is set with any other value, winit will panic.
+    ///
+    /// Panics if no display backend is available. Use `try_new` to handle that case.
     pub fn new() -> EventsLoop {
-        EventsLoop {
-            events_loop: platform::EventsLoop::new(),
-            _marker: ::std::marker::PhantomData,
-        }
+        EventsLoop::from_platform(platform::EventsLoop::new())
     }
 
-    /// Returns the list of all the monitors available on the system.
+    /// Builds a new events loop, like `new`, whose event queues have room for `capacity` events.
     ///
-    // Note: should be replaced with `-> impl Iterator` once stable.
-    #[inline]
-    pub fn get_available_monitors(&self) -> AvailableMonitorsIter {
-        let data = self.events_loop.get_available_monitors();
-        AvailableMonitorsIter{ data: data.into_iter() }
+    /// Events are queued between being received from the system and being passed to the
+    /// callback. The queues keep their memory once they've grown, so they only allocate when a
+    /// burst of events doesn't fit. Giving the size of the largest expected burst avoids
+    /// allocating while events are delivered.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Has no effect. Events are sent to the `EventsLoop` through a channel, which
+    ///   still allocates for each event.
+    /// - **Android and Emscripten:** Has no effect.
+    pub fn with_event_capacity(capacity: usize) -> EventsLoop {
+        let mut events_loop = EventsLoop::new();
+        events_loop.window_target.events_loop.reserve_events(capacity);
+        events_loop
     }
 
-    /// Returns the primary monitor of the system.
-    #[inline]
-    pub fn get_primary_monitor(&self) -> MonitorId {
-        MonitorId { inner: self.events_loop.get_primary_monitor() }
+    /// Builds a new events loop, or returns an error if no display backend is available.
+    ///
+    /// This only fails on Linux, when neither Wayland nor X11 can be connected to, or when
+    /// `WINIT_UNIX_BACKEND` has a value other than `x11` or `wayland`.
+    pub fn try_new() -> Result<EventsLoop, EventsLoopCreationError> {
+        platform::EventsLoop::try_new().map(EventsLoop::from_platform)
+    }
+
+    pub(crate) fn from_platform(events_loop: platform::EventsLoop) -> EventsLoop {
+        let proxy = events_loop.create_proxy();
+        let redraws = redraw::RedrawQueue::new(Box::new(move || proxy.wakeup()));
+        EventsLoop {
+            window_target: EventLoopWindowTarget {
+                events_loop,
+                #[cfg(feature = "test-util")]
+                injected_events: test::EventQueue::new(),
+                subscribers: Default::default(),
+                redraws,
+                live_windows: Arc::new(()),
+                live_proxies: Arc::new(()),
+                forced_hidpi_factor: None,
+                _marker: ::std::marker::PhantomData,
+            },
+            last_frame: None,
+            idle_monitor: None,
+        }
     }
 
     /// Fetches all the events that are pending, calls the callback function for each of them,
     /// and returns.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS:** Only the events that are already queued are delivered, since UIKit can't be run
+    ///   one event at a time. Use `run_forever` instead.
     #[inline]
     pub fn poll_events<F>(&mut self, callback: F)
         where F: FnMut(Event)
     {
-        self.events_loop.poll_events(callback)
+        self.poll_events_shared(callback)
+    }
+
+    // `poll_events` and `run_forever` only need a shared borrow, which `run_async` relies on to
+    // lend the `EventLoopWindowTarget` to the future while the `EventsLoop` is running.
+    fn poll_events_shared<F>(&self, mut callback: F)
+        where F: FnMut(Event)
+    {
+        let subscribers = self.window_target.subscribers.clone();
+        let redraws = &self.window_target.redraws;
+        let mut callback = |event| {
+            redraws.observe(&event);
+            subscribers.dispatch(event, &mut callback)
+        };
+        self.window_target.dispatch_injected_events(|event| {
+            callback(event);
+            ControlFlow::Continue
+        });
+        self.events_loop.poll_events(&mut callback);
+        if let Some(ref idle_monitor) = self.idle_monitor {
+            while let Some(event) = idle_monitor.next_event() {
+                callback(event);
+            }
+        }
+        while let Some(event) = redraws.next_event() {
+            callback(event);
+        }
+    }
+
+    /// Waits for the next vertical blank of the monitor the focused window is on, and then does
+    /// the same as `poll_events`.
+    ///
+    /// Most applications that call `poll_events` in a loop only want to render once per frame.
+    /// Calling this instead paces the loop to the monitor's refresh rate, rather than spinning as
+    /// fast as possible.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Uses DXGI. If no adapter drives the monitor, as in remote desktop sessions,
+    ///   waits for the desktop compositor instead, which follows the primary monitor.
+    /// - **macOS:** Uses a `CVDisplayLink`.
+    /// - **Web:** Returns immediately, since the browser's thread can't block. Use `run_forever`
+    ///   instead.
+    /// - **X11 and Wayland:** Unsupported, so the loop is paced to 60 Hz with a timer instead, as
+    ///   on other platforms and on Windows with neither DXGI nor composition.
+    pub fn poll_events_vsync_aligned<F>(&mut self, callback: F)
+        where F: FnMut(Event)
+    {
+        if self.window_target.events_loop.wait_for_vblank() {
+            self.last_frame = None;
+        } else {
+            let frame_duration = Duration::new(0, 1_000_000_000 / 60);
+            if let Some(last_frame) = self.last_frame {
+                let elapsed = last_frame.elapsed();
+                if elapsed < frame_duration {
+                    thread::sleep(frame_duration - elapsed);
+                }
+            }
+            self.last_frame = Some(Instant::now());
+        }
+        self.poll_events(callback)
     }
 
     /// Calls `callback` every time an event is received. If no event is available, sleeps the
     /// current thread and waits for an event. If the callback returns `ControlFlow::Break` then
     /// `run_forever` will immediately return.
     ///
+    /// The callback is also given the `EventLoopWindowTarget` of the `EventsLoop`, which windows
+    /// can be created with while it's running.
+    ///
     /// # Danger!
     ///
     /// The callback is run after *every* event, so if its execution time is non-trivial the event queue may not empty
     /// at a sufficient rate. Rendering in the callback with vsync enabled **will** cause significant lag.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Web:** Never returns, since the browser only delivers events once control returns to
+    ///   it. After the callback returns `ControlFlow::Break`, it isn't called again.
+    /// - **iOS:** Never returns, since it hands the main thread over to UIKit. After the callback
+    ///   returns `ControlFlow::Break`, it isn't called again.
     #[inline]
     pub fn run_forever<F>(&mut self, callback: F)
-        where F: FnMut(Event) -> ControlFlow
+        where F: FnMut(Event, &EventLoopWindowTarget) -> ControlFlow
+    {
+        self.run_forever_shared(callback)
+    }
+
+    fn run_forever_shared<F>(&self, mut callback: F)
+        where F: FnMut(Event, &EventLoopWindowTarget) -> ControlFlow
+    {
+        let window_target = &self.window_target;
+        let idle_monitor = &self.idle_monitor;
+        let subscribers = &window_target.subscribers;
+        let redraws = &window_target.redraws;
+        let mut callback = |event: Event, window_target: &EventLoopWindowTarget| {
+            redraws.observe(&event);
+            subscribers.dispatch(event, |event| callback(event, window_target))
+        };
+        let dispatch_injected = |callback: &mut FnMut(Event, &EventLoopWindowTarget) -> ControlFlow| {
+            window_target.dispatch_injected_events(|event| callback(event, window_target))
+        };
+        if dispatch_injected(&mut callback) == ControlFlow::Break {
+            return;
+        }
+        window_target.events_loop.run_forever(|event| {
+            if callback(event, window_target) == ControlFlow::Break {
+                return ControlFlow::Break;
+            }
+            if dispatch_injected(&mut callback) == ControlFlow::Break {
+                return ControlFlow::Break;
+            }
+            if let Some(ref idle_monitor) = *idle_monitor {
+                while let Some(event) = idle_monitor.next_event() {
+                    if callback(event, window_target) == ControlFlow::Break {
+                        return ControlFlow::Break;
+                    }
+                }
+            }
+            while let Some(event) = redraws.next_event() {
+                if callback(event, window_target) == ControlFlow::Break {
+                    return ControlFlow::Break;
+                }
+            }
+            ControlFlow::Continue
+        })
+    }
+
+    /// Runs the future returned by `f` to completion, driving it with the `EventsLoop`, and
+    /// returns its output.
+    ///
+    /// The future is given an `EventStream`, which yields the events received by the `EventsLoop`
+    /// and lends out its `EventLoopWindowTarget`. The future is polled on the current thread,
+    /// whenever it's woken up, and the `EventsLoop` sleeps the rest of the time, like with
+    /// `run_forever`. Waking the future up from another thread wakes up the `EventsLoop`, which
+    /// delivers an `Event::Awakened` to the stream.
+    ///
+    /// ```ignore
+    /// use futures::StreamExt;
+    /// use winit::{Event, EventsLoop, Window, WindowEvent};
+    ///
+    /// let mut events_loop = EventsLoop::new();
+    /// events_loop.run_async(|mut events| async move {
+    ///     let _window = Window::new(events.window_target()).unwrap();
+    ///     while let Some(event) = events.next().await {
+    ///         if let Event::WindowEvent { event: WindowEvent::CloseRequested, .. } = event {
+    ///             break;
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// Requires the `futures` feature, which needs Rust 1.51 or later.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Emscripten / Web:** Not supported, and panics, since `run_forever` never returns
+    ///   there.
+    #[cfg(feature = "futures")]
+    pub fn run_async<'a, F, Fut>(&'a mut self, f: F) -> Fut::Output
+        where F: FnOnce(EventStream<'a>) -> Fut,
+              Fut: std::future::Future,
     {
-        self.events_loop.run_forever(callback)
+        if cfg!(any(target_os = "ios", target_os = "emscripten", all(target_arch = "wasm32", target_os = "unknown"))) {
+            panic!("`run_async` isn't supported on this platform, use `run_forever` instead");
+        }
+        stream::run(self, f)
+    }
+
+    /// Emits `Event::UserIdle` once the user hasn't used any input device, anywhere on the system,
+    /// for `threshold`, and `Event::UserActive` once they use one again. `None` stops emitting
+    /// these events, which is the default.
+    ///
+    /// The idle time is checked on a background thread, which wakes up the `EventsLoop` when
+    /// there's a new event, so the `EventsLoop` receives an `Awakened` event along with it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires the XScreenSaver extension and `libXss`.
+    /// - Has no effect on Wayland, iOS, Android, and Emscripten.
+    pub fn set_idle_threshold(&mut self, threshold: Option<Duration>) {
+        self.idle_monitor = threshold.and_then(|threshold| {
+            let query = self.events_loop.create_idle_time_query()?;
+            let proxy = self.events_loop.create_proxy();
+            Some(idle::IdleMonitor::new(query, proxy, threshold))
+        });
+    }
+
+    /// Sets how the windows of the application are presented by the system: whether it has an
+    /// icon in the dock or taskbar, and whether it can be switched to with Alt-Tab or Cmd-Tab.
+    /// `ActivationPolicy::Regular` is the default.
+    ///
+    /// This should be called before creating any window, as the windows that already exist aren't
+    /// affected.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Sets the activation policy of the application, which is overridden by
+    ///   `os::macos::WindowBuilderExt::with_activation_policy` when it's used.
+    /// - **Windows:** `Accessory` and `Prohibited` windows are created with `WS_EX_TOOLWINDOW`, so
+    ///   they have no taskbar button and don't appear in Alt-Tab. Their title bar is also smaller.
+    /// - **X11:** `Accessory` and `Prohibited` windows are created with the
+    ///   `_NET_WM_STATE_SKIP_TASKBAR` and `_NET_WM_STATE_SKIP_PAGER` hints.
+    /// - Has no effect on Wayland, iOS, Android, Emscripten, and Web.
+    pub fn set_activation_policy(&mut self, activation_policy: ActivationPolicy) {
+        self.window_target.events_loop.set_activation_policy(activation_policy);
+    }
+
+    /// On Windows and X11, makes the windows created afterwards use `hidpi_factor` as their DPI
+    /// factor, whichever monitor they're on. `None` goes back to using the DPI factor of their
+    /// monitor, which is the default.
+    ///
+    /// `WindowBuilder::with_forced_hidpi_factor` takes precedence over this. See it for more
+    /// information.
+    ///
+    /// Panics if `hidpi_factor` isn't a normal float greater than 0.
+    pub fn set_forced_hidpi_factor(&mut self, hidpi_factor: Option<f64>) {
+        if let Some(hidpi_factor) = hidpi_factor {
+            assert!(dpi::validate_hidpi_factor(hidpi_factor), "invalid DPI factor: {}", hidpi_factor);
+        }
+        self.window_target.forced_hidpi_factor = hidpi_factor;
+    }
+
+    /// Destroys the events loop, and waits for the background threads it started to exit.
+    ///
+    /// Dropping an `EventsLoop` releases the same resources, but doesn't wait for its threads,
+    /// so programs that create many events loops, like test suites, should prefer this. The
+    /// windows and proxies created with the events loop should be dropped first: in debug builds,
+    /// the ones that are still alive are reported with a warning through the `log` crate.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Waits for the thread that runs the Win32 message loop. The windows that are
+    ///   still alive are destroyed along with it.
+    /// - **X11:** The connection to the X server is shared by all the events loops of the
+    ///   process, and stays open for the next one, so creating more events loops doesn't open
+    ///   more connections.
+    /// - **macOS:** The Objective-C classes used by winit are registered once per process and
+    ///   reused, so they aren't unregistered.
+    pub fn shutdown(self) {
+        if cfg!(debug_assertions) {
+            let windows = Arc::strong_count(&self.live_windows) - 1;
+            if windows > 0 {
+                warn!("`EventsLoop::shutdown` was called while {} of its windows are alive", windows);
+            }
+            let proxies = Arc::strong_count(&self.live_proxies) - 1;
+            if proxies > 0 {
+                warn!("`EventsLoop::shutdown` was called while {} of its proxies are alive", proxies);
+            }
+        }
+        let EventsLoop { window_target, idle_monitor, .. } = self;
+        if let Some(idle_monitor) = idle_monitor {
+            idle_monitor.stop();
+        }
+        window_target.events_loop.shutdown();
+    }
+}
+
+impl std::ops::Deref for EventsLoop {
+    type Target = EventLoopWindowTarget;
+
+    #[inline]
+    fn deref(&self) -> &EventLoopWindowTarget {
+        &self.window_target
+    }
+}
+
+impl EventLoopWindowTarget {
+    /// Returns the list of all the monitors available on the system.
+    ///
+    // Note: should be replaced with `-> impl Iterator` once stable.
+    #[inline]
+    pub fn get_available_monitors(&self) -> AvailableMonitorsIter {
+        let data = self.events_loop.get_available_monitors();
+        AvailableMonitorsIter{ data: data.into_iter() }
+    }
+
+    /// Returns the primary monitor of the system.
+    #[inline]
+    pub fn get_primary_monitor(&self) -> MonitorId {
+        MonitorId { inner: self.events_loop.get_primary_monitor() }
+    }
+
+    /// Returns the visible windows created with this `EventsLoop`, from front to back.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires the window manager to support `_NET_CLIENT_LIST_STACKING`, and is
+    ///   empty otherwise.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Always empty.
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        self.events_loop
+            .get_window_stacking_order()
+            .into_iter()
+            .map(WindowId)
+            .collect()
+    }
+
+    /// Asks the system to wait before ending the user's session, for instance while there's unsaved
+    /// work, or allows it again with `None`. The reason may be shown to the user.
+    ///
+    /// See `Event::SessionEnding`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Logging out or shutting down waits, showing the reason, until the user
+    ///   cancels it or ends the session anyway.
+    /// - **macOS:** The application refuses to terminate, which cancels logging out or shutting
+    ///   down. The reason isn't shown. This only works if the application doesn't have its own
+    ///   `NSApplicationDelegate`.
+    /// - Has no effect on other platforms.
+    pub fn block_session_end(&self, reason: Option<&str>) {
+        self.events_loop.block_session_end(reason)
+    }
+
+    /// Captures which window has input focus, so that it can be given back with `restore_focus`,
+    /// for instance when closing a modal overlay.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Captures the key window.
+    /// - **X11:** Also captures which window's input method context is focused.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Captures nothing.
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        FocusSnapshot {
+            snapshot: self.events_loop.focus_snapshot(),
+        }
+    }
+
+    /// Gives input focus back to the window captured by `focus_snapshot`, if it still exists.
+    ///
+    /// Focus isn't taken away from other applications: this only has an effect if one of the
+    /// application's windows is already focused, or if the system allows the application to
+    /// activate itself.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The window becomes the foreground window, which also gives back its input
+    ///   method context.
+    /// - **macOS:** The window becomes the key window, whose view receives the input method's
+    ///   text.
+    /// - **X11:** The window is activated through `_NET_ACTIVE_WINDOW` when the window manager
+    ///   supports it, and is given input focus directly otherwise. The input method context that
+    ///   was focused is focused again.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Has no effect.
+    pub fn restore_focus(&self, snapshot: FocusSnapshot) {
+        self.events_loop.restore_focus(&snapshot.snapshot)
+    }
+
+    /// Returns the keyboard layouts the user can switch between, in the order the system lists
+    /// them. `Event::KeyboardLayoutChanged` is sent when they change.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Returns the groups of the XKB keymap, named after their symbols, like
+    ///   "English (US)".
+    /// - **Windows:** Returns the input locales of `GetKeyboardLayoutList`, named after their
+    ///   language, so two layouts of the same language have the same name.
+    /// - **macOS:** Returns the enabled keyboard input sources that can be selected.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Returns an empty list.
+    pub fn keyboard_layouts(&self) -> Vec<LayoutInfo> {
+        self.events_loop.keyboard_layouts()
+    }
+
+    /// Returns the input devices attached to the system, whose ids are the `DeviceId`s of the
+    /// events they generate. `DeviceEvent::Added` and `DeviceEvent::Removed` are sent when they
+    /// change.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Returns the slave and floating devices of XInput2, which are the ones of
+    ///   `DeviceEvent`s. `WindowEvent`s have the id of the master device the slave is attached to.
+    /// - **Windows:** Returns the raw input devices. The name is the product string of the device,
+    ///   or its device interface path if it doesn't have one.
+    /// - **macOS:** Returns the mice, keyboards and game controllers IOKit lists, named after
+    ///   their product string. They all have the same id, since every event has the same
+    ///   `DeviceId`.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Returns an empty list.
+    pub fn device_infos(&self) -> Vec<DeviceInfo> {
+        self.events_loop.device_infos()
     }
 
     /// Creates an `EventsLoopProxy` that can be used to wake up the `EventsLoop` from another
@@ -253,14 +792,110 @@ impl EventsLoop {
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy {
             events_loop_proxy: self.events_loop.create_proxy(),
+            _live: self.live_proxies.clone(),
         }
     }
+
+    // Hands the events queued by `test::EventsLoopExt::inject_event` to `callback`, including the
+    // ones it injects itself, until there are none left or it returns `ControlFlow::Break`.
+    #[cfg(feature = "test-util")]
+    #[inline]
+    fn dispatch_injected_events<F>(&self, callback: F) -> ControlFlow
+        where F: FnMut(Event) -> ControlFlow
+    {
+        self.injected_events.dispatch(callback)
+    }
+
+    #[cfg(not(feature = "test-util"))]
+    #[inline]
+    fn dispatch_injected_events<F>(&self, _callback: F) -> ControlFlow
+        where F: FnMut(Event) -> ControlFlow
+    {
+        ControlFlow::Continue
+    }
+}
+
+/// The input focus of the application's windows, captured by
+/// `EventLoopWindowTarget::focus_snapshot`.
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot {
+    snapshot: platform::FocusSnapshot,
+}
+
+impl FocusSnapshot {
+    /// Returns the window that had input focus, if it was one of the application's windows.
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.snapshot.focused_window().map(WindowId)
+    }
+}
+
+/// A keyboard layout the user can switch to, as returned by
+/// `EventLoopWindowTarget::keyboard_layouts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutInfo {
+    /// The name of the layout, as the system shows it to the user, like "English (US)".
+    pub name: String,
+    /// Whether this is the layout currently used to type.
+    pub active: bool,
+}
+
+/// An input device, as returned by `EventLoopWindowTarget::device_infos`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// The id of the device in the events it generates.
+    pub id: DeviceId,
+    /// The name of the device, like "Logitech USB Receiver".
+    pub name: String,
+    pub kind: DeviceKind,
+}
+
+/// The kind of an input device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceKind {
+    /// A mouse, or another pointing device like a touchpad.
+    Mouse,
+    Keyboard,
+    /// Another human interface device, like a game controller or a pen tablet.
+    Hid,
+}
+
+/// A rectangle of a window's client area, in logical pixels from its top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub position: LogicalPosition,
+    pub size: LogicalSize,
 }
 
 /// Used to wake up the `EventsLoop` from another thread.
+///
+/// `EventsLoopProxy` is `Send` and `Sync`, except on the web with the `atomics` target feature, and
+/// doesn't borrow the `EventsLoop`, so it can be shared between threads as is, for instance by an
+/// async executor's wakers. It can also outlive the `EventsLoop`, in which case `wakeup` returns
+/// an error.
+///
+/// Waking up the `EventsLoop` never blocks on it. However, depending on the platform, the
+/// `Awakened` events of several calls to `wakeup` made before the `EventsLoop` handles any of them
+/// may or may not be merged into one, so a wakeup shouldn't be assumed to carry any information.
+///
+/// ## Platform-specific
+///
+/// - **Windows:** Each call posts a message to a hidden window of the thread running the windows,
+///   so wakeups and the closures run on that thread are also delivered while a modal loop is
+///   running, such as the ones used to move or resize a window, or to show a menu.
+/// - **macOS:** Each call posts an event to the application's queue.
+/// - **X11:** Calls are merged until the `EventsLoop` handles them. The first one sends a message
+///   through the X server connection, which involves taking Xlib's display lock.
+/// - **Wayland:** Calls are merged until the `EventsLoop` handles them. The first one sends a
+///   request through the Wayland connection.
+/// - **iOS:** Each call schedules a call on the main thread's run loop, which is only run once
+///   `run_forever` has been called.
+/// - **Emscripten:** Calls are merged until the `EventsLoop` handles them.
+/// - **Web:** The proxy can't be sent to other threads when the `atomics` target feature is
+///   enabled, since the browser's objects it refers to are tied to the thread that created them.
 #[derive(Clone)]
 pub struct EventsLoopProxy {
     events_loop_proxy: platform::EventsLoopProxy,
+    _live: Arc<()>,
 }
 
 impl EventsLoopProxy {
@@ -274,6 +909,16 @@ impl EventsLoopProxy {
     }
 }
 
+// Checks what the documentation of `EventsLoopProxy` and `Window` promises, for whichever backend
+// is built.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown", target_feature = "atomics")))]
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<platform::EventsLoopProxy>();
+    assert_send_sync::<Window>();
+}
+
 /// The error that is returned when an `EventsLoopProxy` attempts to wake up an `EventsLoop` that
 /// no longer exists.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -291,6 +936,38 @@ impl std::error::Error for EventsLoopClosed {
     }
 }
 
+/// Error that can happen while creating an `EventsLoop` with `EventsLoop::try_new`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventsLoopCreationError {
+    /// None of the display backends could be connected to, for instance because neither `DISPLAY`
+    /// nor `WAYLAND_DISPLAY` is set. Contains a description of why each backend failed.
+    BackendUnavailable(String),
+    /// `WINIT_UNIX_BACKEND` is set to a value other than `x11` or `wayland`. Contains that value.
+    UnknownBackend(String),
+}
+
+impl std::fmt::Display for EventsLoopCreationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            EventsLoopCreationError::BackendUnavailable(ref description) => f.write_str(description),
+            EventsLoopCreationError::UnknownBackend(ref value) => write!(
+                f,
+                "Unknown value {:?} for `WINIT_UNIX_BACKEND`, try one of `x11`,`wayland`",
+                value,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EventsLoopCreationError {
+    fn description(&self) -> &str {
+        match *self {
+            EventsLoopCreationError::BackendUnavailable(_) => "No display backend is available",
+            EventsLoopCreationError::UnknownBackend(_) => "Unknown value for `WINIT_UNIX_BACKEND`",
+        }
+    }
+}
+
 /// Object that allows you to build windows.
 #[derive(Clone)]
 pub struct WindowBuilder {
@@ -305,6 +982,16 @@ pub struct WindowBuilder {
 #[derive(Debug, Clone)]
 pub enum CreationError {
     OsError(String),
+    /// A system call failed. Contains its error code and a description of the failure.
+    ///
+    /// The code is an error code of the platform, e.g. the result of `GetLastError` on Windows or
+    /// the error code of an X11 protocol error.
+    OsErrorCode(i32, String),
+    /// The system didn't allow the window to be created.
+    PermissionDenied(String),
+    /// The requested attributes contradict each other, e.g. the minimum dimensions are larger
+    /// than the maximum dimensions.
+    InvalidAttributes(String),
     /// TODO: remove this error
     NotSupported,
 }
@@ -313,6 +1000,9 @@ impl CreationError {
     fn to_string(&self) -> &str {
         match *self {
             CreationError::OsError(ref text) => &text,
+            CreationError::OsErrorCode(_, ref text) => &text,
+            CreationError::PermissionDenied(ref text) => &text,
+            CreationError::InvalidAttributes(ref text) => &text,
             CreationError::NotSupported => "Some of the requested attributes are not supported",
         }
     }
@@ -320,7 +1010,12 @@ impl CreationError {
 
 impl std::fmt::Display for CreationError {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        formatter.write_str(self.to_string())
+        match *self {
+            CreationError::OsErrorCode(code, ref text) => {
+                write!(formatter, "{} (error code {})", text, code)
+            },
+            _ => formatter.write_str(self.to_string()),
+        }
     }
 }
 
@@ -392,8 +1087,153 @@ impl Default for MouseCursor {
     }
 }
 
+/// How the cursor is held by a window, as set by `Window::set_cursor_grab_mode`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CursorGrabMode {
+    /// The cursor moves freely.
+    None,
+    /// The cursor moves within the window's client area, but can't leave it.
+    Confined,
+    /// The cursor stays where it is, so the window receives no `CursorMoved` events, and the
+    /// motion of the mouse is only reported by `DeviceEvent::MouseMotion`.
+    ///
+    /// If the cursor isn't in the window when it's locked, it's moved to the center of the window
+    /// once, and stays there.
+    Locked,
+}
+
+impl Default for CursorGrabMode {
+    fn default() -> Self {
+        CursorGrabMode::None
+    }
+}
+
+/// The input mode an input method editor is forced into, as set by
+/// `Window::set_ime_conversion_mode`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ImeMode {
+    /// The user chooses the input mode, as usual.
+    Default,
+    /// Composition is disabled, and keys are typed directly. This is what fields only accepting
+    /// numbers or identifiers usually want.
+    Direct,
+    /// The IME composes text in the native script of its language, such as hiragana for Japanese
+    /// or hangul for Korean.
+    Native,
+    /// The IME composes Japanese text in katakana.
+    Katakana,
+}
+
+impl Default for ImeMode {
+    fn default() -> Self {
+        ImeMode::Default
+    }
+}
+
+/// Describes the role of a point within a window, as reported by a hit-test callback.
+///
+/// See `Window::set_hit_test_callback`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HitTestResult {
+    /// The point is in the client area. Mouse events are delivered normally.
+    Client,
+    /// The point acts as a title bar. Dragging it with the left mouse button moves the window.
+    Caption,
+    /// The point is on the left resize border.
+    Left,
+    /// The point is on the right resize border.
+    Right,
+    /// The point is on the top resize border.
+    Top,
+    /// The point is on the bottom resize border.
+    Bottom,
+    /// The point is on the top-left resize corner.
+    TopLeft,
+    /// The point is on the top-right resize corner.
+    TopRight,
+    /// The point is on the bottom-left resize corner.
+    BottomLeft,
+    /// The point is on the bottom-right resize corner.
+    BottomRight,
+}
+
+impl Default for HitTestResult {
+    fn default() -> Self {
+        HitTestResult::Client
+    }
+}
+
+pub(crate) type HitTestCallback = std::sync::Arc<Fn(PhysicalPosition) -> HitTestResult + Send + Sync>;
+
+/// An edge of a monitor.
+///
+/// See `MonitorId::get_taskbar_edge` and `Window::reserve_screen_edge`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+impl Edge {
+    /// Returns the edge that's the most inset, or `None` if none are. This is how the edge of a
+    /// taskbar is guessed from the work area of a monitor.
+    #[allow(dead_code)] // Not every platform knows about taskbars.
+    pub(crate) fn of_largest_inset(left: i64, top: i64, right: i64, bottom: i64) -> Option<Edge> {
+        let insets = [(Edge::Left, left), (Edge::Top, top), (Edge::Right, right), (Edge::Bottom, bottom)];
+        insets.iter()
+            .filter(|&&(_, inset)| inset > 0)
+            .max_by_key(|&&(_, inset)| inset)
+            .map(|&(edge, _)| edge)
+    }
+}
+
+/// The contents of a window, as captured by `Window::capture_frame`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CapturedFrame {
+    /// The width of the frame in physical pixels.
+    pub width: u32,
+    /// The height of the frame in physical pixels.
+    pub height: u32,
+    /// The pixels of the frame, row by row from the top, with 8-bit red, green, blue, and alpha
+    /// components in that order.
+    pub rgba: Vec<u8>,
+}
+
+/// The size of a window's decorations on each side of its client area, in logical pixels.
+///
+/// Returned by `Window::get_frame_extents`.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FrameExtents {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+}
+
+/// A native window or view of another application, or of another toolkit, that a window can be
+/// created in.
+///
+/// See `WindowBuilder::with_parent_handle`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RawWindowHandle {
+    /// An `HWND`.
+    Windows(*mut std::os::raw::c_void),
+    /// An `NSView`.
+    MacOS(*mut std::os::raw::c_void),
+    /// The ID of an X11 window.
+    Xlib(std::os::raw::c_ulong),
+}
+
+// The handles are only used on the thread that creates the window.
+unsafe impl Send for RawWindowHandle {}
+unsafe impl Sync for RawWindowHandle {}
+
 /// Attributes to use when creating a window.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct WindowAttributes {
     /// The dimensions of the window. If this is `None`, some platform-specific dimensions will be
     /// used.
@@ -418,7 +1258,9 @@ pub struct WindowAttributes {
 
     /// Whether the window should be set as fullscreen upon creation.
     ///
-    /// The default is `None`.
+    /// The default is `None`. Monitors can't be serialized, so this is always `None` after
+    /// deserializing.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub fullscreen: Option<MonitorId>,
 
     /// The title of the window in the title bar.
@@ -436,12 +1278,35 @@ pub struct WindowAttributes {
     /// The default is `true`.
     pub visible: bool,
 
+    /// Whether a visible window should stay hidden until `Window::present_ready` is called, so that
+    /// nothing is shown before the first frame has been drawn.
+    ///
+    /// The default is `false`.
+    pub visible_on_first_present: bool,
+
+    /// Whether the window should be activated, taking input focus, when it's first shown.
+    ///
+    /// The default is `true`.
+    pub active: bool,
+
+    /// Whether the window can take input focus, either by being clicked or through
+    /// `Window::focus`.
+    ///
+    /// The default is `true`.
+    pub focusable: bool,
+
     /// Whether the the window should be transparent. If this is true, writing colors
     /// with alpha values different than `1.0` will produce a transparent window.
     ///
     /// The default is `false`.
     pub transparent: bool,
 
+    /// Whether what's behind the window should be blurred. This is only visible through the
+    /// transparent parts of a window created with `transparent`.
+    ///
+    /// The default is `false`.
+    pub blur: bool,
+
     /// Whether the window should have borders and bars.
     ///
     /// The default is `true`.
@@ -457,9 +1322,27 @@ pub struct WindowAttributes {
     /// The default is `None`.
     pub window_icon: Option<Icon>,
 
+    /// The window's menu bar.
+    ///
+    /// The default is `None`.
+    pub menu: Option<menu::Menu>,
+
     /// [iOS only] Enable multitouch,
     /// see [multipleTouchEnabled](https://developer.apple.com/documentation/uikit/uiview/1622519-multipletouchenabled)
     pub multitouch: bool,
+
+    /// The DPI factor the window uses instead of the one of its monitor, if any. Only used on
+    /// Windows and X11.
+    ///
+    /// The default is `None`.
+    pub forced_hidpi_factor: Option<f64>,
+
+    /// The native window or view the window is created in, as a child that fills it.
+    ///
+    /// The default is `None`. Handles can't be serialized, so this is always `None` after
+    /// deserializing.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub parent: Option<RawWindowHandle>,
 }
 
 impl Default for WindowAttributes {
@@ -474,11 +1357,18 @@ impl Default for WindowAttributes {
             maximized: false,
             fullscreen: None,
             visible: true,
+            visible_on_first_present: false,
+            active: true,
+            focusable: true,
             transparent: false,
+            blur: false,
             decorations: true,
             always_on_top: false,
             window_icon: None,
+            menu: None,
             multitouch: false,
+            forced_hidpi_factor: None,
+            parent: None,
         }
     }
 }
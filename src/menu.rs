@@ -0,0 +1,163 @@
+//! Native menu bars.
+//!
+//! A `Menu` is a tree of items, separators and submenus, attached to a window with
+//! `WindowBuilder::with_menu` or `Window::set_menu`. Activating an item, either by clicking it or
+//! by pressing its accelerator, generates an `Event::MenuEvent` carrying the item's `MenuId`.
+//!
+//! ## Platform-specific
+//!
+//! - **macOS:** The menu bar belongs to the application rather than to a window, so setting a
+//!   window's menu replaces the menu bar for the whole application. The first submenu is shown as
+//!   the application menu, under the application's name, regardless of its title.
+//! - **Windows:** The menu is drawn below the window's title bar, which shrinks its client area.
+//!   The `logo` modifier can't be used in accelerators and is ignored.
+//! - **X11 / Wayland / iOS / Android / Emscripten:** Unsupported. Menus are silently ignored.
+
+use {ModifiersState, VirtualKeyCode};
+
+/// Identifier of a menu item, chosen by the application.
+///
+/// This is what `Event::MenuEvent` reports when the item is activated, so you'll usually want
+/// every item to have its own ID.
+///
+/// Only 16 bits are available, since that's all Windows reserves for menu item identifiers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MenuId(pub u16);
+
+/// A keyboard shortcut that activates a menu item.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Accelerator {
+    /// The modifiers that need to be held.
+    pub modifiers: ModifiersState,
+    /// The key that needs to be pressed.
+    pub key: VirtualKeyCode,
+}
+
+impl Accelerator {
+    /// Creates an accelerator triggered by pressing `key` while holding `modifiers`.
+    #[inline]
+    pub fn new(modifiers: ModifiersState, key: VirtualKeyCode) -> Accelerator {
+        Accelerator { modifiers, key }
+    }
+}
+
+/// An item that the user can activate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MenuItem {
+    /// The identifier reported when the item is activated.
+    pub id: MenuId,
+
+    /// The label of the item.
+    pub title: String,
+
+    /// The keyboard shortcut of the item.
+    ///
+    /// The default is `None`.
+    pub accelerator: Option<Accelerator>,
+
+    /// Whether the item has a checkmark. If this is `None`, the item isn't checkable.
+    ///
+    /// The checkmark isn't toggled automatically when the item is activated. Set the menu again
+    /// with an updated item to change it.
+    ///
+    /// The default is `None`.
+    pub checked: Option<bool>,
+
+    /// Whether the item can be activated.
+    ///
+    /// The default is `true`.
+    pub enabled: bool,
+}
+
+impl MenuItem {
+    /// Creates an enabled, uncheckable item with no accelerator.
+    #[inline]
+    pub fn new<T: Into<String>>(id: MenuId, title: T) -> MenuItem {
+        MenuItem {
+            id,
+            title: title.into(),
+            accelerator: None,
+            checked: None,
+            enabled: true,
+        }
+    }
+
+    /// Sets the keyboard shortcut of the item.
+    #[inline]
+    pub fn with_accelerator(mut self, accelerator: Accelerator) -> MenuItem {
+        self.accelerator = Some(accelerator);
+        self
+    }
+
+    /// Makes the item checkable, with the given initial state.
+    #[inline]
+    pub fn with_checked(mut self, checked: bool) -> MenuItem {
+        self.checked = Some(checked);
+        self
+    }
+
+    /// Sets whether the item can be activated.
+    #[inline]
+    pub fn with_enabled(mut self, enabled: bool) -> MenuItem {
+        self.enabled = enabled;
+        self
+    }
+}
+
+/// An entry in a `Menu`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MenuEntry {
+    Item(MenuItem),
+    Submenu(Menu),
+    Separator,
+}
+
+/// A list of menu entries.
+///
+/// When used as a window's menu bar, the title of the top-level menu is ignored and its entries
+/// should be submenus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Menu {
+    /// The label of the menu, as shown in the parent menu.
+    pub title: String,
+
+    /// The entries of the menu, from top to bottom.
+    pub entries: Vec<MenuEntry>,
+}
+
+impl Menu {
+    /// Creates an empty menu.
+    #[inline]
+    pub fn new<T: Into<String>>(title: T) -> Menu {
+        Menu {
+            title: title.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Appends an item to the menu.
+    #[inline]
+    pub fn with_item(mut self, item: MenuItem) -> Menu {
+        self.entries.push(MenuEntry::Item(item));
+        self
+    }
+
+    /// Appends a submenu to the menu.
+    #[inline]
+    pub fn with_submenu(mut self, submenu: Menu) -> Menu {
+        self.entries.push(MenuEntry::Submenu(submenu));
+        self
+    }
+
+    /// Appends a separator to the menu.
+    #[inline]
+    pub fn with_separator(mut self) -> Menu {
+        self.entries.push(MenuEntry::Separator);
+        self
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::os::raw::c_void;
 
-use {MonitorId, Window, WindowBuilder};
+use {MonitorId, Orientation, Window, WindowBuilder};
 
 /// Additional methods on `Window` that are specific to iOS.
 pub trait WindowExt {
@@ -15,6 +15,25 @@ pub trait WindowExt {
     ///
     /// The pointer will become invalid when the `Window` is destroyed.
     fn get_uiview(&self) -> *mut c_void;
+
+    /// Sets whether the status bar is hidden while this window is shown.
+    ///
+    /// This has no effect if `UIViewControllerBasedStatusBarAppearance` is set to `NO` in the app's
+    /// `Info.plist`.
+    fn set_prefers_status_bar_hidden(&self, hidden: bool);
+
+    /// Sets whether the home indicator may hide itself when the screen isn't being touched.
+    ///
+    /// This only exists on iOS 11 and later, on devices without a home button.
+    fn set_prefers_home_indicator_hidden(&self, hidden: bool);
+
+    /// Sets the orientations the user interface can be rotated to. An empty list allows the ones
+    /// UIKit allows by default, which are all of them on iPad, and all but `PortraitUpsideDown`
+    /// on iPhone.
+    ///
+    /// If the current orientation isn't allowed anymore, the window is rotated to one that is.
+    /// The orientations must also be allowed in the app's `Info.plist`.
+    fn set_supported_orientations(&self, orientations: &[Orientation]);
 }
 
 impl WindowExt for Window {
@@ -27,6 +46,21 @@ impl WindowExt for Window {
     fn get_uiview(&self) -> *mut c_void {
         self.window.get_uiview() as _
     }
+
+    #[inline]
+    fn set_prefers_status_bar_hidden(&self, hidden: bool) {
+        self.window.set_prefers_status_bar_hidden(hidden)
+    }
+
+    #[inline]
+    fn set_prefers_home_indicator_hidden(&self, hidden: bool) {
+        self.window.set_prefers_home_indicator_hidden(hidden)
+    }
+
+    #[inline]
+    fn set_supported_orientations(&self, orientations: &[Orientation]) {
+        self.window.set_supported_orientations(orientations)
+    }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to iOS.
@@ -35,6 +69,22 @@ pub trait WindowBuilderExt {
     ///
     /// The class will be initialized by calling `[root_view initWithFrame:CGRect]`
     fn with_root_view_class(self, root_view_class: *const c_void) -> WindowBuilder;
+
+    /// Sets whether the status bar is hidden while the window is shown.
+    ///
+    /// See `WindowExt::set_prefers_status_bar_hidden`.
+    fn with_prefers_status_bar_hidden(self, hidden: bool) -> WindowBuilder;
+
+    /// Sets whether the home indicator may hide itself when the screen isn't being touched.
+    ///
+    /// See `WindowExt::set_prefers_home_indicator_hidden`.
+    fn with_prefers_home_indicator_hidden(self, hidden: bool) -> WindowBuilder;
+
+    /// Sets the orientations the user interface can be rotated to, for instance only the landscape
+    /// ones for a game.
+    ///
+    /// See `WindowExt::set_supported_orientations`.
+    fn with_supported_orientations(self, orientations: &[Orientation]) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
@@ -43,6 +93,24 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.root_view_class = unsafe { &*(root_view_class as *const _) };
         self
     }
+
+    #[inline]
+    fn with_prefers_status_bar_hidden(mut self, hidden: bool) -> WindowBuilder {
+        self.platform_specific.prefers_status_bar_hidden = hidden;
+        self
+    }
+
+    #[inline]
+    fn with_prefers_home_indicator_hidden(mut self, hidden: bool) -> WindowBuilder {
+        self.platform_specific.prefers_home_indicator_hidden = hidden;
+        self
+    }
+
+    #[inline]
+    fn with_supported_orientations(mut self, orientations: &[Orientation]) -> WindowBuilder {
+        self.platform_specific.supported_orientations = orientations.to_vec();
+        self
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to iOS.
@@ -2,8 +2,68 @@
 
 use std::convert::From;
 use std::os::raw::c_void;
-use cocoa::appkit::NSApplicationActivationPolicy;
-use {LogicalSize, MonitorId, Window, WindowBuilder};
+use cocoa::appkit::{NSApplicationActivationPolicy, NSApplicationPresentationOptions};
+use platform::Activity;
+use {EventsLoop, LogicalSize, MonitorId, Window, WindowBuilder};
+
+pub use ActivationPolicy;
+
+/// Additional methods on `EventsLoop` that are specific to MacOS.
+pub trait EventsLoopExt {
+    /// Tells the system that the application is performing an activity, which prevents it from
+    /// being throttled by App Nap for as long as the returned `ActivityToken` is alive.
+    ///
+    /// `reason` is a human-readable description of the activity, which is used for debugging
+    /// purposes by the system.
+    ///
+    /// This corresponds to `-[NSProcessInfo beginActivityWithOptions:reason:]`.
+    fn set_activity(&self, options: ActivityOptions, reason: &str) -> ActivityToken;
+}
+
+impl EventsLoopExt for EventsLoop {
+    #[inline]
+    fn set_activity(&self, options: ActivityOptions, reason: &str) -> ActivityToken {
+        ActivityToken {
+            _activity: self.events_loop.begin_activity(options, reason),
+        }
+    }
+}
+
+/// Describes an activity started with `EventsLoopExt::set_activity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActivityOptions {
+    /// The activity was initiated by the user, and App Nap should be disabled while it's running.
+    ///
+    /// If `false`, the activity is treated as a background task (`NSActivityBackground`).
+    pub user_initiated: bool,
+    /// The activity requires the highest amount of timer and I/O precision available, as is the
+    /// case for audio and video playback or real-time rendering (`NSActivityLatencyCritical`).
+    pub latency_critical: bool,
+    /// Prevents the system from sleeping while the activity is running
+    /// (`NSActivityIdleSystemSleepDisabled`).
+    pub prevent_system_sleep: bool,
+    /// Prevents the display from sleeping while the activity is running
+    /// (`NSActivityIdleDisplaySleepDisabled`).
+    pub prevent_display_sleep: bool,
+}
+
+impl Default for ActivityOptions {
+    fn default() -> Self {
+        ActivityOptions {
+            user_initiated: true,
+            latency_critical: false,
+            prevent_system_sleep: false,
+            prevent_display_sleep: false,
+        }
+    }
+}
+
+/// Keeps an activity started with `EventsLoopExt::set_activity` running.
+///
+/// The activity ends when this token is dropped.
+pub struct ActivityToken {
+    _activity: Activity,
+}
 
 /// Additional methods on `Window` that are specific to MacOS.
 pub trait WindowExt {
@@ -16,6 +76,67 @@ pub trait WindowExt {
     ///
     /// The pointer will become invalid when the `Window` is destroyed.
     fn get_nsview(&self) -> *mut c_void;
+
+    /// Returns a pointer to a `CAMetalLayer` that backs the window's `NSView`, to render to with
+    /// Metal. It's created the first time this is called.
+    ///
+    /// Its `contentsScale` follows the window's DPI factor and its frame follows the view's size,
+    /// so the size of its drawables always matches the physical size of the window.
+    ///
+    /// Returns null before macOS 10.11. The pointer will become invalid when the `Window` is
+    /// destroyed.
+    fn get_metal_layer(&self) -> *mut c_void;
+
+    /// Sets a `NSView` that draws the application's dock icon, or restores the default icon if
+    /// `None`. The view is retained by the dock tile.
+    ///
+    /// The dock only redraws the view when `display_dock_tile` is called, so several changes to
+    /// it can be made before showing them all at once.
+    fn set_dock_tile_content_view(&self, view: Option<*mut c_void>);
+
+    /// Redraws the application's dock icon, including the view set with
+    /// `set_dock_tile_content_view`.
+    fn display_dock_tile(&self);
+
+    /// Selects the tab after this window's tab in its tab group, wrapping around to the first.
+    ///
+    /// Does nothing if the window isn't part of a tab group, or before macOS 10.12.
+    fn select_next_tab(&self);
+
+    /// Selects the tab before this window's tab in its tab group, wrapping around to the last.
+    ///
+    /// Does nothing if the window isn't part of a tab group, or before macOS 10.12.
+    fn select_previous_tab(&self);
+
+    /// Merges every window that has the same tabbing identifier as this one into a single window,
+    /// with one tab per window. This is what the "Merge All Windows" menu item does.
+    ///
+    /// Does nothing before macOS 10.12.
+    fn merge_all_windows(&self);
+
+    /// Toggles a fullscreen mode that covers the window's screen with the window, without
+    /// creating a new Space. Unlike `Window::set_fullscreen`, this isn't animated, so it's much
+    /// faster to enter and leave, which is what games usually want.
+    ///
+    /// The dock and menu bar are hidden according to `set_fullscreen_presentation_options`.
+    ///
+    /// Returns `false` if the window was already in the requested state, or if it's in native
+    /// fullscreen, which has to be left first.
+    fn set_simple_fullscreen(&self, fullscreen: bool) -> bool;
+
+    /// Sets how the dock and menu bar behave while the window is fullscreen, both in native
+    /// fullscreen and in simple fullscreen. This takes effect the next time the window enters
+    /// fullscreen.
+    ///
+    /// Until this is called, native fullscreen keeps the options macOS proposes, and simple
+    /// fullscreen uses `FullscreenPresentationOptions::default()`.
+    fn set_fullscreen_presentation_options(&self, options: FullscreenPresentationOptions);
+
+    /// Returns `true` if the window is the main window, whose document the application's
+    /// commands act on. Unlike the key window, which `WindowEvent::Focused` follows, the main
+    /// window stays the same while a panel has keyboard focus, or while the application is
+    /// inactive.
+    fn is_main_window(&self) -> bool;
 }
 
 impl WindowExt for Window {
@@ -28,25 +149,90 @@ impl WindowExt for Window {
     fn get_nsview(&self) -> *mut c_void {
         self.window.get_nsview()
     }
+
+    #[inline]
+    fn get_metal_layer(&self) -> *mut c_void {
+        self.window.get_metal_layer()
+    }
+
+    #[inline]
+    fn set_dock_tile_content_view(&self, view: Option<*mut c_void>) {
+        self.window.set_dock_tile_content_view(view)
+    }
+
+    #[inline]
+    fn display_dock_tile(&self) {
+        self.window.display_dock_tile()
+    }
+
+    #[inline]
+    fn select_next_tab(&self) {
+        self.window.select_next_tab()
+    }
+
+    #[inline]
+    fn select_previous_tab(&self) {
+        self.window.select_previous_tab()
+    }
+
+    #[inline]
+    fn merge_all_windows(&self) {
+        self.window.merge_all_windows()
+    }
+
+    #[inline]
+    fn set_simple_fullscreen(&self, fullscreen: bool) -> bool {
+        self.window.set_simple_fullscreen(fullscreen)
+    }
+
+    #[inline]
+    fn set_fullscreen_presentation_options(&self, options: FullscreenPresentationOptions) {
+        self.window.set_fullscreen_presentation_options(options)
+    }
+
+    #[inline]
+    fn is_main_window(&self) -> bool {
+        self.window.is_main_window()
+    }
 }
 
-/// Corresponds to `NSApplicationActivationPolicy`.
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ActivationPolicy {
-    /// Corresponds to `NSApplicationActivationPolicyRegular`.
-    Regular,
-    /// Corresponds to `NSApplicationActivationPolicyAccessory`.
-    Accessory,
-    /// Corresponds to `NSApplicationActivationPolicyProhibited`.
-    Prohibited,
+/// How the dock and menu bar behave while a window is fullscreen.
+///
+/// By default, both are hidden, and shown when the cursor reaches the edge of the screen they're
+/// on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FullscreenPresentationOptions {
+    /// Hides the dock entirely (`NSApplicationPresentationHideDock`).
+    pub hide_dock: bool,
+    /// Hides the menu bar entirely (`NSApplicationPresentationHideMenuBar`). This also hides the
+    /// dock, since macOS doesn't allow showing it without the menu bar.
+    pub hide_menu_bar: bool,
+    /// Prevents the user from switching to other applications, e.g. with Cmd+Tab
+    /// (`NSApplicationPresentationDisableProcessSwitching`).
+    pub disable_process_switching: bool,
 }
 
-impl Default for ActivationPolicy {
-    fn default() -> Self {
-        ActivationPolicy::Regular
+impl From<FullscreenPresentationOptions> for NSApplicationPresentationOptions {
+    fn from(options: FullscreenPresentationOptions) -> Self {
+        let mut presentation_options = if options.hide_menu_bar {
+            NSApplicationPresentationOptions::NSApplicationPresentationHideMenuBar
+                | NSApplicationPresentationOptions::NSApplicationPresentationHideDock
+        } else if options.hide_dock {
+            NSApplicationPresentationOptions::NSApplicationPresentationAutoHideMenuBar
+                | NSApplicationPresentationOptions::NSApplicationPresentationHideDock
+        } else {
+            NSApplicationPresentationOptions::NSApplicationPresentationAutoHideMenuBar
+                | NSApplicationPresentationOptions::NSApplicationPresentationAutoHideDock
+        };
+        if options.disable_process_switching {
+            presentation_options |=
+                NSApplicationPresentationOptions::NSApplicationPresentationDisableProcessSwitching;
+        }
+        presentation_options
     }
 }
 
+// Each `ActivationPolicy` corresponds to the `NSApplicationActivationPolicy` of the same name.
 impl From<ActivationPolicy> for NSApplicationActivationPolicy {
     fn from(activation_policy: ActivationPolicy) -> Self {
         match activation_policy {
@@ -71,7 +257,8 @@ impl From<ActivationPolicy> for NSApplicationActivationPolicy {
 ///  - `with_titlebar_buttons_hidden`
 ///  - `with_fullsize_content_view`
 pub trait WindowBuilderExt {
-    /// Sets the activation policy for the window being built.
+    /// Sets the activation policy for the window being built, instead of the one given to
+    /// `EventsLoop::set_activation_policy`.
     fn with_activation_policy(self, activation_policy: ActivationPolicy) -> WindowBuilder;
     /// Enables click-and-drag behavior for the entire window, not just the titlebar.
     fn with_movable_by_window_background(self, movable_by_window_background: bool) -> WindowBuilder;
@@ -87,12 +274,17 @@ pub trait WindowBuilderExt {
     fn with_fullsize_content_view(self, fullsize_content_view: bool) -> WindowBuilder;
     /// Build window with `resizeIncrements` property. Values must not be 0.
     fn with_resize_increments(self, increments: LogicalSize) -> WindowBuilder;
+    /// Windows with the same tabbing identifier can be grouped together as tabs of a single
+    /// window, either by the user or with `WindowExt::merge_all_windows`. Requires macOS 10.12.
+    ///
+    /// When the user drags a tab out of its group, `WindowEvent::TabDetached` is sent to it.
+    fn with_tabbing_identifier(self, identifier: &str) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
     #[inline]
     fn with_activation_policy(mut self, activation_policy: ActivationPolicy) -> WindowBuilder {
-        self.platform_specific.activation_policy = activation_policy;
+        self.platform_specific.activation_policy = Some(activation_policy);
         self
     }
 
@@ -137,6 +329,12 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.resize_increments = Some(increments.into());
         self
     }
+
+    #[inline]
+    fn with_tabbing_identifier(mut self, identifier: &str) -> WindowBuilder {
+        self.platform_specific.tabbing_identifier = Some(identifier.to_owned());
+        self
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to MacOS.
@@ -6,6 +6,7 @@
 //!  - `ios`
 //!  - `macos`
 //!  - `unix`
+//!  - `web`
 //!  - `windows`
 //!
 //! However only the module corresponding to the platform you're compiling to will be available.
@@ -14,4 +15,5 @@ pub mod android;
 pub mod ios;
 pub mod macos;
 pub mod unix;
+pub mod web;
 pub mod windows;
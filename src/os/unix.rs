@@ -6,6 +6,7 @@ use std::sync::Arc;
 
 use {
     EventsLoop,
+    EventsLoopCreationError,
     LogicalSize,
     MonitorId,
     Window,
@@ -14,6 +15,7 @@ use {
 use platform::{
     EventsLoop as LinuxEventsLoop,
     Window as LinuxWindow,
+    X11_ERROR_HOOK,
 };
 use platform::x11::XConnection;
 use platform::x11::ffi::XVisualInfo;
@@ -23,7 +25,20 @@ use platform::x11::ffi::XVisualInfo;
 pub use platform::x11;
 
 pub use platform::XNotSupported;
+pub use platform::x11::XError;
 pub use platform::x11::util::WindowType as XWindowType;
+pub use platform::x11::util::PropertyValue as XPropertyValue;
+
+/// Sets the function that's called for every X error, instead of logging it with the
+/// `winit::x11` target.
+///
+/// winit carries on after most errors, such as the ones caused by requests about a window that
+/// was destroyed in the meantime, so the hook is only there to observe them. It's called from
+/// within Xlib's error handler, so it mustn't make any Xlib calls itself, nor set the hook again.
+/// Passing `None` removes the hook that was set before.
+pub fn set_x11_error_hook(hook: Option<Box<Fn(&XError) + Send>>) {
+    *X11_ERROR_HOOK.lock() = hook;
+}
 
 /// Additional methods on `EventsLoop` that are specific to Linux.
 pub trait EventsLoopExt {
@@ -35,6 +50,16 @@ pub trait EventsLoopExt {
     fn new_wayland() -> Self
         where Self: Sized;
 
+    /// Builds a new `EventsLoop` with its own connection to the display server, which can be
+    /// done on any thread. Each thread can then run its own `EventsLoop`, with its own windows.
+    ///
+    /// The backend is chosen like with `EventsLoop::try_new`. On X11, the `EventsLoop`s created
+    /// with `EventsLoop::new` share a single connection, so only one of them can be run at a time
+    /// without taking the events of the others, which isn't the case for the ones created with
+    /// this function. On Wayland, every `EventsLoop` already has its own connection.
+    fn new_any_thread() -> Result<Self, EventsLoopCreationError>
+        where Self: Sized;
+
     /// True if the `EventsLoop` uses Wayland.
     fn is_wayland(&self) -> bool;
 
@@ -43,28 +68,42 @@ pub trait EventsLoopExt {
 
     #[doc(hidden)]
     fn get_xlib_xconnection(&self) -> Option<Arc<XConnection>>;
+
+    /// Returns the value of a property of the root window, such as `_NET_ACTIVE_WINDOW`, if it
+    /// exists and has the given type.
+    ///
+    /// Always returns `None` on Wayland.
+    fn x11_get_root_property(&self, property: &str, property_type: &str) -> Option<XPropertyValue>;
+
+    /// Sets a property of the root window, replacing its previous value.
+    ///
+    /// Has no effect on Wayland.
+    fn x11_change_root_property(&self, property: &str, property_type: &str, value: &XPropertyValue);
+
+    /// Generates `Event::X11RootPropertyChanged` whenever the given property of the root window
+    /// changes or is deleted.
+    ///
+    /// Has no effect on Wayland.
+    fn x11_watch_root_property(&self, property: &str);
 }
 
 impl EventsLoopExt for EventsLoop {
     #[inline]
     fn new_x11() -> Result<Self, XNotSupported> {
-        LinuxEventsLoop::new_x11().map(|ev|
-            EventsLoop {
-                events_loop: ev,
-                _marker: ::std::marker::PhantomData,
-            }
-        )
+        LinuxEventsLoop::new_x11().map(EventsLoop::from_platform)
     }
 
     #[inline]
     fn new_wayland() -> Self {
-        EventsLoop {
-            events_loop: match LinuxEventsLoop::new_wayland() {
-                Ok(e) => e,
-                Err(_) => panic!()      // TODO: propagate
-            },
-            _marker: ::std::marker::PhantomData,
-        }
+        EventsLoop::from_platform(match LinuxEventsLoop::new_wayland() {
+            Ok(e) => e,
+            Err(_) => panic!()      // TODO: propagate
+        })
+    }
+
+    #[inline]
+    fn new_any_thread() -> Result<Self, EventsLoopCreationError> {
+        LinuxEventsLoop::try_new_any_thread().map(EventsLoop::from_platform)
     }
 
     #[inline]
@@ -82,6 +121,25 @@ impl EventsLoopExt for EventsLoop {
     fn get_xlib_xconnection(&self) -> Option<Arc<XConnection>> {
         self.events_loop.x_connection().cloned()
     }
+
+    #[inline]
+    fn x11_get_root_property(&self, property: &str, property_type: &str) -> Option<XPropertyValue> {
+        self.events_loop.x11().and_then(|ev| ev.get_root_property(property, property_type))
+    }
+
+    #[inline]
+    fn x11_change_root_property(&self, property: &str, property_type: &str, value: &XPropertyValue) {
+        if let Some(ev) = self.events_loop.x11() {
+            ev.change_root_property(property, property_type, value);
+        }
+    }
+
+    #[inline]
+    fn x11_watch_root_property(&self, property: &str) {
+        if let Some(ev) = self.events_loop.x11() {
+            ev.watch_root_property(property);
+        }
+    }
 }
 
 /// Additional methods on `Window` that are specific to Unix.
@@ -135,6 +193,34 @@ pub trait WindowExt {
     /// Always return true.
     #[deprecated]
     fn is_ready(&self) -> bool;
+
+    /// Returns the value of a property of the window, if it exists and has the given type.
+    ///
+    /// Always returns `None` on Wayland.
+    fn x11_get_property(&self, property: &str, property_type: &str) -> Option<XPropertyValue>;
+
+    /// Sets a property of the window, replacing its previous value.
+    ///
+    /// Has no effect on Wayland.
+    fn x11_change_property(&self, property: &str, property_type: &str, value: &XPropertyValue);
+
+    /// Generates `WindowEvent::X11PropertyChanged` whenever the given property of the window
+    /// changes or is deleted.
+    ///
+    /// Has no effect on Wayland.
+    fn x11_watch_property(&self, property: &str);
+
+    /// Embeds the window of another application into this window with the XEmbed protocol.
+    ///
+    /// `client` is the ID of an X11 window that supports XEmbed, such as a window built with
+    /// `WindowBuilderExt::with_embed_parent_window`. It's reparented into this window and resized
+    /// to fill it, and follows its size afterwards. While this window has focus, the client gets
+    /// its keyboard input, and this window no longer receives `WindowEvent::KeyboardInput`. Only
+    /// one client is embedded at a time, and the client is let go once it's destroyed or
+    /// reparented elsewhere.
+    ///
+    /// Has no effect on Wayland.
+    fn x11_embed_client(&self, client: raw::c_ulong);
 }
 
 impl WindowExt for Window {
@@ -206,6 +292,35 @@ impl WindowExt for Window {
     fn is_ready(&self) -> bool {
         true
     }
+
+    #[inline]
+    fn x11_get_property(&self, property: &str, property_type: &str) -> Option<XPropertyValue> {
+        match self.window {
+            LinuxWindow::X(ref w) => w.get_property(property, property_type),
+            _ => None
+        }
+    }
+
+    #[inline]
+    fn x11_change_property(&self, property: &str, property_type: &str, value: &XPropertyValue) {
+        if let LinuxWindow::X(ref w) = self.window {
+            w.change_property(property, property_type, value);
+        }
+    }
+
+    #[inline]
+    fn x11_watch_property(&self, property: &str) {
+        if let LinuxWindow::X(ref w) = self.window {
+            w.watch_property(property);
+        }
+    }
+
+    #[inline]
+    fn x11_embed_client(&self, client: raw::c_ulong) {
+        if let LinuxWindow::X(ref w) = self.window {
+            w.embed_client(client);
+        }
+    }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to Unix.
@@ -223,6 +338,26 @@ pub trait WindowBuilderExt {
     fn with_resize_increments(self, increments: LogicalSize) -> WindowBuilder;
     /// Build window with base size hint. Only implemented on X11.
     fn with_base_size(self, base_size: LogicalSize) -> WindowBuilder;
+    /// Build window whose DPI factor follows the monitor containing its center, emitting
+    /// `HiDpiFactorChanged` as soon as the center is moved onto a monitor with a different DPI
+    /// factor; defaults to false. Otherwise, the window is scaled for the monitor it overlaps the
+    /// most. Only relevant on X11.
+    fn with_dynamic_x11_scaling(self, dynamic: bool) -> WindowBuilder;
+
+    /// Build window with cursors sized for its DPI factor, defaults to false.
+    ///
+    /// The cursor size is at least the one configured through `XCURSOR_SIZE` or the `Xcursor.size`
+    /// and `Xft.dpi` resources, and is updated when the DPI factor changes. Without this, the
+    /// cursor can look tiny on hidpi monitors. Only relevant on X11.
+    fn with_hidpi_cursors(self, hidpi_cursors: bool) -> WindowBuilder;
+
+    /// Build window embedded into the X11 window `parent` of another application, with the
+    /// XEmbed protocol.
+    ///
+    /// The embedder decides the size of the window and when it's shown. `WindowEvent::Focused`
+    /// follows the focus given by the embedder, and `Window::focus` asks the embedder for focus.
+    /// Only relevant on X11.
+    fn with_embed_parent_window(self, parent: raw::c_ulong) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
@@ -269,6 +404,24 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.base_size = Some(base_size.into());
         self
     }
+
+    #[inline]
+    fn with_dynamic_x11_scaling(mut self, dynamic: bool) -> WindowBuilder {
+        self.platform_specific.dynamic_x11_scaling = dynamic;
+        self
+    }
+
+    #[inline]
+    fn with_hidpi_cursors(mut self, hidpi_cursors: bool) -> WindowBuilder {
+        self.platform_specific.hidpi_cursors = hidpi_cursors;
+        self
+    }
+
+    #[inline]
+    fn with_embed_parent_window(mut self, parent: raw::c_ulong) -> WindowBuilder {
+        self.platform_specific.embed_parent = Some(parent);
+        self
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to Linux.
@@ -0,0 +1,35 @@
+#![cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+
+use web_sys::HtmlCanvasElement;
+
+use {Window, WindowBuilder};
+
+/// Additional methods on `Window` that are specific to the web.
+pub trait WindowExt {
+    /// Returns the canvas the window draws on.
+    fn get_canvas(&self) -> &HtmlCanvasElement;
+}
+
+impl WindowExt for Window {
+    #[inline]
+    fn get_canvas(&self) -> &HtmlCanvasElement {
+        self.window.get_canvas()
+    }
+}
+
+/// Additional methods on `WindowBuilder` that are specific to the web.
+pub trait WindowBuilderExt {
+    /// Uses a canvas that's already part of the page, rather than adding a new one to its body.
+    ///
+    /// The canvas is resized to the window's dimensions like any other, but the page's title isn't
+    /// changed.
+    fn with_canvas(self, canvas: HtmlCanvasElement) -> WindowBuilder;
+}
+
+impl WindowBuilderExt for WindowBuilder {
+    #[inline]
+    fn with_canvas(mut self, canvas: HtmlCanvasElement) -> WindowBuilder {
+        self.platform_specific.canvas = Some(canvas);
+        self
+    }
+}
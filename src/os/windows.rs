@@ -3,7 +3,9 @@
 use std::os::raw::c_void;
 
 use libc;
+use winapi::shared::minwindef::HINSTANCE;
 use winapi::shared::windef::HWND;
+use winapi::um::winuser::MSG;
 
 use {DeviceId, EventsLoop, Icon, MonitorId, Window, WindowBuilder};
 use platform::EventsLoop as WindowsEventsLoop;
@@ -13,15 +15,43 @@ pub trait EventsLoopExt {
     /// By default, winit on Windows will attempt to enable process-wide DPI awareness. If that's
     /// undesirable, you can create an `EventsLoop` using this function instead.
     fn new_dpi_unaware() -> Self where Self: Sized;
+
+    /// Builds a new `EventsLoop`, which can be done on any thread. Each thread can then run its
+    /// own `EventsLoop`, with its own windows.
+    ///
+    /// This is the same as `EventsLoop::new`, since the windows of every `EventsLoop` live on a
+    /// thread of their own anyway.
+    fn new_any_thread() -> Self where Self: Sized;
+
+    /// Sets a hook that sees the raw messages received by the windows of this `EventsLoop`,
+    /// including the ones winit doesn't translate into events, such as `WM_COPYDATA`. Messages
+    /// posted to the thread of the `EventsLoop` without a window are also passed to it.
+    ///
+    /// The hook is called before winit handles a message. If it returns `true`, the message is
+    /// considered handled, and winit ignores it.
+    ///
+    /// The hook runs on the thread that the windows belong to, which isn't the thread of the
+    /// `EventsLoop`. It replaces any previously set hook.
+    fn set_msg_hook<F>(&self, hook: F)
+        where F: FnMut(*const MSG) -> bool + Send + 'static;
 }
 
 impl EventsLoopExt for EventsLoop {
     #[inline]
     fn new_dpi_unaware() -> Self {
-        EventsLoop {
-            events_loop: WindowsEventsLoop::with_dpi_awareness(false),
-            _marker: ::std::marker::PhantomData,
-        }
+        EventsLoop::from_platform(WindowsEventsLoop::with_dpi_awareness(false))
+    }
+
+    #[inline]
+    fn new_any_thread() -> Self {
+        EventsLoop::new()
+    }
+
+    #[inline]
+    fn set_msg_hook<F>(&self, hook: F)
+        where F: FnMut(*const MSG) -> bool + Send + 'static
+    {
+        self.events_loop.set_msg_hook(hook)
     }
 }
 
@@ -34,6 +64,13 @@ pub trait WindowExt {
 
     /// This sets `ICON_BIG`. A good ceiling here is 256x256.
     fn set_taskbar_icon(&self, taskbar_icon: Option<Icon>);
+
+    /// Sets whether the window is minimized when it loses focus while it's fullscreen, like
+    /// exclusive fullscreen games usually are. `WindowEvent::FullscreenLost` is sent when it is.
+    ///
+    /// Disabled by default, so that a fullscreen window stays visible behind windows of other
+    /// processes on other monitors, or that only cover part of it.
+    fn set_minimize_on_fullscreen_focus_loss(&self, minimize: bool);
 }
 
 impl WindowExt for Window {
@@ -46,6 +83,11 @@ impl WindowExt for Window {
     fn set_taskbar_icon(&self, taskbar_icon: Option<Icon>) {
         self.window.set_taskbar_icon(taskbar_icon)
     }
+
+    #[inline]
+    fn set_minimize_on_fullscreen_focus_loss(&self, minimize: bool) {
+        self.window.set_minimize_on_fullscreen_focus_loss(minimize)
+    }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to Windows.
@@ -58,6 +100,23 @@ pub trait WindowBuilderExt {
 
     /// This sets `WS_EX_NOREDIRECTIONBITMAP`.
     fn with_no_redirection_bitmap(self, flag: bool) -> WindowBuilder;
+
+    /// Enables or disables winit's handling of dropped files. Enabled by default.
+    ///
+    /// When enabled, the window sets `WS_EX_ACCEPTFILES` and generates `DroppedFile` events. Disable
+    /// it if your application registers its own `IDropTarget` on the window (with `RegisterDragDrop`
+    /// on the handle from `WindowExt::get_hwnd`), for example because an embedded framework needs to
+    /// initialize OLE itself. Drops then only go to your drop target.
+    fn with_drag_and_drop(self, flag: bool) -> WindowBuilder;
+
+    /// Sets the name of the window class that the window is created with. It's registered with
+    /// winit's window procedure if it doesn't exist yet. Defaults to `"Window Class"`.
+    fn with_class_name(self, class_name: &str) -> WindowBuilder;
+
+    /// Sets the module instance that the window class is registered with and that the window is
+    /// created with. Defaults to the module of the executable, which isn't the right one when
+    /// winit is used from a DLL.
+    fn with_hinstance(self, hinstance: HINSTANCE) -> WindowBuilder;
 }
 
 impl WindowBuilderExt for WindowBuilder {
@@ -78,6 +137,24 @@ impl WindowBuilderExt for WindowBuilder {
         self.platform_specific.no_redirection_bitmap = flag;
         self
     }
+
+    #[inline]
+    fn with_drag_and_drop(mut self, flag: bool) -> WindowBuilder {
+        self.platform_specific.drag_and_drop = flag;
+        self
+    }
+
+    #[inline]
+    fn with_class_name(mut self, class_name: &str) -> WindowBuilder {
+        self.platform_specific.class_name = Some(class_name.to_owned());
+        self
+    }
+
+    #[inline]
+    fn with_hinstance(mut self, hinstance: HINSTANCE) -> WindowBuilder {
+        self.platform_specific.hinstance = Some(hinstance);
+        self
+    }
 }
 
 /// Additional methods on `MonitorId` that are specific to Windows.
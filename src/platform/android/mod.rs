@@ -4,7 +4,7 @@ extern crate android_glue;
 
 mod ffi;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
 use std::fmt;
 use std::os::raw::c_void;
@@ -29,6 +29,8 @@ use window::MonitorId as RootMonitorId;
 pub struct EventsLoop {
     event_rx: Receiver<android_glue::Event>,
     suspend_callback: RefCell<Option<Box<Fn(bool) -> ()>>>,
+    // Whether the activity is in the background, in which case `run_forever` stops polling.
+    suspended: Cell<bool>,
 }
 
 #[derive(Clone)]
@@ -41,9 +43,15 @@ impl EventsLoop {
         EventsLoop {
             event_rx: rx,
             suspend_callback: Default::default(),
+            suspended: Cell::new(false),
         }
     }
 
+    // Nothing can fail to connect on this platform.
+    pub fn try_new() -> Result<EventsLoop, ::EventsLoopCreationError> {
+        Ok(EventsLoop::new())
+    }
+
     #[inline]
     pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
         let mut rb = VecDeque::with_capacity(1);
@@ -56,92 +64,116 @@ impl EventsLoop {
         MonitorId
     }
 
-    pub fn poll_events<F>(&mut self, mut callback: F)
+    pub fn wait_for_vblank(&mut self) -> bool {
+        false
+    }
+
+    pub fn poll_events<F>(&self, mut callback: F)
         where F: FnMut(::Event)
     {
         while let Ok(event) = self.event_rx.try_recv() {
-            let e = match event{
-                android_glue::Event::EventMotion(motion) => {
+            if let Some(event) = self.translate_event(event) {
+                callback(event);
+            }
+        }
+    }
+
+    fn translate_event(&self, event: android_glue::Event) -> Option<::Event> {
+        match event {
+            android_glue::Event::EventMotion(motion) => {
+                let dpi_factor = MonitorId.get_hidpi_factor();
+                let location = LogicalPosition::from_physical(
+                    (motion.x as f64, motion.y as f64),
+                    dpi_factor,
+                );
+                Some(Event::WindowEvent {
+                    window_id: RootWindowId(WindowId),
+                    event: WindowEvent::Touch(Touch {
+                        phase: match motion.action {
+                            android_glue::MotionAction::Down => TouchPhase::Started,
+                            android_glue::MotionAction::Move => TouchPhase::Moved,
+                            android_glue::MotionAction::Up => TouchPhase::Ended,
+                            android_glue::MotionAction::Cancel => TouchPhase::Cancelled,
+                        },
+                        location,
+                        id: motion.pointer_id as u64,
+                        force: None,
+                        contact_size: None,
+                        device_id: DEVICE_ID,
+                    }),
+                })
+            },
+            android_glue::Event::InitWindow => {
+                // The activity went to foreground.
+                self.suspended.set(false);
+                if let Some(cb) = self.suspend_callback.borrow().as_ref() {
+                    (*cb)(false);
+                }
+                Some(Event::Suspended(false))
+            },
+            android_glue::Event::TermWindow => {
+                // The activity went to background.
+                self.suspended.set(true);
+                if let Some(cb) = self.suspend_callback.borrow().as_ref() {
+                    (*cb)(true);
+                }
+                Some(Event::Suspended(true))
+            },
+            android_glue::Event::WindowResized |
+            android_glue::Event::ConfigChanged => {
+                // Activity Orientation changed or resized.
+                let native_window = unsafe { android_glue::get_native_window() };
+                if native_window.is_null() {
+                    None
+                } else {
                     let dpi_factor = MonitorId.get_hidpi_factor();
-                    let location = LogicalPosition::from_physical(
-                        (motion.x as f64, motion.y as f64),
-                        dpi_factor,
-                    );
-                    Some(Event::WindowEvent {
-                        window_id: RootWindowId(WindowId),
-                        event: WindowEvent::Touch(Touch {
-                            phase: match motion.action {
-                                android_glue::MotionAction::Down => TouchPhase::Started,
-                                android_glue::MotionAction::Move => TouchPhase::Moved,
-                                android_glue::MotionAction::Up => TouchPhase::Ended,
-                                android_glue::MotionAction::Cancel => TouchPhase::Cancelled,
-                            },
-                            location,
-                            id: motion.pointer_id as u64,
-                            device_id: DEVICE_ID,
-                        }),
-                    })
-                },
-                android_glue::Event::InitWindow => {
-                    // The activity went to foreground.
-                    if let Some(cb) = self.suspend_callback.borrow().as_ref() {
-                        (*cb)(false);
-                    }
-                    Some(Event::Suspended(false))
-                },
-                android_glue::Event::TermWindow => {
-                    // The activity went to background.
-                    if let Some(cb) = self.suspend_callback.borrow().as_ref() {
-                        (*cb)(true);
-                    }
-                    Some(Event::Suspended(true))
-                },
-                android_glue::Event::WindowResized |
-                android_glue::Event::ConfigChanged => {
-                    // Activity Orientation changed or resized.
-                    let native_window = unsafe { android_glue::get_native_window() };
-                    if native_window.is_null() {
-                        None
-                    } else {
-                        let dpi_factor = MonitorId.get_hidpi_factor();
-                        let physical_size = MonitorId.get_dimensions();
-                        let size = LogicalSize::from_physical(physical_size, dpi_factor);
-                        Some(Event::WindowEvent {
-                            window_id: RootWindowId(WindowId),
-                            event: WindowEvent::Resized(size),
-                        })
-                    }
-                },
-                android_glue::Event::WindowRedrawNeeded => {
-                    // The activity needs to be redrawn.
+                    let physical_size = MonitorId.get_dimensions();
+                    let size = LogicalSize::from_physical(physical_size, dpi_factor);
                     Some(Event::WindowEvent {
                         window_id: RootWindowId(WindowId),
-                        event: WindowEvent::Refresh,
+                        event: WindowEvent::Resized(size),
                     })
                 }
-                android_glue::Event::Wake => {
-                    Some(Event::Awakened)
-                }
-                _ => {
-                    None
-                }
-            };
-
-            if let Some(event) = e {
-                callback(event);
+            },
+            android_glue::Event::WindowRedrawNeeded => {
+                // The activity needs to be redrawn.
+                Some(Event::WindowEvent {
+                    window_id: RootWindowId(WindowId),
+                    event: WindowEvent::Refresh { damage: None },
+                })
             }
-        };
+            android_glue::Event::Wake => {
+                Some(Event::Awakened)
+            }
+            _ => {
+                None
+            }
+        }
     }
 
     pub fn set_suspend_callback(&self, cb: Option<Box<Fn(bool) -> ()>>) {
         *self.suspend_callback.borrow_mut() = cb;
     }
 
-    pub fn run_forever<F>(&mut self, mut callback: F)
+    pub fn run_forever<F>(&self, mut callback: F)
         where F: FnMut(::Event) -> ::ControlFlow,
     {
         // Yeah that's a very bad implementation.
         loop {
+            if self.suspended.get() {
+                // There's nothing to draw in the background, so rather than waking up every few
+                // milliseconds and draining the battery, wait for the activity to come back, or
+                // for anything else to happen.
+                let event = match self.event_rx.recv() {
+                    Ok(event) => event,
+                    Err(_) => break,
+                };
+                if let Some(event) = self.translate_event(event) {
+                    if let ::ControlFlow::Break = callback(event) {
+                        break;
+                    }
+                }
+            }
             let mut control_flow = ::ControlFlow::Continue;
             self.poll_events(|e| {
                 if let ::ControlFlow::Break = callback(e) {
@@ -158,6 +190,49 @@ impl EventsLoop {
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy
     }
+
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        None
+    }
+
+    pub fn shutdown(self) {
+        // No background threads to wait for.
+    }
+
+    #[inline]
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        Vec::new()
+    }
+
+    pub fn set_activation_policy(&mut self, _activation_policy: ::ActivationPolicy) {
+        // N/A
+    }
+
+    pub fn reserve_events(&mut self, _capacity: usize) {
+        // N/A
+    }
+
+    pub fn block_session_end(&self, _reason: Option<&str>) {
+        // N/A
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        FocusSnapshot
+    }
+
+    pub fn restore_focus(&self, _snapshot: &FocusSnapshot) {
+        // N/A
+    }
+
+    pub fn keyboard_layouts(&self) -> Vec<::LayoutInfo> {
+        // N/A
+        Vec::new()
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        // N/A
+        Vec::new()
+    }
 }
 
 impl EventsLoopProxy {
@@ -167,12 +242,62 @@ impl EventsLoopProxy {
     }
 }
 
+// The idle time isn't available on this platform.
+pub enum IdleTimeQuery {}
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<::std::time::Duration> {
+        match *self {}
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowId;
 
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(_raw: u64) -> WindowId {
+        WindowId
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId;
 
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot;
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        None
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> DeviceId {
+        DeviceId
+    }
+}
+
 pub struct Window {
     native_window: *const c_void,
 }
@@ -228,6 +353,16 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         1.0
     }
+
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        (self.get_position(), self.get_dimensions())
+    }
+
+    #[inline]
+    pub fn get_taskbar_edge(&self) -> Option<::Edge> {
+        None
+    }
 }
 
 #[derive(Clone, Default)]
@@ -240,6 +375,11 @@ impl Window {
                _: PlatformSpecificWindowBuilderAttributes)
                -> Result<Window, CreationError>
     {
+        if win_attribs.parent.is_some() {
+            return Err(CreationError::InvalidAttributes(
+                "Windows can't be created in a parent window on Android.".to_owned(),
+            ));
+        }
         let native_window = unsafe { android_glue::get_native_window() };
         if native_window.is_null() {
             return Err(OsError(format!("Android's native window is null")));
@@ -272,6 +412,11 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn present_ready(&self) {
+        // N/A
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
         // N/A
@@ -285,7 +430,12 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_position(&self, _position: LogicalPosition) {
+    pub fn set_outer_position(&self, _position: LogicalPosition) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, _position: LogicalPosition) {
         // N/A
     }
 
@@ -320,6 +470,11 @@ impl Window {
         self.get_inner_size()
     }
 
+    #[inline]
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        Some(::FrameExtents::default())
+    }
+
     #[inline]
     pub fn set_inner_size(&self, _size: LogicalSize) {
         // N/A
@@ -340,6 +495,16 @@ impl Window {
         Err("Cursor grabbing is not possible on Android.".to_owned())
     }
 
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, _mode: ::CursorGrabMode) -> Result<(), String> {
+        Err("Cursor grabbing is not possible on Android.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, _region: Option<::Rect>) -> Result<(), String> {
+        Err("Cursor confinement is not possible on Android.".to_owned())
+    }
+
     #[inline]
     pub fn hide_cursor(&self, _hide: bool) {
         // N/A
@@ -356,6 +521,59 @@ impl Window {
         // Android has single screen maximized apps so nothing to do
     }
 
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+    }
+
+    #[inline]
+    pub fn set_focusable(&self, _focusable: bool) {
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        ::Insets::default()
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_fullscreen(&self, _monitor: Option<RootMonitorId>) {
         // N/A
@@ -377,11 +595,86 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn set_badge_label(&self, _label: Option<&str>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_progress(&self, _progress: Option<f64>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_overlay_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
     #[inline]
     pub fn set_ime_spot(&self, _spot: LogicalPosition) {
         // N/A
     }
 
+    #[inline]
+    pub fn set_ime_conversion_mode(&self, _mode: ::ImeMode) -> Result<(), String> {
+        Err("Setting the IME conversion mode is not possible on Android.".to_owned())
+    }
+
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, _callback: Option<::HitTestCallback>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_input_region(&self, _region: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Input regions are not supported on Android.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_shape(&self, _shape: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Window shapes are not supported on Android.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_blur(&self, _blur: bool) -> Result<(), String> {
+        Err("Blurring what's behind a window is not supported on Android.".to_owned())
+    }
+
+    #[inline]
+    pub fn reserve_screen_edge(&self, _reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        Err("Reserving screen space is not supported on Android.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_menu(&self, _menu: Option<::menu::Menu>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_accessibility_adapter(&self, _adapter: Option<::accessibility::AccessibilityAdapter>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, _visible: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn capture_frame(&self) -> Option<::CapturedFrame> {
+        None
+    }
+
+    #[inline]
+    pub fn is_frontmost(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn get_current_monitor(&self) -> RootMonitorId {
         RootMonitorId { inner: MonitorId }
@@ -408,5 +701,79 @@ impl Window {
 unsafe impl Send for Window {}
 unsafe impl Sync for Window {}
 
+#[cfg(feature = "shell")]
+pub fn reveal_in_file_manager(_path: &::std::path::Path) -> Result<(), String> {
+    Err("There's no file manager to reveal files in on Android.".to_owned())
+}
+
+#[cfg(feature = "shell")]
+pub fn open_with_default_app(_path: &::std::path::Path) -> Result<(), String> {
+    Err("Opening files is not possible on Android.".to_owned())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId;
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> TrayIconId {
+        TrayIconId
+    }
+}
+
+#[cfg(feature = "tray")]
+pub struct TrayIcon;
+
+#[cfg(feature = "tray")]
+impl TrayIcon {
+    #[inline]
+    pub fn new(
+        _: &EventsLoop,
+        _: ::tray::TrayIconAttributes,
+    ) -> Result<TrayIcon, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId
+    }
+
+    #[inline]
+    pub fn set_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_tooltip(&self, _tooltip: &str) {
+        // N/A
+    }
+}
+
+#[cfg(feature = "surface")]
+pub struct Surface;
+
+#[cfg(feature = "surface")]
+impl Surface {
+    #[inline]
+    pub fn new(_: &Window) -> Result<Surface, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn native(&self) -> ::surface::NativeSurface {
+        unreachable!()
+    }
+
+    #[inline]
+    pub fn resize(&self, _: PhysicalSize) {
+        unreachable!()
+    }
+}
+
 // Constant device ID, to be removed when this backend is updated to report real device IDs.
 const DEVICE_ID: ::DeviceId = ::DeviceId(DeviceId);
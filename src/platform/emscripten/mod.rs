@@ -7,7 +7,7 @@ use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::os::raw::{c_char, c_void, c_double, c_ulong, c_int};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Mutex, Arc};
+use std::sync::{Mutex, Arc, Weak};
 
 use dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
 use window::MonitorId as RootMonitorId;
@@ -27,6 +27,107 @@ unsafe impl Sync for PlatformSpecificWindowBuilderAttributes {}
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId;
 
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot;
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        None
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> DeviceId {
+        DeviceId
+    }
+}
+
+#[cfg(feature = "shell")]
+pub fn reveal_in_file_manager(_path: &::std::path::Path) -> Result<(), String> {
+    Err("There's no file manager to reveal files in on Emscripten.".to_owned())
+}
+
+#[cfg(feature = "shell")]
+pub fn open_with_default_app(_path: &::std::path::Path) -> Result<(), String> {
+    Err("Opening files is not possible on Emscripten.".to_owned())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId;
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> TrayIconId {
+        TrayIconId
+    }
+}
+
+#[cfg(feature = "tray")]
+pub struct TrayIcon;
+
+#[cfg(feature = "tray")]
+impl TrayIcon {
+    #[inline]
+    pub fn new(
+        _: &EventsLoop,
+        _: ::tray::TrayIconAttributes,
+    ) -> Result<TrayIcon, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId
+    }
+
+    #[inline]
+    pub fn set_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_tooltip(&self, _tooltip: &str) {
+        // N/A
+    }
+}
+
+#[cfg(feature = "surface")]
+pub struct Surface;
+
+#[cfg(feature = "surface")]
+impl Surface {
+    #[inline]
+    pub fn new(_: &Window) -> Result<Surface, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn native(&self) -> ::surface::NativeSurface {
+        unreachable!()
+    }
+
+    #[inline]
+    pub fn resize(&self, _: PhysicalSize) {
+        unreachable!()
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct PlatformSpecificHeadlessBuilderAttributes;
 
@@ -53,6 +154,16 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         get_hidpi_factor()
     }
+
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        (self.get_position(), self.get_dimensions())
+    }
+
+    #[inline]
+    pub fn get_taskbar_edge(&self) -> Option<::Edge> {
+        None
+    }
 }
 
 // Used to assign a callback to emscripten main loop
@@ -75,17 +186,32 @@ pub fn set_main_loop_callback<F>(callback : F) where F : FnMut() {
 }
 
 #[derive(Clone)]
-pub struct EventsLoopProxy;
+pub struct EventsLoopProxy {
+    pending_wakeup: Weak<AtomicBool>,
+}
 
 impl EventsLoopProxy {
     pub fn wakeup(&self) -> Result<(), ::EventsLoopClosed> {
-        unimplemented!()
+        let pending_wakeup = self.pending_wakeup.upgrade().ok_or(::EventsLoopClosed)?;
+        // Calls are merged until `poll_events` sees them.
+        pending_wakeup.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+// The idle time isn't available on this platform.
+pub enum IdleTimeQuery {}
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<::std::time::Duration> {
+        match *self {}
     }
 }
 
 pub struct EventsLoop {
     window: Mutex<Option<Arc<Window2>>>,
     interrupted: AtomicBool,
+    pending_wakeup: Arc<AtomicBool>,
 }
 
 impl EventsLoop {
@@ -93,9 +219,15 @@ impl EventsLoop {
         EventsLoop {
             window: Mutex::new(None),
             interrupted: AtomicBool::new(false),
+            pending_wakeup: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    // Nothing can fail to connect on this platform.
+    pub fn try_new() -> Result<EventsLoop, ::EventsLoopCreationError> {
+        Ok(EventsLoop::new())
+    }
+
     #[inline]
     pub fn interrupt(&self) {
         self.interrupted.store(true, Ordering::Relaxed);
@@ -103,7 +235,53 @@ impl EventsLoop {
 
     #[inline]
     pub fn create_proxy(&self) -> EventsLoopProxy {
-        unimplemented!()
+        EventsLoopProxy {
+            pending_wakeup: Arc::downgrade(&self.pending_wakeup),
+        }
+    }
+
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        None
+    }
+
+    pub fn shutdown(self) {
+        // No background threads to wait for.
+    }
+
+    #[inline]
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        Vec::new()
+    }
+
+    pub fn set_activation_policy(&mut self, _activation_policy: ::ActivationPolicy) {
+        // N/A
+    }
+
+    // Events are queued by each window, which don't exist yet.
+    pub fn reserve_events(&mut self, _capacity: usize) {
+        // N/A
+    }
+
+    pub fn block_session_end(&self, _reason: Option<&str>) {
+        // N/A
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        FocusSnapshot
+    }
+
+    pub fn restore_focus(&self, _snapshot: &FocusSnapshot) {
+        // N/A
+    }
+
+    pub fn keyboard_layouts(&self) -> Vec<::LayoutInfo> {
+        // N/A
+        Vec::new()
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        // N/A
+        Vec::new()
     }
 
     #[inline]
@@ -118,9 +296,16 @@ impl EventsLoop {
         MonitorId
     }
 
+    pub fn wait_for_vblank(&self) -> bool {
+        false
+    }
+
     pub fn poll_events<F>(&self, mut callback: F)
         where F: FnMut(::Event)
     {
+        if self.pending_wakeup.swap(false, Ordering::Relaxed) {
+            callback(::Event::Awakened);
+        }
         let ref mut window = *self.window.lock().unwrap();
         if let &mut Some(ref mut window) = window {
             while let Some(event) = window.events.lock().unwrap().pop_front() {
@@ -149,6 +334,20 @@ impl EventsLoop {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowId(usize);
 
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        WindowId(raw as usize)
+    }
+}
+
 pub struct Window2 {
     cursor_grabbed: Mutex<bool>,
     cursor_hidden: Mutex<bool>,
@@ -272,6 +471,11 @@ extern "C" fn keyboard_callback(
                             state: ::ElementState::Pressed,
                             virtual_keycode: key_translate_virt((*event).key, (*event).location),
                             modifiers,
+                            is_composing: false,
+                            is_synthetic: false,
+                            is_repeat: (*event).repeat == ffi::EM_TRUE,
+                            text: None,
+                            is_dead_key: false,
                         },
                     },
                 });
@@ -286,6 +490,11 @@ extern "C" fn keyboard_callback(
                             state: ::ElementState::Released,
                             virtual_keycode: key_translate_virt((*event).key, (*event).location),
                             modifiers,
+                            is_composing: false,
+                            is_synthetic: false,
+                            is_repeat: false,
+                            text: None,
+                            is_dead_key: false,
                         },
                     },
                 });
@@ -328,6 +537,8 @@ extern fn touch_callback(
                         phase,
                         id: touch.identifier as u64,
                         location,
+                        force: None,
+                        contact_size: None,
                     }),
                 });
             }
@@ -373,6 +584,11 @@ impl Window {
         if events_loop.window.lock().unwrap().is_some() {
             return Err(::CreationError::OsError("Cannot create another window".to_owned()));
         }
+        if attribs.parent.is_some() {
+            return Err(::CreationError::InvalidAttributes(
+                "Windows can't be created in a parent window on Emscripten.".to_owned(),
+            ));
+        }
 
         let w = Window2 {
             cursor_grabbed: Default::default(),
@@ -443,7 +659,13 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_position(&self, _: LogicalPosition) {
+    pub fn set_outer_position(&self, _: LogicalPosition) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, _: LogicalPosition) {
+        // N/A
     }
 
     #[inline]
@@ -470,6 +692,11 @@ impl Window {
         self.get_inner_size()
     }
 
+    #[inline]
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        Some(::FrameExtents::default())
+    }
+
     #[inline]
     pub fn set_inner_size(&self, size: LogicalSize) {
         unsafe {
@@ -509,6 +736,11 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn present_ready(&self) {
+        // N/A
+    }
+
     #[inline]
     pub fn set_cursor(&self, _cursor: ::MouseCursor) {
         // N/A
@@ -541,6 +773,21 @@ impl Window {
         Ok(())
     }
 
+    // Pointer lock is the only kind of grab there is, so it's what `grab_cursor` does.
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, mode: ::CursorGrabMode) -> Result<(), String> {
+        match mode {
+            ::CursorGrabMode::None => self.grab_cursor(false),
+            ::CursorGrabMode::Locked => self.grab_cursor(true),
+            ::CursorGrabMode::Confined => Err("Emscripten can only lock the cursor, not confine it.".to_owned()),
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, _region: Option<::Rect>) -> Result<(), String> {
+        Err("Cursor confinement is not possible on Emscripten.".to_owned())
+    }
+
     #[inline]
     pub fn hide_cursor(&self, hide: bool) {
         let mut hidden_lock = self.window.cursor_hidden.lock().unwrap();
@@ -568,6 +815,59 @@ impl Window {
         // iOS has single screen maximized apps so nothing to do
     }
 
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+    }
+
+    #[inline]
+    pub fn set_focusable(&self, _focusable: bool) {
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        ::Insets::default()
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_fullscreen(&self, _monitor: Option<::MonitorId>) {
         // iOS has single screen maximized apps so nothing to do
@@ -588,11 +888,86 @@ impl Window {
         // N/A
     }
 
+    #[inline]
+    pub fn set_badge_label(&self, _label: Option<&str>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_progress(&self, _progress: Option<f64>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_overlay_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
     #[inline]
     pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
         // N/A
     }
 
+    #[inline]
+    pub fn set_ime_conversion_mode(&self, _mode: ::ImeMode) -> Result<(), String> {
+        Err("Setting the IME conversion mode is not possible on Emscripten.".to_owned())
+    }
+
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, _callback: Option<::HitTestCallback>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_input_region(&self, _region: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Input regions are not supported on Emscripten.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_shape(&self, _shape: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Window shapes are not supported on Emscripten.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_blur(&self, _blur: bool) -> Result<(), String> {
+        Err("Blurring what's behind a window is not supported on Emscripten.".to_owned())
+    }
+
+    #[inline]
+    pub fn reserve_screen_edge(&self, _reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        Err("Reserving screen space is not supported on Emscripten.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_menu(&self, _menu: Option<::menu::Menu>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_accessibility_adapter(&self, _adapter: Option<::accessibility::AccessibilityAdapter>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, _visible: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn capture_frame(&self) -> Option<::CapturedFrame> {
+        None
+    }
+
+    #[inline]
+    pub fn is_frontmost(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn get_current_monitor(&self) -> RootMonitorId {
         RootMonitorId { inner: MonitorId }
@@ -0,0 +1,160 @@
+//! The state shared between the `EventsLoop` and UIKit's callbacks.
+//!
+//! Once `UIApplicationMain` is called, UIKit owns the main thread and only calls back into us, so
+//! this lives in thread locals of the main thread rather than in the `EventsLoop`.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+
+use {ControlFlow, Event, WindowEvent};
+use super::WindowId;
+use super::ffi::id;
+
+struct Windows {
+    // Whether `application:didFinishLaunchingWithOptions:` has been called yet.
+    launched: bool,
+    // Every window that hasn't been dropped, which application-wide events are sent to.
+    windows: Vec<id>,
+    // Windows created before the application finished launching, which are shown once it has.
+    queued_windows: Vec<id>,
+}
+
+thread_local! {
+    static WINDOWS: RefCell<Windows> = RefCell::new(Windows {
+        launched: false,
+        windows: Vec::new(),
+        queued_windows: Vec::new(),
+    });
+
+    // Events that haven't been handed to the callback yet.
+    static EVENTS: RefCell<VecDeque<Event>> = RefCell::new(VecDeque::new());
+
+    // The callback passed to `run_forever`, until it returns `ControlFlow::Break`, along with the
+    // function that calls it. It's only set while a `CallbackGuard` borrows it.
+    static CALLBACK: RefCell<Option<(*mut c_void, unsafe fn(*mut c_void, Event) -> ControlFlow)>> =
+        RefCell::new(None);
+}
+
+/// Hands events to a callback for as long as it's borrowed, so that its lifetime doesn't have to
+/// be extended: the callback is forgotten when the guard is dropped, even when unwinding.
+pub struct CallbackGuard<'a> {
+    _callback: PhantomData<&'a mut ()>,
+}
+
+impl<'a> Drop for CallbackGuard<'a> {
+    fn drop(&mut self) {
+        CALLBACK.with(|cell| *cell.borrow_mut() = None);
+    }
+}
+
+pub fn set_callback<'a, F>(callback: &'a mut F) -> CallbackGuard<'a>
+    where F: FnMut(Event) -> ControlFlow
+{
+    unsafe fn call<F>(callback: *mut c_void, event: Event) -> ControlFlow
+        where F: FnMut(Event) -> ControlFlow
+    {
+        (*(callback as *mut F))(event)
+    }
+
+    let callback = (callback as *mut F as *mut c_void, call::<F> as unsafe fn(_, _) -> _);
+    CALLBACK.with(|cell| *cell.borrow_mut() = Some(callback));
+    CallbackGuard { _callback: PhantomData }
+}
+
+/// Hands the events that are already queued to `callback`, for `poll_events`.
+pub fn take_events<F>(mut callback: F)
+    where F: FnMut(Event)
+{
+    while let Some(event) = EVENTS.with(|events| events.borrow_mut().pop_front()) {
+        callback(event);
+    }
+}
+
+/// Makes room for `capacity` queued events.
+pub fn reserve_events(capacity: usize) {
+    EVENTS.with(|events| events.borrow_mut().reserve(capacity));
+}
+
+/// Delivers an event to the callback passed to `run_forever`, or queues it until there is one.
+pub fn send_event(event: Event) {
+    EVENTS.with(|events| events.borrow_mut().push_back(event));
+    run_pending();
+}
+
+/// Sends the same event to every window, for events that UIKit sends to the whole application.
+pub fn send_event_to_all_windows(event: WindowEvent) {
+    let windows = WINDOWS.with(|windows| windows.borrow().windows.clone());
+    for window in windows {
+        send_event(Event::WindowEvent {
+            window_id: ::WindowId(WindowId(window as usize)),
+            event: event.clone(),
+        });
+    }
+}
+
+fn run_pending() {
+    CALLBACK.with(|callback| {
+        // If the callback is already running, it caused this event, e.g. by creating a window.
+        // It will be handled by the loop below once the callback returns.
+        let mut callback = match callback.try_borrow_mut() {
+            Ok(callback) => callback,
+            Err(_) => return,
+        };
+        loop {
+            let (callback_ptr, call) = match *callback {
+                Some(callback) => callback,
+                None => return,
+            };
+            let event = match EVENTS.with(|events| events.borrow_mut().pop_front()) {
+                Some(event) => event,
+                None => return,
+            };
+            // The `CallbackGuard` clears this before the callback goes away.
+            let control_flow = unsafe { call(callback_ptr, event) };
+            if control_flow == ControlFlow::Break {
+                *callback = None;
+            }
+        }
+    });
+}
+
+/// Keeps track of a new window, and shows it if `visible` is set. UIKit only shows windows once
+/// the application has finished launching, so until then they're queued.
+pub unsafe fn add_window(window: id, visible: bool) {
+    let show_now = WINDOWS.with(|windows| {
+        let mut windows = windows.borrow_mut();
+        windows.windows.push(window);
+        if visible && !windows.launched {
+            windows.queued_windows.push(window);
+            false
+        } else {
+            visible
+        }
+    });
+    if show_now {
+        let _: () = msg_send![window, makeKeyAndVisible];
+    }
+}
+
+pub fn remove_window(window: id) {
+    WINDOWS.with(|windows| {
+        let mut windows = windows.borrow_mut();
+        windows.windows.retain(|&w| w != window);
+        windows.queued_windows.retain(|&w| w != window);
+    });
+}
+
+/// Called from `application:didFinishLaunchingWithOptions:`.
+pub unsafe fn did_finish_launching() {
+    let queued_windows = WINDOWS.with(|windows| {
+        let mut windows = windows.borrow_mut();
+        windows.launched = true;
+        ::std::mem::replace(&mut windows.queued_windows, Vec::new())
+    });
+    for window in queued_windows {
+        let _: () = msg_send![window, makeKeyAndVisible];
+    }
+    run_pending();
+}
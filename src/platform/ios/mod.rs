@@ -45,13 +45,24 @@
 //! [app lifecycle](https://developer.apple.com/library/ios/documentation/UIKit/Reference/UIApplicationDelegate_Protocol/).
 //!
 //!
+//! `EventsLoop::run_forever` hands the main thread over to UIKit by calling `UIApplicationMain`,
+//! and never returns. After the callback returns `ControlFlow::Break`, it isn't called again.
+//! `EventsLoop::poll_events` only hands over the events that are already queued, since UIKit can't
+//! be run one event at a time.
+//!
+//! Windows created before `run_forever` is called are shown once the application has finished
+//! launching.
+//!
 //! This is how those event are represented in winit:
 //!
-//!  - applicationDidBecomeActive is Focused(true)
-//!  - applicationWillResignActive is Focused(false)
+//!  - applicationDidBecomeActive is ApplicationActivated, and Focused(true) for every window
+//!  - applicationWillResignActive is ApplicationDeactivated, and Focused(false) for every window
 //!  - applicationDidEnterBackground is Suspended(true)
 //!  - applicationWillEnterForeground is Suspended(false)
-//!  - applicationWillTerminate is Destroyed
+//!  - applicationWillTerminate is Destroyed for every window
+//!
+//! Rotating the device lays the view out again, which is reported as Resized, along with
+//! OrientationChanged.
 //!
 //! Keep in mind that after Destroyed event is received every attempt to draw with
 //! opengl will result in segfault.
@@ -60,53 +71,51 @@
 
 #![cfg(target_os = "ios")]
 
-use std::{fmt, mem, ptr};
-use std::cell::RefCell;
+use std::{fmt, ptr};
+use std::cell::Cell;
 use std::collections::VecDeque;
+use std::ffi::CStr;
 use std::os::raw::*;
-use std::sync::Arc;
 
+use objc::Message;
 use objc::declare::ClassDecl;
-use objc::runtime::{BOOL, Class, Object, Sel, YES};
+use objc::runtime::{BOOL, Class, NO, Object, Protocol, Sel, YES};
 
 use {
     CreationError,
     Event,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MouseCursor,
+    Orientation,
     PhysicalPosition,
     PhysicalSize,
     WindowAttributes,
     WindowEvent,
     WindowId as RootEventId,
 };
-use events::{Touch, TouchPhase};
+use events::{Force, Touch, TouchPhase};
 use window::MonitorId as RootMonitorId;
 
+mod app_state;
 mod ffi;
 use self::ffi::{
-    CFTimeInterval,
-    CFRunLoopRunInMode,
     CGFloat,
     CGPoint,
     CGRect,
+    CGSize,
     id,
-    JBLEN,
-    JmpBuf,
-    kCFRunLoopDefaultMode,
-    kCFRunLoopRunHandledSource,
-    longjmp,
     nil,
     NSString,
-    setjmp,
     UIApplicationMain,
+    UIEdgeInsets,
+    UIKeyboardFrameEndUserInfoKey,
+    UIKeyboardWillHideNotification,
+    UIKeyboardWillShowNotification,
  };
 
-static mut JMPBUF: Option<Box<JmpBuf>> = None;
-
 pub struct Window {
-    _events_queue: Arc<RefCell<VecDeque<Event>>>,
     delegate_state: Box<DelegateState>,
 }
 
@@ -118,34 +127,138 @@ struct DelegateState {
     window: id,
     controller: id,
     view: id,
-    size: LogicalSize,
-    scale: f64,
+    // Hidden view that becomes the first responder to show the keyboard.
+    keyboard_view: id,
+    // The size of the view we last reported.
+    size: Cell<LogicalSize>,
+    // The size given to `set_inner_size`, or `None` if the view fills the window.
+    requested_size: Cell<Option<LogicalSize>>,
+    // The safe area insets of the view we last reported.
+    safe_area_insets: Cell<Insets>,
+    // The orientation we last reported.
+    orientation: Cell<Option<Orientation>>,
 }
 
 impl DelegateState {
-    fn new(window: id, controller: id, view: id, size: LogicalSize, scale: f64) -> DelegateState {
+    fn new(window: id, controller: id, view: id, keyboard_view: id) -> DelegateState {
         DelegateState {
             window,
             controller,
             view,
-            size,
-            scale,
+            keyboard_view,
+            size: Cell::new(LogicalSize::new(0.0, 0.0)),
+            requested_size: Cell::new(None),
+            safe_area_insets: Cell::new(Insets::default()),
+            orientation: Cell::new(None),
+        }
+    }
+
+    fn id(&self) -> RootEventId {
+        RootEventId(WindowId(self.window as usize))
+    }
+
+    // The part of the window that isn't covered by the status bar, the notch or the home
+    // indicator, in the window's coordinates.
+    unsafe fn get_safe_area(&self) -> CGRect {
+        let bounds: CGRect = msg_send![self.window, bounds];
+        // `safeAreaInsets` requires iOS 11. Before that, nothing covers the window.
+        let responds_to_insets: BOOL = msg_send![self.window, respondsToSelector:sel!(safeAreaInsets)];
+        if responds_to_insets == NO {
+            return bounds;
+        }
+        let insets: UIEdgeInsets = msg_send![self.window, safeAreaInsets];
+        CGRect {
+            origin: CGPoint {
+                x: bounds.origin.x + insets.left,
+                y: bounds.origin.y + insets.top,
+            },
+            size: CGSize {
+                width: bounds.size.width - insets.left - insets.right,
+                height: bounds.size.height - insets.top - insets.bottom,
+            },
+        }
+    }
+
+    // Fits the view to the window, or to the size requested with `set_inner_size` inside the
+    // safe area.
+    unsafe fn layout_view(&self) {
+        let frame = match self.requested_size.get() {
+            Some(size) => {
+                let safe_area = self.get_safe_area();
+                CGRect {
+                    origin: safe_area.origin,
+                    size: CGSize {
+                        width: safe_area.size.width.min(size.width as CGFloat),
+                        height: safe_area.size.height.min(size.height as CGFloat),
+                    },
+                }
+            },
+            None => msg_send![self.window, bounds],
+        };
+        let _: () = msg_send![self.view, setFrame:frame];
+    }
+
+    // How far the safe area is inside each edge of the view.
+    unsafe fn get_safe_area_insets(&self) -> Insets {
+        let safe_area = self.get_safe_area();
+        let frame: CGRect = msg_send![self.view, frame];
+        let inset = |inset: CGFloat| inset.max(0.0) as f64;
+        Insets {
+            top: inset(safe_area.origin.y - frame.origin.y),
+            left: inset(safe_area.origin.x - frame.origin.x),
+            bottom: inset(
+                (frame.origin.y + frame.size.height) - (safe_area.origin.y + safe_area.size.height)
+            ),
+            right: inset(
+                (frame.origin.x + frame.size.width) - (safe_area.origin.x + safe_area.size.width)
+            ),
         }
     }
+
+    unsafe fn get_view_size(&self) -> LogicalSize {
+        let bounds: CGRect = msg_send![self.view, bounds];
+        (bounds.size.width as f64, bounds.size.height as f64).into()
+    }
 }
 
 impl Drop for DelegateState {
     fn drop(&mut self) {
+        app_state::remove_window(self.window);
         unsafe {
+            // UIKit may keep the controller around for a bit, so it mustn't look at us anymore.
+            let notification_center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![notification_center, removeObserver:self.controller];
+            (&mut *self.controller).set_ivar("winitState", ptr::null_mut::<c_void>());
+
+            let _: () = msg_send![self.window, setHidden:YES];
             let _: () = msg_send![self.window, release];
             let _: () = msg_send![self.controller, release];
             let _: () = msg_send![self.view, release];
+            let _: () = msg_send![self.keyboard_view, release];
         }
     }
 }
 
-#[derive(Clone)]
-pub struct MonitorId;
+pub struct MonitorId {
+    uiscreen: id,
+}
+
+unsafe impl Send for MonitorId {}
+unsafe impl Sync for MonitorId {}
+
+impl Clone for MonitorId {
+    fn clone(&self) -> MonitorId {
+        unsafe { MonitorId::retain(self.uiscreen) }
+    }
+}
+
+impl Drop for MonitorId {
+    fn drop(&mut self) {
+        unsafe {
+            let _: () = msg_send![self.uiscreen, release];
+        }
+    }
+}
 
 impl fmt::Debug for MonitorId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -169,15 +282,32 @@ impl fmt::Debug for MonitorId {
 }
 
 impl MonitorId {
+    // The screen is released when the `MonitorId` is dropped.
+    unsafe fn retain(uiscreen: id) -> MonitorId {
+        let _: id = msg_send![uiscreen, retain];
+        MonitorId { uiscreen }
+    }
+
+    fn main_screen() -> MonitorId {
+        unsafe {
+            let uiscreen: id = msg_send![class!(UIScreen), mainScreen];
+            MonitorId::retain(uiscreen)
+        }
+    }
+
     #[inline]
     pub fn get_uiscreen(&self) -> id {
-        let class = class!(UIScreen);
-        unsafe { msg_send![class, mainScreen] }
+        self.uiscreen
     }
 
     #[inline]
     pub fn get_name(&self) -> Option<String> {
-        Some("Primary".to_string())
+        let main_screen: id = unsafe { msg_send![class!(UIScreen), mainScreen] };
+        if self.uiscreen == main_screen {
+            Some("Primary".to_string())
+        } else {
+            Some("External".to_string())
+        }
     }
 
     #[inline]
@@ -188,7 +318,7 @@ impl MonitorId {
 
     #[inline]
     pub fn get_position(&self) -> PhysicalPosition {
-        // iOS assumes single screen
+        // External screens aren't arranged relative to the device's own.
         (0, 0).into()
     }
 
@@ -197,10 +327,34 @@ impl MonitorId {
         let scale: CGFloat = unsafe { msg_send![self.get_uiscreen(), nativeScale] };
         scale as f64
     }
+
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        (self.get_position(), self.get_dimensions())
+    }
+
+    #[inline]
+    pub fn get_taskbar_edge(&self) -> Option<::Edge> {
+        None
+    }
+}
+
+// The device's own screen, followed by any external displays connected through AirPlay or a cable.
+fn get_available_monitors() -> VecDeque<MonitorId> {
+    unsafe {
+        let screens: id = msg_send![class!(UIScreen), screens];
+        let count: usize = msg_send![screens, count];
+        (0..count)
+            .map(|index| {
+                let uiscreen: id = msg_send![screens, objectAtIndex:index];
+                MonitorId::retain(uiscreen)
+            })
+            .collect()
+    }
 }
 
 pub struct EventsLoop {
-    events_queue: Arc<RefCell<VecDeque<Event>>>,
+    _private: (),
 }
 
 #[derive(Clone)]
@@ -213,95 +367,187 @@ impl EventsLoop {
                 panic!("`EventsLoop` can only be created on the main thread on iOS");
             }
         }
-        EventsLoop { events_queue: Default::default() }
+        EventsLoop { _private: () }
+    }
+
+    // Nothing can fail to connect on this platform.
+    pub fn try_new() -> Result<EventsLoop, ::EventsLoopCreationError> {
+        Ok(EventsLoop::new())
     }
 
     #[inline]
     pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
-        let mut rb = VecDeque::with_capacity(1);
-        rb.push_back(MonitorId);
-        rb
+        get_available_monitors()
     }
 
     #[inline]
     pub fn get_primary_monitor(&self) -> MonitorId {
-        MonitorId
+        MonitorId::main_screen()
     }
 
-    pub fn poll_events<F>(&mut self, mut callback: F)
+    pub fn wait_for_vblank(&mut self) -> bool {
+        false
+    }
+
+    /// Only hands over the events that are already queued, since UIKit can't be run one event at
+    /// a time.
+    pub fn poll_events<F>(&self, callback: F)
         where F: FnMut(::Event)
     {
-        if let Some(event) = self.events_queue.borrow_mut().pop_front() {
-            callback(event);
-            return;
-        }
-
-        unsafe {
-            // jump hack, so we won't quit on willTerminate event before processing it
-            assert!(JMPBUF.is_some(), "`EventsLoop::poll_events` must be called after window creation on iOS");
-            if setjmp(mem::transmute_copy(&mut JMPBUF)) != 0 {
-                if let Some(event) = self.events_queue.borrow_mut().pop_front() {
-                    callback(event);
-                    return;
-                }
-            }
-        }
-
-        unsafe {
-            // run runloop
-            let seconds: CFTimeInterval = 0.000002;
-            while CFRunLoopRunInMode(kCFRunLoopDefaultMode, seconds, 1) == kCFRunLoopRunHandledSource {}
-        }
-
-        if let Some(event) = self.events_queue.borrow_mut().pop_front() {
-            callback(event)
-        }
+        app_state::take_events(callback);
     }
 
-    pub fn run_forever<F>(&mut self, mut callback: F)
+    /// Starts the application, which never returns. Everything UIKit tells us about from then on
+    /// is handed to the callback.
+    pub fn run_forever<F>(&self, mut callback: F)
         where F: FnMut(::Event) -> ::ControlFlow,
     {
-        // Yeah that's a very bad implementation.
-        loop {
-            let mut control_flow = ::ControlFlow::Continue;
-            self.poll_events(|e| {
-                if let ::ControlFlow::Break = callback(e) {
-                    control_flow = ::ControlFlow::Break;
-                }
-            });
-            if let ::ControlFlow::Break = control_flow {
-                break;
-            }
-            ::std::thread::sleep(::std::time::Duration::from_millis(5));
-        }
+        let _guard = app_state::set_callback(&mut callback);
+
+        create_delegate_class();
+        start_app();
+
+        panic!("Couldn't create `UIApplication`!")
     }
 
     pub fn create_proxy(&self) -> EventsLoopProxy {
+        create_wakeup_class();
         EventsLoopProxy
     }
+
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        None
+    }
+
+    pub fn shutdown(self) {
+        // No background threads to wait for.
+    }
+
+    #[inline]
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        Vec::new()
+    }
+
+    pub fn set_activation_policy(&mut self, _activation_policy: ::ActivationPolicy) {
+        // N/A
+    }
+
+    pub fn reserve_events(&mut self, capacity: usize) {
+        app_state::reserve_events(capacity);
+    }
+
+    pub fn block_session_end(&self, _reason: Option<&str>) {
+        // N/A
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        FocusSnapshot
+    }
+
+    pub fn restore_focus(&self, _snapshot: &FocusSnapshot) {
+        // N/A
+    }
+
+    pub fn keyboard_layouts(&self) -> Vec<::LayoutInfo> {
+        // N/A
+        Vec::new()
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        // N/A
+        Vec::new()
+    }
 }
 
 impl EventsLoopProxy {
+    /// Sends `Awakened` from the main thread's run loop, which UIKit runs once `run_forever` has
+    /// been called.
     pub fn wakeup(&self) -> Result<(), ::EventsLoopClosed> {
-        unimplemented!()
+        unsafe {
+            let class = create_wakeup_class();
+            let _: () = msg_send![class, performSelectorOnMainThread:sel!(wakeup)
+                                                          withObject:nil
+                                                       waitUntilDone:NO];
+        }
+        Ok(())
     }
 }
 
+// The idle time isn't available on this platform.
+pub enum IdleTimeQuery {}
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<::std::time::Duration> {
+        match *self {}
+    }
+}
+
+// The address of the `UIWindow`.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct WindowId;
+pub struct WindowId(usize);
+
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
 
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        WindowId(raw as usize)
+    }
+}
+
+// Touches are told apart by their `UITouchType`, i.e. whether they come from a finger or a stylus.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct DeviceId;
+pub struct DeviceId {
+    uitouch_type: isize,
+}
+
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId { uitouch_type: 0 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot;
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        None
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        self.uitouch_type as u64
+    }
+
+    pub fn from_raw(raw: u64) -> DeviceId {
+        DeviceId { uitouch_type: raw as isize }
+    }
+}
 
 #[derive(Clone)]
 pub struct PlatformSpecificWindowBuilderAttributes {
     pub root_view_class: &'static Class,
+    pub prefers_status_bar_hidden: bool,
+    pub prefers_home_indicator_hidden: bool,
+    pub supported_orientations: Vec<Orientation>,
 }
 
 impl Default for PlatformSpecificWindowBuilderAttributes {
     fn default() -> Self {
         PlatformSpecificWindowBuilderAttributes {
             root_view_class: class!(UIView),
+            prefers_status_bar_hidden: false,
+            prefers_home_indicator_hidden: false,
+            supported_orientations: Vec::new(),
         }
     }
 }
@@ -310,52 +556,75 @@ impl Default for PlatformSpecificWindowBuilderAttributes {
 // so to be consistent with other platforms we have to change that.
 impl Window {
     pub fn new(
-        ev: &EventsLoop,
-        _attributes: WindowAttributes,
+        _ev: &EventsLoop,
+        attributes: WindowAttributes,
         pl_attributes: PlatformSpecificWindowBuilderAttributes,
     ) -> Result<Window, CreationError> {
-        unsafe {
-            debug_assert!(mem::size_of_val(&JMPBUF) == mem::size_of::<Box<JmpBuf>>());
-            assert!(mem::replace(&mut JMPBUF, Some(Box::new([0; JBLEN]))).is_none(), "Only one `Window` is supported on iOS");
+        if attributes.parent.is_some() {
+            return Err(CreationError::InvalidAttributes(
+                "Windows can't be created in a parent window on iOS.".to_owned(),
+            ));
         }
-
         unsafe {
-            if setjmp(mem::transmute_copy(&mut JMPBUF)) != 0 {
-                let app_class = class!(UIApplication);
-                let app: id = msg_send![app_class, sharedApplication];
-                let delegate: id = msg_send![app, delegate];
-                let state: *mut c_void = *(&*delegate).get_ivar("winitState");
-                let mut delegate_state = Box::from_raw(state as *mut DelegateState);
-                let events_queue = &*ev.events_queue;
-                (&mut *delegate).set_ivar("eventsQueue", mem::transmute::<_, *mut c_void>(events_queue));
-
-                // easiest? way to get access to PlatformSpecificWindowBuilderAttributes to configure the view
-                let rect: CGRect = msg_send![MonitorId.get_uiscreen(), bounds];
-
-                let uiview_class = class!(UIView);
-                let root_view_class = pl_attributes.root_view_class;
-                let is_uiview: BOOL = msg_send![root_view_class, isSubclassOfClass:uiview_class];
-                assert!(is_uiview == YES, "`root_view_class` must inherit from `UIView`");
-
-                delegate_state.view = msg_send![root_view_class, alloc];
-                assert!(!delegate_state.view.is_null(), "Failed to create `UIView` instance");
-                delegate_state.view = msg_send![delegate_state.view, initWithFrame:rect];
-                assert!(!delegate_state.view.is_null(), "Failed to initialize `UIView` instance");
-
-                let _: () = msg_send![delegate_state.controller, setView:delegate_state.view];
-                let _: () = msg_send![delegate_state.window, makeKeyAndVisible];
-
-                return Ok(Window {
-                    _events_queue: ev.events_queue.clone(),
-                    delegate_state,
-                });
+            let screen = match attributes.fullscreen {
+                Some(ref monitor) => monitor.inner.get_uiscreen(),
+                None => msg_send![class!(UIScreen), mainScreen],
+            };
+            let bounds: CGRect = msg_send![screen, bounds];
+
+            let window: id = msg_send![class!(UIWindow), alloc];
+            let window: id = msg_send![window, initWithFrame:bounds.clone()];
+            let _: () = msg_send![window, setScreen:screen];
+
+            let controller: id = msg_send![create_view_controller_class(), alloc];
+            let controller: id = msg_send![controller, init];
+
+            let uiview_class = class!(UIView);
+            let root_view_class = pl_attributes.root_view_class;
+            let is_uiview: BOOL = msg_send![root_view_class, isSubclassOfClass:uiview_class];
+            assert!(is_uiview == YES, "`root_view_class` must inherit from `UIView`");
+
+            let view: id = msg_send![root_view_class, alloc];
+            assert!(!view.is_null(), "Failed to create `UIView` instance");
+            let view: id = msg_send![view, initWithFrame:bounds];
+            assert!(!view.is_null(), "Failed to initialize `UIView` instance");
+
+            let keyboard_view: id = msg_send![create_keyboard_view_class(), alloc];
+            let keyboard_view: id = msg_send![keyboard_view, initWithFrame:CGRect {
+                origin: CGPoint { x: 0.0, y: 0.0 },
+                size: CGSize { width: 0.0, height: 0.0 },
+            }];
+            let _: () = msg_send![view, addSubview:keyboard_view];
+
+            let delegate_state = Box::new(DelegateState::new(window, controller, view, keyboard_view));
+            delegate_state.size.set(delegate_state.get_view_size());
+
+            {
+                let controller = &mut *controller;
+                let state_ptr = &*delegate_state as *const DelegateState as *mut c_void;
+                controller.set_ivar("winitState", state_ptr);
+                controller.set_ivar("prefersStatusBarHidden", bool_to_objc(pl_attributes.prefers_status_bar_hidden));
+                controller.set_ivar("prefersHomeIndicatorAutoHidden", bool_to_objc(pl_attributes.prefers_home_indicator_hidden));
+                let orientations = orientation_mask(&pl_attributes.supported_orientations);
+                controller.set_ivar("supportedInterfaceOrientations", orientations);
             }
+            let _: () = msg_send![controller, setView:view];
+            let _: () = msg_send![window, setRootViewController:controller];
+
+            let notification_center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![notification_center, addObserver:controller
+                                                          selector:sel!(keyboardWillShow:)
+                                                              name:UIKeyboardWillShowNotification
+                                                            object:nil];
+            let _: () = msg_send![notification_center, addObserver:controller
+                                                          selector:sel!(keyboardWillHide:)
+                                                              name:UIKeyboardWillHideNotification
+                                                            object:nil];
+
+            app_state::add_window(window, attributes.visible);
+
+            Ok(Window { delegate_state })
         }
-
-        create_delegate_class();
-        start_app();
-
-        panic!("Couldn't create `UIApplication`!")
     }
 
     #[inline]
@@ -375,44 +644,74 @@ impl Window {
 
     #[inline]
     pub fn show(&self) {
-        // N/A
+        unsafe {
+            let _: () = msg_send![self.delegate_state.window, setHidden:NO];
+        }
     }
 
     #[inline]
     pub fn hide(&self) {
+        unsafe {
+            let _: () = msg_send![self.delegate_state.window, setHidden:YES];
+        }
+    }
+
+    #[inline]
+    pub fn present_ready(&self) {
         // N/A
     }
 
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
-        // N/A
-        None
+        let frame: CGRect = unsafe { msg_send![self.delegate_state.window, frame] };
+        Some((frame.origin.x as f64, frame.origin.y as f64).into())
     }
 
     #[inline]
     pub fn get_inner_position(&self) -> Option<LogicalPosition> {
+        let window_frame: CGRect = unsafe { msg_send![self.delegate_state.window, frame] };
+        let view_frame: CGRect = unsafe { msg_send![self.delegate_state.view, frame] };
+        Some((
+            (window_frame.origin.x + view_frame.origin.x) as f64,
+            (window_frame.origin.y + view_frame.origin.y) as f64,
+        ).into())
+    }
+
+    #[inline]
+    pub fn set_outer_position(&self, _position: LogicalPosition) {
         // N/A
-        None
+        // The window always covers its screen
     }
 
     #[inline]
-    pub fn set_position(&self, _position: LogicalPosition) {
+    pub fn set_inner_position(&self, _position: LogicalPosition) {
         // N/A
+        // The window always covers its screen
     }
 
     #[inline]
     pub fn get_inner_size(&self) -> Option<LogicalSize> {
-        Some(self.delegate_state.size)
+        Some(unsafe { self.delegate_state.get_view_size() })
     }
 
     #[inline]
     pub fn get_outer_size(&self) -> Option<LogicalSize> {
-        self.get_inner_size()
+        let bounds: CGRect = unsafe { msg_send![self.delegate_state.window, bounds] };
+        Some((bounds.size.width as f64, bounds.size.height as f64).into())
     }
 
     #[inline]
-    pub fn set_inner_size(&self, _size: LogicalSize) {
-        // N/A
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        // The safe area isn't a decoration of the window.
+        Some(::FrameExtents::default())
+    }
+
+    // The view is placed inside the safe area, and shrunk to fit if it's too small. The size is
+    // remembered, so that it's applied again when the device is rotated.
+    #[inline]
+    pub fn set_inner_size(&self, size: LogicalSize) {
+        self.delegate_state.requested_size.set(Some(size));
+        unsafe { self.delegate_state.layout_view() };
     }
 
     #[inline]
@@ -440,6 +739,16 @@ impl Window {
         Err("Cursor grabbing is not possible on iOS.".to_owned())
     }
 
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, _mode: ::CursorGrabMode) -> Result<(), String> {
+        Err("Cursor grabbing is not possible on iOS.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, _region: Option<::Rect>) -> Result<(), String> {
+        Err("Cursor confinement is not possible on iOS.".to_owned())
+    }
+
     #[inline]
     pub fn hide_cursor(&self, _hide: bool) {
         // N/A
@@ -447,7 +756,7 @@ impl Window {
 
     #[inline]
     pub fn get_hidpi_factor(&self) -> f64 {
-        self.delegate_state.scale
+        self.get_current_monitor().get_hidpi_factor()
     }
 
     #[inline]
@@ -462,9 +771,69 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_fullscreen(&self, _monitor: Option<RootMonitorId>) {
+    pub fn set_minimized(&self, _minimized: bool) {
         // N/A
-        // iOS has single screen maximized apps so nothing to do
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+    }
+
+    #[inline]
+    pub fn set_focusable(&self, _focusable: bool) {
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> Insets {
+        unsafe { self.delegate_state.get_safe_area_insets() }
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
+    // Windows always fill their screen, so this only moves the window to another one. `None` moves
+    // it back to the device's own screen.
+    #[inline]
+    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
+        let monitor = monitor.map(|monitor| monitor.inner).unwrap_or_else(MonitorId::main_screen);
+        unsafe {
+            let window = self.delegate_state.window;
+            let _: () = msg_send![window, setScreen:monitor.get_uiscreen()];
+            let bounds: CGRect = msg_send![monitor.get_uiscreen(), bounds];
+            let _: () = msg_send![window, setFrame:bounds];
+        }
     }
 
     #[inline]
@@ -483,124 +852,301 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
+    pub fn set_badge_label(&self, _label: Option<&str>) {
         // N/A
     }
 
     #[inline]
-    pub fn get_current_monitor(&self) -> RootMonitorId {
-        RootMonitorId { inner: MonitorId }
+    pub fn set_progress(&self, _progress: Option<f64>) {
+        // N/A
     }
 
     #[inline]
-    pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
-        let mut rb = VecDeque::with_capacity(1);
-        rb.push_back(MonitorId);
-        rb
+    pub fn set_overlay_icon(&self, _icon: Option<::Icon>) {
+        // N/A
     }
 
     #[inline]
-    pub fn get_primary_monitor(&self) -> MonitorId {
-        MonitorId
+    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
+        // UIKit places the on-screen keyboard itself, and there's no candidate box to move.
     }
 
     #[inline]
-    pub fn id(&self) -> WindowId {
-        WindowId
+    pub fn set_ime_conversion_mode(&self, _mode: ::ImeMode) -> Result<(), String> {
+        Err("Setting the IME conversion mode is not possible on iOS.".to_owned())
     }
-}
 
-fn create_delegate_class() {
-    extern fn did_finish_launching(this: &mut Object, _: Sel, _: id, _: id) -> BOOL {
-        let screen_class = class!(UIScreen);
-        let window_class = class!(UIWindow);
-        let controller_class = class!(UIViewController);
-        unsafe {
-            let main_screen: id = msg_send![screen_class, mainScreen];
-            let bounds: CGRect = msg_send![main_screen, bounds];
-            let scale: CGFloat = msg_send![main_screen, nativeScale];
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        false
+    }
 
-            let window: id = msg_send![window_class, alloc];
-            let window: id = msg_send![window, initWithFrame:bounds.clone()];
+    #[inline]
+    pub fn set_hit_test_callback(&self, _callback: Option<::HitTestCallback>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_input_region(&self, _region: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Input regions are not supported on iOS.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_shape(&self, _shape: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Window shapes are not supported on iOS.".to_owned())
+    }
 
-            let size = (bounds.size.width as f64, bounds.size.height as f64).into();
+    #[inline]
+    pub fn set_blur(&self, _blur: bool) -> Result<(), String> {
+        Err("Blurring what's behind a window is not supported on iOS.".to_owned())
+    }
 
-            let view_controller: id = msg_send![controller_class, alloc];
-            let view_controller: id = msg_send![view_controller, init];
+    #[inline]
+    pub fn reserve_screen_edge(&self, _reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        Err("Reserving screen space is not supported on iOS.".to_owned())
+    }
 
-            let _: () = msg_send![window, setRootViewController:view_controller];
+    #[inline]
+    pub fn set_menu(&self, _menu: Option<::menu::Menu>) {
+        // N/A
+    }
 
-            let state = Box::new(DelegateState::new(window, view_controller, ptr::null_mut(), size, scale as f64));
-            let state_ptr: *mut DelegateState = mem::transmute(state);
-            this.set_ivar("winitState", state_ptr as *mut c_void);
+    #[inline]
+    pub fn set_accessibility_adapter(&self, _adapter: Option<::accessibility::AccessibilityAdapter>) {
+        // N/A
+    }
 
-            // The `UIView` is setup in `Window::new` which gets `longjmp`'ed to here.
-            // This makes it easier to configure the specific `UIView` type.
-            let _: () = msg_send![this, performSelector:sel!(postLaunch:) withObject:nil afterDelay:0.0];
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, visible: bool) {
+        unsafe {
+            let keyboard_view = self.delegate_state.keyboard_view;
+            if visible {
+                let _: BOOL = msg_send![keyboard_view, becomeFirstResponder];
+            } else {
+                let _: BOOL = msg_send![keyboard_view, resignFirstResponder];
+            }
         }
-        YES
     }
 
-    extern fn post_launch(_: &Object, _: Sel, _: id) {
-        unsafe { longjmp(mem::transmute_copy(&mut JMPBUF), 1); }
+    #[inline]
+    pub fn capture_frame(&self) -> Option<::CapturedFrame> {
+        None
     }
 
-    extern fn did_become_active(this: &Object, _: Sel, _: id) {
+    #[inline]
+    pub fn is_frontmost(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn get_current_monitor(&self) -> RootMonitorId {
         unsafe {
-            let events_queue: *mut c_void = *this.get_ivar("eventsQueue");
-            let events_queue = &*(events_queue as *const RefCell<VecDeque<Event>>);
-            events_queue.borrow_mut().push_back(Event::WindowEvent {
-                window_id: RootEventId(WindowId),
-                event: WindowEvent::Focused(true),
-            });
+            let uiscreen: id = msg_send![self.delegate_state.window, screen];
+            RootMonitorId { inner: MonitorId::retain(uiscreen) }
         }
     }
 
-    extern fn will_resign_active(this: &Object, _: Sel, _: id) {
+    #[inline]
+    pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
+        get_available_monitors()
+    }
+
+    #[inline]
+    pub fn get_primary_monitor(&self) -> MonitorId {
+        MonitorId::main_screen()
+    }
+
+    #[inline]
+    pub fn set_prefers_status_bar_hidden(&self, hidden: bool) {
         unsafe {
-            let events_queue: *mut c_void = *this.get_ivar("eventsQueue");
-            let events_queue = &*(events_queue as *const RefCell<VecDeque<Event>>);
-            events_queue.borrow_mut().push_back(Event::WindowEvent {
-                window_id: RootEventId(WindowId),
-                event: WindowEvent::Focused(false),
-            });
+            let controller = &mut *self.delegate_state.controller;
+            controller.set_ivar("prefersStatusBarHidden", bool_to_objc(hidden));
+            let _: () = msg_send![controller, setNeedsStatusBarAppearanceUpdate];
         }
     }
 
-    extern fn will_enter_foreground(this: &Object, _: Sel, _: id) {
+    #[inline]
+    pub fn set_supported_orientations(&self, orientations: &[Orientation]) {
         unsafe {
-            let events_queue: *mut c_void = *this.get_ivar("eventsQueue");
-            let events_queue = &*(events_queue as *const RefCell<VecDeque<Event>>);
-            events_queue.borrow_mut().push_back(Event::Suspended(false));
+            let controller = &mut *self.delegate_state.controller;
+            controller.set_ivar("supportedInterfaceOrientations", orientation_mask(orientations));
+            let _: () = msg_send![class!(UIViewController), attemptRotationToDeviceOrientation];
         }
     }
 
-    extern fn did_enter_background(this: &Object, _: Sel, _: id) {
+    #[inline]
+    pub fn set_prefers_home_indicator_hidden(&self, hidden: bool) {
         unsafe {
-            let events_queue: *mut c_void = *this.get_ivar("eventsQueue");
-            let events_queue = &*(events_queue as *const RefCell<VecDeque<Event>>);
-            events_queue.borrow_mut().push_back(Event::Suspended(true));
+            let controller = &mut *self.delegate_state.controller;
+            controller.set_ivar("prefersHomeIndicatorAutoHidden", bool_to_objc(hidden));
+            // This requires iOS 11, which is also when the home indicator was introduced.
+            let responds_to_update: BOOL = msg_send![
+                controller,
+                respondsToSelector:sel!(setNeedsUpdateOfHomeIndicatorAutoHidden)
+            ];
+            if responds_to_update == YES {
+                let _: () = msg_send![controller, setNeedsUpdateOfHomeIndicatorAutoHidden];
+            }
         }
     }
 
-    extern fn will_terminate(this: &Object, _: Sel, _: id) {
+    #[inline]
+    pub fn id(&self) -> WindowId {
+        WindowId(self.delegate_state.window as usize)
+    }
+}
+
+fn create_delegate_class() {
+    extern fn did_finish_launching(_: &mut Object, _: Sel, _: id, _: id) -> BOOL {
+        unsafe { app_state::did_finish_launching() };
+        YES
+    }
+
+    extern fn did_become_active(_: &Object, _: Sel, _: id) {
+        app_state::send_event(Event::ApplicationActivated);
+        app_state::send_event_to_all_windows(WindowEvent::Focused(true));
+    }
+
+    extern fn will_resign_active(_: &Object, _: Sel, _: id) {
+        app_state::send_event(Event::ApplicationDeactivated);
+        app_state::send_event_to_all_windows(WindowEvent::Focused(false));
+    }
+
+    extern fn will_enter_foreground(_: &Object, _: Sel, _: id) {
+        app_state::send_event(Event::Suspended(false));
+    }
+
+    extern fn did_enter_background(_: &Object, _: Sel, _: id) {
+        app_state::send_event(Event::Suspended(true));
+    }
+
+    extern fn will_terminate(_: &Object, _: Sel, _: id) {
+        app_state::send_event_to_all_windows(WindowEvent::Destroyed);
+    }
+
+    let ui_responder = class!(UIResponder);
+    let mut decl = ClassDecl::new("AppDelegate", ui_responder).expect("Failed to declare class `AppDelegate`");
+
+    unsafe {
+        decl.add_method(sel!(application:didFinishLaunchingWithOptions:),
+                        did_finish_launching as extern fn(&mut Object, Sel, id, id) -> BOOL);
+
+        decl.add_method(sel!(applicationDidBecomeActive:),
+                        did_become_active as extern fn(&Object, Sel, id));
+
+        decl.add_method(sel!(applicationWillResignActive:),
+                        will_resign_active as extern fn(&Object, Sel, id));
+
+        decl.add_method(sel!(applicationWillEnterForeground:),
+                        will_enter_foreground as extern fn(&Object, Sel, id));
+
+        decl.add_method(sel!(applicationDidEnterBackground:),
+                        did_enter_background as extern fn(&Object, Sel, id));
+
+        decl.add_method(sel!(applicationWillTerminate:),
+                        will_terminate as extern fn(&Object, Sel, id));
+
+        decl.register();
+    }
+}
+
+// A class whose `wakeup` class method sends `Awakened`, for `EventsLoopProxy` to call on the main
+// thread.
+fn create_wakeup_class() -> &'static Class {
+    extern fn wakeup(_: &Class, _: Sel) {
+        app_state::send_event(Event::Awakened);
+    }
+
+    if let Some(class) = Class::get("WinitWakeup") {
+        return class;
+    }
+
+    let ns_object = class!(NSObject);
+    let mut decl = ClassDecl::new("WinitWakeup", ns_object).expect("Failed to declare class `WinitWakeup`");
+
+    unsafe {
+        decl.add_class_method(sel!(wakeup),
+                              wakeup as extern fn(&Class, Sel));
+    }
+
+    decl.register()
+}
+
+// Returns the state of the window the view controller belongs to, or `None` if the window has been
+// dropped.
+unsafe fn get_delegate_state(controller: &Object) -> Option<&DelegateState> {
+    let state: *mut c_void = *controller.get_ivar("winitState");
+    (state as *const DelegateState).as_ref()
+}
+
+// The root view controller of each window. It decides whether the status bar and home indicator
+// are shown and which orientations are supported, reports the view being laid out again, e.g. when
+// the device is rotated, and receives the touches and keyboard notifications of its window.
+fn create_view_controller_class() -> &'static Class {
+    extern fn prefers_status_bar_hidden(this: &Object, _: Sel) -> BOOL {
+        unsafe { *this.get_ivar("prefersStatusBarHidden") }
+    }
+
+    extern fn prefers_home_indicator_auto_hidden(this: &Object, _: Sel) -> BOOL {
+        unsafe { *this.get_ivar("prefersHomeIndicatorAutoHidden") }
+    }
+
+    extern fn supported_interface_orientations(this: &Object, _: Sel) -> usize {
         unsafe {
-            let events_queue: *mut c_void = *this.get_ivar("eventsQueue");
-            let events_queue = &*(events_queue as *const RefCell<VecDeque<Event>>);
-            // push event to the front to garantee that we'll process it
-            // immidiatly after jump
-            events_queue.borrow_mut().push_front(Event::WindowEvent {
-                window_id: RootEventId(WindowId),
-                event: WindowEvent::Destroyed,
-            });
-            longjmp(mem::transmute_copy(&mut JMPBUF), 1);
+            let mask: usize = *this.get_ivar("supportedInterfaceOrientations");
+            if mask == 0 {
+                let superclass = class!(UIViewController);
+                msg_send![super(this, superclass), supportedInterfaceOrientations]
+            } else {
+                mask
+            }
+        }
+    }
+
+    extern fn view_did_layout_subviews(this: &Object, _: Sel) {
+        unsafe {
+            let superclass = class!(UIViewController);
+            let _: () = msg_send![super(this, superclass), viewDidLayoutSubviews];
+
+            let state = match get_delegate_state(this) {
+                Some(state) => state,
+                None => return,
+            };
+
+            state.layout_view();
+            let size = state.get_view_size();
+            if size != state.size.get() {
+                state.size.set(size);
+                app_state::send_event(Event::WindowEvent {
+                    window_id: state.id(),
+                    event: WindowEvent::Resized(size),
+                });
+            }
+            let orientation = get_interface_orientation();
+            if orientation.is_some() && orientation != state.orientation.get() {
+                state.orientation.set(orientation);
+                app_state::send_event(Event::WindowEvent {
+                    window_id: state.id(),
+                    event: WindowEvent::OrientationChanged(orientation.unwrap()),
+                });
+            }
+            let insets = state.get_safe_area_insets();
+            if insets != state.safe_area_insets.get() {
+                state.safe_area_insets.set(insets);
+                app_state::send_event(Event::WindowEvent {
+                    window_id: state.id(),
+                    event: WindowEvent::SafeAreaChanged(insets),
+                });
+            }
         }
     }
 
     extern fn handle_touches(this: &Object, _: Sel, touches: id, _:id) {
         unsafe {
-            let events_queue: *mut c_void = *this.get_ivar("eventsQueue");
-            let events_queue = &*(events_queue as *const RefCell<VecDeque<Event>>);
+            let state = match get_delegate_state(this) {
+                Some(state) => state,
+                None => return,
+            };
 
             let touches_enum: id = msg_send![touches, objectEnumerator];
 
@@ -611,14 +1157,44 @@ fn create_delegate_class() {
                 }
                 let location: CGPoint = msg_send![touch, locationInView:nil];
                 let touch_id = touch as u64;
+                // `type` requires iOS 9. Before that, every touch was a finger on the screen.
+                let mut uitouch_type = 0;
+                let type_sel = Sel::register("type");
+                let responds_to_type: BOOL = msg_send![touch, respondsToSelector:type_sel];
+                if responds_to_type == YES {
+                    uitouch_type = (*touch).send_message(type_sel, ()).unwrap_or(0);
+                }
                 let phase: i32 = msg_send![touch, phase];
+                // `force` requires iOS 9, and is only meaningful on devices with 3D Touch.
+                let mut force = None;
+                let responds_to_force: BOOL = msg_send![touch, respondsToSelector:sel!(force)];
+                if responds_to_force == YES {
+                    let max_possible_force: CGFloat = msg_send![touch, maximumPossibleForce];
+                    if max_possible_force > 0.0 {
+                        let touch_force: CGFloat = msg_send![touch, force];
+                        force = Some(Force::Calibrated {
+                            force: touch_force as f64,
+                            max_possible_force: max_possible_force as f64,
+                        });
+                    }
+                }
+                // `majorRadius` requires iOS 8.
+                let mut contact_size = None;
+                let responds_to_radius: BOOL = msg_send![touch, respondsToSelector:sel!(majorRadius)];
+                if responds_to_radius == YES {
+                    let major_radius: CGFloat = msg_send![touch, majorRadius];
+                    let diameter = 2.0 * major_radius as f64;
+                    contact_size = Some((diameter, diameter).into());
+                }
 
-                events_queue.borrow_mut().push_back(Event::WindowEvent {
-                    window_id: RootEventId(WindowId),
+                app_state::send_event(Event::WindowEvent {
+                    window_id: state.id(),
                     event: WindowEvent::Touch(Touch {
-                        device_id: DEVICE_ID,
+                        device_id: ::DeviceId(DeviceId { uitouch_type }),
                         id: touch_id,
                         location: (location.x as f64, location.y as f64).into(),
+                        force,
+                        contact_size,
                         phase: match phase {
                             0 => TouchPhase::Started,
                             1 => TouchPhase::Moved,
@@ -633,28 +1209,78 @@ fn create_delegate_class() {
         }
     }
 
-    let ui_responder = class!(UIResponder);
-    let mut decl = ClassDecl::new("AppDelegate", ui_responder).expect("Failed to declare class `AppDelegate`");
+    extern fn keyboard_will_show(this: &Object, _: Sel, notification: id) {
+        unsafe {
+            let state = match get_delegate_state(this) {
+                Some(state) => state,
+                None => return,
+            };
+            // Every window hears about the keyboard, but it's only shown for the key window.
+            let is_key_window: BOOL = msg_send![state.window, isKeyWindow];
+            if is_key_window == NO {
+                return;
+            }
 
-    unsafe {
-        decl.add_method(sel!(application:didFinishLaunchingWithOptions:),
-                        did_finish_launching as extern fn(&mut Object, Sel, id, id) -> BOOL);
+            let user_info: id = msg_send![notification, userInfo];
+            let frame: id = msg_send![user_info, objectForKey:UIKeyboardFrameEndUserInfoKey];
+            let frame: CGRect = msg_send![frame, CGRectValue];
+            // The frame is in screen coordinates, which only match the view's if the device
+            // hasn't been rotated.
+            let frame: CGRect = msg_send![state.view, convertRect:frame fromView:nil];
+
+            app_state::send_event(Event::WindowEvent {
+                window_id: state.id(),
+                event: WindowEvent::VirtualKeyboardVisibilityChanged {
+                    visible: true,
+                    position: (frame.origin.x as f64, frame.origin.y as f64).into(),
+                    size: (frame.size.width as f64, frame.size.height as f64).into(),
+                },
+            });
+        }
+    }
 
-        decl.add_method(sel!(applicationDidBecomeActive:),
-                        did_become_active as extern fn(&Object, Sel, id));
+    extern fn keyboard_will_hide(this: &Object, _: Sel, _: id) {
+        unsafe {
+            let state = match get_delegate_state(this) {
+                Some(state) => state,
+                None => return,
+            };
+            let is_key_window: BOOL = msg_send![state.window, isKeyWindow];
+            if is_key_window == NO {
+                return;
+            }
 
-        decl.add_method(sel!(applicationWillResignActive:),
-                        will_resign_active as extern fn(&Object, Sel, id));
+            app_state::send_event(Event::WindowEvent {
+                window_id: state.id(),
+                event: WindowEvent::VirtualKeyboardVisibilityChanged {
+                    visible: false,
+                    position: (0.0, 0.0).into(),
+                    size: (0.0, 0.0).into(),
+                },
+            });
+        }
+    }
 
-        decl.add_method(sel!(applicationWillEnterForeground:),
-                        will_enter_foreground as extern fn(&Object, Sel, id));
+    if let Some(class) = Class::get("WinitViewController") {
+        return class;
+    }
 
-        decl.add_method(sel!(applicationDidEnterBackground:),
-                        did_enter_background as extern fn(&Object, Sel, id));
+    let ui_view_controller = class!(UIViewController);
+    let mut decl = ClassDecl::new("WinitViewController", ui_view_controller)
+        .expect("Failed to declare class `WinitViewController`");
 
-        decl.add_method(sel!(applicationWillTerminate:),
-                        will_terminate as extern fn(&Object, Sel, id));
+    unsafe {
+        decl.add_method(sel!(prefersStatusBarHidden),
+                        prefers_status_bar_hidden as extern fn(&Object, Sel) -> BOOL);
 
+        decl.add_method(sel!(prefersHomeIndicatorAutoHidden),
+                        prefers_home_indicator_auto_hidden as extern fn(&Object, Sel) -> BOOL);
+
+        decl.add_method(sel!(supportedInterfaceOrientations),
+                        supported_interface_orientations as extern fn(&Object, Sel) -> usize);
+
+        decl.add_method(sel!(viewDidLayoutSubviews),
+                        view_did_layout_subviews as extern fn(&Object, Sel));
 
         decl.add_method(sel!(touchesBegan:withEvent:),
                         handle_touches as extern fn(this: &Object, _: Sel, _: id, _:id));
@@ -668,14 +1294,123 @@ fn create_delegate_class() {
         decl.add_method(sel!(touchesCancelled:withEvent:),
                         handle_touches as extern fn(this: &Object, _: Sel, _: id, _:id));
 
+        decl.add_method(sel!(keyboardWillShow:),
+                        keyboard_will_show as extern fn(&Object, Sel, id));
 
-        decl.add_method(sel!(postLaunch:),
-                        post_launch as extern fn(&Object, Sel, id));
+        decl.add_method(sel!(keyboardWillHide:),
+                        keyboard_will_hide as extern fn(&Object, Sel, id));
 
         decl.add_ivar::<*mut c_void>("winitState");
-        decl.add_ivar::<*mut c_void>("eventsQueue");
+        decl.add_ivar::<BOOL>("prefersStatusBarHidden");
+        decl.add_ivar::<BOOL>("prefersHomeIndicatorAutoHidden");
+        decl.add_ivar::<usize>("supportedInterfaceOrientations");
+    }
 
-        decl.register();
+    decl.register()
+}
+
+// A view that isn't displayed, but accepts text input. UIKit only shows the keyboard when the first
+// responder conforms to `UIKeyInput`, which we can't expect of the root view.
+fn create_keyboard_view_class() -> &'static Class {
+    extern fn can_become_first_responder(_: &Object, _: Sel) -> BOOL {
+        YES
+    }
+
+    extern fn has_text(_: &Object, _: Sel) -> BOOL {
+        NO
+    }
+
+    extern fn insert_text(this: &Object, _: Sel, text: id) {
+        unsafe {
+            let text = CStr::from_ptr(text.UTF8String()).to_string_lossy();
+            let window_id = get_keyboard_view_window_id(this);
+            for character in text.chars() {
+                app_state::send_event(Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::ReceivedCharacter(character),
+                });
+            }
+        }
+    }
+
+    extern fn delete_backward(this: &Object, _: Sel) {
+        unsafe {
+            app_state::send_event(Event::WindowEvent {
+                window_id: get_keyboard_view_window_id(this),
+                event: WindowEvent::ReceivedCharacter('\u{8}'),
+            });
+        }
+    }
+
+    unsafe fn get_keyboard_view_window_id(keyboard_view: &Object) -> RootEventId {
+        let window: id = msg_send![keyboard_view, window];
+        RootEventId(WindowId(window as usize))
+    }
+
+    if let Some(class) = Class::get("WinitKeyboardView") {
+        return class;
+    }
+
+    let ui_view = class!(UIView);
+    let mut decl = ClassDecl::new("WinitKeyboardView", ui_view).expect("Failed to declare class `WinitKeyboardView`");
+
+    unsafe {
+        decl.add_method(sel!(canBecomeFirstResponder),
+                        can_become_first_responder as extern fn(&Object, Sel) -> BOOL);
+
+        decl.add_method(sel!(hasText),
+                        has_text as extern fn(&Object, Sel) -> BOOL);
+
+        decl.add_method(sel!(insertText:),
+                        insert_text as extern fn(&Object, Sel, id));
+
+        decl.add_method(sel!(deleteBackward),
+                        delete_backward as extern fn(&Object, Sel));
+    }
+
+    if let Some(protocol) = Protocol::get("UIKeyInput") {
+        decl.add_protocol(protocol);
+    }
+
+    decl.register()
+}
+
+fn bool_to_objc(value: bool) -> BOOL {
+    if value { YES } else { NO }
+}
+
+// The values of `UIInterfaceOrientation`. In landscape, UIKit names the orientation after the side
+// the home button is on.
+const UI_INTERFACE_ORIENTATION_PORTRAIT: isize = 1;
+const UI_INTERFACE_ORIENTATION_PORTRAIT_UPSIDE_DOWN: isize = 2;
+const UI_INTERFACE_ORIENTATION_LANDSCAPE_RIGHT: isize = 3;
+const UI_INTERFACE_ORIENTATION_LANDSCAPE_LEFT: isize = 4;
+
+fn to_ui_interface_orientation(orientation: Orientation) -> isize {
+    match orientation {
+        Orientation::Portrait => UI_INTERFACE_ORIENTATION_PORTRAIT,
+        Orientation::PortraitUpsideDown => UI_INTERFACE_ORIENTATION_PORTRAIT_UPSIDE_DOWN,
+        Orientation::LandscapeLeft => UI_INTERFACE_ORIENTATION_LANDSCAPE_LEFT,
+        Orientation::LandscapeRight => UI_INTERFACE_ORIENTATION_LANDSCAPE_RIGHT,
+    }
+}
+
+// A `UIInterfaceOrientationMask`, which is 0 for an empty list, so that UIKit's default is kept.
+fn orientation_mask(orientations: &[Orientation]) -> usize {
+    orientations
+        .iter()
+        .fold(0, |mask, &orientation| mask | 1 << to_ui_interface_orientation(orientation))
+}
+
+unsafe fn get_interface_orientation() -> Option<Orientation> {
+    let app: id = msg_send![class!(UIApplication), sharedApplication];
+    let orientation: isize = msg_send![app, statusBarOrientation];
+    match orientation {
+        UI_INTERFACE_ORIENTATION_PORTRAIT => Some(Orientation::Portrait),
+        UI_INTERFACE_ORIENTATION_PORTRAIT_UPSIDE_DOWN => Some(Orientation::PortraitUpsideDown),
+        UI_INTERFACE_ORIENTATION_LANDSCAPE_LEFT => Some(Orientation::LandscapeLeft),
+        UI_INTERFACE_ORIENTATION_LANDSCAPE_RIGHT => Some(Orientation::LandscapeRight),
+        _ => None,
     }
 }
 
@@ -686,5 +1421,76 @@ fn start_app() {
     }
 }
 
-// Constant device ID, to be removed when this backend is updated to report real device IDs.
-const DEVICE_ID: ::DeviceId = ::DeviceId(DeviceId);
+#[cfg(feature = "shell")]
+pub fn reveal_in_file_manager(_path: &::std::path::Path) -> Result<(), String> {
+    Err("There's no file manager to reveal files in on iOS.".to_owned())
+}
+
+#[cfg(feature = "shell")]
+pub fn open_with_default_app(_path: &::std::path::Path) -> Result<(), String> {
+    Err("Opening files is not possible on iOS.".to_owned())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId;
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> TrayIconId {
+        TrayIconId
+    }
+}
+
+#[cfg(feature = "tray")]
+pub struct TrayIcon;
+
+#[cfg(feature = "tray")]
+impl TrayIcon {
+    #[inline]
+    pub fn new(
+        _: &EventsLoop,
+        _: ::tray::TrayIconAttributes,
+    ) -> Result<TrayIcon, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId
+    }
+
+    #[inline]
+    pub fn set_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_tooltip(&self, _tooltip: &str) {
+        // N/A
+    }
+}
+
+#[cfg(feature = "surface")]
+pub struct Surface;
+
+#[cfg(feature = "surface")]
+impl Surface {
+    #[inline]
+    pub fn new(_: &Window) -> Result<Surface, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn native(&self) -> ::surface::NativeSurface {
+        unreachable!()
+    }
+
+    #[inline]
+    pub fn resize(&self, _: PhysicalSize) {
+        unreachable!()
+    }
+}
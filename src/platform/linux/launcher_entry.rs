@@ -0,0 +1,70 @@
+//! Badges and progress bars on the application's launcher icon, through the `LauncherEntry` D-Bus
+//! API of Unity, which KDE Plasma's task manager and the docks of GNOME Shell implement too.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// Launchers find the icon of the application from the name of its desktop file.
+fn app_uri() -> Option<String> {
+    // Set by GLib for the applications it launches from a desktop file.
+    let path = env::var_os("GIO_LAUNCHED_DESKTOP_FILE")
+        .map(PathBuf::from)
+        .or_else(|| env::current_exe().ok())?;
+    let name = path.file_stem()?.to_str()?;
+    Some(format!("application://{}.desktop", name))
+}
+
+// Quotes a string in the GVariant text format.
+fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('\'');
+    for c in string.chars() {
+        if c == '\'' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+// Emits the `Update` signal, with `properties` being a dictionary in the GVariant text format.
+// There's no D-Bus connection to send it over, so it goes through `gdbus`, which comes with GLib.
+fn update(properties: &str) {
+    let app_uri = match app_uri() {
+        Some(app_uri) => app_uri,
+        None => return,
+    };
+    let status = Command::new("gdbus")
+        .args(&["emit", "--session", "--object-path", "/"])
+        .args(&["--signal", "com.canonical.Unity.LauncherEntry.Update"])
+        .arg(quote(&app_uri))
+        .arg(properties)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    if let Err(err) = status {
+        warn!("Failed to run `gdbus`: {}", err);
+    }
+}
+
+/// Shows `label` as the count on the launcher icon. Only integers can be shown, so other labels
+/// hide the count, like `None` does.
+pub fn set_count(label: Option<&str>) {
+    match label.and_then(|label| label.trim().parse::<i64>().ok()) {
+        Some(count) => update(&format!("{{'count': <int64 {}>, 'count-visible': <true>}}", count)),
+        None => update("{'count-visible': <false>}"),
+    }
+}
+
+pub fn set_progress(progress: Option<f64>) {
+    match progress {
+        Some(progress) => {
+            let progress = progress.max(0.0).min(1.0);
+            update(&format!("{{'progress': <{:?}>, 'progress-visible': <true>}}", progress))
+        },
+        None => update("{'progress-visible': <false>}"),
+    }
+}
@@ -4,14 +4,17 @@ use std::collections::VecDeque;
 use std::{env, mem};
 use std::ffi::CStr;
 use std::os::raw::*;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use parking_lot::Mutex;
 use sctk::reexports::client::ConnectError;
 
 use {
+    ActivationPolicy,
     CreationError,
     EventsLoopClosed,
+    EventsLoopCreationError,
     Icon,
     MouseCursor,
     ControlFlow,
@@ -21,9 +24,16 @@ use dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
 use window::MonitorId as RootMonitorId;
 use self::x11::{XConnection, XError};
 use self::x11::ffi::XVisualInfo;
+#[cfg(feature = "shell")]
+pub use self::shell::{open_with_default_app, reveal_in_file_manager};
+#[cfg(feature = "surface")]
+pub use self::surface::Surface;
 pub use self::x11::XNotSupported;
 
 mod dlopen;
+mod launcher_entry;
+mod shell;
+mod surface;
 pub mod wayland;
 pub mod x11;
 
@@ -45,12 +55,20 @@ pub struct PlatformSpecificWindowBuilderAttributes {
     pub class: Option<(String, String)>,
     pub override_redirect: bool,
     pub x11_window_type: x11::util::WindowType,
+    pub dynamic_x11_scaling: bool,
+    pub hidpi_cursors: bool,
+    pub embed_parent: Option<c_ulong>,
 }
 
 lazy_static!(
     pub static ref X11_BACKEND: Mutex<Result<Arc<XConnection>, XNotSupported>> = {
         Mutex::new(XConnection::new(Some(x_error_callback)).map(Arc::new))
     };
+    // The connections of the `EventsLoop`s created with `EventsLoopExt::new_any_thread`, so that
+    // `x_error_callback` can find the one an error belongs to.
+    static ref SEPARATE_X11_CONNECTIONS: Mutex<Vec<Weak<XConnection>>> = Mutex::new(Vec::new());
+    // Set with `os::unix::set_x11_error_hook`.
+    pub static ref X11_ERROR_HOOK: Mutex<Option<Box<Fn(&XError) + Send>>> = Mutex::new(None);
 );
 
 pub enum Window {
@@ -70,6 +88,73 @@ pub enum DeviceId {
     Wayland(wayland::DeviceId),
 }
 
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId::X(x11::DeviceId::dummy())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FocusSnapshot {
+    X(x11::FocusSnapshot),
+    // Wayland clients can neither query nor take the keyboard focus.
+    Wayland,
+}
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        match *self {
+            FocusSnapshot::X(ref snapshot) => snapshot.focused_window().map(WindowId::X),
+            FocusSnapshot::Wayland => None,
+        }
+    }
+}
+
+// Wayland IDs are told apart from X11 ones by the highest bit, which neither X11 IDs nor addresses
+// use.
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+const WAYLAND_RAW_ID_BIT: u64 = 1 << 63;
+
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        match self {
+            WindowId::X(id) => id.into_raw(),
+            WindowId::Wayland(id) => id.into_raw() | WAYLAND_RAW_ID_BIT,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        if raw & WAYLAND_RAW_ID_BIT != 0 {
+            WindowId::Wayland(wayland::WindowId::from_raw(raw & !WAYLAND_RAW_ID_BIT))
+        } else {
+            WindowId::X(x11::WindowId::from_raw(raw))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        match self {
+            DeviceId::X(id) => id.into_raw(),
+            DeviceId::Wayland(id) => id.into_raw() | WAYLAND_RAW_ID_BIT,
+        }
+    }
+
+    pub fn from_raw(raw: u64) -> DeviceId {
+        if raw & WAYLAND_RAW_ID_BIT != 0 {
+            DeviceId::Wayland(wayland::DeviceId::from_raw(raw & !WAYLAND_RAW_ID_BIT))
+        } else {
+            DeviceId::X(x11::DeviceId::from_raw(raw))
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum MonitorId {
     X(x11::MonitorId),
@@ -116,6 +201,22 @@ impl MonitorId {
             &MonitorId::Wayland(ref m) => m.get_hidpi_factor() as f64,
         }
     }
+
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        match self {
+            &MonitorId::X(ref m) => m.get_work_area(),
+            &MonitorId::Wayland(ref m) => m.get_work_area(),
+        }
+    }
+
+    #[inline]
+    pub fn get_taskbar_edge(&self) -> Option<::Edge> {
+        match self {
+            &MonitorId::X(ref m) => m.get_taskbar_edge(),
+            &MonitorId::Wayland(_) => None,
+        }
+    }
 }
 
 impl Window {
@@ -127,6 +228,11 @@ impl Window {
     ) -> Result<Self, CreationError> {
         match *events_loop {
             EventsLoop::Wayland(ref events_loop) => {
+                if attribs.parent.is_some() {
+                    return Err(CreationError::InvalidAttributes(
+                        "Windows can't be created in a parent window on Wayland.".to_owned(),
+                    ));
+                }
                 wayland::Window::new(events_loop, attribs).map(Window::Wayland)
             },
             EventsLoop::X(ref events_loop) => {
@@ -167,6 +273,14 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn present_ready(&self) {
+        match self {
+            &Window::X(ref w) => w.present_ready(),
+            &Window::Wayland(ref w) => w.present_ready(),
+        }
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
         match self {
@@ -184,10 +298,18 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_position(&self, position: LogicalPosition) {
+    pub fn set_outer_position(&self, position: LogicalPosition) {
+        match self {
+            &Window::X(ref w) => w.set_outer_position(position),
+            &Window::Wayland(ref w) => w.set_outer_position(position),
+        }
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, position: LogicalPosition) {
         match self {
-            &Window::X(ref w) => w.set_position(position),
-            &Window::Wayland(ref w) => w.set_position(position),
+            &Window::X(ref w) => w.set_inner_position(position),
+            &Window::Wayland(ref w) => w.set_inner_position(position),
         }
     }
 
@@ -207,6 +329,14 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        match self {
+            &Window::X(ref w) => w.get_frame_extents(),
+            &Window::Wayland(ref w) => w.get_frame_extents(),
+        }
+    }
+
     #[inline]
     pub fn set_inner_size(&self, size: LogicalSize) {
         match self {
@@ -255,6 +385,22 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, mode: ::CursorGrabMode) -> Result<(), String> {
+        match self {
+            &Window::X(ref window) => window.set_cursor_grab_mode(mode),
+            &Window::Wayland(ref window) => window.set_cursor_grab_mode(mode),
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, region: Option<::Rect>) -> Result<(), String> {
+        match self {
+            &Window::X(ref window) => window.set_cursor_confine_region(region),
+            &Window::Wayland(ref window) => window.set_cursor_confine_region(region),
+        }
+    }
+
     #[inline]
     pub fn hide_cursor(&self, hide: bool) {
         match self {
@@ -287,6 +433,94 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) {
+        match self {
+            &Window::X(ref w) => w.set_minimized(minimized),
+            &Window::Wayland(ref w) => w.set_minimized(minimized),
+        }
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_minimized(),
+            &Window::Wayland(ref w) => w.is_minimized(),
+        }
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_maximized(),
+            &Window::Wayland(ref w) => w.is_maximized(),
+        }
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_fullscreen(),
+            &Window::Wayland(ref w) => w.is_fullscreen(),
+        }
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_visible(),
+            &Window::Wayland(ref w) => w.is_visible(),
+        }
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_focused(),
+            &Window::Wayland(ref w) => w.is_focused(),
+        }
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+        match self {
+            &Window::X(ref w) => w.focus(),
+            &Window::Wayland(ref w) => w.focus(),
+        }
+    }
+
+    #[inline]
+    pub fn set_focusable(&self, focusable: bool) {
+        match self {
+            &Window::X(ref w) => w.set_focusable(focusable),
+            &Window::Wayland(ref w) => w.set_focusable(focusable),
+        }
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        match self {
+            &Window::X(ref w) => w.get_safe_area_insets(),
+            &Window::Wayland(ref w) => w.get_safe_area_insets(),
+        }
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_resizable(),
+            &Window::Wayland(ref w) => w.is_resizable(),
+        }
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_decorated(),
+            &Window::Wayland(ref w) => w.is_decorated(),
+        }
+    }
+
     #[inline]
     pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
         match self {
@@ -319,6 +553,45 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_badge_label(&self, label: Option<&str>) {
+        launcher_entry::set_count(label);
+    }
+
+    #[inline]
+    pub fn set_progress(&self, progress: Option<f64>) {
+        launcher_entry::set_progress(progress);
+    }
+
+    #[inline]
+    pub fn set_overlay_icon(&self, _icon: Option<Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_menu(&self, _menu: Option<::menu::Menu>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_accessibility_adapter(&self, _adapter: Option<::accessibility::AccessibilityAdapter>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, _visible: bool) {
+        // TODO: On Wayland, enable `zwp_text_input_v3` for the window's surface, which is what
+        // makes the compositor show its on-screen keyboard, and report the input panel's area.
+    }
+
+    #[inline]
+    pub fn capture_frame(&self) -> Option<::CapturedFrame> {
+        match self {
+            &Window::X(ref w) => w.capture_frame(),
+            &Window::Wayland(_) => None,
+        }
+    }
+
     #[inline]
     pub fn set_ime_spot(&self, position: LogicalPosition) {
         match self {
@@ -327,6 +600,64 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_ime_conversion_mode(&self, _mode: ::ImeMode) -> Result<(), String> {
+        Err("Neither XIM nor the Wayland text input protocol can set the IME conversion mode.".to_owned())
+    }
+
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, callback: Option<::HitTestCallback>) {
+        match self {
+            &Window::X(ref w) => w.set_hit_test_callback(callback),
+            &Window::Wayland(_) => (),
+        }
+    }
+
+    #[inline]
+    pub fn set_input_region(&self, region: Option<&[::Rect]>) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.set_input_region(region),
+            &Window::Wayland(ref w) => w.set_input_region(region),
+        }
+    }
+
+    #[inline]
+    pub fn set_shape(&self, shape: Option<&[::Rect]>) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.set_shape(shape),
+            &Window::Wayland(ref w) => w.set_shape(shape),
+        }
+    }
+
+    #[inline]
+    pub fn set_blur(&self, blur: bool) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.set_blur(blur),
+            &Window::Wayland(ref w) => w.set_blur(blur),
+        }
+    }
+
+    #[inline]
+    pub fn reserve_screen_edge(&self, reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        match self {
+            &Window::X(ref w) => w.reserve_screen_edge(reservation),
+            &Window::Wayland(ref w) => w.reserve_screen_edge(reservation),
+        }
+    }
+
+    #[inline]
+    pub fn is_frontmost(&self) -> bool {
+        match self {
+            &Window::X(ref w) => w.is_frontmost(),
+            &Window::Wayland(_) => false,
+        }
+    }
+
     #[inline]
     pub fn get_current_monitor(&self) -> RootMonitorId {
         match self {
@@ -362,8 +693,18 @@ unsafe extern "C" fn x_error_callback(
     display: *mut x11::ffi::Display,
     event: *mut x11::ffi::XErrorEvent,
 ) -> c_int {
-    let xconn_lock = X11_BACKEND.lock();
-    if let Ok(ref xconn) = *xconn_lock {
+    let xconn = match *X11_BACKEND.lock() {
+        Ok(ref xconn) if xconn.display == display => Some(Arc::clone(xconn)),
+        _ => None,
+    };
+    let xconn = xconn.or_else(|| {
+        SEPARATE_X11_CONNECTIONS
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|xconn| xconn.display == display)
+    });
+    if let Some(xconn) = xconn {
         let mut buf: [c_char; 1024] = mem::uninitialized();
         (xconn.xlib.XGetErrorText)(
             display,
@@ -380,7 +721,10 @@ unsafe extern "C" fn x_error_callback(
             minor_code: (*event).minor_code,
         };
 
-        eprintln!("[winit X11 error] {:#?}", error);
+        match *X11_ERROR_HOOK.lock() {
+            Some(ref hook) => hook(&error),
+            None => warn!(target: "winit::x11", "X error: {:?}", error),
+        }
 
         *xconn.latest_error.lock() = Some(error);
     }
@@ -388,6 +732,51 @@ unsafe extern "C" fn x_error_callback(
     0
 }
 
+// Neither backend implements tray icons yet, since the modern protocol (StatusNotifierItem) is
+// only reachable over D-Bus.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId;
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> TrayIconId {
+        TrayIconId
+    }
+}
+
+#[cfg(feature = "tray")]
+pub struct TrayIcon;
+
+#[cfg(feature = "tray")]
+impl TrayIcon {
+    #[inline]
+    pub fn new(
+        _: &EventsLoop,
+        _: ::tray::TrayIconAttributes,
+    ) -> Result<TrayIcon, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId
+    }
+
+    #[inline]
+    pub fn set_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_tooltip(&self, _tooltip: &str) {
+        // N/A
+    }
+}
+
 pub enum EventsLoop {
     Wayland(wayland::EventsLoop),
     X(x11::EventsLoop)
@@ -401,30 +790,43 @@ pub enum EventsLoopProxy {
 
 impl EventsLoop {
     pub fn new() -> EventsLoop {
+        EventsLoop::try_new().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_new() -> Result<EventsLoop, EventsLoopCreationError> {
+        EventsLoop::try_new_with(EventsLoop::new_x11)
+    }
+
+    pub fn try_new_any_thread() -> Result<EventsLoop, EventsLoopCreationError> {
+        EventsLoop::try_new_with(EventsLoop::new_x11_any_thread)
+    }
+
+    fn try_new_with(
+        new_x11: fn() -> Result<EventsLoop, XNotSupported>,
+    ) -> Result<EventsLoop, EventsLoopCreationError> {
         if let Ok(env_var) = env::var(BACKEND_PREFERENCE_ENV_VAR) {
-            match env_var.as_str() {
-                "x11" => {
-                    // TODO: propagate
-                    return EventsLoop::new_x11().expect("Failed to initialize X11 backend");
-                },
-                "wayland" => {
-                    return EventsLoop::new_wayland()
-                        .expect("Failed to initialize Wayland backend");
-                },
-                _ => panic!(
-                    "Unknown environment variable value for {}, try one of `x11`,`wayland`",
-                    BACKEND_PREFERENCE_ENV_VAR,
-                ),
-            }
+            return match env_var.as_str() {
+                "x11" => new_x11().map_err(|err| {
+                    EventsLoopCreationError::BackendUnavailable(
+                        format!("Failed to initialize X11 backend: {}", err)
+                    )
+                }),
+                "wayland" => EventsLoop::new_wayland().map_err(|err| {
+                    EventsLoopCreationError::BackendUnavailable(
+                        format!("Failed to initialize Wayland backend: {:?}", err)
+                    )
+                }),
+                _ => Err(EventsLoopCreationError::UnknownBackend(env_var)),
+            };
         }
 
         let wayland_err = match EventsLoop::new_wayland() {
-            Ok(event_loop) => return event_loop,
+            Ok(event_loop) => return Ok(event_loop),
             Err(err) => err,
         };
 
-        let x11_err = match EventsLoop::new_x11() {
-            Ok(event_loop) => return event_loop,
+        let x11_err = match new_x11() {
+            Ok(event_loop) => return Ok(event_loop),
             Err(err) => err,
         };
 
@@ -436,7 +838,7 @@ r#"Failed to initialize any backend!
             wayland_err,
             x11_err,
         );
-        panic!(err_string);
+        Err(EventsLoopCreationError::BackendUnavailable(err_string))
     }
 
     pub fn new_wayland() -> Result<EventsLoop, ConnectError> {
@@ -454,6 +856,18 @@ r#"Failed to initialize any backend!
             .map_err(|err| err.clone())
     }
 
+    // Unlike `new_x11`, doesn't share the connection with the other `EventsLoop`s, which would
+    // otherwise take each other's events.
+    pub fn new_x11_any_thread() -> Result<EventsLoop, XNotSupported> {
+        let xconn = Arc::new(XConnection::new(Some(x_error_callback))?);
+        {
+            let mut connections = SEPARATE_X11_CONNECTIONS.lock();
+            connections.retain(|xconn| xconn.upgrade().is_some());
+            connections.push(Arc::downgrade(&xconn));
+        }
+        Ok(EventsLoop::X(x11::EventsLoop::new(xconn)))
+    }
+
     #[inline]
     pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
         match *self {
@@ -463,7 +877,6 @@ r#"Failed to initialize any backend!
                 .map(MonitorId::Wayland)
                 .collect(),
             EventsLoop::X(ref evlp) => evlp
-                .x_connection()
                 .get_available_monitors()
                 .into_iter()
                 .map(MonitorId::X)
@@ -475,7 +888,7 @@ r#"Failed to initialize any backend!
     pub fn get_primary_monitor(&self) -> MonitorId {
         match *self {
             EventsLoop::Wayland(ref evlp) => MonitorId::Wayland(evlp.get_primary_monitor()),
-            EventsLoop::X(ref evlp) => MonitorId::X(evlp.x_connection().get_primary_monitor()),
+            EventsLoop::X(ref evlp) => MonitorId::X(evlp.get_primary_monitor()),
         }
     }
 
@@ -486,21 +899,111 @@ r#"Failed to initialize any backend!
         }
     }
 
-    pub fn poll_events<F>(&mut self, callback: F)
+    pub fn shutdown(self) {
+        match self {
+            EventsLoop::Wayland(evlp) => drop(evlp),
+            EventsLoop::X(evlp) => evlp.shutdown(),
+        }
+    }
+
+    pub fn block_session_end(&self, _reason: Option<&str>) {
+        // Neither X11 nor Wayland lets clients delay the end of the session without talking to
+        // the session manager directly.
+    }
+
+    pub fn set_activation_policy(&mut self, activation_policy: ActivationPolicy) {
+        match *self {
+            // Wayland has no way for clients to keep their surfaces out of the taskbar.
+            EventsLoop::Wayland(_) => (),
+            EventsLoop::X(ref mut evlp) => evlp.set_activation_policy(activation_policy),
+        }
+    }
+
+    pub fn reserve_events(&mut self, capacity: usize) {
+        match *self {
+            EventsLoop::Wayland(ref evlp) => evlp.reserve_events(capacity),
+            EventsLoop::X(ref evlp) => evlp.reserve_events(capacity),
+        }
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        match *self {
+            EventsLoop::Wayland(_) => FocusSnapshot::Wayland,
+            EventsLoop::X(ref evlp) => FocusSnapshot::X(evlp.focus_snapshot()),
+        }
+    }
+
+    pub fn restore_focus(&self, snapshot: &FocusSnapshot) {
+        match (self, snapshot) {
+            (&EventsLoop::X(ref evlp), &FocusSnapshot::X(ref snapshot)) => evlp.restore_focus(snapshot),
+            _ => (),
+        }
+    }
+
+    pub fn keyboard_layouts(&self) -> Vec<::LayoutInfo> {
+        match *self {
+            // The keymap is handled by SCTK, which doesn't expose the names of its layouts.
+            EventsLoop::Wayland(_) => Vec::new(),
+            EventsLoop::X(ref evlp) => evlp.keyboard_layouts(),
+        }
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        match *self {
+            // Wayland doesn't tell the input devices apart.
+            EventsLoop::Wayland(_) => Vec::new(),
+            EventsLoop::X(ref evlp) => evlp.device_infos(),
+        }
+    }
+
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        match *self {
+            // Wayland doesn't let clients know how their surfaces are stacked.
+            EventsLoop::Wayland(_) => Vec::new(),
+            EventsLoop::X(ref evlp) => evlp
+                .get_window_stacking_order()
+                .into_iter()
+                .map(WindowId::X)
+                .collect(),
+        }
+    }
+
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        match *self {
+            EventsLoop::Wayland(_) => None,
+            EventsLoop::X(ref evlp) => {
+                let xconn = evlp.x_connection();
+                if xconn.xss.is_some() {
+                    Some(IdleTimeQuery(xconn.clone()))
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+    // Unsupported, so `poll_events_vsync_aligned` falls back to its timer. X11 would need the
+    // Present extension, which x11-dl doesn't bind, and Wayland frame callbacks only fire for
+    // commits, which the renderer makes rather than us.
+    pub fn wait_for_vblank(&mut self) -> bool {
+        false
+    }
+
+    pub fn poll_events<F>(&self, callback: F)
         where F: FnMut(::Event)
     {
         match *self {
-            EventsLoop::Wayland(ref mut evlp) => evlp.poll_events(callback),
-            EventsLoop::X(ref mut evlp) => evlp.poll_events(callback)
+            EventsLoop::Wayland(ref evlp) => evlp.poll_events(callback),
+            EventsLoop::X(ref evlp) => evlp.poll_events(callback)
         }
     }
 
-    pub fn run_forever<F>(&mut self, callback: F)
+    pub fn run_forever<F>(&self, callback: F)
         where F: FnMut(::Event) -> ControlFlow
     {
         match *self {
-            EventsLoop::Wayland(ref mut evlp) => evlp.run_forever(callback),
-            EventsLoop::X(ref mut evlp) => evlp.run_forever(callback)
+            EventsLoop::Wayland(ref evlp) => evlp.run_forever(callback),
+            EventsLoop::X(ref evlp) => evlp.run_forever(callback)
         }
     }
 
@@ -519,6 +1022,14 @@ r#"Failed to initialize any backend!
             EventsLoop::X(ref ev) => Some(ev.x_connection()),
         }
     }
+
+    #[inline]
+    pub fn x11(&self) -> Option<&x11::EventsLoop> {
+        match *self {
+            EventsLoop::Wayland(_) => None,
+            EventsLoop::X(ref ev) => Some(ev),
+        }
+    }
 }
 
 impl EventsLoopProxy {
@@ -529,3 +1040,14 @@ impl EventsLoopProxy {
         }
     }
 }
+
+/// Queries how long the user has been idle for, from any thread.
+///
+/// Wayland doesn't let clients find out, so this is only available on X11.
+pub struct IdleTimeQuery(Arc<XConnection>);
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<Duration> {
+        self.0.get_idle_time()
+    }
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "shell")]
+
+use std::fmt::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// Percent-encodes an absolute path into a `file://` URI.
+fn file_uri(path: &Path) -> String {
+    let mut uri = "file://".to_owned();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            write!(uri, "%{:02X}", byte).unwrap();
+        }
+    }
+    uri
+}
+
+fn run(command: &mut Command, name: &str) -> Result<(), String> {
+    let status = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| format!("Failed to run `{}`: {}", name, err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{}` failed: {}", name, status))
+    }
+}
+
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    let path = path.canonicalize()
+        .map_err(|err| format!("Failed to resolve {:?}: {}", path, err))?;
+    // Implemented by Nautilus, Dolphin, Nemo, Caja, Thunar, and most other file managers.
+    let shown = run(
+        Command::new("dbus-send")
+            .arg("--session")
+            .arg("--print-reply")
+            .arg("--dest=org.freedesktop.FileManager1")
+            .arg("/org/freedesktop/FileManager1")
+            .arg("org.freedesktop.FileManager1.ShowItems")
+            .arg(format!("array:string:{}", file_uri(&path)))
+            .arg("string:"),
+        "dbus-send",
+    );
+    match (shown, path.parent()) {
+        (Ok(()), _) => Ok(()),
+        // The file can't be selected without the interface, but its directory can still be shown.
+        (Err(_), Some(parent)) => open_with_default_app(parent),
+        (Err(err), None) => Err(err),
+    }
+}
+
+pub fn open_with_default_app(path: &Path) -> Result<(), String> {
+    run(Command::new("xdg-open").arg(path), "xdg-open")
+}
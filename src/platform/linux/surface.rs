@@ -0,0 +1,58 @@
+#![cfg(feature = "surface")]
+
+use std::os::raw::c_void;
+
+use wayland_client::egl::WlEglSurface;
+
+use {CreationError, PhysicalSize};
+use surface::NativeSurface;
+use super::Window;
+
+pub enum Surface {
+    X(NativeSurface),
+    Wayland {
+        display: *mut c_void,
+        surface: *mut c_void,
+        egl_surface: WlEglSurface,
+    },
+}
+
+impl Surface {
+    pub fn new(window: &Window) -> Result<Surface, CreationError> {
+        match *window {
+            Window::X(ref w) => Ok(Surface::X(NativeSurface::Xlib {
+                display: w.get_xlib_display(),
+                screen: w.get_xlib_screen_id(),
+                window: w.get_xlib_window(),
+            })),
+            Window::Wayland(ref w) => {
+                let (width, height): (u32, u32) = w.get_inner_size()
+                    .map(|size| size.to_physical(w.hidpi_factor() as f64).into())
+                    .unwrap_or((0, 0));
+                Ok(Surface::Wayland {
+                    display: w.get_display().c_ptr() as *mut _,
+                    surface: w.get_surface().c_ptr() as *mut _,
+                    egl_surface: WlEglSurface::new(w.get_surface(), width as i32, height as i32),
+                })
+            },
+        }
+    }
+
+    pub fn native(&self) -> NativeSurface {
+        match *self {
+            Surface::X(native) => native,
+            Surface::Wayland { display, surface, ref egl_surface } => NativeSurface::Wayland {
+                display,
+                surface,
+                egl_window: egl_surface.ptr() as *mut _,
+            },
+        }
+    }
+
+    pub fn resize(&self, size: PhysicalSize) {
+        if let Surface::Wayland { ref egl_surface, .. } = *self {
+            let (width, height): (u32, u32) = size.into();
+            egl_surface.resize(width as i32, height as i32, 0, 0);
+        }
+    }
+}
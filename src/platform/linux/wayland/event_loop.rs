@@ -1,6 +1,6 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
-use std::fmt;
+use std::{fmt, io, mem};
 use std::sync::{Arc, Mutex, Weak};
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -18,6 +18,13 @@ use sctk::reexports::client::protocol::{wl_keyboard, wl_output, wl_pointer, wl_r
 
 use sctk::reexports::client::protocol::wl_display::RequestsTrait as DisplayRequests;
 
+use wayland_protocols::unstable::pointer_constraints::v1::client::zwp_pointer_constraints_v1::ZwpPointerConstraintsV1;
+use wayland_protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1;
+use wayland_protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_v1::{
+    self,
+    ZwpRelativePointerV1,
+};
+
 pub struct EventsLoopSink {
     buffer: VecDeque<::Event>,
 }
@@ -40,15 +47,6 @@ impl EventsLoopSink {
     pub fn send_raw_event(&mut self, evt: ::Event) {
         self.buffer.push_back(evt);
     }
-
-    fn empty_with<F>(&mut self, callback: &mut F)
-    where
-        F: FnMut(::Event),
-    {
-        for evt in self.buffer.drain(..) {
-            callback(evt)
-        }
-    }
 }
 
 pub struct EventsLoop {
@@ -56,8 +54,12 @@ pub struct EventsLoop {
     pub evq: RefCell<EventQueue>,
     // our sink, shared with some handlers, buffering the events
     sink: Arc<Mutex<EventsLoopSink>>,
+    // the sink's previous buffer, swapped back in when emptying it so that its memory is reused
+    spare_buffer: RefCell<VecDeque<::Event>>,
     // Whether or not there is a pending `Awakened` event to be emitted.
     pending_wakeup: Arc<AtomicBool>,
+    // Whether `ConnectionLost` has been emitted, after which nothing is dispatched anymore
+    connection_lost: Cell<bool>,
     // The window store
     pub store: Arc<Mutex<WindowStore>>,
     // the env
@@ -68,6 +70,10 @@ pub struct EventsLoop {
     pub display: Arc<Display>,
     // The list of seats
     pub seats: Arc<Mutex<Vec<(u32, Proxy<wl_seat::WlSeat>)>>>,
+    // The pointers of all seats, which windows lock when grabbing the cursor
+    pub pointers: Arc<Mutex<Vec<Proxy<wl_pointer::WlPointer>>>>,
+    // The pointer constraints global, if the compositor supports it
+    pub pointer_constraints: Arc<Mutex<Option<Proxy<ZwpPointerConstraintsV1>>>>,
 }
 
 // A handle that can be sent across threads and used to wake up the `EventsLoop`.
@@ -88,8 +94,11 @@ impl EventsLoopProxy {
         let wakeup = self.pending_wakeup.upgrade();
         match (display, wakeup) {
             (Some(display), Some(wakeup)) => {
-                // Update the `EventsLoop`'s `pending_wakeup` flag.
-                wakeup.store(true, Ordering::Relaxed);
+                // Update the `EventsLoop`'s `pending_wakeup` flag. If it was already set, the
+                // `EventsLoop` hasn't handled the previous call yet, and will handle this one too.
+                if wakeup.swap(true, Ordering::AcqRel) {
+                    return Ok(());
+                }
                 // Cause the `EventsLoop` to break from `dispatch` if it is currently blocked.
                 let _ = display.sync();
                 display.flush().map_err(|_| EventsLoopClosed)?;
@@ -103,30 +112,42 @@ impl EventsLoopProxy {
 impl EventsLoop {
     pub fn new() -> Result<EventsLoop, ConnectError> {
         let (display, mut event_queue) = Display::connect_to_env()?;
+        let display = Arc::new(display);
 
         let sink = Arc::new(Mutex::new(EventsLoopSink::new()));
         let store = Arc::new(Mutex::new(WindowStore::new()));
         let seats = Arc::new(Mutex::new(Vec::new()));
+        let pointers = Arc::new(Mutex::new(Vec::new()));
+        let relative_pointer_manager = Arc::new(Mutex::new(None));
+        let pointer_constraints = Arc::new(Mutex::new(None));
 
         let env = Environment::from_registry_with_cb(
             display.get_registry().unwrap(),
             &mut event_queue,
             SeatManager {
+                display: Arc::downgrade(&display),
                 sink: sink.clone(),
                 store: store.clone(),
                 seats: seats.clone(),
+                pointers: pointers.clone(),
+                relative_pointer_manager: relative_pointer_manager,
+                pointer_constraints: pointer_constraints.clone(),
             },
         ).unwrap();
 
         Ok(EventsLoop {
-            display: Arc::new(display),
+            display: display,
             evq: RefCell::new(event_queue),
             sink: sink,
+            spare_buffer: RefCell::new(VecDeque::new()),
             pending_wakeup: Arc::new(AtomicBool::new(false)),
+            connection_lost: Cell::new(false),
             store: store,
             env: env,
             cleanup_needed: Arc::new(Mutex::new(false)),
             seats: seats,
+            pointers: pointers,
+            pointer_constraints: pointer_constraints,
         })
     }
 
@@ -137,60 +158,72 @@ impl EventsLoop {
         }
     }
 
-    pub fn poll_events<F>(&mut self, mut callback: F)
+    pub fn reserve_events(&self, capacity: usize) {
+        self.sink.lock().unwrap().buffer.reserve(capacity);
+        self.spare_buffer.borrow_mut().reserve(capacity);
+    }
+
+    pub fn poll_events<F>(&self, mut callback: F)
     where
         F: FnMut(::Event),
     {
+        if self.connection_lost.get() {
+            return;
+        }
+
         // send pending events to the server
-        self.display.flush().expect("Wayland connection lost.");
+        if let Err(err) = self.display.flush() {
+            return self.lose_connection(err, &mut callback);
+        }
 
         // dispatch any pre-buffered events
-        self.sink.lock().unwrap().empty_with(&mut callback);
+        self.empty_sink(&mut callback);
 
-        // try to read pending events
-        if let Some(h) = self.evq.get_mut().prepare_read() {
-            h.read_events().expect("Wayland connection lost.");
+        // read and dispatch pending wayland events
+        if let Err(err) = self.dispatch_pending() {
+            return self.lose_connection(err, &mut callback);
         }
-        // dispatch wayland events
-        self.evq
-            .get_mut()
-            .dispatch_pending()
-            .expect("Wayland connection lost.");
         self.post_dispatch_triggers();
 
         // dispatch buffered events to client
-        self.sink.lock().unwrap().empty_with(&mut callback);
+        self.empty_sink(&mut callback);
     }
 
-    pub fn run_forever<F>(&mut self, mut callback: F)
+    pub fn run_forever<F>(&self, mut callback: F)
     where
         F: FnMut(::Event) -> ControlFlow,
     {
-        // send pending events to the server
-        self.display.flush().expect("Wayland connection lost.");
+        if self.connection_lost.get() {
+            return;
+        }
 
         // Check for control flow by wrapping the callback.
-        let control_flow = ::std::cell::Cell::new(ControlFlow::Continue);
+        let control_flow = Cell::new(ControlFlow::Continue);
         let mut callback = |event| {
             if let ControlFlow::Break = callback(event) {
                 control_flow.set(ControlFlow::Break);
             }
         };
 
+        // send pending events to the server
+        if let Err(err) = self.display.flush() {
+            return self.lose_connection(err, &mut callback);
+        }
+
         // dispatch any pre-buffered events
         self.post_dispatch_triggers();
-        self.sink.lock().unwrap().empty_with(&mut callback);
+        self.empty_sink(&mut callback);
 
         loop {
             // dispatch events blocking if needed
-            self.evq
-                .get_mut()
-                .dispatch()
-                .expect("Wayland connection lost.");
+            let dispatched = self.evq.borrow_mut().dispatch();
+            if let Err(err) = dispatched {
+                return self.lose_connection(err, &mut callback);
+            }
             self.post_dispatch_triggers();
 
             // empty buffer of events
-            self.sink.lock().unwrap().empty_with(&mut callback);
+            self.empty_sink(&mut callback);
 
             if let ControlFlow::Break = control_flow.get() {
                 break;
@@ -212,12 +245,41 @@ impl EventsLoop {
  */
 
 impl EventsLoop {
-    fn post_dispatch_triggers(&mut self) {
+    // The sink isn't locked while the callback runs, since creating a window from it dispatches
+    // events that go into the sink.
+    fn empty_sink<F>(&self, callback: &mut F)
+    where
+        F: FnMut(::Event),
+    {
+        let mut buffer = self.spare_buffer.borrow_mut();
+        mem::swap(&mut *buffer, &mut self.sink.lock().unwrap().buffer);
+        for evt in buffer.drain(..) {
+            callback(evt)
+        }
+    }
+
+    fn dispatch_pending(&self) -> io::Result<()> {
+        // try to read pending events
+        if let Some(h) = self.evq.borrow_mut().prepare_read() {
+            h.read_events()?;
+        }
+        self.evq.borrow_mut().dispatch_pending()?;
+        Ok(())
+    }
+
+    fn lose_connection<F>(&self, err: io::Error, callback: &mut F)
+    where
+        F: FnMut(::Event),
+    {
+        self.connection_lost.set(true);
+        callback(::Event::ConnectionLost(format!("Wayland connection lost: {}", err)));
+    }
+
+    fn post_dispatch_triggers(&self) {
         let mut sink = self.sink.lock().unwrap();
         // process a possible pending wakeup call
-        if self.pending_wakeup.load(Ordering::Relaxed) {
+        if self.pending_wakeup.swap(false, Ordering::AcqRel) {
             sink.send_raw_event(::Event::Awakened);
-            self.pending_wakeup.store(false, Ordering::Relaxed);
         }
         // prune possible dead windows
         {
@@ -232,7 +294,10 @@ impl EventsLoop {
         }
         // process pending resize/refresh
         self.store.lock().unwrap().for_each(
-            |newsize, size, new_dpi, refresh, frame_refresh, closed, wid, frame| {
+            |newsize, size, new_dpi, refresh, frame_refresh, closed, resizing, wid, frame| {
+                if let Some(true) = resizing {
+                    sink.send_event(::WindowEvent::ResizeStarted, wid);
+                }
                 if let Some(frame) = frame {
                     if let Some((w, h)) = newsize {
                         frame.resize(w, h);
@@ -248,7 +313,10 @@ impl EventsLoop {
                     sink.send_event(::WindowEvent::HiDpiFactorChanged(dpi as f64), wid);
                 }
                 if refresh {
-                    sink.send_event(::WindowEvent::Refresh, wid);
+                    sink.send_event(::WindowEvent::Refresh { damage: None }, wid);
+                }
+                if let Some(false) = resizing {
+                    sink.send_event(::WindowEvent::ResizeEnded, wid);
                 }
                 if closed {
                     sink.send_event(::WindowEvent::CloseRequested, wid);
@@ -263,9 +331,14 @@ impl EventsLoop {
  */
 
 struct SeatManager {
+    // Used to wake up the `EventsLoop` when repeating keys.
+    display: Weak<Display>,
     sink: Arc<Mutex<EventsLoopSink>>,
     store: Arc<Mutex<WindowStore>>,
     seats: Arc<Mutex<Vec<(u32, Proxy<wl_seat::WlSeat>)>>>,
+    pointers: Arc<Mutex<Vec<Proxy<wl_pointer::WlPointer>>>>,
+    relative_pointer_manager: Arc<Mutex<Option<Proxy<ZwpRelativePointerManagerV1>>>>,
+    pointer_constraints: Arc<Mutex<Option<Proxy<ZwpPointerConstraintsV1>>>>,
 }
 
 impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager {
@@ -284,15 +357,43 @@ impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager
                     .bind::<wl_seat::WlSeat>(min(version, 5), id)
                     .unwrap()
                     .implement(SeatData {
+                        display: self.display.clone(),
                         sink: self.sink.clone(),
                         store: self.store.clone(),
+                        pointers: self.pointers.clone(),
+                        relative_pointer_manager: self.relative_pointer_manager.clone(),
                         pointer: None,
+                        relative_pointer: None,
                         keyboard: None,
                         touch: None,
                     });
                 self.store.lock().unwrap().new_seat(&seat);
                 self.seats.lock().unwrap().push((id, seat));
             }
+            GlobalEvent::New {
+                id,
+                ref interface,
+                ..
+            } if interface == "zwp_relative_pointer_manager_v1" =>
+            {
+                let manager = registry
+                    .bind::<ZwpRelativePointerManagerV1>(1, id)
+                    .unwrap()
+                    .implement(|_, _| ());
+                *self.relative_pointer_manager.lock().unwrap() = Some(manager);
+            }
+            GlobalEvent::New {
+                id,
+                ref interface,
+                ..
+            } if interface == "zwp_pointer_constraints_v1" =>
+            {
+                let constraints = registry
+                    .bind::<ZwpPointerConstraintsV1>(1, id)
+                    .unwrap()
+                    .implement(|_, _| ());
+                *self.pointer_constraints.lock().unwrap() = Some(constraints);
+            }
             GlobalEvent::Removed { id, ref interface } if interface == "wl_seat" => {
                 let mut seats = self.seats.lock().unwrap();
                 if let Some(idx) = seats.iter().position(|&(i, _)| i == id) {
@@ -308,9 +409,13 @@ impl Implementation<Proxy<wl_registry::WlRegistry>, GlobalEvent> for SeatManager
 }
 
 struct SeatData {
+    display: Weak<Display>,
     sink: Arc<Mutex<EventsLoopSink>>,
     store: Arc<Mutex<WindowStore>>,
+    pointers: Arc<Mutex<Vec<Proxy<wl_pointer::WlPointer>>>>,
+    relative_pointer_manager: Arc<Mutex<Option<Proxy<ZwpRelativePointerManagerV1>>>>,
     pointer: Option<Proxy<wl_pointer::WlPointer>>,
+    relative_pointer: Option<Proxy<ZwpRelativePointerV1>>,
     keyboard: Option<Proxy<wl_keyboard::WlKeyboard>>,
     touch: Option<Proxy<wl_touch::WlTouch>>,
 }
@@ -323,26 +428,31 @@ impl Implementation<Proxy<wl_seat::WlSeat>, wl_seat::Event> for SeatData {
             wl_seat::Event::Capabilities { capabilities } => {
                 // create pointer if applicable
                 if capabilities.contains(wl_seat::Capability::Pointer) && self.pointer.is_none() {
-                    self.pointer = Some(super::pointer::implement_pointer(
+                    let pointer = super::pointer::implement_pointer(
                         seat.get_pointer().unwrap(),
                         self.sink.clone(),
                         self.store.clone(),
-                    ))
+                    );
+                    if let Some(ref manager) = *self.relative_pointer_manager.lock().unwrap() {
+                        self.relative_pointer = super::pointer::implement_relative_pointer(
+                            manager,
+                            &pointer,
+                            self.sink.clone(),
+                        );
+                    }
+                    self.pointers.lock().unwrap().push(pointer.clone());
+                    self.pointer = Some(pointer);
                 }
                 // destroy pointer if applicable
                 if !capabilities.contains(wl_seat::Capability::Pointer) {
-                    if let Some(pointer) = self.pointer.take() {
-                        if pointer.version() >= 3 {
-                            use self::wl_pointer::RequestsTrait;
-                            pointer.release();
-                        }
-                    }
+                    self.destroy_pointer();
                 }
                 // create keyboard if applicable
                 if capabilities.contains(wl_seat::Capability::Keyboard) && self.keyboard.is_none() {
                     self.keyboard = Some(super::keyboard::init_keyboard(
                         seat.get_keyboard().unwrap(),
                         self.sink.clone(),
+                        self.display.clone(),
                     ))
                 }
                 // destroy keyboard if applicable
@@ -376,14 +486,25 @@ impl Implementation<Proxy<wl_seat::WlSeat>, wl_seat::Event> for SeatData {
     }
 }
 
-impl Drop for SeatData {
-    fn drop(&mut self) {
+impl SeatData {
+    fn destroy_pointer(&mut self) {
+        if let Some(relative_pointer) = self.relative_pointer.take() {
+            use self::zwp_relative_pointer_v1::RequestsTrait;
+            relative_pointer.destroy();
+        }
         if let Some(pointer) = self.pointer.take() {
+            self.pointers.lock().unwrap().retain(|p| !p.equals(&pointer));
             if pointer.version() >= 3 {
                 use self::wl_pointer::RequestsTrait;
                 pointer.release();
             }
         }
+    }
+}
+
+impl Drop for SeatData {
+    fn drop(&mut self) {
+        self.destroy_pointer();
         if let Some(kbd) = self.keyboard.take() {
             if kbd.version() >= 3 {
                 use self::wl_keyboard::RequestsTrait;
@@ -477,6 +598,12 @@ impl MonitorId {
             .with_info(&self.proxy, |_, info| info.scale_factor)
             .unwrap_or(1)
     }
+
+    // The core protocol doesn't tell clients about panels.
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        (self.get_position(), self.get_dimensions())
+    }
 }
 
 pub fn get_primary_monitor(outputs: &OutputMgr) -> MonitorId {
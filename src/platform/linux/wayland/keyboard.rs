@@ -1,19 +1,100 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 
 use {ElementState, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent};
 
-use super::{make_wid, DeviceId, EventsLoopSink};
+use super::{make_wid, DeviceId, EventsLoopSink, WindowId};
 use sctk::keyboard::{self, map_keyboard_auto, Event as KbEvent};
-use sctk::reexports::client::{NewProxy, Proxy};
+use sctk::reexports::client::{Display, NewProxy, Proxy};
+use sctk::reexports::client::protocol::wl_display::RequestsTrait as DisplayRequests;
 use sctk::reexports::client::protocol::wl_keyboard;
 
+/// A key being repeated by a background thread, which stops when this is dropped.
+///
+/// Wayland compositors leave key repeat to clients, only telling them the rate and delay to use.
+struct KeyRepeat {
+    rawkey: u32,
+    stop: Arc<AtomicBool>,
+}
+
+impl KeyRepeat {
+    fn start(
+        sink: Arc<Mutex<EventsLoopSink>>,
+        display: Weak<Display>,
+        wid: WindowId,
+        input: KeyboardInput,
+        (interval, delay): (Duration, Duration),
+    ) -> KeyRepeat {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let rawkey = input.scancode;
+        let input = KeyboardInput { is_repeat: true, ..input };
+        thread::spawn(move || {
+            thread::sleep(delay);
+            loop {
+                let display = match display.upgrade() {
+                    Some(display) => display,
+                    None => break,
+                };
+                {
+                    // The stop flag is checked with the sink locked, since the key's release is
+                    // sent with it locked too, so that no repeat can follow the release.
+                    let mut sink = sink.lock().unwrap();
+                    if thread_stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    sink.send_event(
+                        WindowEvent::KeyboardInput {
+                            device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
+                            input: input.clone(),
+                        },
+                        wid,
+                    );
+                    if let Some(ref text) = input.text {
+                        for chr in text.chars() {
+                            sink.send_event(WindowEvent::ReceivedCharacter(chr), wid);
+                        }
+                    }
+                }
+                // Cause the `EventsLoop` to break from `dispatch`, so that it empties the sink.
+                let _ = display.sync();
+                let _ = display.flush();
+                thread::sleep(interval);
+            }
+        });
+        KeyRepeat { rawkey, stop }
+    }
+}
+
+impl Drop for KeyRepeat {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+fn is_modifier(vkcode: Option<VirtualKeyCode>) -> bool {
+    match vkcode {
+        Some(VirtualKeyCode::LShift) | Some(VirtualKeyCode::RShift) |
+        Some(VirtualKeyCode::LControl) | Some(VirtualKeyCode::RControl) |
+        Some(VirtualKeyCode::LAlt) | Some(VirtualKeyCode::RAlt) |
+        Some(VirtualKeyCode::LWin) | Some(VirtualKeyCode::RWin) => true,
+        _ => false,
+    }
+}
+
 pub fn init_keyboard(
     keyboard: NewProxy<wl_keyboard::WlKeyboard>,
     sink: Arc<Mutex<EventsLoopSink>>,
+    display: Weak<Display>,
 ) -> Proxy<wl_keyboard::WlKeyboard> {
     // { variables to be captured by the closure
     let mut target = None;
     let my_sink = sink.clone();
+    // The interval between repeats and the delay before the first one, if key repeat is enabled.
+    let mut repeat_info = None;
+    let mut repeat: Option<KeyRepeat> = None;
     // }
     let ret = map_keyboard_auto(keyboard, move |evt: KbEvent, _| match evt {
         KbEvent::Enter { surface, .. } => {
@@ -25,6 +106,7 @@ pub fn init_keyboard(
             target = Some(wid);
         }
         KbEvent::Leave { surface, .. } => {
+            repeat = None;
             let wid = make_wid(&surface);
             my_sink
                 .lock()
@@ -46,31 +128,68 @@ pub fn init_keyboard(
                     wl_keyboard::KeyState::Released => ElementState::Released,
                 };
                 let vkcode = key_to_vkey(rawkey, keysym);
+                // Text is only sent on key press, not release.
+                let text = match utf8 {
+                    Some(ref text) if state == ElementState::Pressed && !text.is_empty() => Some(text.clone()),
+                    _ => None,
+                };
+                let is_dead_key = state == ElementState::Pressed && text.is_none() && is_dead_keysym(keysym);
+                let input = KeyboardInput {
+                    state: state,
+                    scancode: rawkey,
+                    virtual_keycode: vkcode,
+                    modifiers: modifiers.into(),
+                    is_composing: false,
+                    is_synthetic: false,
+                    is_repeat: false,
+                    text,
+                    is_dead_key,
+                };
                 let mut guard = my_sink.lock().unwrap();
                 guard.send_event(
                     WindowEvent::KeyboardInput {
                         device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
-                        input: KeyboardInput {
-                            state: state,
-                            scancode: rawkey,
-                            virtual_keycode: vkcode,
-                            modifiers: modifiers.into(),
-                        },
+                        input: input.clone(),
                     },
                     wid,
                 );
-                // send char event only on key press, not release
                 if let ElementState::Released = state {
+                    if repeat.as_ref().map_or(false, |repeat| repeat.rawkey == rawkey) {
+                        repeat = None;
+                    }
                     return;
                 }
-                if let Some(txt) = utf8 {
-                    for chr in txt.chars() {
+                if let Some(ref text) = input.text {
+                    for chr in text.chars() {
                         guard.send_event(WindowEvent::ReceivedCharacter(chr), wid);
                     }
                 }
+                // Like on other platforms, only the last key pressed repeats.
+                repeat = None;
+                if let Some(repeat_info) = repeat_info {
+                    if !is_modifier(vkcode) {
+                        repeat = Some(KeyRepeat::start(
+                            my_sink.clone(),
+                            display.clone(),
+                            wid,
+                            input,
+                            repeat_info,
+                        ));
+                    }
+                }
             }
         }
-        KbEvent::RepeatInfo { .. } => { /* TODO: handle repeat info */ }
+        KbEvent::RepeatInfo { rate, delay } => {
+            // A rate of zero disables key repeat.
+            repeat_info = if rate > 0 {
+                Some((
+                    Duration::from_millis(1000 / rate as u64),
+                    Duration::from_millis(delay as u64),
+                ))
+            } else {
+                None
+            };
+        }
     });
 
     match ret {
@@ -118,6 +237,11 @@ pub fn init_keyboard(
                                     scancode: key,
                                     virtual_keycode: None,
                                     modifiers: ModifiersState::default(),
+                                    is_composing: false,
+                                    is_synthetic: false,
+                                    is_repeat: false,
+                                    text: None,
+                                    is_dead_key: false,
                                 },
                             },
                             wid,
@@ -130,6 +254,13 @@ pub fn init_keyboard(
     }
 }
 
+/// Returns `true` if the keysym is a dead key, or the compose key, which start a compose sequence.
+fn is_dead_keysym(keysym: u32) -> bool {
+    use sctk::keyboard::keysyms;
+    (keysyms::XKB_KEY_dead_grave <= keysym && keysym <= keysyms::XKB_KEY_dead_greek)
+        || keysym == keysyms::XKB_KEY_Multi_key
+}
+
 fn key_to_vkey(rawkey: u32, keysym: u32) -> Option<VirtualKeyCode> {
     match rawkey {
         1 => Some(VirtualKeyCode::Escape),
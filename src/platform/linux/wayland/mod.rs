@@ -16,9 +16,41 @@ mod window;
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId;
 
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> DeviceId {
+        DeviceId
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowId(usize);
 
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        WindowId(raw as usize)
+    }
+}
+
 #[inline]
 fn make_wid(s: &Proxy<wl_surface::WlSurface>) -> WindowId {
     WindowId(s.c_ptr() as usize)
@@ -1,6 +1,6 @@
 use std::sync::{Arc, Mutex};
 
-use {ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use {DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
 use events::ModifiersState;
 
 use super::DeviceId;
@@ -10,6 +10,15 @@ use super::window::WindowStore;
 use sctk::reexports::client::{NewProxy, Proxy};
 use sctk::reexports::client::protocol::wl_pointer::{self, Event as PtrEvent, WlPointer};
 
+use wayland_protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_manager_v1::{
+    RequestsTrait as RelativePointerManagerRequests,
+    ZwpRelativePointerManagerV1,
+};
+use wayland_protocols::unstable::relative_pointer::v1::client::zwp_relative_pointer_v1::{
+    Event as RelativePointerEvent,
+    ZwpRelativePointerV1,
+};
+
 pub fn implement_pointer(
     pointer: NewProxy<WlPointer>,
     sink: Arc<Mutex<EventsLoopSink>>,
@@ -119,6 +128,7 @@ pub fn implement_pointer(
                                 device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                                 delta: MouseScrollDelta::PixelDelta((x as f64, y as f64).into()),
                                 phase: TouchPhase::Moved,
+                                momentum_phase: None,
                                 // TODO: replace dummy value with actual modifier state
                                 modifiers: ModifiersState::default(),
                             },
@@ -149,6 +159,7 @@ pub fn implement_pointer(
                                 device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                                 delta: MouseScrollDelta::LineDelta(x as f32, y as f32),
                                 phase: axis_state,
+                                momentum_phase: None,
                                 // TODO: replace dummy value with actual modifier state
                                 modifiers: ModifiersState::default(),
                             },
@@ -160,6 +171,7 @@ pub fn implement_pointer(
                                 device_id: ::DeviceId(::platform::DeviceId::Wayland(DeviceId)),
                                 delta: MouseScrollDelta::PixelDelta((x as f64, y as f64).into()),
                                 phase: axis_state,
+                                momentum_phase: None,
                                 // TODO: replace dummy value with actual modifier state
                                 modifiers: ModifiersState::default(),
                             },
@@ -171,6 +183,9 @@ pub fn implement_pointer(
             PtrEvent::AxisSource { .. } => (),
             PtrEvent::AxisStop { .. } => {
                 axis_state = TouchPhase::Ended;
+                // Make sure the next frame reports the end of the scroll, even if no axis
+                // motion accompanies the stop.
+                axis_buffer = Some(axis_buffer.unwrap_or((0.0, 0.0)));
             }
             PtrEvent::AxisDiscrete { axis, discrete } => {
                 let (mut x, mut y) = axis_discrete_buffer.unwrap_or((0, 0));
@@ -188,3 +203,39 @@ pub fn implement_pointer(
         }
     })
 }
+
+// Reports the unaccelerated motion of `pointer` as device events, the same way X11 does with
+// XInput2 raw events. Unlike `wl_pointer` motion, this keeps working while the pointer is locked.
+pub fn implement_relative_pointer(
+    manager: &Proxy<ZwpRelativePointerManagerV1>,
+    pointer: &Proxy<WlPointer>,
+    sink: Arc<Mutex<EventsLoopSink>>,
+) -> Option<Proxy<ZwpRelativePointerV1>> {
+    let relative_pointer = manager.get_relative_pointer(pointer).ok()?;
+    Some(relative_pointer.implement(move |evt, _| {
+        let mut sink = sink.lock().unwrap();
+        match evt {
+            RelativePointerEvent::RelativeMotion { dx_unaccel, dy_unaccel, .. } => {
+                let device_id = ::DeviceId(::platform::DeviceId::Wayland(DeviceId));
+                if dx_unaccel != 0.0 {
+                    sink.send_raw_event(Event::DeviceEvent {
+                        device_id,
+                        event: DeviceEvent::Motion { axis: 0, value: dx_unaccel },
+                    });
+                }
+                if dy_unaccel != 0.0 {
+                    sink.send_raw_event(Event::DeviceEvent {
+                        device_id,
+                        event: DeviceEvent::Motion { axis: 1, value: dy_unaccel },
+                    });
+                }
+                if dx_unaccel != 0.0 || dy_unaccel != 0.0 {
+                    sink.send_raw_event(Event::DeviceEvent {
+                        device_id,
+                        event: DeviceEvent::MouseMotion { delta: (dx_unaccel, dy_unaccel) },
+                    });
+                }
+            }
+        }
+    }))
+}
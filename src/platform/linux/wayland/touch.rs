@@ -36,6 +36,8 @@ pub(crate) fn implement_touch(
                             phase: TouchPhase::Started,
                             location: (x, y).into(),
                             id: id as u64,
+                            force: None,
+                            contact_size: None,
                         }),
                         wid,
                     );
@@ -56,6 +58,8 @@ pub(crate) fn implement_touch(
                             phase: TouchPhase::Ended,
                             location: pt.location.into(),
                             id: id as u64,
+                            force: None,
+                            contact_size: None,
                         }),
                         pt.wid,
                     );
@@ -71,6 +75,8 @@ pub(crate) fn implement_touch(
                             phase: TouchPhase::Moved,
                             location: (x, y).into(),
                             id: id as u64,
+                            force: None,
+                            contact_size: None,
                         }),
                         pt.wid,
                     );
@@ -84,6 +90,8 @@ pub(crate) fn implement_touch(
                         phase: TouchPhase::Cancelled,
                         location: pt.location.into(),
                         id: pt.id as u64,
+                        force: None,
+                        contact_size: None,
                     }),
                     pt.wid,
                 );
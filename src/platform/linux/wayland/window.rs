@@ -1,23 +1,43 @@
 use std::collections::VecDeque;
+use std::mem;
 use std::sync::{Arc, Mutex, Weak};
 
-use {CreationError, MouseCursor, WindowAttributes};
+use {CreationError, CursorGrabMode, MouseCursor, WindowAttributes};
 use dpi::{LogicalPosition, LogicalSize};
 use platform::MonitorId as PlatformMonitorId;
 use window::MonitorId as RootMonitorId;
 
-use sctk::window::{BasicFrame, Event as WEvent, Window as SWindow};
+use sctk::window::{BasicFrame, Event as WEvent, State, Window as SWindow};
 use sctk::reexports::client::{Display, Proxy};
-use sctk::reexports::client::protocol::{wl_seat, wl_surface, wl_output};
+use sctk::reexports::client::protocol::{wl_compositor, wl_output, wl_pointer, wl_seat, wl_surface};
 use sctk::reexports::client::protocol::wl_compositor::RequestsTrait as CompositorRequests;
+use sctk::reexports::client::protocol::wl_region::RequestsTrait as RegionRequests;
 use sctk::reexports::client::protocol::wl_surface::RequestsTrait as SurfaceRequests;
 use sctk::output::OutputMgr;
 
+use wayland_protocols::unstable::pointer_constraints::v1::client::zwp_confined_pointer_v1::{
+    RequestsTrait as ConfinedPointerRequests,
+    ZwpConfinedPointerV1,
+};
+use wayland_protocols::unstable::pointer_constraints::v1::client::zwp_locked_pointer_v1::{
+    RequestsTrait as LockedPointerRequests,
+    ZwpLockedPointerV1,
+};
+use wayland_protocols::unstable::pointer_constraints::v1::client::zwp_pointer_constraints_v1::{
+    Lifetime,
+    RequestsTrait as PointerConstraintsRequests,
+    ZwpPointerConstraintsV1,
+};
+
 use super::{make_wid, EventsLoop, MonitorId, WindowId};
 use platform::platform::wayland::event_loop::{get_available_monitors, get_primary_monitor};
 
 pub struct Window {
     surface: Proxy<wl_surface::WlSurface>,
+    // TODO: let applications draw their own decorations, or use libdecor's. SCTK's `Frame` trait
+    // already covers drawing and hit-testing, but its implementations are built by a static
+    // constructor taking wayland-client proxies, so one can't be picked at runtime without making
+    // SCTK part of our public API. libdecor also needs bindings that can share the connection.
     frame: Arc<Mutex<SWindow<BasicFrame>>>,
     monitors: Arc<Mutex<MonitorList>>, // Monitors this window is currently on
     outputs: OutputMgr, // Access to info for all monitors
@@ -25,6 +45,30 @@ pub struct Window {
     kill_switch: (Arc<Mutex<bool>>, Arc<Mutex<bool>>),
     display: Arc<Display>,
     need_frame_refresh: Arc<Mutex<bool>>,
+    pointers: Arc<Mutex<Vec<Proxy<wl_pointer::WlPointer>>>>,
+    pointer_constraints: Arc<Mutex<Option<Proxy<ZwpPointerConstraintsV1>>>>,
+    cursor_grab: Mutex<CursorGrab>,
+    compositor: Proxy<wl_compositor::WlCompositor>,
+    state: Arc<Mutex<StateFlags>>,
+}
+
+#[derive(Default)]
+struct CursorGrab {
+    mode: CursorGrabMode,
+    region: Option<::Rect>,
+    locked_pointers: Vec<Proxy<ZwpLockedPointerV1>>,
+    confined_pointers: Vec<Proxy<ZwpConfinedPointerV1>>,
+}
+
+// The state last reported by the compositor in a configure event, and the state last set by the
+// client.
+#[derive(Debug, Default, Clone, Copy)]
+struct StateFlags {
+    maximized: bool,
+    fullscreen: bool,
+    activated: bool,
+    resizable: bool,
+    decorated: bool,
 }
 
 impl Window {
@@ -67,8 +111,15 @@ impl Window {
             }
         });
 
+        let state = Arc::new(Mutex::new(StateFlags {
+            resizable: attributes.resizable,
+            decorated: attributes.decorations,
+            ..StateFlags::default()
+        }));
+
         let window_store = evlp.store.clone();
         let my_surface = surface.clone();
+        let my_state = state.clone();
         let mut frame = SWindow::<BasicFrame>::init(
             surface.clone(),
             (width, height),
@@ -77,11 +128,18 @@ impl Window {
             &evlp.env.shm,
             &evlp.env.shell,
             move |event, ()| match event {
-                WEvent::Configure { new_size, .. } => {
+                WEvent::Configure { new_size, states } => {
+                    {
+                        let mut state = my_state.lock().unwrap();
+                        state.maximized = states.contains(&State::Maximized);
+                        state.fullscreen = states.contains(&State::Fullscreen);
+                        state.activated = states.contains(&State::Activated);
+                    }
                     let mut store = window_store.lock().unwrap();
                     for window in &mut store.windows {
                         if window.surface.equals(&my_surface) {
                             window.newsize = new_size;
+                            window.resizing = states.contains(&State::Resizing);
                             window.need_refresh = true;
                             *(window.need_frame_refresh.lock().unwrap()) = true;
                             return;
@@ -138,6 +196,8 @@ impl Window {
 
         evlp.store.lock().unwrap().windows.push(InternalWindow {
             closed: false,
+            resizing: false,
+            resizing_sent: false,
             newsize: None,
             size: size.clone(),
             need_refresh: false,
@@ -159,6 +219,11 @@ impl Window {
             size: size,
             kill_switch: (kill_switch, evlp.cleanup_needed.clone()),
             need_frame_refresh: need_frame_refresh,
+            pointers: evlp.pointers.clone(),
+            pointer_constraints: evlp.pointer_constraints.clone(),
+            cursor_grab: Default::default(),
+            compositor: evlp.env.compositor.clone(),
+            state,
         })
     }
 
@@ -181,6 +246,11 @@ impl Window {
         // TODO
     }
 
+    #[inline]
+    pub fn present_ready(&self) {
+        // The surface is only mapped once a buffer is committed to it.
+    }
+
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
         // Not possible with wayland
@@ -194,7 +264,12 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_position(&self, _pos: LogicalPosition) {
+    pub fn set_outer_position(&self, _pos: LogicalPosition) {
+        // Not possible with wayland
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, _pos: LogicalPosition) {
         // Not possible with wayland
     }
 
@@ -209,6 +284,12 @@ impl Window {
         Some((w, h).into())
     }
 
+    #[inline]
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        // The outer size doesn't include the borders drawn by the frame either
+        Some(::FrameExtents::default())
+    }
+
     #[inline]
     // NOTE: This will only resize the borders, the contents must be updated by the user
     pub fn set_inner_size(&self, size: LogicalSize) {
@@ -230,6 +311,7 @@ impl Window {
     #[inline]
     pub fn set_resizable(&self, resizable: bool) {
         self.frame.lock().unwrap().set_resizable(resizable);
+        self.state.lock().unwrap().resizable = resizable;
     }
 
     #[inline]
@@ -240,6 +322,7 @@ impl Window {
     pub fn set_decorations(&self, decorate: bool) {
         self.frame.lock().unwrap().set_decorate(decorate);
         *(self.need_frame_refresh.lock().unwrap()) = true;
+        self.state.lock().unwrap().decorated = decorate;
     }
 
     pub fn set_maximized(&self, maximized: bool) {
@@ -250,6 +333,54 @@ impl Window {
         }
     }
 
+    pub fn set_minimized(&self, minimized: bool) {
+        // xdg-shell has no request to restore a minimized surface.
+        if minimized {
+            self.frame.lock().unwrap().set_minimized();
+        }
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        // The compositor doesn't tell clients whether their surface is minimized.
+        false
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.state.lock().unwrap().maximized
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.state.lock().unwrap().fullscreen
+    }
+
+    pub fn is_visible(&self) -> bool {
+        // Surfaces can't be hidden yet.
+        true
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.state.lock().unwrap().activated
+    }
+
+    // Clients can't activate their windows themselves, nor keep them from being activated.
+    pub fn focus(&self) {
+    }
+
+    pub fn set_focusable(&self, _focusable: bool) {
+    }
+
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        ::Insets::default()
+    }
+
+    pub fn is_resizable(&self) -> bool {
+        self.state.lock().unwrap().resizable
+    }
+
+    pub fn is_decorated(&self) -> bool {
+        self.state.lock().unwrap().decorated
+    }
+
     pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
         if let Some(RootMonitorId {
             inner: PlatformMonitorId::Wayland(ref monitor_id),
@@ -274,9 +405,132 @@ impl Window {
         // TODO: This isn't possible on Wayland yet
     }
 
+    // Constrains the pointers according to the confine region if there's one, or else the grab
+    // mode. A pointer can only have one constraint on a surface, so the previous ones are released.
+    fn update_pointer_constraints(&self, grab: &mut CursorGrab) -> Result<(), String> {
+        for locked_pointer in grab.locked_pointers.drain(..) {
+            locked_pointer.destroy();
+        }
+        for confined_pointer in grab.confined_pointers.drain(..) {
+            confined_pointer.destroy();
+        }
+        if grab.region.is_none() && grab.mode == CursorGrabMode::None {
+            return Ok(());
+        }
+
+        let constraints = self.pointer_constraints.lock().unwrap();
+        let constraints = match *constraints {
+            Some(ref constraints) => constraints,
+            None => return Err("The compositor doesn't support pointer constraints.".to_owned()),
+        };
+        let pointers = self.pointers.lock().unwrap();
+
+        if let Some(region) = grab.region {
+            // Surface coordinates are logical, so the region doesn't depend on the DPI factor.
+            let (x, y): (i32, i32) = region.position.into();
+            let (width, height): (u32, u32) = region.size.into();
+            let wl_region = self.compositor
+                .create_region()
+                .map_err(|_| "Failed to create the confine region.".to_owned())?
+                .implement(|_, _| ());
+            wl_region.add(x, y, width as i32, height as i32);
+            let confined_pointers = &mut grab.confined_pointers;
+            let result: Result<(), String> = pointers.iter().map(|pointer| {
+                let confined_pointer = constraints
+                    .confine_pointer(&self.surface, pointer, Some(&wl_region), Lifetime::Persistent)
+                    .map_err(|_| "Failed to confine the pointer.".to_owned())?
+                    .implement(|_, _| ());
+                confined_pointers.push(confined_pointer);
+                Ok(())
+            }).collect();
+            // The compositor keeps its own copy of the region.
+            wl_region.destroy();
+            return result;
+        }
+
+        for pointer in pointers.iter() {
+            if grab.mode == CursorGrabMode::Locked {
+                // The pointer is locked in place whenever the window has focus, until the grab is
+                // released. Relative motion is still reported through `DeviceEvent::MouseMotion`.
+                let locked_pointer = constraints
+                    .lock_pointer(&self.surface, pointer, None, Lifetime::Persistent)
+                    .map_err(|_| "Failed to lock the pointer.".to_owned())?
+                    .implement(|_, _| ());
+                grab.locked_pointers.push(locked_pointer);
+            } else {
+                let confined_pointer = constraints
+                    .confine_pointer(&self.surface, pointer, None, Lifetime::Persistent)
+                    .map_err(|_| "Failed to confine the pointer.".to_owned())?
+                    .implement(|_, _| ());
+                grab.confined_pointers.push(confined_pointer);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
+        self.set_cursor_grab_mode(if grab { CursorGrabMode::Locked } else { CursorGrabMode::None })
+    }
+
+    pub fn set_cursor_grab_mode(&self, mode: CursorGrabMode) -> Result<(), String> {
+        let mut grab = self.cursor_grab.lock().unwrap();
+        if mode == grab.mode {
+            return Ok(());
+        }
+        let previous_mode = mem::replace(&mut grab.mode, mode);
+        let result = self.update_pointer_constraints(&mut grab);
+        if result.is_err() {
+            grab.mode = previous_mode;
+        }
+        result
+    }
+
+    pub fn set_cursor_confine_region(&self, region: Option<::Rect>) -> Result<(), String> {
+        let mut grab = self.cursor_grab.lock().unwrap();
+        grab.region = region;
+        self.update_pointer_constraints(&mut grab)
+    }
+
+    pub fn set_input_region(&self, region: Option<&[::Rect]>) -> Result<(), String> {
+        match region {
+            Some(region) => {
+                // Surface coordinates are logical, so the region doesn't depend on the DPI factor.
+                let wl_region = self.compositor
+                    .create_region()
+                    .map_err(|_| "Failed to create the input region.".to_owned())?
+                    .implement(|_, _| ());
+                for rect in region {
+                    let (x, y): (i32, i32) = rect.position.into();
+                    let (width, height): (u32, u32) = rect.size.into();
+                    wl_region.add(x, y, width as i32, height as i32);
+                }
+                self.surface.set_input_region(Some(&wl_region));
+                // The compositor keeps its own copy of the region.
+                wl_region.destroy();
+            },
+            None => self.surface.set_input_region(None),
+        }
+        // The input region is double-buffered state, applied on the next commit.
+        self.surface.commit();
+        Ok(())
+    }
+
     #[inline]
-    pub fn grab_cursor(&self, _grab: bool) -> Result<(), String> {
-        Err("Cursor grabbing is not yet possible on Wayland.".to_owned())
+    pub fn set_shape(&self, _shape: Option<&[::Rect]>) -> Result<(), String> {
+        Err("Wayland surfaces can't be clipped; transparent pixels have to be drawn instead.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_blur(&self, _blur: bool) -> Result<(), String> {
+        // TODO: use KWin's `org_kde_kwin_blur`, which needs bindings generated from its XML, since
+        // it isn't part of wayland-protocols.
+        Err("Blurring what's behind a window is not yet possible on Wayland.".to_owned())
+    }
+
+    #[inline]
+    pub fn reserve_screen_edge(&self, _reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        Err("Reserving screen space is not possible on Wayland.".to_owned())
     }
 
     #[inline]
@@ -310,6 +564,9 @@ impl Window {
 
 impl Drop for Window {
     fn drop(&mut self) {
+        for locked_pointer in self.locked_pointers.lock().unwrap().drain(..) {
+            locked_pointer.destroy();
+        }
         *(self.kill_switch.0.lock().unwrap()) = true;
         *(self.kill_switch.1.lock().unwrap()) = true;
     }
@@ -326,6 +583,10 @@ struct InternalWindow {
     need_refresh: bool,
     need_frame_refresh: Arc<Mutex<bool>>,
     closed: bool,
+    // Whether the compositor says the window is being resized, and whether `ResizeStarted` was
+    // the last event sent about it.
+    resizing: bool,
+    resizing_sent: bool,
     kill_switch: Arc<Mutex<bool>>,
     frame: Weak<Mutex<SWindow<BasicFrame>>>,
     current_dpi: i32,
@@ -385,11 +646,17 @@ impl WindowStore {
 
     pub fn for_each<F>(&mut self, mut f: F)
     where
-        F: FnMut(Option<(u32, u32)>, &mut (u32, u32), Option<i32>, bool, bool, bool, WindowId, Option<&mut SWindow<BasicFrame>>),
+        F: FnMut(Option<(u32, u32)>, &mut (u32, u32), Option<i32>, bool, bool, bool, Option<bool>, WindowId, Option<&mut SWindow<BasicFrame>>),
     {
         for window in &mut self.windows {
             let opt_arc = window.frame.upgrade();
             let mut opt_mutex_lock = opt_arc.as_ref().map(|m| m.lock().unwrap());
+            let resizing = if window.resizing != window.resizing_sent {
+                window.resizing_sent = window.resizing;
+                Some(window.resizing)
+            } else {
+                None
+            };
             f(
                 window.newsize.take(),
                 &mut *(window.size.lock().unwrap()),
@@ -397,6 +664,7 @@ impl WindowStore {
                 window.need_refresh,
                 ::std::mem::replace(&mut *window.need_frame_refresh.lock().unwrap(), false),
                 window.closed,
+                resizing,
                 make_wid(&window.surface),
                 opt_mutex_lock.as_mut().map(|m| &mut **m),
             );
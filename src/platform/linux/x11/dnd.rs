@@ -8,7 +8,7 @@ use percent_encoding::percent_decode;
 
 use super::{ffi, util, XConnection, XError};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct DndAtoms {
     pub aware: ffi::Atom,
     pub enter: ffi::Atom,
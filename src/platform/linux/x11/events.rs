@@ -2,6 +2,11 @@ use {events, libc};
 use super::ffi;
 use VirtualKeyCode;
 
+/// Returns `true` if the keysym is a dead key, or the compose key, which start a compose sequence.
+pub fn is_dead_keysym(keysym: libc::c_uint) -> bool {
+    (ffi::XK_dead_grave <= keysym && keysym <= ffi::XK_dead_greek) || keysym == ffi::XK_Multi_key
+}
+
 pub fn keysym_to_element(keysym: libc::c_uint) -> Option<VirtualKeyCode> {
     Some(match keysym {
         ffi::XK_BackSpace => events::VirtualKeyCode::Back,
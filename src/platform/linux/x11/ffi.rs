@@ -6,3 +6,58 @@ pub use x11_dl::xinput2::*;
 pub use x11_dl::xlib_xcb::*;
 pub use x11_dl::error::OpenError;
 pub use x11_dl::xrandr::*;
+pub use x11_dl::xss::*;
+
+use std::mem;
+use std::os::raw::{c_char, c_int};
+
+use platform::platform::dlopen;
+
+// The parts of the Shape extension winit uses, which x11-dl doesn't bind.
+
+#[allow(non_upper_case_globals)]
+pub const ShapeSet: c_int = 0;
+#[allow(non_upper_case_globals)]
+pub const ShapeBounding: c_int = 0;
+#[allow(non_upper_case_globals)]
+pub const ShapeInput: c_int = 2;
+#[allow(non_upper_case_globals)]
+pub const Unsorted: c_int = 0;
+
+#[allow(non_snake_case)]
+pub struct XShape {
+    pub XShapeCombineRectangles: unsafe extern "C" fn(
+        *mut Display,
+        Window,
+        c_int,
+        c_int,
+        c_int,
+        *mut XRectangle,
+        c_int,
+        c_int,
+        c_int,
+    ),
+    pub XShapeCombineMask: unsafe extern "C" fn(*mut Display, Window, c_int, c_int, c_int, Pixmap, c_int),
+}
+
+impl XShape {
+    /// Loads `libXext`, which is never unloaded afterwards.
+    pub fn open() -> Option<XShape> {
+        unsafe {
+            let names: [&[u8]; 2] = [b"libXext.so.6\0", b"libXext.so\0"];
+            let lib = names.iter()
+                .map(|name| dlopen::dlopen(name.as_ptr() as *const c_char, dlopen::RTLD_LAZY))
+                .find(|lib| !lib.is_null())?;
+            let combine_rectangles = dlopen::dlsym(lib, b"XShapeCombineRectangles\0".as_ptr() as *const c_char);
+            let combine_mask = dlopen::dlsym(lib, b"XShapeCombineMask\0".as_ptr() as *const c_char);
+            if combine_rectangles.is_null() || combine_mask.is_null() {
+                dlopen::dlclose(lib);
+                return None;
+            }
+            Some(XShape {
+                XShapeCombineRectangles: mem::transmute(combine_rectangles),
+                XShapeCombineMask: mem::transmute(combine_mask),
+            })
+        }
+    }
+}
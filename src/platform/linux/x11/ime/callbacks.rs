@@ -90,6 +90,7 @@ unsafe fn replace_im(inner: *mut ImeInner) -> Result<(), ReplaceImError> {
 
     let (new_im, is_fallback) = {
         let new_im = (*inner).potential_input_methods.open_im(xconn, None);
+        debug!(target: "winit::ime", "Input methods tried: {:?}", (*inner).potential_input_methods);
         let is_fallback = new_im.is_fallback();
         (
             new_im.ok().ok_or_else(|| {
@@ -29,6 +29,9 @@ pub struct Ime {
     // The actual meat of this struct is boxed away, since it needs to have a fixed location in
     // memory so we can pass a pointer to it around.
     inner: Box<ImeInner>,
+    // The window whose input context was last focused, which is kept while the input method is
+    // destroyed.
+    focused_window: Option<ffi::Window>,
 }
 
 impl Ime {
@@ -57,6 +60,8 @@ impl Ime {
             let _ = unsafe { set_instantiate_callback(&xconn, client_data) };
         }));
 
+        debug!(target: "winit::ime", "Input methods tried: {:?}", inner.potential_input_methods);
+
         let is_fallback = input_method.is_fallback();
         if let Some(input_method) = input_method.ok() {
             inner.im = input_method.im;
@@ -69,7 +74,7 @@ impl Ime {
                 }
                 result?;
             }
-            Ok(Ime { xconn, inner })
+            Ok(Ime { xconn, inner, focused_window: None })
         } else {
             Err(ImeCreationError::OpenFailure(inner.potential_input_methods))
         }
@@ -113,6 +118,9 @@ impl Ime {
     }
 
     pub fn remove_context(&mut self, window: ffi::Window) -> Result<bool, XError> {
+        if self.focused_window == Some(window) {
+            self.focused_window = None;
+        }
         if let Some(Some(context)) = self.inner.contexts.remove(&window) {
             unsafe {
                 self.inner.destroy_ic_if_necessary(context.ic)?;
@@ -124,6 +132,7 @@ impl Ime {
     }
 
     pub fn focus(&mut self, window: ffi::Window) -> Result<bool, XError> {
+        self.focused_window = Some(window);
         if self.is_destroyed() {
             return Ok(false);
         }
@@ -135,6 +144,9 @@ impl Ime {
     }
 
     pub fn unfocus(&mut self, window: ffi::Window) -> Result<bool, XError> {
+        if self.focused_window == Some(window) {
+            self.focused_window = None;
+        }
         if self.is_destroyed() {
             return Ok(false);
         }
@@ -145,6 +157,10 @@ impl Ime {
         }
     }
 
+    pub fn focused_window(&self) -> Option<ffi::Window> {
+        self.focused_window
+    }
+
     pub fn send_xim_spot(&mut self, window: ffi::Window, x: i16, y: i16) {
         if self.is_destroyed() {
             return;
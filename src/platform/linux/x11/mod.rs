@@ -7,56 +7,85 @@ mod window;
 mod xdisplay;
 mod dnd;
 mod ime;
+mod xembed;
 pub mod util;
 
 pub use self::monitor::MonitorId;
 pub use self::window::UnownedWindow;
 pub use self::xdisplay::{XConnection, XNotSupported, XError};
 
-use std::{mem, ptr, slice};
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::{env, mem, ptr, slice};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::ffi::CStr;
 use std::ops::Deref;
 use std::os::raw::*;
+use std::os::unix::ffi::OsStringExt;
 use std::sync::{Arc, mpsc, Weak};
 use std::sync::atomic::{self, AtomicBool};
 
 use libc::{self, setlocale, LC_CTYPE};
 
 use {
+    ActivationPolicy,
     ControlFlow,
     CreationError,
+    DamageRect,
     DeviceEvent,
+    DeviceKind,
+    ElementState,
     Event,
     EventsLoopClosed,
     KeyboardInput,
+    LayoutInfo,
     LogicalPosition,
     LogicalSize,
+    SessionEndReason,
     WindowAttributes,
     WindowEvent,
 };
 use events::ModifiersState;
+use platform::MonitorId as PlatformMonitorId;
 use platform::PlatformSpecificWindowBuilderAttributes;
 use self::dnd::{Dnd, DndState};
 use self::ime::{ImeReceiver, ImeSender, ImeCreationError, Ime};
+use self::util::LogError;
+use window::MonitorId as RootMonitorId;
 
 pub struct EventsLoop {
     xconn: Arc<XConnection>,
     wm_delete_window: ffi::Atom,
-    dnd: Dnd,
+    wm_save_yourself: ffi::Atom,
+    dnd: RefCell<Dnd>,
     ime_receiver: ImeReceiver,
     ime_sender: ImeSender,
     ime: RefCell<Ime>,
     randr_event_offset: c_int,
     windows: RefCell<HashMap<WindowId, Weak<UnownedWindow>>>,
     devices: RefCell<HashMap<DeviceId, Device>>,
+    // Reused for the events of each `XI_Motion`, so that input bursts don't allocate.
+    motion_events: RefCell<Vec<Event>>,
     xi2ext: XExtension,
     pending_wakeup: Arc<AtomicBool>,
     root: ffi::Window,
     // A dummy, `InputOnly` window that we can use to receive wakeup events and interrupt blocking
     // `XNextEvent` calls.
     wakeup_dummy_window: ffi::Window,
+    activation_policy: ActivationPolicy,
+    // Properties of the root window for which `X11RootPropertyChanged` is sent.
+    watched_root_properties: RefCell<HashSet<ffi::Atom>>,
+    // The selection owned by the XSETTINGS manager, and the manager whose events are selected, to
+    // follow changes of the DPI set by the desktop environment.
+    xsettings_selection: ffi::Atom,
+    xsettings_manager: Cell<Option<ffi::Window>>,
+    xkb_event_offset: c_int,
+    // The layouts last reported, since XKB sends the same events for many other changes.
+    keyboard_layouts: RefCell<Vec<LayoutInfo>>,
+    // Keys pressed while one of our windows had focus. Detectable auto-repeat is enabled, so a
+    // press of a key that's already in there is a repeat.
+    held_keycodes: RefCell<HashSet<c_uint>>,
+    // The mouse buttons held on any device, as `(deviceid, button)`.
+    held_buttons: RefCell<HashSet<(c_int, c_int)>>,
 }
 
 #[derive(Clone)]
@@ -71,6 +100,7 @@ impl EventsLoop {
         let root = unsafe { (xconn.xlib.XDefaultRootWindow)(xconn.display) };
 
         let wm_delete_window = unsafe { xconn.get_atom_unchecked(b"WM_DELETE_WINDOW\0") };
+        let wm_save_yourself = unsafe { xconn.get_atom_unchecked(b"WM_SAVE_YOURSELF\0") };
 
         let dnd = Dnd::new(Arc::clone(&xconn))
             .expect("Failed to call XInternAtoms when initializing drag and drop");
@@ -142,22 +172,66 @@ impl EventsLoop {
             )
         };
 
+        let xsettings_selection = xconn.get_xsettings_selection();
+
+        let xkb_event_offset = unsafe {
+            let (mut opcode, mut event_offset, mut error_offset) = (0, 0, 0);
+            // The version of XKB that winit is written against, which is the only one there is.
+            let (mut major, mut minor) = (1, 0);
+            (xconn.xlib.XkbQueryExtension)(
+                xconn.display,
+                &mut opcode,
+                &mut event_offset,
+                &mut error_offset,
+                &mut major,
+                &mut minor,
+            );
+            event_offset
+        };
+
         let result = EventsLoop {
             xconn,
             wm_delete_window,
-            dnd,
+            wm_save_yourself,
+            dnd: RefCell::new(dnd),
             ime_receiver,
             ime_sender,
             ime,
             randr_event_offset,
             windows: Default::default(),
             devices: Default::default(),
+            motion_events: Default::default(),
             xi2ext,
             pending_wakeup: Default::default(),
             root,
             wakeup_dummy_window,
+            activation_policy: ActivationPolicy::Regular,
+            watched_root_properties: Default::default(),
+            xsettings_selection,
+            xsettings_manager: Cell::new(None),
+            xkb_event_offset,
+            keyboard_layouts: Default::default(),
+            held_keycodes: Default::default(),
+            held_buttons: Default::default(),
         };
 
+        *result.keyboard_layouts.borrow_mut() = result.keyboard_layouts();
+        let xkb_mask = ffi::XkbNewKeyboardNotifyMask | ffi::XkbStateNotifyMask | ffi::XkbNamesNotifyMask;
+        if let Some(flusher) = result.xconn.select_xkb_events(util::XKB_USE_CORE_KBD, xkb_mask) {
+            flusher.queue();
+        }
+
+        // `RESOURCE_MANAGER` changes when the `Xft.dpi` resource is set, and XSETTINGS managers
+        // announce themselves with a `MANAGER` message, sent with `StructureNotifyMask`.
+        unsafe {
+            (result.xconn.xlib.XSelectInput)(
+                result.xconn.display,
+                root,
+                ffi::PropertyChangeMask | ffi::StructureNotifyMask,
+            );
+        }
+        result.watch_xsettings_manager();
+
         // Register for device hotplug events
         // (The request buffer is flushed during `init_device`)
         result.xconn.select_xinput_events(
@@ -177,6 +251,14 @@ impl EventsLoop {
         &self.xconn
     }
 
+    pub fn get_primary_monitor(&self) -> MonitorId {
+        monitor::get_primary_monitor(&self.xconn)
+    }
+
+    pub fn get_available_monitors(&self) -> Vec<MonitorId> {
+        monitor::get_available_monitors(&self.xconn)
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy {
             pending_wakeup: Arc::downgrade(&self.pending_wakeup),
@@ -185,7 +267,150 @@ impl EventsLoop {
         }
     }
 
-    pub fn poll_events<F>(&mut self, mut callback: F)
+    pub fn shutdown(self) {
+        let xconn = Arc::clone(&self.xconn);
+        drop(self);
+        // The connection outlives the events loop, so the requests made while dropping it have
+        // to reach the server now, rather than with the next events loop's.
+        unsafe { (xconn.xlib.XSync)(xconn.display, ffi::False) };
+    }
+
+    pub fn set_activation_policy(&mut self, activation_policy: ActivationPolicy) {
+        self.activation_policy = activation_policy;
+    }
+
+    // Other events are passed to the callback as soon as they're translated.
+    pub fn reserve_events(&self, capacity: usize) {
+        self.motion_events.borrow_mut().reserve(capacity);
+    }
+
+    pub fn get_root_property(&self, property: &str, property_type: &str) -> Option<util::PropertyValue> {
+        let property = self.xconn.get_atom_from_str(property)?;
+        let property_type = self.xconn.get_atom_from_str(property_type)?;
+        self.xconn.get_property_value(self.root, property, property_type).ok()
+    }
+
+    pub fn change_root_property(&self, property: &str, property_type: &str, value: &util::PropertyValue) {
+        let property = self.xconn.get_atom_from_str(property);
+        let property_type = self.xconn.get_atom_from_str(property_type);
+        if let (Some(property), Some(property_type)) = (property, property_type) {
+            self.xconn.change_property_value(self.root, property, property_type, value)
+                .flush()
+                .log_error("Failed to change root window property");
+        }
+    }
+
+    pub fn watch_root_property(&self, property: &str) {
+        let property = match self.xconn.get_atom_from_str(property) {
+            Some(property) => property,
+            None => return,
+        };
+        // The root window's `PropertyChangeMask` is always selected.
+        self.watched_root_properties.borrow_mut().insert(property);
+    }
+
+    // Selects the events of the XSETTINGS manager, if there's one, to know when its settings
+    // change or when it exits.
+    fn watch_xsettings_manager(&self) {
+        let manager = self.xconn.get_xsettings_manager();
+        if let Some(manager) = manager {
+            unsafe {
+                (self.xconn.xlib.XSelectInput)(
+                    self.xconn.display,
+                    manager,
+                    ffi::PropertyChangeMask | ffi::StructureNotifyMask,
+                );
+            }
+            // The manager can exit before receiving the request, which isn't worth reporting.
+            let _ = self.xconn.sync_with_server();
+        }
+        self.xsettings_manager.set(manager);
+    }
+
+    /// Returns our visible windows from front to back.
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        let windows = self.windows.borrow();
+        self.xconn
+            .get_client_list_stacking(self.root)
+            .into_iter()
+            .rev()
+            .map(WindowId)
+            .filter(|window_id| windows.contains_key(window_id))
+            .collect()
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        let mut focused_window = 0;
+        let mut revert_to = 0;
+        unsafe {
+            (self.xconn.xlib.XGetInputFocus)(self.xconn.display, &mut focused_window, &mut revert_to);
+        }
+        FocusSnapshot {
+            window: if self.window_exists(focused_window) { Some(focused_window) } else { None },
+            ime_window: self.ime.borrow().focused_window(),
+        }
+    }
+
+    pub fn keyboard_layouts(&self) -> Vec<LayoutInfo> {
+        let (names, active) = self.xconn.get_xkb_groups();
+        names
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| LayoutInfo { name, active: index == active })
+            .collect()
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        let info = match DeviceInfo::get(&self.xconn, ffi::XIAllDevices) {
+            Some(info) => info,
+            None => return Vec::new(),
+        };
+        info.iter()
+            .filter(|info| Device::physical_device(info))
+            .map(|info| ::DeviceInfo {
+                id: mkdid(info.deviceid),
+                name: unsafe { CStr::from_ptr(info.name).to_string_lossy().into_owned() },
+                kind: match info._use {
+                    ffi::XISlaveKeyboard => DeviceKind::Keyboard,
+                    ffi::XISlavePointer => DeviceKind::Mouse,
+                    // Floating slaves aren't attached to a master device, which is usually the
+                    // case of tablets and other devices the X server doesn't use as is.
+                    _ => DeviceKind::Hid,
+                },
+            })
+            .collect()
+    }
+
+    pub fn restore_focus(&self, snapshot: &FocusSnapshot) {
+        if let Some(window) = snapshot.window {
+            if self.window_exists(window) {
+                self.xconn.activate_window(window, self.root).queue();
+            }
+        }
+
+        // The input context of the focused window is also focused when it receives `FocusIn`, but
+        // the snapshot may have been taken while another one was focused directly.
+        {
+            let mut ime = self.ime.borrow_mut();
+            let ime_window = match snapshot.ime_window {
+                Some(window) if self.window_exists(window) => Some(window),
+                _ => None,
+            };
+            if ime.focused_window() != ime_window {
+                if let Some(window) = ime.focused_window() {
+                    let _ = ime.unfocus(window);
+                }
+                if let Some(window) = ime_window {
+                    let _ = ime.focus(window);
+                }
+            }
+        }
+
+        // `XSetInputFocus` fails if the window isn't viewable, in which case focus is left alone.
+        let _ = self.xconn.sync_with_server();
+    }
+
+    pub fn poll_events<F>(&self, mut callback: F)
         where F: FnMut(Event)
     {
         let mut xev = unsafe { mem::uninitialized() };
@@ -204,7 +429,7 @@ impl EventsLoop {
         }
     }
 
-    pub fn run_forever<F>(&mut self, mut callback: F)
+    pub fn run_forever<F>(&self, mut callback: F)
         where F: FnMut(Event) -> ControlFlow
     {
         let mut xev = unsafe { mem::uninitialized() };
@@ -231,9 +456,25 @@ impl EventsLoop {
         }
     }
 
-    fn process_event<F>(&mut self, xev: &mut ffi::XEvent, mut callback: F)
+    fn process_event<F>(&self, xev: &mut ffi::XEvent, mut callback: F)
         where F: FnMut(Event)
     {
+        // The keys pressed in a window that embeds an XEmbed client go to the client, without
+        // going through the input method of the window.
+        if xev.get_type() == ffi::KeyPress || xev.get_type() == ffi::KeyRelease {
+            let xkev: &mut ffi::XKeyEvent = xev.as_mut();
+            let client = self.with_window(xkev.window, |window| {
+                window.shared_state.lock().embedded_client
+            });
+            if let Some(Some(client)) = client {
+                xkev.window = client;
+                self.xconn.send_event(client, None, *xkev)
+                    .flush()
+                    .log_error("Failed to forward key event to embedded window");
+                return;
+            }
+        }
+
         // XFilterEvent tells us when an event has been discarded by the input method.
         // Specifically, this involves all of the KeyPress events in compose/pre-edit sequences,
         // along with an extra copy of the KeyRelease events. This also prevents backspace and
@@ -242,6 +483,9 @@ impl EventsLoop {
             xev,
             { let xev: &ffi::XAnyEvent = xev.as_ref(); xev.window }
         ) } {
+            if xev.get_type() == ffi::KeyPress {
+                self.send_filtered_dead_key(xev.as_mut(), &mut callback);
+            }
             return;
         }
 
@@ -249,22 +493,61 @@ impl EventsLoop {
         match event_type {
             ffi::MappingNotify => {
                 unsafe { (self.xconn.xlib.XRefreshKeyboardMapping)(xev.as_mut()); }
-                self.xconn.check_errors().expect("Failed to call XRefreshKeyboardMapping");
+                self.xconn.check_errors().log_error("Failed to call XRefreshKeyboardMapping");
             }
 
             ffi::ClientMessage => {
                 let client_msg: &ffi::XClientMessageEvent = xev.as_ref();
 
+                if client_msg.window == self.root {
+                    let manager_atom = unsafe { self.xconn.get_atom_unchecked(b"MANAGER\0") };
+                    if client_msg.message_type == manager_atom
+                        && client_msg.data.get_long(1) as ffi::Atom == self.xsettings_selection
+                    {
+                        // A new XSETTINGS manager, with settings of its own.
+                        self.watch_xsettings_manager();
+                        self.refresh_monitors(&mut callback);
+                    }
+                    return;
+                }
+
+                let xembed_atom = unsafe { self.xconn.get_atom_unchecked(b"_XEMBED\0") };
+                if client_msg.message_type == xembed_atom {
+                    self.handle_xembed_message(client_msg, &mut callback);
+                    return;
+                }
+
+                // `dnd` is only borrowed while handling XDnD messages, and never across `callback`,
+                // which can create windows.
+                let dnd_atoms = self.dnd.borrow().atoms;
+
                 let window = client_msg.window;
                 let window_id = mkwid(window);
 
                 if client_msg.data.get_long(0) as ffi::Atom == self.wm_delete_window {
                     callback(Event::WindowEvent { window_id, event: WindowEvent::CloseRequested });
-                } else if client_msg.message_type == self.dnd.atoms.enter {
+                } else if client_msg.data.get_long(0) as ffi::Atom == self.wm_save_yourself {
+                    callback(Event::SessionEnding { reason: SessionEndReason::Unknown });
+                    // The session manager waits for `WM_COMMAND` to be updated, which means that
+                    // the state was saved. Applications save on their own time, so this can't wait
+                    // for them.
+                    let command: Vec<c_uchar> = env::args_os()
+                        .flat_map(|arg| arg.into_vec().into_iter().chain(Some(0)))
+                        .collect();
+                    let wm_command = unsafe { self.xconn.get_atom_unchecked(b"WM_COMMAND\0") };
+                    self.xconn.change_property(
+                        window,
+                        wm_command,
+                        ffi::XA_STRING,
+                        util::PropMode::Replace,
+                        &command,
+                    ).flush().log_error("Failed to set WM_COMMAND");
+                } else if client_msg.message_type == dnd_atoms.enter {
+                    let mut dnd = self.dnd.borrow_mut();
                     let source_window = client_msg.data.get_long(0) as c_ulong;
                     let flags = client_msg.data.get_long(1);
                     let version = flags >> 24;
-                    self.dnd.version = Some(version);
+                    dnd.version = Some(version);
                     let has_more_types = flags - (flags & (c_long::max_value() - 1)) == 1;
                     if !has_more_types {
                         let type_list = vec![
@@ -272,11 +555,13 @@ impl EventsLoop {
                             client_msg.data.get_long(3) as c_ulong,
                             client_msg.data.get_long(4) as c_ulong
                         ];
-                        self.dnd.type_list = Some(type_list);
-                    } else if let Ok(more_types) = unsafe { self.dnd.get_type_list(source_window) } {
-                        self.dnd.type_list = Some(more_types);
+                        dnd.type_list = Some(type_list);
+                    } else if let Ok(more_types) = unsafe { dnd.get_type_list(source_window) } {
+                        dnd.type_list = Some(more_types);
                     }
-                } else if client_msg.message_type == self.dnd.atoms.position {
+                } else if client_msg.message_type == dnd_atoms.position {
+                    let mut dnd = self.dnd.borrow_mut();
+
                     // This event occurs every time the mouse moves while a file's being dragged
                     // over our window. We emit HoveredFile in response; while the Mac OS X backend
                     // does that upon a drag entering, XDnD doesn't have access to the actual drop
@@ -297,21 +582,21 @@ impl EventsLoop {
                     //let y = packed_coordinates & !(x << shift);
 
                     // By our own state flow, version should never be None at this point.
-                    let version = self.dnd.version.unwrap_or(5);
+                    let version = dnd.version.unwrap_or(5);
 
                     // Action is specified in versions 2 and up, though we don't need it anyway.
                     //let action = client_msg.data.get_long(4);
 
-                    let accepted = if let Some(ref type_list) = self.dnd.type_list {
-                        type_list.contains(&self.dnd.atoms.uri_list)
+                    let accepted = if let Some(ref type_list) = dnd.type_list {
+                        type_list.contains(&dnd.atoms.uri_list)
                     } else {
                         false
                     };
 
                     if accepted {
-                        self.dnd.source_window = Some(source_window);
+                        dnd.source_window = Some(source_window);
                         unsafe {
-                            if self.dnd.result.is_none() {
+                            if dnd.result.is_none() {
                                 let time = if version >= 1 {
                                     client_msg.data.get_long(3) as c_ulong
                                 } else {
@@ -319,44 +604,69 @@ impl EventsLoop {
                                     ffi::CurrentTime
                                 };
                                 // This results in the SelectionNotify event below
-                                self.dnd.convert_selection(window, time);
+                                dnd.convert_selection(window, time);
                             }
-                            self.dnd.send_status(window, source_window, DndState::Accepted)
-                                .expect("Failed to send XDnD status message.");
+                            dnd.send_status(window, source_window, DndState::Accepted)
+                                .log_error("Failed to send XDnD status message");
                         }
                     } else {
                         unsafe {
-                            self.dnd.send_status(window, source_window, DndState::Rejected)
-                                .expect("Failed to send XDnD status message.");
-                            self.dnd.send_finished(window, source_window, DndState::Rejected)
-                                .expect("Failed to send XDnD finished message.");
+                            dnd.send_status(window, source_window, DndState::Rejected)
+                                .log_error("Failed to send XDnD status message");
+                            dnd.send_finished(window, source_window, DndState::Rejected)
+                                .log_error("Failed to send XDnD finished message");
                         }
-                        self.dnd.reset();
+                        dnd.reset();
                     }
-                } else if client_msg.message_type == self.dnd.atoms.drop {
-                    if let Some(source_window) = self.dnd.source_window {
-                        if let Some(Ok(ref path_list)) = self.dnd.result {
+                } else if client_msg.message_type == dnd_atoms.drop {
+                    let (source_window, path_list) = {
+                        let mut dnd = self.dnd.borrow_mut();
+                        let source_window = dnd.source_window;
+                        let path_list = match dnd.result.take() {
+                            Some(Ok(path_list)) => path_list,
+                            _ => Vec::new(),
+                        };
+                        dnd.reset();
+                        (source_window, path_list)
+                    };
+                    if let Some(source_window) = source_window {
+                        if !path_list.is_empty() {
+                            // `XdndDrop` carries neither the drop position nor the modifiers, so we
+                            // query the pointer's state instead.
+                            let (position, modifiers) = self.xconn
+                                .query_pointer(window, util::VIRTUAL_CORE_POINTER)
+                                .map(|pointer_state| (
+                                    (pointer_state.win_x, pointer_state.win_y),
+                                    pointer_state.get_modifier_state(),
+                                ))
+                                .unwrap_or_default();
+                            let dpi_factor = self.with_window(window, |window| window.get_hidpi_factor())
+                                .unwrap_or(1.0);
+                            let position = LogicalPosition::from_physical(position, dpi_factor);
                             for path in path_list {
                                 callback(Event::WindowEvent {
                                     window_id,
-                                    event: WindowEvent::DroppedFile(path.clone()),
+                                    event: WindowEvent::DroppedFile {
+                                        path,
+                                        position,
+                                        modifiers,
+                                    },
                                 });
                             }
                         }
+                        let dnd = self.dnd.borrow();
                         unsafe {
-                            self.dnd.send_finished(window, source_window, DndState::Accepted)
-                                .expect("Failed to send XDnD finished message.");
+                            dnd.send_finished(window, source_window, DndState::Accepted)
+                                .log_error("Failed to send XDnD finished message");
                         }
                     }
-                    self.dnd.reset();
-                } else if client_msg.message_type == self.dnd.atoms.leave {
-                    self.dnd.reset();
+                } else if client_msg.message_type == dnd_atoms.leave {
+                    self.dnd.borrow_mut().reset();
                     callback(Event::WindowEvent {
                         window_id,
                         event: WindowEvent::HoveredFileCancelled,
                     });
-                } else if self.pending_wakeup.load(atomic::Ordering::Relaxed) {
-                    self.pending_wakeup.store(false, atomic::Ordering::Relaxed);
+                } else if self.pending_wakeup.swap(false, atomic::Ordering::AcqRel) {
                     callback(Event::Awakened);
                 }
             }
@@ -367,33 +677,39 @@ impl EventsLoop {
                 let window = xsel.requestor;
                 let window_id = mkwid(window);
 
-                if xsel.property == self.dnd.atoms.selection {
-                    let mut result = None;
-
-                    // This is where we receive data from drag and drop
-                    if let Ok(mut data) = unsafe { self.dnd.read_data(window) } {
-                        let parse_result = self.dnd.parse_data(&mut data);
-                        if let Ok(ref path_list) = parse_result {
-                            for path in path_list {
-                                callback(Event::WindowEvent {
-                                    window_id,
-                                    event: WindowEvent::HoveredFile(path.clone()),
-                                });
-                            }
-                        }
-                        result = Some(parse_result);
+                let path_list = {
+                    let mut dnd = self.dnd.borrow_mut();
+                    if xsel.property != dnd.atoms.selection {
+                        return;
                     }
-
-                    self.dnd.result = result;
+                    // This is where we receive data from drag and drop
+                    let result = unsafe { dnd.read_data(window) }
+                        .ok()
+                        .map(|mut data| dnd.parse_data(&mut data));
+                    let path_list = match result {
+                        Some(Ok(ref path_list)) => path_list.clone(),
+                        _ => Vec::new(),
+                    };
+                    dnd.result = result;
+                    path_list
+                };
+                for path in path_list {
+                    callback(Event::WindowEvent {
+                        window_id,
+                        event: WindowEvent::HoveredFile(path),
+                    });
                 }
             }
 
             ffi::ConfigureNotify => {
                 #[derive(Debug, Default)]
                 struct Events {
+                    resize_started: Option<WindowEvent>,
                     resized: Option<WindowEvent>,
+                    move_started: Option<WindowEvent>,
                     moved: Option<WindowEvent>,
                     dpi_changed: Option<WindowEvent>,
+                    fullscreen_changed: Option<WindowEvent>,
                 }
 
                 let xev: &ffi::XConfigureEvent = xev.as_ref();
@@ -412,11 +728,21 @@ impl EventsLoop {
                     let new_inner_size = (xev.width as u32, xev.height as u32);
                     let new_inner_position = (xev.x as i32, xev.y as i32);
 
-                    let monitor = window.get_current_monitor(); // This must be done *before* locking!
+                    let hidpi_factor = window.get_hidpi_factor(); // This must be done *before* locking!
                     let mut shared_state_lock = window.shared_state.lock();
 
                     let (resized, moved) = {
                         let resized = util::maybe_change(&mut shared_state_lock.size, new_inner_size);
+                        if let (true, Some(client)) = (resized, shared_state_lock.embedded_client) {
+                            unsafe {
+                                (window.xconn.xlib.XResizeWindow)(
+                                    window.xconn.display,
+                                    client,
+                                    new_inner_size.0,
+                                    new_inner_size.1,
+                                );
+                            }
+                        }
                         let moved = if is_synthetic {
                             util::maybe_change(&mut shared_state_lock.inner_position, new_inner_position)
                         } else {
@@ -456,8 +782,27 @@ impl EventsLoop {
 
                     let mut events = Events::default();
 
+                    // X11 doesn't tell when the user moves or resizes the window, so that's guessed
+                    // from the window changing while a mouse button is held.
+                    let buttons_held = !self.held_buttons.borrow().is_empty();
+                    if resized
+                        && buttons_held
+                        && !mem::replace(&mut shared_state_lock.interactive_resize, true)
+                    {
+                        events.resize_started = Some(WindowEvent::ResizeStarted);
+                    }
+                    // Resizing from the top or left edges also moves the window.
+                    if moved
+                        && !resized
+                        && buttons_held
+                        && !shared_state_lock.interactive_resize
+                        && !mem::replace(&mut shared_state_lock.interactive_move, true)
+                    {
+                        events.move_started = Some(WindowEvent::MoveStarted);
+                    }
+
                     if resized {
-                        let logical_size = LogicalSize::from_physical(new_inner_size, monitor.hidpi_factor);
+                        let logical_size = LogicalSize::from_physical(new_inner_size, hidpi_factor);
                         events.resized = Some(WindowEvent::Resized(logical_size));
                     }
 
@@ -474,7 +819,7 @@ impl EventsLoop {
                         let outer = frame_extents.inner_pos_to_outer(new_inner_position.0, new_inner_position.1);
                         shared_state_lock.position = Some(outer);
                         if moved {
-                            let logical_position = LogicalPosition::from_physical(outer, monitor.hidpi_factor);
+                            let logical_position = LogicalPosition::from_physical(outer, hidpi_factor);
                             events.moved = Some(WindowEvent::Moved(logical_position));
                         }
                         outer
@@ -497,12 +842,18 @@ impl EventsLoop {
                             });
                         let new_hidpi_factor = {
                             let window_rect = util::AaRect::new(new_outer_position, new_inner_size);
-                            let monitor = self.xconn.get_monitor_for_window(Some(window_rect));
+                            let monitor = monitor::get_monitor_for_window(
+                                &self.xconn,
+                                Some(window_rect),
+                                window.dynamic_scaling,
+                            );
                             let new_hidpi_factor = monitor.hidpi_factor;
                             shared_state_lock.last_monitor = Some(monitor);
                             new_hidpi_factor
                         };
-                        if last_hidpi_factor != new_hidpi_factor {
+                        // Windows with a static DPI factor still keep track of their monitor, but
+                        // aren't rescaled when it changes.
+                        if last_hidpi_factor != new_hidpi_factor && window.static_hidpi_factor.is_none() {
                             events.dpi_changed = Some(WindowEvent::HiDpiFactorChanged(new_hidpi_factor));
                             let (new_width, new_height, flusher) = window.adjust_for_dpi(
                                 last_hidpi_factor,
@@ -512,23 +863,167 @@ impl EventsLoop {
                             );
                             flusher.queue();
                             shared_state_lock.dpi_adjusted = Some((new_width, new_height));
+                            window.update_cursor_for_hidpi_factor(new_hidpi_factor);
                         }
                     }
 
+                    // The window has reached the monitor `set_fullscreen` moved it to, which was
+                    // just looked up above.
+                    if moved
+                        && mem::replace(&mut shared_state_lock.fullscreen_monitor_switch, false)
+                    {
+                        let monitor = shared_state_lock.last_monitor
+                            .clone()
+                            .map(|monitor| RootMonitorId { inner: PlatformMonitorId::X(monitor) });
+                        events.fullscreen_changed = Some(WindowEvent::FullscreenChanged(monitor));
+                    }
+
                     events
                 });
 
                 if let Some(events) = events {
                     let window_id = mkwid(xwindow);
+                    if let Some(event) = events.resize_started {
+                        callback(Event::WindowEvent { window_id, event });
+                    }
                     if let Some(event) = events.resized {
                         callback(Event::WindowEvent { window_id, event });
                     }
+                    if let Some(event) = events.move_started {
+                        callback(Event::WindowEvent { window_id, event });
+                    }
                     if let Some(event) = events.moved {
                         callback(Event::WindowEvent { window_id, event });
                     }
                     if let Some(event) = events.dpi_changed {
                         callback(Event::WindowEvent { window_id, event });
                     }
+                    if let Some(event) = events.fullscreen_changed {
+                        callback(Event::WindowEvent { window_id, event });
+                    }
+                }
+            }
+
+            ffi::PropertyNotify => {
+                let xev: &ffi::XPropertyEvent = xev.as_ref();
+                let xwindow = xev.window;
+
+                if xwindow == self.root {
+                    let resource_manager_atom = unsafe {
+                        self.xconn.get_atom_unchecked(b"RESOURCE_MANAGER\0")
+                    };
+                    if xev.atom == resource_manager_atom {
+                        // The `Xft.dpi` resource may have changed.
+                        self.refresh_monitors(&mut callback);
+                    }
+                    if self.watched_root_properties.borrow().contains(&xev.atom) {
+                        if let Some(property) = self.xconn.get_atom_name(xev.atom) {
+                            callback(Event::X11RootPropertyChanged(property));
+                        }
+                    }
+                    return;
+                }
+
+                let xembed_info_atom = unsafe { self.xconn.get_atom_unchecked(b"_XEMBED_INFO\0") };
+                if xev.atom == xembed_info_atom && !self.window_exists(xwindow) {
+                    // An embedded client wants to be mapped or unmapped.
+                    if self.find_embedder(xwindow).is_some() {
+                        let mapped = xembed::get_info_flags(&self.xconn, xwindow)
+                            .map_or(true, |flags| flags & xembed::XEMBED_MAPPED != 0);
+                        unsafe {
+                            if mapped {
+                                (self.xconn.xlib.XMapWindow)(self.xconn.display, xwindow);
+                            } else {
+                                (self.xconn.xlib.XUnmapWindow)(self.xconn.display, xwindow);
+                            }
+                        }
+                    }
+                    return;
+                }
+
+                if Some(xwindow) == self.xsettings_manager.get() {
+                    let settings_atom = unsafe {
+                        self.xconn.get_atom_unchecked(b"_XSETTINGS_SETTINGS\0")
+                    };
+                    if xev.atom == settings_atom {
+                        self.refresh_monitors(&mut callback);
+                    }
+                    return;
+                }
+
+                let watched = self.with_window(xwindow, |window| {
+                    window.shared_state.lock().watched_properties.contains(&xev.atom)
+                });
+                if watched == Some(true) {
+                    if let Some(property) = self.xconn.get_atom_name(xev.atom) {
+                        callback(Event::WindowEvent {
+                            window_id: mkwid(xwindow),
+                            event: WindowEvent::X11PropertyChanged(property),
+                        });
+                    }
+                }
+
+                let state_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE\0") };
+                if xev.atom == state_atom {
+                    let changed = self.with_window(xwindow, |window| {
+                        let fullscreen = window.is_fullscreen_state_set();
+                        let maximized = window.is_maximized_state_set();
+                        let (fullscreen_changed, maximized_changed) = {
+                            let mut shared_state_lock = window.shared_state.lock();
+                            (
+                                mem::replace(&mut shared_state_lock.fullscreen, fullscreen)
+                                    != fullscreen,
+                                mem::replace(&mut shared_state_lock.maximized, maximized)
+                                    != maximized,
+                            )
+                        };
+                        // The monitor is looked up once the lock is released, since that takes it.
+                        let monitor = if fullscreen {
+                            Some(RootMonitorId {
+                                inner: PlatformMonitorId::X(window.get_current_monitor()),
+                            })
+                        } else {
+                            None
+                        };
+                        (
+                            if fullscreen_changed { Some(monitor) } else { None },
+                            if maximized_changed { Some(maximized) } else { None },
+                        )
+                    });
+                    if let Some((fullscreen, maximized)) = changed {
+                        if let Some(monitor) = fullscreen {
+                            callback(Event::WindowEvent {
+                                window_id: mkwid(xwindow),
+                                event: WindowEvent::FullscreenChanged(monitor),
+                            });
+                        }
+                        if let Some(maximized) = maximized {
+                            callback(Event::WindowEvent {
+                                window_id: mkwid(xwindow),
+                                event: WindowEvent::Maximized(maximized),
+                            });
+                        }
+                    }
+                }
+
+                // The window manager sets `WM_STATE` when it minimizes or restores the window.
+                let wm_state_atom = unsafe { self.xconn.get_atom_unchecked(b"WM_STATE\0") };
+                if xev.atom == wm_state_atom {
+                    let changed = self.with_window(xwindow, |window| {
+                        let minimized = window.is_iconic_state_set();
+                        let mut shared_state_lock = window.shared_state.lock();
+                        if mem::replace(&mut shared_state_lock.minimized, minimized) != minimized {
+                            Some(minimized)
+                        } else {
+                            None
+                        }
+                    });
+                    if let Some(Some(minimized)) = changed {
+                        callback(Event::WindowEvent {
+                            window_id: mkwid(xwindow),
+                            event: WindowEvent::Minimized(minimized),
+                        });
+                    }
                 }
             }
 
@@ -542,6 +1037,14 @@ impl EventsLoop {
                 // effect is that we waste some time trying to query unsupported properties.
                 self.xconn.update_cached_wm_info(self.root);
 
+                // An embedded client that's moved out of its embedder is let go.
+                if let Some(embedder) = self.find_embedder(xev.window) {
+                    if xev.parent != embedder.get_xlib_window() {
+                        embedder.shared_state.lock().embedded_client = None;
+                    }
+                    return;
+                }
+
                 self.with_window(xev.window, |window| {
                     window.invalidate_cached_frame_extents();
                 });
@@ -553,6 +1056,18 @@ impl EventsLoop {
                 let window = xev.window;
                 let window_id = mkwid(window);
 
+                if Some(window) == self.xsettings_manager.get() {
+                    // Another manager may have replaced it, or the `Xft.dpi` resource now applies.
+                    self.watch_xsettings_manager();
+                    self.refresh_monitors(&mut callback);
+                    return;
+                }
+
+                if let Some(embedder) = self.find_embedder(window) {
+                    embedder.shared_state.lock().embedded_client = None;
+                    return;
+                }
+
                 // In the event that the window's been destroyed without being dropped first, we
                 // cleanup again here.
                 self.windows.borrow_mut().remove(&WindowId(window));
@@ -562,7 +1077,7 @@ impl EventsLoop {
                 self.ime
                     .borrow_mut()
                     .remove_context(window)
-                    .expect("Failed to destroy input context");
+                    .log_error("Failed to destroy input context");
 
                 callback(Event::WindowEvent { window_id, event: WindowEvent::Destroyed });
             }
@@ -573,7 +1088,42 @@ impl EventsLoop {
                 let window = xev.window;
                 let window_id = mkwid(window);
 
-                callback(Event::WindowEvent { window_id, event: WindowEvent::Refresh });
+                // `count` is the number of `Expose` events that follow for the same window, so
+                // their rectangles are gathered until it reaches 0.
+                let damage = self.with_window(window, |window| {
+                    let mut shared_state_lock = window.shared_state.lock();
+                    shared_state_lock.damage.push(DamageRect {
+                        x: xev.x as u32,
+                        y: xev.y as u32,
+                        width: xev.width as u32,
+                        height: xev.height as u32,
+                    });
+                    if xev.count == 0 {
+                        Some(mem::replace(&mut shared_state_lock.damage, Vec::new()))
+                    } else {
+                        None
+                    }
+                });
+                if let Some(Some(damage)) = damage {
+                    callback(Event::WindowEvent {
+                        window_id,
+                        event: WindowEvent::Refresh { damage: Some(damage) },
+                    });
+                }
+            }
+
+            ffi::VisibilityNotify => {
+                let xev: &ffi::XVisibilityEvent = xev.as_ref();
+                let xwindow = xev.window;
+                let occluded = xev.state == ffi::VisibilityFullyObscured;
+                self.update_occlusion(xwindow, occluded, &mut callback);
+            }
+
+            ffi::UnmapNotify => {
+                // Minimizing a window unmaps it without generating `VisibilityNotify`. Mapping it
+                // again does, so this is the only case that needs special handling.
+                let xev: &ffi::XUnmapEvent = xev.as_ref();
+                self.update_occlusion(xev.window, true, &mut callback);
             }
 
             ffi::KeyPress | ffi::KeyRelease => {
@@ -596,6 +1146,14 @@ impl EventsLoop {
                 let device = util::VIRTUAL_CORE_KEYBOARD;
                 let device_id = mkdid(device);
 
+                // The text is looked up first, so that it can be sent along with the key.
+                let written = if state == Pressed {
+                    let ic = self.ime.borrow().get_context(window);
+                    ic.map(|ic| self.xconn.lookup_utf8(ic, xkev))
+                } else {
+                    None
+                };
+
                 // When a compose sequence or IME pre-edit is finished, it ends in a KeyPress with
                 // a keycode of 0.
                 if xkev.keycode != 0 {
@@ -615,11 +1173,18 @@ impl EventsLoop {
                             &mut keysym,
                             ptr::null_mut(),
                         );
-                        self.xconn.check_errors().expect("Failed to lookup keysym");
+                        self.xconn.check_errors().log_error("Failed to lookup keysym");
                         keysym
                     };
                     let virtual_keycode = events::keysym_to_element(keysym as c_uint);
 
+                    let is_repeat = if state == Pressed {
+                        !self.held_keycodes.borrow_mut().insert(xkev.keycode)
+                    } else {
+                        self.held_keycodes.borrow_mut().remove(&xkev.keycode);
+                        false
+                    };
+
                     callback(Event::WindowEvent {
                         window_id,
                         event: WindowEvent::KeyboardInput {
@@ -629,18 +1194,22 @@ impl EventsLoop {
                                 scancode: xkev.keycode - 8,
                                 virtual_keycode,
                                 modifiers,
+                                is_composing: false,
+                                is_synthetic: false,
+                                is_repeat,
+                                text: match written {
+                                    Some(ref written) if !written.is_empty() => Some(written.clone()),
+                                    _ => None,
+                                },
+                                // Dead keys only get here when there's no input method to combine
+                                // them, since the input method filters them otherwise.
+                                is_dead_key: state == Pressed && events::is_dead_keysym(keysym as c_uint),
                             },
                         }
                     });
                 }
 
-                if state == Pressed {
-                    let written = if let Some(ic) = self.ime.borrow().get_context(window) {
-                        self.xconn.lookup_utf8(ic, xkev)
-                    } else {
-                        return;
-                    };
-
+                if let Some(written) = written {
                     for chr in written.chars() {
                         let event = Event::WindowEvent {
                             window_id,
@@ -662,7 +1231,7 @@ impl EventsLoop {
                 use events::ElementState::{Pressed, Released};
                 use events::MouseButton::{Left, Right, Middle, Other};
                 use events::MouseScrollDelta::LineDelta;
-                use events::{Touch, TouchPhase};
+                use events::{Force, Touch, TouchPhase};
 
                 match xev.evtype {
                     ffi::XI_ButtonPress | ffi::XI_ButtonRelease => {
@@ -684,6 +1253,17 @@ impl EventsLoop {
                         } else {
                             Released
                         };
+
+                        if state == Pressed && xev.detail as u32 == ffi::Button1 {
+                            let handled = self.with_window(xev.event, |window| {
+                                window.handle_hit_test_press(
+                                    xev.deviceid,
+                                    (xev.event_x, xev.event_y),
+                                    (xev.root_x, xev.root_y),
+                                )
+                            });
+                            if handled.unwrap_or(false) { return; }
+                        }
                         match xev.detail as u32 {
                             ffi::Button1 => callback(Event::WindowEvent {
                                 window_id,
@@ -729,6 +1309,7 @@ impl EventsLoop {
                                             _ => unreachable!(),
                                         },
                                         phase: TouchPhase::Moved,
+                                        momentum_phase: None,
                                         modifiers,
                                     },
                                 });
@@ -782,7 +1363,7 @@ impl EventsLoop {
                         }
 
                         // More gymnastics, for self.devices
-                        let mut events = Vec::new();
+                        let mut events = mem::replace(&mut *self.motion_events.borrow_mut(), Vec::new());
                         {
                             let mask = unsafe { slice::from_raw_parts(xev.valuators.mask, xev.valuators.mask_len as usize) };
                             let mut devices = self.devices.borrow_mut();
@@ -808,6 +1389,7 @@ impl EventsLoop {
                                                     ScrollOrientation::Vertical => LineDelta(0.0, -delta as f32),
                                                 },
                                                 phase: TouchPhase::Moved,
+                                                momentum_phase: None,
                                                 modifiers,
                                             },
                                         });
@@ -825,11 +1407,17 @@ impl EventsLoop {
                                 }
                             }
                         }
-                        for event in events {
+                        for event in events.drain(..) {
                             callback(event);
                         }
+                        *self.motion_events.borrow_mut() = events;
                     }
 
+                    // The cursor only moved between a window and a child of it, like the one the
+                    // cursor is confined to by `set_cursor_confine_region`.
+                    ffi::XI_Enter | ffi::XI_Leave
+                        if unsafe { (*(xev.data as *const ffi::XIEnterEvent)).detail } == ffi::NotifyInferior => (),
+
                     ffi::XI_Enter => {
                         let xev: &ffi::XIEnterEvent = unsafe { &*(xev.data as *const _) };
 
@@ -860,8 +1448,13 @@ impl EventsLoop {
                         // The mods field on this event isn't actually populated, so query the
                         // pointer device. In the future, we can likely remove this round-trip by
                         // relying on Xkb for modifier values.
-                        let modifiers = self.xconn.query_pointer(xev.event, xev.deviceid)
-                            .expect("Failed to query pointer device").get_modifier_state();
+                        let modifiers = match self.xconn.query_pointer(xev.event, xev.deviceid) {
+                            Ok(pointer_state) => pointer_state.get_modifier_state(),
+                            Err(err) => {
+                                warn!("Failed to query pointer device: {}", err);
+                                ModifiersState::default()
+                            },
+                        };
 
                         let dpi_factor = self.with_window(xev.event, |window| {
                             window.get_hidpi_factor()
@@ -908,9 +1501,32 @@ impl EventsLoop {
                         self.ime
                             .borrow_mut()
                             .focus(xev.event)
-                            .expect("Failed to focus input context");
+                            .log_error("Failed to focus input context");
+                        let client = self.with_window(xev.event, |window| {
+                            let mut shared_state = window.shared_state.lock();
+                            shared_state.has_focus = true;
+                            shared_state.embedded_client
+                        });
+                        if let Some(Some(client)) = client {
+                            xembed::send_message(&self.xconn, client, xembed::XEMBED_WINDOW_ACTIVATE, 0, 0, 0)
+                                .queue();
+                            xembed::send_message(
+                                &self.xconn,
+                                client,
+                                xembed::XEMBED_FOCUS_IN,
+                                xembed::XEMBED_FOCUS_CURRENT,
+                                0,
+                                0,
+                            ).queue();
+                        }
 
                         callback(Event::WindowEvent { window_id, event: Focused(true) });
+                        self.send_synthetic_keys(
+                            xev.event,
+                            ElementState::Pressed,
+                            ModifiersState::from(xev.mods),
+                            &mut callback,
+                        );
 
                         // The deviceid for this event is for a keyboard instead of a pointer,
                         // so we have to do a little extra work.
@@ -939,7 +1555,26 @@ impl EventsLoop {
                         self.ime
                             .borrow_mut()
                             .unfocus(xev.event)
-                            .expect("Failed to unfocus input context");
+                            .log_error("Failed to unfocus input context");
+                        let client = self.with_window(xev.event, |window| {
+                            let mut shared_state = window.shared_state.lock();
+                            shared_state.has_focus = false;
+                            shared_state.embedded_client
+                        });
+                        if let Some(Some(client)) = client {
+                            xembed::send_message(&self.xconn, client, xembed::XEMBED_FOCUS_OUT, 0, 0, 0)
+                                .queue();
+                            xembed::send_message(&self.xconn, client, xembed::XEMBED_WINDOW_DEACTIVATE, 0, 0, 0)
+                                .queue();
+                        }
+                        // Keys released while the window doesn't have focus aren't reported.
+                        self.held_keycodes.borrow_mut().clear();
+                        self.send_synthetic_keys(
+                            xev.event,
+                            ElementState::Released,
+                            ModifiersState::from(xev.mods),
+                            &mut callback,
+                        );
                         callback(Event::WindowEvent {
                             window_id: mkwid(xev.event),
                             event: Focused(false),
@@ -963,6 +1598,20 @@ impl EventsLoop {
                                 (xev.event_x as f64, xev.event_y as f64),
                                 dpi_factor,
                             );
+                            let pressure_axis = self.devices
+                                .borrow()
+                                .get(&DeviceId(xev.sourceid))
+                                .and_then(|device| device.pressure_axis);
+                            let force = pressure_axis.and_then(|axis| {
+                                let mask = unsafe { slice::from_raw_parts(xev.valuators.mask, xev.valuators.mask_len as usize) };
+                                if !ffi::XIMaskIsSet(mask, axis.number) {
+                                    return None;
+                                }
+                                // The values of the set valuators are packed, in order.
+                                let index = (0..axis.number).filter(|&i| ffi::XIMaskIsSet(mask, i)).count();
+                                let value = unsafe { *xev.valuators.values.offset(index as isize) };
+                                Some(Force::Normalized((value - axis.min) / (axis.max - axis.min)))
+                            });
                             callback(Event::WindowEvent {
                                 window_id,
                                 event: WindowEvent::Touch(Touch {
@@ -970,6 +1619,8 @@ impl EventsLoop {
                                     phase,
                                     location,
                                     id: xev.detail as u64,
+                                    force,
+                                    contact_size: None,
                                 }),
                             })
                         }
@@ -978,6 +1629,19 @@ impl EventsLoop {
                     ffi::XI_RawButtonPress | ffi::XI_RawButtonRelease => {
                         let xev: &ffi::XIRawEvent = unsafe { &*(xev.data as *const _) };
                         if xev.flags & ffi::XIPointerEmulated == 0 {
+                            let button = (xev.deviceid, xev.detail);
+                            let released_all = {
+                                let mut held_buttons = self.held_buttons.borrow_mut();
+                                if xev.evtype == ffi::XI_RawButtonPress {
+                                    held_buttons.insert(button);
+                                    false
+                                } else {
+                                    held_buttons.remove(&button) && held_buttons.is_empty()
+                                }
+                            };
+                            if released_all {
+                                self.end_interactive_move_resize(&mut callback);
+                            }
                             callback(Event::DeviceEvent { device_id: mkdid(xev.deviceid), event: DeviceEvent::Button {
                                 button: xev.detail as u32,
                                 state: match xev.evtype {
@@ -1049,7 +1713,7 @@ impl EventsLoop {
                                 0,
                             )
                         };
-                        self.xconn.check_errors().expect("Failed to lookup raw keysym");
+                        self.xconn.check_errors().log_error("Failed to lookup raw keysym");
 
                         let virtual_keycode = events::keysym_to_element(keysym as c_uint);
 
@@ -1065,6 +1729,11 @@ impl EventsLoop {
                                 // comprehensive keyboard state updates, but interpreting that
                                 // info manually is going to be involved.
                                 modifiers: ModifiersState::default(),
+                                is_composing: false,
+                                is_synthetic: false,
+                                is_repeat: false,
+                                text: None,
+                                is_dead_key: false,
                             }),
                         });
                     }
@@ -1088,43 +1757,22 @@ impl EventsLoop {
             },
             _ => {
                 if event_type == self.randr_event_offset {
-                    // In the future, it would be quite easy to emit monitor hotplug events.
-                    let prev_list = monitor::invalidate_cached_monitor_list();
-                    if let Some(prev_list) = prev_list {
-                        let new_list = self.xconn.get_available_monitors();
-                        for new_monitor in new_list {
-                            prev_list
-                                .iter()
-                                .find(|prev_monitor| prev_monitor.name == new_monitor.name)
-                                .map(|prev_monitor| {
-                                    if new_monitor.hidpi_factor != prev_monitor.hidpi_factor {
-                                        for (window_id, window) in self.windows.borrow().iter() {
-                                            if let Some(window) = window.upgrade() {
-                                                // Check if the window is on this monitor
-                                                let monitor = window.get_current_monitor();
-                                                if monitor.name == new_monitor.name {
-                                                    callback(Event::WindowEvent {
-                                                        window_id: mkwid(window_id.0),
-                                                        event: WindowEvent::HiDpiFactorChanged(
-                                                            new_monitor.hidpi_factor
-                                                        ),
-                                                    });
-                                                    let (width, height) = match window.get_inner_size_physical() {
-                                                        Some(result) => result,
-                                                        None => continue,
-                                                    };
-                                                    let (_, _, flusher) = window.adjust_for_dpi(
-                                                        prev_monitor.hidpi_factor,
-                                                        new_monitor.hidpi_factor,
-                                                        width as f64,
-                                                        height as f64,
-                                                    );
-                                                    flusher.queue();
-                                                }
-                                            }
-                                        }
-                                    }
-                                });
+                    self.refresh_monitors(&mut callback);
+                } else if event_type == self.xkb_event_offset {
+                    let xkb_event: &ffi::XkbAnyEvent = util::reinterpret(&*xev);
+                    let may_have_changed = match xkb_event.xkb_type {
+                        ffi::XkbStateNotify => {
+                            let state_event: &ffi::XkbStateNotifyEvent = util::reinterpret(&*xev);
+                            state_event.changed & util::XKB_GROUP_STATE_MASK != 0
+                        },
+                        ffi::XkbNewKeyboardNotify | ffi::XkbNamesNotify => true,
+                        _ => false,
+                    };
+                    if may_have_changed {
+                        let layouts = self.keyboard_layouts();
+                        if *self.keyboard_layouts.borrow() != layouts {
+                            *self.keyboard_layouts.borrow_mut() = layouts;
+                            callback(Event::KeyboardLayoutChanged);
                         }
                     }
                 }
@@ -1148,6 +1796,245 @@ impl EventsLoop {
         }
     }
 
+    // Queries the monitors again, and sends `HiDpiFactorChanged` to the windows on the monitors
+    // whose DPI factor changed.
+    fn refresh_monitors<F>(&self, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        // In the future, it would be quite easy to emit monitor hotplug events.
+        let prev_list = monitor::invalidate_cached_monitor_list();
+        if let Some(prev_list) = prev_list {
+            let new_list = monitor::get_available_monitors(&self.xconn);
+            for new_monitor in new_list {
+                prev_list
+                    .iter()
+                    .find(|prev_monitor| prev_monitor.name == new_monitor.name)
+                    .map(|prev_monitor| {
+                        if new_monitor.hidpi_factor != prev_monitor.hidpi_factor {
+                            // The callback could create a window, which needs to
+                            // borrow `self.windows` mutably.
+                            let windows: Vec<_> = self.windows
+                                .borrow()
+                                .iter()
+                                .map(|(window_id, window)| (*window_id, window.clone()))
+                                .collect();
+                            for (window_id, window) in windows {
+                                if let Some(window) = window.upgrade() {
+                                    if window.static_hidpi_factor.is_some() {
+                                        continue;
+                                    }
+                                    // Check if the window is on this monitor
+                                    let monitor = window.get_current_monitor();
+                                    if monitor.name == new_monitor.name {
+                                        callback(Event::WindowEvent {
+                                            window_id: mkwid(window_id.0),
+                                            event: WindowEvent::HiDpiFactorChanged(
+                                                new_monitor.hidpi_factor
+                                            ),
+                                        });
+                                        let (width, height) = match window.get_inner_size_physical() {
+                                            Some(result) => result,
+                                            None => continue,
+                                        };
+                                        let (_, _, flusher) = window.adjust_for_dpi(
+                                            prev_monitor.hidpi_factor,
+                                            new_monitor.hidpi_factor,
+                                            width as f64,
+                                            height as f64,
+                                        );
+                                        flusher.queue();
+                                        window.update_cursor_for_hidpi_factor(
+                                            new_monitor.hidpi_factor,
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                    });
+            }
+        }
+    }
+
+    fn update_occlusion<F>(&self, xwindow: ffi::Window, occluded: bool, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        let changed = self.with_window(xwindow, |window| {
+            let mut shared_state_lock = window.shared_state.lock();
+            mem::replace(&mut shared_state_lock.occluded, occluded) != occluded
+        });
+        if changed == Some(true) {
+            callback(Event::WindowEvent {
+                window_id: mkwid(xwindow),
+                event: WindowEvent::Occluded(occluded),
+            });
+        }
+    }
+
+    // Sends a synthetic `KeyboardInput` for every key that's held down, when a window gains or
+    // loses focus.
+    fn send_synthetic_keys<F>(
+        &self,
+        xwindow: ffi::Window,
+        state: ElementState,
+        modifiers: ModifiersState,
+        callback: &mut F,
+    )
+        where F: FnMut(Event)
+    {
+        let keycodes = match self.xconn.query_pressed_keycodes() {
+            Ok(keycodes) => keycodes,
+            Err(_) => return,
+        };
+        for keycode in keycodes {
+            if keycode < 8 {
+                continue;
+            }
+            let keysym = unsafe {
+                (self.xconn.xlib.XKeycodeToKeysym)(self.xconn.display, keycode, 0)
+            };
+            let virtual_keycode = events::keysym_to_element(keysym as c_uint);
+            if state == ElementState::Pressed {
+                // Further presses of the key are repeats.
+                self.held_keycodes.borrow_mut().insert(keycode as c_uint);
+            }
+            callback(Event::WindowEvent {
+                window_id: mkwid(xwindow),
+                event: WindowEvent::KeyboardInput {
+                    device_id: mkdid(util::VIRTUAL_CORE_KEYBOARD),
+                    input: KeyboardInput {
+                        state,
+                        scancode: keycode as u32 - 8,
+                        virtual_keycode,
+                        modifiers,
+                        is_composing: false,
+                        is_synthetic: true,
+                        is_repeat: false,
+                        text: None,
+                        is_dead_key: false,
+                    },
+                },
+            });
+        }
+    }
+
+    // Handles an `_XEMBED` message sent by the embedder of a window. The window has focus while
+    // the embedder is active and has given it focus.
+    fn handle_xembed_message<F>(&self, client_msg: &ffi::XClientMessageEvent, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        let window = client_msg.window;
+        let focus_changed = self.with_window(window, |window| {
+            let mut shared_state = window.shared_state.lock();
+            match client_msg.data.get_long(1) {
+                xembed::XEMBED_EMBEDDED_NOTIFY => {
+                    shared_state.embedder = Some(client_msg.data.get_long(3) as ffi::Window);
+                },
+                xembed::XEMBED_WINDOW_ACTIVATE => shared_state.embedder_active = true,
+                xembed::XEMBED_WINDOW_DEACTIVATE => shared_state.embedder_active = false,
+                xembed::XEMBED_FOCUS_IN => shared_state.embedder_focused = true,
+                xembed::XEMBED_FOCUS_OUT => shared_state.embedder_focused = false,
+                _ => (),
+            }
+            let has_focus = shared_state.embedder_active && shared_state.embedder_focused;
+            if mem::replace(&mut shared_state.has_focus, has_focus) != has_focus {
+                Some(has_focus)
+            } else {
+                None
+            }
+        });
+        if let Some(Some(focused)) = focus_changed {
+            if focused {
+                self.ime
+                    .borrow_mut()
+                    .focus(window)
+                    .log_error("Failed to focus input context");
+            } else {
+                self.ime
+                    .borrow_mut()
+                    .unfocus(window)
+                    .log_error("Failed to unfocus input context");
+                self.held_keycodes.borrow_mut().clear();
+            }
+            callback(Event::WindowEvent {
+                window_id: mkwid(window),
+                event: WindowEvent::Focused(focused),
+            });
+        }
+    }
+
+    // Ends the moves and resizes guessed from `ConfigureNotify`, once all the mouse buttons have
+    // been released.
+    fn end_interactive_move_resize<F>(&self, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        let mut events = Vec::new();
+        for window in self.windows.borrow().values().filter_map(|window| window.upgrade()) {
+            let window_id = mkwid(window.get_xlib_window());
+            let mut shared_state = window.shared_state.lock();
+            if mem::replace(&mut shared_state.interactive_resize, false) {
+                events.push(Event::WindowEvent { window_id, event: WindowEvent::ResizeEnded });
+            }
+            if mem::replace(&mut shared_state.interactive_move, false) {
+                events.push(Event::WindowEvent { window_id, event: WindowEvent::MoveEnded });
+            }
+        }
+        for event in events {
+            callback(event);
+        }
+    }
+
+    // Returns the window that the given window of another application is embedded into.
+    fn find_embedder(&self, client: ffi::Window) -> Option<Arc<UnownedWindow>> {
+        self.windows
+            .borrow()
+            .values()
+            .filter_map(|window| window.upgrade())
+            .find(|window| window.shared_state.lock().embedded_client == Some(client))
+    }
+
+    // Sends the press of a dead key or of the compose key that was filtered by the input method,
+    // so that the start of a compose sequence can be shown. The other keys of the sequence are
+    // left to the input method.
+    fn send_filtered_dead_key<F>(&self, xkev: &mut ffi::XKeyEvent, callback: &mut F)
+        where F: FnMut(Event)
+    {
+        if xkev.keycode == 0 {
+            return;
+        }
+        let keysym = unsafe {
+            let mut keysym = 0;
+            (self.xconn.xlib.XLookupString)(xkev, ptr::null_mut(), 0, &mut keysym, ptr::null_mut());
+            self.xconn.check_errors().log_error("Failed to lookup keysym");
+            keysym
+        };
+        if !events::is_dead_keysym(keysym as c_uint) {
+            return;
+        }
+        let is_repeat = !self.held_keycodes.borrow_mut().insert(xkev.keycode);
+        callback(Event::WindowEvent {
+            window_id: mkwid(xkev.window),
+            event: WindowEvent::KeyboardInput {
+                device_id: mkdid(util::VIRTUAL_CORE_KEYBOARD),
+                input: KeyboardInput {
+                    state: ElementState::Pressed,
+                    scancode: xkev.keycode - 8,
+                    virtual_keycode: events::keysym_to_element(keysym as c_uint),
+                    modifiers: ModifiersState {
+                        alt: xkev.state & ffi::Mod1Mask != 0,
+                        shift: xkev.state & ffi::ShiftMask != 0,
+                        ctrl: xkev.state & ffi::ControlMask != 0,
+                        logo: xkev.state & ffi::Mod4Mask != 0,
+                    },
+                    is_composing: false,
+                    is_synthetic: false,
+                    is_repeat,
+                    text: None,
+                    is_dead_key: true,
+                },
+            },
+        });
+    }
+
     fn with_window<F, T>(&self, window_id: ffi::Window, callback: F) -> Option<T>
         where F: Fn(&UnownedWindow) -> T
     {
@@ -1174,12 +2061,23 @@ impl EventsLoop {
     }
 }
 
+impl Drop for EventsLoop {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xconn.xlib.XDestroyWindow)(self.xconn.display, self.wakeup_dummy_window);
+        }
+    }
+}
+
 impl EventsLoopProxy {
     pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
-        // Update the `EventsLoop`'s `pending_wakeup` flag.
+        // Update the `EventsLoop`'s `pending_wakeup` flag. If it was already set, the message
+        // sent by the previous call hasn't been processed yet, and will take care of this one too.
         let display = match (self.pending_wakeup.upgrade(), self.xconn.upgrade()) {
             (Some(wakeup), Some(display)) => {
-                wakeup.store(true, atomic::Ordering::Relaxed);
+                if wakeup.swap(true, atomic::Ordering::AcqRel) {
+                    return Ok(());
+                }
                 display
             },
             _ => return Err(EventsLoopClosed),
@@ -1196,9 +2094,7 @@ impl EventsLoopProxy {
             0,
             None,
             [0, 0, 0, 0, 0],
-        ).flush().expect("Failed to call XSendEvent after wakeup");
-
-        Ok(())
+        ).flush().map_err(|_| EventsLoopClosed)
     }
 }
 
@@ -1247,9 +2143,55 @@ impl<'a> Deref for DeviceInfo<'a> {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowId(ffi::Window);
 
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot {
+    window: Option<ffi::Window>,
+    // The window whose input context was focused, which can differ from `window` while focus is
+    // changing.
+    ime_window: Option<ffi::Window>,
+}
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.window.map(WindowId)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        WindowId(raw as ffi::Window)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId(c_int);
 
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId(0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+
+    pub fn from_raw(raw: u64) -> DeviceId {
+        DeviceId(raw as c_int)
+    }
+}
+
 pub struct Window(Arc<UnownedWindow>);
 
 impl Deref for Window {
@@ -1276,13 +2218,7 @@ impl Window {
 
 impl Drop for Window {
     fn drop(&mut self) {
-        let window = self.deref();
-        let xconn = &window.xconn;
-        unsafe {
-            (xconn.xlib.XDestroyWindow)(xconn.display, window.id().0);
-            // If the window was somehow already destroyed, we'll get a `BadWindow` error, which we don't care about.
-            let _ = xconn.check_errors();
-        }
+        self.destroy();
     }
 }
 
@@ -1328,6 +2264,8 @@ fn mkdid(w: c_int) -> ::DeviceId { ::DeviceId(::platform::DeviceId::X(DeviceId(w
 struct Device {
     name: String,
     scroll_axes: Vec<(i32, ScrollAxis)>,
+    // The valuator reporting the pressure of touches, if any.
+    pressure_axis: Option<PressureAxis>,
     // For master devices, this is the paired device (pointer <-> keyboard).
     // For slave devices, this is the master.
     attachment: c_int,
@@ -1340,6 +2278,13 @@ struct ScrollAxis {
     position: f64,
 }
 
+#[derive(Debug, Copy, Clone)]
+struct PressureAxis {
+    number: i32,
+    min: f64,
+    max: f64,
+}
+
 #[derive(Debug, Copy, Clone)]
 enum ScrollOrientation {
     Vertical,
@@ -1350,6 +2295,7 @@ impl Device {
     fn new(el: &EventsLoop, info: &ffi::XIDeviceInfo) -> Self {
         let name = unsafe { CStr::from_ptr(info.name).to_string_lossy() };
         let mut scroll_axes = Vec::new();
+        let mut pressure_axis = None;
 
         if Device::physical_device(info) {
             // Register for global raw events
@@ -1377,6 +2323,17 @@ impl Device {
                             position: 0.0,
                         }));
                     }
+                    ffi::XIValuatorClass => {
+                        let info = unsafe { mem::transmute::<&ffi::XIAnyClassInfo, &ffi::XIValuatorClassInfo>(class) };
+                        let pressure_atom = unsafe { el.xconn.get_atom_unchecked(b"Abs MT Pressure\0") };
+                        if info.label == pressure_atom && info.max > info.min {
+                            pressure_axis = Some(PressureAxis {
+                                number: info.number,
+                                min: info.min,
+                                max: info.max,
+                            });
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -1385,6 +2342,7 @@ impl Device {
         let mut device = Device {
             name: name.into_owned(),
             scroll_axes: scroll_axes,
+            pressure_axis,
             attachment: info.attachment,
         };
         device.reset_scroll_position(info);
@@ -1,8 +1,10 @@
+use std::cmp;
 use std::os::raw::*;
+use std::sync::Arc;
 
 use parking_lot::Mutex;
 
-use {PhysicalPosition, PhysicalSize};
+use {Edge, PhysicalPosition, PhysicalSize};
 use super::{util, XConnection, XError};
 use super::ffi::{
     RRCrtcChangeNotifyMask,
@@ -56,17 +58,20 @@ pub struct MonitorId {
     pub(crate) hidpi_factor: f64,
     /// Used to determine which windows are on this monitor
     pub(crate) rect: util::AaRect,
+    /// Used to look up the space reserved by docks and panels
+    xconn: Arc<XConnection>,
 }
 
 impl MonitorId {
     fn from_repr(
-        xconn: &XConnection,
+        xconn: &Arc<XConnection>,
         resources: *mut XRRScreenResources,
         id: u32,
         repr: util::MonitorRepr,
         primary: bool,
+        xft_dpi: Option<f64>,
     ) -> Self {
-        let (name, hidpi_factor) = unsafe { xconn.get_output_info(resources, &repr) };
+        let (name, hidpi_factor) = unsafe { xconn.get_output_info(resources, &repr, xft_dpi) };
         let (dimensions, position) = unsafe { (repr.get_dimensions(), repr.get_position()) };
         let rect = util::AaRect::new(position, dimensions);
         MonitorId {
@@ -77,6 +82,7 @@ impl MonitorId {
             position,
             primary,
             rect,
+            xconn: Arc::clone(xconn),
         }
     }
 
@@ -101,129 +107,204 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         self.hidpi_factor
     }
-}
 
-impl XConnection {
-    pub fn get_monitor_for_window(&self, window_rect: Option<util::AaRect>) -> MonitorId {
-        let monitors = self.get_available_monitors();
-        let default = monitors
-            .get(0)
-            .expect("[winit] Failed to find any monitors using XRandR.");
-
-        let window_rect = match window_rect {
-            Some(rect) => rect,
-            None => return default.to_owned(),
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        let (left, top, right, bottom) = self.get_reserved_insets();
+        let position = (self.position.0 + left as i32, self.position.1 + top as i32);
+        let dimensions = (
+            cmp::max(self.dimensions.0 as i64 - left - right, 0) as u32,
+            cmp::max(self.dimensions.1 as i64 - top - bottom, 0) as u32,
+        );
+        (position.into(), dimensions.into())
+    }
+
+    pub fn get_taskbar_edge(&self) -> Option<Edge> {
+        let (left, top, right, bottom) = self.get_reserved_insets();
+        Edge::of_largest_inset(left, top, right, bottom)
+    }
+
+    // The space reserved along each edge of the monitor by the struts of docks and panels. Struts
+    // are relative to the edges of the root window, which spans every monitor.
+    fn get_reserved_insets(&self) -> (i64, i64, i64, i64) {
+        let root = unsafe { (self.xconn.xlib.XDefaultRootWindow)(self.xconn.display) };
+        let (root_width, root_height) = match self.xconn.get_geometry(root) {
+            Ok(geometry) => (geometry.width as i64, geometry.height as i64),
+            Err(_) => return (0, 0, 0, 0),
+        };
+        let (x, y) = (self.position.0 as i64, self.position.1 as i64);
+        let (width, height) = (self.dimensions.0 as i64, self.dimensions.1 as i64);
+        // Whether the range covered by a strut along its edge overlaps with the monitor.
+        let overlaps = |start: i64, end: i64, monitor_start: i64, monitor_length: i64| {
+            start < monitor_start + monitor_length && end >= monitor_start
         };
 
-        let mut largest_overlap = 0;
-        let mut matched_monitor = default;
-        for monitor in &monitors {
-            let overlapping_area = window_rect.get_overlapping_area(&monitor.rect);
-            if overlapping_area > largest_overlap {
-                largest_overlap = overlapping_area;
-                matched_monitor = &monitor;
+        let mut insets = (0, 0, 0, 0);
+        for strut in self.xconn.get_struts(root) {
+            let mut values = [0i64; 12];
+            for (value, &strut_value) in values.iter_mut().zip(strut.iter()) {
+                *value = cmp::min(strut_value, i32::max_value() as c_ulong) as i64;
+            }
+            if values[0] > 0 && overlaps(values[4], values[5], y, height) {
+                insets.0 = cmp::max(insets.0, cmp::min(values[0] - x, width));
+            }
+            if values[1] > 0 && overlaps(values[6], values[7], y, height) {
+                insets.2 = cmp::max(insets.2, cmp::min(x + width - (root_width - values[1]), width));
+            }
+            if values[2] > 0 && overlaps(values[8], values[9], x, width) {
+                insets.1 = cmp::max(insets.1, cmp::min(values[2] - y, height));
+            }
+            if values[3] > 0 && overlaps(values[10], values[11], x, width) {
+                insets.3 = cmp::max(insets.3, cmp::min(y + height - (root_height - values[3]), height));
             }
         }
+        insets
+    }
+}
+
+// With `by_center`, the monitor containing the center of the window is preferred to the one it
+// overlaps the most.
+pub fn get_monitor_for_window(
+    xconn: &Arc<XConnection>,
+    window_rect: Option<util::AaRect>,
+    by_center: bool,
+) -> MonitorId {
+    let monitors = get_available_monitors(xconn);
+    let default = monitors
+        .get(0)
+        .expect("[winit] Failed to find any monitors using XRandR.");
 
-        matched_monitor.to_owned()
+    let window_rect = match window_rect {
+        Some(rect) => rect,
+        None => return default.to_owned(),
+    };
+
+    // The monitor containing the center of the window is the one the user would expect the
+    // window to be scaled for. If the center is off-screen, we fall back to the monitor that
+    // the window overlaps the most.
+    if by_center {
+        let (center_x, center_y) = window_rect.center();
+        if let Some(monitor) = monitors
+            .iter()
+            .find(|monitor| monitor.rect.contains_point(center_x, center_y))
+        {
+            return monitor.to_owned();
+        }
     }
 
-    fn query_monitor_list(&self) -> Vec<MonitorId> {
-        unsafe {
-            let root = (self.xlib.XDefaultRootWindow)(self.display);
-            // WARNING: this function is supposedly very slow, on the order of hundreds of ms.
-            // Upon failure, `resources` will be null.
-            let resources = (self.xrandr.XRRGetScreenResources)(self.display, root);
-            if resources.is_null() {
-                panic!("[winit] `XRRGetScreenResources` returned NULL. That should only happen if the root window doesn't exist.");
-            }
+    let mut largest_overlap = 0;
+    let mut matched_monitor = default;
+    for monitor in &monitors {
+        let overlapping_area = window_rect.get_overlapping_area(&monitor.rect);
+        if overlapping_area > largest_overlap {
+            largest_overlap = overlapping_area;
+            matched_monitor = &monitor;
+        }
+    }
 
-            let mut available;
-            let mut has_primary = false;
-
-            if self.xrandr_1_5.is_some() && version_is_at_least(1, 5) && !FORCE_RANDR_COMPAT {
-                // We're in XRandR >= 1.5, enumerate monitors. This supports things like MST and
-                // videowalls.
-                let xrandr_1_5 = self.xrandr_1_5.as_ref().unwrap();
-                let mut monitor_count = 0;
-                let monitors = (xrandr_1_5.XRRGetMonitors)(self.display, root, 1, &mut monitor_count);
-                assert!(monitor_count >= 0);
-                available = Vec::with_capacity(monitor_count as usize);
-                for monitor_index in 0..monitor_count {
-                    let monitor = monitors.offset(monitor_index as isize);
-                    let is_primary = (*monitor).primary != 0;
+    matched_monitor.to_owned()
+}
+
+fn query_monitor_list(xconn: &Arc<XConnection>) -> Vec<MonitorId> {
+    unsafe {
+        let root = (xconn.xlib.XDefaultRootWindow)(xconn.display);
+        // WARNING: this function is supposedly very slow, on the order of hundreds of ms.
+        // Upon failure, `resources` will be null.
+        let resources = (xconn.xrandr.XRRGetScreenResources)(xconn.display, root);
+        if resources.is_null() {
+            panic!("[winit] `XRRGetScreenResources` returned NULL. That should only happen if the root window doesn't exist.");
+        }
+
+        let mut available;
+        let mut has_primary = false;
+        let xft_dpi = xconn.get_xft_dpi();
+
+        if xconn.xrandr_1_5.is_some() && version_is_at_least(1, 5) && !FORCE_RANDR_COMPAT {
+            // We're in XRandR >= 1.5, enumerate monitors. This supports things like MST and
+            // videowalls.
+            let xrandr_1_5 = xconn.xrandr_1_5.as_ref().unwrap();
+            let mut monitor_count = 0;
+            let monitors = (xrandr_1_5.XRRGetMonitors)(xconn.display, root, 1, &mut monitor_count);
+            assert!(monitor_count >= 0);
+            available = Vec::with_capacity(monitor_count as usize);
+            for monitor_index in 0..monitor_count {
+                let monitor = monitors.offset(monitor_index as isize);
+                let is_primary = (*monitor).primary != 0;
+                has_primary |= is_primary;
+                available.push(MonitorId::from_repr(
+                    xconn,
+                    resources,
+                    monitor_index as u32,
+                    monitor.into(),
+                    is_primary,
+                    xft_dpi,
+                ));
+            }
+            (xrandr_1_5.XRRFreeMonitors)(monitors);
+        } else {
+            // We're in XRandR < 1.5, enumerate CRTCs. Everything will work except MST and
+            // videowall setups will also show monitors that aren't in the logical groups the user
+            // cares about.
+            let primary = (xconn.xrandr.XRRGetOutputPrimary)(xconn.display, root);
+            available = Vec::with_capacity((*resources).ncrtc as usize);
+            for crtc_index in 0..(*resources).ncrtc {
+                let crtc_id = *((*resources).crtcs.offset(crtc_index as isize));
+                let crtc = (xconn.xrandr.XRRGetCrtcInfo)(xconn.display, resources, crtc_id);
+                let is_active = (*crtc).width > 0 && (*crtc).height > 0 && (*crtc).noutput > 0;
+                if is_active {
+                    let crtc = util::MonitorRepr::from(crtc);
+                    let is_primary = crtc.get_output() == primary;
                     has_primary |= is_primary;
                     available.push(MonitorId::from_repr(
-                        self,
+                        xconn,
                         resources,
-                        monitor_index as u32,
-                        monitor.into(),
+                        crtc_id as u32,
+                        crtc,
                         is_primary,
+                        xft_dpi,
                     ));
                 }
-                (xrandr_1_5.XRRFreeMonitors)(monitors);
-            } else {
-                // We're in XRandR < 1.5, enumerate CRTCs. Everything will work except MST and
-                // videowall setups will also show monitors that aren't in the logical groups the user
-                // cares about.
-                let primary = (self.xrandr.XRRGetOutputPrimary)(self.display, root);
-                available = Vec::with_capacity((*resources).ncrtc as usize);
-                for crtc_index in 0..(*resources).ncrtc {
-                    let crtc_id = *((*resources).crtcs.offset(crtc_index as isize));
-                    let crtc = (self.xrandr.XRRGetCrtcInfo)(self.display, resources, crtc_id);
-                    let is_active = (*crtc).width > 0 && (*crtc).height > 0 && (*crtc).noutput > 0;
-                    if is_active {
-                        let crtc = util::MonitorRepr::from(crtc);
-                        let is_primary = crtc.get_output() == primary;
-                        has_primary |= is_primary;
-                        available.push(MonitorId::from_repr(
-                            self,
-                            resources,
-                            crtc_id as u32,
-                            crtc,
-                            is_primary,
-                        ));
-                    }
-                    (self.xrandr.XRRFreeCrtcInfo)(crtc);
-                }
+                (xconn.xrandr.XRRFreeCrtcInfo)(crtc);
             }
+        }
 
-            // If no monitors were detected as being primary, we just pick one ourselves!
-            if !has_primary {
-                if let Some(ref mut fallback) = available.first_mut() {
-                    // Setting this here will come in handy if we ever add an `is_primary` method.
-                    fallback.primary = true;
-                }
+        // If no monitors were detected as being primary, we just pick one ourselves!
+        if !has_primary {
+            if let Some(ref mut fallback) = available.first_mut() {
+                // Setting this here will come in handy if we ever add an `is_primary` method.
+                fallback.primary = true;
             }
-
-            (self.xrandr.XRRFreeScreenResources)(resources);
-            available
         }
-    }
 
-    pub fn get_available_monitors(&self) -> Vec<MonitorId> {
-        let mut monitors_lock = MONITORS.lock();
-        (*monitors_lock)
-            .as_ref()
-            .cloned()
-            .or_else(|| {
-                let monitors = Some(self.query_monitor_list());
-                if !DISABLE_MONITOR_LIST_CACHING {
-                    (*monitors_lock) = monitors.clone();
-                }
-                monitors
-            })
-            .unwrap()
+        (xconn.xrandr.XRRFreeScreenResources)(resources);
+        debug!(target: "winit::x11::randr", "Available monitors: {:?}", available);
+        available
     }
+}
 
-    #[inline]
-    pub fn get_primary_monitor(&self) -> MonitorId {
-        self.get_available_monitors()
-            .into_iter()
-            .find(|monitor| monitor.primary)
-            .expect("[winit] Failed to find any monitors using XRandR.")
-    }
+pub fn get_available_monitors(xconn: &Arc<XConnection>) -> Vec<MonitorId> {
+    let mut monitors_lock = MONITORS.lock();
+    (*monitors_lock)
+        .as_ref()
+        .cloned()
+        .or_else(|| {
+            let monitors = Some(query_monitor_list(xconn));
+            if !DISABLE_MONITOR_LIST_CACHING {
+                (*monitors_lock) = monitors.clone();
+            }
+            monitors
+        })
+        .unwrap()
+}
 
+#[inline]
+pub fn get_primary_monitor(xconn: &Arc<XConnection>) -> MonitorId {
+    get_available_monitors(xconn)
+        .into_iter()
+        .find(|monitor| monitor.primary)
+        .expect("[winit] Failed to find any monitors using XRandR.")
+}
+
+impl XConnection {
     pub fn select_xrandr_input(&self, root: Window) -> Result<c_int, XError> {
         {
             let mut version_lock = XRANDR_VERSION.lock();
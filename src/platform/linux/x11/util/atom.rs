@@ -34,11 +34,12 @@ impl XConnection {
                 );
                 panic!(msg);
             }
-            /*println!(
+            trace!(
+                target: "winit::x11::atom",
                 "XInternAtom name:{:?} atom:{:?}",
                 name,
                 atom,
-            );*/
+            );
             (*atom_cache_lock).insert(name.to_owned(), atom);
             atom
         }
@@ -50,6 +51,23 @@ impl XConnection {
         self.get_atom(name)
     }
 
+    // Returns `None` if the name contains a null character.
+    pub fn get_atom_from_str(&self, name: &str) -> Option<ffi::Atom> {
+        CString::new(name).ok().map(|name| self.get_atom(name))
+    }
+
+    pub fn get_atom_name(&self, atom: ffi::Atom) -> Option<String> {
+        unsafe {
+            let name = (self.xlib.XGetAtomName)(self.display, atom);
+            if name.is_null() {
+                return None;
+            }
+            let owned = CStr::from_ptr(name).to_string_lossy().into_owned();
+            (self.xlib.XFree)(name as _);
+            Some(owned)
+        }
+    }
+
     // Note: this doesn't use caching, for the sake of simplicity.
     // If you're dealing with this many atoms, you'll usually want to cache them locally anyway.
     pub unsafe fn get_atoms(&self,  names: &[*mut c_char]) -> Result<Vec<ffi::Atom>, XError> {
@@ -63,10 +81,11 @@ impl XConnection {
         );
         self.check_errors()?;
         atoms.set_len(names.len());
-        /*println!(
+        trace!(
+            target: "winit::x11::atom",
             "XInternAtoms atoms:{:?}",
             atoms,
-        );*/
+        );
         Ok(atoms)
     }
 }
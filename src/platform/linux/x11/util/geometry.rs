@@ -19,6 +19,10 @@ impl AaRect {
         AaRect { x, y, width, height }
     }
 
+    pub fn center(&self) -> (i64, i64) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
     pub fn contains_point(&self, x: i64, y: i64) -> bool {
         x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
     }
@@ -151,6 +155,15 @@ impl FrameExtentsHeuristic {
     }
 }
 
+// Used when the window can't be queried, which happens when it has been destroyed in the meantime.
+fn unknown_frame_extents(context: &str, err: XError) -> FrameExtentsHeuristic {
+    warn!("{}: {}", context, err);
+    FrameExtentsHeuristic {
+        frame_extents: FrameExtents::new(0, 0, 0, 0),
+        heuristic_path: FrameExtentsHeuristicPath::UnsupportedBordered,
+    }
+}
+
 impl XConnection {
     // This is adequate for get_inner_position
     pub fn translate_coords(&self, window: ffi::Window, root: ffi::Window) -> Result<TranslatedCoords, XError> {
@@ -284,7 +297,10 @@ impl XConnection {
         // isn't nested are outlined in the comments throghout this function, but in addition to
         // that, fullscreen windows often aren't nested.
         let (inner_y_rel_root, child) = {
-            let coords = self.translate_coords(window, root).expect("Failed to translate window coordinates");
+            let coords = match self.translate_coords(window, root) {
+                Ok(coords) => coords,
+                Err(err) => return unknown_frame_extents("Failed to translate window coordinates", err),
+            };
             (
                 coords.y_rel_root,
                 coords.child,
@@ -292,7 +308,10 @@ impl XConnection {
         };
 
         let (width, height, border) = {
-            let inner_geometry = self.get_geometry(window).expect("Failed to get inner window geometry");
+            let inner_geometry = match self.get_geometry(window) {
+                Ok(geometry) => geometry,
+                Err(err) => return unknown_frame_extents("Failed to get inner window geometry", err),
+            };
             (
                 inner_geometry.width,
                 inner_geometry.height,
@@ -343,9 +362,15 @@ impl XConnection {
             // If the position value we have is for a nested window used as the client area, we'll
             // just climb up the hierarchy and get the geometry of the outermost window we're
             // nested in.
-            let outer_window = self.climb_hierarchy(window, root).expect("Failed to climb window hierarchy");
+            let outer_window = match self.climb_hierarchy(window, root) {
+                Ok(outer_window) => outer_window,
+                Err(err) => return unknown_frame_extents("Failed to climb window hierarchy", err),
+            };
             let (outer_y, outer_width, outer_height) = {
-                let outer_geometry = self.get_geometry(outer_window).expect("Failed to get outer window geometry");
+                let outer_geometry = match self.get_geometry(outer_window) {
+                    Ok(geometry) => geometry,
+                    Err(err) => return unknown_frame_extents("Failed to get outer window geometry", err),
+                };
                 (
                     outer_geometry.y_rel_parent,
                     outer_geometry.width,
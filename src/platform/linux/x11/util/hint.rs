@@ -89,6 +89,35 @@ impl<'a> NormalHints<'a> {
         }
     }
 
+    pub fn get_position(&self) -> Option<(i32, i32)> {
+        if self.has_flag(ffi::PPosition) {
+            Some((self.size_hints.x as _, self.size_hints.y as _))
+        } else {
+            None
+        }
+    }
+
+    // WARNING: This hint is obsolete, but the flag tells the WM not to place the window itself
+    pub fn set_position(&mut self, position: Option<(i32, i32)>) {
+        if let Some((x, y)) = position {
+            self.size_hints.flags |= ffi::PPosition;
+            self.size_hints.x = x as c_int;
+            self.size_hints.y = y as c_int;
+        } else {
+            self.size_hints.flags &= !ffi::PPosition;
+        }
+    }
+
+    // Tells the WM which point of the frame the position given to `XMoveWindow` refers to.
+    pub fn set_gravity(&mut self, gravity: Option<c_int>) {
+        if let Some(gravity) = gravity {
+            self.size_hints.flags |= ffi::PWinGravity;
+            self.size_hints.win_gravity = gravity;
+        } else {
+            self.size_hints.flags &= !ffi::PWinGravity;
+        }
+    }
+
     pub fn get_size(&self) -> Option<(u32, u32)> {
         self.getter(ffi::PSize, &self.size_hints.width, &self.size_hints.height)
     }
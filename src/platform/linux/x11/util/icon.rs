@@ -1,4 +1,4 @@
-use {Icon, Pixel, PIXEL_SIZE};
+use {Icon, IconImage, Pixel, PIXEL_SIZE};
 use super::*;
 
 impl Pixel {
@@ -16,12 +16,12 @@ impl Pixel {
     }
 }
 
-impl Icon {
-    pub(crate) fn to_cardinals(&self) -> Vec<Cardinal> {
+impl IconImage {
+    fn append_cardinals(&self, data: &mut Vec<Cardinal>) {
         assert_eq!(self.rgba.len() % PIXEL_SIZE, 0);
         let pixel_count = self.rgba.len() / PIXEL_SIZE;
         assert_eq!(pixel_count, (self.width * self.height) as usize);
-        let mut data = Vec::with_capacity(pixel_count);
+        data.reserve(2 + pixel_count);
         data.push(self.width as Cardinal);
         data.push(self.height as Cardinal);
         let pixels = self.rgba.as_ptr() as *const Pixel;
@@ -29,6 +29,16 @@ impl Icon {
             let pixel = unsafe { &*pixels.offset(pixel_index as isize) };
             data.push(pixel.to_packed_argb());
         }
+    }
+}
+
+impl Icon {
+    // `_NET_WM_ICON` holds any number of images one after the other, each preceded by its size.
+    pub(crate) fn to_cardinals(&self) -> Vec<Cardinal> {
+        let mut data = Vec::new();
+        for image in &self.images {
+            image.append_cardinals(&mut data);
+        }
         data
     }
 }
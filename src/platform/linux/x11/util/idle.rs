@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use super::*;
+
+impl XConnection {
+    // Returns how long the user has been idle for, using the XScreenSaver extension.
+    pub fn get_idle_time(&self) -> Option<Duration> {
+        let xss = self.xss.as_ref()?;
+        unsafe {
+            let (mut event_base, mut error_base) = (0, 0);
+            if (xss.XScreenSaverQueryExtension)(self.display, &mut event_base, &mut error_base) == ffi::False {
+                return None;
+            }
+            let info = XSmartPointer::new(self, (xss.XScreenSaverAllocInfo)())?;
+            let root = (self.xlib.XDefaultRootWindow)(self.display);
+            let status = (xss.XScreenSaverQueryInfo)(self.display, root, info.ptr);
+            if status == 0 {
+                return None;
+            }
+            Some(Duration::from_millis(info.idle as u64))
+        }
+    }
+}
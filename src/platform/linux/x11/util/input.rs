@@ -6,6 +6,30 @@ use events::ModifiersState;
 pub const VIRTUAL_CORE_POINTER: c_int = 2;
 pub const VIRTUAL_CORE_KEYBOARD: c_int = 3;
 
+// XKB constants that x11-dl doesn't provide.
+pub const XKB_USE_CORE_KBD: c_uint = 0x0100;
+pub const XKB_GROUP_STATE_MASK: c_uint = 1 << 4;
+const XKB_GROUP_NAMES_MASK: c_uint = 1 << 12;
+
+// `XkbStateRec`, which x11-dl only declares as an opaque type.
+#[repr(C)]
+struct XkbState {
+    group: c_uchar,
+    locked_group: c_uchar,
+    base_group: c_ushort,
+    latched_group: c_ushort,
+    mods: c_uchar,
+    base_mods: c_uchar,
+    latched_mods: c_uchar,
+    locked_mods: c_uchar,
+    compat_state: c_uchar,
+    grab_mods: c_uchar,
+    compat_grab_mods: c_uchar,
+    lookup_mods: c_uchar,
+    compat_lookup_mods: c_uchar,
+    ptr_buttons: c_ushort,
+}
+
 // A base buffer size of 1kB uses a negligible amount of RAM while preventing us from having to
 // re-allocate (and make another round-trip) in the *vast* majority of cases.
 // To test if `lookup_utf8` works correctly, set this to 1.
@@ -29,8 +53,8 @@ pub struct PointerState<'a> {
     child: ffi::Window,
     pub root_x: c_double,
     pub root_y: c_double,
-    win_x: c_double,
-    win_y: c_double,
+    pub win_x: c_double,
+    pub win_y: c_double,
     buttons: ffi::XIButtonState,
     modifiers: ffi::XIModifierState,
     group: ffi::XIGroupState,
@@ -72,7 +96,6 @@ impl XConnection {
         Flusher::new(self)
     }
 
-    #[allow(dead_code)]
     pub fn select_xkb_events(&self, device_id: c_uint, mask: c_ulong) -> Option<Flusher> {
         let status = unsafe {
             (self.xlib.XkbSelectEvents)(
@@ -89,6 +112,35 @@ impl XConnection {
         }
     }
 
+    /// Returns the names of the groups of the core keyboard's keymap, and the index of the active
+    /// one. Each group is a layout the user can switch to.
+    pub fn get_xkb_groups(&self) -> (Vec<String>, usize) {
+        unsafe {
+            let mut names = Vec::new();
+            let desc = (self.xlib.XkbAllocKeyboard)();
+            if !desc.is_null() {
+                let status = (self.xlib.XkbGetNames)(self.display, XKB_GROUP_NAMES_MASK, desc);
+                if status == ffi::Success as c_int && !(*desc).names.is_null() {
+                    names = (*(*desc).names).groups
+                        .iter()
+                        .take_while(|&&atom| atom != 0)
+                        .filter_map(|&atom| self.get_atom_name(atom))
+                        .collect();
+                }
+                (self.xlib.XkbFreeKeyboard)(desc, 0, ffi::True);
+            }
+
+            let mut state: XkbState = mem::zeroed();
+            let status = (self.xlib.XkbGetState)(
+                self.display,
+                XKB_USE_CORE_KBD,
+                &mut state as *mut XkbState as ffi::XkbStatePtr,
+            );
+            let active = if status == ffi::Success as c_int { state.group as usize } else { 0 };
+            (names, active)
+        }
+    }
+
     pub fn query_pointer(&self, window: ffi::Window, device_id: c_int) -> Result<PointerState, XError> {
         unsafe {
             let mut pointer_state: PointerState = mem::uninitialized();
@@ -117,6 +169,24 @@ impl XConnection {
         }
     }
 
+    // Returns the keycodes of the keys that are currently held down.
+    pub fn query_pressed_keycodes(&self) -> Result<Vec<ffi::KeyCode>, XError> {
+        let mut keymap: [c_char; 32] = [0; 32];
+        unsafe {
+            (self.xlib.XQueryKeymap)(self.display, keymap.as_mut_ptr());
+        }
+        self.check_errors()?;
+        let mut keycodes = Vec::new();
+        for (byte_index, &byte) in keymap.iter().enumerate() {
+            for bit in 0..8 {
+                if byte as u8 & (1 << bit) != 0 {
+                    keycodes.push((byte_index * 8 + bit) as ffi::KeyCode);
+                }
+            }
+        }
+        Ok(keycodes)
+    }
+
     fn lookup_utf8_inner(
         &self,
         ic: ffi::XIC,
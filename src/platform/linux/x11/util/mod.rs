@@ -7,11 +7,13 @@ mod format;
 mod geometry;
 mod hint;
 mod icon;
+mod idle;
 mod input;
 mod memory;
 mod randr;
 mod window_property;
 mod wm;
+mod xsettings;
 
 pub use self::atom::*;
 pub use self::client_msg::*;
@@ -54,6 +56,21 @@ where T:
     bitset & flag == flag
 }
 
+// Extracts the component selected by `mask` from a pixel of a `TrueColor` visual, scaled to 8 bits.
+pub fn pixel_component(pixel: c_ulong, mask: c_ulong) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones();
+    let value = (pixel & mask) >> shift;
+    if bits >= 8 {
+        (value >> (bits - 8)) as u8
+    } else {
+        (value * 255 / ((1 << bits) - 1)) as u8
+    }
+}
+
 #[must_use = "This request was made asynchronously, and is still in the output buffer. You must explicitly choose to either `.flush()` (empty the output buffer, sending the request now) or `.queue()` (wait to send the request, allowing you to continue to add more requests without additional round-trips). For more information, see the documentation for `util::flush_requests`."]
 pub struct Flusher<'a> {
     xconn: &'a XConnection,
@@ -78,6 +95,21 @@ impl<'a> Flusher<'a> {
     pub fn queue(self) {}
 }
 
+// For requests that winit can go on without. A window can be destroyed by the server or another
+// client at any time, after which every request about it fails, so their errors are logged rather
+// than panicked on. The error hook set with `os::unix::set_x11_error_hook` has already seen them.
+pub trait LogError {
+    fn log_error(self, context: &str);
+}
+
+impl<T> LogError for Result<T, XError> {
+    fn log_error(self, context: &str) {
+        if let Err(err) = self {
+            warn!("{}: {}", context, err);
+        }
+    }
+}
+
 impl XConnection {
     // This is impoartant, so pay attention!
     // Xlib has an output buffer, and tries to hide the async nature of X from you.
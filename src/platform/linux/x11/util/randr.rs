@@ -7,6 +7,7 @@ use super::*;
 pub fn calc_dpi_factor(
     (width_px, height_px): (u32, u32),
     (width_mm, height_mm): (u64, u64),
+    xft_dpi: Option<f64>,
 ) -> f64 {
     // Override DPI if `WINIT_HIDPI_FACTOR` variable is set
     let dpi_override = env::var("WINIT_HIDPI_FACTOR")
@@ -22,9 +23,18 @@ pub fn calc_dpi_factor(
         return dpi_override;
     }
 
+    // The DPI set by the desktop environment applies to every monitor, and reflects the scale the
+    // user picked, which the physical size of the monitor doesn't.
+    if let Some(xft_dpi) = xft_dpi {
+        let dpi_factor = xft_dpi / 96.0;
+        if validate_hidpi_factor(dpi_factor) {
+            return dpi_factor;
+        }
+    }
+
     // See http://xpra.org/trac/ticket/728 for more information.
     if width_mm == 0 || width_mm == 0 {
-        warn!("XRandR reported that the display's 0mm in size, which is certifiably insane");
+        warn!(target: "winit::x11::randr", "XRandR reported that the display's 0mm in size, which is certifiably insane");
         return 1.0;
     }
 
@@ -79,7 +89,12 @@ impl From<*mut ffi::XRRCrtcInfo> for MonitorRepr {
 }
 
 impl XConnection {
-    pub unsafe fn get_output_info(&self, resources: *mut ffi::XRRScreenResources, repr: &MonitorRepr) -> (String, f64) {
+    pub unsafe fn get_output_info(
+        &self,
+        resources: *mut ffi::XRRScreenResources,
+        repr: &MonitorRepr,
+        xft_dpi: Option<f64>,
+    ) -> (String, f64) {
         let output_info = (self.xrandr.XRRGetOutputInfo)(
             self.display,
             resources,
@@ -93,6 +108,7 @@ impl XConnection {
         let hidpi_factor = calc_dpi_factor(
             repr.get_dimensions(),
             ((*output_info).mm_width as u64, (*output_info).mm_height as u64),
+            xft_dpi,
         );
         (self.xrandr.XRRFreeOutputInfo)(output_info);
         (name, hidpi_factor)
@@ -27,6 +27,16 @@ impl GetPropertyError {
 // To test if `get_property` works correctly, set this to 1.
 const PROPERTY_BUFFER_SIZE: c_long = 1024; // 4k of RAM ought to be enough for anyone!
 
+/// The value of an X11 window property, in one of the three formats X11 supports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    /// Used for strings, such as `UTF8_STRING` properties.
+    Format8(Vec<u8>),
+    Format16(Vec<u16>),
+    /// Used for `CARDINAL`, `ATOM` and `WINDOW` properties, among others.
+    Format32(Vec<u32>),
+}
+
 #[derive(Debug)]
 pub enum PropMode {
     Replace = ffi::PropModeReplace as isize,
@@ -90,7 +100,8 @@ impl XConnection {
                         buf as *mut T,
                         quantity_returned as usize,
                     );
-                    /*println!(
+                    trace!(
+                        target: "winit::x11::property",
                         "XGetWindowProperty prop:{:?} fmt:{:02} len:{:02} off:{:02} out:{:02}, buf:{:?}",
                         property,
                         mem::size_of::<T>() * 8,
@@ -98,7 +109,7 @@ impl XConnection {
                         offset,
                         quantity_returned,
                         new_data,
-                    );*/
+                    );
                     data.extend_from_slice(&new_data);
                     // Fun fact: XGetWindowProperty allocates one extra byte at the end.
                     (self.xlib.XFree)(buf as _); // Don't try to access new_data after this.
@@ -113,6 +124,46 @@ impl XConnection {
         Ok(data)
     }
 
+    // Like `get_property`, but for properties whose format isn't known in advance.
+    pub fn get_property_value(
+        &self,
+        window: c_ulong,
+        property: ffi::Atom,
+        property_type: ffi::Atom,
+    ) -> Result<PropertyValue, GetPropertyError> {
+        match self.get_property::<c_uchar>(window, property, property_type) {
+            Err(GetPropertyError::FormatMismatch(16)) => self
+                .get_property::<c_ushort>(window, property, property_type)
+                .map(PropertyValue::Format16),
+            // Xlib stores 32-bit items in longs, which are 64-bit wide on some platforms.
+            Err(GetPropertyError::FormatMismatch(32)) => self
+                .get_property::<c_ulong>(window, property, property_type)
+                .map(|data| PropertyValue::Format32(data.into_iter().map(|item| item as u32).collect())),
+            result => result.map(PropertyValue::Format8),
+        }
+    }
+
+    pub fn change_property_value<'a>(
+        &'a self,
+        window: c_ulong,
+        property: ffi::Atom,
+        property_type: ffi::Atom,
+        new_value: &PropertyValue,
+    ) -> Flusher<'a> {
+        match *new_value {
+            PropertyValue::Format8(ref data) => {
+                self.change_property(window, property, property_type, PropMode::Replace, data)
+            },
+            PropertyValue::Format16(ref data) => {
+                self.change_property(window, property, property_type, PropMode::Replace, data)
+            },
+            PropertyValue::Format32(ref data) => {
+                let data: Vec<c_ulong> = data.iter().map(|&item| item as c_ulong).collect();
+                self.change_property(window, property, property_type, PropMode::Replace, &data)
+            },
+        }
+    }
+
     pub fn change_property<'a, T: Formattable>(
         &'a self,
         window: c_ulong,
@@ -134,11 +185,12 @@ impl XConnection {
                 new_value.len() as c_int,
             );
         }
-        /*println!(
+        trace!(
+            target: "winit::x11::property",
             "XChangeProperty prop:{:?} val:{:?}",
             property,
             new_value,
-        );*/
+        );
         Flusher::new(self)
     }
 }
@@ -26,6 +26,84 @@ impl XConnection {
         *WM_NAME.lock() = self.get_wm_name(root);
     }
 
+    /// Activates `window` through `_NET_ACTIVE_WINDOW` if the WM supports it, which raises it and
+    /// gives it input focus, or only gives it input focus otherwise.
+    pub fn activate_window(&self, window: ffi::Window, root: ffi::Window) -> Flusher {
+        let active_window_atom = unsafe { self.get_atom_unchecked(b"_NET_ACTIVE_WINDOW\0") };
+        if hint_is_supported(active_window_atom) {
+            self.send_client_msg(
+                window,
+                root,
+                active_window_atom,
+                Some(ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask),
+                [
+                    1, // source indication: normal application
+                    ffi::CurrentTime as c_long,
+                    0,
+                    0,
+                    0,
+                ],
+            )
+        } else {
+            unsafe {
+                (self.xlib.XSetInputFocus)(
+                    self.display,
+                    window,
+                    ffi::RevertToParent,
+                    ffi::CurrentTime,
+                );
+            }
+            Flusher::new(self)
+        }
+    }
+
+    /// Returns the windows managed by the WM from bottom to top, or nothing if it doesn't support
+    /// `_NET_CLIENT_LIST_STACKING`.
+    pub fn get_client_list_stacking(&self, root: ffi::Window) -> Vec<ffi::Window> {
+        let stacking_atom = unsafe { self.get_atom_unchecked(b"_NET_CLIENT_LIST_STACKING\0") };
+        self.get_property(
+            root,
+            stacking_atom,
+            ffi::XA_WINDOW,
+        ).unwrap_or_else(|_| Vec::with_capacity(0))
+    }
+
+    /// Returns the `_NET_WM_STRUT_PARTIAL` of every window managed by the WM. For the windows that
+    /// only have a `_NET_WM_STRUT`, the space is reserved along the whole length of the edges.
+    pub fn get_struts(&self, root: ffi::Window) -> Vec<[c_ulong; 12]> {
+        let client_list_atom = unsafe { self.get_atom_unchecked(b"_NET_CLIENT_LIST\0") };
+        let strut_partial_atom = unsafe { self.get_atom_unchecked(b"_NET_WM_STRUT_PARTIAL\0") };
+        let strut_atom = unsafe { self.get_atom_unchecked(b"_NET_WM_STRUT\0") };
+        let client_list: Vec<ffi::Window> = self.get_property(
+            root,
+            client_list_atom,
+            ffi::XA_WINDOW,
+        ).unwrap_or_else(|_| Vec::with_capacity(0));
+        client_list.into_iter().filter_map(|window| {
+            let strut_partial: Vec<c_ulong> = self.get_property(
+                window,
+                strut_partial_atom,
+                ffi::XA_CARDINAL,
+            ).unwrap_or_else(|_| Vec::with_capacity(0));
+            if strut_partial.len() >= 12 {
+                let mut strut = [0; 12];
+                strut.copy_from_slice(&strut_partial[..12]);
+                return Some(strut);
+            }
+            let strut: Vec<c_ulong> = self.get_property(
+                window,
+                strut_atom,
+                ffi::XA_CARDINAL,
+            ).unwrap_or_else(|_| Vec::with_capacity(0));
+            if strut.len() >= 4 {
+                let end = c_ulong::max_value();
+                Some([strut[0], strut[1], strut[2], strut[3], 0, end, 0, end, 0, end, 0, end])
+            } else {
+                None
+            }
+        }).collect()
+    }
+
     fn get_supported_hints(&self, root: ffi::Window) -> Vec<ffi::Atom> {
         let supported_atom = unsafe { self.get_atom_unchecked(b"_NET_SUPPORTED\0") };
         self.get_property(
@@ -0,0 +1,245 @@
+use std::ffi::CString;
+use std::str;
+
+use super::*;
+
+// Types of the settings in the `_XSETTINGS_SETTINGS` property.
+const XSETTINGS_TYPE_INTEGER: u8 = 0;
+const XSETTINGS_TYPE_STRING: u8 = 1;
+const XSETTINGS_TYPE_COLOR: u8 = 2;
+
+// Reads the settings of an XSETTINGS manager, as described by
+// https://specifications.freedesktop.org/xsettings-spec/xsettings-latest.html
+struct SettingsReader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> SettingsReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(taken)
+    }
+
+    fn take_padded(&mut self, len: usize) -> Option<&'a [u8]> {
+        let taken = self.take(len)?;
+        self.take((4 - len % 4) % 4)?;
+        Some(taken)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.take(2)?;
+        let (high, low) = if self.big_endian { (bytes[0], bytes[1]) } else { (bytes[1], bytes[0]) };
+        Some((high as u16) << 8 | low as u16)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let (first, second) = (self.u16()? as u32, self.u16()? as u32);
+        Some(if self.big_endian { first << 16 | second } else { second << 16 | first })
+    }
+}
+
+// Finds the `Xft/DPI` integer setting, which is the DPI multiplied by 1024.
+fn parse_xsettings_dpi(data: &[u8]) -> Option<f64> {
+    let big_endian = *data.get(0)? != 0;
+    let mut reader = SettingsReader { data, big_endian };
+    reader.take(4)?;
+    let _serial = reader.u32()?;
+    let count = reader.u32()?;
+    for _ in 0..count {
+        let setting_type = reader.take(2)?[0];
+        let name_len = reader.u16()? as usize;
+        let name = reader.take_padded(name_len)?;
+        let _last_change_serial = reader.u32()?;
+        match setting_type {
+            XSETTINGS_TYPE_INTEGER => {
+                let value = reader.u32()? as i32;
+                if name == b"Xft/DPI" {
+                    return if value > 0 { Some(value as f64 / 1024.0) } else { None };
+                }
+            },
+            XSETTINGS_TYPE_STRING => {
+                let len = reader.u32()? as usize;
+                reader.take_padded(len)?;
+            },
+            XSETTINGS_TYPE_COLOR => {
+                reader.take(8)?;
+            },
+            _ => return None,
+        }
+    }
+    None
+}
+
+// Finds the `Xft.dpi` resource in the contents of `RESOURCE_MANAGER`, which are the same as
+// those of an `.Xresources` file after it's been loaded by `xrdb`.
+fn parse_xft_dpi_resource(resources: &str) -> Option<f64> {
+    resources
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if name.trim() == "Xft.dpi" => value.trim().parse().ok(),
+                _ => None,
+            }
+        })
+        .last()
+}
+
+impl XConnection {
+    pub fn get_xsettings_selection(&self) -> ffi::Atom {
+        let screen = unsafe { (self.xlib.XDefaultScreen)(self.display) };
+        self.get_atom(CString::new(format!("_XSETTINGS_S{}", screen)).unwrap())
+    }
+
+    /// Returns the window of the XSETTINGS manager, which desktop environments run to share their
+    /// settings with applications, if there's one.
+    pub fn get_xsettings_manager(&self) -> Option<ffi::Window> {
+        let selection = self.get_xsettings_selection();
+        let manager = unsafe { (self.xlib.XGetSelectionOwner)(self.display, selection) };
+        if manager != 0 { Some(manager) } else { None }
+    }
+
+    fn get_xsettings_dpi(&self) -> Option<f64> {
+        let manager = self.get_xsettings_manager()?;
+        let settings_atom = unsafe { self.get_atom_unchecked(b"_XSETTINGS_SETTINGS\0") };
+        // The manager can exit at any time, so errors are expected here.
+        let data = self.get_property::<c_uchar>(manager, settings_atom, settings_atom).ok()?;
+        parse_xsettings_dpi(&data)
+    }
+
+    fn get_xft_dpi_resource(&self) -> Option<f64> {
+        let root = unsafe { (self.xlib.XDefaultRootWindow)(self.display) };
+        let resource_manager_atom = unsafe { self.get_atom_unchecked(b"RESOURCE_MANAGER\0") };
+        let data = self.get_property::<c_uchar>(root, resource_manager_atom, ffi::XA_STRING).ok()?;
+        parse_xft_dpi_resource(str::from_utf8(&data).ok()?)
+    }
+
+    /// Returns the DPI that the desktop environment asks applications to use, from the `Xft/DPI`
+    /// setting of the XSETTINGS manager, or else from the `Xft.dpi` resource.
+    pub fn get_xft_dpi(&self) -> Option<f64> {
+        self.get_xsettings_dpi().or_else(|| self.get_xft_dpi_resource())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds the contents of `_XSETTINGS_SETTINGS` in either byte order.
+    struct SettingsWriter {
+        data: Vec<u8>,
+        big_endian: bool,
+    }
+
+    impl SettingsWriter {
+        fn new(big_endian: bool, count: u32) -> SettingsWriter {
+            let mut writer = SettingsWriter { data: Vec::new(), big_endian };
+            writer.data.extend_from_slice(&[big_endian as u8, 0, 0, 0]);
+            writer.u32(7);
+            writer.u32(count);
+            writer
+        }
+
+        fn u16(&mut self, value: u16) {
+            let bytes = [(value >> 8) as u8, value as u8];
+            if self.big_endian {
+                self.data.extend_from_slice(&bytes);
+            } else {
+                self.data.extend_from_slice(&[bytes[1], bytes[0]]);
+            }
+        }
+
+        fn u32(&mut self, value: u32) {
+            if self.big_endian {
+                self.u16((value >> 16) as u16);
+                self.u16(value as u16);
+            } else {
+                self.u16(value as u16);
+                self.u16((value >> 16) as u16);
+            }
+        }
+
+        fn padded(&mut self, bytes: &[u8]) {
+            self.data.extend_from_slice(bytes);
+            let padding = (4 - bytes.len() % 4) % 4;
+            self.data.extend(::std::iter::repeat(0).take(padding));
+        }
+
+        fn header(&mut self, setting_type: u8, name: &str) {
+            self.data.extend_from_slice(&[setting_type, 0]);
+            self.u16(name.len() as u16);
+            self.padded(name.as_bytes());
+            self.u32(0);
+        }
+
+        fn integer(&mut self, name: &str, value: i32) {
+            self.header(XSETTINGS_TYPE_INTEGER, name);
+            self.u32(value as u32);
+        }
+
+        fn string(&mut self, name: &str, value: &str) {
+            self.header(XSETTINGS_TYPE_STRING, name);
+            self.u32(value.len() as u32);
+            self.padded(value.as_bytes());
+        }
+
+        fn color(&mut self, name: &str) {
+            self.header(XSETTINGS_TYPE_COLOR, name);
+            self.data.extend_from_slice(&[0xff; 8]);
+        }
+    }
+
+    fn settings(big_endian: bool) -> Vec<u8> {
+        let mut writer = SettingsWriter::new(big_endian, 4);
+        writer.string("Net/ThemeName", "Adwaita");
+        writer.color("Gtk/Color");
+        writer.integer("Gdk/WindowScalingFactor", 2);
+        writer.integer("Xft/DPI", 144 * 1024);
+        writer.data
+    }
+
+    #[test]
+    fn xsettings_dpi_in_either_byte_order() {
+        assert_eq!(parse_xsettings_dpi(&settings(false)), Some(144.0));
+        assert_eq!(parse_xsettings_dpi(&settings(true)), Some(144.0));
+    }
+
+    #[test]
+    fn xsettings_without_dpi() {
+        let mut writer = SettingsWriter::new(false, 1);
+        writer.integer("Net/DoubleClickTime", 400);
+        assert_eq!(parse_xsettings_dpi(&writer.data), None);
+
+        let mut writer = SettingsWriter::new(true, 1);
+        writer.integer("Xft/DPI", -1);
+        assert_eq!(parse_xsettings_dpi(&writer.data), None);
+    }
+
+    #[test]
+    fn truncated_xsettings() {
+        for &big_endian in &[false, true] {
+            let data = settings(big_endian);
+            for len in 0..data.len() {
+                assert_eq!(parse_xsettings_dpi(&data[..len]), None);
+            }
+        }
+
+        // More settings are announced than there are.
+        let mut writer = SettingsWriter::new(false, 2);
+        writer.integer("Net/DoubleClickTime", 400);
+        assert_eq!(parse_xsettings_dpi(&writer.data), None);
+    }
+
+    #[test]
+    fn xft_dpi_resource() {
+        let resources = "Xcursor.size:\t24\nXft.dpi:\t96\nXft.antialias:\t1\nXft.dpi: 120\n";
+        assert_eq!(parse_xft_dpi_resource(resources), Some(120.0));
+        assert_eq!(parse_xft_dpi_resource("Xft.dpi:\tlarge\n"), None);
+        assert_eq!(parse_xft_dpi_resource("Xcursor.size:\t24\n"), None);
+    }
+}
@@ -1,4 +1,5 @@
-use std::{cmp, env, mem};
+use std::{cmp, env, mem, ptr};
+use std::collections::HashSet;
 use std::ffi::CString;
 use std::os::raw::*;
 use std::path::Path;
@@ -7,15 +8,22 @@ use std::sync::Arc;
 use libc;
 use parking_lot::Mutex;
 
-use {Icon, MouseCursor, WindowAttributes};
-use CreationError::{self, OsError};
+use {ActivationPolicy, CapturedFrame, CursorGrabMode, DamageRect, Edge, HitTestCallback, HitTestResult, Icon, MouseCursor, RawWindowHandle, Rect, WindowAttributes};
+use CreationError::{self, InvalidAttributes, OsError, OsErrorCode, PermissionDenied};
 use dpi::{LogicalPosition, LogicalSize};
 use platform::MonitorId as PlatformMonitorId;
 use platform::PlatformSpecificWindowBuilderAttributes;
 use platform::x11::MonitorId as X11MonitorId;
 use window::MonitorId as RootMonitorId;
 
-use super::{ffi, util, ImeSender, XConnection, XError, WindowId, EventsLoop};
+use super::{ffi, monitor, util, xembed, ImeSender, XConnection, XError, WindowId, EventsLoop};
+use super::util::LogError;
+
+// The cursor size most themes use at a DPI factor of 1.
+const BASE_CURSOR_SIZE: f64 = 24.0;
+
+// The first element of `WM_STATE` while the window is minimized. Not declared by Xlib.
+const ICONIC_STATE: c_ulong = 3;
 
 unsafe extern "C" fn visibility_predicate(
     _display: *mut ffi::Display,
@@ -42,12 +50,47 @@ pub struct SharedState {
     pub frame_extents: Option<util::FrameExtentsHeuristic>,
     pub min_dimensions: Option<LogicalSize>,
     pub max_dimensions: Option<LogicalSize>,
+    pub occluded: bool,
+    // Whether `_NET_WM_STATE` contained `_NET_WM_STATE_FULLSCREEN` the last time it changed.
+    pub fullscreen: bool,
+    // Set while a fullscreen window is being moved to another monitor, until it has moved.
+    pub fullscreen_monitor_switch: bool,
+    // Whether `WM_STATE` was `IconicState` the last time it changed.
+    pub minimized: bool,
+    // The rectangles of the `Expose` events received since the last `Refresh`.
+    pub damage: Vec<DamageRect>,
+    // Whether `_NET_WM_STATE` contained both maximization states the last time it changed.
+    pub maximized: bool,
+    // Whether the window has input focus, as of the last `XI_FocusIn` or `XI_FocusOut`.
+    pub has_focus: bool,
+    // As last requested with `show` or `hide`, `set_resizable` and `set_decorations`.
+    pub visible: bool,
+    pub resizable: bool,
+    pub decorations: bool,
+    // Set while the window is kept unmapped until `present_ready` is called.
+    pub pending_present: bool,
+    // Properties for which `X11PropertyChanged` is sent.
+    pub watched_properties: HashSet<ffi::Atom>,
+    // The window this window is embedded into with XEmbed, and whether the embedder is active and
+    // has given this window focus.
+    pub embedder: Option<ffi::Window>,
+    pub embedder_active: bool,
+    pub embedder_focused: bool,
+    // The window of another application embedded into this window with XEmbed.
+    pub embedded_client: Option<ffi::Window>,
+    // Set while the window is guessed to be moved or resized by the user.
+    pub interactive_resize: bool,
+    pub interactive_move: bool,
 }
 
 impl SharedState {
-    fn new(dpi_factor: f64) -> Mutex<Self> {
+    fn new(dpi_factor: f64, window_attrs: &WindowAttributes) -> Mutex<Self> {
         let mut shared_state = SharedState::default();
         shared_state.guessed_dpi = Some(dpi_factor);
+        shared_state.pending_present = window_attrs.visible && window_attrs.visible_on_first_present;
+        shared_state.visible = window_attrs.visible && !shared_state.pending_present;
+        shared_state.resizable = window_attrs.resizable;
+        shared_state.decorations = window_attrs.decorations;
         Mutex::new(shared_state)
     }
 }
@@ -55,16 +98,29 @@ impl SharedState {
 unsafe impl Send for UnownedWindow {}
 unsafe impl Sync for UnownedWindow {}
 
+#[derive(Default)]
+struct CursorGrab {
+    mode: CursorGrabMode,
+    region: Option<Rect>,
+    // The child window the pointer is confined to, for a confine region or a lock.
+    confine_window: Option<ffi::Window>,
+}
+
 pub struct UnownedWindow {
     pub xconn: Arc<XConnection>, // never changes
     xwindow: ffi::Window, // never changes
     root: ffi::Window, // never changes
+    colormap: ffi::Colormap, // never changes
     screen_id: i32, // never changes
     cursor: Mutex<MouseCursor>,
-    cursor_grabbed: Mutex<bool>,
+    cursor_grab: Mutex<CursorGrab>,
     cursor_hidden: Mutex<bool>,
     ime_sender: Mutex<ImeSender>,
+    hit_test: Mutex<Option<HitTestCallback>>,
     pub multitouch: bool, // never changes
+    pub static_hidpi_factor: Option<f64>, // never changes
+    pub dynamic_scaling: bool, // never changes
+    hidpi_cursors: bool, // never changes
     pub shared_state: Mutex<SharedState>,
 }
 
@@ -77,8 +133,17 @@ impl UnownedWindow {
         let xconn = &event_loop.xconn;
         let root = event_loop.root;
 
-        let monitors = xconn.get_available_monitors();
-        let dpi_factor = if !monitors.is_empty() {
+        let parent = match window_attrs.parent {
+            Some(RawWindowHandle::Xlib(parent)) => Some(parent),
+            Some(_) => return Err(InvalidAttributes("The parent handle isn't an X11 window.".to_owned())),
+            None => None,
+        };
+        let parent = pl_attribs.embed_parent.or(parent);
+
+        let monitors = monitor::get_available_monitors(xconn);
+        let dpi_factor = if let Some(forced_hidpi_factor) = window_attrs.forced_hidpi_factor {
+            forced_hidpi_factor
+        } else if !monitors.is_empty() {
             let mut dpi_factor = Some(monitors[0].get_hidpi_factor());
             for monitor in &monitors {
                 if Some(monitor.get_hidpi_factor()) != dpi_factor {
@@ -134,20 +199,31 @@ impl UnownedWindow {
             dimensions
         };
 
+        // A window that starts out fullscreen is created on the monitor it'll cover, so the WM
+        // doesn't have to move it there once it's mapped.
+        let fullscreen_position: Option<(i32, i32)> = match window_attrs.fullscreen {
+            Some(RootMonitorId { inner: PlatformMonitorId::X(ref monitor) }) => {
+                Some(monitor.get_position().into())
+            },
+            _ => None,
+        };
+
         let screen_id = match pl_attribs.screen_id {
             Some(id) => id,
             None => unsafe { (xconn.xlib.XDefaultScreen)(xconn.display) },
         };
 
+        let colormap = if let Some(vi) = pl_attribs.visual_infos {
+            unsafe {
+                let visual = vi.visual;
+                (xconn.xlib.XCreateColormap)(xconn.display, root, visual, ffi::AllocNone)
+            }
+        } else { 0 };
+
         // creating
         let mut set_win_attr = {
             let mut swa: ffi::XSetWindowAttributes = unsafe { mem::zeroed() };
-            swa.colormap = if let Some(vi) = pl_attribs.visual_infos {
-                unsafe {
-                    let visual = vi.visual;
-                    (xconn.xlib.XCreateColormap)(xconn.display, root, visual, ffi::AllocNone)
-                }
-            } else { 0 };
+            swa.colormap = colormap;
             swa.event_mask = ffi::ExposureMask
                 | ffi::StructureNotifyMask
                 | ffi::VisibilityChangeMask
@@ -156,7 +232,8 @@ impl UnownedWindow {
                 | ffi::KeymapStateMask
                 | ffi::ButtonPressMask
                 | ffi::ButtonReleaseMask
-                | ffi::PointerMotionMask;
+                | ffi::PointerMotionMask
+                | ffi::PropertyChangeMask;
             swa.border_pixel = 0;
             swa.override_redirect = pl_attribs.override_redirect as c_int;
             swa
@@ -172,9 +249,9 @@ impl UnownedWindow {
         let xwindow = unsafe {
             (xconn.xlib.XCreateWindow)(
                 xconn.display,
-                root,
-                0,
-                0,
+                parent.unwrap_or(root),
+                fullscreen_position.map_or(0, |position| position.0),
+                fullscreen_position.map_or(0, |position| position.1),
                 dimensions.0 as c_uint,
                 dimensions.1 as c_uint,
                 0,
@@ -196,13 +273,18 @@ impl UnownedWindow {
             xconn: Arc::clone(xconn),
             xwindow,
             root,
+            colormap,
             screen_id,
             cursor: Default::default(),
-            cursor_grabbed: Default::default(),
+            cursor_grab: Default::default(),
             cursor_hidden: Default::default(),
             ime_sender: Mutex::new(event_loop.ime_sender.clone()),
+            hit_test: Default::default(),
             multitouch: window_attrs.multitouch,
-            shared_state: SharedState::new(dpi_factor),
+            static_hidpi_factor: window_attrs.forced_hidpi_factor.map(|_| dpi_factor),
+            dynamic_scaling: pl_attribs.dynamic_x11_scaling,
+            hidpi_cursors: pl_attribs.hidpi_cursors,
+            shared_state: SharedState::new(dpi_factor, &window_attrs),
         };
 
         // Title must be set before mapping. Some tiling window managers (i.e. i3) use the window
@@ -211,6 +293,11 @@ impl UnownedWindow {
         window.set_title_inner(&window_attrs.title).queue();
         window.set_decorations_inner(window_attrs.decorations).queue();
 
+        if let Some(embedder) = pl_attribs.embed_parent {
+            window.shared_state.lock().embedder = Some(embedder);
+            xembed::set_info(xconn, window.xwindow, window_attrs.visible).queue();
+        }
+
         {
             // Enable drag and drop (TODO: extend API to make this toggleable)
             unsafe {
@@ -279,6 +366,7 @@ impl UnownedWindow {
                 if !window_attrs.resizable {
                     if util::wm_name_is_one_of(&["Xfwm4"]) {
                         warn!("To avoid a WM bug, disabling resizing has no effect on Xfwm4");
+                        window.shared_state.lock().resizable = true;
                     } else {
                         max_dimensions = Some(dimensions.into());
                         min_dimensions = Some(dimensions.into());
@@ -290,6 +378,11 @@ impl UnownedWindow {
                 }
 
                 let mut normal_hints = util::NormalHints::new(xconn);
+                normal_hints.set_position(fullscreen_position);
+                // This is the default, but some WMs treat a missing gravity as `StaticGravity`,
+                // which makes `XMoveWindow` move the client area instead of the frame, and windows
+                // drift by the size of their title bar each time their position is restored.
+                normal_hints.set_gravity(Some(ffi::NorthWestGravity));
                 normal_hints.set_size(Some(dimensions));
                 normal_hints.set_min_size(min_dimensions.map(Into::into));
                 normal_hints.set_max_size(max_dimensions.map(Into::into));
@@ -303,18 +396,52 @@ impl UnownedWindow {
                 window.set_icon_inner(icon).queue();
             }
 
-            // Opt into handling window close
+            if window_attrs.blur {
+                window.set_blur_inner(true).queue();
+            }
+
+            if !window_attrs.focusable {
+                window.set_focusable_inner(false)
+                    .map(util::Flusher::queue)
+                    .log_error("Failed to set input hint");
+            }
+
+            // A user time of 0 asks the WM not to focus the window when it's mapped.
+            if !window_attrs.active {
+                let user_time_atom = unsafe { xconn.get_atom_unchecked(b"_NET_WM_USER_TIME\0") };
+                xconn.change_property(
+                    window.xwindow,
+                    user_time_atom,
+                    ffi::XA_CARDINAL,
+                    util::PropMode::Replace,
+                    &[0 as util::Cardinal],
+                ).queue();
+            }
+
+            // Opt into handling window close, and being told when the session ends
             unsafe {
+                let mut protocols = [event_loop.wm_delete_window, event_loop.wm_save_yourself];
                 (xconn.xlib.XSetWMProtocols)(
                     xconn.display,
                     window.xwindow,
-                    &event_loop.wm_delete_window as *const ffi::Atom as *mut ffi::Atom,
-                    1,
+                    protocols.as_mut_ptr(),
+                    protocols.len() as c_int,
                 );
             }//.queue();
 
+            // Until the window is mapped, the WM takes its initial state from the property, rather
+            // than from messages. Setting it here means the window is shown maximized or
+            // fullscreen right away, instead of being shown normally and resized afterwards.
+            window.set_initial_state(
+                window_attrs.maximized,
+                fullscreen_position.is_some(),
+                window_attrs.always_on_top,
+                event_loop.activation_policy != ActivationPolicy::Regular,
+            ).queue();
+
             // Set visibility (map window)
-            if window_attrs.visible {
+            let map_on_creation = window_attrs.visible && !window_attrs.visible_on_first_present;
+            if map_on_creation {
                 unsafe {
                     (xconn.xlib.XMapRaised)(xconn.display, window.xwindow);
                 }//.queue();
@@ -329,6 +456,7 @@ impl UnownedWindow {
                     &mut supported_ptr,
                 );
                 if supported_ptr == ffi::False {
+                    window.destroy();
                     return Err(OsError(format!("`XkbSetDetectableAutoRepeat` failed")));
                 }
             }
@@ -358,22 +486,14 @@ impl UnownedWindow {
                     .borrow_mut()
                     .create_context(window.xwindow);
                 if let Err(err) = result {
+                    window.destroy();
                     return Err(OsError(format!("Failed to create input context: {:?}", err)));
                 }
             }
 
-            // These properties must be set after mapping
-            if window_attrs.maximized {
-                window.set_maximized_inner(window_attrs.maximized).queue();
-            }
-            if window_attrs.fullscreen.is_some() {
-                window.set_fullscreen_inner(window_attrs.fullscreen.clone()).queue();
-            }
-            if window_attrs.always_on_top {
-                window.set_always_on_top_inner(window_attrs.always_on_top).queue();
-            }
-
-            if window_attrs.visible {
+            // Child windows are given focus by their parent.
+            let take_focus = window_attrs.active && window_attrs.focusable && parent.is_none();
+            if map_on_creation && take_focus {
                 unsafe {
                     // XSetInputFocus generates an error if the window is not visible, so we wait
                     // until we receive VisibilityNotify.
@@ -395,11 +515,31 @@ impl UnownedWindow {
         }
 
         // We never want to give the user a broken window, since by then, it's too late to handle.
-        xconn.sync_with_server()
-            .map(|_| window)
-            .map_err(|x_err| OsError(
-                format!("X server returned error while building window: {:?}", x_err)
-            ))
+        match xconn.sync_with_server() {
+            Ok(()) => Ok(window),
+            Err(x_err) => {
+                window.destroy();
+                let description = format!("X server returned error while building window: {:?}", x_err);
+                Err(if x_err.error_code == ffi::BadAccess {
+                    PermissionDenied(description)
+                } else {
+                    OsErrorCode(x_err.error_code as i32, description)
+                })
+            },
+        }
+    }
+
+    // Frees everything we created on the server for this window. Its input context is destroyed
+    // by the `EventsLoop` when it receives `DestroyNotify`, since that has to happen on its thread.
+    pub(super) fn destroy(&self) {
+        unsafe {
+            (self.xconn.xlib.XDestroyWindow)(self.xconn.display, self.xwindow);
+            if self.colormap != 0 {
+                (self.xconn.xlib.XFreeColormap)(self.xconn.display, self.colormap);
+            }
+            // If the window was somehow already destroyed, we'll get a `BadWindow` error, which we don't care about.
+            let _ = self.xconn.check_errors();
+        }
     }
 
     fn logicalize_coords(&self, (x, y): (i32, i32)) -> LogicalPosition {
@@ -459,13 +599,42 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_urgent(&self, is_urgent: bool) {
-        let mut wm_hints = self.xconn.get_wm_hints(self.xwindow).expect("`XGetWMHints` failed");
+        let mut wm_hints = match self.xconn.get_wm_hints(self.xwindow) {
+            Ok(wm_hints) => wm_hints,
+            Err(err) => {
+                warn!("`XGetWMHints` failed: {}", err);
+                return;
+            },
+        };
         if is_urgent {
             (*wm_hints).flags |= ffi::XUrgencyHint;
         } else {
             (*wm_hints).flags &= !ffi::XUrgencyHint;
         }
-        self.xconn.set_wm_hints(self.xwindow, wm_hints).flush().expect("Failed to set urgency hint");
+        self.xconn.set_wm_hints(self.xwindow, wm_hints).flush().log_error("Failed to set urgency hint");
+    }
+
+    pub fn get_property(&self, property: &str, property_type: &str) -> Option<util::PropertyValue> {
+        let property = self.xconn.get_atom_from_str(property)?;
+        let property_type = self.xconn.get_atom_from_str(property_type)?;
+        self.xconn.get_property_value(self.xwindow, property, property_type).ok()
+    }
+
+    pub fn change_property(&self, property: &str, property_type: &str, value: &util::PropertyValue) {
+        let property = self.xconn.get_atom_from_str(property);
+        let property_type = self.xconn.get_atom_from_str(property_type);
+        if let (Some(property), Some(property_type)) = (property, property_type) {
+            self.xconn.change_property_value(self.xwindow, property, property_type, value)
+                .flush()
+                .log_error("Failed to change window property");
+        }
+    }
+
+    // The window always selects `PropertyChangeMask`, so there's nothing to ask the server.
+    pub fn watch_property(&self, property: &str) {
+        if let Some(property) = self.xconn.get_atom_from_str(property) {
+            self.shared_state.lock().watched_properties.insert(property);
+        }
     }
 
     fn set_netwm(
@@ -489,6 +658,64 @@ impl UnownedWindow {
         )
     }
 
+    fn set_initial_state(
+        &self,
+        maximized: bool,
+        fullscreen: bool,
+        always_on_top: bool,
+        skip_taskbar: bool,
+    ) -> util::Flusher {
+        let mut states = Vec::new();
+        unsafe {
+            if maximized {
+                states.push(self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_HORZ\0"));
+                states.push(self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_VERT\0"));
+            }
+            if fullscreen {
+                states.push(self.xconn.get_atom_unchecked(b"_NET_WM_STATE_FULLSCREEN\0"));
+            }
+            if always_on_top {
+                states.push(self.xconn.get_atom_unchecked(b"_NET_WM_STATE_ABOVE\0"));
+            }
+            if skip_taskbar {
+                states.push(self.xconn.get_atom_unchecked(b"_NET_WM_STATE_SKIP_TASKBAR\0"));
+                states.push(self.xconn.get_atom_unchecked(b"_NET_WM_STATE_SKIP_PAGER\0"));
+            }
+        }
+        let state_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE\0") };
+        self.xconn.change_property(
+            self.xwindow,
+            state_atom,
+            ffi::XA_ATOM,
+            util::PropMode::Replace,
+            &states,
+        )
+    }
+
+    pub(crate) fn is_fullscreen_state_set(&self) -> bool {
+        let state_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE\0") };
+        let fullscreen_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_FULLSCREEN\0") };
+        self.xconn.get_property::<ffi::Atom>(self.xwindow, state_atom, ffi::XA_ATOM)
+            .map(|states| states.contains(&fullscreen_atom))
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn is_maximized_state_set(&self) -> bool {
+        let state_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE\0") };
+        let horz_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_HORZ\0") };
+        let vert_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_MAXIMIZED_VERT\0") };
+        self.xconn.get_property::<ffi::Atom>(self.xwindow, state_atom, ffi::XA_ATOM)
+            .map(|states| states.contains(&horz_atom) && states.contains(&vert_atom))
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn is_iconic_state_set(&self) -> bool {
+        let wm_state_atom = unsafe { self.xconn.get_atom_unchecked(b"WM_STATE\0") };
+        self.xconn.get_property::<c_ulong>(self.xwindow, wm_state_atom, wm_state_atom)
+            .map(|state| state.first() == Some(&ICONIC_STATE))
+            .unwrap_or(false)
+    }
+
     fn set_fullscreen_hint(&self, fullscreen: bool) -> util::Flusher {
         let fullscreen_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STATE_FULLSCREEN\0") };
         self.set_netwm(fullscreen.into(), (fullscreen_atom as c_long, 0, 0, 0))
@@ -498,14 +725,34 @@ impl UnownedWindow {
         match monitor {
             None => {
                 let flusher = self.set_fullscreen_hint(false);
-                if let Some(position) = self.shared_state.lock().restore_position.take() {
+                let restore_position = {
+                    let mut shared_state_lock = self.shared_state.lock();
+                    // A move to another monitor that hasn't landed yet mustn't report the window
+                    // as fullscreen anymore.
+                    shared_state_lock.fullscreen_monitor_switch = false;
+                    shared_state_lock.restore_position.take()
+                };
+                if let Some(position) = restore_position {
                     self.set_position_inner(position.0, position.1).queue();
                 }
                 flusher
             },
             Some(RootMonitorId { inner: PlatformMonitorId::X(monitor) }) => {
-                let window_position = self.get_position_physical();
-                self.shared_state.lock().restore_position = window_position;
+                {
+                    let current_monitor = self.get_current_monitor();
+                    let mut shared_state_lock = self.shared_state.lock();
+                    if shared_state_lock.restore_position.is_some() || shared_state_lock.fullscreen {
+                        // Already fullscreen, so the window is moved straight to the new monitor
+                        // and stays fullscreen, rather than going through its windowed state.
+                        // Staying on the same monitor doesn't move it at all.
+                        shared_state_lock.fullscreen_monitor_switch = monitor.get_native_identifier()
+                            != current_monitor.get_native_identifier();
+                    } else {
+                        shared_state_lock.restore_position = self.get_position_physical();
+                    }
+                }
+                // The move and the fullscreen state are flushed together, so the WM sees them
+                // at once.
                 let monitor_origin: (i32, i32) = monitor.get_position().into();
                 self.set_position_inner(monitor_origin.0, monitor_origin.1).queue();
                 self.set_fullscreen_hint(true)
@@ -518,7 +765,7 @@ impl UnownedWindow {
     pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
         self.set_fullscreen_inner(monitor)
             .flush()
-            .expect("Failed to change window fullscreen state");
+            .log_error("Failed to change window fullscreen state");
         self.invalidate_cached_frame_extents();
     }
 
@@ -540,18 +787,22 @@ impl UnownedWindow {
             .cloned();
         monitor
             .unwrap_or_else(|| {
-                let monitor = self.xconn.get_monitor_for_window(self.get_rect()).to_owned();
+                let monitor = monitor::get_monitor_for_window(
+                    &self.xconn,
+                    self.get_rect(),
+                    self.dynamic_scaling,
+                ).to_owned();
                 self.shared_state.lock().last_monitor = Some(monitor.clone());
                 monitor
             })
     }
 
     pub fn get_available_monitors(&self) -> Vec<X11MonitorId> {
-        self.xconn.get_available_monitors()
+        monitor::get_available_monitors(&self.xconn)
     }
 
     pub fn get_primary_monitor(&self) -> X11MonitorId {
-        self.xconn.get_primary_monitor()
+        monitor::get_primary_monitor(&self.xconn)
     }
 
     fn set_maximized_inner(&self, maximized: bool) -> util::Flusher {
@@ -564,26 +815,122 @@ impl UnownedWindow {
     pub fn set_maximized(&self, maximized: bool) {
         self.set_maximized_inner(maximized)
             .flush()
-            .expect("Failed to change window maximization");
+            .log_error("Failed to change window maximization");
         self.invalidate_cached_frame_extents();
     }
 
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) {
+        unsafe {
+            if minimized {
+                (self.xconn.xlib.XIconifyWindow)(self.xconn.display, self.xwindow, self.screen_id);
+            } else if self.is_iconic_state_set() {
+                // Mapping a minimized window restores it. Windows that aren't minimized are left
+                // alone, so that hidden windows aren't shown.
+                (self.xconn.xlib.XMapRaised)(self.xconn.display, self.xwindow);
+            }
+        }
+        self.xconn.flush_requests().log_error("Failed to change window minimization");
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        self.is_iconic_state_set()
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        self.shared_state.lock().maximized
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.shared_state.lock().fullscreen
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.shared_state.lock().visible
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.shared_state.lock().has_focus
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+        let embedder = self.shared_state.lock().embedder;
+        match embedder {
+            Some(embedder) => xembed::send_message(
+                &self.xconn,
+                embedder,
+                xembed::XEMBED_REQUEST_FOCUS,
+                0,
+                0,
+                0,
+            ),
+            None => self.xconn.activate_window(self.xwindow, self.root),
+        }.flush().log_error("Failed to activate window");
+    }
+
+    fn set_focusable_inner(&self, focusable: bool) -> Result<util::Flusher, XError> {
+        let mut wm_hints = self.xconn.get_wm_hints(self.xwindow)?;
+        (*wm_hints).flags |= ffi::InputHint;
+        (*wm_hints).input = if focusable { ffi::True } else { ffi::False };
+        Ok(self.xconn.set_wm_hints(self.xwindow, wm_hints))
+    }
+
+    #[inline]
+    pub fn set_focusable(&self, focusable: bool) {
+        self.set_focusable_inner(focusable)
+            .and_then(util::Flusher::flush)
+            .log_error("Failed to set input hint");
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        ::Insets::default()
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.shared_state.lock().resizable
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.shared_state.lock().decorations
+    }
+
     fn set_title_inner(&self, title: &str) -> util::Flusher {
         let wm_name_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_NAME\0") };
         let utf8_atom = unsafe { self.xconn.get_atom_unchecked(b"UTF8_STRING\0") };
         let title = CString::new(title).expect("Window title contained null byte");
         unsafe {
-            (self.xconn.xlib.XStoreName)(
+            // `WM_NAME` is only used by window managers that don't know about `_NET_WM_NAME`, and can't
+            // hold UTF-8. Xlib stores it as Latin-1 if the title fits, and as COMPOUND_TEXT otherwise.
+            let mut title_ptr = title.as_ptr() as *mut c_char;
+            let mut text_prop: ffi::XTextProperty = mem::zeroed();
+            let result = (self.xconn.xlib.Xutf8TextListToTextProperty)(
                 self.xconn.display,
-                self.xwindow,
-                title.as_ptr() as *const c_char,
+                &mut title_ptr,
+                1,
+                ffi::XStdICCTextStyle,
+                &mut text_prop,
             );
+            // A positive result is the number of characters that couldn't be converted, which
+            // have been replaced with a default character.
+            if result >= ffi::Success as c_int {
+                (self.xconn.xlib.XSetWMName)(self.xconn.display, self.xwindow, &mut text_prop);
+                (self.xconn.xlib.XFree)(text_prop.value as *mut _);
+            }
             self.xconn.change_property(
                 self.xwindow,
                 wm_name_atom,
                 utf8_atom,
                 util::PropMode::Replace,
-                title.as_bytes_with_nul(),
+                title.as_bytes(),
             )
         }
     }
@@ -592,7 +939,7 @@ impl UnownedWindow {
     pub fn set_title(&self, title: &str) {
         self.set_title_inner(title)
             .flush()
-            .expect("Failed to set window title");
+            .log_error("Failed to set window title");
     }
 
     fn set_decorations_inner(&self, decorations: bool) -> util::Flusher {
@@ -616,7 +963,8 @@ impl UnownedWindow {
     pub fn set_decorations(&self, decorations: bool) {
         self.set_decorations_inner(decorations)
             .flush()
-            .expect("Failed to set decoration state");
+            .log_error("Failed to set decoration state");
+        self.shared_state.lock().decorations = decorations;
         self.invalidate_cached_frame_extents();
     }
 
@@ -629,7 +977,7 @@ impl UnownedWindow {
     pub fn set_always_on_top(&self, always_on_top: bool) {
         self.set_always_on_top_inner(always_on_top)
             .flush()
-            .expect("Failed to set always-on-top state");
+            .log_error("Failed to set always-on-top state");
     }
 
     fn set_icon_inner(&self, icon: Icon) -> util::Flusher {
@@ -661,24 +1009,96 @@ impl UnownedWindow {
         match icon {
             Some(icon) => self.set_icon_inner(icon),
             None => self.unset_icon_inner(),
-        }.flush().expect("Failed to set icons");
+        }.flush().log_error("Failed to set icons");
     }
 
     #[inline]
     pub fn show(&self) {
+        self.update_xembed_info(true);
         unsafe {
             (self.xconn.xlib.XMapRaised)(self.xconn.display, self.xwindow);
             self.xconn.flush_requests()
-                .expect("Failed to call XMapRaised");
+                .log_error("Failed to call XMapRaised");
         }
+        let mut shared_state = self.shared_state.lock();
+        shared_state.visible = true;
+        shared_state.pending_present = false;
     }
 
     #[inline]
     pub fn hide(&self) {
+        self.update_xembed_info(false);
         unsafe {
             (self.xconn.xlib.XUnmapWindow)(self.xconn.display, self.xwindow);
             self.xconn.flush_requests()
-                .expect("Failed to call XUnmapWindow");
+                .log_error("Failed to call XUnmapWindow");
+        }
+        let mut shared_state = self.shared_state.lock();
+        shared_state.visible = false;
+        shared_state.pending_present = false;
+    }
+
+    // Tells the embedder whether the window is to be mapped, if it's embedded with XEmbed.
+    fn update_xembed_info(&self, mapped: bool) {
+        if self.shared_state.lock().embedder.is_some() {
+            xembed::set_info(&self.xconn, self.xwindow, mapped).queue();
+        }
+    }
+
+    pub fn embed_client(&self, client: ffi::Window) {
+        let (width, height) = self.get_inner_size_physical().unwrap_or((1, 1));
+        unsafe {
+            // The client is followed to know when it goes away, and when it wants to be mapped.
+            (self.xconn.xlib.XSelectInput)(
+                self.xconn.display,
+                client,
+                ffi::StructureNotifyMask | ffi::PropertyChangeMask,
+            );
+            (self.xconn.xlib.XReparentWindow)(self.xconn.display, client, self.xwindow, 0, 0);
+            (self.xconn.xlib.XResizeWindow)(self.xconn.display, client, width, height);
+        }
+        xembed::send_message(
+            &self.xconn,
+            client,
+            xembed::XEMBED_EMBEDDED_NOTIFY,
+            0,
+            self.xwindow as c_long,
+            xembed::XEMBED_VERSION,
+        ).queue();
+
+        // Clients that don't set `_XEMBED_INFO` are mapped right away.
+        let mapped = xembed::get_info_flags(&self.xconn, client)
+            .map_or(true, |flags| flags & xembed::XEMBED_MAPPED != 0);
+        if mapped {
+            unsafe {
+                (self.xconn.xlib.XMapWindow)(self.xconn.display, client);
+            }
+        }
+
+        let has_focus = {
+            let mut shared_state = self.shared_state.lock();
+            shared_state.embedded_client = Some(client);
+            shared_state.has_focus
+        };
+        if has_focus {
+            xembed::send_message(&self.xconn, client, xembed::XEMBED_WINDOW_ACTIVATE, 0, 0, 0).queue();
+            xembed::send_message(
+                &self.xconn,
+                client,
+                xembed::XEMBED_FOCUS_IN,
+                xembed::XEMBED_FOCUS_CURRENT,
+                0,
+                0,
+            ).queue();
+        }
+        self.xconn.flush_requests().log_error("Failed to embed window");
+    }
+
+    #[inline]
+    pub fn present_ready(&self) {
+        let pending = self.shared_state.lock().pending_present;
+        if pending {
+            self.show();
         }
     }
 
@@ -727,8 +1147,8 @@ impl UnownedWindow {
     }
 
     pub(crate) fn set_position_inner(&self, mut x: i32, mut y: i32) -> util::Flusher {
-        // There are a few WMs that set client area position rather than window position, so
-        // we'll translate for consistency.
+        // `XMoveWindow` moves the frame, as requested by our `NorthWestGravity` hint, except with a
+        // few WMs that set client area position regardless, so we'll translate for consistency.
         if util::wm_name_is_one_of(&["Enlightenment", "FVWM"]) {
             let extents = (*self.shared_state.lock()).frame_extents.clone();
             if let Some(extents) = extents {
@@ -753,15 +1173,32 @@ impl UnownedWindow {
     pub(crate) fn set_position_physical(&self, x: i32, y: i32) {
         self.set_position_inner(x, y)
             .flush()
-            .expect("Failed to call `XMoveWindow`");
+            .log_error("Failed to call `XMoveWindow`");
     }
 
     #[inline]
-    pub fn set_position(&self, logical_position: LogicalPosition) {
+    pub fn set_outer_position(&self, logical_position: LogicalPosition) {
         let (x, y) = logical_position.to_physical(self.get_hidpi_factor()).into();
         self.set_position_physical(x, y);
     }
 
+    pub(crate) fn set_inner_position_physical(&self, x: i32, y: i32) {
+        let extents = self.shared_state.lock().frame_extents.clone();
+        if let Some(extents) = extents {
+            let (x, y) = extents.inner_pos_to_outer(x, y);
+            self.set_position_physical(x, y);
+        } else {
+            self.update_cached_frame_extents();
+            self.set_inner_position_physical(x, y);
+        }
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, logical_position: LogicalPosition) {
+        let (x, y) = logical_position.to_physical(self.get_hidpi_factor()).into();
+        self.set_inner_position_physical(x, y);
+    }
+
     pub(crate) fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
         self.xconn.get_geometry(self.xwindow)
             .ok()
@@ -797,6 +1234,22 @@ impl UnownedWindow {
         }
     }
 
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        let extents = self.shared_state.lock().frame_extents.clone();
+        if let Some(extents) = extents {
+            let logical = extents.frame_extents.as_logical(self.get_hidpi_factor());
+            Some(::FrameExtents {
+                left: logical.left,
+                right: logical.right,
+                top: logical.top,
+                bottom: logical.bottom,
+            })
+        } else {
+            self.update_cached_frame_extents();
+            self.get_frame_extents()
+        }
+    }
+
     pub(crate) fn set_inner_size_physical(&self, width: u32, height: u32) {
         unsafe {
             (self.xconn.xlib.XResizeWindow)(
@@ -806,7 +1259,7 @@ impl UnownedWindow {
                 height as c_uint,
             );
             self.xconn.flush_requests()
-        }.expect("Failed to call `XResizeWindow`");
+        }.log_error("Failed to call `XResizeWindow`");
     }
 
     #[inline]
@@ -826,7 +1279,7 @@ impl UnownedWindow {
 
     pub(crate) fn set_min_dimensions_physical(&self, dimensions: Option<(u32, u32)>) {
         self.update_normal_hints(|normal_hints| normal_hints.set_min_size(dimensions))
-            .expect("Failed to call `XSetWMNormalHints`");
+            .log_error("Failed to call `XSetWMNormalHints`");
     }
 
     #[inline]
@@ -840,7 +1293,7 @@ impl UnownedWindow {
 
     pub(crate) fn set_max_dimensions_physical(&self, dimensions: Option<(u32, u32)>) {
         self.update_normal_hints(|normal_hints| normal_hints.set_max_size(dimensions))
-            .expect("Failed to call `XSetWMNormalHints`");
+            .log_error("Failed to call `XSetWMNormalHints`");
     }
 
     #[inline]
@@ -876,7 +1329,7 @@ impl UnownedWindow {
             normal_hints.set_min_size(min_size);
             normal_hints.set_resize_increments(resize_increments);
             normal_hints.set_base_size(base_size);
-        }).expect("Failed to update normal hints");
+        }).log_error("Failed to update normal hints");
         unsafe {
             (self.xconn.xlib.XResizeWindow)(
                 self.xconn.display,
@@ -896,6 +1349,7 @@ impl UnownedWindow {
             warn!("To avoid a WM bug, disabling resizing has no effect on Xfwm4");
             return;
         }
+        self.shared_state.lock().resizable = resizable;
 
         let (logical_min, logical_max) = if resizable {
             let shared_state_lock = self.shared_state.lock();
@@ -915,7 +1369,7 @@ impl UnownedWindow {
         self.update_normal_hints(|normal_hints| {
             normal_hints.set_min_size(min_dimensions);
             normal_hints.set_max_size(max_dimensions);
-        }).expect("Failed to call `XSetWMNormalHints`");
+        }).log_error("Failed to call `XSetWMNormalHints`");
     }
 
     #[inline]
@@ -945,8 +1399,37 @@ impl UnownedWindow {
         }
     }
 
-    fn load_cursor(&self, name: &[u8]) -> ffi::Cursor {
+    // The size of the cursor images to load when `hidpi_cursors` is set, or `None` to let Xcursor
+    // pick the default size.
+    fn get_cursor_size(&self, hidpi_factor: f64) -> Option<c_int> {
+        if !self.hidpi_cursors {
+            return None;
+        }
+        // The default size already accounts for `XCURSOR_SIZE`, `Xcursor.size` and `Xft.dpi`, so
+        // users who configured it for their hidpi screen don't get doubly scaled cursors.
+        let default_size = unsafe { (self.xconn.xcursor.XcursorGetDefaultSize)(self.xconn.display) };
+        let scaled_size = (BASE_CURSOR_SIZE * hidpi_factor).round() as c_int;
+        Some(cmp::max(default_size, scaled_size))
+    }
+
+    fn load_cursor(&self, name: &[u8], size: Option<c_int>) -> ffi::Cursor {
         unsafe {
+            if let Some(size) = size {
+                let theme = (self.xconn.xcursor.XcursorGetTheme)(self.xconn.display);
+                let image = (self.xconn.xcursor.XcursorLibraryLoadImage)(
+                    name.as_ptr() as *const c_char,
+                    theme,
+                    size,
+                );
+                if !image.is_null() {
+                    let xcursor = (self.xconn.xcursor.XcursorImageLoadCursor)(
+                        self.xconn.display,
+                        image,
+                    );
+                    (self.xconn.xcursor.XcursorImageDestroy)(image);
+                    return xcursor;
+                }
+            }
             (self.xconn.xcursor.XcursorLibraryLoadCursor)(
                 self.xconn.display,
                 name.as_ptr() as *const c_char,
@@ -954,9 +1437,9 @@ impl UnownedWindow {
         }
     }
 
-    fn load_first_existing_cursor(&self, names: &[&[u8]]) -> ffi::Cursor {
+    fn load_first_existing_cursor(&self, names: &[&[u8]], size: Option<c_int>) -> ffi::Cursor {
         for name in names.iter() {
-            let xcursor = self.load_cursor(name);
+            let xcursor = self.load_cursor(name, size);
             if xcursor != 0 {
                 return xcursor;
             }
@@ -965,12 +1448,18 @@ impl UnownedWindow {
     }
 
     fn get_cursor(&self, cursor: MouseCursor) -> ffi::Cursor {
+        self.get_cursor_for_hidpi_factor(cursor, self.get_hidpi_factor())
+    }
+
+    fn get_cursor_for_hidpi_factor(&self, cursor: MouseCursor, hidpi_factor: f64) -> ffi::Cursor {
+        let size = self.get_cursor_size(hidpi_factor);
+
         let load = |name: &[u8]| {
-            self.load_cursor(name)
+            self.load_cursor(name, size)
         };
 
         let loadn = |names: &[&[u8]]| {
-            self.load_first_existing_cursor(names)
+            self.load_first_existing_cursor(names, size)
         };
 
         // Try multiple names in some cases where the name
@@ -1029,7 +1518,7 @@ impl UnownedWindow {
             if cursor != 0 {
                 (self.xconn.xlib.XFreeCursor)(self.xconn.display, cursor);
             }
-            self.xconn.flush_requests().expect("Failed to set or free the cursor");
+            self.xconn.flush_requests().log_error("Failed to set or free the cursor");
         }
     }
 
@@ -1041,6 +1530,14 @@ impl UnownedWindow {
         }
     }
 
+    // Reloads the cursor at the size matching the new DPI factor, if `hidpi_cursors` is set.
+    pub fn update_cursor_for_hidpi_factor(&self, hidpi_factor: f64) {
+        if self.hidpi_cursors && !*self.cursor_hidden.lock() {
+            let cursor = *self.cursor.lock();
+            self.update_cursor(self.get_cursor_for_hidpi_factor(cursor, hidpi_factor));
+        }
+    }
+
     // TODO: This could maybe be cached. I don't think it's worth
     // the complexity, since cursor changes are not so common,
     // and this is just allocating a 1x1 pixmap...
@@ -1079,16 +1576,14 @@ impl UnownedWindow {
         Some(cursor)
     }
 
-    #[inline]
-    pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
-        let mut grabbed_lock = self.cursor_grabbed.lock();
-        if grab == *grabbed_lock { return Ok(()); }
+    // Grabs the pointer so that it's confined to `confine_to`, or ungrabs it with `None`.
+    fn set_pointer_grab(&self, confine_to: Option<ffi::Window>) -> Result<(), String> {
         unsafe {
             // We ungrab before grabbing to prevent passive grabs from causing `AlreadyGrabbed`.
             // Therefore, this is common to both codepaths.
             (self.xconn.xlib.XUngrabPointer)(self.xconn.display, ffi::CurrentTime);
         }
-        let result = if grab {
+        if let Some(confine_to) = confine_to {
             let result = unsafe {
                 (self.xconn.xlib.XGrabPointer)(
                     self.xconn.display,
@@ -1111,7 +1606,7 @@ impl UnownedWindow {
                     ) as c_uint,
                     ffi::GrabModeAsync,
                     ffi::GrabModeAsync,
-                    self.xwindow,
+                    confine_to,
                     0,
                     ffi::CurrentTime,
                 )
@@ -1128,19 +1623,103 @@ impl UnownedWindow {
         } else {
             self.xconn.flush_requests()
                 .map_err(|err| format!("Failed to call `XUngrabPointer`: {:?}", err))
-        };
-        if result.is_ok() {
-            *grabbed_lock = grab;
+        }
+    }
+
+    // Creates an invisible child window to confine the pointer to, which moves along with ours.
+    fn create_confine_window(&self, x: i32, y: i32, width: u32, height: u32) -> ffi::Window {
+        unsafe {
+            let mut set_win_attr: ffi::XSetWindowAttributes = mem::zeroed();
+            let confine_window = (self.xconn.xlib.XCreateWindow)(
+                self.xconn.display,
+                self.xwindow,
+                x,
+                y,
+                cmp::max(width, 1),
+                cmp::max(height, 1),
+                0,
+                0,
+                ffi::InputOnly as c_uint,
+                ptr::null_mut(),
+                0,
+                &mut set_win_attr,
+            );
+            (self.xconn.xlib.XMapWindow)(self.xconn.display, confine_window);
+            confine_window
+        }
+    }
+
+    // Grabs the pointer according to the confine region if there's one, or else the grab mode.
+    fn update_cursor_grab(&self, grab: &mut CursorGrab) -> Result<(), String> {
+        if let Some(confine_window) = grab.confine_window.take() {
+            unsafe { (self.xconn.xlib.XDestroyWindow)(self.xconn.display, confine_window) };
+        }
+
+        if let Some(region) = grab.region {
+            let dpi_factor = self.get_hidpi_factor();
+            let (x, y) = region.position.to_physical(dpi_factor).into();
+            let (width, height) = region.size.to_physical(dpi_factor).into();
+            let confine_window = self.create_confine_window(x, y, width, height);
+            grab.confine_window = Some(confine_window);
+            return self.set_pointer_grab(Some(confine_window));
+        }
+
+        match grab.mode {
+            CursorGrabMode::None => self.set_pointer_grab(None),
+            CursorGrabMode::Confined => self.set_pointer_grab(Some(self.xwindow)),
+            CursorGrabMode::Locked => {
+                // Confining the pointer to a single pixel keeps it in place without warping it
+                // back after every move, while `XI_RawMotion` still reports the mouse's motion.
+                let (width, height) = self.get_inner_size_physical().unwrap_or((0, 0));
+                let position = self.xconn
+                    .query_pointer(self.xwindow, util::VIRTUAL_CORE_POINTER)
+                    .ok()
+                    .map(|pointer| (pointer.win_x as i32, pointer.win_y as i32));
+                let (x, y) = match position {
+                    Some((x, y)) if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height => (x, y),
+                    _ => ((width / 2) as i32, (height / 2) as i32),
+                };
+                let confine_window = self.create_confine_window(x, y, 1, 1);
+                grab.confine_window = Some(confine_window);
+                self.set_pointer_grab(Some(confine_window))
+            },
+        }
+    }
+
+    #[inline]
+    pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
+        self.set_cursor_grab_mode(if grab { CursorGrabMode::Confined } else { CursorGrabMode::None })
+    }
+
+    pub fn set_cursor_grab_mode(&self, mode: CursorGrabMode) -> Result<(), String> {
+        let mut grab = self.cursor_grab.lock();
+        if mode == grab.mode { return Ok(()); }
+        let previous_mode = mem::replace(&mut grab.mode, mode);
+        let result = self.update_cursor_grab(&mut grab);
+        if result.is_err() {
+            grab.mode = previous_mode;
         }
         result
     }
 
+    pub fn set_cursor_confine_region(&self, region: Option<Rect>) -> Result<(), String> {
+        let mut grab = self.cursor_grab.lock();
+        grab.region = region;
+        self.update_cursor_grab(&mut grab)
+    }
+
     #[inline]
     pub fn hide_cursor(&self, hide: bool) {
         let mut hidden_lock = self.cursor_hidden.lock();
         if hide == *hidden_lock {return; }
         let cursor = if hide {
-            self.create_empty_cursor().expect("Failed to create empty cursor")
+            match self.create_empty_cursor() {
+                Some(cursor) => cursor,
+                None => {
+                    warn!("Failed to create empty cursor");
+                    return;
+                },
+            }
         } else {
             self.get_cursor(*self.cursor.lock())
         };
@@ -1151,7 +1730,8 @@ impl UnownedWindow {
 
     #[inline]
     pub fn get_hidpi_factor(&self) -> f64 {
-        self.get_current_monitor().hidpi_factor
+        self.static_hidpi_factor
+            .unwrap_or_else(|| self.get_current_monitor().hidpi_factor)
     }
 
     pub fn set_cursor_position_physical(&self, x: i32, y: i32) -> Result<(), String> {
@@ -1189,6 +1769,263 @@ impl UnownedWindow {
         self.set_ime_spot_physical(x, y);
     }
 
+    pub fn capture_frame(&self) -> Option<CapturedFrame> {
+        let (width, height) = self.get_inner_size_physical()?;
+        let image = unsafe {
+            (self.xconn.xlib.XGetImage)(
+                self.xconn.display,
+                self.xwindow,
+                0,
+                0,
+                width,
+                height,
+                !0, // AllPlanes
+                ffi::ZPixmap,
+            )
+        };
+        if image.is_null() {
+            // This fails with `BadMatch` if the window isn't mapped.
+            let _ = self.xconn.check_errors();
+            return None;
+        }
+
+        let (red_mask, green_mask, blue_mask) = unsafe {
+            ((*image).red_mask, (*image).green_mask, (*image).blue_mask)
+        };
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = unsafe { (self.xconn.xlib.XGetPixel)(image, x as c_int, y as c_int) };
+                rgba.push(util::pixel_component(pixel, red_mask));
+                rgba.push(util::pixel_component(pixel, green_mask));
+                rgba.push(util::pixel_component(pixel, blue_mask));
+                rgba.push(255);
+            }
+        }
+        unsafe { (self.xconn.xlib.XDestroyImage)(image) };
+
+        Some(CapturedFrame { width, height, rgba })
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, callback: Option<HitTestCallback>) {
+        *self.hit_test.lock() = callback;
+    }
+
+    #[inline]
+    pub fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), String> {
+        self.set_shape_inner(ffi::ShapeInput, region)
+            .map_err(|e| format!("Failed to set the input shape: {}", e))
+    }
+
+    #[inline]
+    pub fn set_shape(&self, shape: Option<&[Rect]>) -> Result<(), String> {
+        self.set_shape_inner(ffi::ShapeBounding, shape)
+            .map_err(|e| format!("Failed to set the bounding shape: {}", e))
+    }
+
+    pub fn set_blur(&self, blur: bool) -> Result<(), String> {
+        self.set_blur_inner(blur)
+            .flush()
+            .map_err(|e| format!("Failed to set `_KDE_NET_WM_BLUR_BEHIND_REGION`: {:?}", e))
+    }
+
+    pub fn reserve_screen_edge(&self, reservation: Option<(Edge, f64)>) -> Result<(), String> {
+        let strut_partial_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STRUT_PARTIAL\0") };
+        let strut_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_STRUT\0") };
+        let (edge, thickness) = match reservation {
+            Some(reservation) => reservation,
+            None => {
+                unsafe {
+                    (self.xconn.xlib.XDeleteProperty)(self.xconn.display, self.xwindow, strut_partial_atom);
+                    (self.xconn.xlib.XDeleteProperty)(self.xconn.display, self.xwindow, strut_atom);
+                }
+                return self.xconn.flush_requests()
+                    .map_err(|e| format!("Failed to delete `_NET_WM_STRUT_PARTIAL`: {:?}", e));
+            },
+        };
+
+        // Struts are relative to the edges of the root window, which spans every monitor, and only
+        // cover the monitor along the edge with their start and end coordinates.
+        let geometry = self.xconn.get_geometry(self.root)
+            .map_err(|e| format!("Failed to get the size of the root window: {:?}", e))?;
+        let (root_width, root_height) = (geometry.width as util::Cardinal, geometry.height as util::Cardinal);
+        let monitor = self.get_current_monitor();
+        let (x, y): (i32, i32) = monitor.get_position().into();
+        let (width, height): (u32, u32) = monitor.get_dimensions().into();
+        let (x, y) = (x as util::Cardinal, y as util::Cardinal);
+        let (width, height) = (width as util::Cardinal, height as util::Cardinal);
+        let thickness = (thickness * self.get_hidpi_factor()).round() as util::Cardinal;
+        let mut strut: [util::Cardinal; 12] = [0; 12];
+        match edge {
+            Edge::Left => {
+                strut[0] = x + thickness;
+                strut[4] = y;
+                strut[5] = y + height - 1;
+            },
+            Edge::Right => {
+                strut[1] = root_width - (x + width) + thickness;
+                strut[6] = y;
+                strut[7] = y + height - 1;
+            },
+            Edge::Top => {
+                strut[2] = y + thickness;
+                strut[8] = x;
+                strut[9] = x + width - 1;
+            },
+            Edge::Bottom => {
+                strut[3] = root_height - (y + height) + thickness;
+                strut[10] = x;
+                strut[11] = x + width - 1;
+            },
+        }
+        self.xconn.change_property(
+            self.xwindow,
+            strut_partial_atom,
+            ffi::XA_CARDINAL,
+            util::PropMode::Replace,
+            &strut,
+        ).queue();
+        // For WMs that predate `_NET_WM_STRUT_PARTIAL`.
+        self.xconn.change_property(
+            self.xwindow,
+            strut_atom,
+            ffi::XA_CARDINAL,
+            util::PropMode::Replace,
+            &strut[..4],
+        ).flush().map_err(|e| format!("Failed to set `_NET_WM_STRUT_PARTIAL`: {:?}", e))
+    }
+
+    // KWin blurs the rectangles listed in this property, or the whole window if there are none.
+    fn set_blur_inner(&self, blur: bool) -> util::Flusher {
+        let blur_atom = unsafe { self.xconn.get_atom_unchecked(b"_KDE_NET_WM_BLUR_BEHIND_REGION\0") };
+        if blur {
+            let region: &[util::Cardinal] = &[];
+            self.xconn.change_property(
+                self.xwindow,
+                blur_atom,
+                ffi::XA_CARDINAL,
+                util::PropMode::Replace,
+                region,
+            )
+        } else {
+            unsafe {
+                (self.xconn.xlib.XDeleteProperty)(self.xconn.display, self.xwindow, blur_atom);
+            }
+            util::Flusher::new(&self.xconn)
+        }
+    }
+
+    fn set_shape_inner(&self, kind: c_int, region: Option<&[Rect]>) -> Result<(), String> {
+        let xshape = self.xconn.xshape
+            .as_ref()
+            .ok_or_else(|| "`libXext` couldn't be loaded, so the Shape extension is unavailable.".to_owned())?;
+        let dpi_factor = self.get_hidpi_factor();
+        unsafe {
+            match region {
+                Some(region) => {
+                    let mut rectangles: Vec<_> = region.iter().map(|rect| {
+                        let (x, y): (i32, i32) = rect.position.to_physical(dpi_factor).into();
+                        let (width, height): (u32, u32) = rect.size.to_physical(dpi_factor).into();
+                        ffi::XRectangle {
+                            x: x as c_short,
+                            y: y as c_short,
+                            width: width as c_ushort,
+                            height: height as c_ushort,
+                        }
+                    }).collect();
+                    (xshape.XShapeCombineRectangles)(
+                        self.xconn.display,
+                        self.xwindow,
+                        kind,
+                        0,
+                        0,
+                        rectangles.as_mut_ptr(),
+                        rectangles.len() as c_int,
+                        ffi::ShapeSet,
+                        ffi::Unsorted,
+                    );
+                },
+                // Setting no mask restores the default shape, which covers the whole window.
+                None => (xshape.XShapeCombineMask)(
+                    self.xconn.display,
+                    self.xwindow,
+                    kind,
+                    0,
+                    0,
+                    0,
+                    ffi::ShapeSet,
+                ),
+            }
+            self.xconn.flush_requests().map_err(|e| format!("{:?}", e))
+        }
+    }
+
+    // Asks the hit-test callback what lies under a left click, and hands the press over to the
+    // window manager if it landed on a caption or a resize border. Returns `true` in that case.
+    pub(crate) fn handle_hit_test_press(
+        &self,
+        device_id: c_int,
+        (x, y): (f64, f64),
+        (root_x, root_y): (f64, f64),
+    ) -> bool {
+        let callback = match self.hit_test.lock().clone() {
+            Some(callback) => callback,
+            None => return false,
+        };
+        // Values from the EWMH spec for `_NET_WM_MOVERESIZE`.
+        let direction: c_long = match callback((x, y).into()) {
+            HitTestResult::Client => return false,
+            HitTestResult::TopLeft => 0,
+            HitTestResult::Top => 1,
+            HitTestResult::TopRight => 2,
+            HitTestResult::Right => 3,
+            HitTestResult::BottomRight => 4,
+            HitTestResult::Bottom => 5,
+            HitTestResult::BottomLeft => 6,
+            HitTestResult::Left => 7,
+            HitTestResult::Caption => 8,
+        };
+        unsafe {
+            // The window manager can't take over the pointer while we hold the implicit grab
+            // acquired by the button press.
+            (self.xconn.xinput2.XIUngrabDevice)(self.xconn.display, device_id, ffi::CurrentTime);
+        }
+        let moveresize_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_MOVERESIZE\0") };
+        self.xconn.send_client_msg(
+            self.xwindow,
+            self.root,
+            moveresize_atom,
+            Some(ffi::SubstructureRedirectMask | ffi::SubstructureNotifyMask),
+            [
+                root_x as c_long,
+                root_y as c_long,
+                direction,
+                ffi::Button1 as c_long,
+                1, // source indication: normal application
+            ],
+        ).flush().log_error("Failed to start window move/resize");
+        true
+    }
+
+    /// Whether this is the topmost of the windows belonging to our process, going by the
+    /// `_NET_WM_PID` that `set_pid` gave them.
+    pub fn is_frontmost(&self) -> bool {
+        let pid_atom = unsafe { self.xconn.get_atom_unchecked(b"_NET_WM_PID\0") };
+        let pid = unsafe { libc::getpid() } as util::Cardinal;
+        let frontmost = self.xconn
+            .get_client_list_stacking(self.root)
+            .into_iter()
+            .rev()
+            .find(|&window| {
+                self.xconn
+                    .get_property::<util::Cardinal>(window, pid_atom, ffi::XA_CARDINAL)
+                    .map(|window_pid| window_pid.get(0) == Some(&pid))
+                    .unwrap_or(false)
+            });
+        frontmost == Some(self.xwindow)
+    }
+
     #[inline]
     pub fn id(&self) -> WindowId { WindowId(self.xwindow) }
 }
@@ -15,6 +15,10 @@ pub struct XConnection {
     /// Exposes XRandR functions from version = 1.5
     pub xrandr_1_5: Option<ffi::Xrandr>,
     pub xcursor: ffi::Xcursor,
+    /// Exposes XScreenSaver functions, if `libXss` is installed
+    pub xss: Option<ffi::Xss>,
+    /// Exposes Shape extension functions, if `libXext` is installed
+    pub xshape: Option<ffi::XShape>,
     pub xinput2: ffi::XInput2,
     pub xlib_xcb: ffi::Xlib_xcb,
     pub display: *mut ffi::Display,
@@ -34,6 +38,8 @@ impl XConnection {
         let xrandr = ffi::Xrandr_2_2_0::open()?;
         let xrandr_1_5 = ffi::Xrandr::open().ok();
         let xinput2 = ffi::XInput2::open()?;
+        let xss = ffi::Xss::open().ok();
+        let xshape = ffi::XShape::open();
         let xlib_xcb = ffi::Xlib_xcb::open()?;
 
         unsafe { (xlib.XInitThreads)() };
@@ -53,6 +59,8 @@ impl XConnection {
             xrandr,
             xrandr_1_5,
             xcursor,
+            xss,
+            xshape,
             xinput2,
             xlib_xcb,
             display,
@@ -118,6 +126,9 @@ impl fmt::Display for XError {
 #[derive(Clone, Debug)]
 pub enum XNotSupported {
     /// Failed to load one or several shared libraries.
+    ///
+    /// Xlib and its extensions are always loaded at runtime with `dlopen`, through x11-dl. There's
+    /// no way to link them at build time yet.
     LibraryOpenError(ffi::OpenError),
     /// Connecting to the X server with `XOpenDisplay` failed.
     XOpenDisplayFailed,     // TODO: add better message
@@ -150,6 +161,11 @@ impl Error for XNotSupported {
 
 impl fmt::Display for XNotSupported {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        formatter.write_str(self.description())
+        let description = self.description();
+        match *self {
+            // Says which library or symbol is missing.
+            XNotSupported::LibraryOpenError(ref err) => write!(formatter, "{}: {}", description, err),
+            XNotSupported::XOpenDisplayFailed => formatter.write_str(description),
+        }
     }
 }
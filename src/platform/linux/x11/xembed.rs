@@ -0,0 +1,62 @@
+//! The XEmbed protocol, with which the window of one application is embedded into the window of
+//! another. See https://specifications.freedesktop.org/xembed-spec/xembed-spec-latest.html
+
+use std::os::raw::*;
+
+use super::{ffi, util, XConnection};
+
+pub const XEMBED_VERSION: c_long = 0;
+
+// The flags of `_XEMBED_INFO`.
+pub const XEMBED_MAPPED: c_long = 1 << 0;
+
+// The messages sent with `_XEMBED` client messages.
+pub const XEMBED_EMBEDDED_NOTIFY: c_long = 0;
+pub const XEMBED_WINDOW_ACTIVATE: c_long = 1;
+pub const XEMBED_WINDOW_DEACTIVATE: c_long = 2;
+pub const XEMBED_REQUEST_FOCUS: c_long = 3;
+pub const XEMBED_FOCUS_IN: c_long = 4;
+pub const XEMBED_FOCUS_OUT: c_long = 5;
+
+// The detail of `XEMBED_FOCUS_IN`, which keeps the focus where it was within the client.
+pub const XEMBED_FOCUS_CURRENT: c_long = 0;
+
+/// Sends an `_XEMBED` message to `window`, which is either the embedder or the client.
+pub fn send_message<'a>(
+    xconn: &'a XConnection,
+    window: ffi::Window,
+    message: c_long,
+    detail: c_long,
+    data1: c_long,
+    data2: c_long,
+) -> util::Flusher<'a> {
+    let xembed_atom = unsafe { xconn.get_atom_unchecked(b"_XEMBED\0") };
+    xconn.send_client_msg(
+        window,
+        window,
+        xembed_atom,
+        Some(ffi::NoEventMask),
+        [ffi::CurrentTime as c_long, message, detail, data1, data2],
+    )
+}
+
+/// Sets the `_XEMBED_INFO` of a client, which tells the embedder whether to map it.
+pub fn set_info<'a>(xconn: &'a XConnection, window: ffi::Window, mapped: bool) -> util::Flusher<'a> {
+    let info_atom = unsafe { xconn.get_atom_unchecked(b"_XEMBED_INFO\0") };
+    let flags = if mapped { XEMBED_MAPPED } else { 0 };
+    xconn.change_property(
+        window,
+        info_atom,
+        info_atom,
+        util::PropMode::Replace,
+        &[XEMBED_VERSION, flags],
+    )
+}
+
+/// Returns the flags of the `_XEMBED_INFO` of a client, or `None` if it doesn't support XEmbed.
+pub fn get_info_flags(xconn: &XConnection, window: ffi::Window) -> Option<c_long> {
+    let info_atom = unsafe { xconn.get_atom_unchecked(b"_XEMBED_INFO\0") };
+    xconn.get_property::<c_long>(window, info_atom, info_atom)
+        .ok()
+        .and_then(|info| info.get(1).cloned())
+}
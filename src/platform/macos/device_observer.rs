@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::Weak;
+
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSString, NSUInteger};
+use core_foundation::base::CFRelease;
+use core_foundation::runloop::CFRunLoopGetMain;
+
+use {DeviceEvent, DeviceInfo, DeviceKind, Event};
+use platform::platform::events_loop::{Shared, DEVICE_ID};
+use platform::platform::ffi;
+use platform::platform::window::IdRef;
+
+// The generic desktop usages of the devices that are reported.
+const USAGES: [u32; 5] = [
+    ffi::kHIDUsage_GD_Mouse,
+    ffi::kHIDUsage_GD_Keyboard,
+    ffi::kHIDUsage_GD_Joystick,
+    ffi::kHIDUsage_GD_GamePad,
+    ffi::kHIDUsage_GD_MultiAxisController,
+];
+
+struct ObserverState {
+    shared: Weak<Shared>,
+    // The devices that were attached when the observer was created. The manager calls the
+    // matching callback for them too, which isn't reported.
+    initial_devices: RefCell<HashSet<usize>>,
+}
+
+impl ObserverState {
+    fn send_event(&self, event: DeviceEvent) {
+        if let Some(shared) = self.shared.upgrade() {
+            // The events don't tell the devices apart.
+            let event = Event::DeviceEvent { device_id: DEVICE_ID, event };
+            shared.call_user_callback_with_event_or_store_in_pending(event);
+        }
+    }
+}
+
+// Observes the input devices being attached and detached, with an `IOHIDManager` scheduled on
+// the main run loop.
+pub struct DeviceObserver {
+    manager: ffi::IOHIDManagerRef,
+    state: *mut ObserverState,
+}
+
+impl DeviceObserver {
+    pub fn new(shared: Weak<Shared>) -> Self {
+        unsafe {
+            let state = Box::into_raw(Box::new(ObserverState {
+                shared,
+                initial_devices: RefCell::new(HashSet::new()),
+            }));
+            let manager = ffi::IOHIDManagerCreate(ptr::null(), ffi::kIOHIDOptionsTypeNone);
+            if manager.is_null() {
+                return DeviceObserver { manager, state };
+            }
+
+            let page_key = IdRef::new(NSString::alloc(nil).init_str("DeviceUsagePage"));
+            let usage_key = IdRef::new(NSString::alloc(nil).init_str("DeviceUsage"));
+            let page: id = msg_send![class!(NSNumber), numberWithUnsignedInt:ffi::kHIDPage_GenericDesktop];
+            let matching: id = msg_send![class!(NSMutableArray), array];
+            for &usage in &USAGES {
+                let usage: id = msg_send![class!(NSNumber), numberWithUnsignedInt:usage];
+                let dictionary: id = msg_send![class!(NSMutableDictionary), dictionary];
+                let _: () = msg_send![dictionary, setObject:page forKey:*page_key];
+                let _: () = msg_send![dictionary, setObject:usage forKey:*usage_key];
+                let _: () = msg_send![matching, addObject:dictionary];
+            }
+            ffi::IOHIDManagerSetDeviceMatchingMultiple(manager, matching);
+            ffi::IOHIDManagerRegisterDeviceMatchingCallback(manager, device_matched, state as *mut c_void);
+            ffi::IOHIDManagerRegisterDeviceRemovalCallback(manager, device_removed, state as *mut c_void);
+            ffi::IOHIDManagerOpen(manager, ffi::kIOHIDOptionsTypeNone);
+
+            let devices = ffi::IOHIDManagerCopyDevices(manager);
+            if devices != nil {
+                let devices_array: id = msg_send![devices, allObjects];
+                let count: NSUInteger = msg_send![devices_array, count];
+                let mut initial_devices = (*state).initial_devices.borrow_mut();
+                for i in 0..count {
+                    let device: id = msg_send![devices_array, objectAtIndex:i];
+                    initial_devices.insert(device as usize);
+                }
+                CFRelease(devices as *const c_void);
+            }
+
+            ffi::IOHIDManagerScheduleWithRunLoop(
+                manager,
+                CFRunLoopGetMain() as *mut c_void,
+                ffi::NSRunLoopCommonModes,
+            );
+            DeviceObserver { manager, state }
+        }
+    }
+
+    // Lists the devices the manager matched, named after their product string.
+    pub fn device_infos(&self) -> Vec<DeviceInfo> {
+        if self.manager.is_null() {
+            return Vec::new();
+        }
+        unsafe {
+            let devices = ffi::IOHIDManagerCopyDevices(self.manager);
+            if devices == nil {
+                return Vec::new();
+            }
+            let product_key = IdRef::new(NSString::alloc(nil).init_str("Product"));
+            let usage_key = IdRef::new(NSString::alloc(nil).init_str("PrimaryUsage"));
+            let devices_array: id = msg_send![devices, allObjects];
+            let count: NSUInteger = msg_send![devices_array, count];
+            let mut infos = Vec::with_capacity(count as usize);
+            for i in 0..count {
+                let device: id = msg_send![devices_array, objectAtIndex:i];
+                let device = device as ffi::IOHIDDeviceRef;
+
+                let product = ffi::IOHIDDeviceGetProperty(device, *product_key);
+                let name = if product != nil {
+                    let bytes: *const c_char = msg_send![product, UTF8String];
+                    CStr::from_ptr(bytes).to_string_lossy().into_owned()
+                } else {
+                    String::new()
+                };
+                let usage = ffi::IOHIDDeviceGetProperty(device, *usage_key);
+                let usage: u32 = if usage != nil { msg_send![usage, unsignedIntValue] } else { 0 };
+                let kind = match usage {
+                    ffi::kHIDUsage_GD_Mouse => DeviceKind::Mouse,
+                    ffi::kHIDUsage_GD_Keyboard => DeviceKind::Keyboard,
+                    _ => DeviceKind::Hid,
+                };
+                // The events don't tell the devices apart.
+                infos.push(DeviceInfo { id: DEVICE_ID, name, kind });
+            }
+            CFRelease(devices as *const c_void);
+            infos
+        }
+    }
+}
+
+impl Drop for DeviceObserver {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.manager.is_null() {
+                ffi::IOHIDManagerUnscheduleFromRunLoop(
+                    self.manager,
+                    CFRunLoopGetMain() as *mut c_void,
+                    ffi::NSRunLoopCommonModes,
+                );
+                ffi::IOHIDManagerClose(self.manager, ffi::kIOHIDOptionsTypeNone);
+                CFRelease(self.manager as *const c_void);
+            }
+            Box::from_raw(self.state);
+        }
+    }
+}
+
+extern "C" fn device_matched(context: *mut c_void, _: ffi::IOReturn, _: *mut c_void, device: ffi::IOHIDDeviceRef) {
+    let state = unsafe { &*(context as *const ObserverState) };
+    let initial = state.initial_devices.borrow_mut().remove(&(device as usize));
+    if !initial {
+        state.send_event(DeviceEvent::Added);
+    }
+}
+
+extern "C" fn device_removed(context: *mut c_void, _: ffi::IOReturn, _: *mut c_void, device: ffi::IOHIDDeviceRef) {
+    let state = unsafe { &*(context as *const ObserverState) };
+    state.initial_devices.borrow_mut().remove(&(device as usize));
+    state.send_event(DeviceEvent::Removed);
+}
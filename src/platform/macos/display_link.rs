@@ -0,0 +1,84 @@
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use core_graphics::display::CGDirectDisplayID;
+
+use platform::platform::ffi;
+
+/// Counts the frames shown by a display, so that the events loop can wait for the next one.
+///
+/// The display link calls us back from its own thread for as long as it exists.
+pub struct DisplayLink {
+    link: ffi::CVDisplayLinkRef,
+    display_id: CGDirectDisplayID,
+    // Owned by the display link's callback until the link is stopped.
+    frames: *mut (Mutex<u64>, Condvar),
+}
+
+impl DisplayLink {
+    pub fn new(display_id: CGDirectDisplayID) -> Option<DisplayLink> {
+        unsafe {
+            let mut link = ptr::null_mut();
+            if ffi::CVDisplayLinkCreateWithCGDisplay(display_id, &mut link) != ffi::kCVReturnSuccess {
+                return None;
+            }
+            let frames = Box::into_raw(Box::new((Mutex::new(0), Condvar::new())));
+            ffi::CVDisplayLinkSetOutputCallback(link, output_callback, frames as *mut c_void);
+            if ffi::CVDisplayLinkStart(link) != ffi::kCVReturnSuccess {
+                ffi::CVDisplayLinkRelease(link);
+                Box::from_raw(frames);
+                return None;
+            }
+            Some(DisplayLink { link, display_id, frames })
+        }
+    }
+
+    #[inline]
+    pub fn get_display_id(&self) -> CGDirectDisplayID {
+        self.display_id
+    }
+
+    /// Blocks until the display starts showing its next frame.
+    pub fn wait(&self) {
+        let &(ref mutex, ref condvar) = unsafe { &*self.frames };
+        let mut frame = mutex.lock().unwrap();
+        let current = *frame;
+        while *frame == current {
+            // The display link stops calling us back while the display is asleep.
+            let (guard, timeout) = condvar.wait_timeout(frame, Duration::from_millis(100)).unwrap();
+            frame = guard;
+            if timeout.timed_out() {
+                break;
+            }
+        }
+    }
+}
+
+impl Drop for DisplayLink {
+    fn drop(&mut self) {
+        unsafe {
+            // This waits for the callback to return if it's running.
+            ffi::CVDisplayLinkStop(self.link);
+            ffi::CVDisplayLinkRelease(self.link);
+            Box::from_raw(self.frames);
+        }
+    }
+}
+
+extern "C" fn output_callback(
+    _display_link: ffi::CVDisplayLinkRef,
+    _in_now: *const c_void,
+    _in_output_time: *const c_void,
+    _flags_in: ffi::CVOptionFlags,
+    _flags_out: *mut ffi::CVOptionFlags,
+    display_link_context: *mut c_void,
+) -> ffi::CVReturn {
+    let &(ref mutex, ref condvar) = unsafe {
+        &*(display_link_context as *const (Mutex<u64>, Condvar))
+    };
+    *mutex.lock().unwrap() += 1;
+    condvar.notify_all();
+    ffi::kCVReturnSuccess
+}
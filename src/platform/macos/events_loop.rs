@@ -1,23 +1,41 @@
-use {ControlFlow, EventsLoopClosed};
+use {ActivationPolicy, ControlFlow, EventsLoopClosed};
 use cocoa::{self, appkit, foundation};
 use cocoa::appkit::{NSApplication, NSEvent, NSEventMask, NSEventModifierFlags, NSEventPhase, NSView, NSWindow};
+use cocoa::foundation::NSString;
+use core_graphics::display::CGDisplay;
 use events::{self, ElementState, Event, TouchPhase, WindowEvent, DeviceEvent, ModifiersState, KeyboardInput};
+use os::macos::ActivityOptions;
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, Weak};
-use super::window::Window2;
+use std::time::Duration;
+use super::device_observer::DeviceObserver;
+use super::display_link::DisplayLink;
+use super::keyboard_layout::{self, KeyboardLayoutObserver};
+use super::session::{self, SessionObserver};
+use super::ffi;
+use super::window::{IdRef, Window2};
 use std;
 use std::os::raw::*;
 use super::DeviceId;
 
 pub struct EventsLoop {
-    modifiers: Modifiers,
     pub shared: Arc<Shared>,
+    // Created the first time `wait_for_vblank` is called, and replaced when the key window moves
+    // to another display.
+    display_link: Option<DisplayLink>,
+    pub activation_policy: ActivationPolicy,
+    session_observer: SessionObserver,
+    keyboard_layout_observer: KeyboardLayoutObserver,
+    device_observer: DeviceObserver,
 }
 
 // State shared between the `EventsLoop` and its registered windows.
 pub struct Shared {
     pub windows: Mutex<Vec<Weak<Window2>>>,
     pub pending_events: Mutex<VecDeque<Event>>,
+    // The modifier keys that were reported as held down, which is also updated when a window
+    // gains or loses focus.
+    modifiers: Mutex<Modifiers>,
     // The user event callback given via either of the `poll_events` or `run_forever` methods.
     //
     // We store the user's callback here so that it may be accessed by each of the window delegate
@@ -55,6 +73,7 @@ impl Shared {
         Shared {
             windows: Mutex::new(Vec::new()),
             pending_events: Mutex::new(VecDeque::new()),
+            modifiers: Mutex::new(Modifiers::new()),
             user_callback: UserCallback { mutex: Mutex::new(None) },
         }
     }
@@ -91,14 +110,66 @@ impl Shared {
     //
     // This is called in response to `windowWillClose`.
     pub fn find_and_remove_window(&self, id: super::window::Id) {
+        // Dropping the last reference to a window calls this again, so the windows we upgrade are
+        // only dropped once the lock has been released.
+        let mut upgraded = Vec::new();
         if let Ok(mut windows) = self.windows.lock() {
             windows.retain(|w| match w.upgrade() {
-                Some(w) => w.id() != id,
+                Some(w) => {
+                    let keep = w.id() != id;
+                    upgraded.push(w);
+                    keep
+                },
                 None => false,
             });
         }
     }
 
+    // Sends a synthetic `KeyboardInput` for every key that's held down, when a window gains or
+    // loses focus.
+    pub fn send_synthetic_keys(&self, window_id: ::WindowId, state: ElementState) {
+        let modifiers = current_mods();
+        let mut events = Vec::new();
+        for keycode in 0..0x80 {
+            let pressed = unsafe {
+                ffi::CGEventSourceKeyState(ffi::kCGEventSourceStateCombinedSessionState, keycode)
+            };
+            if pressed {
+                events.push(Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::KeyboardInput {
+                        device_id: DEVICE_ID,
+                        input: KeyboardInput {
+                            state,
+                            scancode: keycode as u32,
+                            virtual_keycode: to_virtual_key_code(keycode),
+                            modifiers,
+                            is_composing: false,
+                            is_synthetic: true,
+                            is_repeat: false,
+                            text: None,
+                            is_dead_key: false,
+                        },
+                    },
+                });
+            }
+        }
+
+        // Modifier keys are only reported by `NSFlagsChanged` when they change, so the state it's
+        // compared with has to match what was just sent.
+        {
+            let focused = state == ElementState::Pressed;
+            let mut tracked = self.modifiers.lock().unwrap();
+            tracked.shift_pressed = focused && modifiers.shift;
+            tracked.ctrl_pressed = focused && modifiers.ctrl;
+            tracked.win_pressed = focused && modifiers.logo;
+            tracked.alt_pressed = focused && modifiers.alt;
+        }
+
+        for event in events {
+            self.call_user_callback_with_event_or_store_in_pending(event);
+        }
+    }
 }
 
 
@@ -170,13 +241,71 @@ impl EventsLoop {
         // marked as the main thread.
         unsafe { appkit::NSApp(); }
 
+        let shared = Arc::new(Shared::new());
+        let session_observer = SessionObserver::new(Arc::downgrade(&shared));
+        let keyboard_layout_observer = KeyboardLayoutObserver::new(Arc::downgrade(&shared));
+        let device_observer = DeviceObserver::new(Arc::downgrade(&shared));
         EventsLoop {
-            shared: Arc::new(Shared::new()),
-            modifiers: Modifiers::new(),
+            shared,
+            display_link: None,
+            activation_policy: ActivationPolicy::Regular,
+            session_observer,
+            keyboard_layout_observer,
+            device_observer,
+        }
+    }
+
+    pub fn set_activation_policy(&mut self, activation_policy: ActivationPolicy) {
+        self.activation_policy = activation_policy;
+    }
+
+    pub fn reserve_events(&mut self, capacity: usize) {
+        self.shared.pending_events.lock().unwrap().reserve(capacity);
+    }
+
+    // Only takes effect while the application has no delegate of its own, since termination is
+    // cancelled from `applicationShouldTerminate:`.
+    pub fn block_session_end(&self, reason: Option<&str>) {
+        session::set_session_end_blocked(reason.is_some());
+    }
+
+    // Nothing can fail to connect on this platform.
+    pub fn try_new() -> Result<Self, ::EventsLoopCreationError> {
+        Ok(EventsLoop::new())
+    }
+
+    // Waits for the next frame of the display the key window is on, or of the main display.
+    pub fn wait_for_vblank(&mut self) -> bool {
+        let display_id = {
+            // Not locked while we upgrade, as dropping a window removes it from the list.
+            let windows = self.shared.windows.lock().unwrap().clone();
+            windows.iter()
+                .filter_map(Weak::upgrade)
+                .find(|window| unsafe {
+                    let is_key_window: cocoa::base::BOOL = msg_send![*window.window, isKeyWindow];
+                    is_key_window == cocoa::base::YES
+                })
+                .map(|window| window.get_current_monitor().inner.get_native_identifier())
+                .unwrap_or_else(|| CGDisplay::main().id)
+        };
+        let is_current = self.display_link
+            .as_ref()
+            .map_or(false, |link| link.get_display_id() == display_id);
+        if !is_current {
+            // Dropping the old link first means there's only ever one callback thread.
+            self.display_link = None;
+            self.display_link = DisplayLink::new(display_id);
+        }
+        match self.display_link {
+            Some(ref link) => {
+                link.wait();
+                true
+            },
+            None => false,
         }
     }
 
-    pub fn poll_events<F>(&mut self, mut callback: F)
+    pub fn poll_events<F>(&self, mut callback: F)
         where F: FnMut(Event),
     {
         unsafe {
@@ -217,7 +346,7 @@ impl EventsLoop {
         self.shared.user_callback.drop();
     }
 
-    pub fn run_forever<F>(&mut self, mut callback: F)
+    pub fn run_forever<F>(&self, mut callback: F)
         where F: FnMut(Event) -> ControlFlow
     {
         unsafe {
@@ -273,7 +402,7 @@ impl EventsLoop {
     }
 
     // Convert some given `NSEvent` into a winit `Event`.
-    unsafe fn ns_event_to_event(&mut self, ns_event: cocoa::base::id) -> Option<Event> {
+    unsafe fn ns_event_to_event(&self, ns_event: cocoa::base::id) -> Option<Event> {
         if ns_event == cocoa::base::nil {
             return None;
         }
@@ -296,7 +425,19 @@ impl EventsLoop {
         // If we don't do this, window does not become main for some reason.
         appkit::NSApp().sendEvent_(ns_event);
 
-        let windows = self.shared.windows.lock().unwrap();
+        // `NSApplication` doesn't send key-up events to the key window while command is held, which
+        // would otherwise leave the key stuck.
+        if event_type == appkit::NSKeyUp
+            && NSEvent::modifierFlags(ns_event).contains(NSEventModifierFlags::NSCommandKeyMask)
+        {
+            let key_window: cocoa::base::id = msg_send![appkit::NSApp(), keyWindow];
+            if key_window != cocoa::base::nil {
+                let _: () = msg_send![key_window, sendEvent:ns_event];
+            }
+        }
+
+        // Not locked while we upgrade, as dropping a window removes it from the list.
+        let windows = self.shared.windows.lock().unwrap().clone();
         let maybe_window = windows.iter()
             .filter_map(Weak::upgrade)
             .find(|window| window_id == window.id());
@@ -316,41 +457,42 @@ impl EventsLoop {
 
         match event_type {
             appkit::NSFlagsChanged => {
+                let mut modifiers = self.shared.modifiers.lock().unwrap();
                 let mut events = std::collections::VecDeque::new();
 
                 if let Some(window_event) = modifier_event(
                     ns_event,
                     NSEventModifierFlags::NSShiftKeyMask,
-                    self.modifiers.shift_pressed,
+                    modifiers.shift_pressed,
                 ) {
-                    self.modifiers.shift_pressed = !self.modifiers.shift_pressed;
+                    modifiers.shift_pressed = !modifiers.shift_pressed;
                     events.push_back(into_event(window_event));
                 }
 
                 if let Some(window_event) = modifier_event(
                     ns_event,
                     NSEventModifierFlags::NSControlKeyMask,
-                    self.modifiers.ctrl_pressed,
+                    modifiers.ctrl_pressed,
                 ) {
-                    self.modifiers.ctrl_pressed = !self.modifiers.ctrl_pressed;
+                    modifiers.ctrl_pressed = !modifiers.ctrl_pressed;
                     events.push_back(into_event(window_event));
                 }
 
                 if let Some(window_event) = modifier_event(
                     ns_event,
                     NSEventModifierFlags::NSCommandKeyMask,
-                    self.modifiers.win_pressed,
+                    modifiers.win_pressed,
                 ) {
-                    self.modifiers.win_pressed = !self.modifiers.win_pressed;
+                    modifiers.win_pressed = !modifiers.win_pressed;
                     events.push_back(into_event(window_event));
                 }
 
                 if let Some(window_event) = modifier_event(
                     ns_event,
                     NSEventModifierFlags::NSAlternateKeyMask,
-                    self.modifiers.alt_pressed,
+                    modifiers.alt_pressed,
                 ) {
-                    self.modifiers.alt_pressed = !self.modifiers.alt_pressed;
+                    modifiers.alt_pressed = !modifiers.alt_pressed;
                     events.push_back(into_event(window_event));
                 }
 
@@ -450,11 +592,10 @@ impl EventsLoop {
                         ns_event.scrollingDeltaY() as f32,
                     )
                 };
-                let phase = match ns_event.phase() {
-                    NSEventPhase::NSEventPhaseMayBegin | NSEventPhase::NSEventPhaseBegan => TouchPhase::Started,
-                    NSEventPhase::NSEventPhaseEnded => TouchPhase::Ended,
-                    _ => TouchPhase::Moved,
-                };
+                let phase = scroll_phase(ns_event.phase()).unwrap_or(TouchPhase::Moved);
+                // Momentum events are sent by the system after the user lifts their fingers, and
+                // have no regular phase of their own.
+                let momentum_phase = scroll_phase(ns_event.momentumPhase());
                 self.shared.pending_events.lock().unwrap().push_back(Event::DeviceEvent {
                     device_id: DEVICE_ID,
                     event: DeviceEvent::MouseWheel {
@@ -471,7 +612,13 @@ impl EventsLoop {
                         },
                     }
                 });
-                let window_event = WindowEvent::MouseWheel { device_id: DEVICE_ID, delta: delta, phase: phase, modifiers: event_mods(ns_event) };
+                let window_event = WindowEvent::MouseWheel {
+                    device_id: DEVICE_ID,
+                    delta: delta,
+                    phase: phase,
+                    momentum_phase: momentum_phase,
+                    modifiers: event_mods(ns_event),
+                };
                 Some(into_event(window_event))
             },
 
@@ -493,10 +640,127 @@ impl EventsLoop {
         }
     }
 
+    pub fn keyboard_layouts(&self) -> Vec<::LayoutInfo> {
+        keyboard_layout::keyboard_layouts()
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        self.device_observer.device_infos()
+    }
+
     pub fn create_proxy(&self) -> Proxy {
         Proxy {}
     }
 
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        Some(IdleTimeQuery)
+    }
+
+    pub fn shutdown(self) {
+        // No background threads to wait for, and the classes stay registered for reuse.
+    }
+
+    /// Returns the visible windows of this events loop from front to back.
+    pub fn get_window_stacking_order(&self) -> Vec<super::window::Id> {
+        // Upgrading the windows outside of the lock, as dropping one locks it again.
+        let windows = self.shared.windows.lock().unwrap().clone();
+        let ids: Vec<_> = windows
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|window| window.id())
+            .collect();
+        unsafe { super::util::get_visible_windows_front_to_back() }
+            .into_iter()
+            .map(super::window::get_window_id)
+            .filter(|id| ids.contains(id))
+            .collect()
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        // Not locked while we upgrade, as dropping a window removes it from the list.
+        let windows = self.shared.windows.lock().unwrap().clone();
+        let key_window = windows.iter()
+            .filter_map(Weak::upgrade)
+            .find(|window| unsafe {
+                let is_key_window: cocoa::base::BOOL = msg_send![*window.window, isKeyWindow];
+                is_key_window == cocoa::base::YES
+            })
+            .map(|window| window.id());
+        FocusSnapshot { key_window }
+    }
+
+    pub fn restore_focus(&self, snapshot: &FocusSnapshot) {
+        let key_window = match snapshot.key_window {
+            Some(key_window) => key_window,
+            None => return,
+        };
+        let windows = self.shared.windows.lock().unwrap().clone();
+        let maybe_window = windows.iter()
+            .filter_map(Weak::upgrade)
+            .find(|window| window.id() == key_window);
+        if let Some(window) = maybe_window {
+            // The view stays the window's first responder, so the input method's text goes back to
+            // it along with the key window status.
+            unsafe {
+                let _: () = msg_send![*window.window, makeKeyWindow];
+            }
+        }
+    }
+
+    pub fn begin_activity(&self, options: ActivityOptions, reason: &str) -> Activity {
+        let mut ns_options = match (options.user_initiated, options.prevent_system_sleep) {
+            (true, true) => ffi::NSActivityUserInitiated,
+            (true, false) => ffi::NSActivityUserInitiatedAllowingIdleSystemSleep,
+            (false, true) => ffi::NSActivityBackground | ffi::NSActivityIdleSystemSleepDisabled,
+            (false, false) => ffi::NSActivityBackground,
+        };
+        if options.latency_critical {
+            ns_options |= ffi::NSActivityLatencyCritical;
+        }
+        if options.prevent_display_sleep {
+            ns_options |= ffi::NSActivityIdleDisplaySleepDisabled;
+        }
+        unsafe {
+            let pool = foundation::NSAutoreleasePool::new(cocoa::base::nil);
+            let process_info: cocoa::base::id = msg_send![class!(NSProcessInfo), processInfo];
+            let reason = IdRef::new(NSString::alloc(cocoa::base::nil).init_str(reason));
+            let token: cocoa::base::id = msg_send![
+                process_info,
+                beginActivityWithOptions:ns_options
+                reason:*reason
+            ];
+            // The returned token is autoreleased, so we need to hold on to it ourselves.
+            let token = IdRef::retain(token);
+            let _: () = msg_send![pool, release];
+            Activity { process_info, token }
+        }
+    }
+
+}
+
+// Keeps an `NSProcessInfo` activity alive, ending it when dropped.
+pub struct Activity {
+    process_info: cocoa::base::id,
+    token: IdRef,
+}
+
+impl Drop for Activity {
+    fn drop(&mut self) {
+        unsafe {
+            let _: () = msg_send![self.process_info, endActivity:*self.token];
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot {
+    key_window: Option<super::window::Id>,
+}
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<super::window::Id> {
+        self.key_window
+    }
 }
 
 impl Proxy {
@@ -523,6 +787,26 @@ impl Proxy {
     }
 }
 
+/// Queries how long the user has been idle for, from any thread.
+pub struct IdleTimeQuery;
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<Duration> {
+        let seconds = unsafe {
+            ffi::CGEventSourceSecondsSinceLastEventType(
+                ffi::kCGEventSourceStateCombinedSessionState,
+                ffi::kCGAnyInputEventType,
+            )
+        };
+        if seconds >= 0.0 {
+            let nanos = (seconds.fract() * 1_000_000_000.0) as u32;
+            Some(Duration::new(seconds as u64, nanos))
+        } else {
+            None
+        }
+    }
+}
+
 pub fn to_virtual_key_code(code: c_ushort) -> Option<events::VirtualKeyCode> {
     Some(match code {
         0x00 => events::VirtualKeyCode::A,
@@ -663,6 +947,28 @@ pub fn event_mods(event: cocoa::base::id) -> ModifiersState {
     let flags = unsafe {
         NSEvent::modifierFlags(event)
     };
+    flags_to_mods(flags)
+}
+
+/// Returns the modifiers that are currently held, for when there's no event to get them from.
+pub fn current_mods() -> ModifiersState {
+    let flags: NSEventModifierFlags = unsafe {
+        msg_send![class!(NSEvent), modifierFlags]
+    };
+    flags_to_mods(flags)
+}
+
+fn scroll_phase(phase: NSEventPhase) -> Option<TouchPhase> {
+    match phase {
+        NSEventPhase::NSEventPhaseMayBegin | NSEventPhase::NSEventPhaseBegan => Some(TouchPhase::Started),
+        NSEventPhase::NSEventPhaseEnded => Some(TouchPhase::Ended),
+        NSEventPhase::NSEventPhaseCancelled => Some(TouchPhase::Cancelled),
+        NSEventPhase::NSEventPhaseNone => None,
+        _ => Some(TouchPhase::Moved),
+    }
+}
+
+fn flags_to_mods(flags: NSEventModifierFlags) -> ModifiersState {
     ModifiersState {
         shift: flags.contains(NSEventModifierFlags::NSShiftKeyMask),
         ctrl: flags.contains(NSEventModifierFlags::NSControlKeyMask),
@@ -693,6 +999,11 @@ unsafe fn modifier_event(
                 scancode,
                 virtual_keycode,
                 modifiers: event_mods(ns_event),
+                is_composing: false,
+                is_synthetic: false,
+                is_repeat: false,
+                text: None,
+                is_dead_key: false,
             },
         })
     } else {
@@ -4,10 +4,17 @@
 
 use cocoa::base::id;
 use cocoa::foundation::{NSInteger, NSUInteger};
+use core_graphics::base::CGFloat;
+use core_graphics::display::CGDirectDisplayID;
 use objc;
+use std::os::raw::c_void;
 
 pub const NSNotFound: NSInteger = NSInteger::max_value();
 
+pub const NSVariableStatusItemLength: CGFloat = -1.0;
+
+pub const NSBitmapFormatAlphaNonpremultiplied: NSInteger = 1 << 1;
+
 #[repr(C)]
 pub struct NSRange {
     pub location: NSUInteger,
@@ -33,6 +40,15 @@ unsafe impl objc::Encode for NSRange {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct NSEdgeInsets {
+    pub top: CGFloat,
+    pub left: CGFloat,
+    pub bottom: CGFloat,
+    pub right: CGFloat,
+}
+
 pub trait NSMutableAttributedString: Sized {
     unsafe fn alloc(_: Self) -> id {
         msg_send![class!(NSMutableAttributedString), alloc]
@@ -105,3 +121,125 @@ pub enum NSWindowLevel {
     NSPopUpMenuWindowLevel = kCGPopUpMenuWindowLevelKey as _,
     NSScreenSaverWindowLevel = kCGScreenSaverWindowLevelKey as _,
 }
+
+pub type NSActivityOptions = u64;
+
+pub const NSActivityIdleDisplaySleepDisabled: NSActivityOptions = 1 << 40;
+pub const NSActivityIdleSystemSleepDisabled: NSActivityOptions = 1 << 20;
+pub const NSActivitySuddenTerminationDisabled: NSActivityOptions = 1 << 14;
+pub const NSActivityAutomaticTerminationDisabled: NSActivityOptions = 1 << 15;
+pub const NSActivityUserInitiated: NSActivityOptions = 0x00FF_FFFF | NSActivityIdleSystemSleepDisabled;
+pub const NSActivityUserInitiatedAllowingIdleSystemSleep: NSActivityOptions =
+    NSActivityUserInitiated & !NSActivityIdleSystemSleepDisabled;
+pub const NSActivityBackground: NSActivityOptions = 0x0000_00FF;
+pub const NSActivityLatencyCritical: NSActivityOptions = 0xFF_0000_0000;
+
+pub type CVDisplayLinkRef = *mut c_void;
+pub type CVReturn = i32;
+pub type CVOptionFlags = u64;
+pub type CVDisplayLinkOutputCallback = extern "C" fn(
+    display_link: CVDisplayLinkRef,
+    in_now: *const c_void,
+    in_output_time: *const c_void,
+    flags_in: CVOptionFlags,
+    flags_out: *mut CVOptionFlags,
+    display_link_context: *mut c_void,
+) -> CVReturn;
+
+pub const kCVReturnSuccess: CVReturn = 0;
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    pub fn CVDisplayLinkCreateWithCGDisplay(
+        display_id: CGDirectDisplayID,
+        display_link_out: *mut CVDisplayLinkRef,
+    ) -> CVReturn;
+    pub fn CVDisplayLinkSetOutputCallback(
+        display_link: CVDisplayLinkRef,
+        callback: CVDisplayLinkOutputCallback,
+        user_info: *mut c_void,
+    ) -> CVReturn;
+    pub fn CVDisplayLinkStart(display_link: CVDisplayLinkRef) -> CVReturn;
+    pub fn CVDisplayLinkStop(display_link: CVDisplayLinkRef) -> CVReturn;
+    pub fn CVDisplayLinkRelease(display_link: CVDisplayLinkRef);
+}
+
+pub type CGEventSourceStateID = i32;
+pub type CGEventType = u32;
+pub type CGKeyCode = u16;
+
+pub const kCGEventSourceStateCombinedSessionState: CGEventSourceStateID = 0;
+pub const kCGAnyInputEventType: CGEventType = !0;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    pub fn CGEventSourceSecondsSinceLastEventType(
+        source_state_id: CGEventSourceStateID,
+        event_type: CGEventType,
+    ) -> f64;
+    pub fn CGEventSourceKeyState(source_state_id: CGEventSourceStateID, key: CGKeyCode) -> bool;
+}
+
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {
+    pub static NSRunLoopCommonModes: id;
+}
+
+pub type TISInputSourceRef = *mut c_void;
+
+// The Text Input Sources API, whose strings and arrays are toll-free bridged with their Foundation
+// counterparts.
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    pub static kTISPropertyInputSourceCategory: id;
+    pub static kTISPropertyInputSourceID: id;
+    pub static kTISPropertyInputSourceIsSelectCapable: id;
+    pub static kTISPropertyLocalizedName: id;
+    pub static kTISCategoryKeyboardInputSource: id;
+
+    pub fn TISCreateInputSourceList(properties: id, include_all_installed: u8) -> id;
+    pub fn TISCopyCurrentKeyboardInputSource() -> TISInputSourceRef;
+    pub fn TISGetInputSourceProperty(source: TISInputSourceRef, key: id) -> id;
+}
+
+pub type IOHIDManagerRef = *mut c_void;
+pub type IOHIDDeviceRef = *mut c_void;
+pub type IOReturn = i32;
+pub type IOOptionBits = u32;
+pub type IOHIDDeviceCallback = extern "C" fn(
+    context: *mut c_void,
+    result: IOReturn,
+    sender: *mut c_void,
+    device: IOHIDDeviceRef,
+);
+
+pub const kIOHIDOptionsTypeNone: IOOptionBits = 0;
+pub const kHIDPage_GenericDesktop: u32 = 0x01;
+pub const kHIDUsage_GD_Mouse: u32 = 0x02;
+pub const kHIDUsage_GD_Joystick: u32 = 0x04;
+pub const kHIDUsage_GD_GamePad: u32 = 0x05;
+pub const kHIDUsage_GD_Keyboard: u32 = 0x06;
+pub const kHIDUsage_GD_MultiAxisController: u32 = 0x08;
+
+// The dictionaries and sets are toll-free bridged with their Foundation counterparts.
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    pub fn IOHIDManagerCreate(allocator: *const c_void, options: IOOptionBits) -> IOHIDManagerRef;
+    pub fn IOHIDManagerSetDeviceMatchingMultiple(manager: IOHIDManagerRef, multiple: id);
+    pub fn IOHIDManagerRegisterDeviceMatchingCallback(
+        manager: IOHIDManagerRef,
+        callback: IOHIDDeviceCallback,
+        context: *mut c_void,
+    );
+    pub fn IOHIDManagerRegisterDeviceRemovalCallback(
+        manager: IOHIDManagerRef,
+        callback: IOHIDDeviceCallback,
+        context: *mut c_void,
+    );
+    pub fn IOHIDManagerScheduleWithRunLoop(manager: IOHIDManagerRef, run_loop: *mut c_void, mode: id);
+    pub fn IOHIDManagerUnscheduleFromRunLoop(manager: IOHIDManagerRef, run_loop: *mut c_void, mode: id);
+    pub fn IOHIDManagerOpen(manager: IOHIDManagerRef, options: IOOptionBits) -> IOReturn;
+    pub fn IOHIDManagerClose(manager: IOHIDManagerRef, options: IOOptionBits) -> IOReturn;
+    pub fn IOHIDManagerCopyDevices(manager: IOHIDManagerRef) -> id;
+    pub fn IOHIDDeviceGetProperty(device: IOHIDDeviceRef, key: id) -> id;
+}
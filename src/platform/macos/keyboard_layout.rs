@@ -0,0 +1,113 @@
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+use std::sync::Weak;
+
+use cocoa::base::{id, nil, BOOL, NO};
+use cocoa::foundation::NSUInteger;
+use core_foundation::base::CFRelease;
+use objc::runtime::{Object, Sel};
+
+use {Event, LayoutInfo};
+use platform::platform::events_loop::Shared;
+use platform::platform::ffi;
+use platform::platform::observer::{self, Observer, ObserverClass};
+use platform::platform::util;
+
+// Posted to the distributed notification center by the Text Input Sources API.
+const SELECTED_SOURCE_CHANGED: &str = "com.apple.Carbon.TISNotifySelectedKeyboardInputSourceChanged";
+const ENABLED_SOURCES_CHANGED: &str = "com.apple.Carbon.TISNotifyEnabledKeyboardInputSourcesChanged";
+
+pub fn keyboard_layouts() -> Vec<LayoutInfo> {
+    unsafe {
+        let properties: id = msg_send![
+            class!(NSDictionary),
+            dictionaryWithObject:ffi::kTISCategoryKeyboardInputSource
+            forKey:ffi::kTISPropertyInputSourceCategory
+        ];
+        let sources = ffi::TISCreateInputSourceList(properties, 0);
+        if sources == nil {
+            return Vec::new();
+        }
+        let current = ffi::TISCopyCurrentKeyboardInputSource();
+        let current_id = if !current.is_null() {
+            ffi::TISGetInputSourceProperty(current, ffi::kTISPropertyInputSourceID)
+        } else {
+            nil
+        };
+
+        let count: NSUInteger = msg_send![sources, count];
+        let mut layouts = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let source: id = msg_send![sources, objectAtIndex:i];
+            let source = source as ffi::TISInputSourceRef;
+            // Excludes the sources that are enabled but only used alongside others, like palettes.
+            let select_capable =
+                ffi::TISGetInputSourceProperty(source, ffi::kTISPropertyInputSourceIsSelectCapable);
+            let select_capable: BOOL = if select_capable != nil {
+                msg_send![select_capable, boolValue]
+            } else {
+                NO
+            };
+            if select_capable == NO {
+                continue;
+            }
+
+            let name = ffi::TISGetInputSourceProperty(source, ffi::kTISPropertyLocalizedName);
+            let name = if name != nil {
+                let bytes: *const c_char = msg_send![name, UTF8String];
+                CStr::from_ptr(bytes).to_string_lossy().into_owned()
+            } else {
+                String::new()
+            };
+            let source_id = ffi::TISGetInputSourceProperty(source, ffi::kTISPropertyInputSourceID);
+            let active: BOOL = if source_id != nil && current_id != nil {
+                msg_send![source_id, isEqualToString:current_id]
+            } else {
+                NO
+            };
+            layouts.push(LayoutInfo { name, active: active != NO });
+        }
+
+        if !current.is_null() {
+            CFRelease(current as *const c_void);
+        }
+        CFRelease(sources as *const c_void);
+        layouts
+    }
+}
+
+// Observes the notifications sent when the user switches to another input source or changes the
+// enabled ones.
+pub struct KeyboardLayoutObserver {
+    _observer: Observer,
+}
+
+impl KeyboardLayoutObserver {
+    pub fn new(shared: Weak<Shared>) -> Self {
+        unsafe {
+            let mut observer = Observer::new(&OBSERVER_CLASS, shared);
+            let center: id = msg_send![class!(NSDistributedNotificationCenter), defaultCenter];
+            for &name in &[SELECTED_SOURCE_CHANGED, ENABLED_SOURCES_CHANGED] {
+                observer.observe(center, name, sel!(keyboardLayoutChanged:), nil);
+            }
+            KeyboardLayoutObserver { _observer: observer }
+        }
+    }
+}
+
+lazy_static! {
+    static ref OBSERVER_CLASS: ObserverClass = unsafe {
+        ObserverClass::declare("WinitKeyboardLayoutObserver", |decl| {
+            decl.add_method(
+                sel!(keyboardLayoutChanged:),
+                keyboard_layout_changed as extern fn(&Object, Sel, id),
+            );
+        })
+    };
+}
+
+extern fn keyboard_layout_changed(this: &Object, _sel: Sel, _notification: id) {
+    util::catch_exception("keyboard_layout_changed", || {
+        unsafe { observer::send_event(this, Event::KeyboardLayoutChanged); }
+    })
+}
@@ -0,0 +1,214 @@
+use std::os::raw::c_void;
+use std::sync::Weak;
+
+use cocoa::appkit::{self, NSApplication, NSEventModifierFlags};
+use cocoa::base::{id, nil, NO, YES};
+use cocoa::foundation::{NSInteger, NSString, NSUInteger};
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+
+use {Event, VirtualKeyCode};
+use menu::{Accelerator, Menu, MenuEntry, MenuId};
+use platform::platform::events_loop::Shared;
+use platform::platform::util;
+use platform::platform::window::IdRef;
+
+/// Replaces the application's menu bar. `None` leaves it empty.
+pub fn set_menu(shared: Weak<Shared>, menu: Option<Menu>) {
+    unsafe {
+        let menu_bar = match menu {
+            Some(menu) => {
+                // Menu items don't retain their target, but they do retain their represented
+                // object, so we make the target that too. That way, it lives exactly as long as
+                // the menu bar it was created for.
+                let state_ptr = Box::into_raw(Box::new(shared)) as *mut c_void;
+                let target: id = msg_send![TARGET_CLASS.0, alloc];
+                let target = IdRef::new(msg_send![target, initWithWinit:state_ptr]);
+                create_menu(&menu, *target)
+            },
+            None => create_menu(&Menu::new(""), nil),
+        };
+        appkit::NSApp().setMainMenu_(*menu_bar);
+    }
+}
+
+unsafe fn create_menu(menu: &Menu, target: id) -> IdRef {
+    let title = IdRef::new(NSString::alloc(nil).init_str(&menu.title));
+    let ns_menu: id = msg_send![class!(NSMenu), alloc];
+    let ns_menu = IdRef::new(msg_send![ns_menu, initWithTitle:*title]);
+    // Otherwise, items are enabled based on whether their target responds to their action.
+    let _: () = msg_send![*ns_menu, setAutoenablesItems:NO];
+
+    for entry in &menu.entries {
+        match entry {
+            &MenuEntry::Item(ref item) => {
+                let (key_equivalent, modifiers) = item.accelerator
+                    .and_then(to_key_equivalent)
+                    .unwrap_or_else(|| (String::new(), 0));
+                let ns_item = new_menu_item(&item.title);
+                let key_equivalent = IdRef::new(NSString::alloc(nil).init_str(&key_equivalent));
+                let _: () = msg_send![*ns_item, setKeyEquivalent:*key_equivalent];
+                let _: () = msg_send![*ns_item, setKeyEquivalentModifierMask:modifiers];
+                let _: () = msg_send![*ns_item, setTag:item.id.0 as NSInteger];
+                let _: () = msg_send![*ns_item, setTarget:target];
+                let _: () = msg_send![*ns_item, setAction:sel!(menuItemSelected:)];
+                let _: () = msg_send![*ns_item, setRepresentedObject:target];
+                let _: () = msg_send![*ns_item, setEnabled:if item.enabled { YES } else { NO }];
+                if item.checked == Some(true) {
+                    let _: () = msg_send![*ns_item, setState:1 as NSInteger];
+                }
+                let _: () = msg_send![*ns_menu, addItem:*ns_item];
+            },
+            &MenuEntry::Submenu(ref submenu) => {
+                let ns_item = new_menu_item(&submenu.title);
+                let ns_submenu = create_menu(submenu, target);
+                let _: () = msg_send![*ns_item, setSubmenu:*ns_submenu];
+                let _: () = msg_send![*ns_menu, addItem:*ns_item];
+            },
+            &MenuEntry::Separator => {
+                let separator: id = msg_send![class!(NSMenuItem), separatorItem];
+                let _: () = msg_send![*ns_menu, addItem:separator];
+            },
+        }
+    }
+
+    ns_menu
+}
+
+unsafe fn new_menu_item(title: &str) -> IdRef {
+    let title = IdRef::new(NSString::alloc(nil).init_str(title));
+    let ns_item: id = msg_send![class!(NSMenuItem), alloc];
+    let ns_item = IdRef::new(msg_send![ns_item, init]);
+    let _: () = msg_send![*ns_item, setTitle:*title];
+    ns_item
+}
+
+// Returns the key equivalent string and modifier mask for an accelerator, or `None` if the key
+// can't be used as a key equivalent.
+fn to_key_equivalent(accelerator: Accelerator) -> Option<(String, NSUInteger)> {
+    let key = match accelerator.key {
+        VirtualKeyCode::Escape => "\u{1b}".to_owned(),
+        VirtualKeyCode::Return => "\r".to_owned(),
+        VirtualKeyCode::Tab => "\t".to_owned(),
+        VirtualKeyCode::Space => " ".to_owned(),
+        VirtualKeyCode::Back => "\u{8}".to_owned(),
+        VirtualKeyCode::Delete => "\u{7f}".to_owned(),
+        VirtualKeyCode::Apostrophe => "'".to_owned(),
+        VirtualKeyCode::Backslash => "\\".to_owned(),
+        VirtualKeyCode::Comma => ",".to_owned(),
+        VirtualKeyCode::Equals => "=".to_owned(),
+        VirtualKeyCode::Grave => "`".to_owned(),
+        VirtualKeyCode::LBracket => "[".to_owned(),
+        VirtualKeyCode::Minus => "-".to_owned(),
+        VirtualKeyCode::Period => ".".to_owned(),
+        VirtualKeyCode::RBracket => "]".to_owned(),
+        VirtualKeyCode::Semicolon => ";".to_owned(),
+        VirtualKeyCode::Slash => "/".to_owned(),
+        // The remaining keys are represented by the function key characters from `NSEvent.h`.
+        VirtualKeyCode::Up => function_key(0xF700),
+        VirtualKeyCode::Down => function_key(0xF701),
+        VirtualKeyCode::Left => function_key(0xF702),
+        VirtualKeyCode::Right => function_key(0xF703),
+        VirtualKeyCode::Insert => function_key(0xF727),
+        VirtualKeyCode::Home => function_key(0xF729),
+        VirtualKeyCode::End => function_key(0xF72B),
+        VirtualKeyCode::PageUp => function_key(0xF72C),
+        VirtualKeyCode::PageDown => function_key(0xF72D),
+        key => {
+            // Letters, digits (`Key1`, `Key2`, etc.) and function keys (`F1`, `F2`, etc.) are
+            // easier to handle by name.
+            let name = format!("{:?}", key);
+            if name.len() == 1 {
+                // Using the lowercase letter means that shift isn't implied.
+                name.to_lowercase()
+            } else if name.starts_with("Key") {
+                name["Key".len()..].to_owned()
+            } else if name.starts_with("F") {
+                let number: u32 = name["F".len()..].parse().ok()?;
+                function_key(0xF704 + number - 1)
+            } else {
+                return None;
+            }
+        },
+    };
+
+    let mut modifiers = NSEventModifierFlags::empty();
+    if accelerator.modifiers.shift {
+        modifiers |= NSEventModifierFlags::NSShiftKeyMask;
+    }
+    if accelerator.modifiers.ctrl {
+        modifiers |= NSEventModifierFlags::NSControlKeyMask;
+    }
+    if accelerator.modifiers.alt {
+        modifiers |= NSEventModifierFlags::NSAlternateKeyMask;
+    }
+    if accelerator.modifiers.logo {
+        modifiers |= NSEventModifierFlags::NSCommandKeyMask;
+    }
+    Some((key, modifiers.bits() as NSUInteger))
+}
+
+fn function_key(character: u32) -> String {
+    ::std::char::from_u32(character)
+        .map(|character| character.to_string())
+        .unwrap_or_default()
+}
+
+struct TargetClass(*const Class);
+unsafe impl Send for TargetClass {}
+unsafe impl Sync for TargetClass {}
+
+lazy_static! {
+    static ref TARGET_CLASS: TargetClass = unsafe {
+        let superclass = class!(NSObject);
+        let mut decl = ClassDecl::new("WinitMenuTarget", superclass).unwrap();
+        decl.add_method(sel!(dealloc), dealloc as extern fn(&Object, Sel));
+        decl.add_method(
+            sel!(initWithWinit:),
+            init_with_winit as extern fn(&Object, Sel, *mut c_void) -> id,
+        );
+        decl.add_method(
+            sel!(menuItemSelected:),
+            menu_item_selected as extern fn(&Object, Sel, id),
+        );
+        decl.add_ivar::<*mut c_void>("winitState");
+        TargetClass(decl.register())
+    };
+}
+
+extern fn dealloc(this: &Object, _sel: Sel) {
+    util::catch_exception("dealloc", || {
+        unsafe {
+            let state: *mut c_void = *this.get_ivar("winitState");
+            Box::from_raw(state as *mut Weak<Shared>);
+            let _: () = msg_send![super(this, class!(NSObject)), dealloc];
+        }
+    })
+}
+
+extern fn init_with_winit(this: &Object, _sel: Sel, state: *mut c_void) -> id {
+    util::catch_exception("init_with_winit", || {
+        unsafe {
+            let this: id = msg_send![this, init];
+            if this != nil {
+                (*this).set_ivar("winitState", state);
+            }
+            this
+        }
+    })
+}
+
+extern fn menu_item_selected(this: &Object, _sel: Sel, sender: id) {
+    util::catch_exception("menu_item_selected", || {
+        unsafe {
+            let state: *mut c_void = *this.get_ivar("winitState");
+            let shared = &*(state as *const Weak<Shared>);
+            let tag: NSInteger = msg_send![sender, tag];
+            if let Some(shared) = shared.upgrade() {
+                shared.call_user_callback_with_event_or_store_in_pending(Event::MenuEvent {
+                    menu_id: MenuId(tag as u16),
+                });
+            }
+        }
+    })
+}
@@ -1,13 +1,53 @@
 #![cfg(target_os = "macos")]
 
-pub use self::events_loop::{EventsLoop, Proxy as EventsLoopProxy};
+pub use self::events_loop::{Activity, EventsLoop, FocusSnapshot, IdleTimeQuery, Proxy as EventsLoopProxy};
 pub use self::monitor::MonitorId;
+#[cfg(feature = "surface")]
+pub use self::surface::Surface;
+#[cfg(feature = "shell")]
+pub use self::shell::{open_with_default_app, reveal_in_file_manager};
+#[cfg(feature = "tray")]
+pub use self::tray::TrayIcon;
 pub use self::window::{Id as WindowId, PlatformSpecificWindowBuilderAttributes, Window2};
 use std::sync::Arc;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId;
 
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> DeviceId {
+        DeviceId
+    }
+}
+
+// The address of the tray icon's `NSStatusItem`. Defined without the `tray` feature too, since
+// `Event::TrayEvent` always exists.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId(usize);
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+
+    pub fn from_raw(raw: u64) -> TrayIconId {
+        TrayIconId(raw as usize)
+    }
+}
+
 use {CreationError};
 
 pub struct Window {
@@ -26,8 +66,9 @@ impl Window {
 
     pub fn new(events_loop: &EventsLoop,
                attributes: ::WindowAttributes,
-               pl_attribs: PlatformSpecificWindowBuilderAttributes) -> Result<Self, CreationError>
+               mut pl_attribs: PlatformSpecificWindowBuilderAttributes) -> Result<Self, CreationError>
     {
+        pl_attribs.activation_policy = pl_attribs.activation_policy.or(Some(events_loop.activation_policy));
         let weak_shared = Arc::downgrade(&events_loop.shared);
         let window = Arc::new(try!(Window2::new(weak_shared, attributes, pl_attribs)));
         let weak_window = Arc::downgrade(&window);
@@ -37,9 +78,18 @@ impl Window {
 
 }
 
+mod device_observer;
+mod display_link;
 mod events_loop;
 mod ffi;
+mod keyboard_layout;
+mod menu;
 mod monitor;
+mod observer;
+mod session;
+mod shell;
+mod surface;
+mod tray;
 mod util;
 mod view;
 mod window;
@@ -6,7 +6,7 @@ use cocoa::base::{id, nil};
 use cocoa::foundation::{NSString, NSUInteger};
 use core_graphics::display::{CGDirectDisplayID, CGDisplay, CGDisplayBounds};
 
-use {PhysicalPosition, PhysicalSize};
+use {Edge, PhysicalPosition, PhysicalSize};
 use super::EventsLoop;
 use super::window::{IdRef, Window2};
 
@@ -122,6 +122,47 @@ impl MonitorId {
         unsafe { NSScreen::backingScaleFactor(screen) as f64 }
     }
 
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        let (left, top, _, _) = self.get_reserved_insets();
+        let hidpi_factor = self.get_hidpi_factor();
+        let position = self.get_position();
+        let position = PhysicalPosition::new(position.x + left * hidpi_factor, position.y + top * hidpi_factor);
+        let size = match self.get_nsscreen() {
+            Some(screen) => {
+                let visible_frame = unsafe { NSScreen::visibleFrame(screen) };
+                PhysicalSize::from_logical(
+                    (visible_frame.size.width as f64, visible_frame.size.height as f64),
+                    hidpi_factor,
+                )
+            },
+            None => self.get_dimensions(),
+        };
+        (position, size)
+    }
+
+    pub fn get_taskbar_edge(&self) -> Option<Edge> {
+        // The top inset is the menu bar, since the Dock can't be at the top.
+        let (left, _, right, bottom) = self.get_reserved_insets();
+        Edge::of_largest_inset(left.round() as i64, 0, right.round() as i64, bottom.round() as i64)
+    }
+
+    // The space taken by the menu bar and the Dock along each edge, in points, as the difference
+    // between the frame of the screen and its visible frame.
+    fn get_reserved_insets(&self) -> (f64, f64, f64, f64) {
+        let screen = match self.get_nsscreen() {
+            Some(screen) => screen,
+            None => return (0.0, 0.0, 0.0, 0.0),
+        };
+        let (frame, visible_frame) = unsafe { (NSScreen::frame(screen), NSScreen::visibleFrame(screen)) };
+        // Cocoa's origin is at the bottom-left corner.
+        (
+            (visible_frame.origin.x - frame.origin.x) as f64,
+            ((frame.origin.y + frame.size.height) - (visible_frame.origin.y + visible_frame.size.height)) as f64,
+            ((frame.origin.x + frame.size.width) - (visible_frame.origin.x + visible_frame.size.width)) as f64,
+            (visible_frame.origin.y - frame.origin.y) as f64,
+        )
+    }
+
     pub(crate) fn get_nsscreen(&self) -> Option<id> {
         unsafe {
             let native_id = self.get_native_identifier();
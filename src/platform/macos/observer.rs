@@ -0,0 +1,118 @@
+use std::os::raw::c_void;
+use std::sync::Weak;
+
+use cocoa::base::{id, nil};
+use cocoa::foundation::NSString;
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+
+use Event;
+use platform::platform::events_loop::Shared;
+use platform::platform::util;
+use platform::platform::window::IdRef;
+
+// A subclass of `NSObject` whose instances observe notifications on behalf of an `EventsLoop`,
+// holding its `Weak<Shared>`.
+pub struct ObserverClass(*const Class);
+unsafe impl Send for ObserverClass {}
+unsafe impl Sync for ObserverClass {}
+
+impl ObserverClass {
+    // `add_methods` adds the methods the notifications are sent to, which can hand events over to
+    // the `EventsLoop` with `send_event`.
+    pub unsafe fn declare<F>(name: &str, add_methods: F) -> ObserverClass
+        where F: FnOnce(&mut ClassDecl)
+    {
+        let superclass = class!(NSObject);
+        let mut decl = ClassDecl::new(name, superclass).unwrap();
+        decl.add_method(sel!(dealloc), dealloc as extern fn(&Object, Sel));
+        decl.add_method(
+            sel!(initWithWinit:),
+            init_with_winit as extern fn(&Object, Sel, *mut c_void) -> id,
+        );
+        add_methods(&mut decl);
+        decl.add_ivar::<*mut c_void>("winitState");
+        ObserverClass(decl.register())
+    }
+}
+
+// An instance of an `ObserverClass`, which is removed from the notification centers it was added
+// to when dropped.
+pub struct Observer {
+    observer: IdRef,
+    centers: Vec<id>,
+}
+
+impl Observer {
+    pub unsafe fn new(class: &ObserverClass, shared: Weak<Shared>) -> Observer {
+        let state = Box::into_raw(Box::new(shared)) as *mut c_void;
+        let observer: id = msg_send![class.0, alloc];
+        Observer {
+            observer: IdRef::new(msg_send![observer, initWithWinit:state]),
+            centers: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> id {
+        *self.observer
+    }
+
+    // Sends the notifications named `name` that `center` receives from `object`, or from any
+    // object if it's `nil`, to `selector`.
+    pub unsafe fn observe(&mut self, center: id, name: &str, selector: Sel, object: id) {
+        let name = IdRef::new(NSString::alloc(nil).init_str(name));
+        let _: () = msg_send![
+            center,
+            addObserver:*self.observer
+            selector:selector
+            name:*name
+            object:object
+        ];
+        if !self.centers.contains(&center) {
+            self.centers.push(center);
+        }
+    }
+}
+
+impl Drop for Observer {
+    fn drop(&mut self) {
+        unsafe {
+            for &center in &self.centers {
+                let _: () = msg_send![center, removeObserver:*self.observer];
+            }
+        }
+    }
+}
+
+extern fn dealloc(this: &Object, _sel: Sel) {
+    util::catch_exception("dealloc", || {
+        unsafe {
+            let state: *mut c_void = *this.get_ivar("winitState");
+            Box::from_raw(state as *mut Weak<Shared>);
+            let _: () = msg_send![super(this, class!(NSObject)), dealloc];
+        }
+    })
+}
+
+extern fn init_with_winit(this: &Object, _sel: Sel, state: *mut c_void) -> id {
+    util::catch_exception("init_with_winit", || {
+        unsafe {
+            let this: id = msg_send![this, init];
+            if this != nil {
+                (*this).set_ivar("winitState", state);
+            }
+            this
+        }
+    })
+}
+
+// Hands `event` to the `EventsLoop` the instance `this` of an `ObserverClass` observes for, if it
+// still exists.
+pub unsafe fn send_event(this: &Object, event: Event) {
+    let state: *mut c_void = *this.get_ivar("winitState");
+    let shared = &*(state as *const Weak<Shared>);
+    if let Some(shared) = shared.upgrade() {
+        shared.call_user_callback_with_event_or_store_in_pending(event);
+    }
+}
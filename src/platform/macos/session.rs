@@ -0,0 +1,133 @@
+use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+
+use cocoa::appkit;
+use cocoa::base::{id, nil};
+use cocoa::foundation::NSUInteger;
+use objc::runtime::{Object, Sel};
+
+use {Event, SessionEndReason};
+use platform::platform::events_loop::Shared;
+use platform::platform::observer::{self, Observer, ObserverClass};
+use platform::platform::util;
+
+// Values of `NSApplicationTerminateReply`.
+const NS_TERMINATE_CANCEL: NSUInteger = 0;
+const NS_TERMINATE_NOW: NSUInteger = 1;
+
+// Set by `block_session_end`, and read when the application is asked to terminate.
+static SESSION_END_BLOCKED: AtomicBool = ATOMIC_BOOL_INIT;
+
+pub fn set_session_end_blocked(blocked: bool) {
+    SESSION_END_BLOCKED.store(blocked, Ordering::SeqCst);
+}
+
+// Observes `NSWorkspaceWillPowerOffNotification` and the activation of the application, and
+// answers `applicationShouldTerminate:` when the application doesn't have a delegate of its own.
+pub struct SessionObserver {
+    observer: Observer,
+}
+
+impl SessionObserver {
+    pub fn new(shared: Weak<Shared>) -> Self {
+        unsafe {
+            let mut observer = Observer::new(&OBSERVER_CLASS, shared);
+
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let center: id = msg_send![workspace, notificationCenter];
+            let selector = sel!(workspaceWillPowerOff:);
+            observer.observe(center, "NSWorkspaceWillPowerOffNotification", selector, nil);
+
+            let app = appkit::NSApp();
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let notifications = [
+                (
+                    "NSApplicationDidBecomeActiveNotification",
+                    sel!(winitApplicationDidBecomeActive:),
+                ),
+                (
+                    "NSApplicationDidResignActiveNotification",
+                    sel!(winitApplicationDidResignActive:),
+                ),
+            ];
+            for &(name, selector) in &notifications {
+                observer.observe(center, name, selector, app);
+            }
+
+            let delegate: id = msg_send![app, delegate];
+            if delegate == nil {
+                let _: () = msg_send![app, setDelegate:observer.id()];
+            }
+
+            SessionObserver { observer }
+        }
+    }
+}
+
+impl Drop for SessionObserver {
+    fn drop(&mut self) {
+        unsafe {
+            let app = appkit::NSApp();
+            let delegate: id = msg_send![app, delegate];
+            if delegate == self.observer.id() {
+                let _: () = msg_send![app, setDelegate:nil];
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref OBSERVER_CLASS: ObserverClass = unsafe {
+        ObserverClass::declare("WinitSessionObserver", |decl| {
+            decl.add_method(
+                sel!(workspaceWillPowerOff:),
+                workspace_will_power_off as extern fn(&Object, Sel, id),
+            );
+            decl.add_method(
+                sel!(winitApplicationDidBecomeActive:),
+                application_did_become_active as extern fn(&Object, Sel, id),
+            );
+            decl.add_method(
+                sel!(winitApplicationDidResignActive:),
+                application_did_resign_active as extern fn(&Object, Sel, id),
+            );
+            decl.add_method(
+                sel!(applicationShouldTerminate:),
+                application_should_terminate as extern fn(&Object, Sel, id) -> NSUInteger,
+            );
+        })
+    };
+}
+
+extern fn workspace_will_power_off(this: &Object, _sel: Sel, _notification: id) {
+    util::catch_exception("workspace_will_power_off", || {
+        // The notification doesn't tell a logout apart from a shutdown or a restart.
+        let event = Event::SessionEnding { reason: SessionEndReason::Unknown };
+        unsafe { observer::send_event(this, event); }
+    })
+}
+
+// The application's delegate is sent these notifications too, under the usual names, so the
+// selectors are named differently for the events not to be sent twice when the observer is the
+// delegate.
+extern fn application_did_become_active(this: &Object, _sel: Sel, _notification: id) {
+    util::catch_exception("application_did_become_active", || {
+        unsafe { observer::send_event(this, Event::ApplicationActivated); }
+    })
+}
+
+extern fn application_did_resign_active(this: &Object, _sel: Sel, _notification: id) {
+    util::catch_exception("application_did_resign_active", || {
+        unsafe { observer::send_event(this, Event::ApplicationDeactivated); }
+    })
+}
+
+extern fn application_should_terminate(_this: &Object, _sel: Sel, _sender: id) -> NSUInteger {
+    util::catch_exception("application_should_terminate", || {
+        if SESSION_END_BLOCKED.load(Ordering::SeqCst) {
+            NS_TERMINATE_CANCEL
+        } else {
+            NS_TERMINATE_NOW
+        }
+    })
+}
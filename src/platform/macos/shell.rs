@@ -0,0 +1,49 @@
+#![cfg(feature = "shell")]
+
+use std::path::Path;
+
+use cocoa::base::{id, nil, BOOL, NO};
+use cocoa::foundation::{NSAutoreleasePool, NSString};
+
+// Returns an autoreleased `NSURL`, so this must be called with an autorelease pool in place.
+unsafe fn file_url(path: &Path) -> Result<id, String> {
+    let path = path.to_str()
+        .ok_or_else(|| format!("{:?} isn't valid UTF-8", path))?;
+    let path = NSString::alloc(nil).init_str(path);
+    let url: id = msg_send![class!(NSURL), fileURLWithPath:path];
+    let _: () = msg_send![path, release];
+    Ok(url)
+}
+
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Err(format!("{:?} doesn't exist", path));
+    }
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+        let result = file_url(path).map(|url| {
+            let urls: id = msg_send![class!(NSArray), arrayWithObject:url];
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let _: () = msg_send![workspace, activateFileViewerSelectingURLs:urls];
+        });
+        let _: () = msg_send![pool, drain];
+        result
+    }
+}
+
+pub fn open_with_default_app(path: &Path) -> Result<(), String> {
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+        let result = file_url(path).and_then(|url| {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let opened: BOOL = msg_send![workspace, openURL:url];
+            if opened != NO {
+                Ok(())
+            } else {
+                Err(format!("`NSWorkspace` failed to open {:?}", path))
+            }
+        });
+        let _: () = msg_send![pool, drain];
+        result
+    }
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "surface")]
+
+use os::macos::WindowExt;
+use {CreationError, PhysicalSize};
+use surface::NativeSurface;
+use platform::platform::Window;
+
+pub struct Surface {
+    native: NativeSurface,
+}
+
+impl Surface {
+    pub fn new(window: &Window) -> Result<Surface, CreationError> {
+        Ok(Surface {
+            native: NativeSurface::MacOS { nsview: window.get_nsview() },
+        })
+    }
+
+    pub fn native(&self) -> NativeSurface {
+        self.native
+    }
+
+    pub fn resize(&self, _size: PhysicalSize) {
+        // N/A: the context is updated by sending it `update`, which is up to its owner.
+    }
+}
@@ -0,0 +1,234 @@
+#![cfg(feature = "tray")]
+
+use std::ptr;
+use std::os::raw::c_void;
+use std::sync::Weak;
+
+use cocoa::appkit::{self, NSEvent};
+use cocoa::base::{id, nil, NO, YES};
+use cocoa::foundation::{NSInteger, NSSize, NSString};
+use core_graphics::base::CGFloat;
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+
+use {CreationError, Event, Icon, IconImage, MouseButton};
+use tray::{TrayEvent, TrayIconAttributes};
+use platform::platform::events_loop::{EventsLoop, Shared};
+use platform::platform::ffi;
+use platform::platform::util;
+use platform::platform::TrayIconId;
+use platform::platform::window::IdRef;
+
+// Stored in the target's "winitState" ivar, and free'd in `dealloc`.
+struct TargetState {
+    shared: Weak<Shared>,
+    tray_id: TrayIconId,
+}
+
+pub struct TrayIcon {
+    status_item: IdRef,
+    // Receives the status item button's action. Controls don't retain their target, so we do.
+    _target: IdRef,
+}
+
+impl TrayIcon {
+    pub fn new(
+        events_loop: &EventsLoop,
+        attributes: TrayIconAttributes,
+    ) -> Result<TrayIcon, CreationError> {
+        unsafe {
+            let status_bar: id = msg_send![class!(NSStatusBar), systemStatusBar];
+            let status_item: id = msg_send![
+                status_bar,
+                statusItemWithLength:ffi::NSVariableStatusItemLength
+            ];
+            let status_item = IdRef::retain(status_item).non_nil().ok_or_else(|| {
+                CreationError::OsError("Couldn't create status item".to_owned())
+            })?;
+
+            let tray_id = TrayIconId(*status_item as usize);
+            let state = Box::new(TargetState {
+                shared: ::std::sync::Arc::downgrade(&events_loop.shared),
+                tray_id,
+            });
+            let state_ptr = Box::into_raw(state) as *mut c_void;
+            let target: id = msg_send![TARGET_CLASS.0, alloc];
+            let target = IdRef::new(msg_send![target, initWithWinit:state_ptr]);
+
+            let button: id = msg_send![*status_item, button];
+            let _: () = msg_send![button, setTarget:*target];
+            let _: () = msg_send![button, setAction:sel!(trayClicked:)];
+            let mask = appkit::NSEventMask::NSLeftMouseUpMask
+                | appkit::NSEventMask::NSRightMouseUpMask
+                | appkit::NSEventMask::NSOtherMouseUpMask;
+            let _: NSInteger = msg_send![button, sendActionOn:mask.bits()];
+
+            let tray = TrayIcon { status_item, _target: target };
+            tray.set_icon(attributes.icon);
+            if let Some(tooltip) = attributes.tooltip {
+                tray.set_tooltip(&tooltip);
+            }
+            Ok(tray)
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId(*self.status_item as usize)
+    }
+
+    pub fn set_icon(&self, icon: Option<Icon>) {
+        unsafe {
+            let button: id = msg_send![*self.status_item, button];
+            match icon {
+                Some(icon) => {
+                    let image = ns_image_from_icon(icon);
+                    let _: () = msg_send![button, setImage:*image];
+                },
+                None => {
+                    let _: () = msg_send![button, setImage:nil];
+                },
+            }
+        }
+    }
+
+    pub fn set_tooltip(&self, tooltip: &str) {
+        unsafe {
+            let button: id = msg_send![*self.status_item, button];
+            let tooltip = IdRef::new(NSString::alloc(nil).init_str(tooltip));
+            let _: () = msg_send![button, setToolTip:*tooltip];
+        }
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        unsafe {
+            let button: id = msg_send![*self.status_item, button];
+            let _: () = msg_send![button, setTarget:nil];
+            let status_bar: id = msg_send![class!(NSStatusBar), systemStatusBar];
+            let _: () = msg_send![status_bar, removeStatusItem:*self.status_item];
+        }
+    }
+}
+
+unsafe fn ns_bitmap_from_icon_image(icon_image: &IconImage) -> IdRef {
+    let color_space = IdRef::new(NSString::alloc(nil).init_str("NSDeviceRGBColorSpace"));
+    let bitmap: id = msg_send![class!(NSBitmapImageRep), alloc];
+    let bitmap = IdRef::new(msg_send![
+        bitmap,
+        initWithBitmapDataPlanes:ptr::null_mut::<*mut u8>()
+        pixelsWide:icon_image.width as NSInteger
+        pixelsHigh:icon_image.height as NSInteger
+        bitsPerSample:8 as NSInteger
+        samplesPerPixel:4 as NSInteger
+        hasAlpha:YES
+        isPlanar:NO
+        colorSpaceName:*color_space
+        bitmapFormat:ffi::NSBitmapFormatAlphaNonpremultiplied
+        bytesPerRow:(icon_image.width * 4) as NSInteger
+        bitsPerPixel:32 as NSInteger
+    ]);
+    let data: *mut u8 = msg_send![*bitmap, bitmapData];
+    ptr::copy_nonoverlapping(icon_image.rgba.as_ptr(), data, icon_image.rgba.len());
+    bitmap
+}
+
+// Builds an image sized to fit in the menu bar, preserving the icon's aspect ratio. Every image of
+// the icon is added as a representation, so that AppKit can pick one for the screen's DPI factor.
+unsafe fn ns_image_from_icon(icon: Icon) -> IdRef {
+    let status_bar: id = msg_send![class!(NSStatusBar), systemStatusBar];
+    let thickness: CGFloat = msg_send![status_bar, thickness];
+    // Leave a bit of padding above and below, like the system's own status items do.
+    let height = thickness - 4.0;
+    let largest = icon.images.last().unwrap();
+    let width = height * largest.width as CGFloat / largest.height as CGFloat;
+
+    let image: id = msg_send![class!(NSImage), alloc];
+    let image = IdRef::new(msg_send![image, initWithSize:NSSize::new(width, height)]);
+    for icon_image in &icon.images {
+        let bitmap = ns_bitmap_from_icon_image(icon_image);
+        // Every representation covers the same area, only with more or fewer pixels.
+        let _: () = msg_send![*bitmap, setSize:NSSize::new(width, height)];
+        let _: () = msg_send![*image, addRepresentation:*bitmap];
+    }
+    image
+}
+
+struct TargetClass(*const Class);
+unsafe impl Send for TargetClass {}
+unsafe impl Sync for TargetClass {}
+
+lazy_static! {
+    static ref TARGET_CLASS: TargetClass = unsafe {
+        let superclass = class!(NSObject);
+        let mut decl = ClassDecl::new("WinitTrayTarget", superclass).unwrap();
+        decl.add_method(sel!(dealloc), dealloc as extern fn(&Object, Sel));
+        decl.add_method(
+            sel!(initWithWinit:),
+            init_with_winit as extern fn(&Object, Sel, *mut c_void) -> id,
+        );
+        decl.add_method(sel!(trayClicked:), tray_clicked as extern fn(&Object, Sel, id));
+        decl.add_ivar::<*mut c_void>("winitState");
+        TargetClass(decl.register())
+    };
+}
+
+extern fn dealloc(this: &Object, _sel: Sel) {
+    util::catch_exception("dealloc", || {
+        unsafe {
+            let state: *mut c_void = *this.get_ivar("winitState");
+            Box::from_raw(state as *mut TargetState);
+            let _: () = msg_send![super(this, class!(NSObject)), dealloc];
+        }
+    })
+}
+
+extern fn init_with_winit(this: &Object, _sel: Sel, state: *mut c_void) -> id {
+    util::catch_exception("init_with_winit", || {
+        unsafe {
+            let this: id = msg_send![this, init];
+            if this != nil {
+                (*this).set_ivar("winitState", state);
+            }
+            this
+        }
+    })
+}
+
+extern fn tray_clicked(this: &Object, _sel: Sel, _sender: id) {
+    util::catch_exception("tray_clicked", || {
+        unsafe {
+            let state: *mut c_void = *this.get_ivar("winitState");
+            let state = &*(state as *const TargetState);
+
+            let event: id = msg_send![appkit::NSApp(), currentEvent];
+            if event == nil {
+                return;
+            }
+            let button = match event.eventType() {
+                appkit::NSLeftMouseUp => MouseButton::Left,
+                appkit::NSRightMouseUp => MouseButton::Right,
+                appkit::NSOtherMouseUp => match event.buttonNumber() {
+                    2 => MouseButton::Middle,
+                    n => MouseButton::Other(n as u8),
+                },
+                _ => return,
+            };
+
+            if let Some(shared) = state.shared.upgrade() {
+                let tray_id = ::tray::TrayIconId(state.tray_id);
+                shared.call_user_callback_with_event_or_store_in_pending(Event::TrayEvent {
+                    tray_id,
+                    event: TrayEvent::Click(button),
+                });
+                if event.clickCount() == 2 {
+                    shared.call_user_callback_with_event_or_store_in_pending(Event::TrayEvent {
+                        tray_id,
+                        event: TrayEvent::DoubleClick(button),
+                    });
+                }
+            }
+        }
+    })
+}
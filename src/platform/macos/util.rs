@@ -1,7 +1,12 @@
-use cocoa::appkit::NSWindowStyleMask;
-use cocoa::base::{id, nil};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::process;
+
+use cocoa::appkit::{NSApp, NSWindowStyleMask};
+use cocoa::base::{id, nil, BOOL, YES};
 use cocoa::foundation::{NSRect, NSUInteger};
 use core_graphics::display::CGDisplay;
+use objc_exception;
 
 use platform::platform::ffi;
 use platform::platform::window::IdRef;
@@ -18,6 +23,34 @@ pub fn bottom_left_to_top_left(rect: NSRect) -> f64 {
     CGDisplay::main().pixels_high() as f64 - (rect.origin.y + rect.size.height)
 }
 
+/// Returns the application's visible windows from front to back.
+pub unsafe fn get_visible_windows_front_to_back() -> Vec<id> {
+    let ordered_windows: id = msg_send![NSApp(), orderedWindows];
+    let count: NSUInteger = msg_send![ordered_windows, count];
+    (0..count)
+        .map(|index| -> id { msg_send![ordered_windows, objectAtIndex:index] })
+        .filter(|&window| {
+            let visible: BOOL = msg_send![window, isVisible];
+            visible == YES
+        })
+        .collect()
+}
+
+/// Returns the number of tabs in the tab group `window` is part of, or 0 if it's not part of one.
+pub unsafe fn get_tabbed_window_count(window: id) -> usize {
+    // Tabs were added in macOS 10.12.
+    let supported: BOOL = msg_send![window, respondsToSelector:sel!(tabbedWindows)];
+    if supported != YES {
+        return 0;
+    }
+    let tabbed_windows: id = msg_send![window, tabbedWindows];
+    if tabbed_windows == nil {
+        return 0;
+    }
+    let count: NSUInteger = msg_send![tabbed_windows, count];
+    count as usize
+}
+
 pub unsafe fn set_style_mask(window: id, view: id, mask: NSWindowStyleMask) {
     use cocoa::appkit::NSWindow;
     window.setStyleMask_(mask);
@@ -31,6 +64,52 @@ pub unsafe fn create_input_context(view: id) -> IdRef {
     IdRef::new(input_context)
 }
 
+// An Objective-C exception unwinding through a Rust frame aborts the process without telling us
+// anything about where it came from, so every method we register with the runtime runs its body
+// through this. Any exception raised is logged and written to stderr along with the method name
+// and the exception's reason before aborting, since unwinding out of these methods would cross
+// back into Objective-C.
+pub fn catch_exception<F, R>(method: &str, f: F) -> R
+    where F: FnOnce() -> R
+{
+    match unsafe { objc_exception::try(f) } {
+        Ok(value) => value,
+        Err(exception) => {
+            let (name, reason) = unsafe { describe_exception(exception as id) };
+            let message = format!("Objective-C exception in `{}`: {}: {}", method, name, reason);
+            error!("{}", message);
+            // Without a logger, the message would go nowhere.
+            eprintln!("[winit] {}", message);
+            process::abort();
+        },
+    }
+}
+
+unsafe fn describe_exception(exception: id) -> (String, String) {
+    unsafe fn to_string(string: id) -> String {
+        if string == nil {
+            return "<unknown>".to_owned();
+        }
+        let bytes: *const c_char = msg_send![string, UTF8String];
+        CStr::from_ptr(bytes).to_string_lossy().into_owned()
+    }
+    // `@throw` accepts any object, so this isn't necessarily an `NSException`.
+    let is_ns_exception: bool = exception != nil
+        && msg_send![exception, isKindOfClass:class!(NSException)];
+    if is_ns_exception {
+        let name: id = msg_send![exception, name];
+        let reason: id = msg_send![exception, reason];
+        (to_string(name), to_string(reason))
+    } else {
+        let description: id = if exception != nil {
+            msg_send![exception, description]
+        } else {
+            nil
+        };
+        ("non-NSException object".to_owned(), to_string(description))
+    }
+}
+
 #[allow(dead_code)]
 pub unsafe fn open_emoji_picker() {
     let app: id = msg_send![class!(NSApplication), sharedApplication];
@@ -1,19 +1,20 @@
 // This is a pretty close port of the implementation in GLFW:
 // https://github.com/glfw/glfw/blob/7ef34eb06de54dd9186d3d21a401b2ef819b59e7/src/cocoa_window.m
 
-use std::{slice, str};
+use std::{cmp, slice, str};
 use std::boxed::Box;
 use std::collections::VecDeque;
 use std::os::raw::*;
 use std::sync::Weak;
 
-use cocoa::base::{id, nil};
+use cocoa::base::{id, nil, YES};
 use cocoa::appkit::{NSEvent, NSView, NSWindow};
 use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString, NSUInteger};
 use objc::declare::ClassDecl;
 use objc::runtime::{Class, Object, Protocol, Sel, BOOL};
 
-use {ElementState, Event, KeyboardInput, MouseButton, WindowEvent, WindowId};
+use accessibility::AccessibilityAdapter;
+use {ElementState, Event, HitTestCallback, HitTestResult, KeyboardInput, LogicalPosition, MouseButton, Rect, WindowEvent, WindowId};
 use platform::platform::events_loop::{DEVICE_ID, event_mods, Shared, to_virtual_key_code};
 use platform::platform::util;
 use platform::platform::ffi::*;
@@ -25,6 +26,11 @@ struct ViewState {
     ime_spot: Option<(f64, f64)>,
     raw_characters: Option<String>,
     last_insert: Option<String>,
+    hit_test: Option<HitTestCallback>,
+    // Set by `set_input_region`. Clicks outside of it aren't handled by the view.
+    input_region: Option<Vec<Rect>>,
+    accessibility_adapter: Option<AccessibilityAdapter>,
+    accessibility_activated: bool,
 }
 
 pub fn new_view(window: id, shared: Weak<Shared>) -> IdRef {
@@ -34,6 +40,10 @@ pub fn new_view(window: id, shared: Weak<Shared>) -> IdRef {
         ime_spot: None,
         raw_characters: None,
         last_insert: None,
+        hit_test: None,
+        input_region: None,
+        accessibility_adapter: None,
+        accessibility_activated: false,
     };
     unsafe {
         // This is free'd in `dealloc`
@@ -58,6 +68,45 @@ pub fn set_ime_spot(view: id, input_context: id, x: f64, y: f64) {
     }
 }
 
+pub fn set_hit_test_callback(view: id, callback: Option<HitTestCallback>) {
+    unsafe {
+        let state_ptr: *mut c_void = *(*view).get_mut_ivar("winitState");
+        let state = &mut *(state_ptr as *mut ViewState);
+        state.hit_test = callback;
+    }
+}
+
+pub fn set_input_region(view: id, region: Option<Vec<Rect>>) {
+    unsafe {
+        let state_ptr: *mut c_void = *(*view).get_mut_ivar("winitState");
+        let state = &mut *(state_ptr as *mut ViewState);
+        state.input_region = region;
+    }
+}
+
+// Sends `Resized` whenever the frame of the view changes. Views in a foreign parent are resized
+// by it, and the window delegate never hears about it, since their `NSWindow` stays hidden.
+pub fn observe_frame_changes(view: id) {
+    unsafe {
+        let _: () = msg_send![view, setPostsFrameChangedNotifications:YES];
+        let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+        let name = IdRef::new(NSString::alloc(nil).init_str("NSViewFrameDidChangeNotification"));
+        let _: () = msg_send![center,
+            addObserver:view
+            selector:sel!(frameDidChange:)
+            name:*name
+            object:view];
+    }
+}
+
+pub fn set_accessibility_adapter(view: id, adapter: Option<AccessibilityAdapter>) {
+    unsafe {
+        let state_ptr: *mut c_void = *(*view).get_mut_ivar("winitState");
+        let state = &mut *(state_ptr as *mut ViewState);
+        state.accessibility_adapter = adapter;
+    }
+}
+
 struct ViewClass(*const Class);
 unsafe impl Send for ViewClass {}
 unsafe impl Sync for ViewClass {}
@@ -122,6 +171,12 @@ lazy_static! {
         decl.add_method(sel!(mouseDragged:), mouse_dragged as extern fn(&Object, Sel, id));
         decl.add_method(sel!(rightMouseDragged:), right_mouse_dragged as extern fn(&Object, Sel, id));
         decl.add_method(sel!(otherMouseDragged:), other_mouse_dragged as extern fn(&Object, Sel, id));
+        decl.add_method(
+            sel!(accessibilityChildren),
+            accessibility_children as extern fn(&Object, Sel) -> id,
+        );
+        decl.add_method(sel!(hitTest:), hit_test as extern fn(&Object, Sel, NSPoint) -> id);
+        decl.add_method(sel!(frameDidChange:), frame_did_change as extern fn(&Object, Sel, id));
         decl.add_ivar::<*mut c_void>("winitState");
         decl.add_ivar::<id>("markedText");
         let protocol = Protocol::get("NSTextInputClient").unwrap();
@@ -131,52 +186,90 @@ lazy_static! {
 }
 
 extern fn dealloc(this: &Object, _sel: Sel) {
-    unsafe {
-        let state: *mut c_void = *this.get_ivar("winitState");
-        let marked_text: id = *this.get_ivar("markedText");
-        let _: () = msg_send![marked_text, release];
-        Box::from_raw(state as *mut ViewState);
-    }
+    util::catch_exception("dealloc", || {
+        unsafe {
+            let state: *mut c_void = *this.get_ivar("winitState");
+            let marked_text: id = *this.get_ivar("markedText");
+            let _: () = msg_send![marked_text, release];
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![center, removeObserver:this];
+            Box::from_raw(state as *mut ViewState);
+        }
+    })
 }
 
 extern fn init_with_winit(this: &Object, _sel: Sel, state: *mut c_void) -> id {
-    unsafe {
-        let this: id = msg_send![this, init];
-        if this != nil {
-            (*this).set_ivar("winitState", state);
-            let marked_text = <id as NSMutableAttributedString>::init(
-                NSMutableAttributedString::alloc(nil),
-            );
-            (*this).set_ivar("markedText", marked_text);
+    util::catch_exception("init_with_winit", || {
+        unsafe {
+            let this: id = msg_send![this, init];
+            if this != nil {
+                (*this).set_ivar("winitState", state);
+                let marked_text = <id as NSMutableAttributedString>::init(
+                    NSMutableAttributedString::alloc(nil),
+                );
+                (*this).set_ivar("markedText", marked_text);
+            }
+            this
         }
-        this
-    }
+    })
 }
 
-extern fn has_marked_text(this: &Object, _sel: Sel) -> BOOL {
-    //println!("hasMarkedText");
+extern fn frame_did_change(this: &Object, _sel: Sel, _notification: id) {
+    util::catch_exception("frame_did_change", || {
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+            let bounds = NSView::bounds(this as *const _ as id);
+            let event = Event::WindowEvent {
+                window_id: WindowId(get_window_id(state.window)),
+                event: WindowEvent::Resized(
+                    (bounds.size.width as f64, bounds.size.height as f64).into(),
+                ),
+            };
+            if let Some(shared) = state.shared.upgrade() {
+                shared.call_user_callback_with_event_or_store_in_pending(event);
+            }
+        }
+    })
+}
+
+// Whether the input context is composing text, in which case it consumes key events.
+fn is_composing(this: &Object) -> bool {
     unsafe {
         let marked_text: id = *this.get_ivar("markedText");
-        (marked_text.length() > 0) as i8
+        marked_text.length() > 0
     }
 }
 
+extern fn has_marked_text(this: &Object, _sel: Sel) -> BOOL {
+    util::catch_exception("has_marked_text", || {
+        //println!("hasMarkedText");
+        unsafe {
+            is_composing(this) as i8
+        }
+    })
+}
+
 extern fn marked_range(this: &Object, _sel: Sel) -> NSRange {
-    //println!("markedRange");
-    unsafe {
-        let marked_text: id = *this.get_ivar("markedText");
-        let length = marked_text.length();
-        if length > 0 {
-            NSRange::new(0, length - 1)
-        } else {
-            util::EMPTY_RANGE
+    util::catch_exception("marked_range", || {
+        //println!("markedRange");
+        unsafe {
+            let marked_text: id = *this.get_ivar("markedText");
+            let length = marked_text.length();
+            if length > 0 {
+                NSRange::new(0, length - 1)
+            } else {
+                util::EMPTY_RANGE
+            }
         }
-    }
+    })
 }
 
 extern fn selected_range(_this: &Object, _sel: Sel) -> NSRange {
-    //println!("selectedRange");
-    util::EMPTY_RANGE
+    util::catch_exception("selected_range", || {
+        //println!("selectedRange");
+        util::EMPTY_RANGE
+    })
 }
 
 extern fn set_marked_text(
@@ -186,35 +279,41 @@ extern fn set_marked_text(
     _selected_range: NSRange,
     _replacement_range: NSRange,
 ) {
-    //println!("setMarkedText");
-    unsafe {
-        let marked_text_ref: &mut id = this.get_mut_ivar("markedText");
-        let _: () = msg_send![(*marked_text_ref), release];
-        let marked_text = NSMutableAttributedString::alloc(nil);
-        let has_attr = msg_send![string, isKindOfClass:class!(NSAttributedString)];
-        if has_attr {
-            marked_text.initWithAttributedString(string);
-        } else {
-            marked_text.initWithString(string);
-        };
-        *marked_text_ref = marked_text;
-    }
+    util::catch_exception("set_marked_text", || {
+        //println!("setMarkedText");
+        unsafe {
+            let marked_text_ref: &mut id = this.get_mut_ivar("markedText");
+            let _: () = msg_send![(*marked_text_ref), release];
+            let marked_text = NSMutableAttributedString::alloc(nil);
+            let has_attr = msg_send![string, isKindOfClass:class!(NSAttributedString)];
+            if has_attr {
+                marked_text.initWithAttributedString(string);
+            } else {
+                marked_text.initWithString(string);
+            };
+            *marked_text_ref = marked_text;
+        }
+    })
 }
 
 extern fn unmark_text(this: &Object, _sel: Sel) {
-    //println!("unmarkText");
-    unsafe {
-        let marked_text: id = *this.get_ivar("markedText");
-        let mutable_string = marked_text.mutableString();
-        let _: () = msg_send![mutable_string, setString:""];
-        let input_context: id = msg_send![this, inputContext];
-        let _: () = msg_send![input_context, discardMarkedText];
-    }
+    util::catch_exception("unmark_text", || {
+        //println!("unmarkText");
+        unsafe {
+            let marked_text: id = *this.get_ivar("markedText");
+            let mutable_string = marked_text.mutableString();
+            let _: () = msg_send![mutable_string, setString:""];
+            let input_context: id = msg_send![this, inputContext];
+            let _: () = msg_send![input_context, discardMarkedText];
+        }
+    })
 }
 
 extern fn valid_attributes_for_marked_text(_this: &Object, _sel: Sel) -> id {
-    //println!("validAttributesForMarkedText");
-    unsafe { msg_send![class!(NSArray), array] }
+    util::catch_exception("valid_attributes_for_marked_text", || {
+        //println!("validAttributesForMarkedText");
+        unsafe { msg_send![class!(NSArray), array] }
+    })
 }
 
 extern fn attributed_substring_for_proposed_range(
@@ -223,13 +322,17 @@ extern fn attributed_substring_for_proposed_range(
     _range: NSRange,
     _actual_range: *mut c_void, // *mut NSRange
 ) -> id {
-    //println!("attributedSubstringForProposedRange");
-    nil
+    util::catch_exception("attributed_substring_for_proposed_range", || {
+        //println!("attributedSubstringForProposedRange");
+        nil
+    })
 }
 
 extern fn character_index_for_point(_this: &Object, _sel: Sel, _point: NSPoint) -> NSUInteger {
-    //println!("characterIndexForPoint");
-    0
+    util::catch_exception("character_index_for_point", || {
+        //println!("characterIndexForPoint");
+        0
+    })
 }
 
 extern fn first_rect_for_character_range(
@@ -238,228 +341,278 @@ extern fn first_rect_for_character_range(
     _range: NSRange,
     _actual_range: *mut c_void, // *mut NSRange
 ) -> NSRect {
-    //println!("firstRectForCharacterRange");
-    unsafe {
-        let state_ptr: *mut c_void = *this.get_ivar("winitState");
-        let state = &mut *(state_ptr as *mut ViewState);
-        let (x, y) = state.ime_spot.unwrap_or_else(|| {
-            let content_rect = NSWindow::contentRectForFrameRect_(
-                state.window,
-                NSWindow::frame(state.window),
-            );
-            let x = content_rect.origin.x;
-            let y = util::bottom_left_to_top_left(content_rect);
-            (x, y)
-        });
-
-        NSRect::new(
-            NSPoint::new(x as _, y as _),
-            NSSize::new(0.0, 0.0),
-        )
-    }
+    util::catch_exception("first_rect_for_character_range", || {
+        //println!("firstRectForCharacterRange");
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+            let (x, y) = state.ime_spot.unwrap_or_else(|| {
+                let content_rect = NSWindow::contentRectForFrameRect_(
+                    state.window,
+                    NSWindow::frame(state.window),
+                );
+                let x = content_rect.origin.x;
+                let y = util::bottom_left_to_top_left(content_rect);
+                (x, y)
+            });
+
+            NSRect::new(
+                NSPoint::new(x as _, y as _),
+                NSSize::new(0.0, 0.0),
+            )
+        }
+    })
 }
 
 extern fn insert_text(this: &Object, _sel: Sel, string: id, _replacement_range: NSRange) {
-    //println!("insertText");
-    unsafe {
-        let state_ptr: *mut c_void = *this.get_ivar("winitState");
-        let state = &mut *(state_ptr as *mut ViewState);
+    util::catch_exception("insert_text", || {
+        //println!("insertText");
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+
+            let has_attr = msg_send![string, isKindOfClass:class!(NSAttributedString)];
+            let characters = if has_attr {
+                // This is a *mut NSAttributedString
+                msg_send![string, string]
+            } else {
+                // This is already a *mut NSString
+                string
+            };
 
-        let has_attr = msg_send![string, isKindOfClass:class!(NSAttributedString)];
-        let characters = if has_attr {
-            // This is a *mut NSAttributedString
-            msg_send![string, string]
-        } else {
-            // This is already a *mut NSString
-            string
-        };
+            let slice = slice::from_raw_parts(
+                characters.UTF8String() as *const c_uchar,
+                characters.len(),
+            );
+            let string = str::from_utf8_unchecked(slice);
+            state.last_insert = Some(string.to_owned());
 
-        let slice = slice::from_raw_parts(
-            characters.UTF8String() as *const c_uchar,
-            characters.len(),
-        );
-        let string = str::from_utf8_unchecked(slice);
-        state.last_insert = Some(string.to_owned());
+            // We don't need this now, but it's here if that changes.
+            //let event: id = msg_send![class!(NSApp), currentEvent];
 
-        // We don't need this now, but it's here if that changes.
-        //let event: id = msg_send![class!(NSApp), currentEvent];
+            let mut events = VecDeque::with_capacity(characters.len());
+            for character in string.chars() {
+                events.push_back(Event::WindowEvent {
+                    window_id: WindowId(get_window_id(state.window)),
+                    event: WindowEvent::ReceivedCharacter(character),
+                });
+            }
 
-        let mut events = VecDeque::with_capacity(characters.len());
-        for character in string.chars() {
-            events.push_back(Event::WindowEvent {
-                window_id: WindowId(get_window_id(state.window)),
-                event: WindowEvent::ReceivedCharacter(character),
-            });
+            if let Some(shared) = state.shared.upgrade() {
+                shared.pending_events
+                    .lock()
+                    .unwrap()
+                    .append(&mut events);
+            }
         }
+    })
+}
+
+extern fn do_command_by_selector(this: &Object, _sel: Sel, command: Sel) {
+    util::catch_exception("do_command_by_selector", || {
+        //println!("doCommandBySelector");
+        // Basically, we're sent this message whenever a keyboard event that doesn't generate a "human readable" character
+        // happens, i.e. newlines, tabs, and Ctrl+C.
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+
+            let shared = if let Some(shared) = state.shared.upgrade() {
+                shared
+            } else {
+                return;
+            };
+
+            let mut events = VecDeque::with_capacity(1);
+            if command == sel!(insertNewline:) {
+                // The `else` condition would emit the same character, but I'm keeping this here both...
+                // 1) as a reminder for how `doCommandBySelector` works
+                // 2) to make our use of carriage return explicit
+                events.push_back(Event::WindowEvent {
+                    window_id: WindowId(get_window_id(state.window)),
+                    event: WindowEvent::ReceivedCharacter('\r'),
+                });
+            } else {
+                let raw_characters = state.raw_characters.take();
+                if let Some(raw_characters) = raw_characters {
+                    for character in raw_characters.chars() {
+                        events.push_back(Event::WindowEvent {
+                            window_id: WindowId(get_window_id(state.window)),
+                            event: WindowEvent::ReceivedCharacter(character),
+                        });
+                    }
+                }
+            };
 
-        if let Some(shared) = state.shared.upgrade() {
             shared.pending_events
                 .lock()
                 .unwrap()
                 .append(&mut events);
         }
-    }
-}
-
-extern fn do_command_by_selector(this: &Object, _sel: Sel, command: Sel) {
-    //println!("doCommandBySelector");
-    // Basically, we're sent this message whenever a keyboard event that doesn't generate a "human readable" character
-    // happens, i.e. newlines, tabs, and Ctrl+C.
-    unsafe {
-        let state_ptr: *mut c_void = *this.get_ivar("winitState");
-        let state = &mut *(state_ptr as *mut ViewState);
-
-        let shared = if let Some(shared) = state.shared.upgrade() {
-            shared
-        } else {
-            return;
-        };
-
-        let mut events = VecDeque::with_capacity(1);
-        if command == sel!(insertNewline:) {
-            // The `else` condition would emit the same character, but I'm keeping this here both...
-            // 1) as a reminder for how `doCommandBySelector` works
-            // 2) to make our use of carriage return explicit
-            events.push_back(Event::WindowEvent {
-                window_id: WindowId(get_window_id(state.window)),
-                event: WindowEvent::ReceivedCharacter('\r'),
-            });
-        } else {
-            let raw_characters = state.raw_characters.take();
-            if let Some(raw_characters) = raw_characters {
-                for character in raw_characters.chars() {
-                    events.push_back(Event::WindowEvent {
-                        window_id: WindowId(get_window_id(state.window)),
-                        event: WindowEvent::ReceivedCharacter(character),
-                    });
-                }
-            }
-        };
-
-        shared.pending_events
-            .lock()
-            .unwrap()
-            .append(&mut events);
-    }
+    })
 }
 
 extern fn key_down(this: &Object, _sel: Sel, event: id) {
-    //println!("keyDown");
-    unsafe {
-        let state_ptr: *mut c_void = *this.get_ivar("winitState");
-        let state = &mut *(state_ptr as *mut ViewState);
-        let window_id = WindowId(get_window_id(state.window));
-
-        let keycode: c_ushort = msg_send![event, keyCode];
-        let virtual_keycode = to_virtual_key_code(keycode);
-        let scancode = keycode as u32;
-        let is_repeat = msg_send![event, isARepeat];
-
-        let window_event = Event::WindowEvent {
-            window_id,
-            event: WindowEvent::KeyboardInput {
-                device_id: DEVICE_ID,
-                input: KeyboardInput {
-                    state: ElementState::Pressed,
-                    scancode,
-                    virtual_keycode,
-                    modifiers: event_mods(event),
-                },
-            },
-        };
-
-        state.raw_characters = {
-            let characters: id = msg_send![event, characters];
-            let slice = slice::from_raw_parts(
-                characters.UTF8String() as *const c_uchar,
-                characters.len(),
-            );
-            let string = str::from_utf8_unchecked(slice);
-            Some(string.to_owned())
-        };
-
-        if let Some(shared) = state.shared.upgrade() {
-            shared.pending_events
-                .lock()
-                .unwrap()
-                .push_back(window_event);
-            // Emit `ReceivedCharacter` for key repeats
-            if is_repeat && state.last_insert.is_some() {
-                let last_insert = state.last_insert.as_ref().unwrap();
-                for character in last_insert.chars() {
-                    let window_event = Event::WindowEvent {
-                        window_id,
-                        event: WindowEvent::ReceivedCharacter(character),
-                    };
-                    shared.pending_events
-                        .lock()
-                        .unwrap()
-                        .push_back(window_event);
+    util::catch_exception("key_down", || {
+        //println!("keyDown");
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+            let window_id = WindowId(get_window_id(state.window));
+
+            let keycode: c_ushort = msg_send![event, keyCode];
+            let virtual_keycode = to_virtual_key_code(keycode);
+            let scancode = keycode as u32;
+            let is_repeat = msg_send![event, isARepeat];
+            let was_composing = is_composing(this);
+
+            state.raw_characters = {
+                let characters: id = msg_send![event, characters];
+                let slice = slice::from_raw_parts(
+                    characters.UTF8String() as *const c_uchar,
+                    characters.len(),
+                );
+                let string = str::from_utf8_unchecked(slice);
+                Some(string.to_owned())
+            };
+            // Dead keys don't produce any characters by themselves.
+            let is_dead_key = state.raw_characters.as_ref().map_or(false, |characters| characters.is_empty());
+
+            if let Some(shared) = state.shared.upgrade() {
+                // We only know whether the input context used the key once it's been interpreted,
+                // but the key event still has to come before any characters it produced.
+                let index = shared.pending_events.lock().unwrap().len();
+                // Emit `ReceivedCharacter` for key repeats. While composing, repeats need to reach
+                // the input context like any other key, or it'd miss e.g. a held backspace.
+                if is_repeat && !was_composing && state.last_insert.is_some() {
+                    let last_insert = state.last_insert.as_ref().unwrap();
+                    for character in last_insert.chars() {
+                        let window_event = Event::WindowEvent {
+                            window_id,
+                            event: WindowEvent::ReceivedCharacter(character),
+                        };
+                        shared.pending_events
+                            .lock()
+                            .unwrap()
+                            .push_back(window_event);
+                    }
+                } else {
+                    // Some keys (and only *some*, with no known reason) don't trigger `insertText`, while others do...
+                    // So, we don't give repeats the opportunity to trigger that, since otherwise our hack will cause some
+                    // keys to generate twice as many characters.
+                    let array: id = msg_send![class!(NSArray), arrayWithObject:event];
+                    let (): _ = msg_send![this, interpretKeyEvents:array];
                 }
-            } else {
-                // Some keys (and only *some*, with no known reason) don't trigger `insertText`, while others do...
-                // So, we don't give repeats the opportunity to trigger that, since otherwise our hack will cause some
-                // keys to generate twice as many characters.
-                let array: id = msg_send![class!(NSArray), arrayWithObject:event];
-                let (): _ = msg_send![this, interpretKeyEvents:array];
+
+                // This includes both the key that starts a composition and the one that ends it.
+                let is_composing = was_composing || is_composing(this);
+                let mut pending_events = shared.pending_events.lock().unwrap();
+                let index = cmp::min(index, pending_events.len());
+                // The characters the key produced are the ones that were just queued, unless the
+                // input context used the key, in which case they were committed by it.
+                let text = if is_composing {
+                    None
+                } else {
+                    let text: String = pending_events
+                        .iter()
+                        .skip(index)
+                        .filter_map(|event| match *event {
+                            Event::WindowEvent {
+                                window_id: id,
+                                event: WindowEvent::ReceivedCharacter(character),
+                            } if id == window_id => Some(character),
+                            _ => None,
+                        })
+                        .collect();
+                    if text.is_empty() { None } else { Some(text) }
+                };
+                let window_event = Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::KeyboardInput {
+                        device_id: DEVICE_ID,
+                        input: KeyboardInput {
+                            state: ElementState::Pressed,
+                            scancode,
+                            virtual_keycode,
+                            modifiers: event_mods(event),
+                            is_composing,
+                            is_synthetic: false,
+                            is_repeat,
+                            text,
+                            is_dead_key,
+                        },
+                    },
+                };
+                pending_events.insert(index, window_event);
             }
         }
-    }
+    })
 }
 
 extern fn key_up(this: &Object, _sel: Sel, event: id) {
-    //println!("keyUp");
-    unsafe {
-        let state_ptr: *mut c_void = *this.get_ivar("winitState");
-        let state = &mut *(state_ptr as *mut ViewState);
-
-        state.last_insert = None;
-
-        let keycode: c_ushort = msg_send![event, keyCode];
-        let virtual_keycode = to_virtual_key_code(keycode);
-        let scancode = keycode as u32;
-        let window_event = Event::WindowEvent {
-            window_id: WindowId(get_window_id(state.window)),
-            event: WindowEvent::KeyboardInput {
-                device_id: DEVICE_ID,
-                input: KeyboardInput {
-                    state: ElementState::Released,
-                    scancode,
-                    virtual_keycode,
-                    modifiers: event_mods(event),
+    util::catch_exception("key_up", || {
+        //println!("keyUp");
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &mut *(state_ptr as *mut ViewState);
+
+            state.last_insert = None;
+
+            let keycode: c_ushort = msg_send![event, keyCode];
+            let virtual_keycode = to_virtual_key_code(keycode);
+            let scancode = keycode as u32;
+            let window_event = Event::WindowEvent {
+                window_id: WindowId(get_window_id(state.window)),
+                event: WindowEvent::KeyboardInput {
+                    device_id: DEVICE_ID,
+                    input: KeyboardInput {
+                        state: ElementState::Released,
+                        scancode,
+                        virtual_keycode,
+                        modifiers: event_mods(event),
+                        is_composing: is_composing(this),
+                        is_synthetic: false,
+                        is_repeat: false,
+                        text: None,
+                        is_dead_key: false,
+                    },
                 },
-            },
-        };
+            };
 
-        if let Some(shared) = state.shared.upgrade() {
-            shared.pending_events
-                .lock()
-                .unwrap()
-                .push_back(window_event);
+            if let Some(shared) = state.shared.upgrade() {
+                shared.pending_events
+                    .lock()
+                    .unwrap()
+                    .push_back(window_event);
+            }
         }
-    }
+    })
 }
 
 extern fn insert_tab(this: &Object, _sel: Sel, _sender: id) {
-    unsafe {
-        let window: id = msg_send![this, window];
-        let first_responder: id = msg_send![window, firstResponder];
-        let this_ptr = this as *const _ as *mut _;
-        if first_responder == this_ptr {
-            let (): _ = msg_send![window, selectNextKeyView:this];
+    util::catch_exception("insert_tab", || {
+        unsafe {
+            let window: id = msg_send![this, window];
+            let first_responder: id = msg_send![window, firstResponder];
+            let this_ptr = this as *const _ as *mut _;
+            if first_responder == this_ptr {
+                let (): _ = msg_send![window, selectNextKeyView:this];
+            }
         }
-    }
+    })
 }
 
 extern fn insert_back_tab(this: &Object, _sel: Sel, _sender: id) {
-    unsafe {
-        let window: id = msg_send![this, window];
-        let first_responder: id = msg_send![window, firstResponder];
-        let this_ptr = this as *const _ as *mut _;
-        if first_responder == this_ptr {
-            let (): _ = msg_send![window, selectPreviousKeyView:this];
+    util::catch_exception("insert_back_tab", || {
+        unsafe {
+            let window: id = msg_send![this, window];
+            let first_responder: id = msg_send![window, firstResponder];
+            let this_ptr = this as *const _ as *mut _;
+            if first_responder == this_ptr {
+                let (): _ = msg_send![window, selectPreviousKeyView:this];
+            }
         }
-    }
+    })
 }
 
 fn mouse_click(this: &Object, event: id, button: MouseButton, button_state: ElementState) {
@@ -486,28 +639,102 @@ fn mouse_click(this: &Object, event: id, button: MouseButton, button_state: Elem
     }
 }
 
+// Ignores the clicks that land outside of the input region.
+extern fn hit_test(this: &Object, _sel: Sel, point: NSPoint) -> id {
+    util::catch_exception("hit_test", || {
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let state = &*(state_ptr as *mut ViewState);
+
+            if let Some(ref region) = state.input_region {
+                // `point` is in the coordinate system of the superview.
+                let view: id = this as *const _ as *mut _;
+                let superview: id = msg_send![view, superview];
+                let view_point = view.convertPoint_fromView_(point, superview);
+                let view_rect = NSView::frame(view);
+                let x = view_point.x as f64;
+                let y = view_rect.size.height as f64 - view_point.y as f64;
+                let inside = region.iter().any(|rect| {
+                    x >= rect.position.x && x < rect.position.x + rect.size.width
+                        && y >= rect.position.y && y < rect.position.y + rect.size.height
+                });
+                if !inside {
+                    return nil;
+                }
+            }
+
+            msg_send![super(this, class!(NSView)), hitTest:point]
+        }
+    })
+}
+
+// Starts dragging the window if the hit-test callback reports that the click landed on a caption.
+fn hit_test_drag(this: &Object, event: id) -> bool {
+    unsafe {
+        let state_ptr: *mut c_void = *this.get_ivar("winitState");
+        let state = &mut *(state_ptr as *mut ViewState);
+
+        let hit_test = match state.hit_test {
+            Some(ref hit_test) => hit_test.clone(),
+            None => return false,
+        };
+
+        let view: id = this as *const _ as *mut _;
+        let window_point = event.locationInWindow();
+        let view_point = view.convertPoint_fromView_(window_point, nil);
+        let view_rect = NSView::frame(view);
+        let x = view_point.x as f64;
+        let y = view_rect.size.height as f64 - view_point.y as f64;
+        let dpi_factor = NSWindow::backingScaleFactor(state.window) as f64;
+
+        match hit_test(LogicalPosition::new(x, y).to_physical(dpi_factor)) {
+            HitTestResult::Caption => {
+                let _: () = msg_send![state.window, performWindowDragWithEvent:event];
+                true
+            },
+            // Resizing borderless windows isn't possible without reimplementing it ourselves.
+            _ => false,
+        }
+    }
+}
+
 extern fn mouse_down(this: &Object, _sel: Sel, event: id) {
-    mouse_click(this, event, MouseButton::Left, ElementState::Pressed);
+    util::catch_exception("mouse_down", || {
+        if hit_test_drag(this, event) {
+            return;
+        }
+        mouse_click(this, event, MouseButton::Left, ElementState::Pressed);
+    })
 }
 
 extern fn mouse_up(this: &Object, _sel: Sel, event: id) {
-    mouse_click(this, event, MouseButton::Left, ElementState::Released);
+    util::catch_exception("mouse_up", || {
+        mouse_click(this, event, MouseButton::Left, ElementState::Released);
+    })
 }
 
 extern fn right_mouse_down(this: &Object, _sel: Sel, event: id) {
-    mouse_click(this, event, MouseButton::Right, ElementState::Pressed);
+    util::catch_exception("right_mouse_down", || {
+        mouse_click(this, event, MouseButton::Right, ElementState::Pressed);
+    })
 }
 
 extern fn right_mouse_up(this: &Object, _sel: Sel, event: id) {
-    mouse_click(this, event, MouseButton::Right, ElementState::Released);
+    util::catch_exception("right_mouse_up", || {
+        mouse_click(this, event, MouseButton::Right, ElementState::Released);
+    })
 }
 
 extern fn other_mouse_down(this: &Object, _sel: Sel, event: id) {
-    mouse_click(this, event, MouseButton::Middle, ElementState::Pressed);
+    util::catch_exception("other_mouse_down", || {
+        mouse_click(this, event, MouseButton::Middle, ElementState::Pressed);
+    })
 }
 
 extern fn other_mouse_up(this: &Object, _sel: Sel, event: id) {
-    mouse_click(this, event, MouseButton::Middle, ElementState::Released);
+    util::catch_exception("other_mouse_up", || {
+        mouse_click(this, event, MouseButton::Middle, ElementState::Released);
+    })
 }
 
 fn mouse_motion(this: &Object, event: id) {
@@ -552,17 +779,60 @@ fn mouse_motion(this: &Object, event: id) {
 }
 
 extern fn mouse_moved(this: &Object, _sel: Sel, event: id) {
-    mouse_motion(this, event);
+    util::catch_exception("mouse_moved", || {
+        mouse_motion(this, event);
+    })
 }
 
 extern fn mouse_dragged(this: &Object, _sel: Sel, event: id) {
-    mouse_motion(this, event);
+    util::catch_exception("mouse_dragged", || {
+        mouse_motion(this, event);
+    })
 }
 
 extern fn right_mouse_dragged(this: &Object, _sel: Sel, event: id) {
-    mouse_motion(this, event);
+    util::catch_exception("right_mouse_dragged", || {
+        mouse_motion(this, event);
+    })
 }
 
 extern fn other_mouse_dragged(this: &Object, _sel: Sel, event: id) {
-    mouse_motion(this, event);
+    util::catch_exception("other_mouse_dragged", || {
+        mouse_motion(this, event);
+    })
+}
+
+extern fn accessibility_children(this: &Object, _sel: Sel) -> id {
+    util::catch_exception("accessibility_children", || {
+        unsafe {
+            let state_ptr: *mut c_void = *this.get_ivar("winitState");
+            let (first_query, window_id, shared) = {
+                let state = &mut *(state_ptr as *mut ViewState);
+                let first_query = !state.accessibility_activated;
+                state.accessibility_activated = true;
+                (first_query, get_window_id(state.window), state.shared.clone())
+            };
+
+            // The callback could set the adapter, so we can't hold on to the state while calling it.
+            if first_query {
+                if let Some(shared) = shared.upgrade() {
+                    shared.call_user_callback_with_event_or_store_in_pending(Event::WindowEvent {
+                        window_id: WindowId(window_id),
+                        event: WindowEvent::AccessibilityActivated,
+                    });
+                }
+            }
+
+            let state = &*(state_ptr as *const ViewState);
+            let root_object = state.accessibility_adapter
+                .as_ref()
+                .map(|adapter| adapter.get_root_object() as id)
+                .unwrap_or(nil);
+            if root_object != nil {
+                msg_send![class!(NSArray), arrayWithObject:root_object]
+            } else {
+                msg_send![super(this, class!(NSView)), accessibilityChildren]
+            }
+        }
+    })
 }
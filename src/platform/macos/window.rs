@@ -1,5 +1,6 @@
 use std;
 use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
 use std::ops::Deref;
 use std::os::raw::c_void;
 use std::sync::Weak;
@@ -10,42 +11,83 @@ use cocoa::appkit::{
     self,
     CGFloat,
     NSApplication,
+    NSApplicationPresentationOptions,
     NSColor,
     NSScreen,
     NSView,
     NSWindow,
     NSWindowButton,
+    NSWindowOcclusionState,
     NSWindowStyleMask,
 };
 use cocoa::base::{id, nil};
-use cocoa::foundation::{NSAutoreleasePool, NSDictionary, NSPoint, NSRect, NSSize, NSString};
+use cocoa::foundation::{NSArray, NSAutoreleasePool, NSDictionary, NSPoint, NSRect, NSSize, NSString, NSUInteger};
 
+use core_graphics::base::{kCGBitmapByteOrder32Big, kCGImageAlphaPremultipliedLast};
+use core_graphics::color_space::CGColorSpace;
+use core_graphics::context::CGContext;
 use core_graphics::display::CGDisplay;
+use core_graphics::geometry::{CGPoint, CGRect, CGSize};
+use core_graphics::window::{
+    self as cg_window,
+    CGWindowID,
+    kCGWindowImageBoundsIgnoreFraming,
+    kCGWindowListOptionIncludingWindow,
+};
 
 use objc;
 use objc::runtime::{Class, Object, Sel, BOOL, YES, NO};
 use objc::declare::ClassDecl;
 
 use {
+    CapturedFrame,
     CreationError,
+    CursorGrabMode,
+    ElementState,
     Event,
+    HitTestCallback,
+    ImeMode,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MouseCursor,
+    RawWindowHandle,
+    Rect,
     WindowAttributes,
     WindowEvent,
     WindowId,
 };
-use CreationError::OsError;
-use os::macos::{ActivationPolicy, WindowExt};
-use platform::platform::{ffi, util};
-use platform::platform::events_loop::{EventsLoop, Shared};
-use platform::platform::view::{new_view, set_ime_spot};
+use CreationError::{InvalidAttributes, OsError};
+use os::macos::{ActivationPolicy, FullscreenPresentationOptions, WindowExt};
+use platform::platform::{ffi, menu, util};
+use platform::platform::events_loop::{current_mods, EventsLoop, Shared};
+use platform::platform::view::{
+    new_view,
+    observe_frame_changes,
+    set_accessibility_adapter,
+    set_hit_test_callback,
+    set_ime_spot,
+    set_input_region,
+};
 use window::MonitorId as RootMonitorId;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id(pub usize);
 
+#[cfg(feature = "serde")]
+impl Id {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl Id {
+    pub fn from_raw(raw: u64) -> Id {
+        Id(raw as usize)
+    }
+}
+
 // TODO: It's possible for delegate methods to be called asynchronously, causing data races / `RefCell` panics.
 pub struct DelegateState {
     view: IdRef,
@@ -65,9 +107,116 @@ pub struct DelegateState {
 
     // Used to prevent redundant events.
     previous_dpi_factor: f64,
+    previous_maximized: bool,
+
+    // Whether the window was part of a tab group the last time we checked.
+    tabbed: bool,
+
+    // The safe area insets we last reported.
+    previous_safe_area_insets: Insets,
+
+    // Polls the position of the window while the user drags it. Retained, or `nil`.
+    move_timer: id,
+
+    // Created by `WindowExt::get_metal_layer`, and kept in sync with the view's size and scale.
+    metal_layer: RefCell<Option<IdRef>>,
+    // Created by `set_shape`, and set as the mask of the view's layer. Its sublayers are the
+    // visible parts of the window.
+    shape_mask: RefCell<Option<IdRef>>,
+    // Created by `set_blur`. While it's set, it's the content view, and the view is its subview.
+    blur_view: RefCell<Option<IdRef>>,
+
+    // Set by `set_fullscreen_presentation_options`.
+    presentation_options: Cell<Option<FullscreenPresentationOptions>>,
+    // The monitor to enter fullscreen on once the window has left fullscreen on its current one.
+    pending_fullscreen_monitor: RefCell<Option<RootMonitorId>>,
+    // What to restore when leaving simple fullscreen, which is set while in it.
+    simple_fullscreen: Cell<Option<SimpleFullscreenState>>,
+}
+
+#[derive(Clone, Copy)]
+struct SimpleFullscreenState {
+    frame: NSRect,
+    style_mask: NSWindowStyleMask,
+    presentation_options: NSApplicationPresentationOptions,
 }
 
 impl DelegateState {
+    // How far the part of the screen that the camera housing doesn't cover is inside each edge of
+    // the content view, which it can only reach in fullscreen.
+    fn get_safe_area_insets(&self) -> Insets {
+        unsafe {
+            let screen: id = msg_send![*self.window, screen];
+            if screen == nil {
+                return Insets::default();
+            }
+            // `safeAreaInsets` requires macOS 12.
+            let responds_to_insets: BOOL = msg_send![screen, respondsToSelector:sel!(safeAreaInsets)];
+            if responds_to_insets == NO {
+                return Insets::default();
+            }
+            let screen_insets: ffi::NSEdgeInsets = msg_send![screen, safeAreaInsets];
+            let screen_frame = NSScreen::frame(screen);
+            let content = NSWindow::contentRectForFrameRect_(*self.window, NSWindow::frame(*self.window));
+            // In screen coordinates, whose origin is at the bottom-left.
+            let safe_min_x = screen_frame.origin.x + screen_insets.left;
+            let safe_min_y = screen_frame.origin.y + screen_insets.bottom;
+            let safe_max_x = screen_frame.origin.x + screen_frame.size.width - screen_insets.right;
+            let safe_max_y = screen_frame.origin.y + screen_frame.size.height - screen_insets.top;
+            let inset = |inset: CGFloat| inset.max(0.0) as f64;
+            Insets {
+                top: inset(content.origin.y + content.size.height - safe_max_y),
+                left: inset(safe_min_x - content.origin.x),
+                bottom: inset(safe_min_y - content.origin.y),
+                right: inset(content.origin.x + content.size.width - safe_max_x),
+            }
+        }
+    }
+
+    unsafe fn update_metal_layer(&self) {
+        if let Some(ref layer) = *self.metal_layer.borrow() {
+            let scale_factor = NSWindow::backingScaleFactor(*self.window);
+            let bounds = NSView::bounds(*self.view);
+            let _: () = msg_send![**layer, setContentsScale:scale_factor];
+            let _: () = msg_send![**layer, setFrame:bounds];
+        }
+    }
+
+    // The mask has to cover the view, which may have resized, and be set on the view's current
+    // layer, which `get_metal_layer` replaces.
+    unsafe fn update_shape_mask(&self) {
+        if let Some(ref mask) = *self.shape_mask.borrow() {
+            let bounds = NSView::bounds(*self.view);
+            let _: () = msg_send![**mask, setFrame:bounds];
+            let layer: id = msg_send![*self.view, layer];
+            let _: () = msg_send![layer, setMask:**mask];
+            let _: () = msg_send![*self.window, invalidateShadow];
+        }
+    }
+
+    // The window has to be transparent for whatever is behind it to show through.
+    unsafe fn update_opacity(&self) {
+        let transparent = self.win_attribs.borrow().transparent
+            || self.shape_mask.borrow().is_some()
+            || self.blur_view.borrow().is_some();
+        if transparent {
+            self.window.setOpaque_(NO);
+            self.window.setBackgroundColor_(NSColor::clearColor(nil));
+        } else {
+            self.window.setOpaque_(YES);
+            let color: id = msg_send![class!(NSColor), windowBackgroundColor];
+            self.window.setBackgroundColor_(color);
+        }
+    }
+
+    unsafe fn stop_move_timer(&mut self) {
+        if self.move_timer != nil {
+            let _: () = msg_send![self.move_timer, invalidate];
+            let _: () = msg_send![self.move_timer, release];
+            self.move_timer = nil;
+        }
+    }
+
     fn is_zoomed(&self) -> bool {
         unsafe {
             // Because isZoomed do not work in Borderless mode, we set it
@@ -91,6 +240,20 @@ impl DelegateState {
         }
     }
 
+    // Because toggleFullScreen will not work if the StyleMask is none,
+    // We set a normal style to it temporary.
+    // It will clean up at window_did_exit_fullscreen.
+    unsafe fn enter_fullscreen(&self) {
+        let curr_mask = self.window.styleMask();
+        let required = NSWindowStyleMask::NSTitledWindowMask | NSWindowStyleMask::NSResizableWindowMask;
+        if !curr_mask.contains(required) {
+            util::set_style_mask(*self.window, *self.view, required);
+            self.save_style_mask.set(Some(curr_mask));
+        }
+
+        self.window.toggleFullScreen_(nil);
+    }
+
     fn restore_state_from_fullscreen(&mut self) {
         let maximized = unsafe {
             let mut win_attribs = self.win_attribs.borrow_mut();
@@ -178,12 +341,53 @@ impl WindowDelegate {
         }
     }
 
+    pub fn emit_synthetic_keys(state: &mut DelegateState, key_state: ElementState) {
+        let window_id = WindowId(get_window_id(*state.window));
+        if let Some(shared) = state.shared.upgrade() {
+            shared.send_synthetic_keys(window_id, key_state);
+        }
+    }
+
     pub fn emit_resize_event(state: &mut DelegateState) {
         let rect = unsafe { NSView::frame(*state.view) };
         let size = LogicalSize::new(rect.size.width as f64, rect.size.height as f64);
         WindowDelegate::emit_event(state, WindowEvent::Resized(size));
     }
 
+    // Sends `TabDetached` for the windows that left their tab group since the last check. Tabs that
+    // aren't the key window can be moved to a window of their own too, from the tab's menu, so
+    // every window of the `EventsLoop` is checked.
+    pub fn emit_tab_detached_events(state: &mut DelegateState) {
+        WindowDelegate::emit_tab_detached_event(state);
+        let shared = match state.shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        // Upgrading the windows outside of the lock, as dropping one locks it again.
+        let windows = shared.windows.lock().unwrap().clone();
+        for window in windows.iter().filter_map(Weak::upgrade) {
+            if *window.window != *state.window {
+                // The delegate of the window mutates its state through the same pointer.
+                let other_state = &*window.delegate.state as *const _ as *mut DelegateState;
+                WindowDelegate::emit_tab_detached_event(unsafe { &mut *other_state });
+            }
+        }
+    }
+
+    // Sends `TabDetached` if the window left its tab group by being moved out of it. A window
+    // whose other tabs were closed also leaves its group, but stays where it was.
+    fn emit_tab_detached_event(state: &mut DelegateState) {
+        let tabbed = unsafe { util::get_tabbed_window_count(*state.window) } > 1;
+        let was_tabbed = std::mem::replace(&mut state.tabbed, tabbed);
+        if was_tabbed && !tabbed {
+            let rect = unsafe { NSWindow::frame(*state.window) };
+            let position = (rect.origin.x as f64, util::bottom_left_to_top_left(rect));
+            if state.previous_position != Some(position) {
+                WindowDelegate::emit_event(state, WindowEvent::TabDetached);
+            }
+        }
+    }
+
     pub fn emit_move_event(state: &mut DelegateState) {
         let rect = unsafe { NSWindow::frame(*state.window) };
         let x = rect.origin.x as f64;
@@ -195,121 +399,293 @@ impl WindowDelegate {
         }
     }
 
+    // Sends `Maximized` when the window is zoomed or unzoomed, including from its title bar.
+    //
+    // Only windows with a title bar that are resizable can be zoomed. For the others,
+    // `set_maximized` sets the frame directly, while `win_attribs` is borrowed, so they're skipped.
+    pub fn emit_maximized_event(state: &mut DelegateState) {
+        let zoomable = NSWindowStyleMask::NSTitledWindowMask | NSWindowStyleMask::NSResizableWindowMask;
+        let mask = unsafe { state.window.styleMask() };
+        if !mask.contains(zoomable) || mask.contains(NSWindowStyleMask::NSFullScreenWindowMask) {
+            return;
+        }
+        let is_zoomed: BOOL = unsafe { msg_send![*state.window, isZoomed] };
+        let maximized = is_zoomed == YES;
+        if maximized != state.previous_maximized {
+            state.previous_maximized = maximized;
+            WindowDelegate::emit_event(state, WindowEvent::Maximized(maximized));
+        }
+    }
+
+    // Sends `SafeAreaChanged` when the area the camera housing doesn't cover changes.
+    pub fn emit_safe_area_event(state: &mut DelegateState) {
+        let insets = state.get_safe_area_insets();
+        if insets != state.previous_safe_area_insets {
+            state.previous_safe_area_insets = insets;
+            WindowDelegate::emit_event(state, WindowEvent::SafeAreaChanged(insets));
+        }
+    }
+
     /// Get the delegate class, initiailizing it neccessary
     fn class() -> *const Class {
         use std::os::raw::c_void;
 
         extern fn window_should_close(this: &Object, _: Sel, _: id) -> BOOL {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                WindowDelegate::emit_event(state, WindowEvent::CloseRequested);
-            }
-            NO
+            util::catch_exception("window_should_close", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::CloseRequested);
+                }
+                NO
+            })
         }
 
         extern fn window_will_close(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
+            util::catch_exception("window_will_close", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
 
-                WindowDelegate::emit_event(state, WindowEvent::Destroyed);
+                    WindowDelegate::emit_event(state, WindowEvent::Destroyed);
 
-                // Remove the window from the shared state.
-                if let Some(shared) = state.shared.upgrade() {
-                    let window_id = get_window_id(*state.window);
-                    shared.find_and_remove_window(window_id);
+                    // Remove the window from the shared state.
+                    if let Some(shared) = state.shared.upgrade() {
+                        let window_id = get_window_id(*state.window);
+                        shared.find_and_remove_window(window_id);
+                    }
                 }
-            }
+            })
         }
 
         extern fn window_did_resize(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                WindowDelegate::emit_resize_event(state);
-                WindowDelegate::emit_move_event(state);
-            }
+            util::catch_exception("window_did_resize", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    state.update_metal_layer();
+                    state.update_shape_mask();
+                    WindowDelegate::emit_resize_event(state);
+                    WindowDelegate::emit_move_event(state);
+                    WindowDelegate::emit_maximized_event(state);
+                    WindowDelegate::emit_safe_area_event(state);
+                }
+            })
+        }
+
+        // Depending on the version of macOS and on the window, `windowDidMove:` may only be sent once
+        // a drag ends, so the position is polled until the mouse button is released.
+        extern fn window_will_move(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_will_move", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    if state.move_timer != nil {
+                        return;
+                    }
+
+                    WindowDelegate::emit_event(state, WindowEvent::MoveStarted);
+                    let timer: id = msg_send![class!(NSTimer),
+                        timerWithTimeInterval:(1.0 / 60.0)
+                        target:this
+                        selector:sel!(winitPollPosition:)
+                        userInfo:nil
+                        repeats:YES];
+                    // The common modes include the one used while AppKit tracks the drag itself.
+                    let run_loop: id = msg_send![class!(NSRunLoop), currentRunLoop];
+                    let _: () = msg_send![run_loop, addTimer:timer forMode:ffi::NSRunLoopCommonModes];
+                    let _: id = msg_send![timer, retain];
+                    state.move_timer = timer;
+                }
+            })
+        }
+
+        extern fn poll_position(this: &Object, _: Sel, _: id) {
+            util::catch_exception("poll_position", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_move_event(state);
+
+                    let pressed_buttons: NSUInteger = msg_send![class!(NSEvent), pressedMouseButtons];
+                    if pressed_buttons & 1 == 0 {
+                        state.stop_move_timer();
+                        WindowDelegate::emit_event(state, WindowEvent::MoveEnded);
+                    }
+                }
+            })
         }
 
         // This won't be triggered if the move was part of a resize.
         extern fn window_did_move(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                WindowDelegate::emit_move_event(state);
-            }
+            util::catch_exception("window_did_move", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_tab_detached_events(state);
+                    WindowDelegate::emit_move_event(state);
+                    WindowDelegate::emit_safe_area_event(state);
+                }
+            })
         }
 
         extern fn window_did_change_screen(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                let dpi_factor = NSWindow::backingScaleFactor(*state.window) as f64;
-                if state.previous_dpi_factor != dpi_factor {
-                    state.previous_dpi_factor = dpi_factor;
-                    WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
-                    WindowDelegate::emit_resize_event(state);
+            util::catch_exception("window_did_change_screen", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    let dpi_factor = NSWindow::backingScaleFactor(*state.window) as f64;
+                    if state.previous_dpi_factor != dpi_factor {
+                        state.previous_dpi_factor = dpi_factor;
+                        state.update_metal_layer();
+                        WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
+                        WindowDelegate::emit_resize_event(state);
+                    }
                 }
-            }
+            })
         }
 
         // This will always be called before `window_did_change_screen`.
         extern fn window_did_change_backing_properties(this: &Object, _:Sel, _:id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                let dpi_factor = NSWindow::backingScaleFactor(*state.window) as f64;
-                if state.previous_dpi_factor != dpi_factor {
-                    state.previous_dpi_factor = dpi_factor;
-                    WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
-                    WindowDelegate::emit_resize_event(state);
+            util::catch_exception("window_did_change_backing_properties", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    let dpi_factor = NSWindow::backingScaleFactor(*state.window) as f64;
+                    if state.previous_dpi_factor != dpi_factor {
+                        state.previous_dpi_factor = dpi_factor;
+                        state.update_metal_layer();
+                        WindowDelegate::emit_event(state, WindowEvent::HiDpiFactorChanged(dpi_factor));
+                        WindowDelegate::emit_resize_event(state);
+                    }
                 }
-            }
+            })
         }
 
         extern fn window_did_become_key(this: &Object, _: Sel, _: id) {
-            unsafe {
-                // TODO: center the cursor if the window had mouse grab when it
-                // lost focus
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                WindowDelegate::emit_event(state, WindowEvent::Focused(true));
-            }
+            util::catch_exception("window_did_become_key", || {
+                unsafe {
+                    // TODO: center the cursor if the window had mouse grab when it
+                    // lost focus
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_tab_detached_events(state);
+                    WindowDelegate::emit_event(state, WindowEvent::Focused(true));
+                    WindowDelegate::emit_synthetic_keys(state, ElementState::Pressed);
+                }
+            })
         }
 
         extern fn window_did_resign_key(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                WindowDelegate::emit_event(state, WindowEvent::Focused(false));
-            }
+            util::catch_exception("window_did_resign_key", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_synthetic_keys(state, ElementState::Released);
+                    WindowDelegate::emit_event(state, WindowEvent::Focused(false));
+                }
+            })
         }
 
-        /// Invoked when the dragged image enters destination bounds or frame
-        extern fn dragging_entered(this: &Object, _: Sel, sender: id) -> BOOL {
-            use cocoa::appkit::NSPasteboard;
-            use cocoa::foundation::NSFastEnumeration;
-            use std::path::PathBuf;
+        extern fn window_will_start_live_resize(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_will_start_live_resize", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::ResizeStarted);
+                }
+            })
+        }
+
+        extern fn window_did_end_live_resize(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_did_end_live_resize", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::ResizeEnded);
+                }
+            })
+        }
+
+        extern fn window_did_become_main(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_did_become_main", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::MainWindowChanged(true));
+                }
+            })
+        }
 
-            let pb: id = unsafe { msg_send![sender, draggingPasteboard] };
-            let filenames = unsafe { NSPasteboard::propertyListForType(pb, appkit::NSFilenamesPboardType) };
+        extern fn window_did_resign_main(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_did_resign_main", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::MainWindowChanged(false));
+                }
+            })
+        }
 
-            for file in unsafe { filenames.iter() } {
-                use cocoa::foundation::NSString;
-                use std::ffi::CStr;
+        extern fn window_did_change_occlusion_state(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_did_change_occlusion_state", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    let visible = state.window
+                        .occlusionState()
+                        .contains(NSWindowOcclusionState::NSWindowOcclusionStateVisible);
+                    WindowDelegate::emit_event(state, WindowEvent::Occluded(!visible));
+                }
+            })
+        }
 
+        extern fn window_did_miniaturize(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_did_miniaturize", || {
                 unsafe {
-                    let f = NSString::UTF8String(file);
-                    let path = CStr::from_ptr(f).to_string_lossy().into_owned();
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::Minimized(true));
+                }
+            })
+        }
 
+        extern fn window_did_deminiaturize(this: &Object, _: Sel, _: id) {
+            util::catch_exception("window_did_deminiaturize", || {
+                unsafe {
                     let state: *mut c_void = *this.get_ivar("winitState");
                     let state = &mut *(state as *mut DelegateState);
-                    WindowDelegate::emit_event(state, WindowEvent::HoveredFile(PathBuf::from(path)));
+                    WindowDelegate::emit_event(state, WindowEvent::Minimized(false));
                 }
-            };
+            })
+        }
+
+        /// Invoked when the dragged image enters destination bounds or frame
+        extern fn dragging_entered(this: &Object, _: Sel, sender: id) -> BOOL {
+            util::catch_exception("dragging_entered", || {
+                use cocoa::appkit::NSPasteboard;
+                use cocoa::foundation::NSFastEnumeration;
+                use std::path::PathBuf;
+
+                let pb: id = unsafe { msg_send![sender, draggingPasteboard] };
+                let filenames = unsafe { NSPasteboard::propertyListForType(pb, appkit::NSFilenamesPboardType) };
+
+                for file in unsafe { filenames.iter() } {
+                    use cocoa::foundation::NSString;
+                    use std::ffi::CStr;
+
+                    unsafe {
+                        let f = NSString::UTF8String(file);
+                        let path = CStr::from_ptr(f).to_string_lossy().into_owned();
 
-            YES
+                        let state: *mut c_void = *this.get_ivar("winitState");
+                        let state = &mut *(state as *mut DelegateState);
+                        WindowDelegate::emit_event(state, WindowEvent::HoveredFile(PathBuf::from(path)));
+                    }
+                };
+
+                YES
+            })
         }
 
         /// Invoked when the image is released
@@ -317,28 +693,44 @@ impl WindowDelegate {
 
         /// Invoked after the released image has been removed from the screen
         extern fn perform_drag_operation(this: &Object, _: Sel, sender: id) -> BOOL {
-            use cocoa::appkit::NSPasteboard;
-            use cocoa::foundation::NSFastEnumeration;
-            use std::path::PathBuf;
+            util::catch_exception("perform_drag_operation", || {
+                use cocoa::appkit::NSPasteboard;
+                use cocoa::foundation::NSFastEnumeration;
+                use std::path::PathBuf;
 
-            let pb: id = unsafe { msg_send![sender, draggingPasteboard] };
-            let filenames = unsafe { NSPasteboard::propertyListForType(pb, appkit::NSFilenamesPboardType) };
-
-            for file in unsafe { filenames.iter() } {
-                use cocoa::foundation::NSString;
-                use std::ffi::CStr;
-
-                unsafe {
-                    let f = NSString::UTF8String(file);
-                    let path = CStr::from_ptr(f).to_string_lossy().into_owned();
+                let pb: id = unsafe { msg_send![sender, draggingPasteboard] };
+                let filenames = unsafe { NSPasteboard::propertyListForType(pb, appkit::NSFilenamesPboardType) };
 
+                let position = unsafe {
                     let state: *mut c_void = *this.get_ivar("winitState");
-                    let state = &mut *(state as *mut DelegateState);
-                    WindowDelegate::emit_event(state, WindowEvent::DroppedFile(PathBuf::from(path)));
-                }
-            };
+                    let state = &*(state as *const DelegateState);
+                    let window_point: NSPoint = msg_send![sender, draggingLocation];
+                    let view_point = state.view.convertPoint_fromView_(window_point, nil);
+                    let view_rect = NSView::frame(*state.view);
+                    (view_point.x as f64, view_rect.size.height as f64 - view_point.y as f64).into()
+                };
+                let modifiers = current_mods();
+
+                for file in unsafe { filenames.iter() } {
+                    use cocoa::foundation::NSString;
+                    use std::ffi::CStr;
+
+                    unsafe {
+                        let f = NSString::UTF8String(file);
+                        let path = CStr::from_ptr(f).to_string_lossy().into_owned();
+
+                        let state: *mut c_void = *this.get_ivar("winitState");
+                        let state = &mut *(state as *mut DelegateState);
+                        WindowDelegate::emit_event(state, WindowEvent::DroppedFile {
+                            path: PathBuf::from(path),
+                            position,
+                            modifiers,
+                        });
+                    }
+                };
 
-            YES
+                YES
+            })
         }
 
         /// Invoked when the dragging operation is complete
@@ -346,43 +738,96 @@ impl WindowDelegate {
 
         /// Invoked when the dragging operation is cancelled
         extern fn dragging_exited(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                WindowDelegate::emit_event(state, WindowEvent::HoveredFileCancelled);
-            }
+            util::catch_exception("dragging_exited", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    WindowDelegate::emit_event(state, WindowEvent::HoveredFileCancelled);
+                }
+            })
         }
 
         /// Invoked when entered fullscreen
         extern fn window_did_enter_fullscreen(this: &Object, _: Sel, _: id){
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                state.win_attribs.borrow_mut().fullscreen = Some(get_current_monitor(*state.window));
+            util::catch_exception("window_did_enter_fullscreen", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    let monitor = get_current_monitor(*state.window);
+                    state.win_attribs.borrow_mut().fullscreen = Some(monitor.clone());
 
-                state.handle_with_fullscreen = false;
-            }
+                    state.handle_with_fullscreen = false;
+                    let event = WindowEvent::FullscreenChanged(Some(monitor));
+                    WindowDelegate::emit_event(state, event);
+                }
+            })
         }
 
         /// Invoked when before enter fullscreen
         extern fn window_will_enter_fullscreen(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-                let is_zoomed = state.is_zoomed();
+            util::catch_exception("window_will_enter_fullscreen", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    let is_zoomed = state.is_zoomed();
 
-                state.win_attribs.borrow_mut().maximized = is_zoomed;
-            }
+                    state.win_attribs.borrow_mut().maximized = is_zoomed;
+                }
+            })
+        }
+
+        /// Invoked before entering fullscreen, to choose how the dock and menu bar behave
+        extern fn window_will_use_fullscreen_presentation_options(
+            this: &Object,
+            _: Sel,
+            _: id,
+            proposed_options: NSUInteger,
+        ) -> NSUInteger {
+            util::catch_exception("window_will_use_fullscreen_presentation_options", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+                    let fullscreen =
+                        NSApplicationPresentationOptions::NSApplicationPresentationFullScreen;
+                    match state.presentation_options.get() {
+                        Some(options) => {
+                            (NSApplicationPresentationOptions::from(options) | fullscreen).bits()
+                        },
+                        None => proposed_options,
+                    }
+                }
+            })
         }
 
         /// Invoked when exited fullscreen
         extern fn window_did_exit_fullscreen(this: &Object, _: Sel, _: id){
-            let state = unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                &mut *(state as *mut DelegateState)
-            };
+            util::catch_exception("window_did_exit_fullscreen", || {
+                let state = unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    &mut *(state as *mut DelegateState)
+                };
 
-            state.restore_state_from_fullscreen();
+                state.restore_state_from_fullscreen();
+
+                // When moving to another monitor, the window only passes through its windowed
+                // state, so no event is sent until it's fullscreen again.
+                let pending_monitor = state.pending_fullscreen_monitor.borrow_mut().take();
+                match pending_monitor {
+                    Some(monitor) => unsafe {
+                        // Both frames are in points, with the origin at the bottom-left.
+                        if let Some(screen) = monitor.inner.get_nsscreen() {
+                            let frame = NSScreen::frame(screen);
+                            let top_left = NSPoint::new(
+                                frame.origin.x,
+                                frame.origin.y + frame.size.height,
+                            );
+                            NSWindow::setFrameTopLeftPoint_(*state.window, top_left);
+                        }
+                        state.enter_fullscreen();
+                    },
+                    None => WindowDelegate::emit_event(state, WindowEvent::FullscreenChanged(None)),
+                }
+            })
         }
 
         /// Invoked when fail to enter fullscreen
@@ -402,20 +847,22 @@ impl WindowDelegate {
         /// This method indicates that there was an error, and you should clean up any
         /// work you may have done to prepare to enter full-screen mode.
         extern fn window_did_fail_to_enter_fullscreen(this: &Object, _: Sel, _: id) {
-            unsafe {
-                let state: *mut c_void = *this.get_ivar("winitState");
-                let state = &mut *(state as *mut DelegateState);
-
-                if state.handle_with_fullscreen {
-                    let _: () = msg_send![*state.window,
-                        performSelector:sel!(toggleFullScreen:)
-                        withObject:nil
-                        afterDelay: 0.5
-                    ];
-                } else {
-                    state.restore_state_from_fullscreen();
+            util::catch_exception("window_did_fail_to_enter_fullscreen", || {
+                unsafe {
+                    let state: *mut c_void = *this.get_ivar("winitState");
+                    let state = &mut *(state as *mut DelegateState);
+
+                    if state.handle_with_fullscreen {
+                        let _: () = msg_send![*state.window,
+                            performSelector:sel!(toggleFullScreen:)
+                            withObject:nil
+                            afterDelay: 0.5
+                        ];
+                    } else {
+                        state.restore_state_from_fullscreen();
+                    }
                 }
-            }
+            })
         }
 
         static mut DELEGATE_CLASS: *const Class = 0 as *const Class;
@@ -433,6 +880,14 @@ impl WindowDelegate {
                 window_will_close as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidResize:),
                 window_did_resize as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowWillStartLiveResize:),
+                window_will_start_live_resize as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidEndLiveResize:),
+                window_did_end_live_resize as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowWillMove:),
+                window_will_move as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(winitPollPosition:),
+                poll_position as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidMove:),
                 window_did_move as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidChangeScreen:),
@@ -443,6 +898,16 @@ impl WindowDelegate {
                 window_did_become_key as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidResignKey:),
                 window_did_resign_key as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidBecomeMain:),
+                window_did_become_main as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidResignMain:),
+                window_did_resign_main as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidChangeOcclusionState:),
+                window_did_change_occlusion_state as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidMiniaturize:),
+                window_did_miniaturize as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(windowDidDeminiaturize:),
+                window_did_deminiaturize as extern fn(&Object, Sel, id));
 
             // callbacks for drag and drop events
             decl.add_method(sel!(draggingEntered:),
@@ -461,6 +926,9 @@ impl WindowDelegate {
                 window_did_enter_fullscreen as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowWillEnterFullScreen:),
                 window_will_enter_fullscreen as extern fn(&Object, Sel, id));
+            decl.add_method(sel!(window:willUseFullScreenPresentationOptions:),
+                window_will_use_fullscreen_presentation_options
+                    as extern fn(&Object, Sel, id, NSUInteger) -> NSUInteger);
             decl.add_method(sel!(windowDidExitFullScreen:),
                 window_did_exit_fullscreen as extern fn(&Object, Sel, id));
             decl.add_method(sel!(windowDidFailToEnterFullScreen:),
@@ -505,6 +973,8 @@ impl Drop for WindowDelegate {
             // NOTE: setDelegate:nil at first retains the previous value,
             // and then autoreleases it, so autorelease pool is needed
             let autoreleasepool = NSAutoreleasePool::new(nil);
+            // The timer retains us, and would keep firing after the state is gone.
+            self.state.stop_move_timer();
             let _: () = msg_send![*self.state.window, setDelegate:nil];
             let _: () = msg_send![autoreleasepool, drain];
         }
@@ -513,7 +983,7 @@ impl Drop for WindowDelegate {
 
 #[derive(Clone, Default)]
 pub struct PlatformSpecificWindowBuilderAttributes {
-    pub activation_policy: ActivationPolicy,
+    pub activation_policy: Option<ActivationPolicy>,
     pub movable_by_window_background: bool,
     pub titlebar_transparent: bool,
     pub title_hidden: bool,
@@ -521,6 +991,7 @@ pub struct PlatformSpecificWindowBuilderAttributes {
     pub titlebar_buttons_hidden: bool,
     pub fullsize_content_view: bool,
     pub resize_increments: Option<LogicalSize>,
+    pub tabbing_identifier: Option<String>,
 }
 
 pub struct Window2 {
@@ -529,6 +1000,14 @@ pub struct Window2 {
     pub delegate: WindowDelegate,
     pub input_context: IdRef,
     cursor_hidden: AtomicBool,
+    cursor_grabbed: AtomicBool,
+    // Set while the window is kept ordered out until `present_ready` is called.
+    pending_present: AtomicBool,
+    // Whether the window becomes the key window when it's first ordered in.
+    activate_on_present: bool,
+    // Whether the view is in the view of another application, in which case the window is never
+    // shown.
+    parented: bool,
 }
 
 unsafe impl Send for Window2 {}
@@ -584,6 +1063,108 @@ impl WindowExt for Window2 {
     fn get_nsview(&self) -> *mut c_void {
         *self.view as *mut c_void
     }
+
+    fn get_metal_layer(&self) -> *mut c_void {
+        let state = &self.delegate.state;
+        if let Some(ref layer) = *state.metal_layer.borrow() {
+            return **layer as *mut c_void;
+        }
+        // `CAMetalLayer` requires macOS 10.11.
+        let class = match Class::get("CAMetalLayer") {
+            Some(class) => class,
+            None => return std::ptr::null_mut(),
+        };
+        unsafe {
+            let layer = IdRef::new(msg_send![class, new]);
+            // Setting the layer before `wantsLayer` makes the view layer-hosting, so AppKit
+            // leaves the layer's contents to us.
+            let _: () = msg_send![*self.view, setLayer:*layer];
+            let _: () = msg_send![*self.view, setWantsLayer:YES];
+            let raw_layer = *layer;
+            *state.metal_layer.borrow_mut() = Some(layer);
+            state.update_metal_layer();
+            state.update_shape_mask();
+            raw_layer as *mut c_void
+        }
+    }
+
+    fn set_dock_tile_content_view(&self, view: Option<*mut c_void>) {
+        unsafe {
+            let dock_tile: id = msg_send![appkit::NSApp(), dockTile];
+            let view = view.map_or(nil, |view| view as id);
+            let _: () = msg_send![dock_tile, setContentView:view];
+        }
+    }
+
+    fn display_dock_tile(&self) {
+        unsafe {
+            let dock_tile: id = msg_send![appkit::NSApp(), dockTile];
+            let _: () = msg_send![dock_tile, display];
+        }
+    }
+
+    #[inline]
+    fn select_next_tab(&self) {
+        self.send_tab_action(sel!(selectNextTab:))
+    }
+
+    #[inline]
+    fn select_previous_tab(&self) {
+        self.send_tab_action(sel!(selectPreviousTab:))
+    }
+
+    #[inline]
+    fn merge_all_windows(&self) {
+        self.send_tab_action(sel!(mergeAllWindows:))
+    }
+
+    fn set_simple_fullscreen(&self, fullscreen: bool) -> bool {
+        let state = &self.delegate.state;
+        if state.win_attribs.borrow().fullscreen.is_some() {
+            return false;
+        }
+        unsafe {
+            let app = appkit::NSApp();
+            match (fullscreen, state.simple_fullscreen.get()) {
+                (true, None) => {
+                    state.simple_fullscreen.set(Some(SimpleFullscreenState {
+                        frame: NSWindow::frame(*self.window),
+                        style_mask: self.window.styleMask(),
+                        presentation_options: app.presentationOptions_(),
+                    }));
+                    let options = state.presentation_options.get().unwrap_or_default();
+                    app.setPresentationOptions_(options.into());
+                    util::set_style_mask(
+                        *self.window,
+                        *self.view,
+                        NSWindowStyleMask::NSBorderlessWindowMask,
+                    );
+                    let screen = NSScreen::frame(self.window.screen());
+                    self.window.setFrame_display_(screen, YES);
+                    true
+                },
+                (false, Some(saved)) => {
+                    state.simple_fullscreen.set(None);
+                    app.setPresentationOptions_(saved.presentation_options);
+                    util::set_style_mask(*self.window, *self.view, saved.style_mask);
+                    self.window.setFrame_display_(saved.frame, YES);
+                    true
+                },
+                _ => false,
+            }
+        }
+    }
+
+    #[inline]
+    fn set_fullscreen_presentation_options(&self, options: FullscreenPresentationOptions) {
+        self.delegate.state.presentation_options.set(Some(options));
+    }
+
+    #[inline]
+    fn is_main_window(&self) -> bool {
+        let is_main_window: BOOL = unsafe { msg_send![*self.window, isMainWindow] };
+        is_main_window == YES
+    }
 }
 
 impl Window2 {
@@ -601,11 +1182,23 @@ impl Window2 {
         // Might as well save some RAM...
         win_attribs.window_icon.take();
 
+        let parent_view = match win_attribs.parent {
+            Some(RawWindowHandle::MacOS(parent_view)) => Some(parent_view as id),
+            Some(_) => return Err(InvalidAttributes("The parent handle isn't an `NSView`.".to_owned())),
+            None => None,
+        };
+        if parent_view.is_some() {
+            // Only the view is shown, in its parent.
+            win_attribs.visible = false;
+            win_attribs.fullscreen = None;
+            win_attribs.maximized = false;
+        }
+
         let autoreleasepool = unsafe {
             NSAutoreleasePool::new(nil)
         };
 
-        let app = match Window2::create_app(pl_attribs.activation_policy) {
+        let app = match Window2::create_app(pl_attribs.activation_policy.unwrap_or_default()) {
             Some(app) => app,
             None => {
                 let _: () = unsafe { msg_send![autoreleasepool, drain] };
@@ -630,13 +1223,32 @@ impl Window2 {
 
         let input_context = unsafe { util::create_input_context(*view) };
 
+        if let Some(parent_view) = parent_view {
+            unsafe {
+                // The window keeps an empty content view, since its own view goes into the
+                // parent, which it fills.
+                let placeholder: id = msg_send![class!(NSView), new];
+                window.setContentView_(placeholder);
+                let _: () = msg_send![placeholder, release];
+                let resizable = appkit::NSViewWidthSizable | appkit::NSViewHeightSizable;
+                let _: () = msg_send![*view, setAutoresizingMask:resizable as NSUInteger];
+                let _: () = msg_send![parent_view, addSubview:*view];
+                let bounds = NSView::bounds(parent_view);
+                let _: () = msg_send![*view, setFrame:bounds];
+                observe_frame_changes(*view);
+            }
+        }
+
         unsafe {
             if win_attribs.transparent {
                 (*window as id).setOpaque_(NO);
                 (*window as id).setBackgroundColor_(NSColor::clearColor(nil));
             }
 
-            app.activateIgnoringOtherApps_(YES);
+            // The application hosting the view decides when it's active.
+            if parent_view.is_none() {
+                app.activateIgnoringOtherApps_(YES);
+            }
 
             if let Some(dimensions) = win_attribs.min_dimensions {
                 nswindow_set_min_dimensions(window.0, dimensions);
@@ -663,6 +1275,16 @@ impl Window2 {
             handle_with_fullscreen: win_attribs.fullscreen.is_some(),
             previous_position: None,
             previous_dpi_factor: dpi_factor,
+            previous_maximized: false,
+            tabbed: false,
+            previous_safe_area_insets: Insets::default(),
+            move_timer: nil,
+            metal_layer: RefCell::new(None),
+            shape_mask: RefCell::new(None),
+            blur_view: RefCell::new(None),
+            presentation_options: Cell::new(None),
+            pending_fullscreen_monitor: RefCell::new(None),
+            simple_fullscreen: Cell::new(None),
         };
         delegate_state.win_attribs.borrow_mut().fullscreen = None;
 
@@ -677,32 +1299,56 @@ impl Window2 {
             delegate: WindowDelegate::new(delegate_state),
             input_context,
             cursor_hidden: Default::default(),
+            cursor_grabbed: Default::default(),
+            pending_present: AtomicBool::new(win_attribs.visible && win_attribs.visible_on_first_present),
+            activate_on_present: win_attribs.active,
+            parented: parent_view.is_some(),
         };
 
+        if !win_attribs.focusable {
+            window.set_focusable(false);
+        }
+
+        if let Some(menu) = win_attribs.menu.clone() {
+            window.set_menu(Some(menu));
+        }
+
+        // Maximize before entering fullscreen, so that the window is still maximized once it
+        // leaves fullscreen.
+        if win_attribs.maximized {
+            window.delegate.state.perform_maximized(win_attribs.maximized);
+        }
+
         // Set fullscreen mode after we setup everything
         if let Some(ref monitor) = win_attribs.fullscreen {
             unsafe {
+                // A window becomes fullscreen on the monitor it's on.
                 if monitor.inner != get_current_monitor(*window.window).inner {
-                    unimplemented!();
+                    let position = monitor.get_position().to_logical(monitor.get_hidpi_factor());
+                    window.set_outer_position(position);
                 }
             }
             window.set_fullscreen(Some(monitor.clone()));
         }
 
-        // Make key have to be after set fullscreen
+        if win_attribs.blur {
+            let _ = window.set_blur(true);
+        }
+
+        // Make key have to be after set fullscreen and maximized
         // to prevent normal size window brefly appears
         unsafe {
-            if win_attribs.visible {
-                window.window.makeKeyAndOrderFront_(nil);
-            } else {
+            if win_attribs.visible && !win_attribs.visible_on_first_present {
+                if win_attribs.active {
+                    window.window.makeKeyAndOrderFront_(nil);
+                } else {
+                    window.window.orderFront_(nil);
+                }
+            } else if win_attribs.active && parent_view.is_none() {
                 window.window.makeKeyWindow();
             }
         }
 
-        if win_attribs.maximized {
-            window.delegate.state.perform_maximized(win_attribs.maximized);
-        }
-
         let _: () = unsafe { msg_send![autoreleasepool, drain] };
 
         Ok(window)
@@ -733,7 +1379,9 @@ impl Window2 {
             let window_superclass = class!(NSWindow);
             let mut decl = ClassDecl::new("WinitWindow", window_superclass).unwrap();
             decl.add_method(sel!(canBecomeMainWindow), yes as extern fn(&Object, Sel) -> BOOL);
-            decl.add_method(sel!(canBecomeKeyWindow), yes as extern fn(&Object, Sel) -> BOOL);
+            decl.add_method(sel!(canBecomeKeyWindow), can_become_key_window as extern fn(&Object, Sel) -> BOOL);
+            // Zeroed when the window is allocated, so windows are focusable by default.
+            decl.add_ivar::<BOOL>("winitNotFocusable");
             WINDOW2_CLASS = decl.register();
         });
 
@@ -838,6 +1486,14 @@ impl Window2 {
                     }
                 }
 
+                if let Some(ref identifier) = pl_attrs.tabbing_identifier {
+                    let supported: BOOL = msg_send![*window, respondsToSelector:sel!(setTabbingIdentifier:)];
+                    if supported == YES {
+                        let identifier = IdRef::new(NSString::alloc(nil).init_str(identifier));
+                        let _: () = msg_send![*window, setTabbingIdentifier:*identifier];
+                    }
+                }
+
                 window.center();
                 window
             });
@@ -867,12 +1523,39 @@ impl Window2 {
 
     #[inline]
     pub fn show(&self) {
-        unsafe { NSWindow::makeKeyAndOrderFront_(*self.window, nil); }
+        self.pending_present.store(false, Ordering::Release);
+        unsafe {
+            if self.parented {
+                let _: () = msg_send![*self.view, setHidden:NO];
+            } else {
+                NSWindow::makeKeyAndOrderFront_(*self.window, nil);
+            }
+        }
     }
 
     #[inline]
     pub fn hide(&self) {
-        unsafe { NSWindow::orderOut_(*self.window, nil); }
+        self.pending_present.store(false, Ordering::Release);
+        unsafe {
+            if self.parented {
+                let _: () = msg_send![*self.view, setHidden:YES];
+            } else {
+                NSWindow::orderOut_(*self.window, nil);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn present_ready(&self) {
+        if self.pending_present.swap(false, Ordering::AcqRel) {
+            unsafe {
+                if self.activate_on_present {
+                    NSWindow::makeKeyAndOrderFront_(*self.window, nil);
+                } else {
+                    NSWindow::orderFront_(*self.window, nil);
+                }
+            }
+        }
     }
 
     pub fn get_position(&self) -> Option<LogicalPosition> {
@@ -896,7 +1579,7 @@ impl Window2 {
         ).into())
     }
 
-    pub fn set_position(&self, position: LogicalPosition) {
+    pub fn set_outer_position(&self, position: LogicalPosition) {
         let dummy = NSRect::new(
             NSPoint::new(
                 position.x,
@@ -911,22 +1594,63 @@ impl Window2 {
         }
     }
 
+    pub fn set_inner_position(&self, position: LogicalPosition) {
+        // The title bar is the only part of the frame above or left of the content view.
+        let outer = self.get_position().unwrap();
+        let inner = self.get_inner_position().unwrap();
+        self.set_outer_position(LogicalPosition::new(
+            position.x - (inner.x - outer.x),
+            position.y - (inner.y - outer.y),
+        ));
+    }
+
     #[inline]
     pub fn get_inner_size(&self) -> Option<LogicalSize> {
-        let view_frame = unsafe { NSView::frame(*self.view) };
+        let view_frame = unsafe {
+            if self.parented {
+                NSView::bounds(*self.view)
+            } else {
+                NSView::frame(*self.view)
+            }
+        };
         Some((view_frame.size.width as f64, view_frame.size.height as f64).into())
     }
 
     #[inline]
     pub fn get_outer_size(&self) -> Option<LogicalSize> {
+        // The hidden `NSWindow` of a parented view has nothing to do with its size.
+        if self.parented {
+            return self.get_inner_size();
+        }
         let view_frame = unsafe { NSWindow::frame(*self.window) };
         Some((view_frame.size.width as f64, view_frame.size.height as f64).into())
     }
 
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        let (frame_rect, content_rect) = unsafe {
+            let frame_rect = NSWindow::frame(*self.window);
+            (frame_rect, NSWindow::contentRectForFrameRect_(*self.window, frame_rect))
+        };
+        // Both rectangles have their origin at the bottom-left.
+        Some(::FrameExtents {
+            left: (content_rect.origin.x - frame_rect.origin.x) as f64,
+            right: ((frame_rect.origin.x + frame_rect.size.width)
+                - (content_rect.origin.x + content_rect.size.width)) as f64,
+            top: ((frame_rect.origin.y + frame_rect.size.height)
+                - (content_rect.origin.y + content_rect.size.height)) as f64,
+            bottom: (content_rect.origin.y - frame_rect.origin.y) as f64,
+        })
+    }
+
     #[inline]
     pub fn set_inner_size(&self, size: LogicalSize) {
+        let size = NSSize::new(size.width as CGFloat, size.height as CGFloat);
         unsafe {
-            NSWindow::setContentSize_(*self.window, NSSize::new(size.width as CGFloat, size.height as CGFloat));
+            if self.parented {
+                let _: () = msg_send![*self.view, setFrameSize:size];
+            } else {
+                NSWindow::setContentSize_(*self.window, size);
+            }
         }
     }
 
@@ -1000,8 +1724,29 @@ impl Window2 {
     #[inline]
     pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
         // TODO: Do this for real https://stackoverflow.com/a/40922095/5435443
+        self.set_cursor_grab_mode(if grab { CursorGrabMode::Locked } else { CursorGrabMode::None })
+    }
+
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, mode: CursorGrabMode) -> Result<(), String> {
+        let grab = match mode {
+            CursorGrabMode::None => false,
+            CursorGrabMode::Locked => true,
+            CursorGrabMode::Confined => {
+                return Err("Confining the cursor is not yet possible on macOS.".to_owned());
+            },
+        };
+        // Dissociating the cursor from the mouse freezes it, while `NSEvent` deltas still report
+        // the mouse's motion.
         CGDisplay::associate_mouse_and_mouse_cursor_position(!grab)
-            .map_err(|status| format!("Failed to grab cursor: `CGError` {:?}", status))
+            .map_err(|status| format!("Failed to grab cursor: `CGError` {:?}", status))?;
+        self.cursor_grabbed.store(grab, Ordering::Release);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, _region: Option<::Rect>) -> Result<(), String> {
+        Err("Confining the cursor to a region is not yet possible on macOS.".to_owned())
     }
 
     #[inline]
@@ -1036,7 +1781,10 @@ impl Window2 {
         };
         CGDisplay::warp_mouse_cursor_position(point)
             .map_err(|e| format!("`CGWarpMouseCursorPosition` failed: {:?}", e))?;
-        CGDisplay::associate_mouse_and_mouse_cursor_position(true)
+        // Warping suppresses mouse events for a short while unless the cursor is re-associated,
+        // but that would also release a grab, which FPS-style controls rely on.
+        let grabbed = self.cursor_grabbed.load(Ordering::Acquire);
+        CGDisplay::associate_mouse_and_mouse_cursor_position(!grabbed)
             .map_err(|e| format!("`CGAssociateMouseAndMouseCursorPosition` failed: {:?}", e))?;
 
         Ok(())
@@ -1048,44 +1796,93 @@ impl Window2 {
     }
 
     #[inline]
-    /// TODO: Right now set_fullscreen do not work on switching monitors
-    /// in fullscreen mode
-    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
-        let state = &self.delegate.state;
-        let current = {
-            let win_attribs = state.win_attribs.borrow_mut();
-
-            let current = win_attribs.fullscreen.clone();
-            match (&current, monitor) {
-                (&None, None) => {
-                    return;
-                }
-                (&Some(ref a), Some(ref b)) if a.inner != b.inner => {
-                    unimplemented!();
-                }
-                (&Some(_), Some(_)) => {
-                    return;
-                }
-                _ => (),
+    pub fn set_minimized(&self, minimized: bool) {
+        unsafe {
+            if minimized {
+                let _: () = msg_send![*self.window, miniaturize:nil];
+            } else {
+                let _: () = msg_send![*self.window, deminiaturize:nil];
             }
+        }
+    }
 
-            current
-        };
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        let is_miniaturized: BOOL = unsafe { msg_send![*self.window, isMiniaturized] };
+        is_miniaturized == YES
+    }
 
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        self.delegate.state.is_zoomed()
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.delegate.state.win_attribs.borrow().fullscreen.is_some()
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        // `isVisible` is false while the window is miniaturized.
+        let is_visible: BOOL = unsafe { msg_send![*self.window, isVisible] };
+        is_visible == YES || self.is_minimized()
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        let is_key_window: BOOL = unsafe { msg_send![*self.window, isKeyWindow] };
+        is_key_window == YES
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+        unsafe { NSWindow::makeKeyAndOrderFront_(*self.window, nil); }
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> Insets {
+        self.delegate.state.get_safe_area_insets()
+    }
+
+    pub fn set_focusable(&self, focusable: bool) {
         unsafe {
-            // Because toggleFullScreen will not work if the StyleMask is none,
-            // We set a normal style to it temporary.
-            // It will clean up at window_did_exit_fullscreen.
-            if current.is_none() {
-                let curr_mask = state.window.styleMask();
-                let required = NSWindowStyleMask::NSTitledWindowMask | NSWindowStyleMask::NSResizableWindowMask;
-                if !curr_mask.contains(required) {
-                    util::set_style_mask(*self.window, *self.view, required);
-                    state.save_style_mask.set(Some(curr_mask));
-                }
-            }
+            let window = &mut *(*self.window as *mut Object);
+            // A window that is already the key window stays so until another one becomes key.
+            window.set_ivar::<BOOL>("winitNotFocusable", if focusable { NO } else { YES });
+        }
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.delegate.state.win_attribs.borrow().resizable
+    }
 
-            self.window.toggleFullScreen_(nil);
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.delegate.state.win_attribs.borrow().decorations
+    }
+
+    /// Switching to another monitor while already fullscreen isn't seamless: the window visibly
+    /// leaves fullscreen before re-entering it on the new screen.
+    #[inline]
+    pub fn set_fullscreen(&self, monitor: Option<RootMonitorId>) {
+        if monitor.is_some() {
+            self.set_simple_fullscreen(false);
+        }
+        let state = &self.delegate.state;
+        let current = state.win_attribs.borrow().fullscreen.clone();
+        match (current, monitor) {
+            (None, None) => (),
+            (Some(ref a), Some(ref b)) if a.inner == b.inner => (),
+            (Some(_), Some(monitor)) => {
+                // A fullscreen window can't move to another screen, so it leaves fullscreen, and
+                // enters it again on the new screen in `window_did_exit_fullscreen`.
+                *state.pending_fullscreen_monitor.borrow_mut() = Some(monitor);
+                unsafe { self.window.toggleFullScreen_(nil) };
+            },
+            (None, Some(_)) => unsafe { state.enter_fullscreen() },
+            (Some(_), None) => unsafe { self.window.toggleFullScreen_(nil) },
         }
     }
 
@@ -1135,6 +1932,51 @@ impl Window2 {
         }
     }
 
+    pub fn set_badge_label(&self, label: Option<&str>) {
+        unsafe {
+            let dock_tile: id = msg_send![appkit::NSApp(), dockTile];
+            let current_label: id = msg_send![dock_tile, badgeLabel];
+            let current_label = if current_label == nil {
+                None
+            } else {
+                Some(CStr::from_ptr(current_label.UTF8String()).to_string_lossy())
+            };
+            // Setting a label redraws the dock tile, even if it's the same one.
+            if current_label.as_ref().map(|label| &**label) == label {
+                return;
+            }
+            match label {
+                Some(label) => {
+                    let label = IdRef::new(NSString::alloc(nil).init_str(label));
+                    let _: () = msg_send![dock_tile, setBadgeLabel:*label];
+                },
+                None => {
+                    let _: () = msg_send![dock_tile, setBadgeLabel:nil];
+                },
+            }
+        }
+    }
+
+    // Sends an action that's only implemented by `NSWindow` on macOS 10.12 and later.
+    fn send_tab_action(&self, action: Sel) {
+        unsafe {
+            let supported: BOOL = msg_send![*self.window, respondsToSelector:action];
+            if supported == YES {
+                let _: id = msg_send![*self.window, performSelector:action withObject:nil];
+            }
+        }
+    }
+
+    #[inline]
+    pub fn set_progress(&self, _progress: Option<f64>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_overlay_icon(&self, _icon: Option<::Icon>) {
+        // N/A: the dock can only show badge labels.
+    }
+
     #[inline]
     pub fn set_window_icon(&self, _icon: Option<::Icon>) {
         // macOS doesn't have window icons. Though, there is `setRepresentedFilename`, but that's
@@ -1146,11 +1988,224 @@ impl Window2 {
         // https://developer.apple.com/library/content/documentation/Cocoa/Conceptual/WinPanel/Tasks/SettingWindowTitle.html
     }
 
+    #[inline]
+    pub fn set_menu(&self, menu: Option<::menu::Menu>) {
+        // The menu bar is shared by every window, so this is the same as setting it on the app.
+        menu::set_menu(Weak::clone(&self.delegate.state.shared), menu);
+    }
+
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, _visible: bool) {
+        // N/A
+    }
+
+    pub fn capture_frame(&self) -> Option<CapturedFrame> {
+        unsafe {
+            let content_rect = NSWindow::contentRectForFrameRect_(
+                *self.window,
+                NSWindow::frame(*self.window),
+            );
+            // The area to capture is in screen coordinates, with the origin at the top-left.
+            let bounds = CGRect::new(
+                &CGPoint::new(content_rect.origin.x, util::bottom_left_to_top_left(content_rect)),
+                &CGSize::new(content_rect.size.width, content_rect.size.height),
+            );
+            let window_number: cocoa::foundation::NSInteger = msg_send![*self.window, windowNumber];
+            let image = cg_window::create_image(
+                bounds,
+                kCGWindowListOptionIncludingWindow,
+                window_number as CGWindowID,
+                kCGWindowImageBoundsIgnoreFraming,
+            )?;
+            let (width, height) = (image.width(), image.height());
+            if width == 0 || height == 0 {
+                return None;
+            }
+
+            // The image can be in any format, so we let Core Graphics convert it by drawing it.
+            let mut context = CGContext::create_bitmap_context(
+                None,
+                width,
+                height,
+                8,
+                width * 4,
+                &CGColorSpace::create_device_rgb(),
+                kCGImageAlphaPremultipliedLast | kCGBitmapByteOrder32Big,
+            );
+            context.draw_image(
+                CGRect::new(
+                    &CGPoint::new(0.0, 0.0),
+                    &CGSize::new(width as CGFloat, height as CGFloat),
+                ),
+                &image,
+            );
+            let rgba = context.data().to_vec();
+            Some(CapturedFrame { width: width as u32, height: height as u32, rgba })
+        }
+    }
+
     #[inline]
     pub fn set_ime_spot(&self, logical_spot: LogicalPosition) {
         set_ime_spot(*self.view, *self.input_context, logical_spot.x, logical_spot.y);
     }
 
+    pub fn set_ime_conversion_mode(&self, mode: ImeMode) -> Result<(), String> {
+        unsafe {
+            let input_context = *self.input_context;
+            let allowed_locales = if mode == ImeMode::Direct {
+                // `NSAllRomanInputSourcesLocaleIdentifier`
+                let roman = IdRef::new(NSString::alloc(nil).init_str("AppleAllRoman"));
+                NSArray::arrayWithObject(nil, *roman)
+            } else {
+                nil
+            };
+            let _: () = msg_send![input_context, setAllowedInputSourceLocales:allowed_locales];
+            if mode == ImeMode::Default || mode == ImeMode::Direct {
+                return Ok(());
+            }
+
+            // There's no notion of conversion mode on macOS, so instead an enabled input source
+            // matching the mode is selected, going by the identifiers Apple's input methods use.
+            let sources: id = msg_send![input_context, keyboardInputSources];
+            let count: NSUInteger = if sources != nil { msg_send![sources, count] } else { 0 };
+            for i in 0..count {
+                let source: id = msg_send![sources, objectAtIndex:i];
+                let identifier = CStr::from_ptr(source.UTF8String()).to_string_lossy();
+                if !identifier.contains(".inputmethod.") {
+                    continue;
+                }
+                let is_katakana = identifier.ends_with(".Katakana");
+                let is_roman = identifier.ends_with("Roman") || identifier.ends_with(".HalfWidthKana");
+                let matches = match mode {
+                    ImeMode::Katakana => is_katakana,
+                    _ => !is_katakana && !is_roman,
+                };
+                if matches {
+                    let _: () = msg_send![input_context, setSelectedKeyboardInputSource:source];
+                    return Ok(());
+                }
+            }
+            Err(format!("No enabled input source matches {:?}.", mode))
+        }
+    }
+
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, callback: Option<HitTestCallback>) {
+        set_hit_test_callback(*self.view, callback);
+    }
+
+    pub fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), String> {
+        // Only a window that ignores mouse events lets clicks through to other applications. Views
+        // can merely decline the clicks outside of the region.
+        let ignores_mouse_events = region.map_or(false, |region| region.is_empty());
+        unsafe {
+            let _: () = msg_send![*self.window, setIgnoresMouseEvents:if ignores_mouse_events { YES } else { NO }];
+        }
+        set_input_region(*self.view, region.map(|region| region.to_vec()));
+        Ok(())
+    }
+
+    pub fn set_shape(&self, shape: Option<&[Rect]>) -> Result<(), String> {
+        let state = &self.delegate.state;
+        unsafe {
+            match shape {
+                Some(shape) => {
+                    let _: () = msg_send![*self.view, setWantsLayer:YES];
+
+                    let mask = IdRef::new(msg_send![class!(CALayer), new]);
+                    // Makes the sublayers relative to the top-left corner, so that they don't have
+                    // to move when the view is resized.
+                    let _: () = msg_send![*mask, setGeometryFlipped:YES];
+                    let color: id = msg_send![class!(NSColor), blackColor];
+                    let cg_color: id = msg_send![color, CGColor];
+                    for rect in shape {
+                        let sublayer: id = msg_send![class!(CALayer), new];
+                        let frame = NSRect::new(
+                            NSPoint::new(rect.position.x as CGFloat, rect.position.y as CGFloat),
+                            NSSize::new(rect.size.width as CGFloat, rect.size.height as CGFloat),
+                        );
+                        let _: () = msg_send![sublayer, setFrame:frame];
+                        let _: () = msg_send![sublayer, setBackgroundColor:cg_color];
+                        let _: () = msg_send![*mask, addSublayer:sublayer];
+                        let _: () = msg_send![sublayer, release];
+                    }
+                    *state.shape_mask.borrow_mut() = Some(mask);
+                    state.update_shape_mask();
+                },
+                None => {
+                    if state.shape_mask.borrow_mut().take().is_some() {
+                        let layer: id = msg_send![*self.view, layer];
+                        let _: () = msg_send![layer, setMask:nil];
+                        let _: () = msg_send![*self.window, invalidateShadow];
+                    }
+                },
+            }
+            state.update_opacity();
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn reserve_screen_edge(&self, _reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        Err("Only the Dock and the menu bar can reserve screen space on macOS.".to_owned())
+    }
+
+    pub fn set_blur(&self, blur: bool) -> Result<(), String> {
+        let state = &self.delegate.state;
+        if blur == state.blur_view.borrow().is_some() {
+            return Ok(());
+        }
+        unsafe {
+            if blur {
+                let class = Class::get("NSVisualEffectView")
+                    .ok_or_else(|| "`NSVisualEffectView` requires macOS 10.10.".to_owned())?;
+                let frame = NSView::frame(*self.view);
+                let blur_view: id = msg_send![class, alloc];
+                let blur_view = IdRef::new(msg_send![blur_view, initWithFrame:frame]);
+                // NSVisualEffectBlendingModeBehindWindow
+                let _: () = msg_send![*blur_view, setBlendingMode:0 as cocoa::foundation::NSInteger];
+                // NSVisualEffectStateActive, so that the blur doesn't go away when the window
+                // loses focus.
+                let _: () = msg_send![*blur_view, setState:1 as cocoa::foundation::NSInteger];
+                // Subviews are drawn above their superview, so the view is moved into the effect
+                // view, which takes its place as the content view.
+                let resizable = appkit::NSViewWidthSizable | appkit::NSViewHeightSizable;
+                let _: () = msg_send![*self.view, setAutoresizingMask:resizable as NSUInteger];
+                self.window.setContentView_(*blur_view);
+                let _: () = msg_send![*blur_view, addSubview:*self.view];
+                let bounds = NSView::bounds(*blur_view);
+                let _: () = msg_send![*self.view, setFrame:bounds];
+                *state.blur_view.borrow_mut() = Some(blur_view);
+            } else {
+                let _: () = msg_send![*self.view, removeFromSuperview];
+                self.window.setContentView_(*self.view);
+                *state.blur_view.borrow_mut() = None;
+            }
+            // Moving the view out of the window makes it resign first responder.
+            self.window.makeFirstResponder_(*self.view);
+            state.update_opacity();
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_accessibility_adapter(&self, adapter: Option<::accessibility::AccessibilityAdapter>) {
+        set_accessibility_adapter(*self.view, adapter);
+    }
+
+    /// Whether this is the frontmost of the application's visible windows.
+    pub fn is_frontmost(&self) -> bool {
+        let frontmost = unsafe { util::get_visible_windows_front_to_back() }
+            .into_iter()
+            .next();
+        frontmost == Some(*self.window)
+    }
+
     #[inline]
     pub fn get_current_monitor(&self) -> RootMonitorId {
         unsafe {
@@ -1220,7 +2275,6 @@ impl IdRef {
         IdRef(i)
     }
 
-    #[allow(dead_code)]
     pub fn retain(i: id) -> IdRef {
         if i != nil {
             let _: id = unsafe { msg_send![i, retain] };
@@ -1264,3 +2318,8 @@ impl Clone for IdRef {
 extern fn yes(_: &Object, _: Sel) -> BOOL {
     YES
 }
+
+extern fn can_become_key_window(this: &Object, _: Sel) -> BOOL {
+    let not_focusable: BOOL = unsafe { *this.get_ivar("winitNotFocusable") };
+    if not_focusable == YES { NO } else { YES }
+}
@@ -18,9 +18,12 @@ mod platform;
 #[cfg(target_os = "emscripten")]
 #[path="emscripten/mod.rs"]
 mod platform;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[path="web/mod.rs"]
+mod platform;
 
 #[cfg(all(not(target_os = "ios"), not(target_os = "windows"), not(target_os = "linux"),
   not(target_os = "macos"), not(target_os = "android"), not(target_os = "dragonfly"),
   not(target_os = "freebsd"), not(target_os = "netbsd"), not(target_os = "openbsd"),
-  not(target_os = "emscripten")))]
+  not(target_os = "emscripten"), not(all(target_arch = "wasm32", target_os = "unknown"))))]
 compile_error!("The platform you're compiling for is not supported by winit");
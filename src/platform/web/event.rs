@@ -0,0 +1,191 @@
+use web_sys::{KeyboardEvent, MouseEvent};
+
+use {ModifiersState, MouseButton, VirtualKeyCode};
+
+pub fn mouse_modifiers(event: &MouseEvent) -> ModifiersState {
+    ModifiersState {
+        shift: event.shift_key(),
+        ctrl: event.ctrl_key(),
+        alt: event.alt_key(),
+        logo: event.meta_key(),
+    }
+}
+
+pub fn keyboard_modifiers(event: &KeyboardEvent) -> ModifiersState {
+    ModifiersState {
+        shift: event.shift_key(),
+        ctrl: event.ctrl_key(),
+        alt: event.alt_key(),
+        logo: event.meta_key(),
+    }
+}
+
+pub fn mouse_button(event: &MouseEvent) -> MouseButton {
+    match event.button() {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        other => MouseButton::Other(other as u8),
+    }
+}
+
+/// Returns the character typed by a key press, if it typed exactly one.
+///
+/// `key` is either the character or the name of a key that doesn't type one, e.g. "Enter".
+pub fn received_character(event: &KeyboardEvent) -> Option<char> {
+    if event.ctrl_key() || event.meta_key() {
+        // These are shortcuts rather than text.
+        return None;
+    }
+    let key = event.key();
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => Some(character),
+        _ => None,
+    }
+}
+
+/// Translates the `code` of a keyboard event, which identifies the physical key as if it were on a
+/// US keyboard.
+pub fn virtual_key_code(event: &KeyboardEvent) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+    Some(match &event.code()[..] {
+        "Digit1" => Key1,
+        "Digit2" => Key2,
+        "Digit3" => Key3,
+        "Digit4" => Key4,
+        "Digit5" => Key5,
+        "Digit6" => Key6,
+        "Digit7" => Key7,
+        "Digit8" => Key8,
+        "Digit9" => Key9,
+        "Digit0" => Key0,
+        "KeyA" => A,
+        "KeyB" => B,
+        "KeyC" => C,
+        "KeyD" => D,
+        "KeyE" => E,
+        "KeyF" => F,
+        "KeyG" => G,
+        "KeyH" => H,
+        "KeyI" => I,
+        "KeyJ" => J,
+        "KeyK" => K,
+        "KeyL" => L,
+        "KeyM" => M,
+        "KeyN" => N,
+        "KeyO" => O,
+        "KeyP" => P,
+        "KeyQ" => Q,
+        "KeyR" => R,
+        "KeyS" => S,
+        "KeyT" => T,
+        "KeyU" => U,
+        "KeyV" => V,
+        "KeyW" => W,
+        "KeyX" => X,
+        "KeyY" => Y,
+        "KeyZ" => Z,
+        "Escape" => Escape,
+        "F1" => F1,
+        "F2" => F2,
+        "F3" => F3,
+        "F4" => F4,
+        "F5" => F5,
+        "F6" => F6,
+        "F7" => F7,
+        "F8" => F8,
+        "F9" => F9,
+        "F10" => F10,
+        "F11" => F11,
+        "F12" => F12,
+        "F13" => F13,
+        "F14" => F14,
+        "F15" => F15,
+        "PrintScreen" => Snapshot,
+        "ScrollLock" => Scroll,
+        "Pause" => Pause,
+        "Insert" => Insert,
+        "Home" => Home,
+        "Delete" => Delete,
+        "End" => End,
+        "PageDown" => PageDown,
+        "PageUp" => PageUp,
+        "ArrowLeft" => Left,
+        "ArrowUp" => Up,
+        "ArrowRight" => Right,
+        "ArrowDown" => Down,
+        "Backspace" => Back,
+        "Enter" => Return,
+        "Space" => Space,
+        "NumLock" => Numlock,
+        "Numpad0" => Numpad0,
+        "Numpad1" => Numpad1,
+        "Numpad2" => Numpad2,
+        "Numpad3" => Numpad3,
+        "Numpad4" => Numpad4,
+        "Numpad5" => Numpad5,
+        "Numpad6" => Numpad6,
+        "Numpad7" => Numpad7,
+        "Numpad8" => Numpad8,
+        "Numpad9" => Numpad9,
+        "NumpadAdd" => Add,
+        "NumpadComma" => NumpadComma,
+        "NumpadDecimal" => Decimal,
+        "NumpadDivide" => Divide,
+        "NumpadEnter" => NumpadEnter,
+        "NumpadEqual" => NumpadEquals,
+        "NumpadMultiply" => Multiply,
+        "NumpadSubtract" => Subtract,
+        "Quote" => Apostrophe,
+        "ContextMenu" => Apps,
+        "Backslash" => Backslash,
+        "CapsLock" => Capital,
+        "Comma" => Comma,
+        "Convert" => Convert,
+        "Equal" => Equals,
+        "Backquote" => Grave,
+        "KanaMode" => Kana,
+        "AltLeft" => LAlt,
+        "BracketLeft" => LBracket,
+        "ControlLeft" => LControl,
+        "ShiftLeft" => LShift,
+        "MetaLeft" | "OSLeft" => LWin,
+        "LaunchMail" => Mail,
+        "MediaSelect" => MediaSelect,
+        "MediaStop" => MediaStop,
+        "Minus" => Minus,
+        "AudioVolumeMute" | "VolumeMute" => Mute,
+        "MediaTrackNext" => NextTrack,
+        "NonConvert" => NoConvert,
+        "IntlBackslash" => OEM102,
+        "Period" => Period,
+        "MediaPlayPause" => PlayPause,
+        "Power" => Power,
+        "MediaTrackPrevious" => PrevTrack,
+        "AltRight" => RAlt,
+        "BracketRight" => RBracket,
+        "ControlRight" => RControl,
+        "ShiftRight" => RShift,
+        "MetaRight" | "OSRight" => RWin,
+        "Semicolon" => Semicolon,
+        "Slash" => Slash,
+        "Sleep" => Sleep,
+        "Tab" => Tab,
+        "AudioVolumeDown" | "VolumeDown" => VolumeDown,
+        "AudioVolumeUp" | "VolumeUp" => VolumeUp,
+        "WakeUp" => Wake,
+        "BrowserBack" => WebBack,
+        "BrowserFavorites" => WebFavorites,
+        "BrowserForward" => WebForward,
+        "BrowserHome" => WebHome,
+        "BrowserRefresh" => WebRefresh,
+        "BrowserSearch" => WebSearch,
+        "BrowserStop" => WebStop,
+        "IntlYen" => Yen,
+        "Copy" => Copy,
+        "Paste" => Paste,
+        "Cut" => Cut,
+        _ => return None,
+    })
+}
@@ -0,0 +1,205 @@
+use std::{mem, time};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen;
+
+use {ControlFlow, Event, EventsLoopClosed};
+use super::{FocusSnapshot, MonitorId, WindowId};
+
+/// The state shared between the `EventsLoop` and the DOM listeners of its windows.
+pub struct Shared {
+    // Events received while nobody was there to handle them.
+    events: RefCell<VecDeque<Event>>,
+    // The callback passed to `run_forever`, until it returns `ControlFlow::Break`.
+    runner: RefCell<Option<Box<FnMut(Event) -> ControlFlow>>>,
+}
+
+impl Shared {
+    /// Delivers an event to the callback passed to `run_forever`, or queues it for the next call to
+    /// `poll_events` if there isn't one.
+    pub fn send_event(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+        self.run_pending();
+    }
+
+    fn run_pending(&self) {
+        // If the callback is already running, it caused this event, e.g. by focusing the canvas.
+        // It will be handled by the loop below once the callback returns.
+        let mut runner = match self.runner.try_borrow_mut() {
+            Ok(runner) => runner,
+            Err(_) => return,
+        };
+        loop {
+            let event = self.events.borrow_mut().pop_front();
+            let (callback, event) = match (runner.as_mut(), event) {
+                (Some(callback), Some(event)) => (callback, event),
+                (None, Some(event)) => {
+                    self.events.borrow_mut().push_front(event);
+                    return;
+                },
+                (_, None) => return,
+            };
+            if callback(event) == ControlFlow::Break {
+                *runner = None;
+            }
+        }
+    }
+}
+
+pub struct EventsLoop {
+    shared: Rc<Shared>,
+}
+
+impl EventsLoop {
+    pub fn new() -> EventsLoop {
+        EventsLoop {
+            shared: Rc::new(Shared {
+                events: RefCell::new(VecDeque::new()),
+                runner: RefCell::new(None),
+            }),
+        }
+    }
+
+    // Nothing can fail to connect on this platform.
+    pub fn try_new() -> Result<EventsLoop, ::EventsLoopCreationError> {
+        Ok(EventsLoop::new())
+    }
+
+    #[inline]
+    pub(super) fn shared(&self) -> &Rc<Shared> {
+        &self.shared
+    }
+
+    #[inline]
+    pub fn create_proxy(&self) -> EventsLoopProxy {
+        EventsLoopProxy {
+            shared: Rc::downgrade(&self.shared),
+        }
+    }
+
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        None
+    }
+
+    pub fn shutdown(self) {
+        // No background threads to wait for.
+    }
+
+    #[inline]
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        Vec::new()
+    }
+
+    pub fn set_activation_policy(&mut self, _activation_policy: ::ActivationPolicy) {
+        // N/A
+    }
+
+    pub fn reserve_events(&mut self, capacity: usize) {
+        self.shared.events.borrow_mut().reserve(capacity);
+    }
+
+    pub fn block_session_end(&self, _reason: Option<&str>) {
+        // N/A
+    }
+
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        FocusSnapshot
+    }
+
+    pub fn restore_focus(&self, _snapshot: &FocusSnapshot) {
+        // N/A
+    }
+
+    pub fn keyboard_layouts(&self) -> Vec<::LayoutInfo> {
+        // N/A
+        Vec::new()
+    }
+
+    pub fn device_infos(&self) -> Vec<::DeviceInfo> {
+        // N/A
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
+        let mut list = VecDeque::with_capacity(1);
+        list.push_back(MonitorId);
+        list
+    }
+
+    #[inline]
+    pub fn get_primary_monitor(&self) -> MonitorId {
+        MonitorId
+    }
+
+    pub fn wait_for_vblank(&self) -> bool {
+        // The browser's thread can't block, and it paces animations by itself anyway.
+        true
+    }
+
+    pub fn poll_events<F>(&self, mut callback: F)
+        where F: FnMut(Event)
+    {
+        loop {
+            let event = self.shared.events.borrow_mut().pop_front();
+            match event {
+                Some(event) => callback(event),
+                None => break,
+            }
+        }
+    }
+
+    /// Hands the callback over to the DOM listeners and never returns.
+    ///
+    /// Browsers only deliver events once control returns to them, so we get there by throwing a
+    /// JavaScript exception. As with `emscripten_set_main_loop`, the stack isn't unwound, which
+    /// keeps anything the callback borrows from it alive.
+    pub fn run_forever<F>(&self, callback: F)
+        where F: FnMut(Event) -> ControlFlow
+    {
+        let callback: Box<FnMut(Event) -> ControlFlow + '_> = Box::new(callback);
+        let callback: Box<FnMut(Event) -> ControlFlow + 'static> = unsafe {
+            mem::transmute(callback)
+        };
+        *self.shared.runner.borrow_mut() = Some(callback);
+
+        // Handle whatever happened before we were called.
+        self.shared.run_pending();
+
+        wasm_bindgen::throw_str(
+            "Using exceptions for control flow, don't mind me. This isn't actually an error!"
+        );
+    }
+}
+
+/// Used to wake up the `EventsLoop` from inside its own callbacks, or from code run by the browser
+/// outside of them.
+#[derive(Clone)]
+pub struct EventsLoopProxy {
+    shared: Weak<Shared>,
+}
+
+// Without the `atomics` target feature, there are no other threads to send it to.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for EventsLoopProxy {}
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Sync for EventsLoopProxy {}
+
+impl EventsLoopProxy {
+    pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
+        let shared = self.shared.upgrade().ok_or(EventsLoopClosed)?;
+        shared.send_event(Event::Awakened);
+        Ok(())
+    }
+}
+
+// The idle time isn't available on this platform.
+pub enum IdleTimeQuery {}
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<time::Duration> {
+        match *self {}
+    }
+}
@@ -0,0 +1,183 @@
+#![cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+
+//! Runs inside a web browser, with each window drawing on an HTML canvas.
+//!
+//! Everything here happens on the browser's main thread, which is the only one there is.
+
+pub use self::events_loop::{EventsLoop, EventsLoopProxy, IdleTimeQuery};
+pub use self::window::{Window, WindowId};
+
+use web_sys;
+use web_sys::HtmlCanvasElement;
+
+use dpi::{PhysicalPosition, PhysicalSize};
+
+mod event;
+mod events_loop;
+mod window;
+
+#[derive(Clone, Default)]
+pub struct PlatformSpecificWindowBuilderAttributes {
+    pub canvas: Option<HtmlCanvasElement>,
+}
+
+// Without the `atomics` target feature, there are no other threads to send these to.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for PlatformSpecificWindowBuilderAttributes {}
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Sync for PlatformSpecificWindowBuilderAttributes {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceId;
+
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot;
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        None
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> DeviceId {
+        DeviceId
+    }
+}
+
+#[cfg(feature = "shell")]
+pub fn reveal_in_file_manager(_path: &::std::path::Path) -> Result<(), String> {
+    Err("There's no file manager to reveal files in on the web.".to_owned())
+}
+
+#[cfg(feature = "shell")]
+pub fn open_with_default_app(_path: &::std::path::Path) -> Result<(), String> {
+    Err("Opening files is not possible on the web.".to_owned())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId;
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        0
+    }
+
+    pub fn from_raw(_raw: u64) -> TrayIconId {
+        TrayIconId
+    }
+}
+
+#[cfg(feature = "tray")]
+pub struct TrayIcon;
+
+#[cfg(feature = "tray")]
+impl TrayIcon {
+    #[inline]
+    pub fn new(
+        _: &EventsLoop,
+        _: ::tray::TrayIconAttributes,
+    ) -> Result<TrayIcon, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId
+    }
+
+    #[inline]
+    pub fn set_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_tooltip(&self, _tooltip: &str) {
+        // N/A
+    }
+}
+
+#[cfg(feature = "surface")]
+pub struct Surface;
+
+#[cfg(feature = "surface")]
+impl Surface {
+    #[inline]
+    pub fn new(_: &Window) -> Result<Surface, ::CreationError> {
+        Err(::CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn native(&self) -> ::surface::NativeSurface {
+        unreachable!()
+    }
+
+    #[inline]
+    pub fn resize(&self, _: PhysicalSize) {
+        unreachable!()
+    }
+}
+
+/// The screen the browser is running on. Browsers don't tell us about any others.
+#[derive(Debug, Clone)]
+pub struct MonitorId;
+
+impl MonitorId {
+    #[inline]
+    pub fn get_name(&self) -> Option<String> {
+        Some("Browser".to_owned())
+    }
+
+    #[inline]
+    pub fn get_position(&self) -> PhysicalPosition {
+        (0, 0).into()
+    }
+
+    #[inline]
+    pub fn get_dimensions(&self) -> PhysicalSize {
+        let screen = match web_sys::window().and_then(|window| window.screen().ok()) {
+            Some(screen) => screen,
+            None => return (0, 0).into(),
+        };
+        let width = screen.width().unwrap_or(0) as f64;
+        let height = screen.height().unwrap_or(0) as f64;
+        // The screen's dimensions are given in CSS pixels.
+        let dpi_factor = get_hidpi_factor();
+        PhysicalSize::new(width * dpi_factor, height * dpi_factor)
+    }
+
+    #[inline]
+    pub fn get_hidpi_factor(&self) -> f64 {
+        get_hidpi_factor()
+    }
+
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        (self.get_position(), self.get_dimensions())
+    }
+
+    #[inline]
+    pub fn get_taskbar_edge(&self) -> Option<::Edge> {
+        None
+    }
+}
+
+/// The number of physical pixels per CSS pixel, which is what logical units are here.
+fn get_hidpi_factor() -> f64 {
+    web_sys::window()
+        .map(|window| window.device_pixel_ratio())
+        .unwrap_or(1.0)
+}
@@ -0,0 +1,799 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys;
+use web_sys::{
+    Document,
+    Element,
+    EventTarget,
+    FocusEvent,
+    HtmlCanvasElement,
+    KeyboardEvent,
+    MouseEvent,
+    TouchEvent,
+    WheelEvent,
+};
+
+use {
+    CreationError,
+    DeviceEvent,
+    ElementState,
+    Event,
+    Force,
+    KeyboardInput,
+    LogicalPosition,
+    LogicalSize,
+    MouseCursor,
+    MouseScrollDelta,
+    Touch,
+    TouchPhase,
+    WindowAttributes,
+    WindowEvent,
+};
+use window::MonitorId as RootMonitorId;
+use super::{event, DeviceId, EventsLoop, MonitorId, PlatformSpecificWindowBuilderAttributes};
+use super::events_loop::Shared;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowId(usize);
+
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        WindowId(raw as usize)
+    }
+}
+
+static NEXT_WINDOW_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A DOM event listener, which is removed when dropped.
+struct Listener {
+    target: EventTarget,
+    name: &'static str,
+    closure: Closure<FnMut(web_sys::Event)>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = self.target.remove_event_listener_with_callback(
+            self.name,
+            self.closure.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// The part of the window that its listeners need.
+struct WindowState {
+    id: WindowId,
+    canvas: HtmlCanvasElement,
+    shared: Weak<Shared>,
+    size: Cell<LogicalSize>,
+    hidpi_factor: Cell<f64>,
+}
+
+impl WindowState {
+    fn send_event(&self, event: WindowEvent) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.send_event(Event::WindowEvent {
+                window_id: ::WindowId(self.id),
+                event,
+            });
+        }
+    }
+
+    fn send_device_event(&self, event: DeviceEvent) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.send_event(Event::DeviceEvent {
+                device_id: ::DeviceId(DeviceId),
+                event,
+            });
+        }
+    }
+
+    /// Makes the canvas's drawing buffer match its size on the page, which is what the inner size
+    /// of the window is.
+    fn update_size(&self) {
+        let hidpi_factor = super::get_hidpi_factor();
+        if hidpi_factor != self.hidpi_factor.get() {
+            self.hidpi_factor.set(hidpi_factor);
+            self.send_event(WindowEvent::HiDpiFactorChanged(hidpi_factor));
+        }
+
+        let size = LogicalSize::new(
+            self.canvas.client_width() as f64,
+            self.canvas.client_height() as f64,
+        );
+        let physical_size = size.to_physical(hidpi_factor);
+        self.canvas.set_width(physical_size.width.round() as u32);
+        self.canvas.set_height(physical_size.height.round() as u32);
+        if size != self.size.get() {
+            self.size.set(size);
+            self.send_event(WindowEvent::Resized(size));
+        }
+    }
+
+    /// Converts a position relative to the browser's viewport, as found in touch events.
+    fn client_to_canvas(&self, x: f64, y: f64) -> LogicalPosition {
+        let rect = self.canvas.get_bounding_client_rect();
+        LogicalPosition::new(x - rect.left(), y - rect.top())
+    }
+}
+
+pub struct Window {
+    state: Rc<WindowState>,
+    document: Document,
+    // Whether we created the canvas, in which case we remove it again.
+    owns_canvas: bool,
+    cursor: Cell<MouseCursor>,
+    cursor_hidden: Cell<bool>,
+    _listeners: Vec<Listener>,
+}
+
+// Without the `atomics` target feature, there are no other threads to send it to.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for Window {}
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Sync for Window {}
+
+impl Window {
+    pub fn new(
+        events_loop: &EventsLoop,
+        attribs: WindowAttributes,
+        pl_attribs: PlatformSpecificWindowBuilderAttributes,
+    ) -> Result<Window, CreationError> {
+        if attribs.parent.is_some() {
+            return Err(CreationError::InvalidAttributes(
+                "Windows can't be created in a parent window on the web.".to_owned(),
+            ));
+        }
+        let window = web_sys::window()
+            .ok_or_else(|| CreationError::OsError("`window` isn't available".to_owned()))?;
+        let document = window.document()
+            .ok_or_else(|| CreationError::OsError("`document` isn't available".to_owned()))?;
+
+        let (canvas, owns_canvas) = match pl_attribs.canvas {
+            Some(canvas) => (canvas, false),
+            None => {
+                let canvas = document.create_element("canvas")
+                    .ok()
+                    .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+                    .ok_or_else(|| CreationError::OsError("Couldn't create a canvas".to_owned()))?;
+                let body = document.body()
+                    .ok_or_else(|| CreationError::OsError("`document` has no body".to_owned()))?;
+                body.append_child(&canvas)
+                    .map_err(|_| CreationError::OsError("Couldn't add the canvas".to_owned()))?;
+                (canvas, true)
+            },
+        };
+        // Otherwise, the canvas can't get the keyboard focus.
+        if attribs.focusable {
+            let _ = canvas.set_attribute("tabindex", "0");
+        }
+
+        let state = Rc::new(WindowState {
+            id: WindowId(NEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed)),
+            canvas,
+            shared: Rc::downgrade(events_loop.shared()),
+            size: Cell::new(LogicalSize::new(0.0, 0.0)),
+            hidpi_factor: Cell::new(super::get_hidpi_factor()),
+        });
+
+        let listeners = vec![
+            listen(&state, state.canvas.as_ref(), "mousedown", |state, event: MouseEvent| {
+                state.send_event(WindowEvent::MouseInput {
+                    device_id: ::DeviceId(DeviceId),
+                    state: ElementState::Pressed,
+                    button: event::mouse_button(&event),
+                    modifiers: event::mouse_modifiers(&event),
+                });
+            }),
+            listen(&state, state.canvas.as_ref(), "mouseup", |state, event: MouseEvent| {
+                state.send_event(WindowEvent::MouseInput {
+                    device_id: ::DeviceId(DeviceId),
+                    state: ElementState::Released,
+                    button: event::mouse_button(&event),
+                    modifiers: event::mouse_modifiers(&event),
+                });
+            }),
+            listen(&state, state.canvas.as_ref(), "mousemove", |state, event: MouseEvent| {
+                state.send_event(WindowEvent::CursorMoved {
+                    device_id: ::DeviceId(DeviceId),
+                    position: LogicalPosition::new(
+                        event.offset_x() as f64,
+                        event.offset_y() as f64,
+                    ),
+                    modifiers: event::mouse_modifiers(&event),
+                });
+                state.send_device_event(DeviceEvent::MouseMotion {
+                    delta: (event.movement_x() as f64, event.movement_y() as f64),
+                });
+            }),
+            listen(&state, state.canvas.as_ref(), "mouseenter", |state, _: MouseEvent| {
+                state.send_event(WindowEvent::CursorEntered { device_id: ::DeviceId(DeviceId) });
+            }),
+            listen(&state, state.canvas.as_ref(), "mouseleave", |state, _: MouseEvent| {
+                state.send_event(WindowEvent::CursorLeft { device_id: ::DeviceId(DeviceId) });
+            }),
+            listen(&state, state.canvas.as_ref(), "wheel", |state, event: WheelEvent| {
+                // Keeps the page from scrolling.
+                event.prevent_default();
+                // Browsers consider scrolling down to be positive.
+                let (x, y) = (-event.delta_x(), -event.delta_y());
+                let delta = match event.delta_mode() {
+                    WheelEvent::DOM_DELTA_PIXEL => {
+                        MouseScrollDelta::PixelDelta(LogicalPosition::new(x, y))
+                    },
+                    _ => MouseScrollDelta::LineDelta(x as f32, y as f32),
+                };
+                state.send_event(WindowEvent::MouseWheel {
+                    device_id: ::DeviceId(DeviceId),
+                    delta,
+                    phase: TouchPhase::Moved,
+                    momentum_phase: None,
+                    modifiers: event::mouse_modifiers(&event),
+                });
+            }),
+            listen(&state, state.canvas.as_ref(), "contextmenu", |_, event: MouseEvent| {
+                // Right clicks are for the application to handle.
+                event.prevent_default();
+            }),
+            listen(&state, state.canvas.as_ref(), "keydown", |state, event: KeyboardEvent| {
+                let character = event::received_character(&event);
+                if character.is_some() || !(event.ctrl_key() || event.meta_key()) {
+                    // Keeps keys like space and tab from scrolling the page or moving the focus,
+                    // but leaves the browser's shortcuts alone.
+                    event.prevent_default();
+                }
+                send_keyboard_input(&state, &event, ElementState::Pressed, character);
+                if let Some(character) = character {
+                    state.send_event(WindowEvent::ReceivedCharacter(character));
+                }
+            }),
+            listen(&state, state.canvas.as_ref(), "keyup", |state, event: KeyboardEvent| {
+                send_keyboard_input(&state, &event, ElementState::Released, None);
+            }),
+            listen(&state, state.canvas.as_ref(), "focus", |state, _: FocusEvent| {
+                state.send_event(WindowEvent::Focused(true));
+            }),
+            listen(&state, state.canvas.as_ref(), "blur", |state, _: FocusEvent| {
+                state.send_event(WindowEvent::Focused(false));
+            }),
+            listen(&state, state.canvas.as_ref(), "touchstart", |state, event: TouchEvent| {
+                send_touches(&state, &event, TouchPhase::Started);
+            }),
+            listen(&state, state.canvas.as_ref(), "touchmove", |state, event: TouchEvent| {
+                send_touches(&state, &event, TouchPhase::Moved);
+            }),
+            listen(&state, state.canvas.as_ref(), "touchend", |state, event: TouchEvent| {
+                send_touches(&state, &event, TouchPhase::Ended);
+            }),
+            listen(&state, state.canvas.as_ref(), "touchcancel", |state, event: TouchEvent| {
+                send_touches(&state, &event, TouchPhase::Cancelled);
+            }),
+            // Zooming the page changes the device pixel ratio, and also fires this.
+            listen(&state, window.as_ref(), "resize", |state, _: web_sys::Event| {
+                state.update_size();
+            }),
+            listen(&state, document.as_ref(), "visibilitychange", |state, _: web_sys::Event| {
+                let hidden = web_sys::window()
+                    .and_then(|window| window.document())
+                    .map(|document| document.hidden())
+                    .unwrap_or(false);
+                state.send_event(WindowEvent::Occluded(hidden));
+            }),
+        ];
+
+        let window = Window {
+            state,
+            document,
+            owns_canvas,
+            cursor: Cell::new(MouseCursor::Default),
+            cursor_hidden: Cell::new(false),
+            _listeners: listeners,
+        };
+
+        // A canvas that was given to us is part of a page that has its own title.
+        if owns_canvas {
+            window.set_title(&attribs.title);
+        }
+        match attribs.dimensions {
+            Some(size) => window.set_inner_size(size),
+            None => window.state.update_size(),
+        }
+        if !attribs.visible {
+            window.hide();
+        }
+        if attribs.fullscreen.is_some() {
+            window.set_fullscreen(attribs.fullscreen);
+        }
+
+        Ok(window)
+    }
+
+    #[inline]
+    pub fn get_canvas(&self) -> &HtmlCanvasElement {
+        &self.state.canvas
+    }
+
+    #[inline]
+    pub fn id(&self) -> WindowId {
+        self.state.id
+    }
+
+    #[inline]
+    pub fn set_title(&self, title: &str) {
+        self.document.set_title(title);
+    }
+
+    #[inline]
+    pub fn get_position(&self) -> Option<LogicalPosition> {
+        self.get_inner_position()
+    }
+
+    #[inline]
+    pub fn get_inner_position(&self) -> Option<LogicalPosition> {
+        // Relative to the browser's viewport.
+        let rect = self.state.canvas.get_bounding_client_rect();
+        Some(LogicalPosition::new(rect.left(), rect.top()))
+    }
+
+    #[inline]
+    pub fn set_outer_position(&self, _: LogicalPosition) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, _: LogicalPosition) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn get_inner_size(&self) -> Option<LogicalSize> {
+        Some(self.state.size.get())
+    }
+
+    #[inline]
+    pub fn get_outer_size(&self) -> Option<LogicalSize> {
+        self.get_inner_size()
+    }
+
+    #[inline]
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        Some(::FrameExtents::default())
+    }
+
+    #[inline]
+    pub fn set_inner_size(&self, size: LogicalSize) {
+        let style = self.state.canvas.style();
+        let _ = style.set_property("width", &format!("{}px", size.width));
+        let _ = style.set_property("height", &format!("{}px", size.height));
+        self.state.update_size();
+    }
+
+    #[inline]
+    pub fn set_min_dimensions(&self, _dimensions: Option<LogicalSize>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_max_dimensions(&self, _dimensions: Option<LogicalSize>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_resizable(&self, _resizable: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn show(&self) {
+        let _ = self.state.canvas.style().remove_property("display");
+    }
+
+    #[inline]
+    pub fn hide(&self) {
+        let _ = self.state.canvas.style().set_property("display", "none");
+    }
+
+    #[inline]
+    pub fn present_ready(&self) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_cursor(&self, cursor: MouseCursor) {
+        self.cursor.set(cursor);
+        if !self.cursor_hidden.get() {
+            self.apply_cursor(cursor_name(cursor));
+        }
+    }
+
+    #[inline]
+    pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
+        if grab {
+            // Browsers only grant this in response to user input.
+            self.state.canvas.request_pointer_lock();
+        } else if self.document.pointer_lock_element().is_some() {
+            self.document.exit_pointer_lock();
+        }
+        Ok(())
+    }
+
+    // Pointer lock is the only kind of grab there is, so it's what `grab_cursor` does.
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, mode: ::CursorGrabMode) -> Result<(), String> {
+        match mode {
+            ::CursorGrabMode::None => self.grab_cursor(false),
+            ::CursorGrabMode::Locked => self.grab_cursor(true),
+            ::CursorGrabMode::Confined => Err("Browsers can only lock the cursor, not confine it.".to_owned()),
+        }
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, _region: Option<::Rect>) -> Result<(), String> {
+        Err("Browsers can only lock the cursor, not confine it.".to_owned())
+    }
+
+    #[inline]
+    pub fn hide_cursor(&self, hide: bool) {
+        self.cursor_hidden.set(hide);
+        if hide {
+            self.apply_cursor("none");
+        } else {
+            self.apply_cursor(cursor_name(self.cursor.get()));
+        }
+    }
+
+    fn apply_cursor(&self, name: &str) {
+        let _ = self.state.canvas.style().set_property("cursor", name);
+    }
+
+    #[inline]
+    pub fn get_hidpi_factor(&self) -> f64 {
+        super::get_hidpi_factor()
+    }
+
+    #[inline]
+    pub fn set_cursor_position(&self, _position: LogicalPosition) -> Result<(), String> {
+        Err("Setting cursor position is not possible in browsers.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_maximized(&self, _maximized: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_minimized(&self, _minimized: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        let canvas: &Element = self.state.canvas.as_ref();
+        self.document.fullscreen_element().map_or(false, |element| &element == canvas)
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.state.canvas
+            .style()
+            .get_property_value("display")
+            .map_or(true, |display| display != "none")
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        let canvas: &Element = self.state.canvas.as_ref();
+        self.document.active_element().map_or(false, |element| &element == canvas)
+    }
+
+    #[inline]
+    pub fn focus(&self) {
+        let _ = self.state.canvas.focus();
+    }
+
+    pub fn set_focusable(&self, focusable: bool) {
+        if focusable {
+            let _ = self.state.canvas.set_attribute("tabindex", "0");
+        } else {
+            let _ = self.state.canvas.remove_attribute("tabindex");
+        }
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        ::Insets::default()
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_fullscreen(&self, monitor: Option<::MonitorId>) {
+        if monitor.is_some() {
+            // Browsers only grant this in response to user input.
+            let _ = self.state.canvas.request_fullscreen();
+        } else if self.document.fullscreen_element().is_some() {
+            self.document.exit_fullscreen();
+        }
+    }
+
+    #[inline]
+    pub fn set_decorations(&self, _decorations: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_always_on_top(&self, _always_on_top: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_window_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_badge_label(&self, _label: Option<&str>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_progress(&self, _progress: Option<f64>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_overlay_icon(&self, _icon: Option<::Icon>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_ime_conversion_mode(&self, _mode: ::ImeMode) -> Result<(), String> {
+        Err("Browsers don't let pages set the IME conversion mode.".to_owned())
+    }
+
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, _callback: Option<::HitTestCallback>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_input_region(&self, region: Option<&[::Rect]>) -> Result<(), String> {
+        let style = self.state.canvas.style();
+        match region {
+            None => {
+                let _ = style.remove_property("pointer-events");
+            },
+            Some(region) if region.is_empty() => {
+                let _ = style.set_property("pointer-events", "none");
+            },
+            Some(_) => return Err("Browsers can only make the whole canvas click-through.".to_owned()),
+        }
+        Ok(())
+    }
+
+    pub fn set_shape(&self, shape: Option<&[::Rect]>) -> Result<(), String> {
+        let style = self.state.canvas.style();
+        let result = match shape {
+            None => style.remove_property("clip-path").map(|_| ()),
+            // An empty path isn't valid, so the clip is shrunk to nothing instead.
+            Some(shape) if shape.is_empty() => style.set_property("clip-path", "inset(50%)"),
+            Some(shape) => {
+                // CSS pixels are logical pixels. All the rectangles wind the same way, so the
+                // path covers their union.
+                let subpaths: Vec<_> = shape.iter().map(|rect| format!(
+                    "M {} {} h {} v {} h {} Z",
+                    rect.position.x,
+                    rect.position.y,
+                    rect.size.width,
+                    rect.size.height,
+                    -rect.size.width,
+                )).collect();
+                style.set_property("clip-path", &format!("path('{}')", subpaths.join(" ")))
+            },
+        };
+        result.map_err(|_| "The browser doesn't support `clip-path`.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_blur(&self, _blur: bool) -> Result<(), String> {
+        Err("Blurring what's behind a window is not supported on the web.".to_owned())
+    }
+
+    #[inline]
+    pub fn reserve_screen_edge(&self, _reservation: Option<(::Edge, f64)>) -> Result<(), String> {
+        Err("Reserving screen space is not supported on the web.".to_owned())
+    }
+
+    #[inline]
+    pub fn set_menu(&self, _menu: Option<::menu::Menu>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_accessibility_adapter(&self, _adapter: Option<::accessibility::AccessibilityAdapter>) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, _visible: bool) {
+        // N/A
+    }
+
+    #[inline]
+    pub fn capture_frame(&self) -> Option<::CapturedFrame> {
+        None
+    }
+
+    #[inline]
+    pub fn is_frontmost(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn get_current_monitor(&self) -> RootMonitorId {
+        RootMonitorId { inner: MonitorId }
+    }
+
+    #[inline]
+    pub fn get_available_monitors(&self) -> VecDeque<MonitorId> {
+        let mut list = VecDeque::with_capacity(1);
+        list.push_back(MonitorId);
+        list
+    }
+
+    #[inline]
+    pub fn get_primary_monitor(&self) -> MonitorId {
+        MonitorId
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        if self.owns_canvas {
+            self.state.canvas.remove();
+        }
+    }
+}
+
+// Adds a listener for `name` events on `target`, which is removed when the result is dropped.
+fn listen<E, F>(state: &Rc<WindowState>, target: &EventTarget, name: &'static str, mut handler: F)
+    -> Listener
+    where E: JsCast, F: FnMut(&WindowState, E) + 'static
+{
+    let state = state.clone();
+    let closure = Closure::wrap(Box::new(move |event: web_sys::Event| {
+        handler(&state, event.unchecked_into::<E>());
+    }) as Box<FnMut(web_sys::Event)>);
+    let _ = target.add_event_listener_with_callback(name, closure.as_ref().unchecked_ref());
+    Listener { target: target.clone(), name, closure }
+}
+
+fn send_keyboard_input(
+    state: &WindowState,
+    event: &KeyboardEvent,
+    key_state: ElementState,
+    character: Option<char>,
+) {
+    state.send_event(WindowEvent::KeyboardInput {
+        device_id: ::DeviceId(DeviceId),
+        input: KeyboardInput {
+            scancode: event.key_code(),
+            state: key_state,
+            virtual_keycode: event::virtual_key_code(event),
+            modifiers: event::keyboard_modifiers(event),
+            is_composing: event.is_composing(),
+            is_synthetic: false,
+            is_repeat: event.repeat(),
+            text: character.map(|character| character.to_string()),
+            is_dead_key: key_state == ElementState::Pressed && event.key() == "Dead",
+        },
+    });
+}
+
+fn send_touches(state: &WindowState, event: &TouchEvent, phase: TouchPhase) {
+    // Otherwise, the browser scrolls the page and generates mouse events of its own.
+    event.prevent_default();
+    let touches = event.changed_touches();
+    for index in 0..touches.length() {
+        let touch = match touches.get(index) {
+            Some(touch) => touch,
+            None => continue,
+        };
+        // Both are 0 when the browser or device doesn't know.
+        let force = touch.force() as f64;
+        let (radius_x, radius_y) = (touch.radius_x() as f64, touch.radius_y() as f64);
+        state.send_event(WindowEvent::Touch(Touch {
+            device_id: ::DeviceId(DeviceId),
+            phase,
+            location: state.client_to_canvas(touch.client_x() as f64, touch.client_y() as f64),
+            id: touch.identifier() as u64,
+            force: if force > 0.0 { Some(Force::Normalized(force)) } else { None },
+            contact_size: if radius_x > 0.0 || radius_y > 0.0 {
+                Some(LogicalSize::new(radius_x * 2.0, radius_y * 2.0))
+            } else {
+                None
+            },
+        }));
+    }
+}
+
+fn cursor_name(cursor: MouseCursor) -> &'static str {
+    match cursor {
+        MouseCursor::Default => "default",
+        MouseCursor::Crosshair => "crosshair",
+        MouseCursor::Hand => "pointer",
+        MouseCursor::Arrow => "default",
+        MouseCursor::Move => "move",
+        MouseCursor::Text => "text",
+        MouseCursor::Wait => "wait",
+        MouseCursor::Help => "help",
+        MouseCursor::Progress => "progress",
+        MouseCursor::NotAllowed => "not-allowed",
+        MouseCursor::ContextMenu => "context-menu",
+        MouseCursor::Cell => "cell",
+        MouseCursor::VerticalText => "vertical-text",
+        MouseCursor::Alias => "alias",
+        MouseCursor::Copy => "copy",
+        MouseCursor::NoDrop => "no-drop",
+        MouseCursor::Grab => "grab",
+        MouseCursor::Grabbing => "grabbing",
+        MouseCursor::AllScroll => "all-scroll",
+        MouseCursor::ZoomIn => "zoom-in",
+        MouseCursor::ZoomOut => "zoom-out",
+        MouseCursor::EResize => "e-resize",
+        MouseCursor::NResize => "n-resize",
+        MouseCursor::NeResize => "ne-resize",
+        MouseCursor::NwResize => "nw-resize",
+        MouseCursor::SResize => "s-resize",
+        MouseCursor::SeResize => "se-resize",
+        MouseCursor::SwResize => "sw-resize",
+        MouseCursor::WResize => "w-resize",
+        MouseCursor::EwResize => "ew-resize",
+        MouseCursor::NsResize => "ns-resize",
+        MouseCursor::NeswResize => "nesw-resize",
+        MouseCursor::NwseResize => "nwse-resize",
+        MouseCursor::ColResize => "col-resize",
+        MouseCursor::RowResize => "row-resize",
+    }
+}
@@ -0,0 +1,22 @@
+//! The parts of UI Automation that winapi doesn't provide yet.
+
+#![allow(non_snake_case, non_upper_case_globals)]
+
+use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::unknwnbase::IUnknown;
+use winapi::um::winnt::LONG;
+
+/// The object ID that `WM_GETOBJECT` uses to request a UI Automation provider.
+pub const UiaRootObjectId: LONG = -25;
+
+#[link(name = "uiautomationcore")]
+extern "system" {
+    // The last parameter is an `IRawElementProviderSimple`.
+    pub fn UiaReturnRawElementProvider(
+        hwnd: HWND,
+        wParam: WPARAM,
+        lParam: LPARAM,
+        el: *mut IUnknown,
+    ) -> LRESULT;
+}
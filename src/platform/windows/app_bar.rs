@@ -0,0 +1,104 @@
+//! Appbars, which reserve an edge of a monitor like the taskbar does, and the taskbar itself.
+
+use std::{mem, ptr};
+
+use winapi::shared::minwindef::{DWORD, UINT};
+use winapi::shared::windef::{HMONITOR, HWND};
+use winapi::um::shellapi::{self, APPBARDATA};
+use winapi::um::winnt::LONG;
+use winapi::um::winuser;
+
+use Edge;
+use platform::platform::events_loop::APP_BAR_MSG_ID;
+
+fn to_abe(edge: Edge) -> UINT {
+    match edge {
+        Edge::Left => shellapi::ABE_LEFT,
+        Edge::Top => shellapi::ABE_TOP,
+        Edge::Right => shellapi::ABE_RIGHT,
+        Edge::Bottom => shellapi::ABE_BOTTOM,
+    }
+}
+
+fn from_abe(abe: UINT) -> Edge {
+    match abe {
+        shellapi::ABE_LEFT => Edge::Left,
+        shellapi::ABE_TOP => Edge::Top,
+        shellapi::ABE_RIGHT => Edge::Right,
+        _ => Edge::Bottom,
+    }
+}
+
+unsafe fn app_bar_data(window: HWND) -> APPBARDATA {
+    let mut data: APPBARDATA = mem::zeroed();
+    data.cbSize = mem::size_of::<APPBARDATA>() as DWORD;
+    data.hWnd = window;
+    data
+}
+
+/// Returns the edge of the primary taskbar if it's on `monitor`. Unlike the work area, this
+/// also works when the taskbar auto-hides.
+pub unsafe fn get_taskbar_edge(monitor: HMONITOR) -> Option<Edge> {
+    let mut data = app_bar_data(ptr::null_mut());
+    if shellapi::SHAppBarMessage(shellapi::ABM_GETTASKBARPOS, &mut data) == 0 {
+        return None;
+    }
+    if winuser::MonitorFromRect(&data.rc, winuser::MONITOR_DEFAULTTONULL) == monitor {
+        Some(from_abe(data.uEdge))
+    } else {
+        None
+    }
+}
+
+/// Registers the window as an appbar unless `registered` is set, and reserves a band of
+/// `thickness` physical pixels along `edge` of its monitor. The window is moved into the band,
+/// which the shell may have moved away from other appbars.
+pub unsafe fn reserve(window: HWND, registered: bool, edge: Edge, thickness: LONG) -> Result<(), String> {
+    let mut data = app_bar_data(window);
+    if !registered {
+        data.uCallbackMessage = *APP_BAR_MSG_ID;
+        if shellapi::SHAppBarMessage(shellapi::ABM_NEW, &mut data) == 0 {
+            return Err("The window couldn't be registered as an appbar.".to_owned());
+        }
+    }
+
+    let monitor = winuser::MonitorFromWindow(window, winuser::MONITOR_DEFAULTTONEAREST);
+    let mut monitor_info: winuser::MONITORINFO = mem::zeroed();
+    monitor_info.cbSize = mem::size_of::<winuser::MONITORINFO>() as DWORD;
+    if winuser::GetMonitorInfoW(monitor, &mut monitor_info) == 0 {
+        if !registered {
+            remove(window);
+        }
+        return Err("`GetMonitorInfoW` failed".to_owned());
+    }
+
+    // The shell fits the proposed rectangle between the other appbars, after which it's given its
+    // thickness again, since the shell may have changed it.
+    data.uEdge = to_abe(edge);
+    data.rc = monitor_info.rcMonitor;
+    shellapi::SHAppBarMessage(shellapi::ABM_QUERYPOS, &mut data);
+    match edge {
+        Edge::Left => data.rc.right = data.rc.left + thickness,
+        Edge::Top => data.rc.bottom = data.rc.top + thickness,
+        Edge::Right => data.rc.left = data.rc.right - thickness,
+        Edge::Bottom => data.rc.top = data.rc.bottom - thickness,
+    }
+    shellapi::SHAppBarMessage(shellapi::ABM_SETPOS, &mut data);
+
+    winuser::SetWindowPos(
+        window,
+        ptr::null_mut(),
+        data.rc.left,
+        data.rc.top,
+        data.rc.right - data.rc.left,
+        data.rc.bottom - data.rc.top,
+        winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE,
+    );
+    Ok(())
+}
+
+/// Releases the space reserved by the appbar.
+pub unsafe fn remove(window: HWND) {
+    let mut data = app_bar_data(window);
+    shellapi::SHAppBarMessage(shellapi::ABM_REMOVE, &mut data);
+}
@@ -20,7 +20,7 @@ use winapi::um::shellscalingapi::{
     PROCESS_PER_MONITOR_DPI_AWARE,
 };
 use winapi::um::wingdi::{GetDeviceCaps, LOGPIXELSX};
-use winapi::um::winnt::{HRESULT, LPCSTR};
+use winapi::um::winnt::{HANDLE, HRESULT, LPCSTR};
 use winapi::um::winuser::{self, MONITOR_DEFAULTTONEAREST};
 
 const DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2: DPI_AWARENESS_CONTEXT = -4isize as _;
@@ -43,7 +43,7 @@ type EnableNonClientDpiScaling = unsafe extern "system" fn (hwnd: HWND) -> BOOL;
 
 // Helper function to dynamically load function pointer.
 // `library` and `function` must be zero-terminated.
-fn get_function_impl(library: &str, function: &str) -> Option<*const c_void> {
+pub fn get_function_impl(library: &str, function: &str) -> Option<*const c_void> {
     assert_eq!(library.chars().last(), Some('\0'));
     assert_eq!(function.chars().last(), Some('\0'));
 
@@ -144,7 +144,33 @@ pub fn dpi_to_scale_factor(dpi: u32) -> f64 {
     dpi as f64 / BASE_DPI as f64
 }
 
+pub fn scale_factor_to_dpi(scale_factor: f64) -> u32 {
+    ((scale_factor * BASE_DPI as f64).round() as u32).max(1)
+}
+
+// Name of the window property holding the DPI of windows created with a forced DPI factor.
+const FORCED_DPI_PROP: &[u8] = b"WinitForcedDpi\0";
+
+// The property is read by `get_hwnd_dpi`, so every DPI computation made for the window uses it.
+pub unsafe fn set_forced_hwnd_dpi(hwnd: HWND, dpi: u32) {
+    winuser::SetPropA(hwnd, FORCED_DPI_PROP.as_ptr() as LPCSTR, dpi as usize as HANDLE);
+}
+
+pub unsafe fn get_forced_hwnd_dpi(hwnd: HWND) -> Option<u32> {
+    let dpi = winuser::GetPropA(hwnd, FORCED_DPI_PROP.as_ptr() as LPCSTR) as usize as u32;
+    if dpi != 0 { Some(dpi) } else { None }
+}
+
+// Window properties have to be removed before the window is destroyed.
+pub unsafe fn remove_forced_hwnd_dpi(hwnd: HWND) {
+    winuser::RemovePropA(hwnd, FORCED_DPI_PROP.as_ptr() as LPCSTR);
+}
+
 pub unsafe fn get_hwnd_dpi(hwnd: HWND) -> u32 {
+    if let Some(dpi) = get_forced_hwnd_dpi(hwnd) {
+        return dpi;
+    }
+
     let hdc = winuser::GetDC(hwnd);
     if hdc.is_null() {
         panic!("[winit] `GetDC` returned null!");
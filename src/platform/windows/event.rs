@@ -28,6 +28,35 @@ pub fn get_key_mods() -> ModifiersState {
     mods
 }
 
+// Returns the scancode and virtual key code of every key that's held down, according to the
+// keyboard state of the calling thread.
+pub fn get_pressed_keys() -> Vec<(ScanCode, Option<VirtualKeyCode>)> {
+    let mut keyboard_state = [0u8; 256];
+    if unsafe { winuser::GetKeyboardState(keyboard_state.as_mut_ptr()) } == 0 {
+        return Vec::new();
+    }
+    keyboard_state
+        .iter()
+        .enumerate()
+        .filter(|&(_, &state)| state & 0x80 != 0)
+        .map(|(vkey, _)| vkey as c_int)
+        .filter(|&vkey| match vkey {
+            // Mouse buttons aren't keys.
+            winuser::VK_LBUTTON | winuser::VK_RBUTTON | winuser::VK_MBUTTON |
+            winuser::VK_XBUTTON1 | winuser::VK_XBUTTON2 => false,
+            // Their left and right versions are held down as well.
+            winuser::VK_SHIFT | winuser::VK_CONTROL | winuser::VK_MENU => false,
+            _ => true,
+        })
+        .map(|vkey| {
+            let scancode = unsafe {
+                winuser::MapVirtualKeyW(vkey as UINT, winuser::MAPVK_VK_TO_VSC)
+            };
+            (scancode, vkey_to_winit_vkey(vkey))
+        })
+        .collect()
+}
+
 pub fn vkey_to_winit_vkey(vkey: c_int) -> Option<VirtualKeyCode> {
     // VK_* codes are documented here https://msdn.microsoft.com/en-us/library/windows/desktop/dd375731(v=vs.85).aspx
     match vkey {
@@ -14,54 +14,99 @@
 
 use std::{mem, ptr, thread};
 use std::cell::RefCell;
-use std::collections::HashMap;
-use std::ffi::OsString;
-use std::os::windows::ffi::OsStringExt;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
 use std::os::windows::io::AsRawHandle;
-use std::sync::{Arc, Barrier, Condvar, mpsc, Mutex};
+use std::sync::{Arc, Condvar, mpsc, Mutex};
+use std::time::Duration;
 
 use winapi::ctypes::c_int;
+use winapi::shared::basetsd::UINT32;
 use winapi::shared::minwindef::{
     BOOL,
     DWORD,
+    FALSE,
     HIWORD,
+    HKL,
     INT,
     LOWORD,
     LPARAM,
     LRESULT,
     MAX_PATH,
+    TRUE,
     UINT,
     WPARAM,
 };
 use winapi::shared::windef::{HWND, POINT, RECT};
 use winapi::shared::windowsx;
-use winapi::um::{winuser, shellapi, processthreadsapi};
-use winapi::um::winnt::{LONG, LPCSTR, SHORT};
+use winapi::shared::winerror::{ERROR_NOT_ENOUGH_QUOTA, HRESULT, S_OK};
+use winapi::um::{dwmapi, errhandlingapi, libloaderapi, winnls, winuser, shellapi, processthreadsapi, sysinfoapi};
+use winapi::um::winnls::LCTYPE;
+use winapi::um::winnt::{HANDLE, LONG, LPCSTR, LPCWSTR, MAKELCID, SHORT, SORT_DEFAULT};
 
 use {
+    ActivationPolicy,
     ControlFlow,
+    DeviceInfo,
+    DeviceKind,
+    ElementState,
     Event,
     EventsLoopClosed,
+    HitTestCallback,
+    HitTestResult,
     KeyboardInput,
+    LayoutInfo,
     LogicalPosition,
     LogicalSize,
     PhysicalSize,
     WindowEvent,
     WindowId as SuperWindowId,
 };
-use events::{DeviceEvent, Touch, TouchPhase};
-use platform::platform::{event, Cursor, WindowId, DEVICE_ID, wrap_device_id, util};
+use events::{DamageRect, DeviceEvent, Force, Touch, TouchPhase};
+use accessibility::AccessibilityAdapter;
+use menu::MenuId;
+use platform::platform::{accessibility, app_bar, event, ime, Cursor, WindowId, DEVICE_ID, wrap_device_id, util};
 use platform::platform::dpi::{
     become_dpi_aware,
     dpi_to_scale_factor,
     enable_non_client_dpi_scaling,
+    get_forced_hwnd_dpi,
+    get_function_impl,
     get_hwnd_scale_factor,
+    remove_forced_hwnd_dpi,
 };
 use platform::platform::event::{handle_extended_keys, process_key_params, vkey_to_winit_vkey};
 use platform::platform::icon::WinIcon;
-use platform::platform::raw_input::{get_raw_input_data, get_raw_mouse_button_state};
+use platform::platform::menu::Accelerators;
+use platform::platform::vblank::{get_focused_monitor, VBlankOutput};
+use platform::platform::virtual_keyboard::{self, InputPane};
+use platform::platform::raw_input::{
+    get_hid_product_string,
+    get_raw_input_data,
+    get_raw_input_device_list,
+    get_raw_input_device_name,
+    get_raw_mouse_button_state,
+};
 use platform::platform::window::adjust_size;
 
+// winapi's declaration doesn't have a return type.
+#[link(name = "dwmapi")]
+extern "system" {
+    fn DwmFlush() -> HRESULT;
+}
+
+// Not declared by winapi.
+#[link(name = "user32")]
+extern "system" {
+    fn ShutdownBlockReasonCreate(hwnd: HWND, reason: LPCWSTR) -> BOOL;
+    fn ShutdownBlockReasonDestroy(hwnd: HWND) -> BOOL;
+}
+
+// Not declared by winapi.
+const LOCALE_SLOCALIZEDDISPLAYNAME: LCTYPE = 0x0000_0002;
+const DWM_CLOAKED_APP: DWORD = 0x0000_0001;
+
 /// Contains saved window info for switching between fullscreen
 #[derive(Clone)]
 pub struct SavedWindowInfo {
@@ -83,7 +128,9 @@ pub struct SavedWindowInfo {
 pub struct WindowState {
     /// Cursor to set at the next `WM_SETCURSOR` event received.
     pub cursor: Cursor,
-    pub cursor_grabbed: bool,
+    pub cursor_grab_mode: ::CursorGrabMode,
+    /// Takes the place of `cursor_grab_mode` while it's set.
+    pub cursor_confine_region: Option<::Rect>,
     pub cursor_hidden: bool,
     /// Used by `WM_GETMINMAXINFO`.
     pub max_size: Option<PhysicalSize>,
@@ -102,6 +149,48 @@ pub struct WindowState {
     pub always_on_top: bool,
     pub maximized: bool,
     pub resizable: bool,
+    /// Used by `WM_NCHITTEST`.
+    pub hit_test: Option<HitTestCallback>,
+    /// The accelerator table of the window's menu, if it has one.
+    pub accelerators: Option<Arc<Accelerators>>,
+    /// Tracks the touch keyboard once `set_virtual_keyboard_visible` has been called.
+    pub input_pane: Option<Arc<InputPane>>,
+    /// Whether the last `Occluded` event reported the window as occluded.
+    pub occluded: bool,
+    /// Whether the last `Minimized` event reported the window as minimized.
+    pub minimized: bool,
+    /// Whether the last `Maximized` event reported the window as maximized. Unlike `maximized`,
+    /// this follows changes made by the user, and is false while the window is fullscreen.
+    pub last_maximized: bool,
+    /// Whether the last `Focused` event reported the window as focused.
+    pub has_focus: bool,
+    /// Whether the window is minimized when it loses focus while it's fullscreen.
+    pub minimize_on_fullscreen_focus_loss: bool,
+    /// The IME mode forced by `set_ime_conversion_mode`, applied again on `WM_SETFOCUS`.
+    pub ime_mode: ::ImeMode,
+    /// The IME status before a mode was forced, restored when going back to `ImeMode::Default`.
+    pub ime_saved_status: Option<ime::ImeStatus>,
+    /// Provides the UI Automation provider returned by `WM_GETOBJECT`.
+    pub accessibility_adapter: Option<AccessibilityAdapter>,
+    /// Whether `AccessibilityActivated` was sent, which also means that UI Automation has to be
+    /// notified when the window is destroyed.
+    pub accessibility_activated: bool,
+    /// Set while the window is kept hidden until `present_ready` is called.
+    pub pending_present: bool,
+    /// Whether the window was shown but cloaked while `pending_present` is set, instead of being
+    /// left hidden.
+    pub present_cloaked: bool,
+    /// Set by `set_shape`, in physical client coordinates.
+    pub shape: Option<Vec<RECT>>,
+    /// Set by `set_input_region`, in physical client coordinates. Both this and `shape` make up
+    /// the window region.
+    pub input_region: Option<Vec<RECT>>,
+    /// Whether `DwmEnableBlurBehindWindow` was enabled to make the window transparent, in which
+    /// case it stays enabled when `set_blur` disables the blur.
+    pub transparent: bool,
+    /// Set while the window is registered as an appbar by `reserve_screen_edge`, with the
+    /// thickness of the reserved band in physical pixels.
+    pub app_bar: Option<(::Edge, LONG)>,
 }
 
 impl WindowState {
@@ -136,12 +225,19 @@ impl Inserter {
 pub struct EventsLoop {
     // Id of the background thread from the Win32 API.
     thread_id: DWORD,
+    // The message-only window of the background thread, which `EventsLoopProxy` posts to.
+    thread_msg_target: HWND,
+    // Taken by `shutdown`, to wait for the background thread to exit.
+    thread: Option<thread::JoinHandle<()>>,
     // Receiver for the events. The sender is in the background thread.
     receiver: mpsc::Receiver<Event>,
     // Variable that contains the block state of the win32 event loop thread during a WM_SIZE event.
     // The mutex's value is `true` when it's blocked, and should be set to false when it's done
     // blocking. That's done by the parent thread when it receives a Resized event.
     win32_block_loop: Arc<(Mutex<bool>, Condvar)>,
+    pub(crate) activation_policy: ActivationPolicy,
+    // The output `wait_for_vblank` last waited on, kept until the focused window changes monitor.
+    vblank_output: Option<VBlankOutput>,
 }
 
 impl EventsLoop {
@@ -149,6 +245,11 @@ impl EventsLoop {
         Self::with_dpi_awareness(true)
     }
 
+    // Nothing can fail to connect on this platform.
+    pub fn try_new() -> Result<EventsLoop, ::EventsLoopCreationError> {
+        Ok(EventsLoop::new())
+    }
+
     pub fn with_dpi_awareness(dpi_aware: bool) -> EventsLoop {
         become_dpi_aware(dpi_aware);
 
@@ -157,10 +258,9 @@ impl EventsLoop {
         let win32_block_loop = Arc::new((Mutex::new(false), Condvar::new()));
         let win32_block_loop_child = win32_block_loop.clone();
 
-        // Local barrier in order to block the `new()` function until the background thread has
-        // an events queue.
-        let barrier = Arc::new(Barrier::new(2));
-        let barrier_clone = barrier.clone();
+        // Blocks the `new()` function until the background thread has an events queue, and gives
+        // it the window that the messages of `EventsLoopProxy` are posted to.
+        let (thread_msg_target_tx, thread_msg_target_rx) = mpsc::channel();
 
         let thread = thread::spawn(move || {
             CONTEXT_STASH.with(|context_stash| {
@@ -168,7 +268,17 @@ impl EventsLoop {
                     sender: tx,
                     windows: HashMap::with_capacity(4),
                     win32_block_loop: win32_block_loop_child,
-                    mouse_buttons_down: 0
+                    mouse_buttons_down: 0,
+                    session_end_block: None,
+                    session_ending: false,
+                    app_active: false,
+                    keyboard_layout: unsafe { winuser::GetKeyboardLayout(0) },
+                    size_move: None,
+                    input_devices: get_raw_input_device_list()
+                        .unwrap_or_else(Vec::new)
+                        .into_iter()
+                        .map(|device| device.hDevice)
+                        .collect(),
                 });
             });
 
@@ -177,10 +287,10 @@ impl EventsLoop {
                 // will fail. In order to avoid this situation, we call `IsGuiThread` to initialize
                 // it.
                 winuser::IsGUIThread(1);
-                // Then only we unblock the `new()` function. We are sure that we don't call
-                // `PostThreadMessageA()` before `new()` returns.
-                barrier_clone.wait();
-                drop(barrier_clone);
+                // Then only we unblock the `new()` function, once there's a window for the proxies
+                // to post to.
+                let thread_msg_target = create_thread_msg_target();
+                thread_msg_target_tx.send(thread_msg_target as usize).unwrap();
 
                 let mut msg = mem::uninitialized();
 
@@ -192,17 +302,19 @@ impl EventsLoop {
                     }
 
                     match msg.message {
-                        x if x == *EXEC_MSG_ID => {
-                            let mut function: Box<Box<FnMut(Inserter)>> = Box::from_raw(msg.wParam as usize as *mut _);
-                            function(Inserter(ptr::null_mut()));
-                        },
-                        x if x == *WAKEUP_MSG_ID => {
-                            send_event(Event::Awakened);
+                        _ if msg.hwnd.is_null() => {
+                            // Thread messages aren't dispatched to any window procedure, so this
+                            // is the only place the hook can see them.
+                            run_msg_hook(&msg);
                         },
                         _ => {
-                            // Calls `callback` below.
-                            winuser::TranslateMessage(&msg);
-                            winuser::DispatchMessageW(&msg);
+                            // A message handled by an accelerator has been turned into a
+                            // `WM_COMMAND`, and mustn't be dispatched as is.
+                            if !translate_accelerator(&mut msg) {
+                                // Calls `callback` below.
+                                winuser::TranslateMessage(&msg);
+                                winuser::DispatchMessageW(&msg);
+                            }
                         }
                     }
                 }
@@ -210,7 +322,7 @@ impl EventsLoop {
         });
 
         // Blocks this function until the background thread has an events loop. See other comments.
-        barrier.wait();
+        let thread_msg_target = thread_msg_target_rx.recv().unwrap() as HWND;
 
         let thread_id = unsafe {
             let handle = mem::transmute(thread.as_raw_handle());
@@ -219,12 +331,40 @@ impl EventsLoop {
 
         EventsLoop {
             thread_id,
+            thread_msg_target,
+            thread: Some(thread),
             receiver: rx,
             win32_block_loop,
+            activation_policy: ActivationPolicy::Regular,
+            vblank_output: None,
         }
     }
 
-    pub fn poll_events<F>(&mut self, mut callback: F)
+    pub fn set_activation_policy(&mut self, activation_policy: ActivationPolicy) {
+        self.activation_policy = activation_policy;
+    }
+
+    // Events are sent from the background thread through an mpsc channel, which allocates for
+    // each of them. Replacing it with a preallocated queue is left for later.
+    pub fn reserve_events(&mut self, _capacity: usize) {
+        // N/A
+    }
+
+    // Waits on the DXGI output of the monitor the focused window is on. If there's none,
+    // `DwmFlush` returns once the compositor has presented its next frame, which follows the
+    // refresh of the primary monitor. It fails right away if composition is disabled.
+    pub fn wait_for_vblank(&mut self) -> bool {
+        let monitor = unsafe { get_focused_monitor() };
+        if self.vblank_output.as_ref().map(VBlankOutput::monitor) != Some(monitor) {
+            self.vblank_output = unsafe { VBlankOutput::new(monitor) };
+        }
+        match self.vblank_output {
+            Some(ref output) if output.wait() => true,
+            _ => unsafe { DwmFlush() == S_OK },
+        }
+    }
+
+    pub fn poll_events<F>(&self, mut callback: F)
         where F: FnMut(Event)
     {
         loop {
@@ -247,7 +387,7 @@ impl EventsLoop {
         }
     }
 
-    pub fn run_forever<F>(&mut self, mut callback: F)
+    pub fn run_forever<F>(&self, mut callback: F)
         where F: FnMut(Event) -> ControlFlow
     {
         loop {
@@ -274,12 +414,144 @@ impl EventsLoop {
         }
     }
 
+    pub fn keyboard_layouts(&self) -> Vec<LayoutInfo> {
+        unsafe {
+            let count = winuser::GetKeyboardLayoutList(0, ptr::null_mut());
+            let mut layouts: Vec<HKL> = vec![ptr::null_mut(); count as usize];
+            let count = winuser::GetKeyboardLayoutList(count, layouts.as_mut_ptr());
+            layouts.truncate(count as usize);
+
+            // The layout that's active in the thread the windows receive their input in.
+            let active = winuser::GetKeyboardLayout(self.thread_id);
+            layouts
+                .into_iter()
+                .map(|layout| LayoutInfo {
+                    name: get_layout_language_name(layout),
+                    active: layout == active,
+                })
+                .collect()
+        }
+    }
+
+    pub fn device_infos(&self) -> Vec<DeviceInfo> {
+        let devices = get_raw_input_device_list().unwrap_or_else(Vec::new);
+        devices
+            .into_iter()
+            .filter_map(|device| {
+                let kind = match device.dwType {
+                    winuser::RIM_TYPEMOUSE => DeviceKind::Mouse,
+                    winuser::RIM_TYPEKEYBOARD => DeviceKind::Keyboard,
+                    _ => DeviceKind::Hid,
+                };
+                let path = get_raw_input_device_name(device.hDevice)?;
+                let name = get_hid_product_string(&path).unwrap_or(path);
+                Some(DeviceInfo {
+                    id: wrap_device_id(device.hDevice as _),
+                    name,
+                    kind,
+                })
+            })
+            .collect()
+    }
+
     pub fn create_proxy(&self) -> EventsLoopProxy {
         EventsLoopProxy {
-            thread_id: self.thread_id,
+            thread_msg_target: self.thread_msg_target,
+        }
+    }
+
+    pub fn create_idle_time_query(&self) -> Option<IdleTimeQuery> {
+        Some(IdleTimeQuery)
+    }
+
+    pub fn shutdown(mut self) {
+        // `drop` posts `WM_QUIT`, which makes the background thread exit.
+        let thread = self.thread.take();
+        let win32_block_loop = self.win32_block_loop.clone();
+        drop(self);
+        // The background thread might be waiting for a `Resized` event to be processed, which
+        // won't happen anymore. Now that the receiver is gone, it won't wait again after this.
+        {
+            let (ref mutex, ref cvar) = *win32_block_loop;
+            let mut block_thread = mutex.lock().unwrap();
+            *block_thread = false;
+            cvar.notify_all();
+        }
+        if let Some(thread) = thread {
+            let _ = thread.join();
         }
     }
 
+    pub fn block_session_end(&self, reason: Option<&str>) {
+        let mut reason = reason.map(|reason| {
+            OsStr::new(reason).encode_wide().chain(Some(0)).collect::<Vec<_>>()
+        });
+        self.execute_in_thread(move |_| {
+            let reason = reason.take();
+            CONTEXT_STASH.with(|context_stash| {
+                if let Some(cstash) = context_stash.borrow_mut().as_mut() {
+                    // Reasons are only given to windows while the session is ending.
+                    if reason.is_none() {
+                        for &window in cstash.windows.keys() {
+                            unsafe { ShutdownBlockReasonDestroy(window) };
+                        }
+                    }
+                    cstash.session_end_block = reason;
+                }
+            });
+        });
+    }
+
+    pub fn set_msg_hook<F>(&self, hook: F)
+        where F: FnMut(*const winuser::MSG) -> bool + Send + 'static
+    {
+        let mut hook = Some(hook);
+        self.execute_in_thread(move |_| {
+            let hook = hook.take().map(|hook| Box::new(hook) as Box<FnMut(_) -> bool>);
+            MSG_HOOK.with(|msg_hook| *msg_hook.borrow_mut() = hook);
+        });
+    }
+
+    /// Returns the visible windows of this events loop from front to back.
+    pub fn get_window_stacking_order(&self) -> Vec<WindowId> {
+        let thread_id = self.thread_id;
+        util::get_windows_front_to_back(|hwnd| unsafe {
+            winuser::GetWindowThreadProcessId(hwnd, ptr::null_mut()) == thread_id
+        }).into_iter().map(WindowId).collect()
+    }
+
+    /// Captures the foreground window, if it's one of the windows of this events loop.
+    pub fn focus_snapshot(&self) -> FocusSnapshot {
+        let thread_id = self.thread_id;
+        let hwnd = unsafe { winuser::GetForegroundWindow() };
+        let is_ours = !hwnd.is_null() && unsafe {
+            winuser::GetWindowThreadProcessId(hwnd, ptr::null_mut()) == thread_id
+        };
+        FocusSnapshot {
+            window: if is_ours { Some(WindowId(hwnd)) } else { None },
+        }
+    }
+
+    pub fn restore_focus(&self, snapshot: &FocusSnapshot) {
+        let window = match snapshot.window {
+            Some(window) => window,
+            None => return,
+        };
+        let thread_id = self.thread_id;
+        self.execute_in_thread(move |_| unsafe {
+            let WindowId(hwnd) = window;
+            // The window may have been destroyed, and its handle reused, since the snapshot.
+            if winuser::IsWindow(hwnd) == FALSE
+                || winuser::GetWindowThreadProcessId(hwnd, ptr::null_mut()) != thread_id
+            {
+                return;
+            }
+            // Keyboard focus, and with it the window's input method context, follows activation.
+            winuser::SetForegroundWindow(hwnd);
+            winuser::SetFocus(hwnd);
+        });
+    }
+
     /// Executes a function in the background thread.
     ///
     /// Note that we use a FnMut instead of a FnOnce because we're too lazy to create an equivalent
@@ -294,6 +566,17 @@ impl EventsLoop {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct FocusSnapshot {
+    window: Option<WindowId>,
+}
+
+impl FocusSnapshot {
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.window
+    }
+}
+
 impl Drop for EventsLoop {
     fn drop(&mut self) {
         unsafe {
@@ -305,23 +588,31 @@ impl Drop for EventsLoop {
 
 #[derive(Clone)]
 pub struct EventsLoopProxy {
-    thread_id: DWORD,
+    thread_msg_target: HWND,
 }
 
+unsafe impl Send for EventsLoopProxy {}
+unsafe impl Sync for EventsLoopProxy {}
+
 impl EventsLoopProxy {
     pub fn wakeup(&self) -> Result<(), EventsLoopClosed> {
         unsafe {
-            if winuser::PostThreadMessageA(self.thread_id, *WAKEUP_MSG_ID, 0, 0) != 0 {
+            if winuser::PostMessageW(self.thread_msg_target, *WAKEUP_MSG_ID, 0, 0) != 0 {
                 Ok(())
             } else {
-                // https://msdn.microsoft.com/fr-fr/library/windows/desktop/ms644946(v=vs.85).aspx
+                // https://docs.microsoft.com/en-us/windows/desktop/api/winuser/nf-winuser-postmessagew
                 // > If the function fails, the return value is zero. To get extended error
-                // > information, call GetLastError. GetLastError returns ERROR_INVALID_THREAD_ID
-                // > if idThread is not a valid thread identifier, or if the thread specified by
-                // > idThread does not have a message queue. GetLastError returns
-                // > ERROR_NOT_ENOUGH_QUOTA when the message limit is hit.
-                // TODO: handle ERROR_NOT_ENOUGH_QUOTA
-                Err(EventsLoopClosed)
+                // > information, call GetLastError. GetLastError returns ERROR_NOT_ENOUGH_QUOTA
+                // > when the limit is hit.
+                // The window is destroyed along with the background thread, which makes it an
+                // invalid handle.
+                if errhandlingapi::GetLastError() == ERROR_NOT_ENOUGH_QUOTA {
+                    // The queue is full of messages, so the thread will wake up anyway. It's
+                    // most likely full of previous wakeups, too.
+                    Ok(())
+                } else {
+                    Err(EventsLoopClosed)
+                }
             }
         }
     }
@@ -348,16 +639,36 @@ impl EventsLoopProxy {
         let raw = Box::into_raw(double_box);
 
         let res = unsafe {
-            winuser::PostThreadMessageA(
-                self.thread_id,
+            winuser::PostMessageW(
+                self.thread_msg_target,
                 *EXEC_MSG_ID,
                 raw as *mut () as usize as WPARAM,
                 0,
             )
         };
-        // PostThreadMessage can only fail if the thread ID is invalid (which shouldn't happen as
-        // the events loop is still alive) or if the queue is full.
-        assert!(res != 0, "PostThreadMessage failed; is the messages queue full?");
+        // PostMessage can only fail if the window is invalid (which shouldn't happen as the
+        // events loop is still alive) or if the queue is full.
+        assert!(res != 0, "PostMessage failed; is the messages queue full?");
+    }
+}
+
+/// Queries how long the user has been idle for, from any thread.
+pub struct IdleTimeQuery;
+
+impl IdleTimeQuery {
+    pub fn get_idle_time(&self) -> Option<Duration> {
+        unsafe {
+            let mut info = winuser::LASTINPUTINFO {
+                cbSize: mem::size_of::<winuser::LASTINPUTINFO>() as UINT,
+                dwTime: 0,
+            };
+            if winuser::GetLastInputInfo(&mut info) == 0 {
+                return None;
+            }
+            // Both are tick counts, which wrap around every 49.7 days.
+            let idle_ms = sysinfoapi::GetTickCount().wrapping_sub(info.dwTime);
+            Some(Duration::from_millis(idle_ms as u64))
+        }
     }
 }
 
@@ -391,20 +702,135 @@ lazy_static! {
             winuser::RegisterWindowMessageA("Winit::InitialDpiMsg\0".as_ptr() as LPCSTR)
         }
     };
+    // Message sent by the shell to the windows registered as appbars by `reserve_screen_edge`.
+    // WPARAM is the notification, and LPARAM depends on it.
+    pub static ref APP_BAR_MSG_ID: u32 = {
+        unsafe {
+            winuser::RegisterWindowMessageA("Winit::AppBarMsg\0".as_ptr() as LPCSTR)
+        }
+    };
+    // Message posted by the window's `InputPane` when the touch keyboard is shown or hidden.
+    // WPARAM and LPARAM are unused.
+    pub static ref VIRTUAL_KEYBOARD_MSG_ID: u32 = {
+        unsafe {
+            winuser::RegisterWindowMessageA("Winit::VirtualKeyboardMsg\0".as_ptr() as LPCSTR)
+        }
+    };
+}
+
+// Only available since Windows 8.
+type GetPointerTouchInfo = unsafe extern "system" fn(
+    pointerId: UINT32,
+    touchInfo: *mut winuser::POINTER_TOUCH_INFO,
+) -> BOOL;
+
+lazy_static! {
+    static ref GET_POINTER_TOUCH_INFO: Option<GetPointerTouchInfo> = get_function!(
+        "user32.dll",
+        GetPointerTouchInfo
+    );
+}
+
+// The modal loop of a window being moved or resized by the user. `WM_ENTERSIZEMOVE` doesn't say
+// which of the two it is, so that's only known once the window is dragged.
+#[derive(Debug, Clone, Copy)]
+struct SizeMove {
+    window: HWND,
+    resizing: bool,
+    moving: bool,
 }
 
 // There's no parameters passed to the callback function, so it needs to get its context stashed
 // in a thread-local variable.
 thread_local!(static CONTEXT_STASH: RefCell<Option<ThreadLocalData>> = RefCell::new(None));
+// Set with `os::windows::EventsLoopExt::set_msg_hook`. It isn't part of `ThreadLocalData`, since
+// the hook may do anything, including sending messages that need `CONTEXT_STASH`.
+thread_local!(static MSG_HOOK: RefCell<Option<Box<FnMut(*const winuser::MSG) -> bool>>> = RefCell::new(None));
 struct ThreadLocalData {
     sender: mpsc::Sender<Event>,
     windows: HashMap<HWND, Arc<Mutex<WindowState>>>,
     win32_block_loop: Arc<(Mutex<bool>, Condvar)>,
-    mouse_buttons_down: u32
+    mouse_buttons_down: u32,
+    // Set with `EventLoopWindowTarget::block_session_end`, null-terminated.
+    session_end_block: Option<Vec<u16>>,
+    // Set once `SessionEnding` was sent, since every window receives `WM_QUERYENDSESSION`.
+    session_ending: bool,
+    // Whether `ApplicationActivated` was sent last, since every window receives `WM_ACTIVATEAPP`.
+    app_active: bool,
+    // The layout `KeyboardLayoutChanged` was last sent for, since every window receives
+    // `WM_INPUTLANGCHANGE`.
+    keyboard_layout: HKL,
+    // Set while a window is in the modal loop of an interactive move or resize.
+    size_move: Option<SizeMove>,
+    // The raw input devices that are attached. Registering a window for raw input notifies it of
+    // the devices that were already attached, which isn't reported again.
+    input_devices: HashSet<HANDLE>,
+}
+
+/// Creates the message-only window that `EventsLoopProxy` posts its messages to.
+///
+/// The modal loops Windows runs while a window is moved or resized, or while a menu is open, drop
+/// the messages posted to the thread instead of a window. Posting to a window means that functions
+/// given to `execute_in_thread` still run, and wakeups are still delivered, while the user drags
+/// a window, rather than being lost.
+unsafe fn create_thread_msg_target() -> HWND {
+    let class_name: Vec<_> = OsStr::new("Winit Thread Message Target")
+        .encode_wide()
+        .chain(Some(0).into_iter())
+        .collect();
+
+    let class = winuser::WNDCLASSEXW {
+        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as UINT,
+        style: 0,
+        lpfnWndProc: Some(thread_msg_target_callback),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
+        hIcon: ptr::null_mut(),
+        hCursor: ptr::null_mut(),
+        hbrBackground: ptr::null_mut(),
+        lpszMenuName: ptr::null(),
+        lpszClassName: class_name.as_ptr(),
+        hIconSm: ptr::null_mut(),
+    };
+    // Every events loop registers the class, which fails harmlessly after the first time.
+    winuser::RegisterClassExW(&class);
+
+    let window = winuser::CreateWindowExW(
+        0,
+        class_name.as_ptr(),
+        ptr::null::<u16>() as LPCWSTR,
+        0,
+        0, 0, 0, 0,
+        winuser::HWND_MESSAGE,
+        ptr::null_mut(),
+        libloaderapi::GetModuleHandleW(ptr::null()),
+        ptr::null_mut(),
+    );
+    assert!(!window.is_null(), "Failed to create the window of the events loop");
+    window
+}
+
+unsafe extern "system" fn thread_msg_target_callback(
+    window: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == *EXEC_MSG_ID {
+        let mut function: Box<Box<FnMut(Inserter)>> = Box::from_raw(wparam as usize as *mut _);
+        function(Inserter(ptr::null_mut()));
+        0
+    } else if msg == *WAKEUP_MSG_ID {
+        send_event(Event::Awakened);
+        0
+    } else {
+        winuser::DefWindowProcW(window, msg, wparam, lparam)
+    }
 }
 
 // Utility function that dispatches an event on the current thread.
-fn send_event(event: Event) {
+pub fn send_event(event: Event) {
     CONTEXT_STASH.with(|context_stash| {
         let context_stash = context_stash.borrow();
 
@@ -412,6 +838,225 @@ fn send_event(event: Event) {
     });
 }
 
+/// Returns the display name of the language of a keyboard layout, like "English (United States)".
+///
+/// That's the name Windows shows for the layout unless there are several for the same language.
+unsafe fn get_layout_language_name(layout: HKL) -> String {
+    let locale = MAKELCID(LOWORD(layout as usize as DWORD), SORT_DEFAULT);
+    let mut buffer = [0u16; 128];
+    let len = winnls::GetLocaleInfoW(
+        locale,
+        LOCALE_SLOCALIZEDDISPLAYNAME,
+        buffer.as_mut_ptr(),
+        buffer.len() as c_int,
+    );
+    if len > 0 {
+        // The length includes the null terminator.
+        OsString::from_wide(&buffer[..len as usize - 1]).to_string_lossy().into_owned()
+    } else {
+        String::new()
+    }
+}
+
+/// Sends `Occluded` if the window's occlusion changed since the last time it was sent.
+///
+/// Windows doesn't tell us when a window is covered by other windows, so we only consider it
+/// occluded when it's minimized or cloaked. Cloaking happens when switching virtual desktops,
+/// which also changes the focus, so it's enough to check whenever the window is resized or gains
+/// or loses focus. Cloaking done by us for `with_visible_on_first_present` doesn't count, since
+/// the window is waiting to be drawn to.
+unsafe fn update_occlusion(window: HWND) {
+    let mut cloaked: DWORD = 0;
+    dwmapi::DwmGetWindowAttribute(
+        window,
+        dwmapi::DWMWA_CLOAKED,
+        &mut cloaked as *mut DWORD as *mut _,
+        mem::size_of::<DWORD>() as DWORD,
+    );
+    let occluded = winuser::IsIconic(window) != 0 || cloaked & !DWM_CLOAKED_APP != 0;
+    let changed = CONTEXT_STASH.with(|context_stash| {
+        context_stash
+            .borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map(|window_state| {
+                let mut window_state = window_state.lock().unwrap();
+                mem::replace(&mut window_state.occluded, occluded) != occluded
+            })
+            .unwrap_or(false)
+    });
+    if changed {
+        send_event(Event::WindowEvent {
+            window_id: SuperWindowId(WindowId(window)),
+            event: WindowEvent::Occluded(occluded),
+        });
+    }
+}
+
+/// Sends `Minimized` if the window was minimized or restored since the last time it was sent.
+unsafe fn update_minimized(window: HWND, minimized: bool) {
+    let changed = CONTEXT_STASH.with(|context_stash| {
+        context_stash
+            .borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map(|window_state| {
+                let mut window_state = window_state.lock().unwrap();
+                mem::replace(&mut window_state.minimized, minimized) != minimized
+            })
+            .unwrap_or(false)
+    });
+    if changed {
+        send_event(Event::WindowEvent {
+            window_id: SuperWindowId(WindowId(window)),
+            event: WindowEvent::Minimized(minimized),
+        });
+    }
+}
+
+/// Sends `Maximized` if the window was maximized or restored since the last time it was sent.
+unsafe fn update_maximized(window: HWND, maximized: bool) {
+    let changed = CONTEXT_STASH.with(|context_stash| {
+        context_stash
+            .borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map(|window_state| {
+                let mut window_state = window_state.lock().unwrap();
+                mem::replace(&mut window_state.last_maximized, maximized) != maximized
+            })
+            .unwrap_or(false)
+    });
+    if changed {
+        send_event(Event::WindowEvent {
+            window_id: SuperWindowId(WindowId(window)),
+            event: WindowEvent::Maximized(maximized),
+        });
+    }
+}
+
+/// Sends `FullscreenLost` if a fullscreen window was deactivated in favor of a window on the same
+/// monitor, or if it's minimized on losing focus.
+unsafe fn handle_fullscreen_deactivation(window: HWND, activated: HWND) {
+    let (fullscreen, minimize) = CONTEXT_STASH.with(|context_stash| {
+        context_stash
+            .borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map(|window_state| {
+                let window_state = window_state.lock().unwrap();
+                (window_state.fullscreen.is_some(), window_state.minimize_on_fullscreen_focus_loss)
+            })
+            .unwrap_or((false, false))
+    });
+    if !fullscreen {
+        return;
+    }
+    let covered = !activated.is_null()
+        && winuser::MonitorFromWindow(activated, winuser::MONITOR_DEFAULTTONULL)
+            == winuser::MonitorFromWindow(window, winuser::MONITOR_DEFAULTTONEAREST);
+    if minimize {
+        // Another window is already being activated, so this one mustn't take the focus back.
+        winuser::ShowWindow(window, winuser::SW_SHOWMINNOACTIVE);
+    }
+    if minimize || covered {
+        send_event(Event::WindowEvent {
+            window_id: SuperWindowId(WindowId(window)),
+            event: WindowEvent::FullscreenLost,
+        });
+    }
+}
+
+/// Records whether the window has focus, for `Window::is_focused`.
+fn set_has_focus(window: HWND, has_focus: bool) {
+    CONTEXT_STASH.with(|context_stash| {
+        let context_stash = context_stash.borrow();
+        if let Some(window_state) = context_stash.as_ref().and_then(|cstash| cstash.windows.get(&window)) {
+            window_state.lock().unwrap().has_focus = has_focus;
+        }
+    });
+}
+
+/// Forces the IME mode set by `set_ime_conversion_mode` again, since the user may have switched
+/// modes while the window didn't have focus.
+unsafe fn reapply_ime_mode(window: HWND) {
+    let ime_mode = CONTEXT_STASH.with(|context_stash| {
+        let context_stash = context_stash.borrow();
+        context_stash.as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .map(|window_state| window_state.lock().unwrap().ime_mode)
+    });
+    if let Some(ime_mode) = ime_mode {
+        let _ = ime::set_mode(window, ime_mode);
+    }
+}
+
+/// Sends a synthetic `KeyboardInput` for every key that's held down, when a window gains or loses
+/// focus.
+unsafe fn send_synthetic_keys(window: HWND, state: ElementState) {
+    let modifiers = event::get_key_mods();
+    for (scancode, virtual_keycode) in event::get_pressed_keys() {
+        send_event(Event::WindowEvent {
+            window_id: SuperWindowId(WindowId(window)),
+            event: WindowEvent::KeyboardInput {
+                device_id: DEVICE_ID,
+                input: KeyboardInput {
+                    state,
+                    scancode,
+                    virtual_keycode,
+                    modifiers,
+                    is_composing: false,
+                    is_synthetic: true,
+                    is_repeat: false,
+                    text: None,
+                    is_dead_key: false,
+                },
+            },
+        });
+    }
+}
+
+/// Removes the `WM_CHAR` and `WM_DEADCHAR` messages that `TranslateMessage` posted for the key
+/// that's being handled, and returns their text, so that it can be sent along with the key, and
+/// whether the key is a dead key.
+///
+/// Since the key messages that follow in the queue haven't been translated yet, only this key's
+/// characters can be there.
+unsafe fn take_key_chars(window: HWND) -> (String, bool) {
+    let mut units = Vec::new();
+    let mut is_dead_key = false;
+    let mut msg: winuser::MSG = mem::uninitialized();
+    // `WM_DEADCHAR` directly follows `WM_CHAR`.
+    while winuser::PeekMessageW(&mut msg, window, winuser::WM_CHAR, winuser::WM_DEADCHAR, winuser::PM_REMOVE) != 0 {
+        if msg.message == winuser::WM_DEADCHAR {
+            is_dead_key = true;
+        } else {
+            units.push(msg.wParam as u16);
+        }
+    }
+    (String::from_utf16_lossy(&units), is_dead_key)
+}
+
+/// Gives the accelerators of the window's menu a chance to handle the message. Returns `true` if
+/// one of them did.
+unsafe fn translate_accelerator(msg: &mut winuser::MSG) -> bool {
+    // `GA_ROOT` isn't provided by winapi yet.
+    const GA_ROOT: UINT = 2;
+    let window = winuser::GetAncestor(msg.hwnd, GA_ROOT);
+    // The accelerator sends `WM_COMMAND` to `callback` right away, so we can't hold the borrow.
+    let accelerators = CONTEXT_STASH.with(|context_stash| {
+        context_stash
+            .borrow()
+            .as_ref()
+            .and_then(|cstash| cstash.windows.get(&window))
+            .and_then(|window_state| window_state.lock().unwrap().accelerators.clone())
+    });
+    match accelerators {
+        Some(accelerators) => winuser::TranslateAcceleratorW(window, accelerators.handle, msg) != 0,
+        None => false,
+    }
+}
+
 /// Capture mouse input, allowing `window` to receive mouse events when the cursor is outside of
 /// the window.
 unsafe fn capture_mouse(window: HWND) {
@@ -438,6 +1083,15 @@ unsafe fn release_mouse() {
     });
 }
 
+// Returns `true` if the hook handled the message. The hook isn't run again for messages it sends
+// itself, since it's already borrowed.
+fn run_msg_hook(msg: *const winuser::MSG) -> bool {
+    MSG_HOOK.with(|msg_hook| match msg_hook.try_borrow_mut() {
+        Ok(mut msg_hook) => msg_hook.as_mut().map(|hook| hook(msg)).unwrap_or(false),
+        Err(_) => false,
+    })
+}
+
 /// Any window whose callback is configured to this function will have its events propagated
 /// through the events loop of the thread the window was created in.
 //
@@ -451,6 +1105,19 @@ pub unsafe extern "system" fn callback(
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
+    let pos = winuser::GetMessagePos();
+    let hook_msg = winuser::MSG {
+        hwnd: window,
+        message: msg,
+        wParam: wparam,
+        lParam: lparam,
+        time: winuser::GetMessageTime() as DWORD,
+        pt: POINT { x: windowsx::GET_X_LPARAM(pos as LPARAM), y: windowsx::GET_Y_LPARAM(pos as LPARAM) },
+    };
+    if run_msg_hook(&hook_msg) {
+        return 0;
+    }
+
     match msg {
         winuser::WM_NCCREATE => {
             enable_non_client_dpi_scaling(window);
@@ -468,10 +1135,24 @@ pub unsafe extern "system" fn callback(
 
         winuser::WM_DESTROY => {
             use events::WindowEvent::Destroyed;
-            CONTEXT_STASH.with(|context_stash| {
+            remove_forced_hwnd_dpi(window);
+            let window_state = CONTEXT_STASH.with(|context_stash| {
                 let mut context_stash = context_stash.borrow_mut();
-                context_stash.as_mut().unwrap().windows.remove(&window);
+                context_stash.as_mut().unwrap().windows.remove(&window)
             });
+            let (accessibility_activated, app_bar) = window_state
+                .map(|window_state| {
+                    let window_state = window_state.lock().unwrap();
+                    (window_state.accessibility_activated, window_state.app_bar.is_some())
+                })
+                .unwrap_or((false, false));
+            if app_bar {
+                app_bar::remove(window);
+            }
+            if accessibility_activated {
+                // Lets UI Automation release the providers we returned.
+                accessibility::UiaReturnRawElementProvider(window, 0, 0, ptr::null_mut());
+            }
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
                 event: Destroyed
@@ -481,10 +1162,75 @@ pub unsafe extern "system" fn callback(
 
         winuser::WM_PAINT => {
             use events::WindowEvent::Refresh;
+            // The update region is validated by `DefWindowProcW`, so it has to be retrieved first.
+            let damage = util::get_update_rects(window).map(|rects| {
+                rects.iter().map(|rect| DamageRect {
+                    x: rect.left.max(0) as u32,
+                    y: rect.top.max(0) as u32,
+                    width: (rect.right - rect.left).max(0) as u32,
+                    height: (rect.bottom - rect.top).max(0) as u32,
+                }).collect()
+            });
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: Refresh,
+                event: Refresh { damage },
+            });
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
+        winuser::WM_ENTERSIZEMOVE => {
+            CONTEXT_STASH.with(|context_stash| {
+                if let Some(cstash) = context_stash.borrow_mut().as_mut() {
+                    cstash.size_move = Some(SizeMove { window, resizing: false, moving: false });
+                }
+            });
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
+        winuser::WM_SIZING | winuser::WM_MOVING => {
+            use events::WindowEvent::{MoveStarted, ResizeStarted};
+
+            let resizing = msg == winuser::WM_SIZING;
+            let started = CONTEXT_STASH.with(|context_stash| {
+                let mut context_stash = context_stash.borrow_mut();
+                match context_stash.as_mut().and_then(|cstash| cstash.size_move.as_mut()) {
+                    Some(ref mut size_move) if size_move.window == window => if resizing {
+                        !mem::replace(&mut size_move.resizing, true)
+                    } else {
+                        !mem::replace(&mut size_move.moving, true)
+                    },
+                    _ => false,
+                }
+            });
+            if started {
+                send_event(Event::WindowEvent {
+                    window_id: SuperWindowId(WindowId(window)),
+                    event: if resizing { ResizeStarted } else { MoveStarted },
+                });
+            }
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
+        winuser::WM_EXITSIZEMOVE => {
+            use events::WindowEvent::{MoveEnded, ResizeEnded};
+
+            let size_move = CONTEXT_STASH.with(|context_stash| {
+                context_stash.borrow_mut().as_mut().and_then(|cstash| cstash.size_move.take())
             });
+            if let Some(size_move) = size_move {
+                if size_move.resizing {
+                    send_event(Event::WindowEvent {
+                        window_id: SuperWindowId(WindowId(size_move.window)),
+                        event: ResizeEnded,
+                    });
+                }
+                if size_move.moving {
+                    send_event(Event::WindowEvent {
+                        window_id: SuperWindowId(WindowId(size_move.window)),
+                        event: MoveEnded,
+                    });
+                }
+            }
             winuser::DefWindowProcW(window, msg, wparam, lparam)
         },
 
@@ -536,8 +1282,10 @@ pub unsafe extern "system" fn callback(
                     *block_thread = true;
 
                     // The event needs to be sent after the lock to ensure that `notify_all` is
-                    // called after `wait`.
-                    cstash.sender.send(event).ok();
+                    // called after `wait`. If the events loop is gone, nothing will process it.
+                    if cstash.sender.send(event).is_err() {
+                        *block_thread = false;
+                    }
 
                     while *block_thread {
                         block_thread = cvar.wait(block_thread).unwrap();
@@ -546,9 +1294,65 @@ pub unsafe extern "system" fn callback(
                     cstash.sender.send(event).ok();
                 }
             });
+            update_minimized(window, wparam == winuser::SIZE_MINIMIZED);
+            // A maximized window stays maximized while it's minimized.
+            if wparam != winuser::SIZE_MINIMIZED {
+                update_maximized(window, wparam == winuser::SIZE_MAXIMIZED);
+            }
+            update_occlusion(window);
             0
         },
 
+        winuser::WM_COMMAND => {
+            // `lparam` is only set for notifications sent by controls, which we don't create, so
+            // anything else comes from a menu item or one of its accelerators.
+            if lparam == 0 {
+                send_event(Event::MenuEvent {
+                    menu_id: MenuId(LOWORD(wparam as DWORD)),
+                });
+                0
+            } else {
+                winuser::DefWindowProcW(window, msg, wparam, lparam)
+            }
+        },
+
+        winuser::WM_NCHITTEST => {
+            let hit_test = CONTEXT_STASH.with(|context_stash| {
+                context_stash
+                    .borrow()
+                    .as_ref()
+                    .and_then(|cstash| cstash.windows.get(&window))
+                    .and_then(|window_state| window_state.lock().unwrap().hit_test.clone())
+            });
+
+            if let Some(hit_test) = hit_test {
+                // The coordinates are relative to the screen, so we have to convert them first.
+                let mut point = POINT {
+                    x: windowsx::GET_X_LPARAM(lparam),
+                    y: windowsx::GET_Y_LPARAM(lparam),
+                };
+                winuser::ScreenToClient(window, &mut point);
+                let result = match hit_test((point.x, point.y).into()) {
+                    // This keeps the system's own caption and borders working.
+                    HitTestResult::Client => {
+                        return winuser::DefWindowProcW(window, msg, wparam, lparam);
+                    },
+                    HitTestResult::Caption => winuser::HTCAPTION,
+                    HitTestResult::Left => winuser::HTLEFT,
+                    HitTestResult::Right => winuser::HTRIGHT,
+                    HitTestResult::Top => winuser::HTTOP,
+                    HitTestResult::Bottom => winuser::HTBOTTOM,
+                    HitTestResult::TopLeft => winuser::HTTOPLEFT,
+                    HitTestResult::TopRight => winuser::HTTOPRIGHT,
+                    HitTestResult::BottomLeft => winuser::HTBOTTOMLEFT,
+                    HitTestResult::BottomRight => winuser::HTBOTTOMRIGHT,
+                };
+                result as LRESULT
+            } else {
+                winuser::DefWindowProcW(window, msg, wparam, lparam)
+            }
+        },
+
         winuser::WM_CHAR => {
             use std::mem;
             use events::WindowEvent::ReceivedCharacter;
@@ -640,17 +1444,31 @@ pub unsafe extern "system" fn callback(
             0
         },
 
-        winuser::WM_MOUSEWHEEL => {
+        winuser::WM_MOUSEWHEEL | winuser::WM_MOUSEHWHEEL |
+        winuser::WM_POINTERWHEEL | winuser::WM_POINTERHWHEEL => {
             use events::MouseScrollDelta::LineDelta;
             use events::TouchPhase;
 
+            // High-resolution devices (like precision touchpads) send deltas smaller than
+            // `WHEEL_DELTA`, so this is kept fractional rather than rounded to whole lines.
             let value = (wparam >> 16) as i16;
             let value = value as i32;
             let value = value as f32 / winuser::WHEEL_DELTA as f32;
 
+            let delta = match msg {
+                winuser::WM_MOUSEHWHEEL | winuser::WM_POINTERHWHEEL => LineDelta(value, 0.0),
+                _ => LineDelta(0.0, value),
+            };
+
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
-                event: WindowEvent::MouseWheel { device_id: DEVICE_ID, delta: LineDelta(0.0, value), phase: TouchPhase::Moved, modifiers: event::get_key_mods() },
+                event: WindowEvent::MouseWheel {
+                    device_id: DEVICE_ID,
+                    delta,
+                    phase: TouchPhase::Moved,
+                    momentum_phase: None,
+                    modifiers: event::get_key_mods(),
+                },
             });
 
             0
@@ -663,6 +1481,14 @@ pub unsafe extern "system" fn callback(
                 winuser::DefWindowProcW(window, msg, wparam, lparam)
             } else {
                 if let Some((scancode, vkey)) = process_key_params(wparam, lparam) {
+                    // Windows doesn't emit a delete character by default, but in order to make it
+                    // consistent with the other platforms we'll emit a delete character here.
+                    let (text, is_dead_key) = if vkey == Some(VirtualKeyCode::Delete) {
+                        ("\u{7F}".to_owned(), false)
+                    } else {
+                        take_key_chars(window)
+                    };
+                    let text = if text.is_empty() { None } else { Some(text) };
                     send_event(Event::WindowEvent {
                         window_id: SuperWindowId(WindowId(window)),
                         event: WindowEvent::KeyboardInput {
@@ -672,16 +1498,22 @@ pub unsafe extern "system" fn callback(
                                 scancode: scancode,
                                 virtual_keycode: vkey,
                                 modifiers: event::get_key_mods(),
+                                is_composing: false,
+                                is_synthetic: false,
+                                // Bit 30 is the previous key state, which is set for repeats.
+                                is_repeat: lparam & (1 << 30) != 0,
+                                text: text.clone(),
+                                is_dead_key,
                             }
                         }
                     });
-                    // Windows doesn't emit a delete character by default, but in order to make it
-                    // consistent with the other platforms we'll emit a delete character here.
-                    if vkey == Some(VirtualKeyCode::Delete) {
-                        send_event(Event::WindowEvent {
-                            window_id: SuperWindowId(WindowId(window)),
-                            event: WindowEvent::ReceivedCharacter('\u{7F}'),
-                        });
+                    if let Some(text) = text {
+                        for chr in text.chars() {
+                            send_event(Event::WindowEvent {
+                                window_id: SuperWindowId(WindowId(window)),
+                                event: WindowEvent::ReceivedCharacter(chr),
+                            });
+                        }
                     }
                 }
                 0
@@ -700,6 +1532,11 @@ pub unsafe extern "system" fn callback(
                             scancode: scancode,
                             virtual_keycode: vkey,
                             modifiers: event::get_key_mods(),
+                            is_composing: false,
+                            is_synthetic: false,
+                            is_repeat: false,
+                            text: None,
+                            is_dead_key: false,
                         },
                     }
                 });
@@ -822,17 +1659,24 @@ pub unsafe extern "system" fn callback(
         },
 
         winuser::WM_INPUT_DEVICE_CHANGE => {
-            let event = match wparam as _ {
-                winuser::GIDC_ARRIVAL => DeviceEvent::Added,
-                winuser::GIDC_REMOVAL => DeviceEvent::Removed,
-                _ => unreachable!(),
-            };
-
-            send_event(Event::DeviceEvent {
-                device_id: wrap_device_id(lparam as _),
-                event,
+            let device = lparam as HANDLE;
+            let event = CONTEXT_STASH.with(|context_stash| {
+                let mut context_stash = context_stash.borrow_mut();
+                let cstash = context_stash.as_mut()?;
+                match wparam as _ {
+                    winuser::GIDC_ARRIVAL if cstash.input_devices.insert(device) => Some(DeviceEvent::Added),
+                    winuser::GIDC_REMOVAL if cstash.input_devices.remove(&device) => Some(DeviceEvent::Removed),
+                    _ => None,
+                }
             });
 
+            if let Some(event) = event {
+                send_event(Event::DeviceEvent {
+                    device_id: wrap_device_id(lparam as _),
+                    event,
+                });
+            }
+
             winuser::DefWindowProcW(window, msg, wparam, lparam)
         },
 
@@ -874,10 +1718,10 @@ pub unsafe extern "system" fn callback(
                     }
 
                     if util::has_flag(mouse.usButtonFlags, winuser::RI_MOUSE_WHEEL) {
-                        let delta = mouse.usButtonData as SHORT / winuser::WHEEL_DELTA;
+                        let delta = mouse.usButtonData as SHORT as f32 / winuser::WHEEL_DELTA as f32;
                         send_event(Event::DeviceEvent {
                             device_id,
-                            event: MouseWheel { delta: LineDelta(0.0, delta as f32) }
+                            event: MouseWheel { delta: LineDelta(0.0, delta) }
                         });
                     }
 
@@ -930,6 +1774,11 @@ pub unsafe extern "system" fn callback(
                                     state,
                                     virtual_keycode,
                                     modifiers: event::get_key_mods(),
+                                    is_composing: false,
+                                    is_synthetic: false,
+                                    is_repeat: false,
+                                    text: None,
+                                    is_dead_key: false,
                                 }),
                             });
                         }
@@ -956,6 +1805,26 @@ pub unsafe extern "system" fn callback(
                     let x = (input.x as f64) / 100f64;
                     let y = (input.y as f64) / 100f64;
                     let location = LogicalPosition::from_physical((x, y), dpi_factor);
+                    let contact_size = if input.dwMask & winuser::TOUCHINPUTMASKF_CONTACTAREA != 0 {
+                        let width = (input.cxContact as f64) / 100f64;
+                        let height = (input.cyContact as f64) / 100f64;
+                        Some(LogicalSize::from_physical((width, height), dpi_factor))
+                    } else {
+                        None
+                    };
+                    // Since Windows 8, touch input is built on top of pointer input, and touch
+                    // points are identified by their pointer ID.
+                    let force = GET_POINTER_TOUCH_INFO.and_then(|GetPointerTouchInfo| {
+                        let mut touch_info: winuser::POINTER_TOUCH_INFO = mem::zeroed();
+                        if GetPointerTouchInfo(input.dwID, &mut touch_info) != 0
+                            && touch_info.touchMask & winuser::TOUCH_MASK_PRESSURE != 0
+                        {
+                            // The pressure ranges from 0 to 1024.
+                            Some(Force::Normalized(touch_info.pressure as f64 / 1024f64))
+                        } else {
+                            None
+                        }
+                    });
                     send_event( Event::WindowEvent {
                         window_id: SuperWindowId(WindowId(window)),
                         event: WindowEvent::Touch(Touch {
@@ -972,6 +1841,8 @@ pub unsafe extern "system" fn callback(
                             location,
                             id: input.dwID as u64,
                             device_id: DEVICE_ID,
+                            force,
+                            contact_size,
                         })
                     });
                 }
@@ -982,10 +1853,13 @@ pub unsafe extern "system" fn callback(
 
         winuser::WM_SETFOCUS => {
             use events::WindowEvent::{Focused, CursorMoved};
+            set_has_focus(window, true);
+            reapply_ime_mode(window);
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
                 event: Focused(true)
             });
+            send_synthetic_keys(window, ElementState::Pressed);
 
             let x = windowsx::GET_X_LPARAM(lparam) as f64;
             let y = windowsx::GET_Y_LPARAM(lparam) as f64;
@@ -997,15 +1871,45 @@ pub unsafe extern "system" fn callback(
                 event: CursorMoved { device_id: DEVICE_ID, position, modifiers: event::get_key_mods() },
             });
 
+            update_occlusion(window);
             0
         },
 
+        winuser::WM_ACTIVATE => {
+            // `lparam` is the window being activated in place of this one, if there's one.
+            if LOWORD(wparam as DWORD) == winuser::WA_INACTIVE {
+                handle_fullscreen_deactivation(window, lparam as HWND);
+            }
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
+        winuser::WM_ACTIVATEAPP => {
+            let active = wparam != 0;
+            let changed = CONTEXT_STASH.with(|context_stash| {
+                match context_stash.borrow_mut().as_mut() {
+                    Some(cstash) => mem::replace(&mut cstash.app_active, active) != active,
+                    None => false,
+                }
+            });
+            if changed {
+                send_event(if active {
+                    Event::ApplicationActivated
+                } else {
+                    Event::ApplicationDeactivated
+                });
+            }
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
         winuser::WM_KILLFOCUS => {
             use events::WindowEvent::Focused;
+            set_has_focus(window, false);
+            send_synthetic_keys(window, ElementState::Released);
             send_event(Event::WindowEvent {
                 window_id: SuperWindowId(WindowId(window)),
                 event: Focused(false)
             });
+            update_occlusion(window);
             0
         },
 
@@ -1038,6 +1942,87 @@ pub unsafe extern "system" fn callback(
             }
         },
 
+        winuser::WM_GETOBJECT if lparam as LONG == accessibility::UiaRootObjectId => {
+            let (first_query, adapter) = CONTEXT_STASH.with(|context_stash| {
+                context_stash
+                    .borrow()
+                    .as_ref()
+                    .and_then(|cstash| cstash.windows.get(&window))
+                    .map(|window_state| {
+                        let mut window_state = window_state.lock().unwrap();
+                        let first_query = !mem::replace(&mut window_state.accessibility_activated, true);
+                        (first_query, window_state.accessibility_adapter.clone())
+                    })
+                    .unwrap_or((false, None))
+            });
+            if first_query {
+                send_event(Event::WindowEvent {
+                    window_id: SuperWindowId(WindowId(window)),
+                    event: WindowEvent::AccessibilityActivated,
+                });
+            }
+            let root_object = adapter
+                .map(|adapter| adapter.get_root_object())
+                .unwrap_or(ptr::null_mut());
+            if !root_object.is_null() {
+                accessibility::UiaReturnRawElementProvider(window, wparam, lparam, root_object as _)
+            } else {
+                winuser::DefWindowProcW(window, msg, wparam, lparam)
+            }
+        },
+
+        winuser::WM_INPUTLANGCHANGE => {
+            let layout = lparam as HKL;
+            let changed = CONTEXT_STASH.with(|context_stash| {
+                match context_stash.borrow_mut().as_mut() {
+                    Some(cstash) => mem::replace(&mut cstash.keyboard_layout, layout) != layout,
+                    None => false,
+                }
+            });
+            if changed {
+                send_event(Event::KeyboardLayoutChanged);
+            }
+            winuser::DefWindowProcW(window, msg, wparam, lparam)
+        },
+
+        winuser::WM_QUERYENDSESSION => {
+            use events::SessionEndReason;
+
+            let (first_query, block_reason) = CONTEXT_STASH.with(|context_stash| {
+                match context_stash.borrow_mut().as_mut() {
+                    Some(cstash) => (
+                        !mem::replace(&mut cstash.session_ending, true),
+                        cstash.session_end_block.clone(),
+                    ),
+                    None => (false, None),
+                }
+            });
+            if first_query {
+                let reason = if lparam as UINT & winuser::ENDSESSION_LOGOFF != 0 {
+                    SessionEndReason::Logout
+                } else {
+                    SessionEndReason::Shutdown
+                };
+                send_event(Event::SessionEnding { reason });
+            }
+            match block_reason {
+                Some(reason) => {
+                    ShutdownBlockReasonCreate(window, reason.as_ptr());
+                    FALSE as LRESULT
+                },
+                None => TRUE as LRESULT,
+            }
+        },
+
+        winuser::WM_ENDSESSION => {
+            CONTEXT_STASH.with(|context_stash| {
+                if let Some(cstash) = context_stash.borrow_mut().as_mut() {
+                    cstash.session_ending = false;
+                }
+            });
+            0
+        },
+
         winuser::WM_DROPFILES => {
             use events::WindowEvent::DroppedFile;
 
@@ -1045,13 +2030,23 @@ pub unsafe extern "system" fn callback(
             let mut pathbuf: [u16; MAX_PATH] = mem::uninitialized();
             let num_drops = shellapi::DragQueryFileW(hdrop, 0xFFFFFFFF, ptr::null_mut(), 0);
 
+            let mut point = POINT { x: 0, y: 0 };
+            shellapi::DragQueryPoint(hdrop, &mut point);
+            let dpi_factor = get_hwnd_scale_factor(window);
+            let position = LogicalPosition::from_physical((point.x, point.y), dpi_factor);
+            let modifiers = event::get_key_mods();
+
             for i in 0..num_drops {
                 let nch = shellapi::DragQueryFileW(hdrop, i, pathbuf.as_mut_ptr(),
                                                   MAX_PATH as u32) as usize;
                 if nch > 0 {
                     send_event(Event::WindowEvent {
                         window_id: SuperWindowId(WindowId(window)),
-                        event: DroppedFile(OsString::from_wide(&pathbuf[0..nch]).into())
+                        event: DroppedFile {
+                            path: OsString::from_wide(&pathbuf[0..nch]).into(),
+                            position,
+                            modifiers,
+                        },
                     });
                 }
             }
@@ -1091,6 +2086,11 @@ pub unsafe extern "system" fn callback(
 
         // Only sent on Windows 8.1 or newer. On Windows 7 and older user has to log out to change
         // DPI, therefore all applications are closed while DPI is changing.
+        winuser::WM_DPICHANGED if get_forced_hwnd_dpi(window).is_some() => {
+            // The window keeps its size and DPI factor.
+            0
+        },
+
         winuser::WM_DPICHANGED => {
             use events::WindowEvent::HiDpiFactorChanged;
 
@@ -1165,6 +2165,18 @@ pub unsafe extern "system" fn callback(
                     window_id: SuperWindowId(WindowId(window)),
                     event: HiDpiFactorChanged(scale_factor),
                 });
+                // A window created maximized or fullscreen already covers the right area.
+                let fullscreen = CONTEXT_STASH.with(|context_stash| {
+                    context_stash
+                        .borrow()
+                        .as_ref()
+                        .and_then(|cstash| cstash.windows.get(&window))
+                        .map(|window_state| window_state.lock().unwrap().fullscreen.is_some())
+                        .unwrap_or(false)
+                });
+                if fullscreen || winuser::IsZoomed(window) != 0 {
+                    return 0;
+                }
                 // Automatically resize for actual DPI
                 let width = LOWORD(lparam as DWORD) as u32;
                 let height = HIWORD(lparam as DWORD) as u32;
@@ -1198,6 +2210,35 @@ pub unsafe extern "system" fn callback(
                     | winuser::SWP_NOACTIVATE,
                 );
                 0
+            } else if msg == *APP_BAR_MSG_ID {
+                // Other appbars or the taskbar moved, so the band has to be reserved again.
+                if wparam as DWORD == shellapi::ABN_POSCHANGED {
+                    let app_bar = CONTEXT_STASH.with(|context_stash| {
+                        context_stash
+                            .borrow()
+                            .as_ref()
+                            .and_then(|cstash| cstash.windows.get(&window))
+                            .and_then(|window_state| window_state.lock().unwrap().app_bar)
+                    });
+                    if let Some((edge, thickness)) = app_bar {
+                        let _ = app_bar::reserve(window, true, edge, thickness);
+                    }
+                }
+                0
+            } else if msg == *VIRTUAL_KEYBOARD_MSG_ID {
+                let location = CONTEXT_STASH.with(|context_stash| {
+                    context_stash
+                        .borrow()
+                        .as_ref()
+                        .and_then(|cstash| cstash.windows.get(&window))
+                        .and_then(|window_state| window_state.lock().unwrap().input_pane.clone())
+                })
+                    .and_then(|input_pane| input_pane.get_location());
+                send_event(Event::WindowEvent {
+                    window_id: SuperWindowId(WindowId(window)),
+                    event: virtual_keyboard::visibility_changed(window, location),
+                });
+                0
             } else {
                 winuser::DefWindowProcW(window, msg, wparam, lparam)
             }
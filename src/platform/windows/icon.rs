@@ -16,12 +16,21 @@ impl Pixel {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum IconType {
     Small = winuser::ICON_SMALL as isize,
     Big = winuser::ICON_BIG as isize,
 }
 
+impl IconType {
+    fn size_metric(&self) -> c_int {
+        match *self {
+            IconType::Small => winuser::SM_CXSMICON,
+            IconType::Big => winuser::SM_CXICON,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct WinIcon {
     pub handle: HICON,
@@ -50,8 +59,11 @@ impl WinIcon {
         }
     }
 
-    pub fn from_icon(icon: Icon) -> Result<Self, util::WinError> {
-        Self::from_rgba(icon.rgba, icon.width, icon.height)
+    // Picks the image of `icon` that suits `icon_type` best.
+    pub fn from_icon(icon: Icon, icon_type: IconType) -> Result<Self, util::WinError> {
+        let size = unsafe { winuser::GetSystemMetrics(icon_type.size_metric()) };
+        let image = icon.into_image_for_size(size as u32);
+        Self::from_rgba(image.rgba, image.width, image.height)
     }
 
     pub fn from_rgba(mut rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, util::WinError> {
@@ -0,0 +1,87 @@
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, LPDWORD, TRUE};
+use winapi::shared::windef::HWND;
+use winapi::um::imm::{self, HIMC};
+
+use ImeMode;
+
+// Not declared by winapi.
+#[link(name = "imm32")]
+extern "system" {
+    fn ImmGetConversionStatus(himc: HIMC, lpfdwConversion: LPDWORD, lpfdwSentence: LPDWORD) -> BOOL;
+    fn ImmSetConversionStatus(himc: HIMC, fdwConversion: DWORD, fdwSentence: DWORD) -> BOOL;
+}
+
+const IME_CMODE_NATIVE: DWORD = 0x0001;
+const IME_CMODE_KATAKANA: DWORD = 0x0002;
+const IME_CMODE_FULLSHAPE: DWORD = 0x0008;
+
+/// The status of the IME before a mode was forced, so that it can be restored.
+#[derive(Debug, Clone, Copy)]
+pub struct ImeStatus {
+    open: BOOL,
+    conversion: DWORD,
+    sentence: DWORD,
+}
+
+/// Calls `f` with the input context of the window, or returns `None` if there's no IME for the
+/// current keyboard layout.
+unsafe fn with_input_context<T, F>(window: HWND, f: F) -> Option<T>
+    where F: FnOnce(HIMC) -> T
+{
+    let himc = imm::ImmGetContext(window);
+    if himc.is_null() {
+        return None;
+    }
+    let result = f(himc);
+    imm::ImmReleaseContext(window, himc);
+    Some(result)
+}
+
+pub unsafe fn has_input_context(window: HWND) -> bool {
+    with_input_context(window, |_| ()).is_some()
+}
+
+pub unsafe fn get_status(window: HWND) -> Option<ImeStatus> {
+    with_input_context(window, |himc| {
+        let mut status = ImeStatus {
+            open: imm::ImmGetOpenStatus(himc),
+            conversion: 0,
+            sentence: 0,
+        };
+        ImmGetConversionStatus(himc, &mut status.conversion, &mut status.sentence);
+        status
+    })
+}
+
+pub unsafe fn set_status(window: HWND, status: ImeStatus) -> Result<(), String> {
+    with_input_context(window, |himc| {
+        ImmSetConversionStatus(himc, status.conversion, status.sentence);
+        imm::ImmSetOpenStatus(himc, status.open);
+    }).ok_or_else(no_input_context)
+}
+
+/// Forces the IME into `mode`. `ImeMode::Default` does nothing, since restoring the previous
+/// status is done with `set_status`.
+pub unsafe fn set_mode(window: HWND, mode: ImeMode) -> Result<(), String> {
+    with_input_context(window, |himc| {
+        let native_mode = match mode {
+            ImeMode::Default => return,
+            ImeMode::Direct => {
+                imm::ImmSetOpenStatus(himc, FALSE);
+                return;
+            },
+            ImeMode::Native => IME_CMODE_NATIVE | IME_CMODE_FULLSHAPE,
+            ImeMode::Katakana => IME_CMODE_NATIVE | IME_CMODE_KATAKANA | IME_CMODE_FULLSHAPE,
+        };
+        let (mut conversion, mut sentence) = (0, 0);
+        ImmGetConversionStatus(himc, &mut conversion, &mut sentence);
+        // Other flags, like the romaji input of Japanese IMEs, are left as the user set them.
+        conversion &= !(IME_CMODE_NATIVE | IME_CMODE_KATAKANA | IME_CMODE_FULLSHAPE);
+        imm::ImmSetOpenStatus(himc, TRUE);
+        ImmSetConversionStatus(himc, conversion | native_mode, sentence);
+    }).ok_or_else(no_input_context)
+}
+
+fn no_input_context() -> String {
+    "There's no IME for the current keyboard layout.".to_owned()
+}
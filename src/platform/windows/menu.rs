@@ -0,0 +1,140 @@
+use std::{mem, ptr};
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+
+use winapi::ctypes::c_int;
+use winapi::shared::basetsd::UINT_PTR;
+use winapi::shared::minwindef::{BYTE, WORD};
+use winapi::shared::windef::{HACCEL, HMENU};
+use winapi::um::winnt::LPCWSTR;
+use winapi::um::winuser;
+
+use menu::{Accelerator, Menu, MenuEntry};
+use platform::platform::event::vkey_to_winit_vkey;
+
+/// An accelerator table, destroyed when dropped.
+#[derive(Debug)]
+pub struct Accelerators {
+    pub handle: HACCEL,
+}
+
+unsafe impl Send for Accelerators {}
+unsafe impl Sync for Accelerators {}
+
+impl Drop for Accelerators {
+    fn drop(&mut self) {
+        unsafe { winuser::DestroyAcceleratorTable(self.handle) };
+    }
+}
+
+/// Builds a menu bar out of `menu`, along with the accelerator table for the items that have an
+/// accelerator.
+///
+/// The menu bar is destroyed along with the window it's attached to, but needs to be destroyed
+/// manually with `DestroyMenu` if it's detached.
+pub unsafe fn create_menu_bar(menu: &Menu) -> (HMENU, Option<Accelerators>) {
+    let hmenu = winuser::CreateMenu();
+    let mut accels = Vec::new();
+    append_entries(hmenu, &menu.entries, &mut accels);
+    let accelerators = if !accels.is_empty() {
+        let handle = winuser::CreateAcceleratorTableW(accels.as_mut_ptr(), accels.len() as c_int);
+        if !handle.is_null() {
+            Some(Accelerators { handle })
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    (hmenu, accelerators)
+}
+
+unsafe fn append_entries(hmenu: HMENU, entries: &[MenuEntry], accels: &mut Vec<winuser::ACCEL>) {
+    for entry in entries {
+        match entry {
+            &MenuEntry::Item(ref item) => {
+                let mut label = item.title.clone();
+                if let Some(accelerator) = item.accelerator {
+                    if let Some(accel) = to_accel(accelerator, item.id.0) {
+                        label.push('\t');
+                        label.push_str(&accelerator_label(accelerator));
+                        accels.push(accel);
+                    }
+                }
+                let mut flags = winuser::MF_STRING;
+                if item.checked == Some(true) {
+                    flags |= winuser::MF_CHECKED;
+                }
+                if !item.enabled {
+                    flags |= winuser::MF_GRAYED;
+                }
+                let label = to_wide(&label);
+                winuser::AppendMenuW(hmenu, flags, item.id.0 as UINT_PTR, label.as_ptr());
+            },
+            &MenuEntry::Submenu(ref submenu) => {
+                let popup = winuser::CreatePopupMenu();
+                append_entries(popup, &submenu.entries, accels);
+                let label = to_wide(&submenu.title);
+                winuser::AppendMenuW(
+                    hmenu,
+                    winuser::MF_STRING | winuser::MF_POPUP,
+                    popup as UINT_PTR,
+                    label.as_ptr(),
+                );
+            },
+            &MenuEntry::Separator => {
+                winuser::AppendMenuW(
+                    hmenu,
+                    winuser::MF_SEPARATOR,
+                    0,
+                    ptr::null::<u16>() as LPCWSTR,
+                );
+            },
+        }
+    }
+}
+
+fn to_accel(accelerator: Accelerator, id: u16) -> Option<winuser::ACCEL> {
+    // There's no mapping in that direction, so we search the one used for keyboard input instead.
+    let vkey = (1..255).find(|&vkey| vkey_to_winit_vkey(vkey) == Some(accelerator.key))?;
+    let mut virt = winuser::FVIRTKEY;
+    if accelerator.modifiers.shift {
+        virt |= winuser::FSHIFT;
+    }
+    if accelerator.modifiers.ctrl {
+        virt |= winuser::FCONTROL;
+    }
+    if accelerator.modifiers.alt {
+        virt |= winuser::FALT;
+    }
+    let mut accel: winuser::ACCEL = unsafe { mem::zeroed() };
+    accel.fVirt = virt as BYTE;
+    accel.key = vkey as WORD;
+    accel.cmd = id as WORD;
+    Some(accel)
+}
+
+// Formats the accelerator the way it's conventionally shown next to the item, i.e. "Ctrl+Shift+S".
+fn accelerator_label(accelerator: Accelerator) -> String {
+    let mut label = String::new();
+    if accelerator.modifiers.ctrl {
+        label.push_str("Ctrl+");
+    }
+    if accelerator.modifiers.alt {
+        label.push_str("Alt+");
+    }
+    if accelerator.modifiers.shift {
+        label.push_str("Shift+");
+    }
+    let key = format!("{:?}", accelerator.key);
+    // The digit keys are named `Key1`, `Key2`, etc.
+    label.push_str(key.trim_left_matches("Key"));
+    label
+}
+
+fn to_wide(text: &str) -> Vec<u16> {
+    OsStr::new(text)
+        .encode_wide()
+        .chain(Some(0).into_iter())
+        .collect()
+}
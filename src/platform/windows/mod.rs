@@ -1,17 +1,43 @@
 #![cfg(target_os = "windows")]
 
 use winapi;
+use winapi::shared::minwindef::HINSTANCE;
 use winapi::shared::windef::HWND;
 
-pub use self::events_loop::{EventsLoop, EventsLoopProxy};
+pub use self::events_loop::{EventsLoop, EventsLoopProxy, FocusSnapshot, IdleTimeQuery};
 pub use self::monitor::MonitorId;
+#[cfg(feature = "surface")]
+pub use self::surface::Surface;
+#[cfg(feature = "shell")]
+pub use self::shell::{open_with_default_app, reveal_in_file_manager};
+#[cfg(feature = "tray")]
+pub use self::tray::TrayIcon;
 pub use self::window::Window;
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct PlatformSpecificWindowBuilderAttributes {
     pub parent: Option<HWND>,
     pub taskbar_icon: Option<::Icon>,
     pub no_redirection_bitmap: bool,
+    pub drag_and_drop: bool,
+    pub class_name: Option<String>,
+    pub hinstance: Option<HINSTANCE>,
+    // Copied from the `EventsLoop` when the window is created.
+    pub activation_policy: ::ActivationPolicy,
+}
+
+impl Default for PlatformSpecificWindowBuilderAttributes {
+    fn default() -> Self {
+        PlatformSpecificWindowBuilderAttributes {
+            parent: None,
+            taskbar_icon: None,
+            no_redirection_bitmap: false,
+            drag_and_drop: true,
+            class_name: None,
+            hinstance: None,
+            activation_policy: ::ActivationPolicy::Regular,
+        }
+    }
 }
 
 unsafe impl Send for PlatformSpecificWindowBuilderAttributes {}
@@ -26,7 +52,23 @@ unsafe impl Sync for Cursor {}
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId(u32);
 
+#[cfg(feature = "serde")]
 impl DeviceId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+
+    pub fn from_raw(raw: u64) -> DeviceId {
+        DeviceId(raw as u32)
+    }
+}
+
+impl DeviceId {
+    #[cfg(feature = "test-util")]
+    pub fn dummy() -> DeviceId {
+        DeviceId(0)
+    }
+
     pub fn get_persistent_identifier(&self) -> Option<String> {
         if self.0 != 0 {
             raw_input::get_raw_input_device_name(self.0 as _)
@@ -48,11 +90,54 @@ pub struct WindowId(HWND);
 unsafe impl Send for WindowId {}
 unsafe impl Sync for WindowId {}
 
+#[cfg(feature = "serde")]
+impl WindowId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[cfg(any(test, feature = "serde", feature = "test-util"))]
+impl WindowId {
+    pub fn from_raw(raw: u64) -> WindowId {
+        WindowId(raw as HWND)
+    }
+}
+
+// The message-only window that receives the notifications of the tray icon. Defined without the
+// `tray` feature too, since `Event::TrayEvent` always exists.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId(HWND);
+unsafe impl Send for TrayIconId {}
+unsafe impl Sync for TrayIconId {}
+
+#[cfg(feature = "serde")]
+impl TrayIconId {
+    pub fn into_raw(self) -> u64 {
+        self.0 as u64
+    }
+
+    pub fn from_raw(raw: u64) -> TrayIconId {
+        TrayIconId(raw as HWND)
+    }
+}
+
+mod accessibility;
+mod app_bar;
+#[macro_use]
 mod dpi;
 mod event;
 mod events_loop;
 mod icon;
+mod ime;
+mod menu;
 mod monitor;
 mod raw_input;
+mod shell;
+mod surface;
+mod taskbar;
+mod tray;
 mod util;
+mod vblank;
+mod virtual_keyboard;
 mod window;
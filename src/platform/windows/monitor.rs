@@ -1,5 +1,5 @@
 use winapi::shared::minwindef::{BOOL, DWORD, LPARAM, TRUE};
-use winapi::shared::windef::{HDC, HMONITOR, HWND, LPRECT, POINT};
+use winapi::shared::windef::{HDC, HMONITOR, HWND, LPRECT, POINT, RECT};
 use winapi::um::winnt::LONG;
 use winapi::um::winuser;
 
@@ -7,7 +7,9 @@ use std::{mem, ptr};
 use std::collections::VecDeque;
 
 use super::{EventsLoop, util};
+use Edge;
 use dpi::{PhysicalPosition, PhysicalSize};
+use platform::platform::app_bar;
 use platform::platform::dpi::{dpi_to_scale_factor, get_monitor_dpi};
 use platform::platform::window::Window;
 
@@ -170,4 +172,37 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         self.hidpi_factor
     }
+
+    // Unlike the rest, the work area changes whenever a taskbar or appbar moves, so it's not
+    // stored.
+    fn get_work_rect(&self) -> Option<RECT> {
+        get_monitor_info(self.hmonitor.0)
+            .ok()
+            .map(|monitor_info| monitor_info.rcWork)
+    }
+
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        match self.get_work_rect() {
+            Some(rect) => (
+                (rect.left as i32, rect.top as i32).into(),
+                ((rect.right - rect.left) as u32, (rect.bottom - rect.top) as u32).into(),
+            ),
+            None => (self.get_position(), self.get_dimensions()),
+        }
+    }
+
+    pub fn get_taskbar_edge(&self) -> Option<Edge> {
+        if let Some(edge) = unsafe { app_bar::get_taskbar_edge(self.hmonitor.0) } {
+            return Some(edge);
+        }
+        // Taskbars on other monitors can only be found through the space they reserve.
+        let rect = self.get_work_rect()?;
+        let (left, top) = (self.position.0 as i64, self.position.1 as i64);
+        Edge::of_largest_inset(
+            rect.left as i64 - left,
+            rect.top as i64 - top,
+            left + self.dimensions.0 as i64 - rect.right as i64,
+            top + self.dimensions.1 as i64 - rect.bottom as i64,
+        )
+    }
 }
@@ -1,15 +1,21 @@
+use std::ffi::OsStr;
 use std::mem::{self, size_of};
+use std::os::windows::ffi::OsStrExt;
 use std::ptr;
 
 use winapi::ctypes::wchar_t;
-use winapi::shared::minwindef::{UINT, USHORT, TRUE};
+use winapi::shared::hidsdi;
+use winapi::shared::minwindef::{ULONG, UINT, USHORT, TRUE};
 use winapi::shared::hidusage::{
     HID_USAGE_PAGE_GENERIC,
     HID_USAGE_GENERIC_MOUSE,
+    HID_USAGE_GENERIC_JOYSTICK,
+    HID_USAGE_GENERIC_GAMEPAD,
     HID_USAGE_GENERIC_KEYBOARD,
 };
 use winapi::shared::windef::HWND;
-use winapi::um::winnt::HANDLE;
+use winapi::um::{fileapi, handleapi};
+use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, HANDLE};
 use winapi::um::winuser::{
     self,
     RAWINPUTDEVICELIST,
@@ -34,7 +40,6 @@ use winapi::um::winuser::{
 use platform::platform::util;
 use events::ElementState;
 
-#[allow(dead_code)]
 pub fn get_raw_input_device_list() -> Option<Vec<RAWINPUTDEVICELIST>> {
     let list_size = size_of::<RAWINPUTDEVICELIST>() as UINT;
 
@@ -142,7 +147,45 @@ pub fn get_raw_input_device_name(handle: HANDLE) -> Option<String> {
 
     unsafe { name.set_len(minimum_size as _) };
 
-    Some(util::wchar_to_string(&name))
+    Some(util::wchar_ptr_to_string(name.as_ptr()))
+}
+
+/// Returns the product string of the device whose interface path is `path`, which is the name
+/// given by `get_raw_input_device_name`.
+pub fn get_hid_product_string(path: &str) -> Option<String> {
+    let path: Vec<u16> = OsStr::new(path).encode_wide().chain(Some(0)).collect();
+    unsafe {
+        // No access rights are needed to query the strings of the device, which also lets mice
+        // and keyboards be opened while the system has them open exclusively.
+        let handle = fileapi::CreateFileW(
+            path.as_ptr(),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            ptr::null_mut(),
+            fileapi::OPEN_EXISTING,
+            0,
+            ptr::null_mut(),
+        );
+        if handle == handleapi::INVALID_HANDLE_VALUE {
+            return None;
+        }
+        // USB strings are at most 126 characters long.
+        let mut buffer = [0 as wchar_t; 127];
+        let status = hidsdi::HidD_GetProductString(
+            handle,
+            buffer.as_mut_ptr() as _,
+            (buffer.len() * size_of::<wchar_t>()) as ULONG,
+        );
+        handleapi::CloseHandle(handle);
+        // The string isn't null-terminated if it fills the buffer, which the last element keeps
+        // from happening.
+        let name = util::wchar_ptr_to_string(buffer.as_ptr());
+        if status == 0 || name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
 }
 
 pub fn register_raw_input_devices(devices: &[RAWINPUTDEVICE]) -> bool {
@@ -157,12 +200,12 @@ pub fn register_raw_input_devices(devices: &[RAWINPUTDEVICE]) -> bool {
     success == TRUE
 }
 
-pub fn register_all_mice_and_keyboards_for_raw_input(window_handle: HWND) -> bool {
+pub fn register_all_devices_for_raw_input(window_handle: HWND) -> bool {
     // RIDEV_DEVNOTIFY: receive hotplug events
     // RIDEV_INPUTSINK: receive events even if we're not in the foreground
     let flags = RIDEV_DEVNOTIFY | RIDEV_INPUTSINK;
 
-    let devices: [RAWINPUTDEVICE; 2] = [
+    let devices: [RAWINPUTDEVICE; 4] = [
         RAWINPUTDEVICE {
             usUsagePage: HID_USAGE_PAGE_GENERIC,
             usUsage: HID_USAGE_GENERIC_MOUSE,
@@ -175,6 +218,20 @@ pub fn register_all_mice_and_keyboards_for_raw_input(window_handle: HWND) -> boo
             dwFlags: flags,
             hwndTarget: window_handle,
         },
+        // Controllers are only registered for their hotplug events, since their input isn't
+        // handled.
+        RAWINPUTDEVICE {
+            usUsagePage: HID_USAGE_PAGE_GENERIC,
+            usUsage: HID_USAGE_GENERIC_JOYSTICK,
+            dwFlags: RIDEV_DEVNOTIFY,
+            hwndTarget: window_handle,
+        },
+        RAWINPUTDEVICE {
+            usUsagePage: HID_USAGE_PAGE_GENERIC,
+            usUsage: HID_USAGE_GENERIC_GAMEPAD,
+            dwFlags: RIDEV_DEVNOTIFY,
+            hwndTarget: window_handle,
+        },
     ];
 
     register_raw_input_devices(&devices)
@@ -0,0 +1,64 @@
+#![cfg(feature = "shell")]
+
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::{shellapi, shlobj, winuser};
+use winapi::um::shtypes::PIDLIST_ABSOLUTE;
+use winapi::um::winnt::LPCWSTR;
+
+use platform::platform::window::com_initialized;
+
+// Not declared by winapi.
+#[link(name = "shell32")]
+extern "system" {
+    fn ILCreateFromPathW(pszPath: LPCWSTR) -> PIDLIST_ABSOLUTE;
+    fn ILFree(pidl: PIDLIST_ABSOLUTE);
+}
+
+fn to_wide(path: &OsStr) -> Vec<u16> {
+    path.encode_wide().chain(Some(0)).collect()
+}
+
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    com_initialized();
+    let path = to_wide(path.as_os_str());
+    unsafe {
+        let pidl = ILCreateFromPathW(path.as_ptr());
+        if pidl.is_null() {
+            return Err("`ILCreateFromPathW` failed".to_owned());
+        }
+        // With no children given, the item the ID list points to is selected in its folder.
+        let result = shlobj::SHOpenFolderAndSelectItems(pidl, 0, ptr::null(), 0);
+        ILFree(pidl);
+        if SUCCEEDED(result) {
+            Ok(())
+        } else {
+            Err(format!("`SHOpenFolderAndSelectItems` failed: HRESULT {:#x}", result))
+        }
+    }
+}
+
+pub fn open_with_default_app(path: &Path) -> Result<(), String> {
+    let operation = to_wide(OsStr::new("open"));
+    let path = to_wide(path.as_os_str());
+    let result = unsafe {
+        shellapi::ShellExecuteW(
+            ptr::null_mut(),
+            operation.as_ptr(),
+            path.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            winuser::SW_SHOWNORMAL,
+        )
+    };
+    // For compatibility with 16-bit Windows, errors are the values up to 32.
+    if result as usize > 32 {
+        Ok(())
+    } else {
+        Err(format!("`ShellExecuteW` failed with error {}", result as usize))
+    }
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "surface")]
+
+use winapi::shared::windef::{HDC, HWND};
+use winapi::um::winuser;
+
+use {CreationError, PhysicalSize};
+use surface::NativeSurface;
+use platform::platform::Window;
+
+pub struct Surface {
+    hwnd: HWND,
+    hdc: HDC,
+}
+
+impl Surface {
+    pub fn new(window: &Window) -> Result<Surface, CreationError> {
+        let hwnd = window.hwnd();
+        // Window classes are registered with `CS_OWNDC`, so this is the same device context for
+        // the lifetime of the window.
+        let hdc = unsafe { winuser::GetDC(hwnd) };
+        if hdc.is_null() {
+            return Err(CreationError::OsError("`GetDC` failed".to_owned()));
+        }
+        Ok(Surface { hwnd, hdc })
+    }
+
+    pub fn native(&self) -> NativeSurface {
+        NativeSurface::Windows {
+            hwnd: self.hwnd as *mut _,
+            hdc: self.hdc as *mut _,
+        }
+    }
+
+    pub fn resize(&self, _size: PhysicalSize) {
+        // N/A: the device context always covers the whole client area.
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        unsafe { winuser::ReleaseDC(self.hwnd, self.hdc) };
+    }
+}
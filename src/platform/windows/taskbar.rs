@@ -0,0 +1,111 @@
+//! Access to the window's taskbar button, through `ITaskbarList3`.
+
+#![allow(non_snake_case)]
+
+use std::ptr;
+use std::cell::Cell;
+
+use winapi::Interface;
+use winapi::ctypes::c_void;
+use winapi::shared::minwindef::{DWORD, UINT};
+use winapi::shared::ntdef::ULONGLONG;
+use winapi::shared::windef::{HICON, HWND, RECT};
+use winapi::shared::winerror::{HRESULT, S_OK};
+use winapi::um::combaseapi;
+use winapi::um::shobjidl_core::{CLSID_TaskbarList, ITaskbarList2, ITaskbarList2Vtbl};
+use winapi::um::winnt::LPCWSTR;
+
+use platform::platform::window::com_initialized;
+
+// Not provided by winapi yet.
+RIDL!{#[uuid(0xea1afb91, 0x9e28, 0x4b86, 0x90, 0xe9, 0x9e, 0x9f, 0x8a, 0x5e, 0xef, 0xaf)]
+interface ITaskbarList3(ITaskbarList3Vtbl): ITaskbarList2(ITaskbarList2Vtbl) {
+    fn SetProgressValue(
+        hwnd: HWND,
+        ullCompleted: ULONGLONG,
+        ullTotal: ULONGLONG,
+    ) -> HRESULT,
+    fn SetProgressState(
+        hwnd: HWND,
+        tbpFlags: UINT,
+    ) -> HRESULT,
+    fn RegisterTab(
+        hwndTab: HWND,
+        hwndMDI: HWND,
+    ) -> HRESULT,
+    fn UnregisterTab(
+        hwndTab: HWND,
+    ) -> HRESULT,
+    fn SetTabOrder(
+        hwndTab: HWND,
+        hwndInsertBefore: HWND,
+    ) -> HRESULT,
+    fn SetTabActive(
+        hwndTab: HWND,
+        hwndMDI: HWND,
+        dwReserved: DWORD,
+    ) -> HRESULT,
+    fn ThumbBarAddButtons(
+        hwnd: HWND,
+        cButtons: UINT,
+        pButton: *mut c_void,
+    ) -> HRESULT,
+    fn ThumbBarUpdateButtons(
+        hwnd: HWND,
+        cButtons: UINT,
+        pButton: *mut c_void,
+    ) -> HRESULT,
+    fn ThumbBarSetImageList(
+        hwnd: HWND,
+        himl: *mut c_void,
+    ) -> HRESULT,
+    fn SetOverlayIcon(
+        hwnd: HWND,
+        hIcon: HICON,
+        pszDescription: LPCWSTR,
+    ) -> HRESULT,
+    fn SetThumbnailTooltip(
+        hwnd: HWND,
+        pszTip: LPCWSTR,
+    ) -> HRESULT,
+    fn SetThumbnailClip(
+        hwnd: HWND,
+        prcClip: *mut RECT,
+    ) -> HRESULT,
+}}
+
+// Not provided by winapi yet.
+pub const TBPF_NOPROGRESS: UINT = 0;
+
+thread_local!{
+    static TASKBAR_LIST: Cell<*mut ITaskbarList3> = Cell::new(ptr::null_mut());
+}
+
+/// Calls `f` with this thread's taskbar list, creating it on first use.
+///
+/// Does nothing if the taskbar list can't be created, which is the case on versions of Windows
+/// older than 7.
+pub unsafe fn with_taskbar_list<F: FnOnce(&ITaskbarList3)>(f: F) {
+    com_initialized();
+
+    TASKBAR_LIST.with(|task_bar_list_ptr| {
+        let mut task_bar_list = task_bar_list_ptr.get();
+
+        if task_bar_list.is_null() {
+            let hr = combaseapi::CoCreateInstance(
+                &CLSID_TaskbarList,
+                ptr::null_mut(),
+                combaseapi::CLSCTX_ALL,
+                &ITaskbarList3::uuidof(),
+                &mut task_bar_list as *mut _ as *mut _,
+            );
+
+            if hr != S_OK || (*task_bar_list).HrInit() != S_OK {
+                return;
+            }
+            task_bar_list_ptr.set(task_bar_list);
+        }
+
+        f(&*task_bar_list);
+    })
+}
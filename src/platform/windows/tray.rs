@@ -0,0 +1,260 @@
+#![cfg(feature = "tray")]
+
+use std::{cmp, mem, ptr};
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::channel;
+
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::{libloaderapi, shellapi, winuser};
+use winapi::um::winnt::{LPCSTR, LPCWSTR};
+
+use {CreationError, Event, Icon, MouseButton};
+use tray::{TrayEvent, TrayIconAttributes};
+use platform::platform::events_loop::{self, EventsLoop, DESTROY_MSG_ID};
+use platform::platform::icon::{IconType, WinIcon};
+use platform::platform::util;
+use platform::platform::TrayIconId;
+
+lazy_static! {
+    // Message sent by the shell when the user interacts with a tray icon.
+    // WPARAM is the icon's ID (always 0, since each icon has its own window), and LPARAM is the
+    // mouse message that triggered it.
+    static ref TRAY_MSG_ID: u32 = {
+        unsafe {
+            winuser::RegisterWindowMessageA("Winit::TrayMsg\0".as_ptr() as LPCSTR)
+        }
+    };
+    // Message broadcast to top-level windows when the taskbar is created, which happens again
+    // whenever Explorer restarts. The icons added before are gone by then.
+    static ref TASKBAR_CREATED_MSG_ID: u32 = {
+        unsafe {
+            winuser::RegisterWindowMessageA("TaskbarCreated\0".as_ptr() as LPCSTR)
+        }
+    };
+}
+
+// What a tray icon shows, kept to add it again when the taskbar is recreated.
+struct TrayState {
+    // The shell doesn't copy the icon, so we need to keep it alive for as long as it's displayed.
+    icon: Option<WinIcon>,
+    tooltip: Option<String>,
+}
+
+/// A tray icon, along with the hidden window that receives its notifications.
+pub struct TrayIcon {
+    window: TrayIconId,
+    // Also owned by the window, which needs it when the taskbar is recreated.
+    state: Arc<Mutex<TrayState>>,
+}
+
+impl TrayIcon {
+    pub fn new(
+        events_loop: &EventsLoop,
+        attributes: TrayIconAttributes,
+    ) -> Result<TrayIcon, CreationError> {
+        let icon = match attributes.icon {
+            Some(icon) => Some(WinIcon::from_icon(icon, IconType::Small).map_err(|err| {
+                CreationError::OsError(format!("Failed to create tray icon: {:?}", err))
+            })?),
+            None => None,
+        };
+
+        let state = Arc::new(Mutex::new(TrayState { icon, tooltip: attributes.tooltip }));
+
+        // The window needs to be created in the events loop thread, so that the shell's
+        // notifications end up being dispatched there.
+        let (tx, rx) = channel();
+        let mut window_state = Some(Arc::clone(&state));
+        events_loop.execute_in_thread(move |_| {
+            let window_state = window_state.take().unwrap();
+            let _ = tx.send(unsafe { create_hidden_window(window_state) });
+        });
+        let window = rx.recv().unwrap()?;
+
+        if !unsafe { add_icon(window.0, &state.lock().unwrap()) } {
+            unsafe { winuser::PostMessageW(window.0, *DESTROY_MSG_ID, 0, 0) };
+            return Err(CreationError::OsError(format!(
+                "Shell_NotifyIconW function failed: {:?}",
+                util::WinError::from_last_error(),
+            )));
+        }
+
+        Ok(TrayIcon { window, state })
+    }
+
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        self.window
+    }
+
+    pub fn set_icon(&self, icon: Option<Icon>) {
+        let icon = match icon {
+            Some(icon) => match WinIcon::from_icon(icon, IconType::Small) {
+                Ok(icon) => Some(icon),
+                Err(err) => {
+                    warn!("Failed to create tray icon, keeping the current one: {:?}", err);
+                    return;
+                },
+            },
+            None => None,
+        };
+        let mut data = notify_icon_data(self.window.0);
+        data.uFlags = shellapi::NIF_ICON;
+        data.hIcon = icon
+            .as_ref()
+            .map(|icon| icon.handle)
+            .unwrap_or(ptr::null_mut());
+        // Hold the lock until the shell has switched over, so the old icon outlives its use.
+        let mut state = self.state.lock().unwrap();
+        unsafe { shellapi::Shell_NotifyIconW(shellapi::NIM_MODIFY, &mut data) };
+        state.icon = icon;
+    }
+
+    pub fn set_tooltip(&self, tooltip: &str) {
+        let mut data = notify_icon_data(self.window.0);
+        data.uFlags = shellapi::NIF_TIP;
+        set_tooltip_text(&mut data, tooltip);
+        let mut state = self.state.lock().unwrap();
+        unsafe { shellapi::Shell_NotifyIconW(shellapi::NIM_MODIFY, &mut data) };
+        state.tooltip = Some(tooltip.to_owned());
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        let mut data = notify_icon_data(self.window.0);
+        unsafe {
+            shellapi::Shell_NotifyIconW(shellapi::NIM_DELETE, &mut data);
+            // The window must be destroyed from the same thread that created it, so we send a
+            // custom message to be handled by our callback to do the actual work.
+            winuser::PostMessageW(self.window.0, *DESTROY_MSG_ID, 0, 0);
+        }
+    }
+}
+
+fn notify_icon_data(window: HWND) -> shellapi::NOTIFYICONDATAW {
+    let mut data: shellapi::NOTIFYICONDATAW = unsafe { mem::zeroed() };
+    data.cbSize = mem::size_of::<shellapi::NOTIFYICONDATAW>() as _;
+    data.hWnd = window;
+    data.uID = 0;
+    data
+}
+
+// Adds the icon of `window` to the notification area. Returns `false` if that failed.
+unsafe fn add_icon(window: HWND, state: &TrayState) -> bool {
+    let mut data = notify_icon_data(window);
+    data.uFlags = shellapi::NIF_MESSAGE;
+    data.uCallbackMessage = *TRAY_MSG_ID;
+    if let Some(ref icon) = state.icon {
+        data.uFlags |= shellapi::NIF_ICON;
+        data.hIcon = icon.handle;
+    }
+    if let Some(ref tooltip) = state.tooltip {
+        data.uFlags |= shellapi::NIF_TIP;
+        set_tooltip_text(&mut data, tooltip);
+    }
+    shellapi::Shell_NotifyIconW(shellapi::NIM_ADD, &mut data) != 0
+}
+
+fn set_tooltip_text(data: &mut shellapi::NOTIFYICONDATAW, tooltip: &str) {
+    let tooltip: Vec<u16> = OsStr::new(tooltip).encode_wide().collect();
+    // Tooltips longer than the buffer are truncated, leaving room for the null terminator.
+    let len = cmp::min(tooltip.len(), data.szTip.len() - 1);
+    data.szTip[..len].copy_from_slice(&tooltip[..len]);
+    data.szTip[len] = 0;
+}
+
+// The window is a hidden top-level one rather than a message-only one, since message-only windows
+// don't receive broadcasts like `TaskbarCreated`. It keeps a reference to `state` until it's
+// destroyed.
+unsafe fn create_hidden_window(state: Arc<Mutex<TrayState>>) -> Result<TrayIconId, CreationError> {
+    let class_name: Vec<_> = OsStr::new("Winit Tray Class")
+        .encode_wide()
+        .chain(Some(0).into_iter())
+        .collect();
+
+    let class = winuser::WNDCLASSEXW {
+        cbSize: mem::size_of::<winuser::WNDCLASSEXW>() as UINT,
+        style: 0,
+        lpfnWndProc: Some(callback),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
+        hIcon: ptr::null_mut(),
+        hCursor: ptr::null_mut(),
+        hbrBackground: ptr::null_mut(),
+        lpszMenuName: ptr::null(),
+        lpszClassName: class_name.as_ptr(),
+        hIconSm: ptr::null_mut(),
+    };
+    // As with regular windows, registering the class more than once fails harmlessly.
+    winuser::RegisterClassExW(&class);
+
+    let window = winuser::CreateWindowExW(
+        winuser::WS_EX_TOOLWINDOW,
+        class_name.as_ptr(),
+        ptr::null::<u16>() as LPCWSTR,
+        0,
+        0, 0, 0, 0,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        libloaderapi::GetModuleHandleW(ptr::null()),
+        ptr::null_mut(),
+    );
+    if window.is_null() {
+        return Err(CreationError::OsError(format!(
+            "CreateWindowEx function failed: {:?}",
+            util::WinError::from_last_error(),
+        )));
+    }
+    winuser::SetWindowLongPtrW(window, winuser::GWLP_USERDATA, Arc::into_raw(state) as _);
+    Ok(TrayIconId(window))
+}
+
+unsafe extern "system" fn callback(
+    window: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == *TRAY_MSG_ID {
+        let event = match lparam as UINT {
+            winuser::WM_LBUTTONUP => Some(TrayEvent::Click(MouseButton::Left)),
+            winuser::WM_RBUTTONUP => Some(TrayEvent::Click(MouseButton::Right)),
+            winuser::WM_MBUTTONUP => Some(TrayEvent::Click(MouseButton::Middle)),
+            winuser::WM_LBUTTONDBLCLK => Some(TrayEvent::DoubleClick(MouseButton::Left)),
+            winuser::WM_RBUTTONDBLCLK => Some(TrayEvent::DoubleClick(MouseButton::Right)),
+            winuser::WM_MBUTTONDBLCLK => Some(TrayEvent::DoubleClick(MouseButton::Middle)),
+            _ => None,
+        };
+        if let Some(event) = event {
+            events_loop::send_event(Event::TrayEvent {
+                tray_id: ::tray::TrayIconId(TrayIconId(window)),
+                event,
+            });
+        }
+        0
+    } else if msg == *TASKBAR_CREATED_MSG_ID {
+        let state = winuser::GetWindowLongPtrW(window, winuser::GWLP_USERDATA);
+        let state = state as *const Mutex<TrayState>;
+        if !state.is_null() && !add_icon(window, &(*state).lock().unwrap()) {
+            warn!("Failed to add the tray icon again after the taskbar was recreated");
+        }
+        0
+    } else if msg == *DESTROY_MSG_ID {
+        winuser::DestroyWindow(window);
+        0
+    } else if msg == winuser::WM_NCDESTROY {
+        let state = winuser::SetWindowLongPtrW(window, winuser::GWLP_USERDATA, 0);
+        let state = state as *const Mutex<TrayState>;
+        if !state.is_null() {
+            drop(Arc::from_raw(state));
+        }
+        winuser::DefWindowProcW(window, msg, wparam, lparam)
+    } else {
+        winuser::DefWindowProcW(window, msg, wparam, lparam)
+    }
+}
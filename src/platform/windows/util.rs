@@ -2,7 +2,7 @@ use std::{self, mem, ptr, slice};
 use std::ops::BitAnd;
 
 use winapi::ctypes::wchar_t;
-use winapi::shared::minwindef::{BOOL, DWORD};
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, HRGN};
 use winapi::shared::windef::{HWND, POINT, RECT};
 use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::winbase::{
@@ -19,7 +19,7 @@ use winapi::um::winnt::{
     LANG_NEUTRAL,
     SUBLANG_DEFAULT,
 };
-use winapi::um::winuser;
+use winapi::um::{wingdi, winuser};
 
 pub fn has_flag<T>(bitset: T, flag: T) -> bool
 where T:
@@ -55,13 +55,54 @@ pub fn get_window_rect(hwnd: HWND) -> Option<RECT> {
     unsafe { status_map(|rect| winuser::GetWindowRect(hwnd, rect)) }
 }
 
-// This won't be needed anymore if we just add a derive to winapi.
-pub fn rect_eq(a: &RECT, b: &RECT) -> bool {
-    let left_eq = a.left == b.left;
-    let right_eq = a.right == b.right;
-    let top_eq = a.top == b.top;
-    let bottom_eq = a.bottom == b.bottom;
-    left_eq && right_eq && top_eq && bottom_eq
+/// Returns the visible top-level windows for which `keep` returns true, from front to back.
+pub fn get_windows_front_to_back<F>(mut keep: F) -> Vec<HWND>
+    where F: FnMut(HWND) -> bool
+{
+    let mut windows = Vec::new();
+    unsafe {
+        let mut hwnd = winuser::GetTopWindow(ptr::null_mut());
+        while !hwnd.is_null() {
+            if winuser::IsWindowVisible(hwnd) != 0 && keep(hwnd) {
+                windows.push(hwnd);
+            }
+            hwnd = winuser::GetWindow(hwnd, winuser::GW_HWNDNEXT);
+        }
+    }
+    windows
+}
+
+/// Returns the rectangles making up the window's update region, or `None` if it's empty or
+/// couldn't be retrieved.
+pub unsafe fn get_update_rects(hwnd: HWND) -> Option<Vec<RECT>> {
+    let region = wingdi::CreateRectRgn(0, 0, 0, 0);
+    if region.is_null() {
+        return None;
+    }
+    let rects = get_region_rects(hwnd, region);
+    wingdi::DeleteObject(region as _);
+    rects
+}
+
+unsafe fn get_region_rects(hwnd: HWND, region: HRGN) -> Option<Vec<RECT>> {
+    match winuser::GetUpdateRgn(hwnd, region, FALSE) {
+        wingdi::ERROR | wingdi::NULLREGION => return None,
+        _ => (),
+    }
+    let size = wingdi::GetRegionData(region, 0, ptr::null_mut());
+    if size == 0 {
+        return None;
+    }
+    // The rectangles follow the `RGNDATA` header, so the buffer is allocated as `RECT`s to keep
+    // them aligned.
+    let rect_size = mem::size_of::<RECT>();
+    let mut buffer: Vec<RECT> = Vec::with_capacity((size as usize + rect_size - 1) / rect_size);
+    let data = buffer.as_mut_ptr() as *mut wingdi::RGNDATA;
+    if wingdi::GetRegionData(region, size, data) == 0 {
+        return None;
+    }
+    let rects = (*data).Buffer.as_ptr() as *const RECT;
+    Some(slice::from_raw_parts(rects, (*data).rdh.nCount as usize).to_vec())
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
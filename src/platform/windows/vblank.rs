@@ -0,0 +1,97 @@
+//! Waiting for the vertical blank of a monitor, through `IDXGIOutput`.
+
+use std::{mem, ptr};
+
+use winapi::Interface;
+use winapi::shared::dxgi::{
+    CreateDXGIFactory1,
+    DXGI_OUTPUT_DESC,
+    IDXGIAdapter1,
+    IDXGIFactory1,
+    IDXGIOutput,
+};
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::windef::{HMONITOR, POINT};
+use winapi::shared::winerror::S_OK;
+use winapi::um::{processthreadsapi, winuser};
+
+/// The DXGI output of a monitor.
+pub struct VBlankOutput {
+    monitor: HMONITOR,
+    output: *mut IDXGIOutput,
+}
+
+// DXGI objects can be used from any thread.
+unsafe impl Send for VBlankOutput {}
+
+impl VBlankOutput {
+    /// Finds the output of `monitor` among those of every adapter.
+    ///
+    /// Returns `None` if DXGI isn't available, or no adapter drives the monitor, which is the
+    /// case in remote desktop sessions.
+    pub unsafe fn new(monitor: HMONITOR) -> Option<VBlankOutput> {
+        let mut factory: *mut IDXGIFactory1 = ptr::null_mut();
+        let hr = CreateDXGIFactory1(&IDXGIFactory1::uuidof(), &mut factory as *mut _ as *mut _);
+        if hr != S_OK {
+            return None;
+        }
+
+        let mut found = None;
+        let mut adapter_index = 0;
+        'adapters: loop {
+            let mut adapter: *mut IDXGIAdapter1 = ptr::null_mut();
+            if (*factory).EnumAdapters1(adapter_index, &mut adapter) != S_OK {
+                break;
+            }
+            let mut output_index = 0;
+            loop {
+                let mut output: *mut IDXGIOutput = ptr::null_mut();
+                if (*adapter).EnumOutputs(output_index, &mut output) != S_OK {
+                    break;
+                }
+                let mut desc: DXGI_OUTPUT_DESC = mem::zeroed();
+                if (*output).GetDesc(&mut desc) == S_OK && desc.Monitor == monitor {
+                    found = Some(VBlankOutput { monitor, output });
+                    (*adapter).Release();
+                    break 'adapters;
+                }
+                (*output).Release();
+                output_index += 1;
+            }
+            (*adapter).Release();
+            adapter_index += 1;
+        }
+        (*factory).Release();
+        found
+    }
+
+    #[inline]
+    pub fn monitor(&self) -> HMONITOR {
+        self.monitor
+    }
+
+    /// Blocks until the next vertical blank of the monitor. Returns `false` if that failed.
+    #[inline]
+    pub fn wait(&self) -> bool {
+        unsafe { (*self.output).WaitForVBlank() == S_OK }
+    }
+}
+
+impl Drop for VBlankOutput {
+    fn drop(&mut self) {
+        unsafe { (*self.output).Release(); }
+    }
+}
+
+/// Returns the monitor the foreground window is on if it belongs to this process, and the primary
+/// monitor otherwise.
+pub unsafe fn get_focused_monitor() -> HMONITOR {
+    let window = winuser::GetForegroundWindow();
+    let mut process_id: DWORD = 0;
+    winuser::GetWindowThreadProcessId(window, &mut process_id);
+    if !window.is_null() && process_id == processthreadsapi::GetCurrentProcessId() {
+        winuser::MonitorFromWindow(window, winuser::MONITOR_DEFAULTTOPRIMARY)
+    } else {
+        winuser::MonitorFromPoint(POINT { x: 0, y: 0 }, winuser::MONITOR_DEFAULTTOPRIMARY)
+    }
+}
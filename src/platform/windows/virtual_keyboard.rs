@@ -0,0 +1,292 @@
+//! Support for the touch keyboard.
+//!
+//! There's no documented way for a desktop application to show the touch keyboard, so we use the
+//! undocumented `ITipInvocation` interface like everyone else. Since all it can do is toggle the
+//! keyboard, we rely on `IFrameworkInputPane` to find out whether it's currently shown, which also
+//! notifies us whenever that changes.
+
+#![allow(non_snake_case)]
+
+use std::{env, mem, ptr};
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use winapi::Interface;
+use winapi::ctypes::c_void;
+use winapi::shared::guiddef::{IsEqualIID, REFIID};
+use winapi::shared::minwindef::{BOOL, DWORD, ULONG};
+use winapi::shared::windef::{HWND, POINT, RECT};
+use winapi::shared::wtypesbase::{CLSCTX_INPROC_HANDLER, CLSCTX_LOCAL_SERVER};
+use winapi::shared::winerror::{E_NOINTERFACE, HRESULT, S_OK};
+use winapi::um::{combaseapi, shellapi, winuser};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+
+use {LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, WindowEvent};
+use platform::platform::dpi::get_hwnd_scale_factor;
+use platform::platform::events_loop::VIRTUAL_KEYBOARD_MSG_ID;
+use platform::platform::window::com_initialized;
+
+// None of these are provided by winapi yet.
+DEFINE_GUID!{CLSID_UIHostNoLaunch,
+    0x4ce576fa, 0x83dc, 0x4f88, 0x95, 0x1c, 0x9d, 0x07, 0x82, 0xb4, 0xe3, 0x76}
+RIDL!{#[uuid(0x37c994e7, 0x432b, 0x4834, 0xa2, 0xf7, 0xdc, 0xe1, 0xf1, 0x3b, 0x83, 0x4b)]
+interface ITipInvocation(ITipInvocationVtbl): IUnknown(IUnknownVtbl) {
+    fn Toggle(
+        hwnd: HWND,
+    ) -> HRESULT,
+}}
+
+DEFINE_GUID!{CLSID_FrameworkInputPane,
+    0xd5120aa3, 0x46ba, 0x44c5, 0x82, 0x2d, 0xca, 0x80, 0x92, 0xc1, 0xfc, 0x72}
+RIDL!{#[uuid(0x226c537b, 0x1e76, 0x4d9e, 0xa7, 0x60, 0x33, 0xdb, 0x29, 0x92, 0x2f, 0x18)]
+interface IFrameworkInputPaneHandler(IFrameworkInputPaneHandlerVtbl): IUnknown(IUnknownVtbl) {
+    fn Showing(
+        prcInputPaneScreenLocation: *mut RECT,
+        fEnsureFocusedElementInView: BOOL,
+    ) -> HRESULT,
+    fn Hiding(
+        fEnsureFocusedElementInView: BOOL,
+    ) -> HRESULT,
+}}
+RIDL!{#[uuid(0x5752238b, 0x24f0, 0x495a, 0x82, 0xf1, 0x2f, 0xd5, 0x93, 0x05, 0x67, 0x96)]
+interface IFrameworkInputPane(IFrameworkInputPaneVtbl): IUnknown(IUnknownVtbl) {
+    fn Advise(
+        pWindow: *mut IUnknown,
+        pHandler: *mut IFrameworkInputPaneHandler,
+        pdwCookie: *mut DWORD,
+    ) -> HRESULT,
+    fn AdviseWithHWND(
+        hwnd: HWND,
+        pHandler: *mut IFrameworkInputPaneHandler,
+        pdwCookie: *mut DWORD,
+    ) -> HRESULT,
+    fn Unadvise(
+        dwCookie: DWORD,
+    ) -> HRESULT,
+    fn Location(
+        prcInputPaneScreenLocation: *mut RECT,
+    ) -> HRESULT,
+}}
+
+/// Keeps track of the touch keyboard on behalf of a window.
+///
+/// Whenever the keyboard is shown or hidden, `VIRTUAL_KEYBOARD_MSG_ID` is posted to the window.
+#[derive(Debug)]
+pub struct InputPane {
+    pane: *mut IFrameworkInputPane,
+    cookie: DWORD,
+}
+
+unsafe impl Send for InputPane {}
+unsafe impl Sync for InputPane {}
+
+impl InputPane {
+    /// Returns `None` if the input pane isn't available, which is the case before Windows 8.
+    pub unsafe fn new(window: HWND) -> Option<InputPane> {
+        com_initialized();
+
+        let mut pane: *mut IFrameworkInputPane = ptr::null_mut();
+        let hr = combaseapi::CoCreateInstance(
+            &CLSID_FrameworkInputPane,
+            ptr::null_mut(),
+            combaseapi::CLSCTX_ALL,
+            &IFrameworkInputPane::uuidof(),
+            &mut pane as *mut _ as *mut _,
+        );
+        if hr != S_OK {
+            return None;
+        }
+
+        let handler = InputPaneHandler::new(window);
+        let mut cookie = 0;
+        let hr = (*pane).AdviseWithHWND(window, handler, &mut cookie);
+        // The input pane takes its own reference to the handler.
+        (*handler).Release();
+        if hr != S_OK {
+            (*pane).Release();
+            return None;
+        }
+
+        Some(InputPane { pane, cookie })
+    }
+
+    /// Returns the area covered by the keyboard in screen coordinates, or `None` if it's hidden.
+    pub fn get_location(&self) -> Option<RECT> {
+        unsafe {
+            let mut rect: RECT = mem::zeroed();
+            let hr = (*self.pane).Location(&mut rect);
+            if hr == S_OK && rect.right > rect.left && rect.bottom > rect.top {
+                Some(rect)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl Drop for InputPane {
+    fn drop(&mut self) {
+        unsafe {
+            (*self.pane).Unadvise(self.cookie);
+            (*self.pane).Release();
+        }
+    }
+}
+
+/// Shows or hides the touch keyboard. `shown` is whether it's currently shown.
+pub unsafe fn set_visible(visible: bool, shown: bool) {
+    if visible == shown {
+        return;
+    }
+
+    com_initialized();
+
+    let mut tip: *mut ITipInvocation = ptr::null_mut();
+    let hr = combaseapi::CoCreateInstance(
+        &CLSID_UIHostNoLaunch,
+        ptr::null_mut(),
+        CLSCTX_INPROC_HANDLER | CLSCTX_LOCAL_SERVER,
+        &ITipInvocation::uuidof(),
+        &mut tip as *mut _ as *mut _,
+    );
+    if hr == S_OK {
+        (*tip).Toggle(winuser::GetDesktopWindow());
+        (*tip).Release();
+    } else if visible {
+        // The keyboard isn't running, and launching it shows it.
+        launch_touch_keyboard();
+    }
+}
+
+unsafe fn launch_touch_keyboard() {
+    // In 32-bit processes on 64-bit Windows, `CommonProgramFiles` points to the 32-bit directory,
+    // which doesn't contain `TabTip.exe`. `CommonProgramW6432` always points to the 64-bit one.
+    let common_files = match env::var_os("CommonProgramW6432")
+        .or_else(|| env::var_os("CommonProgramFiles"))
+    {
+        Some(common_files) => common_files,
+        None => return,
+    };
+    let path = Path::new(&common_files).join(r"microsoft shared\ink\TabTip.exe");
+    let path: Vec<u16> = OsStr::new(&path)
+        .encode_wide()
+        .chain(Some(0).into_iter())
+        .collect();
+    shellapi::ShellExecuteW(
+        ptr::null_mut(),
+        ptr::null(),
+        path.as_ptr(),
+        ptr::null(),
+        ptr::null(),
+        winuser::SW_SHOWNORMAL,
+    );
+}
+
+/// Builds the event reporting that the keyboard now covers `location`, in screen coordinates, or
+/// that it's hidden if that's `None`.
+pub unsafe fn visibility_changed(window: HWND, location: Option<RECT>) -> WindowEvent {
+    match location {
+        Some(rect) => {
+            let mut top_left = POINT { x: rect.left, y: rect.top };
+            winuser::ScreenToClient(window, &mut top_left);
+            let dpi_factor = get_hwnd_scale_factor(window);
+            let position = PhysicalPosition::new(top_left.x as f64, top_left.y as f64)
+                .to_logical(dpi_factor);
+            let size = PhysicalSize::new(
+                (rect.right - rect.left) as f64,
+                (rect.bottom - rect.top) as f64,
+            ).to_logical(dpi_factor);
+            WindowEvent::VirtualKeyboardVisibilityChanged { visible: true, position, size }
+        },
+        None => WindowEvent::VirtualKeyboardVisibilityChanged {
+            visible: false,
+            position: LogicalPosition::new(0.0, 0.0),
+            size: LogicalSize::new(0.0, 0.0),
+        },
+    }
+}
+
+// The COM object receiving the input pane's notifications. Since they could come from any thread,
+// all it does is posting a message to the window, which finds out what changed by itself.
+#[repr(C)]
+struct InputPaneHandler {
+    interface: IFrameworkInputPaneHandler,
+    refcount: AtomicUsize,
+    window: HWND,
+}
+
+static INPUT_PANE_HANDLER_VTBL: IFrameworkInputPaneHandlerVtbl = IFrameworkInputPaneHandlerVtbl {
+    parent: IUnknownVtbl {
+        QueryInterface: InputPaneHandler::QueryInterface,
+        AddRef: InputPaneHandler::AddRef,
+        Release: InputPaneHandler::Release,
+    },
+    Showing: InputPaneHandler::Showing,
+    Hiding: InputPaneHandler::Hiding,
+};
+
+impl InputPaneHandler {
+    // The returned object starts with a reference count of 1.
+    fn new(window: HWND) -> *mut IFrameworkInputPaneHandler {
+        let handler = Box::new(InputPaneHandler {
+            interface: IFrameworkInputPaneHandler { lpVtbl: &INPUT_PANE_HANDLER_VTBL },
+            refcount: AtomicUsize::new(1),
+            window,
+        });
+        Box::into_raw(handler) as *mut IFrameworkInputPaneHandler
+    }
+
+    unsafe extern "system" fn QueryInterface(
+        this: *mut IUnknown,
+        riid: REFIID,
+        ppvObject: *mut *mut c_void,
+    ) -> HRESULT {
+        if IsEqualIID(&*riid, &IUnknown::uuidof())
+            || IsEqualIID(&*riid, &IFrameworkInputPaneHandler::uuidof())
+        {
+            InputPaneHandler::AddRef(this);
+            *ppvObject = this as *mut c_void;
+            S_OK
+        } else {
+            *ppvObject = ptr::null_mut();
+            E_NOINTERFACE
+        }
+    }
+
+    unsafe extern "system" fn AddRef(this: *mut IUnknown) -> ULONG {
+        let handler = &*(this as *const InputPaneHandler);
+        (handler.refcount.fetch_add(1, Ordering::SeqCst) + 1) as ULONG
+    }
+
+    unsafe extern "system" fn Release(this: *mut IUnknown) -> ULONG {
+        let count = {
+            let handler = &*(this as *const InputPaneHandler);
+            handler.refcount.fetch_sub(1, Ordering::SeqCst) - 1
+        };
+        if count == 0 {
+            Box::from_raw(this as *mut InputPaneHandler);
+        }
+        count as ULONG
+    }
+
+    unsafe extern "system" fn Showing(
+        this: *mut IFrameworkInputPaneHandler,
+        _prcInputPaneScreenLocation: *mut RECT,
+        _fEnsureFocusedElementInView: BOOL,
+    ) -> HRESULT {
+        let handler = &*(this as *const InputPaneHandler);
+        winuser::PostMessageW(handler.window, *VIRTUAL_KEYBOARD_MSG_ID, 0, 0);
+        S_OK
+    }
+
+    unsafe extern "system" fn Hiding(
+        this: *mut IFrameworkInputPaneHandler,
+        _fEnsureFocusedElementInView: BOOL,
+    ) -> HRESULT {
+        let handler = &*(this as *const InputPaneHandler);
+        winuser::PostMessageW(handler.window, *VIRTUAL_KEYBOARD_MSG_ID, 0, 0);
+        S_OK
+    }
+}
@@ -1,41 +1,97 @@
 #![cfg(target_os = "windows")]
 
 use std::{io, mem, ptr};
-use std::cell::Cell;
 use std::ffi::OsStr;
+use std::os::raw::c_void;
 use std::os::windows::ffi::OsStrExt;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::channel;
 
 use winapi::ctypes::c_int;
-use winapi::shared::minwindef::{BOOL, DWORD, FALSE, LPARAM, TRUE, UINT, WORD, WPARAM};
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, HINSTANCE, HRGN, LPARAM, TRUE, UINT, WORD, WPARAM};
 use winapi::shared::windef::{HWND, LPPOINT, POINT, RECT};
-use winapi::um::{combaseapi, dwmapi, libloaderapi, winuser};
+use winapi::shared::winerror::{ERROR_ACCESS_DENIED, SUCCEEDED};
+use winapi::um::{combaseapi, dwmapi, libloaderapi, processthreadsapi, wingdi, winuser};
 use winapi::um::objbase::COINIT_MULTITHREADED;
-use winapi::um::shobjidl_core::{CLSID_TaskbarList, ITaskbarList2};
 use winapi::um::winnt::{LONG, LPCWSTR};
 
 use {
+    ActivationPolicy,
+    CapturedFrame,
     CreationError,
+    CursorGrabMode,
+    Edge,
+    Event,
+    HitTestCallback,
     Icon,
+    ImeMode,
     LogicalPosition,
     LogicalSize,
     MonitorId as RootMonitorId,
     MouseCursor,
     PhysicalSize,
+    RawWindowHandle,
+    Rect,
     WindowAttributes,
+    WindowEvent,
+    WindowId as SuperWindowId,
+};
+use accessibility::AccessibilityAdapter;
+use menu::Menu;
+use platform::platform::{app_bar, ime, Cursor, PlatformSpecificWindowBuilderAttributes, WindowId};
+use platform::platform::dpi::{
+    dpi_to_scale_factor,
+    get_function_impl,
+    get_hwnd_dpi,
+    scale_factor_to_dpi,
+    set_forced_hwnd_dpi,
 };
-use platform::platform::{Cursor, PlatformSpecificWindowBuilderAttributes, WindowId};
-use platform::platform::dpi::{dpi_to_scale_factor, get_hwnd_dpi};
 use platform::platform::events_loop::{self, EventsLoop, DESTROY_MSG_ID, INITIAL_DPI_MSG_ID};
 use platform::platform::events_loop::WindowState;
 use platform::platform::icon::{self, IconType, WinIcon};
+use platform::platform::menu;
 use platform::platform::monitor::get_available_monitors;
-use platform::platform::raw_input::register_all_mice_and_keyboards_for_raw_input;
+use platform::platform::raw_input::register_all_devices_for_raw_input;
+use platform::platform::taskbar;
 use platform::platform::util;
+use platform::platform::virtual_keyboard::{self, InputPane};
 
 const WS_RESIZABLE: DWORD = winuser::WS_SIZEBOX | winuser::WS_MAXIMIZEBOX;
 
+// Undocumented, but used by the shell since Windows 10 to blur what's behind its windows.
+type SetWindowCompositionAttribute = unsafe extern "system" fn(
+    hwnd: HWND,
+    data: *mut WindowCompositionAttribData,
+) -> BOOL;
+
+#[repr(C)]
+struct WindowCompositionAttribData {
+    attrib: DWORD,
+    data: *mut c_void,
+    size: UINT,
+}
+
+#[repr(C)]
+struct AccentPolicy {
+    accent_state: DWORD,
+    accent_flags: DWORD,
+    gradient_color: DWORD,
+    animation_id: DWORD,
+}
+
+const WCA_ACCENT_POLICY: DWORD = 19;
+const ACCENT_DISABLED: DWORD = 0;
+const ACCENT_ENABLE_BLURBEHIND: DWORD = 3;
+// Not exported by the version of winapi we depend on.
+const DWM_BB_ENABLE: DWORD = 0x1;
+
+lazy_static! {
+    static ref SET_WINDOW_COMPOSITION_ATTRIBUTE: Option<SetWindowCompositionAttribute> = get_function!(
+        "user32.dll",
+        SetWindowCompositionAttribute
+    );
+}
+
 /// The Win32 implementation of the main `Window` object.
 pub struct Window {
     /// Main handle for the window.
@@ -74,8 +130,16 @@ impl Window {
     pub fn new(
         events_loop: &EventsLoop,
         w_attr: WindowAttributes,
-        pl_attr: PlatformSpecificWindowBuilderAttributes,
+        mut pl_attr: PlatformSpecificWindowBuilderAttributes,
     ) -> Result<Window, CreationError> {
+        pl_attr.activation_policy = events_loop.activation_policy;
+        match w_attr.parent {
+            Some(RawWindowHandle::Windows(parent)) => pl_attr.parent = Some(parent as HWND),
+            Some(_) => return Err(CreationError::InvalidAttributes(
+                "The parent handle isn't an `HWND`.".to_owned(),
+            )),
+            None => (),
+        }
         let (tx, rx) = channel();
         let proxy = events_loop.create_proxy();
         events_loop.execute_in_thread(move |inserter| {
@@ -99,6 +163,7 @@ impl Window {
 
     #[inline]
     pub fn show(&self) {
+        self.end_pending_present();
         unsafe {
             winuser::ShowWindow(self.window.0, winuser::SW_SHOW);
         }
@@ -106,11 +171,36 @@ impl Window {
 
     #[inline]
     pub fn hide(&self) {
+        self.end_pending_present();
         unsafe {
             winuser::ShowWindow(self.window.0, winuser::SW_HIDE);
         }
     }
 
+    #[inline]
+    pub fn present_ready(&self) {
+        if self.end_pending_present() {
+            unsafe {
+                winuser::ShowWindow(self.window.0, winuser::SW_SHOW);
+            }
+        }
+    }
+
+    /// Stops waiting for `present_ready`, uncloaking the window if it was cloaked. Returns `true`
+    /// if the window was left hidden instead, in which case it still has to be shown.
+    fn end_pending_present(&self) -> bool {
+        let mut window_state = self.window_state.lock().unwrap();
+        if !mem::replace(&mut window_state.pending_present, false) {
+            return false;
+        }
+        if mem::replace(&mut window_state.present_cloaked, false) {
+            unsafe { set_cloaked(self.window.0, false) };
+            false
+        } else {
+            true
+        }
+    }
+
     pub(crate) fn get_position_physical(&self) -> Option<(i32, i32)> {
         util::get_window_rect(self.window.0)
             .map(|rect| (rect.left as i32, rect.top as i32))
@@ -158,12 +248,29 @@ impl Window {
     }
 
     #[inline]
-    pub fn set_position(&self, logical_position: LogicalPosition) {
+    pub fn set_outer_position(&self, logical_position: LogicalPosition) {
         let dpi_factor = self.get_hidpi_factor();
         let (x, y) = logical_position.to_physical(dpi_factor).into();
         self.set_position_physical(x, y);
     }
 
+    pub(crate) fn set_inner_position_physical(&self, x: i32, y: i32) {
+        // The frame keeps its size when the window moves, so the current offset of the client
+        // area from the window's top-left corner still applies at the new position.
+        let outer = self.get_position_physical();
+        let inner = self.get_inner_position_physical();
+        if let (Some((outer_x, outer_y)), Some((inner_x, inner_y))) = (outer, inner) {
+            self.set_position_physical(x - (inner_x - outer_x), y - (inner_y - outer_y));
+        }
+    }
+
+    #[inline]
+    pub fn set_inner_position(&self, logical_position: LogicalPosition) {
+        let dpi_factor = self.get_hidpi_factor();
+        let (x, y) = logical_position.to_physical(dpi_factor).into();
+        self.set_inner_position_physical(x, y);
+    }
+
     pub(crate) fn get_inner_size_physical(&self) -> Option<(u32, u32)> {
         let mut rect: RECT = unsafe { mem::uninitialized() };
         if unsafe { winuser::GetClientRect(self.window.0, &mut rect) } == 0 {
@@ -201,6 +308,20 @@ impl Window {
             })
     }
 
+    pub fn get_frame_extents(&self) -> Option<::FrameExtents> {
+        let window_rect = util::get_window_rect(self.window.0)?;
+        let (x, y) = self.get_inner_position_physical()?;
+        let (width, height) = self.get_inner_size_physical()?;
+        let dpi_factor = self.get_hidpi_factor();
+        let logicalize = |value: i32| value as f64 / dpi_factor;
+        Some(::FrameExtents {
+            left: logicalize(x - window_rect.left),
+            right: logicalize(window_rect.right - (x + width as i32)),
+            top: logicalize(y - window_rect.top),
+            bottom: logicalize(window_rect.bottom - (y + height as i32)),
+        })
+    }
+
     pub(crate) fn set_inner_size_physical(&self, x: u32, y: u32) {
         unsafe {
             let mut rect = RECT {
@@ -327,23 +448,19 @@ impl Window {
         cur.cursor = Cursor(cursor_id);
     }
 
-    unsafe fn cursor_is_grabbed(&self) -> Result<bool, String> {
-        let mut client_rect: RECT = mem::uninitialized();
-        let mut clip_rect: RECT = mem::uninitialized();
-        if winuser::GetClientRect(self.window.0, &mut client_rect) == 0 {
-            return Err("`GetClientRect` failed".to_owned());
-        }
+    // Clips the cursor to a rectangle of the client area.
+    unsafe fn clip_cursor_to_client_rect(window: &WindowWrapper, mut rect: RECT) -> Result<(), String> {
         // A `POINT` is two `LONG`s (x, y), and the `RECT` field after `left` is `top`.
-        if winuser::ClientToScreen(self.window.0, &mut client_rect.left as *mut _ as LPPOINT) == 0 {
+        if winuser::ClientToScreen(window.0, &mut rect.left as *mut _ as LPPOINT) == 0 {
             return Err("`ClientToScreen` (left, top) failed".to_owned());
         }
-        if winuser::ClientToScreen(self.window.0, &mut client_rect.right as *mut _ as LPPOINT) == 0 {
+        if winuser::ClientToScreen(window.0, &mut rect.right as *mut _ as LPPOINT) == 0 {
             return Err("`ClientToScreen` (right, bottom) failed".to_owned());
         }
-        if winuser::GetClipCursor(&mut clip_rect) == 0 {
-            return Err("`GetClipCursor` failed".to_owned());
+        if winuser::ClipCursor(&rect) == 0 {
+            return Err("`ClipCursor` failed".to_owned());
         }
-        Ok(util::rect_eq(&client_rect, &clip_rect))
+        Ok(())
     }
 
     pub(crate) unsafe fn grab_cursor_inner(window: &WindowWrapper, grab: bool) -> Result<(), String> {
@@ -352,42 +469,92 @@ impl Window {
             if winuser::GetClientRect(window.0, &mut rect) == 0 {
                 return Err("`GetClientRect` failed".to_owned());
             }
-            // A `POINT` is two `LONG`s (x, y), and the `RECT` field after `left` is `top`.
-            if winuser::ClientToScreen(window.0, &mut rect.left as *mut _ as LPPOINT) == 0 {
-                return Err("`ClientToScreen` (left, top) failed".to_owned());
-            }
-            if winuser::ClientToScreen(window.0, &mut rect.right as *mut _ as LPPOINT) == 0 {
-                return Err("`ClientToScreen` (right, bottom) failed".to_owned());
-            }
-            if winuser::ClipCursor(&rect) == 0 {
-                return Err("`ClipCursor` failed".to_owned());
-            }
+            Self::clip_cursor_to_client_rect(window, rect)
         } else {
             if winuser::ClipCursor(ptr::null()) == 0 {
                 return Err("`ClipCursor` failed".to_owned());
             }
+            Ok(())
+        }
+    }
+
+    // Clips the cursor to the confine region if there's one, or else according to the grab mode.
+    pub(crate) unsafe fn update_cursor_clip(window: &WindowWrapper, window_state: &WindowState) -> Result<(), String> {
+        match window_state.cursor_confine_region {
+            Some(region) => {
+                let (x, y): (i32, i32) = region.position.to_physical(window_state.dpi_factor).into();
+                let (width, height): (u32, u32) = region.size.to_physical(window_state.dpi_factor).into();
+                let rect = RECT {
+                    left: x,
+                    top: y,
+                    right: x + width as LONG,
+                    bottom: y + height as LONG,
+                };
+                Self::clip_cursor_to_client_rect(window, rect)
+            },
+            None => match window_state.cursor_grab_mode {
+                CursorGrabMode::None => Self::grab_cursor_inner(window, false),
+                CursorGrabMode::Confined => Self::grab_cursor_inner(window, true),
+                CursorGrabMode::Locked => {
+                    // Clipping the cursor to a single pixel keeps it in place without warping it
+                    // back after every move, while raw input still reports the mouse's motion.
+                    let mut client_rect = mem::uninitialized();
+                    if winuser::GetClientRect(window.0, &mut client_rect) == 0 {
+                        return Err("`GetClientRect` failed".to_owned());
+                    }
+                    let mut cursor = util::get_cursor_pos()
+                        .ok_or("`GetCursorPos` failed".to_owned())?;
+                    winuser::ScreenToClient(window.0, &mut cursor);
+                    let (x, y) = if winuser::PtInRect(&client_rect, cursor) != 0 {
+                        (cursor.x, cursor.y)
+                    } else {
+                        (client_rect.right / 2, client_rect.bottom / 2)
+                    };
+                    let rect = RECT { left: x, top: y, right: x + 1, bottom: y + 1 };
+                    Self::clip_cursor_to_client_rect(window, rect)
+                },
+            },
         }
-        Ok(())
     }
 
     #[inline]
     pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
-        let currently_grabbed = unsafe { self.cursor_is_grabbed() }?;
-        let window_state_lock = self.window_state.lock().unwrap();
-        if currently_grabbed == grab && grab == window_state_lock.cursor_grabbed {
-            return Ok(());
-        }
+        self.set_cursor_grab_mode(if grab { CursorGrabMode::Confined } else { CursorGrabMode::None })
+    }
+
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, mode: CursorGrabMode) -> Result<(), String> {
         let window = self.window.clone();
         let window_state = Arc::clone(&self.window_state);
         let (tx, rx) = channel();
         self.events_loop_proxy.execute_in_thread(move |_| {
-            let result = unsafe { Self::grab_cursor_inner(&window, grab) };
-            if result.is_ok() {
-                window_state.lock().unwrap().cursor_grabbed = grab;
+            let mut window_state_lock = window_state.lock().unwrap();
+            // The clip is applied again even if the mode didn't change, in case something else
+            // changed it, but there's no need to release a clip that isn't ours.
+            if mode == CursorGrabMode::None && window_state_lock.cursor_grab_mode == mode {
+                let _ = tx.send(Ok(()));
+                return;
+            }
+            let previous_mode = mem::replace(&mut window_state_lock.cursor_grab_mode, mode);
+            let result = unsafe { Self::update_cursor_clip(&window, &window_state_lock) };
+            if result.is_err() {
+                window_state_lock.cursor_grab_mode = previous_mode;
             }
             let _ = tx.send(result);
         });
-        drop(window_state_lock);
+        rx.recv().unwrap()
+    }
+
+    #[inline]
+    pub fn set_cursor_confine_region(&self, region: Option<Rect>) -> Result<(), String> {
+        let window = self.window.clone();
+        let window_state = Arc::clone(&self.window_state);
+        let (tx, rx) = channel();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let mut window_state_lock = window_state.lock().unwrap();
+            window_state_lock.cursor_confine_region = region;
+            let _ = tx.send(unsafe { Self::update_cursor_clip(&window, &window_state_lock) });
+        });
         rx.recv().unwrap()
     }
 
@@ -448,9 +615,9 @@ impl Window {
     #[inline]
     pub fn set_maximized(&self, maximized: bool) {
         let mut window_state = self.window_state.lock().unwrap();
-        window_state.maximized = true;
+        window_state.maximized = maximized;
         // We only maximize if we're not in fullscreen.
-        if window_state.fullscreen.is_none() {
+        if window_state.fullscreen.is_some() {
             return;
         }
 
@@ -470,6 +637,86 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) {
+        let window = self.window.clone();
+        unsafe {
+            // `ShowWindow` resizes the window, so it must be called from the main thread.
+            self.events_loop_proxy.execute_in_thread(move |_| {
+                if minimized {
+                    winuser::ShowWindow(window.0, winuser::SW_MINIMIZE);
+                } else if winuser::IsIconic(window.0) != 0 {
+                    // Restoring a window that isn't minimized would unmaximize it.
+                    winuser::ShowWindow(window.0, winuser::SW_RESTORE);
+                }
+            });
+        }
+    }
+
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        unsafe { winuser::IsIconic(self.window.0) != 0 }
+    }
+
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        // `window_state.maximized` is only what was requested, and is kept while fullscreen.
+        unsafe { winuser::IsZoomed(self.window.0) != 0 }
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.window_state.lock().unwrap().fullscreen.is_some()
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        unsafe { winuser::IsWindowVisible(self.window.0) != 0 }
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.window_state.lock().unwrap().has_focus
+    }
+
+    pub fn focus(&self) {
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            // Keyboard focus follows activation, but isn't given back to a window that's already
+            // the foreground window.
+            winuser::SetForegroundWindow(window.0);
+            winuser::SetFocus(window.0);
+        });
+    }
+
+    pub fn set_focusable(&self, focusable: bool) {
+        let window = self.window.clone();
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            let ex_style = winuser::GetWindowLongW(window.0, winuser::GWL_EXSTYLE) as DWORD;
+            let ex_style = if focusable {
+                ex_style & !winuser::WS_EX_NOACTIVATE
+            } else {
+                ex_style | winuser::WS_EX_NOACTIVATE
+            };
+            winuser::SetWindowLongW(window.0, winuser::GWL_EXSTYLE, ex_style as LONG);
+        });
+    }
+
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> ::Insets {
+        ::Insets::default()
+    }
+
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.window_state.lock().unwrap().resizable
+    }
+
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.window_state.lock().unwrap().decorations
+    }
+
     unsafe fn set_fullscreen_style(&self, window_state: &mut WindowState) -> (LONG, LONG) {
         if window_state.fullscreen.is_none() || window_state.saved_window_info.is_none() {
             let rect = util::get_window_rect(self.window.0).expect("`GetWindowRect` failed");
@@ -557,7 +804,7 @@ impl Window {
             mark_fullscreen(window.0, false);
 
             let window_state_lock = window_state.lock().unwrap();
-            let _ = Self::grab_cursor_inner(&window, window_state_lock.cursor_grabbed);
+            let _ = Self::update_cursor_clip(&window, &window_state_lock);
         });
     }
 
@@ -608,13 +855,25 @@ impl Window {
                         mark_fullscreen(window.0, true);
 
                         let window_state_lock = window_state.lock().unwrap();
-                        let _ = Self::grab_cursor_inner(&window, window_state_lock.cursor_grabbed);
+                        let _ = Self::update_cursor_clip(&window, &window_state_lock);
                     });
                 }
                 &None => {
                     self.restore_saved_window(&mut window_state_lock);
                 }
             }
+
+            // This runs after the closure queued above, once the window has been moved.
+            if window_state_lock.fullscreen.is_some() || monitor.is_some() {
+                let window_id = WindowId(self.window.0);
+                let fullscreen = monitor.clone();
+                self.events_loop_proxy.execute_in_thread(move |_| {
+                    events_loop::send_event(Event::WindowEvent {
+                        window_id: SuperWindowId(window_id),
+                        event: WindowEvent::FullscreenChanged(fullscreen.clone()),
+                    });
+                });
+            }
         }
 
         window_state_lock.fullscreen = monitor;
@@ -720,6 +979,17 @@ impl Window {
         }
     }
 
+    /// Whether this is the topmost of the visible windows belonging to our process.
+    pub fn is_frontmost(&self) -> bool {
+        let process_id = unsafe { processthreadsapi::GetCurrentProcessId() };
+        let frontmost = util::get_windows_front_to_back(|hwnd| {
+            let mut window_process_id = 0;
+            unsafe { winuser::GetWindowThreadProcessId(hwnd, &mut window_process_id) };
+            window_process_id == process_id
+        }).into_iter().next();
+        frontmost == Some(self.window.0)
+    }
+
     #[inline]
     pub fn get_current_monitor(&self) -> RootMonitorId {
         RootMonitorId {
@@ -731,7 +1001,7 @@ impl Window {
     pub fn set_window_icon(&self, mut window_icon: Option<Icon>) {
         let window_icon = window_icon
             .take()
-            .map(|icon| WinIcon::from_icon(icon).expect("Failed to create `ICON_SMALL`"));
+            .map(|icon| WinIcon::from_icon(icon, IconType::Small).expect("Failed to create `ICON_SMALL`"));
         if let Some(ref window_icon) = window_icon {
             window_icon.set_for_window(self.window.0, IconType::Small);
         } else {
@@ -740,11 +1010,16 @@ impl Window {
         self.window_state.lock().unwrap().window_icon = window_icon;
     }
 
+    #[inline]
+    pub fn set_minimize_on_fullscreen_focus_loss(&self, minimize: bool) {
+        self.window_state.lock().unwrap().minimize_on_fullscreen_focus_loss = minimize;
+    }
+
     #[inline]
     pub fn set_taskbar_icon(&self, mut taskbar_icon: Option<Icon>) {
         let taskbar_icon = taskbar_icon
             .take()
-            .map(|icon| WinIcon::from_icon(icon).expect("Failed to create `ICON_BIG`"));
+            .map(|icon| WinIcon::from_icon(icon, IconType::Big).expect("Failed to create `ICON_BIG`"));
         if let Some(ref taskbar_icon) = taskbar_icon {
             taskbar_icon.set_for_window(self.window.0, IconType::Big);
         } else {
@@ -753,10 +1028,325 @@ impl Window {
         self.window_state.lock().unwrap().taskbar_icon = taskbar_icon;
     }
 
+    #[inline]
+    pub fn set_badge_label(&self, _label: Option<&str>) {
+        // N/A: the taskbar can only show overlay icons.
+    }
+
+    pub fn set_overlay_icon(&self, overlay_icon: Option<Icon>) {
+        let overlay_icon = overlay_icon
+            .map(|icon| WinIcon::from_icon(icon, IconType::Small).expect("Failed to create overlay icon"));
+        let handle = overlay_icon.as_ref().map(|icon| icon.handle).unwrap_or(ptr::null_mut());
+        // The taskbar keeps its own copy of the icon, so ours can be destroyed right away.
+        unsafe {
+            taskbar::with_taskbar_list(|task_bar_list| {
+                task_bar_list.SetOverlayIcon(self.window.0, handle, ptr::null());
+            });
+        }
+    }
+
+    pub fn set_progress(&self, progress: Option<f64>) {
+        // The taskbar shows progress in steps of `1 / total`.
+        let total = 10_000;
+        unsafe {
+            taskbar::with_taskbar_list(|task_bar_list| match progress {
+                Some(progress) => {
+                    let completed = (progress.max(0.0).min(1.0) * total as f64) as u64;
+                    // Also shows the progress bar if it was hidden.
+                    task_bar_list.SetProgressValue(self.window.0, completed, total);
+                },
+                None => {
+                    task_bar_list.SetProgressState(self.window.0, taskbar::TBPF_NOPROGRESS);
+                },
+            });
+        }
+    }
+
+    pub fn set_menu(&self, menu: Option<Menu>) {
+        let window = self.window.clone();
+        let window_state = Arc::clone(&self.window_state);
+        let mut menu = Some(menu);
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            let (hmenu, accelerators) = match menu.take().unwrap() {
+                Some(menu) => {
+                    let (hmenu, accelerators) = menu::create_menu_bar(&menu);
+                    (hmenu, accelerators.map(Arc::new))
+                },
+                None => (ptr::null_mut(), None),
+            };
+            // `SetMenu` doesn't destroy the menu it replaces.
+            let old_hmenu = winuser::GetMenu(window.0);
+            winuser::SetMenu(window.0, hmenu);
+            if !old_hmenu.is_null() {
+                winuser::DestroyMenu(old_hmenu);
+            }
+            window_state.lock().unwrap().accelerators = accelerators;
+        });
+    }
+
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, visible: bool) {
+        let window = self.window.clone();
+        let window_state = Arc::clone(&self.window_state);
+        self.events_loop_proxy.execute_in_thread(move |_| unsafe {
+            let input_pane = {
+                let mut window_state = window_state.lock().unwrap();
+                if window_state.input_pane.is_none() {
+                    window_state.input_pane = InputPane::new(window.0).map(Arc::new);
+                }
+                window_state.input_pane.clone()
+            };
+            // Without the input pane, we can't tell whether the keyboard is shown, so we assume
+            // it isn't. That only matters on Windows 7, where the keyboard is rarely used anyway.
+            let shown = input_pane
+                .and_then(|input_pane| input_pane.get_location())
+                .is_some();
+            virtual_keyboard::set_visible(visible, shown);
+        });
+    }
+
+    pub fn capture_frame(&self) -> Option<CapturedFrame> {
+        unsafe {
+            let mut rect: RECT = mem::uninitialized();
+            if winuser::GetClientRect(self.window.0, &mut rect) == 0 {
+                return None;
+            }
+            let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+            // The client area is empty while the window is minimized.
+            if width <= 0 || height <= 0 {
+                return None;
+            }
+
+            let window_dc = winuser::GetDC(self.window.0);
+            let dc = wingdi::CreateCompatibleDC(window_dc);
+            let bitmap = wingdi::CreateCompatibleBitmap(window_dc, width, height);
+            winuser::ReleaseDC(self.window.0, window_dc);
+
+            let previous = wingdi::SelectObject(dc, bitmap as _);
+            // Without `PW_RENDERFULLCONTENT`, DirectX and OpenGL content comes out black.
+            let printed = winuser::PrintWindow(
+                self.window.0,
+                dc,
+                winuser::PW_CLIENTONLY | winuser::PW_RENDERFULLCONTENT,
+            ) != 0;
+            wingdi::SelectObject(dc, previous);
+
+            let mut info: wingdi::BITMAPINFO = mem::zeroed();
+            info.bmiHeader.biSize = mem::size_of::<wingdi::BITMAPINFOHEADER>() as DWORD;
+            info.bmiHeader.biWidth = width;
+            // A negative height gives us the rows from the top.
+            info.bmiHeader.biHeight = -height;
+            info.bmiHeader.biPlanes = 1;
+            info.bmiHeader.biBitCount = 32;
+            info.bmiHeader.biCompression = wingdi::BI_RGB;
+            let mut pixels = vec![0u8; width as usize * height as usize * 4];
+            let lines = if printed {
+                wingdi::GetDIBits(
+                    dc,
+                    bitmap,
+                    0,
+                    height as UINT,
+                    pixels.as_mut_ptr() as *mut _,
+                    &mut info,
+                    wingdi::DIB_RGB_COLORS,
+                )
+            } else {
+                0
+            };
+            wingdi::DeleteObject(bitmap as _);
+            wingdi::DeleteDC(dc);
+            if lines != height {
+                return None;
+            }
+
+            // The pixels are BGRX.
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+                pixel[3] = 255;
+            }
+            Some(CapturedFrame { width: width as u32, height: height as u32, rgba: pixels })
+        }
+    }
+
     #[inline]
     pub fn set_ime_spot(&self, _logical_spot: LogicalPosition) {
         unimplemented!();
     }
+
+    #[inline]
+    pub fn set_ime_conversion_mode(&self, mode: ImeMode) -> Result<(), String> {
+        let window = self.window.clone();
+        let window_state = Arc::clone(&self.window_state);
+        let (tx, rx) = channel();
+        // Input contexts belong to the thread the window was created in.
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let mut window_state_lock = window_state.lock().unwrap();
+            let result = unsafe {
+                if mode == ImeMode::Default {
+                    match window_state_lock.ime_saved_status.take() {
+                        Some(status) => ime::set_status(window.0, status),
+                        None => Ok(()),
+                    }
+                } else {
+                    if window_state_lock.ime_saved_status.is_none() {
+                        window_state_lock.ime_saved_status = ime::get_status(window.0);
+                    }
+                    ime::set_mode(window.0, mode)
+                }
+            };
+            if result.is_ok() {
+                window_state_lock.ime_mode = mode;
+            }
+            let _ = tx.send(result);
+        });
+        rx.recv().unwrap()
+    }
+
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        let window = self.window.clone();
+        let (tx, rx) = channel();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let _ = tx.send(unsafe { ime::has_input_context(window.0) });
+        });
+        rx.recv().unwrap()
+    }
+
+    #[inline]
+    pub fn set_hit_test_callback(&self, callback: Option<HitTestCallback>) {
+        self.window_state.lock().unwrap().hit_test = callback;
+    }
+
+    pub fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), String> {
+        let mut rects = region.map(|region| self.to_physical_rects(region));
+        let window = self.window.clone();
+        let window_state = self.window_state.clone();
+        let (tx, rx) = channel();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let mut window_state = window_state.lock().unwrap();
+            window_state.input_region = rects.take();
+            let _ = tx.send(unsafe { Self::set_input_region_inner(&window, &window_state) });
+        });
+        rx.recv().unwrap()
+    }
+
+    unsafe fn set_input_region_inner(window: &WindowWrapper, window_state: &WindowState) -> Result<(), String> {
+        // A window region can't be empty without hiding the window, so a window that's entirely
+        // click-through is made transparent to hit-testing instead.
+        let click_through = window_state.input_region.as_ref().map_or(false, |rects| rects.is_empty());
+        let ex_style = winuser::GetWindowLongW(window.0, winuser::GWL_EXSTYLE) as DWORD;
+        let click_through_style = winuser::WS_EX_TRANSPARENT | winuser::WS_EX_LAYERED;
+        if click_through {
+            winuser::SetWindowLongW(window.0, winuser::GWL_EXSTYLE, (ex_style | click_through_style) as LONG);
+            // Layered windows aren't drawn until their attributes are set.
+            winuser::SetLayeredWindowAttributes(window.0, 0, 255, winuser::LWA_ALPHA);
+        } else if ex_style & winuser::WS_EX_TRANSPARENT != 0 {
+            winuser::SetWindowLongW(window.0, winuser::GWL_EXSTYLE, (ex_style & !click_through_style) as LONG);
+        }
+        Self::update_window_region(window, window_state)
+    }
+
+    pub fn reserve_screen_edge(&self, reservation: Option<(Edge, f64)>) -> Result<(), String> {
+        let dpi_factor = self.get_hidpi_factor();
+        let reservation = reservation.map(|(edge, thickness)| (edge, (thickness * dpi_factor).round() as LONG));
+        let window = self.window.clone();
+        let window_state = self.window_state.clone();
+        let (tx, rx) = channel();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let mut window_state = window_state.lock().unwrap();
+            let registered = window_state.app_bar.is_some();
+            let result = match reservation {
+                Some((edge, thickness)) => unsafe { app_bar::reserve(window.0, registered, edge, thickness) },
+                None => {
+                    if registered {
+                        unsafe { app_bar::remove(window.0) };
+                    }
+                    Ok(())
+                },
+            };
+            if result.is_ok() {
+                window_state.app_bar = reservation;
+            }
+            let _ = tx.send(result);
+        });
+        rx.recv().unwrap()
+    }
+
+    pub fn set_blur(&self, blur: bool) -> Result<(), String> {
+        let transparent = self.window_state.lock().unwrap().transparent;
+        unsafe { set_blur(self.window.0, blur, transparent) };
+        Ok(())
+    }
+
+    pub fn set_shape(&self, shape: Option<&[Rect]>) -> Result<(), String> {
+        let mut rects = shape.map(|shape| self.to_physical_rects(shape));
+        let window = self.window.clone();
+        let window_state = self.window_state.clone();
+        let (tx, rx) = channel();
+        self.events_loop_proxy.execute_in_thread(move |_| {
+            let mut window_state = window_state.lock().unwrap();
+            window_state.shape = rects.take();
+            let _ = tx.send(unsafe { Self::update_window_region(&window, &window_state) });
+        });
+        rx.recv().unwrap()
+    }
+
+    fn to_physical_rects(&self, rects: &[Rect]) -> Vec<RECT> {
+        let dpi_factor = self.get_hidpi_factor();
+        rects.iter().map(|rect| {
+            let (x, y): (i32, i32) = rect.position.to_physical(dpi_factor).into();
+            let (width, height): (u32, u32) = rect.size.to_physical(dpi_factor).into();
+            RECT {
+                left: x,
+                top: y,
+                right: x + width as LONG,
+                bottom: y + height as LONG,
+            }
+        }).collect()
+    }
+
+    // The window region is the intersection of the shape and of the input region. An empty input
+    // region is left out, since it's handled by making the window click-through.
+    unsafe fn update_window_region(window: &WindowWrapper, window_state: &WindowState) -> Result<(), String> {
+        let input_region = window_state.input_region
+            .as_ref()
+            .and_then(|rects| if rects.is_empty() { None } else { Some(rects) });
+
+        // Window regions are relative to the top-left corner of the window rather than of its
+        // client area.
+        let mut window_rect = mem::uninitialized();
+        if winuser::GetWindowRect(window.0, &mut window_rect) == 0 {
+            return Err("`GetWindowRect` failed".to_owned());
+        }
+        let mut client_origin = POINT { x: 0, y: 0 };
+        winuser::ClientToScreen(window.0, &mut client_origin);
+        let offset = (client_origin.x - window_rect.left, client_origin.y - window_rect.top);
+
+        let region = match (window_state.shape.as_ref(), input_region) {
+            (Some(shape), Some(input_region)) => {
+                let region = create_region(shape, offset);
+                let input_region = create_region(input_region, offset);
+                wingdi::CombineRgn(region, region, input_region, wingdi::RGN_AND);
+                wingdi::DeleteObject(input_region as _);
+                region
+            },
+            (Some(rects), None) | (None, Some(rects)) => create_region(rects, offset),
+            (None, None) => ptr::null_mut(),
+        };
+        // The system owns the region once it's been set.
+        if winuser::SetWindowRgn(window.0, region, TRUE) == 0 {
+            if !region.is_null() {
+                wingdi::DeleteObject(region as _);
+            }
+            return Err("`SetWindowRgn` failed".to_owned());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_accessibility_adapter(&self, adapter: Option<AccessibilityAdapter>) {
+        self.window_state.lock().unwrap().accessibility_adapter = adapter;
+    }
 }
 
 impl Drop for Window {
@@ -812,7 +1402,7 @@ unsafe fn init(
     let window_icon = {
         let icon = attributes.window_icon
             .take()
-            .map(WinIcon::from_icon);
+            .map(|icon| WinIcon::from_icon(icon, IconType::Small));
         if icon.is_some() {
             Some(icon.unwrap().map_err(|err| {
                 CreationError::OsError(format!("Failed to create `ICON_SMALL`: {:?}", err))
@@ -824,7 +1414,7 @@ unsafe fn init(
     let taskbar_icon = {
         let icon = pl_attribs.taskbar_icon
             .take()
-            .map(WinIcon::from_icon);
+            .map(|icon| WinIcon::from_icon(icon, IconType::Big));
         if icon.is_some() {
             Some(icon.unwrap().map_err(|err| {
                 CreationError::OsError(format!("Failed to create `ICON_BIG`: {:?}", err))
@@ -835,9 +1425,19 @@ unsafe fn init(
     };
 
     // registering the window class
-    let class_name = register_window_class(&window_icon, &taskbar_icon);
+    let hinstance = pl_attribs.hinstance
+        .unwrap_or_else(|| libloaderapi::GetModuleHandleW(ptr::null()));
+    let class_name = register_window_class(
+        pl_attribs.class_name.as_ref().map(String::as_str).unwrap_or("Window Class"),
+        hinstance,
+        &window_icon,
+        &taskbar_icon,
+    );
 
-    let guessed_dpi_factor = {
+    let forced_dpi = attributes.forced_hidpi_factor.map(scale_factor_to_dpi);
+    let guessed_dpi_factor = if let Some(forced_dpi) = forced_dpi {
+        dpi_to_scale_factor(forced_dpi)
+    } else {
         let monitors = get_available_monitors();
         let dpi_factor = if !monitors.is_empty() {
             let mut dpi_factor = Some(monitors[0].get_hidpi_factor());
@@ -899,9 +1499,32 @@ unsafe fn init(
     if pl_attribs.no_redirection_bitmap {
         ex_style |= winuser::WS_EX_NOREDIRECTIONBITMAP;
     }
+    if pl_attribs.activation_policy != ActivationPolicy::Regular {
+        // Tool windows have no taskbar button and are left out of Alt-Tab.
+        ex_style &= !winuser::WS_EX_APPWINDOW;
+        ex_style |= winuser::WS_EX_TOOLWINDOW;
+    }
+    if pl_attribs.drag_and_drop {
+        ex_style |= winuser::WS_EX_ACCEPTFILES;
+    }
+    if !attributes.focusable {
+        ex_style |= winuser::WS_EX_NOACTIVATE;
+    }
+
+    // The menu bar is part of the non-client area, so it's passed along to `CreateWindowEx` and
+    // accounted for when computing the size of the window.
+    let (menu, accelerators) = match attributes.menu {
+        Some(ref menu) => {
+            let (menu, accelerators) = menu::create_menu_bar(menu);
+            (menu, accelerators.map(Arc::new))
+        },
+        None => (ptr::null_mut(), None),
+    };
 
     // adjusting the window coordinates using the style
-    winuser::AdjustWindowRectEx(&mut rect, style, 0, ex_style);
+    winuser::AdjustWindowRectEx(&mut rect, style, !menu.is_null() as BOOL, ex_style);
+
+    let pending_present = attributes.visible && attributes.visible_on_first_present;
 
     // creating the real window this time, by using the functions in `extra_functions`
     let real_window = {
@@ -922,12 +1545,20 @@ unsafe fn init(
             (None, None)
         };
 
-        let mut style = if !attributes.visible {
+        // A fullscreen window is only shown once it covers its monitor, and a maximized one is
+        // created maximized, so that neither is shown at its normal size first. A window waiting
+        // for `present_ready` is only shown once it has been cloaked. A visible window is activated
+        // when it's created, so an inactive one is shown afterwards instead.
+        let mut style = if !attributes.visible || attributes.fullscreen.is_some() || pending_present || !attributes.active {
             style
         } else {
             style | winuser::WS_VISIBLE
         };
 
+        if attributes.maximized {
+            style |= winuser::WS_MAXIMIZE;
+        }
+
         if !attributes.resizable {
             style &= !WS_RESIZABLE;
         }
@@ -936,7 +1567,7 @@ unsafe fn init(
             style |= winuser::WS_CHILD;
         }
 
-        let handle = winuser::CreateWindowExW(ex_style | winuser::WS_EX_ACCEPTFILES,
+        let handle = winuser::CreateWindowExW(ex_style,
             class_name.as_ptr(),
             title.as_ptr() as LPCWSTR,
             style | winuser::WS_CLIPSIBLINGS | winuser::WS_CLIPCHILDREN,
@@ -944,21 +1575,34 @@ unsafe fn init(
             adjusted_width.unwrap_or(winuser::CW_USEDEFAULT),
             adjusted_height.unwrap_or(winuser::CW_USEDEFAULT),
             pl_attribs.parent.unwrap_or(ptr::null_mut()),
-            ptr::null_mut(),
-            libloaderapi::GetModuleHandleW(ptr::null()),
+            menu,
+            hinstance,
             ptr::null_mut(),
         );
 
         if handle.is_null() {
-            return Err(CreationError::OsError(format!("CreateWindowEx function failed: {}",
-                                              format!("{}", io::Error::last_os_error()))));
+            if !menu.is_null() {
+                winuser::DestroyMenu(menu);
+            }
+            let err = io::Error::last_os_error();
+            let description = format!("CreateWindowEx function failed: {}", err);
+            return Err(match err.raw_os_error() {
+                Some(code) if code == ERROR_ACCESS_DENIED as i32 => {
+                    CreationError::PermissionDenied(description)
+                },
+                Some(code) => CreationError::OsErrorCode(code, description),
+                None => CreationError::OsError(description),
+            });
         }
 
         WindowWrapper(handle)
     };
 
+    // A cloaked window can be shown and drawn to without appearing on screen.
+    let present_cloaked = pending_present && set_cloaked(real_window.0, true);
+
     // Set up raw input
-    register_all_mice_and_keyboards_for_raw_input(real_window.0);
+    register_all_devices_for_raw_input(real_window.0);
 
     // Register for touch events if applicable
     {
@@ -968,6 +1612,10 @@ unsafe fn init(
         }
     }
 
+    if let Some(forced_dpi) = forced_dpi {
+        set_forced_hwnd_dpi(real_window.0, forced_dpi);
+    }
+
     let dpi = get_hwnd_dpi(real_window.0);
     let dpi_factor = dpi_to_scale_factor(dpi);
     if dpi_factor != guessed_dpi_factor {
@@ -992,7 +1640,8 @@ unsafe fn init(
             .map(|logical_size| PhysicalSize::from_logical(logical_size, dpi_factor));
         let mut window_state = events_loop::WindowState {
             cursor: Cursor(winuser::IDC_ARROW), // use arrow by default
-            cursor_grabbed: false,
+            cursor_grab_mode: CursorGrabMode::None,
+            cursor_confine_region: None,
             cursor_hidden: false,
             max_size,
             min_size,
@@ -1006,6 +1655,24 @@ unsafe fn init(
             maximized: attributes.maximized,
             resizable: attributes.resizable,
             always_on_top: attributes.always_on_top,
+            hit_test: None,
+            accelerators,
+            input_pane: None,
+            occluded: false,
+            minimized: false,
+            last_maximized: false,
+            has_focus: false,
+            minimize_on_fullscreen_focus_loss: false,
+            ime_mode: ImeMode::Default,
+            ime_saved_status: None,
+            accessibility_adapter: None,
+            accessibility_activated: false,
+            pending_present,
+            present_cloaked,
+            shape: None,
+            input_region: None,
+            transparent: attributes.transparent && !pl_attribs.no_redirection_bitmap,
+            app_bar: None,
         };
         // Creating a mutex to track the current window state
         Arc::new(Mutex::new(window_state))
@@ -1013,14 +1680,10 @@ unsafe fn init(
 
     // making the window transparent
     if attributes.transparent && !pl_attribs.no_redirection_bitmap {
-        let bb = dwmapi::DWM_BLURBEHIND {
-            dwFlags: 0x1, // FIXME: DWM_BB_ENABLE;
-            fEnable: 1,
-            hRgnBlur: ptr::null_mut(),
-            fTransitionOnMaximized: 0,
-        };
-
-        dwmapi::DwmEnableBlurBehindWindow(real_window.0, &bb);
+        enable_blur_behind(real_window.0, true);
+    }
+    if attributes.blur {
+        set_blur(real_window.0, true, attributes.transparent && !pl_attribs.no_redirection_bitmap);
     }
 
     let win = Window {
@@ -1029,10 +1692,23 @@ unsafe fn init(
         events_loop_proxy,
     };
 
-    win.set_maximized(attributes.maximized);
+    // `SW_SHOWNA` shows the window in its current state, maximized or not, without activating it.
+    let active = attributes.active;
+    let show_cmd = if active { winuser::SW_SHOW } else { winuser::SW_SHOWNA };
     if let Some(_) = attributes.fullscreen {
         win.set_fullscreen(attributes.fullscreen);
-        force_window_active(win.window.0);
+        if attributes.visible && (!pending_present || present_cloaked) {
+            // This runs after the closure queued by `set_fullscreen`.
+            let window = win.window.clone();
+            win.events_loop_proxy.execute_in_thread(move |_| {
+                winuser::ShowWindow(window.0, show_cmd);
+                if active {
+                    force_window_active(window.0);
+                }
+            });
+        }
+    } else if present_cloaked || (attributes.visible && !pending_present && !active) {
+        winuser::ShowWindow(win.window.0, show_cmd);
     }
 
     inserter.insert(win.window.0, win.window_state.clone());
@@ -1041,10 +1717,12 @@ unsafe fn init(
 }
 
 unsafe fn register_window_class(
+    class_name: &str,
+    hinstance: HINSTANCE,
     window_icon: &Option<WinIcon>,
     taskbar_icon: &Option<WinIcon>,
 ) -> Vec<u16> {
-    let class_name: Vec<_> = OsStr::new("Window Class")
+    let class_name: Vec<_> = OsStr::new(class_name)
         .encode_wide()
         .chain(Some(0).into_iter())
         .collect();
@@ -1064,7 +1742,7 @@ unsafe fn register_window_class(
         lpfnWndProc: Some(events_loop::callback),
         cbClsExtra: 0,
         cbWndExtra: 0,
-        hInstance: libloaderapi::GetModuleHandleW(ptr::null()),
+        hInstance: hinstance,
         hIcon: h_icon,
         hCursor: ptr::null_mut(), // must be null in order for cursor state to work properly
         hbrBackground: ptr::null_mut(),
@@ -1096,8 +1774,6 @@ thread_local!{
             ComInitialized(ptr::null_mut())
         }
     };
-
-    static TASKBAR_LIST: Cell<*mut ITaskbarList2> = Cell::new(ptr::null_mut());
 }
 
 pub fn com_initialized() {
@@ -1113,33 +1789,68 @@ pub fn com_initialized() {
 // heuristics to determine how the window should be treated, which means
 // that it could still consider the window as fullscreen. :(
 unsafe fn mark_fullscreen(handle: HWND, fullscreen: bool) {
-    com_initialized();
+    taskbar::with_taskbar_list(|task_bar_list| {
+        task_bar_list.MarkFullscreenWindow(handle, if fullscreen { 1 } else { 0 });
+    })
+}
 
-    TASKBAR_LIST.with(|task_bar_list_ptr| {
-        let mut task_bar_list = task_bar_list_ptr.get();
+unsafe fn enable_blur_behind(handle: HWND, enable: bool) {
+    let bb = dwmapi::DWM_BLURBEHIND {
+        dwFlags: DWM_BB_ENABLE,
+        fEnable: if enable { TRUE } else { FALSE },
+        hRgnBlur: ptr::null_mut(),
+        fTransitionOnMaximized: 0,
+    };
 
-        if task_bar_list == ptr::null_mut() {
-            use winapi::shared::winerror::S_OK;
-            use winapi::Interface;
+    dwmapi::DwmEnableBlurBehindWindow(handle, &bb);
+}
 
-            let hr = combaseapi::CoCreateInstance(
-                &CLSID_TaskbarList,
-                ptr::null_mut(),
-                combaseapi::CLSCTX_ALL,
-                &ITaskbarList2::uuidof(),
-                &mut task_bar_list as *mut _ as *mut _,
-            );
+// `DwmEnableBlurBehindWindow` only blurs on Windows Vista and 7, and merely makes the window
+// transparent since Windows 8, so it stays enabled for transparent windows. The accent policy
+// blurs on Windows 10, and is ignored before.
+unsafe fn set_blur(handle: HWND, blur: bool, transparent: bool) {
+    enable_blur_behind(handle, blur || transparent);
+    if let Some(set_window_composition_attribute) = *SET_WINDOW_COMPOSITION_ATTRIBUTE {
+        let mut policy = AccentPolicy {
+            accent_state: if blur { ACCENT_ENABLE_BLURBEHIND } else { ACCENT_DISABLED },
+            accent_flags: 0,
+            gradient_color: 0,
+            animation_id: 0,
+        };
+        let mut data = WindowCompositionAttribData {
+            attrib: WCA_ACCENT_POLICY,
+            data: &mut policy as *mut AccentPolicy as *mut c_void,
+            size: mem::size_of::<AccentPolicy>() as UINT,
+        };
+        set_window_composition_attribute(handle, &mut data);
+    }
+}
 
-            if hr != S_OK || (*task_bar_list).HrInit() != S_OK {
-                // In some old windows, the taskbar object could not be created, we just ignore it
-                return;
-            }
-            task_bar_list_ptr.set(task_bar_list)
-        }
+unsafe fn create_region(rects: &[RECT], (dx, dy): (LONG, LONG)) -> HRGN {
+    let region = wingdi::CreateRectRgn(0, 0, 0, 0);
+    for rect in rects {
+        let rect_region = wingdi::CreateRectRgn(
+            rect.left + dx,
+            rect.top + dy,
+            rect.right + dx,
+            rect.bottom + dy,
+        );
+        wingdi::CombineRgn(region, region, rect_region, wingdi::RGN_OR);
+        wingdi::DeleteObject(rect_region as _);
+    }
+    region
+}
 
-        task_bar_list = task_bar_list_ptr.get();
-        (*task_bar_list).MarkFullscreenWindow(handle, if fullscreen { 1 } else { 0 });
-    })
+/// Returns `false` if cloaking isn't supported, which is the case before Windows 8.
+unsafe fn set_cloaked(handle: HWND, cloaked: bool) -> bool {
+    let value: BOOL = if cloaked { TRUE } else { FALSE };
+    let result = dwmapi::DwmSetWindowAttribute(
+        handle,
+        dwmapi::DWMWA_CLOAK,
+        &value as *const BOOL as *const _,
+        mem::size_of::<BOOL>() as DWORD,
+    );
+    SUCCEEDED(result)
 }
 
 unsafe fn force_window_active(handle: HWND) {
@@ -0,0 +1,299 @@
+//! Redraws requested with `Window::request_redraw`, merged and throttled per window.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use {Event, EventsLoopClosed, WindowEvent, WindowId};
+
+#[derive(Default)]
+struct WindowRedraws {
+    requested: bool,
+    // Whether the `EventsLoop` was woken up for the current request.
+    woken: bool,
+    throttle: Option<Duration>,
+    // When the window last received `Refresh`, from us or from the system.
+    last_refresh: Option<Instant>,
+}
+
+impl WindowRedraws {
+    // When the current request can be delivered, if there's one, with `now` if it isn't throttled.
+    fn due(&self, now: Instant) -> Option<Instant> {
+        if !self.requested {
+            return None;
+        }
+        match (self.throttle, self.last_refresh) {
+            (Some(throttle), Some(last_refresh)) => Some(last_refresh + throttle),
+            _ => Some(now),
+        }
+    }
+
+    fn refreshed(&mut self, now: Instant) {
+        self.requested = false;
+        self.woken = false;
+        self.last_refresh = Some(now);
+    }
+}
+
+#[derive(Default)]
+struct State {
+    windows: HashMap<WindowId, WindowRedraws>,
+    // The timer thread is only started by the first request that has to wait for its throttle.
+    timer_started: bool,
+    stopped: bool,
+}
+
+impl State {
+    // Records that the `EventsLoop` is being woken up for the requests that are due.
+    fn wake_due(&mut self, now: Instant) {
+        for redraws in self.windows.values_mut() {
+            if redraws.due(now).map_or(false, |due| due <= now) {
+                redraws.woken = true;
+            }
+        }
+    }
+}
+
+// Shared with the timer thread, and with the windows, which can be sent to other threads. On the
+// web with the `atomics` target feature, the proxy it calls can't be, and neither can windows.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown", target_feature = "atomics")))]
+pub(crate) type Wakeup = Box<Fn() -> Result<(), EventsLoopClosed> + Send + Sync>;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", target_feature = "atomics"))]
+pub(crate) type Wakeup = Box<Fn() -> Result<(), EventsLoopClosed>>;
+
+struct Shared {
+    state: Mutex<State>,
+    // Notified when a request has to wait for its throttle, and when the `RedrawQueue` is dropped.
+    timer: Condvar,
+    wakeup: Wakeup,
+}
+
+/// Queues the `Refresh` events requested by the windows of an `EventLoopWindowTarget`, and wakes
+/// up its `EventsLoop` once they can be delivered.
+pub(crate) struct RedrawQueue(Arc<Shared>);
+
+/// Requests redraws for a window. Shared by the windows created with an `EventLoopWindowTarget`.
+#[derive(Clone)]
+pub(crate) struct RedrawRequester(Arc<Shared>);
+
+impl RedrawQueue {
+    /// `wakeup` is called to wake up the `EventsLoop`, from any thread.
+    pub fn new(wakeup: Wakeup) -> RedrawQueue {
+        RedrawQueue(Arc::new(Shared {
+            state: Default::default(),
+            timer: Condvar::new(),
+            wakeup,
+        }))
+    }
+
+    pub fn requester(&self) -> RedrawRequester {
+        RedrawRequester(Arc::clone(&self.0))
+    }
+
+    /// Keeps track of the `Refresh` events sent by the system, which the requested ones are
+    /// merged with, and of the windows that are destroyed.
+    pub fn observe(&self, event: &Event) {
+        match *event {
+            Event::WindowEvent { window_id, event: WindowEvent::Refresh { .. } } => {
+                let mut state = self.0.state.lock().unwrap();
+                if let Some(redraws) = state.windows.get_mut(&window_id) {
+                    redraws.refreshed(Instant::now());
+                }
+            },
+            Event::WindowEvent { window_id, event: WindowEvent::Destroyed } => {
+                self.0.state.lock().unwrap().windows.remove(&window_id);
+            },
+            _ => (),
+        }
+    }
+
+    /// Returns the next requested `Refresh` event that can be delivered, if any.
+    pub fn next_event(&self) -> Option<Event> {
+        let mut state = self.0.state.lock().unwrap();
+        let now = Instant::now();
+        let window_id = state.windows.iter()
+            .find(|&(_, redraws)| redraws.due(now).map_or(false, |due| due <= now))
+            .map(|(window_id, _)| *window_id)?;
+        state.windows.get_mut(&window_id).unwrap().refreshed(now);
+        Some(Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Refresh { damage: None },
+        })
+    }
+}
+
+impl Drop for RedrawQueue {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().stopped = true;
+        self.0.timer.notify_all();
+    }
+}
+
+impl RedrawRequester {
+    pub fn request(&self, window_id: WindowId) {
+        let mut state = self.0.state.lock().unwrap();
+        let now = Instant::now();
+        let due = {
+            let redraws = state.windows.entry(window_id).or_insert_with(Default::default);
+            if redraws.requested {
+                return;
+            }
+            redraws.requested = true;
+            redraws.due(now)
+        };
+        self.schedule(state, due, now);
+    }
+
+    pub fn set_throttle(&self, window_id: WindowId, throttle: Option<Duration>) {
+        let mut state = self.0.state.lock().unwrap();
+        let now = Instant::now();
+        let due = {
+            let redraws = state.windows.entry(window_id).or_insert_with(Default::default);
+            redraws.throttle = throttle;
+            if redraws.woken {
+                return;
+            }
+            redraws.due(now)
+        };
+        self.schedule(state, due, now);
+    }
+
+    // Wakes up the `EventsLoop` if a request is due now, and leaves it to the timer thread
+    // otherwise.
+    fn schedule(&self, mut state: MutexGuard<State>, due: Option<Instant>, now: Instant) {
+        let due = match due {
+            Some(due) => due,
+            None => return,
+        };
+        if due <= now {
+            state.wake_due(now);
+            drop(state);
+            let _ = (self.0.wakeup)();
+        } else {
+            if !state.timer_started {
+                state.timer_started = true;
+                start_timer(Arc::clone(&self.0));
+            }
+            self.0.timer.notify_all();
+        }
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "emscripten")))]
+fn start_timer(shared: Arc<Shared>) {
+    ::std::thread::spawn(move || run_timer(shared));
+}
+
+// Throttled requests are delivered with the first event received once they're due instead.
+#[cfg(any(target_arch = "wasm32", target_os = "emscripten"))]
+fn start_timer(_shared: Arc<Shared>) {}
+
+// Wakes up the `EventsLoop` whenever a throttled request becomes due, until the `RedrawQueue`
+// is dropped.
+#[cfg(not(any(target_arch = "wasm32", target_os = "emscripten")))]
+fn run_timer(shared: Arc<Shared>) {
+    let mut state = shared.state.lock().unwrap();
+    while !state.stopped {
+        let now = Instant::now();
+        let next_due = state.windows.values()
+            .filter(|redraws| !redraws.woken)
+            .filter_map(|redraws| redraws.due(now))
+            .min();
+        state = match next_due {
+            Some(due) if due <= now => {
+                state.wake_due(now);
+                drop(state);
+                if (shared.wakeup)().is_err() {
+                    return;
+                }
+                shared.state.lock().unwrap()
+            },
+            Some(due) => shared.timer.wait_timeout(state, due - now).unwrap().0,
+            None => shared.timer.wait(state).unwrap(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use platform;
+    use super::*;
+
+    fn window_id(raw: u64) -> WindowId {
+        WindowId(platform::WindowId::from_raw(raw))
+    }
+
+    fn refresh(window_id: WindowId) -> Event {
+        Event::WindowEvent { window_id, event: WindowEvent::Refresh { damage: None } }
+    }
+
+    fn queue() -> (RedrawQueue, Arc<AtomicUsize>) {
+        let wakeups = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&wakeups);
+        let queue = RedrawQueue::new(Box::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }));
+        (queue, wakeups)
+    }
+
+    fn refreshed_windows(queue: &RedrawQueue) -> Vec<WindowId> {
+        let mut windows = Vec::new();
+        while let Some(event) = queue.next_event() {
+            match event {
+                Event::WindowEvent { window_id, .. } => windows.push(window_id),
+                _ => unreachable!(),
+            }
+        }
+        windows
+    }
+
+    #[test]
+    fn requests_are_merged() {
+        let (queue, wakeups) = queue();
+        let requester = queue.requester();
+        requester.request(window_id(1));
+        requester.request(window_id(1));
+        assert_eq!(wakeups.load(Ordering::SeqCst), 1);
+        assert_eq!(refreshed_windows(&queue), vec![window_id(1)]);
+
+        // A redraw sent by the system fulfills the requests made before it.
+        requester.request(window_id(1));
+        queue.observe(&refresh(window_id(1)));
+        assert_eq!(refreshed_windows(&queue), vec![]);
+    }
+
+    #[test]
+    fn throttled_requests_wait_for_their_interval() {
+        let (queue, wakeups) = queue();
+        let requester = queue.requester();
+        requester.set_throttle(window_id(1), Some(Duration::from_millis(50)));
+        requester.request(window_id(1));
+        assert_eq!(refreshed_windows(&queue), vec![window_id(1)]);
+
+        requester.request(window_id(1));
+        requester.request(window_id(2));
+        assert_eq!(refreshed_windows(&queue), vec![window_id(2)]);
+        assert_eq!(wakeups.load(Ordering::SeqCst), 2);
+
+        // The timer thread wakes the events loop up once the interval has passed.
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(wakeups.load(Ordering::SeqCst), 3);
+        assert_eq!(refreshed_windows(&queue), vec![window_id(1)]);
+    }
+
+    #[test]
+    fn destroyed_windows_are_forgotten() {
+        let (queue, _) = queue();
+        let requester = queue.requester();
+        requester.request(window_id(1));
+        queue.observe(&Event::WindowEvent {
+            window_id: window_id(1),
+            event: WindowEvent::Destroyed,
+        });
+        assert_eq!(refreshed_windows(&queue), vec![]);
+    }
+}
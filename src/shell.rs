@@ -0,0 +1,33 @@
+//! Integration with the desktop's file manager.
+//!
+//! These functions hand a file over to the rest of the desktop, for instance to show the user
+//! where a document was just saved. They don't need an `EventsLoop`, and can be called from any
+//! thread.
+//!
+//! This module is only available when the `shell` feature is enabled.
+//!
+//! ## Platform-specific
+//!
+//! - **Windows:** Uses `SHOpenFolderAndSelectItems` and `ShellExecuteW`.
+//! - **macOS:** Uses `NSWorkspace`.
+//! - **Linux / BSD:** Revealing asks the file manager over the `org.freedesktop.FileManager1`
+//!   D-Bus interface, through `dbus-send`, and falls back to opening the parent directory.
+//!   Opening uses `xdg-open`.
+//! - **iOS / Android / Emscripten / Web:** Unsupported, and returns an error.
+
+use std::path::Path;
+
+use platform;
+
+/// Opens the file manager at the directory containing `path`, with `path` selected.
+#[inline]
+pub fn reveal_in_file_manager<P: AsRef<Path>>(path: P) -> Result<(), String> {
+    platform::reveal_in_file_manager(path.as_ref())
+}
+
+/// Opens `path` with the application the user chose for its type, or opens a directory in the
+/// file manager.
+#[inline]
+pub fn open_with_default_app<P: AsRef<Path>>(path: P) -> Result<(), String> {
+    platform::open_with_default_app(path.as_ref())
+}
@@ -0,0 +1,153 @@
+//! Running a future on the `EventsLoop`, as done by `EventsLoop::run_async`.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use futures_core::Stream;
+
+use {ControlFlow, Event, EventLoopWindowTarget, EventsLoop};
+
+/// The events received by an `EventsLoop`, as a `Stream`.
+///
+/// Given to the future run by `EventsLoop::run_async`. The stream ends once the connection to the
+/// display server is lost, after `Event::ConnectionLost` has been delivered.
+pub struct EventStream<'a> {
+    queue: Rc<Queue>,
+    window_target: &'a EventLoopWindowTarget,
+}
+
+impl<'a> EventStream<'a> {
+    /// Returns the `EventLoopWindowTarget` of the `EventsLoop`, which windows can be created with
+    /// while the future is running.
+    #[inline]
+    pub fn window_target(&self) -> &'a EventLoopWindowTarget {
+        self.window_target
+    }
+}
+
+impl<'a> Stream for EventStream<'a> {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Event>> {
+        if let Some(event) = self.queue.events.borrow_mut().pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if self.queue.closed.get() {
+            return Poll::Ready(None);
+        }
+        *self.queue.waker.borrow_mut() = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+struct Queue {
+    events: RefCell<VecDeque<Event>>,
+    waker: RefCell<Option<Waker>>,
+    closed: Cell<bool>,
+}
+
+impl Queue {
+    fn wake(&self) {
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Wakes up the `EventsLoop` when the future has to be polled again.
+struct LoopWaker {
+    woken: AtomicBool,
+    // Set while the `EventsLoop` is waiting for events. The rest of the time, the future is polled
+    // again anyway once the events have been handled, and waking up the `EventsLoop` would only
+    // deliver an `Event::Awakened`, which would in turn wake the future again.
+    waiting: AtomicBool,
+    // `Waker`s have to be `Send` and `Sync`, which the proxy isn't on the web with the `atomics`
+    // target feature. `run_async` isn't supported on the web anyway.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    proxy: ::EventsLoopProxy,
+    // Once the stream has ended, the `EventsLoop` can't be woken up anymore, so the thread waits
+    // for the future to be woken up by itself.
+    thread: Thread,
+}
+
+impl Wake for LoopWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            if self.waiting.load(Ordering::SeqCst) {
+                let _ = self.proxy.wakeup();
+            }
+        }
+        self.thread.unpark();
+    }
+}
+
+pub(crate) fn run<'a, F, Fut>(events_loop: &'a EventsLoop, f: F) -> Fut::Output
+    where F: FnOnce(EventStream<'a>) -> Fut,
+          Fut: Future,
+{
+    let queue = Rc::new(Queue {
+        events: RefCell::new(VecDeque::new()),
+        waker: RefCell::new(None),
+        closed: Cell::new(false),
+    });
+    let loop_waker = Arc::new(LoopWaker {
+        woken: AtomicBool::new(true),
+        waiting: AtomicBool::new(false),
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        proxy: events_loop.create_proxy(),
+        thread: thread::current(),
+    });
+    let waker = Waker::from(loop_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let future = f(EventStream { queue: queue.clone(), window_target: events_loop });
+    let mut future = Box::pin(future);
+    loop {
+        if loop_waker.woken.swap(false, Ordering::SeqCst) {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            continue;
+        }
+        if queue.closed.get() {
+            thread::park();
+            continue;
+        }
+
+        // `waiting` has to be set before `woken` is checked again, so that a wakeup from another
+        // thread is either seen here or makes the `EventsLoop` wake up.
+        loop_waker.waiting.store(true, Ordering::SeqCst);
+        if loop_waker.woken.load(Ordering::SeqCst) {
+            loop_waker.waiting.store(false, Ordering::SeqCst);
+            continue;
+        }
+        {
+            let mut events = queue.events.borrow_mut();
+            events_loop.run_forever_shared(|event, _| {
+                loop_waker.waiting.store(false, Ordering::SeqCst);
+                events.push_back(event);
+                ControlFlow::Break
+            });
+            loop_waker.waiting.store(false, Ordering::SeqCst);
+            events_loop.poll_events_shared(|event| events.push_back(event));
+            // `run_forever` only returns without an event once the connection is lost.
+            if events.is_empty() {
+                queue.closed.set(true);
+            }
+        }
+        queue.wake();
+    }
+}
@@ -0,0 +1,225 @@
+//! Per-window event callbacks, registered with `Window::on_event`.
+
+use std::sync::{Arc, Mutex, Weak};
+
+use {Event, WindowEvent, WindowId};
+
+/// When the callbacks given to `Window::on_event` are called, relative to the callback given to
+/// `EventsLoop::poll_events` or `EventsLoop::run_forever`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchOrder {
+    /// The subscriber sees the event before the main callback.
+    BeforeMainCallback,
+    /// The subscriber sees the event after the main callback.
+    AfterMainCallback,
+}
+
+/// Keeps a callback given to `Window::on_event` registered. The callback is removed when this is
+/// dropped.
+#[must_use = "the callback is removed as soon as the `SubscriptionHandle` is dropped"]
+pub struct SubscriptionHandle {
+    subscribers: Weak<Mutex<SubscriberList>>,
+    id: u64,
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            let mut subscribers = subscribers.lock().unwrap();
+            subscribers.entries.retain(|entry| entry.id != self.id);
+        }
+    }
+}
+
+struct Subscriber {
+    id: u64,
+    window_id: WindowId,
+    order: DispatchOrder,
+    // Taken out while it's being called, so that the list isn't locked during the call.
+    callback: Option<Box<FnMut(&WindowEvent) + Send>>,
+}
+
+#[derive(Default)]
+struct SubscriberList {
+    next_id: u64,
+    entries: Vec<Subscriber>,
+}
+
+// Shared by an `EventLoopWindowTarget` and the windows created with it.
+#[derive(Clone, Default)]
+pub(crate) struct Subscribers(Arc<Mutex<SubscriberList>>);
+
+impl Subscribers {
+    pub fn subscribe<F>(&self, window_id: WindowId, order: DispatchOrder, callback: F) -> SubscriptionHandle
+        where F: FnMut(&WindowEvent) + Send + 'static
+    {
+        let mut subscribers = self.0.lock().unwrap();
+        let id = subscribers.next_id;
+        subscribers.next_id += 1;
+        subscribers.entries.push(Subscriber {
+            id,
+            window_id,
+            order,
+            callback: Some(Box::new(callback)),
+        });
+        SubscriptionHandle {
+            subscribers: Arc::downgrade(&self.0),
+            id,
+        }
+    }
+
+    // Hands `event` to `callback`, and to the subscribers of its window around it.
+    pub fn dispatch<F, R>(&self, event: Event, callback: F) -> R
+        where F: FnOnce(Event) -> R
+    {
+        // With the `tracing` feature, profilers can tell the time spent handling each event apart.
+        #[cfg(feature = "tracing")]
+        let span = trace_span!(target: "winit::dispatch", "dispatch", event = ?event);
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        match event {
+            Event::WindowEvent { window_id, event } => {
+                let destroyed = match event {
+                    WindowEvent::Destroyed => true,
+                    _ => false,
+                };
+                self.notify(window_id, DispatchOrder::BeforeMainCallback, &event);
+                // Only cloned when it's needed, since most windows have no subscribers.
+                let event_after = if self.has_subscribers(window_id, DispatchOrder::AfterMainCallback) {
+                    Some(event.clone())
+                } else {
+                    None
+                };
+                let result = callback(Event::WindowEvent { window_id, event });
+                if let Some(event) = event_after {
+                    self.notify(window_id, DispatchOrder::AfterMainCallback, &event);
+                }
+                // Window ids can be reused by windows created later, which mustn't inherit the
+                // subscribers of this one.
+                if destroyed {
+                    self.0.lock().unwrap().entries.retain(|entry| entry.window_id != window_id);
+                }
+                result
+            },
+            event => callback(event),
+        }
+    }
+
+    fn has_subscribers(&self, window_id: WindowId, order: DispatchOrder) -> bool {
+        self.0.lock().unwrap().entries.iter()
+            .any(|entry| entry.window_id == window_id && entry.order == order)
+    }
+
+    fn notify(&self, window_id: WindowId, order: DispatchOrder, event: &WindowEvent) {
+        // Subscribers can subscribe or drop handles from their callbacks, so the list isn't locked
+        // while they're called.
+        let mut callbacks: Vec<_> = {
+            let mut subscribers = self.0.lock().unwrap();
+            subscribers.entries.iter_mut()
+                .filter(|entry| entry.window_id == window_id && entry.order == order)
+                .filter_map(|entry| entry.callback.take().map(|callback| (entry.id, callback)))
+                .collect()
+        };
+        for &mut (_, ref mut callback) in &mut callbacks {
+            callback(event);
+        }
+        let mut subscribers = self.0.lock().unwrap();
+        for (id, callback) in callbacks {
+            if let Some(entry) = subscribers.entries.iter_mut().find(|entry| entry.id == id) {
+                entry.callback = Some(callback);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use {Event, WindowEvent, WindowId};
+    use platform;
+    use super::*;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    fn window_id(raw: u64) -> WindowId {
+        WindowId(platform::WindowId::from_raw(raw))
+    }
+
+    fn subscribe(
+        subscribers: &Subscribers,
+        window_id: WindowId,
+        order: DispatchOrder,
+        log: &Log,
+        name: &'static str,
+    ) -> SubscriptionHandle {
+        let log = log.clone();
+        subscribers.subscribe(window_id, order, move |event| {
+            if let WindowEvent::ReceivedCharacter(c) = *event {
+                log.lock().unwrap().push(format!("{} {}", name, c));
+            }
+        })
+    }
+
+    fn dispatch_character(subscribers: &Subscribers, window_id: WindowId, c: char, log: &Log) {
+        let event = Event::WindowEvent { window_id, event: WindowEvent::ReceivedCharacter(c) };
+        subscribers.dispatch(event, |event| {
+            if let Event::WindowEvent { event: WindowEvent::ReceivedCharacter(c), .. } = event {
+                log.lock().unwrap().push(format!("main {}", c));
+            }
+        });
+    }
+
+    #[test]
+    fn subscribers_see_their_window_around_main_callback() {
+        let subscribers = Subscribers::default();
+        let log = Log::default();
+        let _after = subscribe(&subscribers, window_id(1), DispatchOrder::AfterMainCallback, &log, "after");
+        let _before = subscribe(&subscribers, window_id(1), DispatchOrder::BeforeMainCallback, &log, "before");
+        let _other = subscribe(&subscribers, window_id(2), DispatchOrder::BeforeMainCallback, &log, "other");
+
+        dispatch_character(&subscribers, window_id(1), 'a', &log);
+        assert_eq!(*log.lock().unwrap(), ["before a", "main a", "after a"]);
+    }
+
+    #[test]
+    fn dropping_handle_unsubscribes() {
+        let subscribers = Subscribers::default();
+        let log = Log::default();
+        let before = subscribe(&subscribers, window_id(1), DispatchOrder::BeforeMainCallback, &log, "before");
+        let _after = subscribe(&subscribers, window_id(1), DispatchOrder::AfterMainCallback, &log, "after");
+
+        drop(before);
+        dispatch_character(&subscribers, window_id(1), 'b', &log);
+        assert_eq!(*log.lock().unwrap(), ["main b", "after b"]);
+    }
+
+    #[test]
+    fn other_events_only_reach_main_callback() {
+        let subscribers = Subscribers::default();
+        let log = Log::default();
+        let _before = subscribe(&subscribers, window_id(1), DispatchOrder::BeforeMainCallback, &log, "before");
+
+        let result = subscribers.dispatch(Event::Awakened, |event| match event {
+            Event::Awakened => 42,
+            _ => 0,
+        });
+        assert_eq!(result, 42);
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn destroyed_window_is_unsubscribed() {
+        let subscribers = Subscribers::default();
+        let log = Log::default();
+        let _after = subscribe(&subscribers, window_id(1), DispatchOrder::AfterMainCallback, &log, "after");
+        let _other = subscribe(&subscribers, window_id(2), DispatchOrder::AfterMainCallback, &log, "other");
+
+        let event = WindowEvent::Destroyed;
+        subscribers.dispatch(Event::WindowEvent { window_id: window_id(1), event }, |_| ());
+        dispatch_character(&subscribers, window_id(1), 'c', &log);
+        dispatch_character(&subscribers, window_id(2), 'd', &log);
+        assert_eq!(*log.lock().unwrap(), ["main c", "main d", "other d"]);
+    }
+}
@@ -0,0 +1,105 @@
+//! Native handles for rendering into a window with OpenGL.
+//!
+//! winit doesn't create OpenGL contexts, but a `Surface` gathers everything that EGL, WGL or
+//! `NSOpenGLContext` needs to create one for a `Window`, and keeps it alive for as long as the
+//! window. A context library typically creates a `Surface` along with its context, drops it along
+//! with its context, and calls `Surface::resize` whenever the window receives
+//! `WindowEvent::Resized`.
+//!
+//! This module is only available when the `surface` feature is enabled.
+//!
+//! ## Platform-specific
+//!
+//! Surfaces are implemented on X11, Wayland, Windows and macOS. Creating a `Surface` on any other
+//! platform returns `CreationError::NotSupported`.
+
+use std::marker::PhantomData;
+use std::os::raw::{c_int, c_ulong, c_void};
+
+use {CreationError, PhysicalSize, Window};
+use platform;
+
+/// The native handles making up a `Surface`.
+///
+/// The pointers are valid for as long as the `Surface` they were returned by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NativeSurface {
+    /// An X11 window, which is the native window type of both EGL and GLX.
+    ///
+    /// The window's visual has to match the config the context is created with, so the window
+    /// should be created with `os::unix::WindowBuilderExt::with_x11_visual`.
+    Xlib {
+        /// The Xlib `Display`.
+        display: *mut c_void,
+        /// The screen the window was created on.
+        screen: c_int,
+        /// The X11 window.
+        window: c_ulong,
+    },
+    /// A Wayland surface, along with the `wl_egl_window` to pass to `eglCreateWindowSurface`.
+    Wayland {
+        /// The `wl_display`.
+        display: *mut c_void,
+        /// The window's `wl_surface`.
+        surface: *mut c_void,
+        /// The `wl_egl_window` wrapping the `wl_surface`. Its size is kept up to date by
+        /// `Surface::resize`.
+        egl_window: *mut c_void,
+    },
+    /// A window and its device context, which `SetPixelFormat`, `wglCreateContext` and
+    /// `wglMakeCurrent` take.
+    Windows {
+        /// The `HWND`.
+        hwnd: *mut c_void,
+        /// The window's `HDC`. Windows are created with `CS_OWNDC`, so it doesn't change for the
+        /// lifetime of the window.
+        hdc: *mut c_void,
+    },
+    /// The `NSView` to pass to `-[NSOpenGLContext setView:]`.
+    ///
+    /// The context has to be sent `update` after the view is resized.
+    MacOS {
+        /// The window's content view.
+        nsview: *mut c_void,
+    },
+}
+
+/// The native handles needed to render into a `Window` with OpenGL.
+///
+/// A `Surface` can't outlive the window it was created for.
+pub struct Surface<'a> {
+    surface: platform::Surface,
+    _window: PhantomData<&'a Window>,
+}
+
+impl<'a> Surface<'a> {
+    /// Creates a surface for `window`.
+    ///
+    /// A window can have several surfaces, although on Wayland, only one `wl_egl_window` can
+    /// wrap a given `wl_surface` at a time.
+    #[inline]
+    pub fn new(window: &'a Window) -> Result<Surface<'a>, CreationError> {
+        Ok(Surface {
+            surface: platform::Surface::new(&window.window)?,
+            _window: PhantomData,
+        })
+    }
+
+    /// Returns the native handles of the surface.
+    #[inline]
+    pub fn native(&self) -> NativeSurface {
+        self.surface.native()
+    }
+
+    /// Resizes the surface to match the window's client area, which should be done whenever the
+    /// window receives `WindowEvent::Resized`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This only has an effect on Wayland, where surfaces don't follow the size of the window on
+    /// their own.
+    #[inline]
+    pub fn resize(&self, size: PhysicalSize) {
+        self.surface.resize(size)
+    }
+}
@@ -0,0 +1,229 @@
+//! Helpers for testing applications built on winit. Requires the `test-util` feature.
+//!
+//! Events injected into an `EventsLoop` are handed to the callbacks of `poll_events` and
+//! `run_forever` the same way as events from the system, so an application's event handling can
+//! be driven by synthetic input. Together with an `EventRecorder`, input recorded in a real
+//! session can be played back deterministically.
+//!
+//! Injected events only reach the application: the backend doesn't see them, so they don't
+//! change what winit knows about the windows. For instance, injecting `WindowEvent::Resized`
+//! doesn't change what `Window::get_inner_size` returns, and injecting `WindowEvent::Focused`
+//! doesn't change what `Window::is_focused` returns.
+//!
+//! ```no_run
+//! use winit::{ElementState, Event, EventsLoop, MouseButton, Window, WindowEvent};
+//! use winit::test::EventsLoopExt;
+//!
+//! let mut events_loop = EventsLoop::new();
+//! let window = Window::new(&events_loop).unwrap();
+//!
+//! events_loop.inject_event(Event::WindowEvent {
+//!     window_id: window.id(),
+//!     event: WindowEvent::MouseInput {
+//!         device_id: winit::test::dummy_device_id(),
+//!         state: ElementState::Pressed,
+//!         button: MouseButton::Left,
+//!         modifiers: Default::default(),
+//!     },
+//! });
+//! events_loop.poll_events(|event| println!("{:?}", event));
+//! ```
+//!
+//! Creating an `EventsLoop` requires a display server, although not a physical input device: on
+//! Linux, tests can be run under `xvfb-run`. Event handling that doesn't need real windows can be
+//! tested without a display server, by injecting events into an `EventQueue` instead.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use {ControlFlow, DeviceId, Event, EventLoopWindowTarget, EventsLoop, WindowId};
+use platform;
+
+/// Additional methods on `EventsLoop` and `EventLoopWindowTarget` for testing.
+pub trait EventsLoopExt {
+    /// Queues `event` to be handed to the callback of `poll_events` or `run_forever`.
+    ///
+    /// Injected events are delivered in the order they were injected, before any pending events
+    /// from the system. Events injected from the callback of `run_forever` are delivered as soon
+    /// as the callback returns.
+    fn inject_event(&self, event: Event);
+}
+
+impl EventsLoopExt for EventLoopWindowTarget {
+    #[inline]
+    fn inject_event(&self, event: Event) {
+        self.injected_events.inject_event(event);
+    }
+}
+
+impl EventsLoopExt for EventsLoop {
+    #[inline]
+    fn inject_event(&self, event: Event) {
+        self.injected_events.inject_event(event);
+    }
+}
+
+/// Events injected without an `EventsLoop`, for testing event handling without a display server.
+///
+/// The events are dispatched the same way an `EventsLoop` dispatches the events injected into
+/// it, but there are no events from the system. Since there are no windows either, the
+/// `WindowId`s of the injected events don't have to belong to any window.
+///
+/// ```
+/// use winit::{Event, WindowEvent};
+/// use winit::test::{EventQueue, EventsLoopExt};
+///
+/// let queue = EventQueue::new();
+/// queue.inject_event(Event::Awakened);
+/// let mut events = Vec::new();
+/// queue.poll_events(|event| events.push(event));
+/// assert_eq!(events.len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    events: RefCell<VecDeque<Event>>,
+}
+
+impl EventQueue {
+    /// Creates an empty queue.
+    #[inline]
+    pub fn new() -> EventQueue {
+        EventQueue::default()
+    }
+
+    /// Hands the injected events to `callback`, in order, including the ones injected while it
+    /// runs, and returns once there are none left.
+    pub fn poll_events<F>(&self, mut callback: F)
+        where F: FnMut(Event)
+    {
+        self.dispatch(|event| {
+            callback(event);
+            ControlFlow::Continue
+        });
+    }
+
+    /// Hands the injected events to `callback`, in order, until it returns `ControlFlow::Break`.
+    ///
+    /// Unlike `EventsLoop::run_forever`, this doesn't wait for more events, and returns once there
+    /// are none left.
+    pub fn run_until_empty<F>(&self, callback: F)
+        where F: FnMut(Event) -> ControlFlow
+    {
+        self.dispatch(callback);
+    }
+
+    pub(crate) fn dispatch<F>(&self, mut callback: F) -> ControlFlow
+        where F: FnMut(Event) -> ControlFlow
+    {
+        loop {
+            // The queue mustn't be borrowed while the callback runs, since it can inject events.
+            let event = match self.events.borrow_mut().pop_front() {
+                Some(event) => event,
+                None => return ControlFlow::Continue,
+            };
+            if callback(event) == ControlFlow::Break {
+                return ControlFlow::Break;
+            }
+        }
+    }
+}
+
+impl EventsLoopExt for EventQueue {
+    #[inline]
+    fn inject_event(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+    }
+}
+
+/// Returns a `DeviceId` to use in synthetic events.
+///
+/// It may or may not be equal to the ID of a real device, depending on the platform.
+#[inline]
+pub fn dummy_device_id() -> DeviceId {
+    DeviceId(platform::DeviceId::dummy())
+}
+
+/// Returns a `WindowId` to use in synthetic events, such as the ones injected into an
+/// `EventQueue`.
+///
+/// It may or may not be equal to the ID of a real window, depending on the platform.
+#[inline]
+pub fn dummy_window_id() -> WindowId {
+    WindowId(platform::WindowId::from_raw(0))
+}
+
+/// An event, and when it was received relative to the start of the recording.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RecordedEvent {
+    pub time: Duration,
+    pub event: Event,
+}
+
+/// Records events received from an `EventsLoop`, so they can be played back later.
+///
+/// With the `serde` feature, recordings can be saved by serializing `events()`. Note that the
+/// `WindowId`s and `DeviceId`s of recorded events are only meaningful for as long as the windows
+/// and devices they refer to exist.
+#[derive(Clone, Debug)]
+pub struct EventRecorder {
+    start: Instant,
+    events: Vec<RecordedEvent>,
+}
+
+impl EventRecorder {
+    /// Starts an empty recording.
+    #[inline]
+    pub fn new() -> EventRecorder {
+        EventRecorder {
+            start: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds `event` to the recording.
+    #[inline]
+    pub fn record(&mut self, event: &Event) {
+        self.events.push(RecordedEvent {
+            time: self.start.elapsed(),
+            event: event.clone(),
+        });
+    }
+
+    /// Returns the events recorded so far, oldest first.
+    #[inline]
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Removes the events recorded so far, and returns them.
+    #[inline]
+    pub fn take_events(&mut self) -> Vec<RecordedEvent> {
+        ::std::mem::replace(&mut self.events, Vec::new())
+    }
+
+    /// Injects every event of the recording into `events_loop`, which can also be an
+    /// `EventQueue`, in order.
+    ///
+    /// The events are delivered by the next call to `poll_events` or `run_forever`, regardless
+    /// of the time between them in the recording.
+    pub fn replay<T: EventsLoopExt + ?Sized>(&self, events_loop: &T) {
+        replay(&self.events, events_loop);
+    }
+}
+
+impl Default for EventRecorder {
+    #[inline]
+    fn default() -> EventRecorder {
+        EventRecorder::new()
+    }
+}
+
+/// Injects `events` into `events_loop`, in order. Useful to play back a recording that was saved
+/// and loaded.
+pub fn replay<T: EventsLoopExt + ?Sized>(events: &[RecordedEvent], events_loop: &T) {
+    for recorded in events {
+        events_loop.inject_event(recorded.event.clone());
+    }
+}
@@ -0,0 +1,141 @@
+//! System tray icons.
+//!
+//! A `TrayIcon` is a small icon living in the area of the desktop dedicated to background
+//! applications: the notification area on Windows, or the right side of the menu bar on macOS.
+//! Clicking it generates an `Event::TrayEvent`, delivered through the `EventsLoop` the icon was
+//! created with.
+//!
+//! `TrayIcon` and `TrayIconBuilder` are only available when the `tray` feature is enabled. The
+//! types used by `Event::TrayEvent` are always available, so that enabling the feature doesn't
+//! change the `Event` enum.
+//!
+//! ## Platform-specific
+//!
+//! Tray icons are currently only implemented on Windows and macOS. Building a `TrayIcon` on any
+//! other platform returns `CreationError::NotSupported`.
+//!
+//! - **Windows:** Icons are added again when Explorer restarts.
+//! - **X11 / Wayland:** Not implemented yet. Desktops show tray icons through the
+//!   StatusNotifierItem D-Bus service, which winit would have to serve, and it has no D-Bus
+//!   connection to do that with.
+
+#[cfg(feature = "tray")]
+use {CreationError, EventLoopWindowTarget, Icon};
+use MouseButton;
+use platform;
+
+/// Identifier of a tray icon. Unique for each tray icon.
+///
+/// Can be obtained with `tray_icon.id()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrayIconId(pub(crate) platform::TrayIconId);
+
+/// Describes an event from a `TrayIcon`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TrayEvent {
+    /// The tray icon was clicked with the given mouse button.
+    Click(MouseButton),
+
+    /// The tray icon was double-clicked with the given mouse button.
+    ///
+    /// The clicks making up a double-click still generate `Click` events of their own.
+    DoubleClick(MouseButton),
+}
+
+/// Attributes to use when creating a tray icon.
+#[cfg(feature = "tray")]
+#[derive(Debug, Clone, Default)]
+pub struct TrayIconAttributes {
+    /// The icon displayed in the tray.
+    ///
+    /// The default is `None`.
+    pub icon: Option<Icon>,
+
+    /// The text shown when hovering over the tray icon.
+    ///
+    /// The default is `None`.
+    pub tooltip: Option<String>,
+}
+
+/// Object that allows you to build tray icons.
+#[cfg(feature = "tray")]
+#[derive(Clone)]
+pub struct TrayIconBuilder {
+    /// The attributes to use to create the tray icon.
+    pub attributes: TrayIconAttributes,
+}
+
+#[cfg(feature = "tray")]
+impl TrayIconBuilder {
+    /// Initializes a new `TrayIconBuilder` with default values.
+    #[inline]
+    pub fn new() -> TrayIconBuilder {
+        TrayIconBuilder {
+            attributes: Default::default(),
+        }
+    }
+
+    /// Sets the icon displayed in the tray.
+    #[inline]
+    pub fn with_icon(mut self, icon: Icon) -> TrayIconBuilder {
+        self.attributes.icon = Some(icon);
+        self
+    }
+
+    /// Sets the text shown when hovering over the tray icon.
+    #[inline]
+    pub fn with_tooltip<T: Into<String>>(mut self, tooltip: T) -> TrayIconBuilder {
+        self.attributes.tooltip = Some(tooltip.into());
+        self
+    }
+
+    /// Builds the tray icon.
+    ///
+    /// Error should be very rare and only occur in case of permission denied, incompatible system,
+    /// out of memory, etc.
+    #[inline]
+    pub fn build(self, window_target: &EventLoopWindowTarget) -> Result<TrayIcon, CreationError> {
+        platform::TrayIcon::new(&window_target.events_loop, self.attributes)
+            .map(|tray| TrayIcon { tray })
+    }
+}
+
+/// Represents an icon in the system tray.
+///
+/// The icon is removed from the tray when this object is dropped.
+#[cfg(feature = "tray")]
+pub struct TrayIcon {
+    tray: platform::TrayIcon,
+}
+
+#[cfg(feature = "tray")]
+impl TrayIcon {
+    /// Creates a new tray icon with no icon image and no tooltip.
+    ///
+    /// Equivalent to `TrayIconBuilder::new().build(window_target)`.
+    #[inline]
+    pub fn new(window_target: &EventLoopWindowTarget) -> Result<TrayIcon, CreationError> {
+        TrayIconBuilder::new().build(window_target)
+    }
+
+    /// Returns an identifier unique to the tray icon.
+    #[inline]
+    pub fn id(&self) -> TrayIconId {
+        TrayIconId(self.tray.id())
+    }
+
+    /// Sets the icon displayed in the tray. `None` leaves an empty space in its place.
+    ///
+    /// If the icon can't be created, a warning is logged and the current icon is kept.
+    #[inline]
+    pub fn set_icon(&self, icon: Option<Icon>) {
+        self.tray.set_icon(icon)
+    }
+
+    /// Sets the text shown when hovering over the tray icon.
+    #[inline]
+    pub fn set_tooltip(&self, tooltip: &str) {
+        self.tray.set_tooltip(tooltip)
+    }
+}
@@ -1,19 +1,36 @@
 use std::collections::vec_deque::IntoIter as VecDequeIter;
+use std::sync::Arc;
+use std::time::Duration;
 
 use {
+    CapturedFrame,
     CreationError,
-    EventsLoop,
+    CursorGrabMode,
+    DispatchOrder,
+    Edge,
+    EventLoopWindowTarget,
+    FrameExtents,
+    HitTestResult,
     Icon,
+    ImeMode,
+    Insets,
     LogicalPosition,
     LogicalSize,
     MouseCursor,
     PhysicalPosition,
     PhysicalSize,
     platform,
+    RawWindowHandle,
+    Rect,
+    SubscriptionHandle,
     Window,
     WindowBuilder,
+    WindowEvent,
     WindowId,
 };
+use accessibility::AccessibilityHandler;
+use dpi::validate_hidpi_factor;
+use menu::Menu;
 
 impl WindowBuilder {
     /// Initializes a new `WindowBuilder` with default values.
@@ -63,9 +80,11 @@ impl WindowBuilder {
     }
 
     /// Requests a specific title for the window.
+    ///
+    /// Null characters are removed, as most platforms can't display them.
     #[inline]
     pub fn with_title<T: Into<String>>(mut self, title: T) -> WindowBuilder {
-        self.window.title = title.into();
+        self.window.title = title.into().replace('\0', "");
         self
     }
 
@@ -91,6 +110,47 @@ impl WindowBuilder {
         self
     }
 
+    /// Sets whether the window is kept hidden until `Window::present_ready` is called, so that
+    /// the user doesn't see the window before its first frame has been drawn.
+    ///
+    /// This has no effect if the window is created hidden.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The window is shown but cloaked, so that it can be rendered to before it
+    ///   appears. On Windows 7, where cloaking isn't supported, it's shown by `present_ready`.
+    /// - **Wayland:** A window only appears once a buffer has been attached to it, so this has no
+    ///   effect.
+    /// - **Web and Emscripten:** The browser doesn't draw the page before control returns to it, so
+    ///   this has no effect.
+    /// - **Android and iOS:** Has no effect.
+    #[inline]
+    pub fn with_visible_on_first_present(mut self, visible_on_first_present: bool) -> WindowBuilder {
+        self.window.visible_on_first_present = visible_on_first_present;
+        self
+    }
+
+    /// Sets whether the window is activated, taking input focus away from the window that has it,
+    /// when it's first shown. Windows created inactive are still brought in front of the others.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The window manager is asked not to focus the window, through a
+    ///   `_NET_WM_USER_TIME` of 0.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Has no effect.
+    #[inline]
+    pub fn with_active(mut self, active: bool) -> WindowBuilder {
+        self.window.active = active;
+        self
+    }
+
+    /// Sets whether the window can take input focus. See `Window::set_focusable`.
+    #[inline]
+    pub fn with_focusable(mut self, focusable: bool) -> WindowBuilder {
+        self.window.focusable = focusable;
+        self
+    }
+
     /// Sets whether the background of the window should be transparent.
     #[inline]
     pub fn with_transparency(mut self, transparent: bool) -> WindowBuilder {
@@ -98,6 +158,16 @@ impl WindowBuilder {
         self
     }
 
+    /// Sets whether what's behind the window should be blurred, which is visible through the
+    /// transparent parts of the window.
+    ///
+    /// See `Window::set_blur` for the platforms this is supported on. Elsewhere, it's ignored.
+    #[inline]
+    pub fn with_blur(mut self, blur: bool) -> WindowBuilder {
+        self.window.blur = blur;
+        self
+    }
+
     /// Sets whether the window should have a border, a title bar, etc.
     #[inline]
     pub fn with_decorations(mut self, decorations: bool) -> WindowBuilder {
@@ -130,6 +200,15 @@ impl WindowBuilder {
         self
     }
 
+    /// Sets the window's menu bar.
+    ///
+    /// See the `menu` module for platform-specific details.
+    #[inline]
+    pub fn with_menu(mut self, menu: Menu) -> WindowBuilder {
+        self.window.menu = Some(menu);
+        self
+    }
+
     /// Enables multitouch.
     #[inline]
     pub fn with_multitouch(mut self) -> WindowBuilder {
@@ -137,12 +216,65 @@ impl WindowBuilder {
         self
     }
 
+    /// On Windows and X11, makes the window use `hidpi_factor` as its DPI factor, instead of the
+    /// one of its monitor. Logical sizes and positions are converted with it, and the window never
+    /// receives `HiDpiFactorChanged`. This is meant for tools that need the same output on every
+    /// machine, like screenshot tests.
+    ///
+    /// Panics if `hidpi_factor` isn't a normal float greater than 0.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The DPI factor is rounded to a multiple of 1/96.
+    /// - **X11:** Takes precedence over the `WINIT_HIDPI_FACTOR` environment variable.
+    /// - Has no effect on macOS, Wayland, iOS, Android, Emscripten, and Web, where the system
+    ///   decides the scale of the window's surface. Wayland only supports integer buffer scales,
+    ///   and macOS always renders at the backing scale factor of the screen.
+    #[inline]
+    pub fn with_forced_hidpi_factor(mut self, hidpi_factor: f64) -> WindowBuilder {
+        assert!(validate_hidpi_factor(hidpi_factor), "invalid DPI factor: {}", hidpi_factor);
+        self.window.forced_hidpi_factor = Some(hidpi_factor);
+        self
+    }
+
+    /// Creates the window as a child of a native window or view, such as the one a plugin host
+    /// gives the editor of a plugin, instead of as a top-level window.
+    ///
+    /// Building the window fails with `CreationError::InvalidAttributes` if the handle doesn't
+    /// belong to the current platform.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The window is a `WS_CHILD` of the `HWND`, like with
+    ///   `WindowBuilderExt::with_parent_window`.
+    /// - **macOS:** The view of the window is added as a subview of the `NSView`, which it fills
+    ///   and follows the size of, sending `WindowEvent::Resized` when the parent resizes it. The
+    ///   `NSWindow` of the window is never shown, and window-level methods like `set_title` have
+    ///   no visible effect.
+    /// - **X11:** The window is created in the X11 window, without XEmbed.
+    /// - Always fails on Wayland, iOS, Android, Emscripten, and Web.
+    #[inline]
+    pub fn with_parent_handle(mut self, parent: RawWindowHandle) -> WindowBuilder {
+        self.window.parent = Some(parent);
+        self
+    }
+
     /// Builds the window.
     ///
     /// Error should be very rare and only occur in case of permission denied, incompatible system,
     /// out of memory, etc.
     #[inline]
-    pub fn build(mut self, events_loop: &EventsLoop) -> Result<Window, CreationError> {
+    pub fn build(mut self, window_target: &EventLoopWindowTarget) -> Result<Window, CreationError> {
+        if let (Some(min), Some(max)) = (self.window.min_dimensions, self.window.max_dimensions) {
+            if min.width > max.width || min.height > max.height {
+                return Err(CreationError::InvalidAttributes(format!(
+                    "The minimum dimensions {:?} are larger than the maximum dimensions {:?}",
+                    min,
+                    max,
+                )));
+            }
+        }
+
         self.window.dimensions = Some(self.window.dimensions.unwrap_or_else(|| {
             if let Some(ref monitor) = self.window.fullscreen {
                 // resizing the window to the dimensions of the monitor when fullscreen
@@ -153,34 +285,49 @@ impl WindowBuilder {
             }
         }));
 
+        if self.window.forced_hidpi_factor.is_none() {
+            self.window.forced_hidpi_factor = window_target.forced_hidpi_factor;
+        }
+
         // building
         platform::Window::new(
-            &events_loop.events_loop,
+            &window_target.events_loop,
             self.window,
             self.platform_specific,
-        ).map(|window| Window { window })
+        ).map(|window| Window {
+            window,
+            subscribers: window_target.subscribers.clone(),
+            redraws: window_target.redraws.requester(),
+            _live: window_target.live_windows.clone(),
+        })
     }
 }
 
 impl Window {
     /// Creates a new Window for platforms where this is appropriate.
     ///
-    /// This function is equivalent to `WindowBuilder::new().build(events_loop)`.
+    /// This function is equivalent to `WindowBuilder::new().build(window_target)`.
     ///
     /// Error should be very rare and only occur in case of permission denied, incompatible system,
     ///  out of memory, etc.
     #[inline]
-    pub fn new(events_loop: &EventsLoop) -> Result<Window, CreationError> {
+    pub fn new(window_target: &EventLoopWindowTarget) -> Result<Window, CreationError> {
         let builder = WindowBuilder::new();
-        builder.build(events_loop)
+        builder.build(window_target)
     }
 
     /// Modifies the title of the window.
     ///
+    /// Null characters are removed, as most platforms can't display them.
+    ///
     /// This is a no-op if the window has already been closed.
     #[inline]
     pub fn set_title(&self, title: &str) {
-        self.window.set_title(title)
+        if title.contains('\0') {
+            self.window.set_title(&title.replace('\0', ""))
+        } else {
+            self.window.set_title(title)
+        }
     }
 
     /// Shows the window if it was hidden.
@@ -205,6 +352,16 @@ impl Window {
         self.window.hide()
     }
 
+    /// Reveals a window created with `WindowBuilder::with_visible_on_first_present`. Call this
+    /// once the first frame has been presented.
+    ///
+    /// Has no effect if the window has already been revealed, or if `show` or `hide` were called
+    /// in the meantime.
+    #[inline]
+    pub fn present_ready(&self) {
+        self.window.present_ready()
+    }
+
     /// Returns the position of the top-left hand corner of the window relative to the
     ///  top-left hand corner of the desktop.
     ///
@@ -216,6 +373,11 @@ impl Window {
     ///  of the visible screen region.
     ///
     /// Returns `None` if the window no longer exists.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Always returns `None`, since clients can't know where their windows are.
+    ///   `WindowEvent::Moved` isn't generated either.
     #[inline]
     pub fn get_position(&self) -> Option<LogicalPosition> {
         self.window.get_position()
@@ -232,12 +394,62 @@ impl Window {
 
     /// Modifies the position of the window.
     ///
+    /// This is the same as `set_outer_position`. Some backends used to move the client area
+    /// instead, so it's kept only for compatibility.
+    #[inline]
+    #[deprecated(note = "use `set_outer_position` or `set_inner_position` instead")]
+    pub fn set_position(&self, position: LogicalPosition) {
+        self.set_outer_position(position)
+    }
+
+    /// Moves the window so that the top-left hand corner of its frame, including the title bar
+    /// and borders, is at `position`. This is the inverse of `get_position`.
+    ///
     /// See `get_position` for more information about the coordinates.
     ///
     /// This is a no-op if the window has already been closed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This is a no-op on Wayland, iOS, Android, Emscripten and the web.
     #[inline]
-    pub fn set_position(&self, position: LogicalPosition) {
-        self.window.set_position(position)
+    pub fn set_outer_position(&self, position: LogicalPosition) {
+        self.window.set_outer_position(position)
+    }
+
+    /// Moves the window so that the top-left hand corner of its client area is at `position`.
+    /// This is the inverse of `get_inner_position`.
+    ///
+    /// The same conditions that apply to `set_outer_position` apply to this method.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The size of the frame is estimated when the window manager doesn't report it,
+    ///   as with `get_frame_extents`.
+    #[inline]
+    pub fn set_inner_position(&self, position: LogicalPosition) {
+        self.window.set_inner_position(position)
+    }
+
+    /// Returns how far the safe area of the window is from each edge of its client area. The safe
+    /// area is the part of the client area that isn't covered by a display cutout, like a notch
+    /// or a camera housing, or by system UI, like a status bar or a home indicator. Content that
+    /// must stay visible or reachable should be drawn in it, while backgrounds can still fill the
+    /// whole client area.
+    ///
+    /// `WindowEvent::SafeAreaChanged` is sent when this changes, for instance when the device is
+    /// rotated.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS:** Requires iOS 11. Views given a size with `set_inner_size` are already laid out
+    ///   within the safe area, so they only have insets if they reach its edges.
+    /// - **macOS:** Only the camera housing of the built-in display is reported, which only covers
+    ///   fullscreen windows. Requires macOS 12.
+    /// - Always zero on other platforms, where nothing covers the client area.
+    #[inline]
+    pub fn get_safe_area_insets(&self) -> Insets {
+        self.window.get_safe_area_insets()
     }
 
     /// Returns the logical size of the window's client area.
@@ -263,6 +475,74 @@ impl Window {
         self.window.get_outer_size()
     }
 
+    /// Returns the size of the title bar and borders on each side of the client area, which is
+    /// the difference between `get_outer_size` and `get_inner_size`.
+    ///
+    /// This follows changes to the decorations, e.g. by `set_decorations` or `set_fullscreen`.
+    ///
+    /// Returns `None` if the window no longer exists.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Depends on the window manager. If it doesn't support `_NET_FRAME_EXTENTS`, the
+    ///   extents are guessed from the window's position within its parent.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Always zero.
+    #[inline]
+    pub fn get_frame_extents(&self) -> Option<FrameExtents> {
+        self.window.get_frame_extents()
+    }
+
+    /// Returns the physical position and size of the entire window, including the title bar and
+    /// borders, in desktop coordinates.
+    ///
+    /// This is consistent with the bounds of other windows, even if they have a different DPI
+    /// factor, which makes it suitable for finding out which window is under a point.
+    ///
+    /// Returns `None` if the window no longer exists, or if its position is unknown.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** The desktop is laid out in logical units, so the physical bounds of windows on
+    ///   monitors with different DPI factors can overlap. Use `convert_point_to` when comparing
+    ///   windows.
+    /// - **Wayland / Android / iOS:** Always returns `None`.
+    #[inline]
+    pub fn outer_bounds_physical(&self) -> Option<(PhysicalPosition, PhysicalSize)> {
+        let hidpi_factor = self.get_hidpi_factor();
+        let position = self.get_position()?.to_physical(hidpi_factor);
+        let size = self.get_outer_size()?.to_physical(hidpi_factor);
+        Some((position, size))
+    }
+
+    /// Converts a position relative to the client area of this window into one relative to the
+    /// client area of `other`.
+    ///
+    /// Each position is in the logical units of its own window, so this accounts for the windows
+    /// having different DPI factors. The result may lie outside of `other`.
+    ///
+    /// Returns `None` if either window no longer exists, or if its position is unknown, which is
+    /// always the case on Wayland, Android, and iOS.
+    pub fn convert_point_to(&self, other: &Window, position: LogicalPosition) -> Option<LogicalPosition> {
+        let origin = self.get_inner_position()?;
+        let other_origin = other.get_inner_position()?;
+        if cfg!(target_os = "macos") {
+            // The desktop is already laid out in logical units.
+            return Some(LogicalPosition::new(
+                origin.x + position.x - other_origin.x,
+                origin.y + position.y - other_origin.y,
+            ));
+        }
+        let hidpi_factor = self.get_hidpi_factor();
+        let other_hidpi_factor = other.get_hidpi_factor();
+        let origin = origin.to_physical(hidpi_factor);
+        let position = position.to_physical(hidpi_factor);
+        let other_origin = other_origin.to_physical(other_hidpi_factor);
+        Some(PhysicalPosition::new(
+            origin.x + position.x - other_origin.x,
+            origin.y + position.y - other_origin.y,
+        ).to_logical(other_hidpi_factor))
+    }
+
     /// Modifies the inner size of the window.
     ///
     /// See `get_inner_size` for more information about the values.
@@ -332,16 +612,57 @@ impl Window {
 
     /// Grabs the cursor, preventing it from leaving the window.
     ///
+    /// This is `set_cursor_grab_mode` with `CursorGrabMode::Confined`, or `CursorGrabMode::None`
+    /// to release the cursor, except where noted below.
+    ///
     /// ## Platform-specific
     ///
     /// On macOS, this presently merely locks the cursor in a fixed location, which looks visually awkward.
     ///
+    /// On Wayland, this also locks the cursor in place, and returns an error if the compositor doesn't
+    /// support the pointer constraints protocol.
+    ///
     /// This has no effect on Android or iOS.
     #[inline]
     pub fn grab_cursor(&self, grab: bool) -> Result<(), String> {
         self.window.grab_cursor(grab)
     }
 
+    /// Confines the cursor to the window, locks it in place, or releases it.
+    ///
+    /// `CursorGrabMode::Locked` is meant for controls driven by the mouse's motion rather than the
+    /// cursor's position, like the camera of a first-person game. Unlike warping the cursor back
+    /// to the center of the window after each move, it doesn't make the cursor jitter. The cursor
+    /// stays visible unless it's hidden with `hide_cursor`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Returns an error if the compositor doesn't support the pointer constraints
+    ///   protocol.
+    /// - **macOS / Emscripten / Web:** `CursorGrabMode::Confined` is unsupported, and returns an
+    ///   error.
+    /// - **iOS / Android:** Unsupported, and returns an error.
+    #[inline]
+    pub fn set_cursor_grab_mode(&self, mode: CursorGrabMode) -> Result<(), String> {
+        self.window.set_cursor_grab_mode(mode)
+    }
+
+    /// Confines the cursor to a region of the window's client area, or releases it with `None`, so
+    /// for instance each player of a split-screen game can be kept to their half of the window.
+    ///
+    /// While a region is set, it takes the place of the cursor grab mode. The region is converted
+    /// to physical pixels with the DPI factor the window has when it's set.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Returns an error if the compositor doesn't support the pointer constraints
+    ///   protocol.
+    /// - **macOS / iOS / Android / Emscripten / Web:** Unsupported, and returns an error.
+    #[inline]
+    pub fn set_cursor_confine_region(&self, region: Option<Rect>) -> Result<(), String> {
+        self.window.set_cursor_confine_region(region)
+    }
+
     /// Hides the cursor, making it invisible but still usable.
     ///
     /// ## Platform-specific
@@ -363,7 +684,139 @@ impl Window {
         self.window.set_maximized(maximized)
     }
 
+    /// Minimizes the window, or restores it if it's minimized.
+    ///
+    /// `WindowEvent::Minimized` is sent once the window has been minimized or restored.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Windows can only be minimized, as the compositor decides when to restore
+    ///   them.
+    /// - Has no effect on iOS, Android, Emscripten or Web.
+    #[inline]
+    pub fn set_minimized(&self, minimized: bool) {
+        self.window.set_minimized(minimized)
+    }
+
+    /// Returns whether the window is minimized.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Always false on Wayland, iOS, Android, Emscripten and Web.
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        self.window.is_minimized()
+    }
+
+    /// Returns whether the window is maximized.
+    ///
+    /// Like the other `is_*` methods below, this follows changes made by the user or the window
+    /// manager, and changes requested with the corresponding setter once they've taken effect.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Updated when the window manager changes `_NET_WM_STATE`, which it usually does
+    ///   before the events loop handles the next events.
+    /// - Always false on iOS, Android, Emscripten and Web.
+    #[inline]
+    pub fn is_maximized(&self) -> bool {
+        self.window.is_maximized()
+    }
+
+    /// Returns whether the window is fullscreen.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Updated when the window manager changes `_NET_WM_STATE`.
+    /// - Always false on iOS, Android and Emscripten.
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.is_fullscreen()
+    }
+
+    /// Returns false if the window has been hidden with `hide`, or was built invisible and hasn't
+    /// been shown yet. Minimized windows are still considered visible.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Always true on Wayland, iOS, Android and Emscripten.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.window.is_visible()
+    }
+
+    /// Returns whether the window has input focus, as reported by the last `WindowEvent::Focused`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Based on whether the compositor reports the window as activated.
+    /// - Always false on iOS, Android and Emscripten.
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.window.is_focused()
+    }
+
+    /// Brings the window in front of the others and gives it input focus.
+    ///
+    /// Focus isn't taken away from other applications: this only has an effect if one of the
+    /// application's windows is already focused, or if the system allows the application to
+    /// activate itself. Otherwise, the window may only ask for the user's attention.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** The window is activated through `_NET_ACTIVE_WINDOW` when the window manager
+    ///   supports it, and is given input focus directly otherwise.
+    /// - **Web:** Focuses the canvas.
+    /// - **Wayland / iOS / Android / Emscripten:** Has no effect.
+    #[inline]
+    pub fn focus(&self) {
+        self.window.focus()
+    }
+
+    /// Sets whether the window can take input focus, either by being clicked or through
+    /// `Window::focus`. Windows that can't, like tool palettes, can still be clicked without
+    /// taking focus away from the window that has it. The default is `true`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Sets `WS_EX_NOACTIVATE`.
+    /// - **X11:** Sets the input field of `WM_HINTS`, which window managers follow when giving
+    ///   focus to windows.
+    /// - **macOS:** The window can't become the key window. It still becomes the main window.
+    /// - **Web:** Removes the canvas from the tab order, which keeps it from being focused.
+    /// - **Wayland / iOS / Android / Emscripten:** Has no effect.
+    #[inline]
+    pub fn set_focusable(&self, focusable: bool) {
+        self.window.set_focusable(focusable)
+    }
+
+    /// Returns whether the window can be resized by the user, as set with `set_resizable`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Always true on Xfwm, where `set_resizable(false)` has no effect.
+    /// - Always false on iOS, Android, Emscripten and Web.
+    #[inline]
+    pub fn is_resizable(&self) -> bool {
+        self.window.is_resizable()
+    }
+
+    /// Returns whether the window has decorations, as set with `set_decorations`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Always false on iOS, Android, Emscripten and Web.
+    #[inline]
+    pub fn is_decorated(&self) -> bool {
+        self.window.is_decorated()
+    }
+
     /// Sets the window to fullscreen or back
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Moving a fullscreen window to another monitor makes it visibly leave
+    ///   fullscreen before entering it again on the new monitor.
     #[inline]
     pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
         self.window.set_fullscreen(monitor)
@@ -394,12 +847,275 @@ impl Window {
         self.window.set_window_icon(window_icon)
     }
 
+    /// Sets the text of a badge shown on the application's icon, such as a count of unread
+    /// messages, or removes it if `None`.
+    ///
+    /// The badge belongs to the application rather than to this window, so it is shared by all
+    /// windows.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** The badge is shown on the dock icon. Setting the label that is already shown
+    ///   does nothing, which avoids redrawing the dock tile.
+    /// - **X11 / Wayland:** Only integer labels are shown, as a count on the application's
+    ///   launcher icon, by launchers implementing Unity's `LauncherEntry` D-Bus API, like KDE
+    ///   Plasma's task manager. Other labels hide the count. The launcher finds the icon from the
+    ///   `GIO_LAUNCHED_DESKTOP_FILE` environment variable, or else from a desktop file named like
+    ///   the executable. The signal is sent with `gdbus`, and nothing happens without it.
+    /// - **Windows:** Has no effect. Use `set_overlay_icon` instead.
+    /// - Has no effect on other platforms.
+    #[inline]
+    pub fn set_badge_label(&self, label: Option<&str>) {
+        self.window.set_badge_label(label)
+    }
+
+    /// Sets a small icon drawn over the corner of the window's taskbar button, or removes it if
+    /// `None`. This is typically used to show a status, or a count of unread messages.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This only has an effect on Windows 7 and later. On macOS, use `set_badge_label` instead.
+    #[inline]
+    pub fn set_overlay_icon(&self, overlay_icon: Option<Icon>) {
+        self.window.set_overlay_icon(overlay_icon)
+    }
+
+    /// Shows the progress of a long operation, as a fraction from `0.0` to `1.0`, or hides it if
+    /// `None`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The progress is shown on the window's taskbar button, on Windows 7 and later.
+    /// - **X11 / Wayland:** The progress is shown on the application's launcher icon, so it's
+    ///   shared by all windows. The same launchers as with `set_badge_label` show it.
+    /// - Has no effect on other platforms.
+    #[inline]
+    pub fn set_progress(&self, progress: Option<f64>) {
+        self.window.set_progress(progress)
+    }
+
+    /// Sets the window's menu bar. `None` removes it.
+    ///
+    /// See the `menu` module for platform-specific details.
+    #[inline]
+    pub fn set_menu(&self, menu: Option<Menu>) {
+        self.window.set_menu(menu)
+    }
+
     /// Sets location of IME candidate box in client area coordinates relative to the top left.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Only has an effect on X11 and macOS. In particular, the on-screen keyboard shown by
+    ///   `set_virtual_keyboard_visible` doesn't follow it.
     #[inline]
     pub fn set_ime_spot(&self, position: LogicalPosition) {
         self.window.set_ime_spot(position)
     }
 
+    /// Forces the IME into an input mode while the window has focus, or lets the user choose it
+    /// again with `ImeMode::Default`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Maps to the IMM32 open and conversion status, which is applied again whenever
+    ///   the window gains focus since the user may switch modes in between. Going back to
+    ///   `ImeMode::Default` restores the status the IME had before. Returns an error if there's no
+    ///   IME for the current keyboard layout.
+    /// - **macOS:** `ImeMode::Direct` only allows roman input sources. The other modes select a
+    ///   matching input source among the enabled ones, and return an error if there's none.
+    /// - **X11 / Wayland / iOS / Android / Emscripten / Web:** Unsupported, and returns an error.
+    ///
+    /// Use `supports_ime_conversion_mode` to know if this is supported beforehand.
+    #[inline]
+    pub fn set_ime_conversion_mode(&self, mode: ImeMode) -> Result<(), String> {
+        self.window.set_ime_conversion_mode(mode)
+    }
+
+    /// Returns whether `set_ime_conversion_mode` is supported for this window.
+    #[inline]
+    pub fn supports_ime_conversion_mode(&self) -> bool {
+        self.window.supports_ime_conversion_mode()
+    }
+
+    /// Sets a callback that decides which parts of the window act as a title bar or as resize
+    /// borders, which is useful for windows drawing their own decorations.
+    ///
+    /// The callback is given a position in physical client area coordinates, and is called
+    /// whenever the system needs to know what lies at that position. It should return quickly.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The callback is invoked from the events loop thread while handling
+    ///   `WM_NCHITTEST`. `HitTestResult::Client` leaves the point to the system, so decorated
+    ///   windows keep their own title bar and resize borders.
+    /// - **X11:** The callback is invoked when the left mouse button is pressed. If it returns anything
+    ///   other than `HitTestResult::Client`, the window manager is asked to move or resize the window,
+    ///   and no `MouseInput` event is generated for that press.
+    /// - **macOS:** Only `HitTestResult::Caption` is supported. Resize borders are treated as
+    ///   `HitTestResult::Client`.
+    /// - **Wayland:** Not supported. The callback is never called, and the window can't be moved or
+    ///   resized through it.
+    /// - This has no effect on Android, iOS, or Emscripten.
+    #[inline]
+    pub fn set_hit_test_callback<F>(&self, callback: F)
+        where F: Fn(PhysicalPosition) -> HitTestResult + Send + Sync + 'static
+    {
+        self.window.set_hit_test_callback(Some(Arc::new(callback)))
+    }
+
+    /// Removes the callback set by `set_hit_test_callback`, making the whole window behave as client
+    /// area again.
+    #[inline]
+    pub fn remove_hit_test_callback(&self) {
+        self.window.set_hit_test_callback(None)
+    }
+
+    /// Restricts the parts of the window that receive mouse and touch input to a set of rectangles
+    /// of its client area, making the rest click-through. An empty set makes the whole window
+    /// click-through, and `None` makes the whole window receive input again, which is the default.
+    ///
+    /// This is mostly useful for overlays, and for undecorated windows with irregular shapes. The
+    /// region is converted to physical pixels with the DPI factor the window has when it's set.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** A non-empty region also clips what's drawn, and the window's decorations.
+    /// - **macOS:** Only an empty region lets clicks through to other applications. Otherwise,
+    ///   clicks outside the region are simply ignored.
+    /// - **X11:** Requires the Shape extension and `libXext`.
+    /// - **Web:** Only an empty region is supported, which lets events through to the page.
+    /// - **iOS / Android / Emscripten:** Unsupported, and returns an error.
+    #[inline]
+    pub fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), String> {
+        self.window.set_input_region(region)
+    }
+
+    /// Clips the visible part of the window to a set of rectangles of its client area, for splash
+    /// screens and other non-rectangular windows. An empty set hides the whole window, and `None`
+    /// shows the whole window again, which is the default.
+    ///
+    /// The parts that are clipped away are click-through as well. This is meant for undecorated
+    /// windows, and the region is converted to physical pixels with the DPI factor the window has
+    /// when it's set.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Combined with the input region, since both are implemented with the window
+    ///   region.
+    /// - **macOS:** The window is made transparent, and the view's layer is masked.
+    /// - **X11:** Requires the Shape extension and `libXext`.
+    /// - **Web:** Uses the `clip-path` CSS property, which older browsers don't support.
+    /// - **Wayland / iOS / Android / Emscripten:** Unsupported, and returns an error. On Wayland,
+    ///   draw transparent pixels instead.
+    #[inline]
+    pub fn set_shape(&self, shape: Option<&[Rect]>) -> Result<(), String> {
+        self.window.set_shape(shape)
+    }
+
+    /// Sets whether what's behind the window is blurred. The blur is only visible through the
+    /// transparent parts of a window created with `WindowBuilder::with_transparency`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Uses the undocumented accent policy of the shell on Windows 10, and
+    ///   `DwmEnableBlurBehindWindow` on older versions. Windows 8 doesn't blur windows at all.
+    /// - **macOS:** Places an `NSVisualEffectView` behind the view, which requires macOS 10.10.
+    /// - **X11:** Sets `_KDE_NET_WM_BLUR_BEHIND_REGION`, which only KWin honors.
+    /// - **Wayland:** Not implemented yet, and returns an error. KWin supports blur through its
+    ///   `org_kde_kwin_blur` protocol, which winit has no bindings for.
+    /// - **Web / iOS / Android / Emscripten:** Unsupported, and returns an error.
+    #[inline]
+    pub fn set_blur(&self, blur: bool) -> Result<(), String> {
+        self.window.set_blur(blur)
+    }
+
+    /// Reserves a band along an edge of the current monitor for the window, which is usually a
+    /// dock or a toolbar, so that other windows aren't maximized over it. `thickness` is the width
+    /// of the band, in logical pixels. `None` releases the space.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The window is registered as an appbar, and moved into the band, whose
+    ///   position is kept up to date when other appbars move.
+    /// - **X11:** Sets `_NET_WM_STRUT_PARTIAL` and `_NET_WM_STRUT`, which window managers usually
+    ///   only honor for windows of the `Dock` type. The window has to be moved into the band by
+    ///   the application.
+    /// - **macOS / Wayland / Web / iOS / Android / Emscripten:** Unsupported, and returns an error.
+    #[inline]
+    pub fn reserve_screen_edge(&self, reservation: Option<(Edge, f64)>) -> Result<(), String> {
+        self.window.reserve_screen_edge(reservation)
+    }
+
+    /// Sets the handler providing the accessibility objects of the window, replacing any previous
+    /// one.
+    ///
+    /// See the `accessibility` module for details.
+    #[inline]
+    pub fn set_accessibility_adapter(&self, adapter: Box<AccessibilityHandler>) {
+        self.window.set_accessibility_adapter(Some(Arc::from(adapter)))
+    }
+
+    /// Removes the handler set by `set_accessibility_adapter`.
+    #[inline]
+    pub fn remove_accessibility_adapter(&self) {
+        self.window.set_accessibility_adapter(None)
+    }
+
+    /// Shows or hides the on-screen keyboard, for use when a text field drawn by the application
+    /// gains or loses focus.
+    ///
+    /// Changes in the keyboard's visibility, whether caused by this or by the user dismissing it,
+    /// are reported with `WindowEvent::VirtualKeyboardVisibilityChanged`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS:** Text typed on the keyboard is delivered as `ReceivedCharacter` events, with
+    ///   `'\u{8}'` standing for backspace.
+    /// - **Windows:** Uses the touch keyboard, launching it if it isn't running yet. Its visibility
+    ///   is only reported once this has been called at least once.
+    /// - **Wayland:** The text-input protocol, through which compositors show their on-screen
+    ///   keyboard, isn't supported yet.
+    /// - This has no effect on macOS, X11, Wayland, Android, or Emscripten.
+    #[inline]
+    pub fn set_virtual_keyboard_visible(&self, visible: bool) {
+        self.window.set_virtual_keyboard_visible(visible)
+    }
+
+    /// Captures the current contents of the window's client area, which is useful for automated UI
+    /// testing or bug reporting tools.
+    ///
+    /// Returns `None` if the contents can't be captured, for instance because the window is hidden
+    /// or minimized.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Uses `PrintWindow`, which asks the window to draw itself into a bitmap.
+    ///   Capturing content rendered with DirectX or OpenGL requires Windows 8.1.
+    /// - **macOS:** Uses `CGWindowListCreateImage`, which works even if other windows cover this one.
+    /// - **X11:** Uses `XGetImage`. Without a compositing manager, the parts of the window covered
+    ///   by other windows contain those windows instead.
+    /// - Always returns `None` on Wayland, iOS, Android, and Emscripten.
+    #[inline]
+    pub fn capture_frame(&self) -> Option<CapturedFrame> {
+        self.window.capture_frame()
+    }
+
+    /// Returns whether the window is in front of the application's other visible windows.
+    ///
+    /// Windows created with other `EventsLoop`s, or by other libraries, are taken into account as
+    /// long as they belong to this process.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires the window manager to support `_NET_CLIENT_LIST_STACKING`, and always
+    ///   returns `false` otherwise. Only windows with a `_NET_WM_PID` are taken into account.
+    /// - **Wayland / iOS / Android / Emscripten / Web:** Always returns `false`.
+    #[inline]
+    pub fn is_frontmost(&self) -> bool {
+        self.window.is_frontmost()
+    }
+
     /// Returns the monitor on which the window currently resides
     #[inline]
     pub fn get_current_monitor(&self) -> MonitorId {
@@ -408,7 +1124,7 @@ impl Window {
 
     /// Returns the list of all the monitors available on the system.
     ///
-    /// This is the same as `EventsLoop::get_available_monitors`, and is provided for convenience.
+    /// This is the same as `EventLoopWindowTarget::get_available_monitors`, and is provided for convenience.
     #[inline]
     pub fn get_available_monitors(&self) -> AvailableMonitorsIter {
         let data = self.window.get_available_monitors();
@@ -417,7 +1133,7 @@ impl Window {
 
     /// Returns the primary monitor of the system.
     ///
-    /// This is the same as `EventsLoop::get_primary_monitor`, and is provided for convenience.
+    /// This is the same as `EventLoopWindowTarget::get_primary_monitor`, and is provided for convenience.
     #[inline]
     pub fn get_primary_monitor(&self) -> MonitorId {
         MonitorId { inner: self.window.get_primary_monitor() }
@@ -427,6 +1143,47 @@ impl Window {
     pub fn id(&self) -> WindowId {
         WindowId(self.window.id())
     }
+
+    /// Calls `callback` with every event of this window, before or after the callback given to
+    /// `EventsLoop::poll_events` or `EventsLoop::run_forever` receives it, depending on `order`.
+    ///
+    /// This lets separate parts of an application handle the events of the windows they're
+    /// interested in on their own. The callback is called on the thread of the `EventsLoop`, and
+    /// stays registered until the returned `SubscriptionHandle` is dropped, or until the window is
+    /// destroyed, after it has received `WindowEvent::Destroyed`.
+    ///
+    /// Subscribers of a window are called in the order they subscribed.
+    pub fn on_event<F>(&self, order: DispatchOrder, callback: F) -> SubscriptionHandle
+        where F: FnMut(&WindowEvent) + Send + 'static
+    {
+        self.subscribers.subscribe(self.id(), order, callback)
+    }
+
+    /// Asks for a `WindowEvent::Refresh` to be delivered for this window, so that it can be
+    /// redrawn.
+    ///
+    /// Requests made before the window receives its next `Refresh` are merged into it, including
+    /// the `Refresh` events sent by the system, which carry the damaged areas when it knows them.
+    /// The `Refresh` events delivered for requests have no damage. This can be called from any
+    /// thread, and wakes up the `EventsLoop` once the event can be delivered, which also
+    /// delivers an `Event::Awakened`.
+    #[inline]
+    pub fn request_redraw(&self) {
+        self.redraws.request(self.id())
+    }
+
+    /// Sets the minimum time between the `Refresh` events delivered for `request_redraw`, counted
+    /// from the last `Refresh` the window received. Requests made sooner than that are delayed
+    /// until the interval has passed. `None`, the default, delivers them right away.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Web / Emscripten:** There's no thread to time the interval with, so delayed requests
+    ///   are delivered with the first event received once it has passed.
+    #[inline]
+    pub fn set_redraw_throttle(&self, throttle: Option<Duration>) {
+        self.redraws.set_throttle(self.id(), throttle)
+    }
 }
 
 /// An iterator for the list of available monitors.
@@ -491,4 +1248,33 @@ impl MonitorId {
     pub fn get_hidpi_factor(&self) -> f64 {
         self.inner.get_hidpi_factor()
     }
+
+    /// Returns the position and size of the work area of the monitor, which is the part of it that
+    /// isn't reserved by taskbars, docks and panels.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** The menu bar is excluded from the work area as well.
+    /// - **X11:** Computed from the `_NET_WM_STRUT_PARTIAL` and `_NET_WM_STRUT` properties of the
+    ///   windows listed in `_NET_CLIENT_LIST`.
+    /// - **Wayland / Web / iOS / Android / Emscripten:** Always returns the whole monitor.
+    #[inline]
+    pub fn get_work_area(&self) -> (PhysicalPosition, PhysicalSize) {
+        self.inner.get_work_area()
+    }
+
+    /// Returns the edge of the monitor the taskbar or dock is on, or `None` if it isn't on this
+    /// monitor.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** The edge of the primary taskbar is known even when it auto-hides, but the one
+    ///   of other taskbars is guessed from the work area, like on other platforms.
+    /// - **macOS:** Returns the edge of the Dock, which is `None` if the Dock auto-hides.
+    /// - **X11:** Returns the edge with the largest reserved space.
+    /// - **Wayland / Web / iOS / Android / Emscripten:** Always returns `None`.
+    #[inline]
+    pub fn get_taskbar_edge(&self) -> Option<Edge> {
+        self.inner.get_taskbar_edge()
+    }
 }
@@ -0,0 +1,49 @@
+use winit::{self, ControlFlow, Event, WindowEvent};
+use winit::test::EventsLoopExt;
+
+fn received_characters(events: &[Event]) -> String {
+    events.iter().filter_map(|event| match *event {
+        Event::WindowEvent { event: WindowEvent::ReceivedCharacter(c), .. } => Some(c),
+        _ => None,
+    }).collect()
+}
+
+// The dispatch itself is tested without a display in tests/event_injection.rs. This checks that a
+// real `EventsLoop` delivers injected events along with the ones from the system.
+pub fn run() {
+    let mut events_loop = winit::EventsLoop::new();
+    let window = winit::WindowBuilder::new()
+        .with_visibility(false)
+        .build(&events_loop)
+        .unwrap();
+    let window_id = window.id();
+    let character = |c| Event::WindowEvent {
+        window_id,
+        event: WindowEvent::ReceivedCharacter(c),
+    };
+
+    // Injected events are delivered by `poll_events`, in order.
+    for c in "abc".chars() {
+        events_loop.inject_event(character(c));
+    }
+    let mut received = Vec::new();
+    events_loop.poll_events(|event| received.push(event));
+    assert_eq!(received_characters(&received), "abc");
+
+    // Events injected from the callback of `run_forever` are delivered once it returns.
+    events_loop.inject_event(character('x'));
+    let mut received = Vec::new();
+    events_loop.run_forever(|event, window_target| {
+        let done = match event {
+            Event::WindowEvent { event: WindowEvent::ReceivedCharacter('x'), .. } => {
+                window_target.inject_event(character('y'));
+                false
+            },
+            Event::WindowEvent { event: WindowEvent::ReceivedCharacter('y'), .. } => true,
+            _ => false,
+        };
+        received.push(event);
+        if done { ControlFlow::Break } else { ControlFlow::Continue }
+    });
+    assert_eq!(received_characters(&received), "xy");
+}
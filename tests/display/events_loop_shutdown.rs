@@ -0,0 +1,95 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use log::{self, Level, LevelFilter, Log, Metadata, Record};
+use winit;
+
+const EVENTS_LOOP_COUNT: usize = 50;
+
+lazy_static! {
+    static ref WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+// Keeps the warnings logged by winit, to check the ones about resources left alive.
+struct WarningRecorder;
+
+impl Log for WarningRecorder {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            WARNINGS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static RECORDER: WarningRecorder = WarningRecorder;
+
+fn take_shutdown_warnings() -> Vec<String> {
+    let mut warnings = WARNINGS.lock().unwrap();
+    let shutdown_warnings = warnings
+        .iter()
+        .filter(|warning| warning.starts_with("`EventsLoop::shutdown`"))
+        .cloned()
+        .collect();
+    warnings.clear();
+    shutdown_warnings
+}
+
+pub fn run() {
+    log::set_logger(&RECORDER).unwrap();
+    log::set_max_level(LevelFilter::Warn);
+
+    for _ in 0..EVENTS_LOOP_COUNT {
+        let mut events_loop = winit::EventsLoop::new();
+        let window = winit::WindowBuilder::new()
+            .with_visibility(false)
+            .build(&events_loop)
+            .unwrap();
+        let proxy = events_loop.create_proxy();
+        proxy.wakeup().unwrap();
+        events_loop.poll_events(|_| ());
+        drop(proxy);
+        drop(window);
+        events_loop.shutdown();
+    }
+    assert_eq!(take_shutdown_warnings(), Vec::<String>::new());
+
+    // The windows and proxies that are still alive are only reported in debug builds.
+    let events_loop = winit::EventsLoop::new();
+    let window = winit::WindowBuilder::new()
+        .with_visibility(false)
+        .build(&events_loop)
+        .unwrap();
+    let proxy = events_loop.create_proxy();
+    events_loop.shutdown();
+    let expected = if cfg!(debug_assertions) {
+        vec![
+            "`EventsLoop::shutdown` was called while 1 of its windows are alive".to_owned(),
+            "`EventsLoop::shutdown` was called while 1 of its proxies are alive".to_owned(),
+        ]
+    } else {
+        Vec::new()
+    };
+    assert_eq!(take_shutdown_warnings(), expected);
+    drop(proxy);
+    drop(window);
+
+    // A resize that's never processed mustn't keep the backend from shutting down. On Windows,
+    // the background thread waits for `Resized` to be handled before carrying on.
+    let events_loop = winit::EventsLoop::new();
+    let window = winit::WindowBuilder::new()
+        .with_visibility(false)
+        .build(&events_loop)
+        .unwrap();
+    window.set_inner_size((300, 200).into());
+    thread::sleep(Duration::from_millis(100));
+    drop(window);
+    events_loop.shutdown();
+    take_shutdown_warnings();
+}
@@ -0,0 +1,61 @@
+// Tests that create real windows. They run without the test harness, since macOS only allows
+// windows to be created on the main thread, and need a display server: on Linux, run them under
+// `xvfb-run` when there's none.
+
+#[macro_use]
+extern crate lazy_static;
+extern crate log;
+#[cfg(target_os = "macos")]
+#[macro_use]
+extern crate objc;
+#[cfg(target_os = "windows")]
+extern crate winapi;
+extern crate winit;
+
+#[cfg(feature = "test-util")]
+mod event_injection;
+mod events_loop_shutdown;
+mod window_initial_state;
+mod window_stress;
+mod window_title;
+
+use std::env;
+
+fn has_display() -> bool {
+    if cfg!(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )) {
+        env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some()
+    } else {
+        true
+    }
+}
+
+fn main() {
+    if !has_display() {
+        // Otherwise CI would pass without testing anything.
+        if env::var_os("CI").is_some() {
+            panic!("no display available on CI, run the display tests under `xvfb-run`");
+        }
+        println!("no display available, skipping the display tests");
+        return;
+    }
+
+    let mut tests: Vec<(&str, fn())> = vec![
+        ("window_stress", window_stress::run),
+        ("window_title", window_title::run),
+        ("window_initial_state", window_initial_state::run),
+        ("events_loop_shutdown", events_loop_shutdown::run),
+    ];
+    #[cfg(feature = "test-util")]
+    tests.push(("event_injection", event_injection::run));
+    for &(name, test) in &tests {
+        println!("test {} ...", name);
+        test();
+    }
+    println!("{} display tests passed", tests.len());
+}
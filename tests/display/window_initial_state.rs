@@ -0,0 +1,39 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use winit::{self, Event, WindowEvent};
+
+// Counts the `Resized` events received in the first second after creating the window.
+fn count_resizes(events_loop: &mut winit::EventsLoop, builder: winit::WindowBuilder) -> usize {
+    let _window = builder.build(events_loop).unwrap();
+    let start = Instant::now();
+    let mut resizes = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        events_loop.poll_events(|event| {
+            if let Event::WindowEvent { event: WindowEvent::Resized(_), .. } = event {
+                resizes += 1;
+            }
+        });
+        thread::sleep(Duration::from_millis(10));
+    }
+    resizes
+}
+
+pub fn run() {
+    let mut events_loop = winit::EventsLoop::new();
+
+    // A window created maximized is never shown at its normal size, so it's resized at most once,
+    // when the window manager reports the maximized size.
+    let resizes = count_resizes(
+        &mut events_loop,
+        winit::WindowBuilder::new().with_maximized(true),
+    );
+    assert!(resizes <= 1, "maximized window was resized {} times", resizes);
+
+    let monitor = events_loop.get_primary_monitor();
+    let resizes = count_resizes(
+        &mut events_loop,
+        winit::WindowBuilder::new().with_fullscreen(Some(monitor)),
+    );
+    assert!(resizes <= 1, "fullscreen window was resized {} times", resizes);
+}
@@ -0,0 +1,113 @@
+use winit;
+
+const WINDOW_COUNT: usize = 1000;
+// Some backends free windows lazily, so a few may still be around once they've all been dropped.
+const MAX_LEFTOVER_WINDOWS: usize = WINDOW_COUNT / 10;
+
+pub fn run() {
+    let mut events_loop = winit::EventsLoop::new();
+    let initial_count = native_window_count(&events_loop);
+    for i in 0..WINDOW_COUNT {
+        let window = winit::WindowBuilder::new()
+            .with_title(format!("window_stress {}", i))
+            .with_visibility(false)
+            .build(&events_loop)
+            .unwrap();
+        drop(window);
+        // Some of the cleanup happens when the backend hears about the window being destroyed.
+        events_loop.poll_events(|_| ());
+    }
+
+    if let (Some(initial_count), Some(count)) = (initial_count, native_window_count(&events_loop)) {
+        let leftover = count.saturating_sub(initial_count);
+        assert!(
+            leftover <= MAX_LEFTOVER_WINDOWS,
+            "{} of the {} windows created are still alive",
+            leftover,
+            WINDOW_COUNT
+        );
+    }
+}
+
+// The USER objects of the process, which include its windows.
+#[cfg(target_os = "windows")]
+fn native_window_count(_events_loop: &winit::EventsLoop) -> Option<usize> {
+    use winapi::shared::minwindef::DWORD;
+    use winapi::um::processthreadsapi::GetCurrentProcess;
+    use winapi::um::winnt::HANDLE;
+
+    const GR_USEROBJECTS: DWORD = 1;
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetGuiResources(process: HANDLE, flags: DWORD) -> DWORD;
+    }
+
+    Some(unsafe { GetGuiResources(GetCurrentProcess(), GR_USEROBJECTS) } as usize)
+}
+
+// The windows the application owns, including the ones that are closed but not released.
+#[cfg(target_os = "macos")]
+fn native_window_count(_events_loop: &winit::EventsLoop) -> Option<usize> {
+    use objc::runtime::Object;
+
+    unsafe {
+        let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        let windows: *mut Object = msg_send![app, windows];
+        let count: usize = msg_send![windows, count];
+        Some(count)
+    }
+}
+
+// The children of the root window, which the hidden windows stay, since they're never mapped.
+// Other clients can create windows in the meantime too, hence the leeway.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+fn native_window_count(events_loop: &winit::EventsLoop) -> Option<usize> {
+    use std::{mem, ptr};
+    use std::os::raw::c_uint;
+    use winit::os::unix::EventsLoopExt;
+
+    let xconn = events_loop.get_xlib_xconnection()?;
+    unsafe {
+        let root = (xconn.xlib.XDefaultRootWindow)(xconn.display);
+        let mut root_return = mem::zeroed();
+        let mut parent_return = mem::zeroed();
+        let mut children = ptr::null_mut();
+        let mut count: c_uint = 0;
+        let status = (xconn.xlib.XQueryTree)(
+            xconn.display,
+            root,
+            &mut root_return,
+            &mut parent_return,
+            &mut children,
+            &mut count,
+        );
+        if !children.is_null() {
+            (xconn.xlib.XFree)(children as *mut _);
+        }
+        if status == 0 {
+            None
+        } else {
+            Some(count as usize)
+        }
+    }
+}
+
+// The windows can't be counted.
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+fn native_window_count(_events_loop: &winit::EventsLoop) -> Option<usize> {
+    None
+}
@@ -0,0 +1,68 @@
+use winit;
+
+const TITLES: &[&str] = &[
+    "ASCII",
+    "Latin-1: àéîõü",
+    "CJK: 窓のタイトル",
+    "Emoji: 🦀🪟",
+    "Null\0in the middle",
+    "",
+];
+
+pub fn run() {
+    let mut events_loop = winit::EventsLoop::new();
+    for title in TITLES {
+        let window = winit::WindowBuilder::new()
+            .with_title(*title)
+            .with_visibility(false)
+            .build(&events_loop)
+            .unwrap();
+        check_title(&window, title);
+        for title in TITLES {
+            window.set_title(title);
+            check_title(&window, title);
+        }
+        events_loop.poll_events(|_| ());
+    }
+}
+
+// Reads the title back from the window's properties, which is only possible on X11.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+fn check_title(window: &winit::Window, title: &str) {
+    use winit::os::unix::{WindowExt, XPropertyValue};
+
+    if window.get_xlib_window().is_none() {
+        return;
+    }
+    let expected = title.replace('\0', "");
+    assert_eq!(
+        window.x11_get_property("_NET_WM_NAME", "UTF8_STRING"),
+        Some(XPropertyValue::Format8(expected.clone().into_bytes())),
+    );
+    // `WM_NAME` can't hold UTF-8, and is only stored as Latin-1 when the title fits in it.
+    let latin1: Option<Vec<u8>> = expected
+        .chars()
+        .map(|c| if (c as u32) < 0x100 { Some(c as u8) } else { None })
+        .collect();
+    if let Some(latin1) = latin1 {
+        assert_eq!(
+            window.x11_get_property("WM_NAME", "STRING"),
+            Some(XPropertyValue::Format8(latin1)),
+        );
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+fn check_title(_window: &winit::Window, _title: &str) {}
@@ -0,0 +1,78 @@
+extern crate winit;
+
+use winit::{ControlFlow, Event, WindowEvent};
+use winit::test::{self, EventQueue, EventRecorder, EventsLoopExt};
+
+fn character(c: char) -> Event {
+    Event::WindowEvent {
+        window_id: test::dummy_window_id(),
+        event: WindowEvent::ReceivedCharacter(c),
+    }
+}
+
+fn received_characters(events: &[Event]) -> String {
+    events.iter().filter_map(|event| match *event {
+        Event::WindowEvent { event: WindowEvent::ReceivedCharacter(c), .. } => Some(c),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn injected_events_are_delivered_in_order() {
+    let queue = EventQueue::new();
+    for c in "abc".chars() {
+        queue.inject_event(character(c));
+    }
+    let mut received = Vec::new();
+    queue.poll_events(|event| received.push(event));
+    assert_eq!(received_characters(&received), "abc");
+
+    // Everything was delivered.
+    queue.poll_events(|_| panic!("the queue should be empty"));
+}
+
+#[test]
+fn events_injected_from_callback_are_delivered() {
+    let queue = EventQueue::new();
+    queue.inject_event(character('x'));
+    let mut received = Vec::new();
+    queue.poll_events(|event| {
+        if let Event::WindowEvent { event: WindowEvent::ReceivedCharacter('x'), .. } = event {
+            queue.inject_event(character('y'));
+        }
+        received.push(event);
+    });
+    assert_eq!(received_characters(&received), "xy");
+}
+
+#[test]
+fn break_keeps_remaining_events() {
+    let queue = EventQueue::new();
+    for c in "ab".chars() {
+        queue.inject_event(character(c));
+    }
+    let mut received = Vec::new();
+    queue.run_until_empty(|event| {
+        received.push(event);
+        ControlFlow::Break
+    });
+    assert_eq!(received_characters(&received), "a");
+    queue.poll_events(|event| received.push(event));
+    assert_eq!(received_characters(&received), "ab");
+}
+
+#[test]
+fn recording_is_replayed() {
+    let queue = EventQueue::new();
+    for c in "xy".chars() {
+        queue.inject_event(character(c));
+    }
+    let mut recorder = EventRecorder::new();
+    queue.poll_events(|event| recorder.record(&event));
+    assert_eq!(recorder.events().len(), 2);
+
+    recorder.replay(&queue);
+    let mut replayed = Vec::new();
+    queue.poll_events(|event| replayed.push(event));
+    assert_eq!(received_characters(&replayed), "xy");
+}
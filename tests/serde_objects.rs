@@ -0,0 +1,72 @@
+extern crate serde;
+extern crate serde_json;
+extern crate winit;
+
+use serde::{Deserialize, Serialize};
+use winit::{Event, ModifiersState, WindowEvent, WindowId};
+use winit::dpi::LogicalPosition;
+
+fn needs_serde<S: Serialize + for<'de> Deserialize<'de>>() {}
+
+#[test]
+fn events_serde() {
+    // ensures that events and their contents implement `Serialize` and `Deserialize`
+    needs_serde::<winit::Event>();
+    needs_serde::<winit::WindowEvent>();
+    needs_serde::<winit::DeviceEvent>();
+    needs_serde::<winit::KeyboardInput>();
+    needs_serde::<winit::Touch>();
+    needs_serde::<winit::ModifiersState>();
+    needs_serde::<winit::VirtualKeyCode>();
+    needs_serde::<winit::Insets>();
+    needs_serde::<winit::Orientation>();
+}
+
+#[test]
+fn ids_serde() {
+    needs_serde::<winit::WindowId>();
+    needs_serde::<winit::DeviceId>();
+}
+
+#[test]
+fn dpi_serde() {
+    needs_serde::<winit::dpi::LogicalPosition>();
+    needs_serde::<winit::dpi::PhysicalPosition>();
+    needs_serde::<winit::dpi::LogicalSize>();
+    needs_serde::<winit::dpi::PhysicalSize>();
+}
+
+#[test]
+fn window_attributes_serde() {
+    needs_serde::<winit::WindowAttributes>();
+    needs_serde::<winit::Icon>();
+    needs_serde::<winit::menu::Menu>();
+}
+
+// Debug output is compared, since events don't implement `PartialEq`.
+fn assert_round_trip(event: Event) {
+    let json = serde_json::to_string(&event).unwrap();
+    let deserialized: Event = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", deserialized), format!("{:?}", event));
+    assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+}
+
+#[test]
+fn window_event_round_trip() {
+    // IDs are serialized as opaque integers, which lets one be made up without creating a window.
+    let window_id: WindowId = serde_json::from_str("1").unwrap();
+    assert_eq!(serde_json::to_string(&window_id).unwrap(), "1");
+
+    assert_round_trip(Event::WindowEvent {
+        window_id,
+        event: WindowEvent::ReceivedCharacter('\u{3042}'),
+    });
+    assert_round_trip(Event::WindowEvent {
+        window_id,
+        event: WindowEvent::CursorMoved {
+            device_id: winit::test::dummy_device_id(),
+            position: LogicalPosition::new(12.5, -3.0),
+            modifiers: ModifiersState { shift: true, ..Default::default() },
+        },
+    });
+}
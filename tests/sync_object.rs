@@ -7,3 +7,9 @@ fn window_sync() {
     // ensures that `winit::Window` implements `Sync`
     needs_sync::<winit::Window>();
 }
+
+#[test]
+fn events_loop_proxy_sync() {
+    // ensures that `winit::EventsLoopProxy` implements `Sync`
+    needs_sync::<winit::EventsLoopProxy>();
+}